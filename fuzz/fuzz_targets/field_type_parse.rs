@@ -0,0 +1,8 @@
+#![no_main]
+
+use class_reader::field_type::FieldType;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|descriptor: &str| {
+    let _ = FieldType::parse(descriptor);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use class_reader::instruction::Instruction;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for address in 0..data.len() {
+        let _ = Instruction::parse(data, address);
+    }
+    let _ = Instruction::parse_instructions(data);
+});
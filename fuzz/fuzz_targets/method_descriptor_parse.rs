@@ -0,0 +1,8 @@
+#![no_main]
+
+use class_reader::method_descriptor::MethodDescriptor;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|descriptor: &str| {
+    let _ = MethodDescriptor::parse(descriptor);
+});
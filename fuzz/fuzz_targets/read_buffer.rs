@@ -0,0 +1,30 @@
+#![no_main]
+
+use class_reader::{
+    class_visitor::ClassVisitor, instruction::Instruction, read_buffer, read_buffer_lenient,
+    span::hex_dump, view_buffer, visit_buffer, write_class,
+};
+use libfuzzer_sys::fuzz_target;
+
+struct IgnoringVisitor;
+
+impl ClassVisitor for IgnoringVisitor {}
+
+fuzz_target!(|data: &[u8]| {
+    let _ = visit_buffer(data, &mut IgnoringVisitor);
+    let _ = view_buffer(data);
+    let _ = hex_dump(data);
+    let _ = read_buffer(data);
+
+    if let Ok((class, _)) = read_buffer_lenient(data) {
+        let _ = class.constants.to_string();
+        for code in class
+            .methods
+            .iter()
+            .filter_map(|method| method.code.as_ref())
+        {
+            let _ = Instruction::parse_instructions(&code.code);
+        }
+        let _ = write_class(&class);
+    }
+});
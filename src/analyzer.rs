@@ -0,0 +1,874 @@
+use std::{collections::BTreeMap, fmt, fmt::Formatter};
+
+use thiserror::Error;
+
+use crate::{
+    class_file_method::ClassFileMethod,
+    constant_pool::ConstantPool,
+    exception_table::ExceptionTableEntry,
+    field_type::FieldType,
+    instruction::{Instruction, LocalVariableAccess},
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// An abstract value, tracked by an [Analyzer] in the local variables and in the operand stack
+pub trait Value: Clone + PartialEq + fmt::Debug {
+    /// Returns the number of slots taken by the value, i.e. 2 for long and double values
+    /// and 1 for everything else
+    fn slots(&self) -> u16;
+}
+
+/// Defines the semantic of the instructions for an [Analyzer], by creating the abstract values
+/// that they produce. The stack manipulation is done by the analyzer, so the interpreter only
+/// needs to compute the results. All the callbacks receive the address and the instruction
+/// being executed, and can fail with an error message that stops the analysis.
+pub trait Interpreter<V: Value> {
+    /// Returns the value of a local variable that has not been assigned yet, which is also
+    /// used for the second slot of long and double values
+    fn new_empty_value(&mut self) -> V;
+
+    /// Returns the value of a parameter, or of `this`, at the start of the method
+    fn new_parameter_value(&mut self, local: u16, field_type: &FieldType) -> V;
+
+    /// Returns the value of the exception on the stack at the start of an exception handler
+    fn new_exception_value(&mut self, handler: &ExceptionTableEntry) -> V;
+
+    /// Instructions that push a value without popping anything: `aconst_null`, `iconst_0`,
+    /// `bipush`, `ldc`, `getstatic`, `new`, ...
+    fn new_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+    ) -> std::result::Result<V, String>;
+
+    /// Instructions that move a value without changing it: loads, stores, `dup` and `swap`
+    fn copy_operation(
+        &mut self,
+        _pc: ProgramCounter,
+        _instruction: &Instruction,
+        value: &V,
+    ) -> std::result::Result<V, String> {
+        Ok(value.clone())
+    }
+
+    /// Instructions that replace a value with a new one: `ineg`, conversions, `iinc`,
+    /// `getfield`, `newarray`, `anewarray`, `arraylength`, `checkcast` and `instanceof`
+    fn unary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        value: &V,
+    ) -> std::result::Result<V, String>;
+
+    /// Instructions that combine two values into a new one: arithmetic, comparisons and
+    /// array loads
+    fn binary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        first: &V,
+        second: &V,
+    ) -> std::result::Result<V, String>;
+
+    /// Instructions that produce a value from any number of values: invocations of methods
+    /// that do not return `void`, with the receiver first, and `multianewarray`
+    fn nary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        values: &[V],
+    ) -> std::result::Result<V, String>;
+
+    /// Instructions that consume values without producing any: branches, switches, returns,
+    /// `athrow`, field and array stores, monitors and invocations of `void` methods
+    fn consume(
+        &mut self,
+        _pc: ProgramCounter,
+        _instruction: &Instruction,
+        _values: &[V],
+    ) -> std::result::Result<(), String> {
+        Ok(())
+    }
+
+    /// Merges two values coming from different execution paths
+    fn merge(&mut self, first: &V, second: &V) -> V;
+}
+
+/// An error that stopped the analysis of a method
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzerError {
+    /// Address of the instruction being analyzed, if the problem is in the code
+    pub address: Option<ProgramCounter>,
+    pub message: String,
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(address) = self.address {
+            write!(f, " at address {address}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The abstract state before an instruction. Like the JVM, the local variables are indexed by
+/// slot, so long and double values are followed by an empty value. The stack instead has one
+/// entry per value, regardless of its size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame<V: Value> {
+    pub locals: Vec<V>,
+    pub stack: Vec<V>,
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Runs an abstract interpretation of the code of a method, in the style of the `Analyzer`
+/// of the ASM library. Starting from the parameters, the instructions are executed on the
+/// abstract values defined by the [Interpreter] until reaching a fixpoint: the frames are
+/// merged wherever control flow joins, including at the start of exception handlers.
+///
+/// Subroutines (`jsr` and `ret`) and `invokedynamic` are not supported.
+pub struct Analyzer<'a, V: Value> {
+    interpreter: &'a mut dyn Interpreter<V>,
+}
+
+impl<'a, V: Value> Analyzer<'a, V> {
+    pub fn new(interpreter: &'a mut dyn Interpreter<V>) -> Self {
+        Self { interpreter }
+    }
+
+    /// Returns the frame before each instruction that can be executed, by address. Methods
+    /// without code have no frames.
+    pub fn analyze(
+        &mut self,
+        class_name: &str,
+        method: &ClassFileMethod,
+        constants: &ConstantPool,
+    ) -> std::result::Result<BTreeMap<ProgramCounter, Frame<V>>, AnalyzerError> {
+        let Some(code) = &method.code else {
+            return Ok(BTreeMap::new());
+        };
+        let error = |address, message| AnalyzerError { address, message };
+        let instructions = Instruction::parse_instructions(&code.code)
+            .map_err(|err| error(None, err.to_string()))?;
+        let index_of_address = |address: usize| {
+            instructions
+                .binary_search_by_key(&address, |(address, _)| *address)
+                .map_err(|_| format!("address {address} is not an instruction"))
+        };
+        let max_stack = code.max_stack.into_usize_safe();
+
+        let mut frames: Vec<Option<Frame<V>>> = vec![None; instructions.len()];
+        let mut pending = vec![false; instructions.len()];
+        let mut worklist = Vec::new();
+        if !instructions.is_empty() {
+            frames[0] = Some(
+                self.initial_frame(class_name, method, code.max_locals)
+                    .map_err(|message| error(None, message))?,
+            );
+            pending[0] = true;
+            worklist.push(0);
+        }
+
+        while let Some(index) = worklist.pop() {
+            pending[index] = false;
+            let (address, instruction) = &instructions[index];
+            let pc = ProgramCounter(*address as u16);
+            let at = |message| error(Some(pc), message);
+            let input = frames[index]
+                .clone()
+                .expect("queued instructions have a frame");
+            let mut output = input.clone();
+            output
+                .execute(pc, instruction, constants, max_stack, self.interpreter)
+                .map_err(at)?;
+
+            let mut successors = Vec::new();
+            for target in instruction.jump_targets() {
+                successors.push((
+                    index_of_address(target.into_usize_safe()).map_err(at)?,
+                    output.clone(),
+                ));
+            }
+            if instruction.can_fall_through() {
+                if index + 1 == instructions.len() {
+                    return Err(at("execution falls off the end of the code".to_string()));
+                }
+                successors.push((index + 1, output.clone()));
+            }
+            for entry in code.exception_table.lookup(pc) {
+                let handler = index_of_address(entry.handler_pc.0.into_usize_safe()).map_err(at)?;
+                let exception = self.interpreter.new_exception_value(entry);
+                for locals in [&input.locals, &output.locals] {
+                    let frame = Frame {
+                        locals: locals.clone(),
+                        stack: vec![exception.clone()],
+                    };
+                    successors.push((handler, frame));
+                }
+            }
+
+            for (successor, frame) in successors {
+                let changed = match &mut frames[successor] {
+                    Some(existing) => existing.merge(&frame, self.interpreter).map_err(at)?,
+                    slot @ None => {
+                        *slot = Some(frame);
+                        true
+                    }
+                };
+                if changed && !pending[successor] {
+                    pending[successor] = true;
+                    worklist.push(successor);
+                }
+            }
+        }
+
+        Ok(instructions
+            .iter()
+            .zip(frames)
+            .filter_map(|((address, _), frame)| {
+                frame.map(|frame| (ProgramCounter(*address as u16), frame))
+            })
+            .collect())
+    }
+
+    fn initial_frame(
+        &mut self,
+        class_name: &str,
+        method: &ClassFileMethod,
+        max_locals: u16,
+    ) -> Result<Frame<V>> {
+        let mut parameters = Vec::new();
+        if !method.is_static() {
+            parameters.push(FieldType::Object(class_name.to_string()));
+        }
+        parameters.extend(method.parsed_type_descriptor.parameters.iter().cloned());
+
+        let mut locals = Vec::new();
+        for parameter in parameters.iter() {
+            let value = self
+                .interpreter
+                .new_parameter_value(locals.len() as u16, parameter);
+            let slots = value.slots();
+            locals.push(value);
+            if slots == 2 {
+                locals.push(self.interpreter.new_empty_value());
+            }
+        }
+        if locals.len() > max_locals.into_usize_safe() {
+            return Err("the parameters do not fit in max_locals".to_string());
+        }
+        while locals.len() < max_locals.into_usize_safe() {
+            locals.push(self.interpreter.new_empty_value());
+        }
+        Ok(Frame {
+            locals,
+            stack: Vec::new(),
+        })
+    }
+}
+
+impl<V: Value> Frame<V> {
+    /// Returns the number of slots used by the values on the stack
+    pub fn stack_slots(&self) -> usize {
+        self.stack
+            .iter()
+            .map(|value| value.slots().into_usize_safe())
+            .sum()
+    }
+
+    /// Merges the given frame into this one, returning whether this frame changed
+    fn merge(&mut self, other: &Frame<V>, interpreter: &mut dyn Interpreter<V>) -> Result<bool> {
+        if self.stack.len() != other.stack.len() {
+            return Err(format!(
+                "incompatible stack heights {} and {} when merging frames",
+                self.stack.len(),
+                other.stack.len()
+            ));
+        }
+        let mut changed = false;
+        for (value, other) in self
+            .locals
+            .iter_mut()
+            .chain(self.stack.iter_mut())
+            .zip(other.locals.iter().chain(other.stack.iter()))
+        {
+            let merged = interpreter.merge(value, other);
+            if merged != *value {
+                *value = merged;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    fn pop(&mut self) -> Result<V> {
+        self.stack
+            .pop()
+            .ok_or_else(|| "operand stack underflow".to_string())
+    }
+
+    /// Pops a value that must take a single slot
+    fn pop_single(&mut self) -> Result<V> {
+        let value = self.pop()?;
+        if value.slots() != 1 {
+            return Err("instruction splits a long or double value".to_string());
+        }
+        Ok(value)
+    }
+
+    fn pop_values(&mut self, count: usize) -> Result<Vec<V>> {
+        if self.stack.len() < count {
+            return Err("operand stack underflow".to_string());
+        }
+        Ok(self.stack.split_off(self.stack.len() - count))
+    }
+
+    fn push_all(&mut self, values: impl IntoIterator<Item = V>) {
+        self.stack.extend(values);
+    }
+
+    fn local(&self, index: u16) -> Result<&V> {
+        self.locals
+            .get(index.into_usize_safe())
+            .ok_or_else(|| format!("local variable {index} exceeds max_locals"))
+    }
+
+    fn set_local(
+        &mut self,
+        index: u16,
+        value: V,
+        interpreter: &mut dyn Interpreter<V>,
+    ) -> Result<()> {
+        let index = index.into_usize_safe();
+        let slots = value.slots().into_usize_safe();
+        if index + slots > self.locals.len() {
+            return Err(format!("local variable {index} exceeds max_locals"));
+        }
+        // Overwriting the second slot of a long or double invalidates the value
+        if index > 0 && self.locals[index - 1].slots() == 2 {
+            self.locals[index - 1] = interpreter.new_empty_value();
+        }
+        self.locals[index] = value;
+        if slots == 2 {
+            self.locals[index + 1] = interpreter.new_empty_value();
+        }
+        Ok(())
+    }
+
+    /// Simulates the execution of the instruction, updating the frame
+    pub(crate) fn execute(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        constants: &ConstantPool,
+        max_stack: usize,
+        interpreter: &mut dyn Interpreter<V>,
+    ) -> Result<()> {
+        self.execute_instruction(pc, instruction, constants, interpreter)?;
+        if self.stack_slots() > max_stack {
+            return Err("operand stack overflow".to_string());
+        }
+        Ok(())
+    }
+
+    fn execute_instruction(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        constants: &ConstantPool,
+        interpreter: &mut dyn Interpreter<V>,
+    ) -> Result<()> {
+        if let Some(access) = instruction.local_variable_access() {
+            match access {
+                LocalVariableAccess::Load(_, index) => {
+                    let value = interpreter.copy_operation(pc, instruction, self.local(index)?)?;
+                    self.stack.push(value);
+                }
+                LocalVariableAccess::Store(_, index) => {
+                    let value = self.pop()?;
+                    let value = interpreter.copy_operation(pc, instruction, &value)?;
+                    self.set_local(index, value, interpreter)?;
+                }
+                LocalVariableAccess::Increment(index) => {
+                    let value = interpreter.unary_operation(pc, instruction, self.local(index)?)?;
+                    self.set_local(index, value, interpreter)?;
+                }
+                LocalVariableAccess::Ret(_) => {
+                    return Err("subroutines are not supported".to_string())
+                }
+            }
+            return Ok(());
+        }
+
+        match instruction {
+            Instruction::Nop | Instruction::Goto { .. } | Instruction::Goto_w { .. } => {}
+            Instruction::Aconst_null
+            | Instruction::Iconst_m1
+            | Instruction::Iconst_0
+            | Instruction::Iconst_1
+            | Instruction::Iconst_2
+            | Instruction::Iconst_3
+            | Instruction::Iconst_4
+            | Instruction::Iconst_5
+            | Instruction::Lconst_0
+            | Instruction::Lconst_1
+            | Instruction::Fconst_0
+            | Instruction::Fconst_1
+            | Instruction::Fconst_2
+            | Instruction::Dconst_0
+            | Instruction::Dconst_1
+            | Instruction::Bipush { .. }
+            | Instruction::Sipush { .. }
+            | Instruction::Ldc { .. }
+            | Instruction::Ldc_w { .. }
+            | Instruction::Ldc2_w { .. }
+            | Instruction::Getstatic { .. }
+            | Instruction::New { .. } => {
+                let value = interpreter.new_operation(pc, instruction)?;
+                self.stack.push(value);
+            }
+
+            Instruction::Pop => {
+                self.pop_single()?;
+            }
+            Instruction::Pop2 => {
+                if self.pop()?.slots() == 1 {
+                    self.pop_single()?;
+                }
+            }
+            Instruction::Dup => {
+                let value = self.pop_single()?;
+                let copy = interpreter.copy_operation(pc, instruction, &value)?;
+                self.push_all([value, copy]);
+            }
+            Instruction::Dup_x1 => {
+                let first = self.pop_single()?;
+                let second = self.pop_single()?;
+                let copy = interpreter.copy_operation(pc, instruction, &first)?;
+                self.push_all([copy, second, first]);
+            }
+            Instruction::Dup_x2 => {
+                let first = self.pop_single()?;
+                let second = self.pop()?;
+                let copy = interpreter.copy_operation(pc, instruction, &first)?;
+                if second.slots() == 2 {
+                    self.push_all([copy, second, first]);
+                } else {
+                    let third = self.pop_single()?;
+                    self.push_all([copy, third, second, first]);
+                }
+            }
+            Instruction::Dup2 => {
+                let first = self.pop()?;
+                if first.slots() == 2 {
+                    let copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    self.push_all([first, copy]);
+                } else {
+                    let second = self.pop_single()?;
+                    let second_copy = interpreter.copy_operation(pc, instruction, &second)?;
+                    let first_copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    self.push_all([second, first, second_copy, first_copy]);
+                }
+            }
+            Instruction::Dup2_x1 => {
+                let first = self.pop()?;
+                if first.slots() == 2 {
+                    let second = self.pop_single()?;
+                    let copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    self.push_all([copy, second, first]);
+                } else {
+                    let second = self.pop_single()?;
+                    let third = self.pop_single()?;
+                    let second_copy = interpreter.copy_operation(pc, instruction, &second)?;
+                    let first_copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    self.push_all([second_copy, first_copy, third, second, first]);
+                }
+            }
+            Instruction::Dup2_x2 => {
+                let first = self.pop()?;
+                if first.slots() == 2 {
+                    let second = self.pop()?;
+                    let copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    if second.slots() == 2 {
+                        self.push_all([copy, second, first]);
+                    } else {
+                        let third = self.pop_single()?;
+                        self.push_all([copy, third, second, first]);
+                    }
+                } else {
+                    let second = self.pop_single()?;
+                    let third = self.pop()?;
+                    let second_copy = interpreter.copy_operation(pc, instruction, &second)?;
+                    let first_copy = interpreter.copy_operation(pc, instruction, &first)?;
+                    if third.slots() == 2 {
+                        self.push_all([second_copy, first_copy, third, second, first]);
+                    } else {
+                        let fourth = self.pop_single()?;
+                        self.push_all([second_copy, first_copy, fourth, third, second, first]);
+                    }
+                }
+            }
+            Instruction::Swap => {
+                let first = self.pop_single()?;
+                let second = self.pop_single()?;
+                let first = interpreter.copy_operation(pc, instruction, &first)?;
+                let second = interpreter.copy_operation(pc, instruction, &second)?;
+                self.push_all([first, second]);
+            }
+
+            Instruction::Ineg
+            | Instruction::Lneg
+            | Instruction::Fneg
+            | Instruction::Dneg
+            | Instruction::I2l
+            | Instruction::I2f
+            | Instruction::I2d
+            | Instruction::L2i
+            | Instruction::L2f
+            | Instruction::L2d
+            | Instruction::F2i
+            | Instruction::F2l
+            | Instruction::F2d
+            | Instruction::D2i
+            | Instruction::D2l
+            | Instruction::D2f
+            | Instruction::I2b
+            | Instruction::I2c
+            | Instruction::I2s
+            | Instruction::Getfield { .. }
+            | Instruction::Newarray { .. }
+            | Instruction::Anewarray { .. }
+            | Instruction::Arraylength
+            | Instruction::Checkcast { .. }
+            | Instruction::Instanceof { .. } => {
+                let value = self.pop()?;
+                let result = interpreter.unary_operation(pc, instruction, &value)?;
+                self.stack.push(result);
+            }
+
+            Instruction::Ifeq { .. }
+            | Instruction::Ifne { .. }
+            | Instruction::Iflt { .. }
+            | Instruction::Ifge { .. }
+            | Instruction::Ifgt { .. }
+            | Instruction::Ifle { .. }
+            | Instruction::Ifnull { .. }
+            | Instruction::Ifnonnull { .. }
+            | Instruction::Tableswitch { .. }
+            | Instruction::Lookupswitch { .. }
+            | Instruction::Ireturn
+            | Instruction::Lreturn
+            | Instruction::Freturn
+            | Instruction::Dreturn
+            | Instruction::Areturn
+            | Instruction::Putstatic { .. }
+            | Instruction::Athrow
+            | Instruction::Monitorenter
+            | Instruction::Monitorexit => {
+                let value = self.pop()?;
+                interpreter.consume(pc, instruction, &[value])?;
+            }
+            Instruction::Return => interpreter.consume(pc, instruction, &[])?,
+
+            Instruction::Iaload
+            | Instruction::Laload
+            | Instruction::Faload
+            | Instruction::Daload
+            | Instruction::Aaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload
+            | Instruction::Iadd
+            | Instruction::Ladd
+            | Instruction::Fadd
+            | Instruction::Dadd
+            | Instruction::Isub
+            | Instruction::Lsub
+            | Instruction::Fsub
+            | Instruction::Dsub
+            | Instruction::Imul
+            | Instruction::Lmul
+            | Instruction::Fmul
+            | Instruction::Dmul
+            | Instruction::Idiv
+            | Instruction::Ldiv
+            | Instruction::Fdiv
+            | Instruction::Ddiv
+            | Instruction::Irem
+            | Instruction::Lrem
+            | Instruction::Frem
+            | Instruction::Drem
+            | Instruction::Ishl
+            | Instruction::Lshl
+            | Instruction::Ishr
+            | Instruction::Lshr
+            | Instruction::Iushr
+            | Instruction::Lushr
+            | Instruction::Iand
+            | Instruction::Land
+            | Instruction::Ior
+            | Instruction::Lor
+            | Instruction::Ixor
+            | Instruction::Lxor
+            | Instruction::Lcmp
+            | Instruction::Fcmpl
+            | Instruction::Fcmpg
+            | Instruction::Dcmpl
+            | Instruction::Dcmpg => {
+                let second = self.pop()?;
+                let first = self.pop()?;
+                let result = interpreter.binary_operation(pc, instruction, &first, &second)?;
+                self.stack.push(result);
+            }
+
+            Instruction::If_icmpeq { .. }
+            | Instruction::If_icmpne { .. }
+            | Instruction::If_icmplt { .. }
+            | Instruction::If_icmpge { .. }
+            | Instruction::If_icmpgt { .. }
+            | Instruction::If_icmple { .. }
+            | Instruction::If_acmpeq { .. }
+            | Instruction::If_acmpne { .. }
+            | Instruction::Putfield { .. } => {
+                let values = self.pop_values(2)?;
+                interpreter.consume(pc, instruction, &values)?;
+            }
+            Instruction::Iastore
+            | Instruction::Lastore
+            | Instruction::Fastore
+            | Instruction::Dastore
+            | Instruction::Aastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Sastore => {
+                let values = self.pop_values(3)?;
+                interpreter.consume(pc, instruction, &values)?;
+            }
+
+            Instruction::Invokevirtual { method }
+            | Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                let reference = constants
+                    .member_reference(*method)
+                    .map_err(|err| err.to_string())?;
+                let descriptor = MethodDescriptor::parse(&reference.type_descriptor)
+                    .map_err(|err| err.to_string())?;
+                let receiver =
+                    usize::from(!matches!(instruction, Instruction::Invokestatic { .. }));
+                let values = self.pop_values(receiver + descriptor.parameters.len())?;
+                if descriptor.return_type.is_some() {
+                    let result = interpreter.nary_operation(pc, instruction, &values)?;
+                    self.stack.push(result);
+                } else {
+                    interpreter.consume(pc, instruction, &values)?;
+                }
+            }
+            Instruction::Multianewarray { dimensions, .. } => {
+                let values = self.pop_values(dimensions.into_usize_safe())?;
+                let result = interpreter.nary_operation(pc, instruction, &values)?;
+                self.stack.push(result);
+            }
+
+            Instruction::Invokedynamic { .. } => {
+                return Err("invokedynamic is not supported".to_string())
+            }
+            Instruction::Jsr { .. } | Instruction::Jsr_w { .. } => {
+                return Err("subroutines are not supported".to_string())
+            }
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analyzer::{Analyzer, AnalyzerError, Interpreter, Value},
+        class_file_method::ClassFileMethod,
+        code_builder::{test_method, CodeBuilder, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        exception_table::ExceptionTableEntry,
+        field_type::FieldType,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+    };
+
+    /// A small nullness analysis, which records the references returned by the method
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Nullness {
+        Null,
+        NonNull,
+        MaybeNull,
+        Other,
+    }
+
+    impl Value for Nullness {
+        fn slots(&self) -> u16 {
+            1
+        }
+    }
+
+    #[derive(Default)]
+    struct NullnessInterpreter {
+        returned: Vec<Nullness>,
+    }
+
+    impl Interpreter<Nullness> for NullnessInterpreter {
+        fn new_empty_value(&mut self) -> Nullness {
+            Nullness::Other
+        }
+
+        fn new_parameter_value(&mut self, _local: u16, field_type: &FieldType) -> Nullness {
+            match field_type {
+                FieldType::Base(_) => Nullness::Other,
+                _ => Nullness::MaybeNull,
+            }
+        }
+
+        fn new_exception_value(&mut self, _handler: &ExceptionTableEntry) -> Nullness {
+            Nullness::NonNull
+        }
+
+        fn new_operation(
+            &mut self,
+            _pc: ProgramCounter,
+            instruction: &Instruction,
+        ) -> Result<Nullness, String> {
+            Ok(match instruction {
+                Instruction::Aconst_null => Nullness::Null,
+                Instruction::New { .. } => Nullness::NonNull,
+                _ => Nullness::Other,
+            })
+        }
+
+        fn unary_operation(
+            &mut self,
+            _pc: ProgramCounter,
+            _instruction: &Instruction,
+            _value: &Nullness,
+        ) -> Result<Nullness, String> {
+            Ok(Nullness::Other)
+        }
+
+        fn binary_operation(
+            &mut self,
+            _pc: ProgramCounter,
+            _instruction: &Instruction,
+            _first: &Nullness,
+            _second: &Nullness,
+        ) -> Result<Nullness, String> {
+            Ok(Nullness::Other)
+        }
+
+        fn nary_operation(
+            &mut self,
+            _pc: ProgramCounter,
+            _instruction: &Instruction,
+            _values: &[Nullness],
+        ) -> Result<Nullness, String> {
+            Ok(Nullness::MaybeNull)
+        }
+
+        fn consume(
+            &mut self,
+            _pc: ProgramCounter,
+            instruction: &Instruction,
+            values: &[Nullness],
+        ) -> Result<(), String> {
+            if *instruction == Instruction::Areturn {
+                self.returned.push(values[0]);
+            }
+            Ok(())
+        }
+
+        fn merge(&mut self, first: &Nullness, second: &Nullness) -> Nullness {
+            if first == second {
+                *first
+            } else {
+                Nullness::MaybeNull
+            }
+        }
+    }
+
+    fn method(descriptor: &str, build: impl FnOnce(&mut CodeBuilder)) -> ClassFileMethod {
+        let mut constants = ConstantPool::new();
+        test_method(
+            &mut constants,
+            MethodFlags::STATIC,
+            "test",
+            descriptor,
+            build,
+        )
+    }
+
+    #[test]
+    fn merges_values_at_join_points() {
+        let method = method("(I)Ljava/lang/Object;", |code| {
+            let join = code.new_label();
+            code.instruction(Instruction::Aconst_null)
+                .store(LocalKind::Reference, 1)
+                .load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, join)
+                .new_object("java/lang/Object")
+                .store(LocalKind::Reference, 1)
+                .place_label(join)
+                .load(LocalKind::Reference, 1)
+                .instruction(Instruction::Areturn);
+        });
+        let mut interpreter = NullnessInterpreter::default();
+        let frames = Analyzer::new(&mut interpreter)
+            .analyze("a/A", &method, &ConstantPool::new())
+            .unwrap();
+
+        assert_eq!(Nullness::Null, frames[&ProgramCounter(3)].locals[1]);
+        assert_eq!(vec![Nullness::NonNull], frames[&ProgramCounter(9)].stack);
+        assert_eq!(Nullness::MaybeNull, frames[&ProgramCounter(10)].locals[1]);
+        assert_eq!(vec![Nullness::MaybeNull], frames[&ProgramCounter(11)].stack);
+        assert_eq!(vec![Nullness::MaybeNull], interpreter.returned);
+    }
+
+    #[test]
+    fn skips_unreachable_code() {
+        let method = method("()V", |code| {
+            code.instruction(Instruction::Return)
+                .instruction(Instruction::Return);
+        });
+        let mut interpreter = NullnessInterpreter::default();
+        let frames = Analyzer::new(&mut interpreter)
+            .analyze("a/A", &method, &ConstantPool::new())
+            .unwrap();
+
+        assert_eq!(
+            vec![ProgramCounter(0)],
+            frames.keys().copied().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reports_stack_underflows() {
+        let method = method("()V", |code| {
+            code.instruction(Instruction::Return);
+        });
+        let mut method = method;
+        method.code.as_mut().unwrap().code = vec![0x57, 0xb1]; // pop, return
+        let mut interpreter = NullnessInterpreter::default();
+        let error = Analyzer::new(&mut interpreter)
+            .analyze("a/A", &method, &ConstantPool::new())
+            .unwrap_err();
+
+        assert_eq!(
+            AnalyzerError {
+                address: Some(ProgramCounter(0)),
+                message: "operand stack underflow".to_string(),
+            },
+            error
+        );
+    }
+}
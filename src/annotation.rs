@@ -0,0 +1,360 @@
+use crate::{
+    buffer::Buffer,
+    class_reader_error::{ClassReaderError, Result},
+    class_writer_error::ClassWriterError,
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    read_limits::{ReadLimit, ReadLimits},
+};
+
+/// An annotation applied to a class, field or method, as stored in the
+/// `RuntimeVisibleAnnotations` and `RuntimeInvisibleAnnotations` attributes:
+/// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.16
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    /// Type of the annotation, as a field descriptor, i.e. `Ljava/lang/Deprecated;`
+    pub type_descriptor: String,
+    /// Values of the annotation's elements, by name
+    pub elements: Vec<(String, ElementValue)>,
+}
+
+/// Value of an element of an annotation
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ElementValue {
+    Byte(i8),
+    Char(u16),
+    Double(f64),
+    Float(f32),
+    Int(i32),
+    Long(i64),
+    Short(i16),
+    Boolean(bool),
+    String(String),
+    Enum {
+        type_descriptor: String,
+        const_name: String,
+    },
+    /// A class literal, stored as a return descriptor, i.e. `Ljava/lang/String;` or `V`
+    Class(String),
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+impl Annotation {
+    pub fn new(type_descriptor: &str) -> Self {
+        Self {
+            type_descriptor: type_descriptor.to_string(),
+            elements: Vec::new(),
+        }
+    }
+
+    /// Adds an element to the annotation
+    pub fn element(mut self, name: &str, value: ElementValue) -> Self {
+        self.elements.push((name.to_string(), value));
+        self
+    }
+
+    /// Encodes a list of annotations in the format used by the content of the
+    /// `Runtime(In)VisibleAnnotations` attributes, adding the needed constants to the pool
+    pub fn encode_attribute(
+        annotations: &[Annotation],
+        constants: &mut ConstantPool,
+    ) -> std::result::Result<Vec<u8>, ClassWriterError> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(annotations.len() as u16).to_be_bytes());
+        for annotation in annotations {
+            annotation.encode(&mut bytes, constants)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes the content of a `Runtime(In)VisibleAnnotations` attribute
+    pub fn decode_attribute(bytes: &[u8], constants: &ConstantPool) -> Result<Vec<Annotation>> {
+        Self::decode_attribute_with_limits(bytes, constants, &ReadLimits::default())
+    }
+
+    /// Decodes the content of a `Runtime(In)VisibleAnnotations` attribute, failing if the
+    /// values are nested deeper than the limits allow
+    pub fn decode_attribute_with_limits(
+        bytes: &[u8],
+        constants: &ConstantPool,
+        limits: &ReadLimits,
+    ) -> Result<Vec<Annotation>> {
+        let mut buffer = Buffer::new(bytes);
+        let count = buffer.read_u16()?;
+        (0..count)
+            .map(|_| Self::decode(&mut buffer, constants, limits, 0))
+            .collect()
+    }
+
+    fn encode(
+        &self,
+        bytes: &mut Vec<u8>,
+        constants: &mut ConstantPool,
+    ) -> std::result::Result<(), ClassWriterError> {
+        bytes.extend_from_slice(&constants.intern_utf8(&self.type_descriptor)?.to_be_bytes());
+        bytes.extend_from_slice(&(self.elements.len() as u16).to_be_bytes());
+        for (name, value) in self.elements.iter() {
+            bytes.extend_from_slice(&constants.intern_utf8(name)?.to_be_bytes());
+            value.encode(bytes, constants)?;
+        }
+        Ok(())
+    }
+
+    fn decode(
+        buffer: &mut Buffer,
+        constants: &ConstantPool,
+        limits: &ReadLimits,
+        depth: usize,
+    ) -> Result<Annotation> {
+        let type_descriptor = constants.text_of(buffer.read_u16()?)?;
+        let count = buffer.read_u16()?;
+        let elements = (0..count)
+            .map(|_| {
+                let name = constants.text_of(buffer.read_u16()?)?;
+                let value = ElementValue::decode(buffer, constants, limits, depth + 1)?;
+                Ok((name, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Annotation {
+            type_descriptor,
+            elements,
+        })
+    }
+}
+
+impl ElementValue {
+    fn tag(&self) -> u8 {
+        match self {
+            ElementValue::Byte(_) => b'B',
+            ElementValue::Char(_) => b'C',
+            ElementValue::Double(_) => b'D',
+            ElementValue::Float(_) => b'F',
+            ElementValue::Int(_) => b'I',
+            ElementValue::Long(_) => b'J',
+            ElementValue::Short(_) => b'S',
+            ElementValue::Boolean(_) => b'Z',
+            ElementValue::String(_) => b's',
+            ElementValue::Enum { .. } => b'e',
+            ElementValue::Class(_) => b'c',
+            ElementValue::Annotation(_) => b'@',
+            ElementValue::Array(_) => b'[',
+        }
+    }
+
+    fn encode(
+        &self,
+        bytes: &mut Vec<u8>,
+        constants: &mut ConstantPool,
+    ) -> std::result::Result<(), ClassWriterError> {
+        bytes.push(self.tag());
+        let index = match self {
+            ElementValue::Byte(value) => {
+                constants.intern(ConstantPoolEntry::Integer(*value as i32))?
+            }
+            ElementValue::Char(value) => {
+                constants.intern(ConstantPoolEntry::Integer(*value as i32))?
+            }
+            ElementValue::Short(value) => {
+                constants.intern(ConstantPoolEntry::Integer(*value as i32))?
+            }
+            ElementValue::Boolean(value) => {
+                constants.intern(ConstantPoolEntry::Integer(*value as i32))?
+            }
+            ElementValue::Int(value) => constants.intern(ConstantPoolEntry::Integer(*value))?,
+            ElementValue::Double(value) => constants.intern(ConstantPoolEntry::Double(*value))?,
+            ElementValue::Float(value) => constants.intern(ConstantPoolEntry::Float(*value))?,
+            ElementValue::Long(value) => constants.intern(ConstantPoolEntry::Long(*value))?,
+            ElementValue::String(value) | ElementValue::Class(value) => {
+                constants.intern_utf8(value)?
+            }
+            ElementValue::Enum {
+                type_descriptor,
+                const_name,
+            } => {
+                bytes.extend_from_slice(&constants.intern_utf8(type_descriptor)?.to_be_bytes());
+                constants.intern_utf8(const_name)?
+            }
+            ElementValue::Annotation(annotation) => {
+                return annotation.encode(bytes, constants);
+            }
+            ElementValue::Array(values) => {
+                bytes.extend_from_slice(&(values.len() as u16).to_be_bytes());
+                for value in values {
+                    value.encode(bytes, constants)?;
+                }
+                return Ok(());
+            }
+        };
+        bytes.extend_from_slice(&index.to_be_bytes());
+        Ok(())
+    }
+
+    /// The depth counts the annotations and arrays that contain the value
+    fn decode(
+        buffer: &mut Buffer,
+        constants: &ConstantPool,
+        limits: &ReadLimits,
+        depth: usize,
+    ) -> Result<ElementValue> {
+        limits.check(ReadLimit::AnnotationDepth, depth)?;
+        let tag = buffer.read_u8()?;
+        Ok(match tag {
+            b'B' | b'C' | b'I' | b'S' | b'Z' => {
+                let value = match constants.get(buffer.read_u16()?)? {
+                    ConstantPoolEntry::Integer(value) => *value,
+                    entry => return Err(Self::invalid_constant(entry)),
+                };
+                match tag {
+                    b'B' => ElementValue::Byte(value as i8),
+                    b'C' => ElementValue::Char(value as u16),
+                    b'S' => ElementValue::Short(value as i16),
+                    b'Z' => ElementValue::Boolean(value != 0),
+                    _ => ElementValue::Int(value),
+                }
+            }
+            b'D' => match constants.get(buffer.read_u16()?)? {
+                ConstantPoolEntry::Double(value) => ElementValue::Double(*value),
+                entry => return Err(Self::invalid_constant(entry)),
+            },
+            b'F' => match constants.get(buffer.read_u16()?)? {
+                ConstantPoolEntry::Float(value) => ElementValue::Float(*value),
+                entry => return Err(Self::invalid_constant(entry)),
+            },
+            b'J' => match constants.get(buffer.read_u16()?)? {
+                ConstantPoolEntry::Long(value) => ElementValue::Long(*value),
+                entry => return Err(Self::invalid_constant(entry)),
+            },
+            b's' => ElementValue::String(constants.text_of(buffer.read_u16()?)?),
+            b'e' => ElementValue::Enum {
+                type_descriptor: constants.text_of(buffer.read_u16()?)?,
+                const_name: constants.text_of(buffer.read_u16()?)?,
+            },
+            b'c' => ElementValue::Class(constants.text_of(buffer.read_u16()?)?),
+            b'@' => ElementValue::Annotation(Annotation::decode(buffer, constants, limits, depth)?),
+            b'[' => {
+                let count = buffer.read_u16()?;
+                ElementValue::Array(
+                    (0..count)
+                        .map(|_| ElementValue::decode(buffer, constants, limits, depth + 1))
+                        .collect::<Result<Vec<_>>>()?,
+                )
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid annotation element tag: {tag}"
+                )))
+            }
+        })
+    }
+
+    fn invalid_constant(entry: &ConstantPoolEntry) -> ClassReaderError {
+        ClassReaderError::invalid_class_data(format!(
+            "invalid constant for annotation element: {entry:?}"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        annotation::{Annotation, ElementValue},
+        class_reader_error::ClassReaderError,
+        constant_pool::ConstantPool,
+        read_limits::{ReadLimit, ReadLimits},
+    };
+
+    #[test]
+    fn can_encode_and_decode_annotations() {
+        let annotations = vec![
+            Annotation::new("Ljava/lang/Deprecated;"),
+            Annotation::new("La/Config;")
+                .element("name", ElementValue::String("test".to_string()))
+                .element("enabled", ElementValue::Boolean(true))
+                .element("ratio", ElementValue::Double(0.5))
+                .element(
+                    "policy",
+                    ElementValue::Enum {
+                        type_descriptor: "La/Policy;".to_string(),
+                        const_name: "STRICT".to_string(),
+                    },
+                )
+                .element(
+                    "values",
+                    ElementValue::Array(vec![ElementValue::Long(1), ElementValue::Char(65)]),
+                )
+                .element(
+                    "nested",
+                    ElementValue::Annotation(Annotation::new("La/Nested;").element(
+                        "type",
+                        ElementValue::Class("Ljava/lang/String;".to_string()),
+                    )),
+                ),
+        ];
+
+        let mut constants = ConstantPool::new();
+        let bytes = Annotation::encode_attribute(&annotations, &mut constants).unwrap();
+        assert_eq!(
+            annotations,
+            Annotation::decode_attribute(&bytes, &constants).unwrap()
+        );
+    }
+
+    #[test]
+    fn cannot_decode_truncated_annotations() {
+        let mut constants = ConstantPool::new();
+        let bytes =
+            Annotation::encode_attribute(&[Annotation::new("La/Marker;")], &mut constants).unwrap();
+        assert!(Annotation::decode_attribute(&bytes[..bytes.len() - 1], &constants).is_err());
+    }
+
+    #[test]
+    fn cannot_decode_values_nested_too_deep() {
+        let mut constants = ConstantPool::new();
+        let value = (0..10).fold(ElementValue::Int(1), |value, _| {
+            ElementValue::Array(vec![value])
+        });
+        let annotations = vec![Annotation::new("La/Nested;").element("value", value)];
+        let bytes = Annotation::encode_attribute(&annotations, &mut constants).unwrap();
+
+        assert_eq!(
+            annotations,
+            Annotation::decode_attribute(&bytes, &constants).unwrap()
+        );
+        assert_eq!(
+            Err(ClassReaderError::LimitExceeded(
+                ReadLimit::AnnotationDepth,
+                5
+            )),
+            Annotation::decode_attribute_with_limits(
+                &bytes,
+                &constants,
+                &ReadLimits::default().max_annotation_depth(5)
+            )
+        );
+    }
+
+    #[test]
+    fn deeply_nested_values_do_not_overflow_the_stack() {
+        let mut constants = ConstantPool::new();
+        let mut bytes =
+            Annotation::encode_attribute(&[Annotation::new("La/Nested;")], &mut constants).unwrap();
+        // Replace the empty elements with one element that is an array of arrays of arrays...
+        let name = constants.intern_utf8("value").unwrap();
+        bytes.truncate(bytes.len() - 2);
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&name.to_be_bytes());
+        for _ in 0..100_000 {
+            bytes.extend_from_slice(&[b'[', 0, 1]);
+        }
+
+        assert_eq!(
+            Err(ClassReaderError::LimitExceeded(
+                ReadLimit::AnnotationDepth,
+                255
+            )),
+            Annotation::decode_attribute(&bytes, &constants)
+        );
+    }
+}
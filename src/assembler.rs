@@ -0,0 +1,1696 @@
+use std::{collections::HashMap, str::FromStr, sync::OnceLock};
+
+use bitflags::Flags;
+
+use crate::{
+    assembler_error::{AssemblerError, Result},
+    attribute::Attribute,
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    class_file_version::ClassFileVersion,
+    class_writer_error::ClassWriterError,
+    code_builder::compute_max_stack,
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    exception_table::{ExceptionTable, ExceptionTableEntry},
+    field_flags::FieldFlags,
+    field_type::FieldType,
+    instruction::{Instruction, NewArrayType, WideInstruction},
+    line_number::LineNumber,
+    line_number_table::{LineNumberTable, LineNumberTableEntry},
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+    stack_map_table::{initial_locals, StackMapFrame, StackMapTable, VerificationType},
+};
+
+/// Assembles a class from its textual form. The syntax is line based: every line holds a
+/// directive, starting with a `.`, or an instruction, optionally preceded by a label like
+/// `loop:`. Comments start with a `;` at the beginning of a token and run to the end of the
+/// line. Words containing spaces or special characters can be written as quoted strings,
+/// with the escapes `\"`, `\\`, `\n`, `\r`, `\t` and `\u{...}`.
+///
+/// ```text
+/// .version 52
+/// .class public super Hello
+/// .super java/lang/Object
+///
+/// .method public static main ([Ljava/lang/String;)V
+///   .code
+///         getstatic java/lang/System out Ljava/io/PrintStream;
+///         ldc "Hello, world!"
+///         invokevirtual java/io/PrintStream println (Ljava/lang/String;)V
+///         return
+///   .end code
+/// .end method
+/// ```
+///
+/// The directives at the class level are `.version`, `.class` with the flags and the name,
+/// `.super`, `.implements`, `.source`, `.deprecated`, and `.attribute` followed by the name
+/// and the content in hexadecimal. A class without `.super` has no superclass.
+///
+/// The constant pool can be laid out explicitly with `.const #1 = Methodref #3.#16`,
+/// `.const #2 = Utf8 "text"` or `.const #3 = Long 42`, using the kinds `Utf8`, `Integer`,
+/// `Float`, `Long`, `Double`, `Class`, `String`, `Fieldref`, `Methodref`,
+/// `InterfaceMethodref` and `NameAndType`. These constants come first in the pool, in the
+/// order they are declared, regardless of where they appear in the source.
+///
+/// Fields are declared by `.field` with the flags, the name, the descriptor and optionally
+/// a constant value like `= Integer 42`, and end with `.end field`. Methods are declared by
+/// `.method` with the flags, the name and the descriptor, and end with `.end method`. Both
+/// can contain `.deprecated` and `.attribute`, and methods also `.throws` and a `.code`
+/// block, ending with `.end code`.
+///
+/// `.code` can be followed by `stack` and `locals` with the limits of the code, which are
+/// computed when omitted. Instructions are written with the mnemonics printed by `javap`,
+/// and the instructions modified by `wide` with an `_w` suffix, i.e. `iinc_w 300 1`. Their
+/// operands are:
+/// - constants, given either by their index as `#12` or in a resolved form like
+///   `Methodref java/lang/Object <init> ()V`, which is added to the pool if missing. The
+///   kind can be omitted when it is implied by the instruction, i.e. `Fieldref` for
+///   `getfield`, `Class` for `new` and `String` for `ldc`;
+/// - jump targets, given by a label or an address;
+/// - local variable indexes, numbers, and the element type of `newarray`.
+///
+/// `tableswitch` takes the lowest key followed by the targets, and `lookupswitch` pairs of
+/// key and target, both followed by `default` and the default target.
+///
+/// Code blocks can also contain:
+/// - `.catch` with the class or `any`, followed by `from`, `to` and `using` with the range
+///   and the handler;
+/// - `.line` with the line number of the following instruction, or of the given position;
+/// - `.frame` with a position, `locals` and `stack` followed by the types in the
+///   `StackMapTable` frame: `top`, `int`, `float`, `long`, `double`, `null`,
+///   `uninitialized_this`, `class` and a name, or `uninitialized` and a position;
+/// - `.bytes` with some raw bytecode in hexadecimal;
+/// - `.attribute`, which adds a raw attribute to the code.
+///
+/// Nothing is validated beyond what the class file format can represent, so that invalid
+/// classes can be generated on purpose. [crate::disassembler::to_assembly] produces the
+/// source of an existing class.
+pub fn assemble(source: &str) -> Result<ClassFile> {
+    let statements = tokenize(source)?;
+    let mut assembler = Assembler {
+        statements: &statements,
+        position: 0,
+        class: ClassFile::default(),
+        has_class: false,
+    };
+    assembler.read_constants()?;
+    assembler.read_class()?;
+    Ok(assembler.class)
+}
+
+/// A word of the source, or a quoted string
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    quoted: bool,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: impl Into<String>) -> AssemblerError {
+        AssemblerError::new(self.line, self.column, message)
+    }
+
+    /// Quoted strings are never keywords
+    fn is(&self, keyword: &str) -> bool {
+        !self.quoted && self.text == keyword
+    }
+}
+
+/// The tokens of a non-empty line, along with the column just past its end
+struct Statement {
+    tokens: Vec<Token>,
+    line: usize,
+    end_column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Statement>> {
+    let mut statements = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let tokens = tokenize_line(line, index + 1)?;
+        if !tokens.is_empty() {
+            statements.push(Statement {
+                tokens,
+                line: index + 1,
+                end_column: line.chars().count() + 1,
+            });
+        }
+    }
+    Ok(statements)
+}
+
+fn tokenize_line(line: &str, line_number: usize) -> Result<Vec<Token>> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        if c == ';' {
+            break;
+        }
+
+        let column = index + 1;
+        let error =
+            |index: usize, message: &str| AssemblerError::new(line_number, index + 1, message);
+        let mut text = String::new();
+        let quoted = c == '"';
+        if quoted {
+            index += 1;
+            loop {
+                match chars.get(index) {
+                    None => return Err(error(column - 1, "unterminated string")),
+                    Some('"') => break,
+                    Some('\\') => {
+                        let (escaped, length) = match chars.get(index + 1) {
+                            Some('"') => ('"', 2),
+                            Some('\\') => ('\\', 2),
+                            Some('n') => ('\n', 2),
+                            Some('r') => ('\r', 2),
+                            Some('t') => ('\t', 2),
+                            Some('u') => {
+                                let end = chars[index..]
+                                    .iter()
+                                    .position(|c| *c == '}')
+                                    .filter(|_| chars.get(index + 2) == Some(&'{'))
+                                    .ok_or_else(|| error(index, "invalid unicode escape"))?;
+                                let digits: String = chars[index + 3..index + end].iter().collect();
+                                let escaped = u32::from_str_radix(&digits, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or_else(|| error(index, "invalid unicode escape"))?;
+                                (escaped, end + 1)
+                            }
+                            _ => return Err(error(index, "invalid escape sequence")),
+                        };
+                        text.push(escaped);
+                        index += length;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+        } else {
+            while index < chars.len() && !chars[index].is_whitespace() {
+                text.push(chars[index]);
+                index += 1;
+            }
+        }
+        tokens.push(Token {
+            text,
+            quoted,
+            line: line_number,
+            column,
+        });
+    }
+    Ok(tokens)
+}
+
+/// Reads the operands of a statement, following its first token
+struct Operands<'s> {
+    statement: &'s Statement,
+    position: usize,
+}
+
+impl<'s> Operands<'s> {
+    fn new(statement: &'s Statement) -> Self {
+        Self {
+            statement,
+            position: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<&'s Token> {
+        self.statement.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&'s Token> {
+        let token = self.peek().ok_or_else(|| {
+            AssemblerError::new(
+                self.statement.line,
+                self.statement.end_column,
+                format!("expected {expected}"),
+            )
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Skips the next token if it is the given keyword
+    fn next_if(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|token| token.is(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        let token = self.next(&format!("`{keyword}`"))?;
+        if token.is(keyword) {
+            Ok(())
+        } else {
+            Err(token.error(format!("expected `{keyword}`, found `{}`", token.text)))
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> Result<String> {
+        Ok(self.next(expected)?.text.clone())
+    }
+
+    fn number<T: FromStr>(&mut self, expected: &str) -> Result<T> {
+        let token = self.next(expected)?;
+        token
+            .text
+            .parse()
+            .map_err(|_| token.error(format!("expected {expected}, found `{}`", token.text)))
+    }
+
+    fn hex(&mut self) -> Result<Vec<u8>> {
+        let token = self.next("bytes in hexadecimal")?;
+        let digits: Vec<char> = token.text.chars().filter(|c| !c.is_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|pair| {
+                let pair: String = pair.iter().collect();
+                match pair.len() {
+                    2 => u8::from_str_radix(&pair, 16).ok(),
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| token.error("invalid hexadecimal bytes"))
+    }
+
+    fn position(&mut self) -> Result<Position> {
+        let token = self.next("a label or an address")?;
+        if !token.quoted && token.text.starts_with(|c: char| c.is_ascii_digit()) {
+            let address = token
+                .text
+                .parse()
+                .map_err(|_| token.error(format!("invalid address `{}`", token.text)))?;
+            Ok(Position::Address(address))
+        } else {
+            Ok(Position::Label(token.clone()))
+        }
+    }
+
+    fn finish(&self) -> Result<()> {
+        match self.peek() {
+            Some(token) => Err(token.error(format!("unexpected `{}`", token.text))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A position in the code, given by a label or by an address
+#[derive(Debug, Clone)]
+enum Position {
+    Label(Token),
+    Address(u16),
+}
+
+/// Reads flags like `public static`, named after the constants of the flags type in lower
+/// case
+fn flags<F: Flags>(operands: &mut Operands) -> F {
+    let mut flags = F::empty();
+    while let Some(flag) = operands
+        .peek()
+        .filter(|token| !token.quoted && token.text.chars().all(|c| c.is_ascii_lowercase()))
+        .and_then(|token| F::from_name(&token.text.to_ascii_uppercase()))
+    {
+        flags.insert(flag);
+        operands.position += 1;
+    }
+    flags
+}
+
+/// Reads a reference to a constant by index, like `#12`
+fn constant_index(token: &Token) -> Result<u16> {
+    token
+        .text
+        .strip_prefix('#')
+        .filter(|_| !token.quoted)
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| token.error(format!("expected a constant index, found `{}`", token.text)))
+}
+
+/// Reads a pair of constant indexes separated by the given character, like `#3.#16`
+fn constant_index_pair(token: &Token, separator: char) -> Result<(u16, u16)> {
+    let error = || {
+        token.error(format!(
+            "expected two constant indexes separated by `{separator}`, found `{}`",
+            token.text
+        ))
+    };
+    let (first, second) = token.text.split_once(separator).ok_or_else(error)?;
+    let index = |text: &str| {
+        text.strip_prefix('#')
+            .and_then(|index| index.parse().ok())
+            .ok_or_else(error)
+    };
+    Ok((index(first)?, index(second)?))
+}
+
+/// The kinds of the constants, named as in the constant pool listing of `javap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConstantKind {
+    Utf8,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Class,
+    String,
+    Fieldref,
+    Methodref,
+    InterfaceMethodref,
+    NameAndType,
+}
+
+impl ConstantKind {
+    const ALL: [ConstantKind; 11] = [
+        ConstantKind::Utf8,
+        ConstantKind::Integer,
+        ConstantKind::Float,
+        ConstantKind::Long,
+        ConstantKind::Double,
+        ConstantKind::Class,
+        ConstantKind::String,
+        ConstantKind::Fieldref,
+        ConstantKind::Methodref,
+        ConstantKind::InterfaceMethodref,
+        ConstantKind::NameAndType,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ConstantKind::Utf8 => "Utf8",
+            ConstantKind::Integer => "Integer",
+            ConstantKind::Float => "Float",
+            ConstantKind::Long => "Long",
+            ConstantKind::Double => "Double",
+            ConstantKind::Class => "Class",
+            ConstantKind::String => "String",
+            ConstantKind::Fieldref => "Fieldref",
+            ConstantKind::Methodref => "Methodref",
+            ConstantKind::InterfaceMethodref => "InterfaceMethodref",
+            ConstantKind::NameAndType => "NameAndType",
+        }
+    }
+
+    fn of(token: &Token) -> Option<ConstantKind> {
+        Self::ALL.into_iter().find(|kind| token.is(kind.name()))
+    }
+}
+
+/// A constant in its resolved form, where references to other constants are replaced by
+/// their content, i.e. `Methodref java/lang/Object <init> ()V`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SymbolicConstant {
+    Utf8(String),
+    Integer(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(String),
+    String(String),
+    Fieldref(String, String, String),
+    Methodref(String, String, String),
+    InterfaceMethodref(String, String, String),
+    NameAndType(String, String),
+}
+
+impl SymbolicConstant {
+    fn read(kind: ConstantKind, operands: &mut Operands) -> Result<Self> {
+        let member = |operands: &mut Operands| -> Result<(String, String, String)> {
+            Ok((
+                operands.word("a class name")?,
+                operands.word("a member name")?,
+                operands.word("a descriptor")?,
+            ))
+        };
+        Ok(match kind {
+            ConstantKind::Utf8 => SymbolicConstant::Utf8(operands.word("a string")?),
+            ConstantKind::Integer => SymbolicConstant::Integer(operands.number("an integer")?),
+            ConstantKind::Float => SymbolicConstant::Float(operands.number("a float")?),
+            ConstantKind::Long => SymbolicConstant::Long(operands.number("a long")?),
+            ConstantKind::Double => SymbolicConstant::Double(operands.number("a double")?),
+            ConstantKind::Class => SymbolicConstant::Class(operands.word("a class name")?),
+            ConstantKind::String => SymbolicConstant::String(operands.word("a string")?),
+            ConstantKind::Fieldref => {
+                let (class, name, descriptor) = member(operands)?;
+                SymbolicConstant::Fieldref(class, name, descriptor)
+            }
+            ConstantKind::Methodref => {
+                let (class, name, descriptor) = member(operands)?;
+                SymbolicConstant::Methodref(class, name, descriptor)
+            }
+            ConstantKind::InterfaceMethodref => {
+                let (class, name, descriptor) = member(operands)?;
+                SymbolicConstant::InterfaceMethodref(class, name, descriptor)
+            }
+            ConstantKind::NameAndType => SymbolicConstant::NameAndType(
+                operands.word("a member name")?,
+                operands.word("a descriptor")?,
+            ),
+        })
+    }
+
+    /// Resolves the constant at the given index, if it is valid
+    pub(crate) fn resolve(constants: &ConstantPool, index: u16) -> Option<Self> {
+        let utf8 = |index: u16| match constants.get(index) {
+            Ok(ConstantPoolEntry::Utf8(text)) => Some(text.clone()),
+            _ => None,
+        };
+        let class = |index: u16| match constants.get(index) {
+            Ok(ConstantPoolEntry::ClassReference(name)) => utf8(*name),
+            _ => None,
+        };
+        let member =
+            |class_index: u16, name_and_type_index: u16| match constants.get(name_and_type_index) {
+                Ok(ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor)) => {
+                    Some((class(class_index)?, utf8(*name)?, utf8(*descriptor)?))
+                }
+                _ => None,
+            };
+        Some(match constants.get(index).ok()? {
+            ConstantPoolEntry::Utf8(text) => SymbolicConstant::Utf8(text.clone()),
+            ConstantPoolEntry::Integer(value) => SymbolicConstant::Integer(*value),
+            ConstantPoolEntry::Float(value) => SymbolicConstant::Float(*value),
+            ConstantPoolEntry::Long(value) => SymbolicConstant::Long(*value),
+            ConstantPoolEntry::Double(value) => SymbolicConstant::Double(*value),
+            ConstantPoolEntry::ClassReference(name) => SymbolicConstant::Class(utf8(*name)?),
+            ConstantPoolEntry::StringReference(text) => SymbolicConstant::String(utf8(*text)?),
+            ConstantPoolEntry::FieldReference(class, name_and_type) => {
+                let (class, name, descriptor) = member(*class, *name_and_type)?;
+                SymbolicConstant::Fieldref(class, name, descriptor)
+            }
+            ConstantPoolEntry::MethodReference(class, name_and_type) => {
+                let (class, name, descriptor) = member(*class, *name_and_type)?;
+                SymbolicConstant::Methodref(class, name, descriptor)
+            }
+            ConstantPoolEntry::InterfaceMethodReference(class, name_and_type) => {
+                let (class, name, descriptor) = member(*class, *name_and_type)?;
+                SymbolicConstant::InterfaceMethodref(class, name, descriptor)
+            }
+            ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor) => {
+                SymbolicConstant::NameAndType(utf8(*name)?, utf8(*descriptor)?)
+            }
+        })
+    }
+
+    /// Returns the index of the constant, adding it and the ones it refers to if missing
+    pub(crate) fn intern(
+        &self,
+        constants: &mut ConstantPool,
+    ) -> std::result::Result<u16, ClassWriterError> {
+        match self {
+            SymbolicConstant::Utf8(text) => constants.intern_utf8(text),
+            SymbolicConstant::Integer(value) => {
+                constants.intern(ConstantPoolEntry::Integer(*value))
+            }
+            SymbolicConstant::Float(value) => constants.intern(ConstantPoolEntry::Float(*value)),
+            SymbolicConstant::Long(value) => constants.intern(ConstantPoolEntry::Long(*value)),
+            SymbolicConstant::Double(value) => constants.intern(ConstantPoolEntry::Double(*value)),
+            SymbolicConstant::Class(name) => constants.intern_class_reference(name),
+            SymbolicConstant::String(text) => constants.intern_string_reference(text),
+            SymbolicConstant::Fieldref(class, name, descriptor) => {
+                constants.intern_field_reference(class, name, descriptor)
+            }
+            SymbolicConstant::Methodref(class, name, descriptor) => {
+                constants.intern_method_reference(class, name, descriptor)
+            }
+            SymbolicConstant::InterfaceMethodref(class, name, descriptor) => {
+                constants.intern_interface_method_reference(class, name, descriptor)
+            }
+            SymbolicConstant::NameAndType(name, descriptor) => {
+                constants.intern_name_and_type(name, descriptor)
+            }
+        }
+    }
+
+    pub(crate) fn kind(&self) -> ConstantKind {
+        match self {
+            SymbolicConstant::Utf8(_) => ConstantKind::Utf8,
+            SymbolicConstant::Integer(_) => ConstantKind::Integer,
+            SymbolicConstant::Float(_) => ConstantKind::Float,
+            SymbolicConstant::Long(_) => ConstantKind::Long,
+            SymbolicConstant::Double(_) => ConstantKind::Double,
+            SymbolicConstant::Class(_) => ConstantKind::Class,
+            SymbolicConstant::String(_) => ConstantKind::String,
+            SymbolicConstant::Fieldref(..) => ConstantKind::Fieldref,
+            SymbolicConstant::Methodref(..) => ConstantKind::Methodref,
+            SymbolicConstant::InterfaceMethodref(..) => ConstantKind::InterfaceMethodref,
+            SymbolicConstant::NameAndType(..) => ConstantKind::NameAndType,
+        }
+    }
+
+    /// The operands of the constant, without its kind
+    pub(crate) fn operands(&self) -> String {
+        match self {
+            SymbolicConstant::Utf8(text)
+            | SymbolicConstant::Class(text)
+            | SymbolicConstant::String(text) => word(text),
+            SymbolicConstant::Integer(value) => value.to_string(),
+            SymbolicConstant::Float(value) => format!("{value:?}"),
+            SymbolicConstant::Long(value) => value.to_string(),
+            SymbolicConstant::Double(value) => format!("{value:?}"),
+            SymbolicConstant::Fieldref(class, name, descriptor)
+            | SymbolicConstant::Methodref(class, name, descriptor)
+            | SymbolicConstant::InterfaceMethodref(class, name, descriptor) => {
+                format!("{} {} {}", word(class), word(name), word(descriptor))
+            }
+            SymbolicConstant::NameAndType(name, descriptor) => {
+                format!("{} {}", word(name), word(descriptor))
+            }
+        }
+    }
+}
+
+/// Words with a meaning in some context, which must be quoted when used as names
+const KEYWORDS: [&str; 14] = [
+    "=",
+    "any",
+    "class",
+    "default",
+    "from",
+    "locals",
+    "stack",
+    "to",
+    "uninitialized",
+    "using",
+    "top",
+    "null",
+    "uninitialized_this",
+    "int",
+];
+
+/// Writes a word of the source, quoting it if it would not be read back as it is
+pub(crate) fn word(text: &str) -> String {
+    let is_keyword = KEYWORDS.contains(&text)
+        || ConstantKind::ALL.iter().any(|kind| kind.name() == text)
+        || text.chars().all(|c| c.is_ascii_lowercase())
+            && (ClassAccessFlags::from_name(&text.to_ascii_uppercase()).is_some()
+                || FieldFlags::from_name(&text.to_ascii_uppercase()).is_some()
+                || MethodFlags::from_name(&text.to_ascii_uppercase()).is_some());
+    let needs_quotes = is_keyword
+        || text.is_empty()
+        || text.starts_with(['#', '"', ';', '.'])
+        || text.ends_with(':')
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '\\');
+    if !needs_quotes {
+        return text.to_string();
+    }
+
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes bytes in hexadecimal, as expected by `.attribute` and `.bytes`
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+    format!("\"{digits}\"")
+}
+
+/// Reads a constant given by index or in the resolved form, adding it to the pool if needed.
+/// The kind can be omitted if a default one is given.
+fn constant(
+    operands: &mut Operands,
+    constants: &mut ConstantPool,
+    default_kind: Option<ConstantKind>,
+) -> Result<u16> {
+    let token = operands.peek();
+    if let Some(token) = token.filter(|token| !token.quoted && token.text.starts_with('#')) {
+        operands.position += 1;
+        return constant_index(token);
+    }
+    let kind = match token.and_then(ConstantKind::of) {
+        Some(kind) => {
+            operands.position += 1;
+            kind
+        }
+        None => default_kind.ok_or_else(|| match token {
+            Some(token) => token.error(format!("expected a constant, found `{}`", token.text)),
+            None => AssemblerError::new(
+                operands.statement.line,
+                operands.statement.end_column,
+                "expected a constant",
+            ),
+        })?,
+    };
+    SymbolicConstant::read(kind, operands)?
+        .intern(constants)
+        .map_err(|error| {
+            AssemblerError::new(
+                operands.statement.line,
+                operands.statement.end_column,
+                error.to_string(),
+            )
+        })
+}
+
+/// Reads a class attribute, given by its name and its content in hexadecimal
+fn attribute(operands: &mut Operands) -> Result<Attribute> {
+    Ok(Attribute {
+        name: operands.word("the name of the attribute")?,
+        bytes: operands.hex()?,
+    })
+}
+
+struct Assembler<'s> {
+    statements: &'s [Statement],
+    /// Index of the next statement to read
+    position: usize,
+    class: ClassFile,
+    has_class: bool,
+}
+
+impl<'s> Assembler<'s> {
+    fn next_statement(&mut self) -> Option<&'s Statement> {
+        let statement = self.statements.get(self.position)?;
+        self.position += 1;
+        Some(statement)
+    }
+
+    /// Reads the next statement of a block, or returns `None` when reaching its `.end`
+    fn block_statement(&mut self, block: &str) -> Result<Option<&'s Statement>> {
+        let statement = self.next_statement().ok_or_else(|| {
+            let (line, column) = self
+                .statements
+                .last()
+                .map_or((1, 1), |last| (last.line, last.end_column));
+            AssemblerError::new(line, column, format!("expected `.end {block}`"))
+        })?;
+        if !statement.tokens[0].is(".end") {
+            return Ok(Some(statement));
+        }
+        let mut operands = Operands::new(statement);
+        operands.keyword(block)?;
+        operands.finish()?;
+        Ok(None)
+    }
+
+    /// Builds the constant pool from the `.const` directives, before anything else can add
+    /// constants to it
+    fn read_constants(&mut self) -> Result<()> {
+        let statements = self.statements;
+        for statement in statements.iter().filter(|s| s.tokens[0].is(".const")) {
+            let mut operands = Operands::new(statement);
+            let index_token = operands.next("the index of the constant")?;
+            let index = constant_index(index_token)?;
+            let expected_index = self.class.constants.len() + 1;
+            if usize::from(index) != expected_index {
+                return Err(index_token.error(format!(
+                    "expected constant #{expected_index}, since constants must be declared in order"
+                )));
+            }
+            operands.keyword("=")?;
+            let kind_token = operands.next("the kind of the constant")?;
+            let kind = ConstantKind::of(kind_token).ok_or_else(|| {
+                kind_token.error(format!("unknown constant kind `{}`", kind_token.text))
+            })?;
+            let entry = match kind {
+                ConstantKind::Utf8 => ConstantPoolEntry::Utf8(operands.word("a string")?),
+                ConstantKind::Integer => ConstantPoolEntry::Integer(operands.number("an integer")?),
+                ConstantKind::Float => ConstantPoolEntry::Float(operands.number("a float")?),
+                ConstantKind::Long => ConstantPoolEntry::Long(operands.number("a long")?),
+                ConstantKind::Double => ConstantPoolEntry::Double(operands.number("a double")?),
+                ConstantKind::Class => ConstantPoolEntry::ClassReference(constant_index(
+                    operands.next("a constant index")?,
+                )?),
+                ConstantKind::String => ConstantPoolEntry::StringReference(constant_index(
+                    operands.next("a constant index")?,
+                )?),
+                ConstantKind::Fieldref => {
+                    let (class, name_and_type) =
+                        constant_index_pair(operands.next("`#class.#name_and_type`")?, '.')?;
+                    ConstantPoolEntry::FieldReference(class, name_and_type)
+                }
+                ConstantKind::Methodref => {
+                    let (class, name_and_type) =
+                        constant_index_pair(operands.next("`#class.#name_and_type`")?, '.')?;
+                    ConstantPoolEntry::MethodReference(class, name_and_type)
+                }
+                ConstantKind::InterfaceMethodref => {
+                    let (class, name_and_type) =
+                        constant_index_pair(operands.next("`#class.#name_and_type`")?, '.')?;
+                    ConstantPoolEntry::InterfaceMethodReference(class, name_and_type)
+                }
+                ConstantKind::NameAndType => {
+                    let (name, descriptor) =
+                        constant_index_pair(operands.next("`#name:#descriptor`")?, ':')?;
+                    ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor)
+                }
+            };
+            operands.finish()?;
+            self.class
+                .constants
+                .add(entry)
+                .map_err(|error| index_token.error(error.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn read_class(&mut self) -> Result<()> {
+        while let Some(statement) = self.next_statement() {
+            let directive = &statement.tokens[0];
+            let mut operands = Operands::new(statement);
+            match directive.text.as_str() {
+                _ if directive.quoted => {
+                    return Err(directive
+                        .error(format!("expected a directive, found `{}`", directive.text)))
+                }
+                ".const" => continue,
+                ".version" => {
+                    let token = operands.next("a major version")?;
+                    self.class.version = token
+                        .text
+                        .parse()
+                        .ok()
+                        .and_then(|major| ClassFileVersion::from(major, 0).ok())
+                        .ok_or_else(|| {
+                            token.error(format!("unsupported version `{}`", token.text))
+                        })?;
+                }
+                ".class" => {
+                    if self.has_class {
+                        return Err(directive.error("duplicate `.class` directive"));
+                    }
+                    self.class.flags = flags::<ClassAccessFlags>(&mut operands);
+                    self.class.name = operands.word("the name of the class")?;
+                    self.has_class = true;
+                }
+                ".super" => {
+                    self.class.superclass = Some(operands.word("the name of the superclass")?)
+                }
+                ".implements" => {
+                    let interface = operands.word("the name of the interface")?;
+                    self.class.interfaces.push(interface)
+                }
+                ".source" => {
+                    self.class.source_file = Some(operands.word("the name of the source file")?)
+                }
+                ".deprecated" => self.class.deprecated = true,
+                ".attribute" => self.class.attributes.push(attribute(&mut operands)?),
+                ".field" => {
+                    let field = self.read_field(&mut operands)?;
+                    self.class.fields.push(field);
+                }
+                ".method" => {
+                    if !self.has_class {
+                        return Err(
+                            directive.error("the `.class` directive must come before the methods")
+                        );
+                    }
+                    let method = self.read_method(&mut operands)?;
+                    self.class.methods.push(method);
+                }
+                _ => return Err(directive.error(format!("unknown directive `{}`", directive.text))),
+            }
+            operands.finish()?;
+        }
+        if !self.has_class {
+            return Err(AssemblerError::new(1, 1, "missing `.class` directive"));
+        }
+        Ok(())
+    }
+
+    fn read_field(&mut self, operands: &mut Operands) -> Result<ClassFileField> {
+        let flags = flags::<FieldFlags>(operands);
+        let name = operands.word("the name of the field")?;
+        let descriptor = operands.next("the descriptor of the field")?;
+        let type_descriptor = FieldType::parse(&descriptor.text).map_err(|_| {
+            descriptor.error(format!("invalid field descriptor `{}`", descriptor.text))
+        })?;
+        let constant_value = if operands.next_if("=") {
+            Some(Self::read_constant_value(operands)?)
+        } else {
+            None
+        };
+        operands.finish()?;
+
+        let mut field = ClassFileField {
+            flags,
+            name,
+            type_descriptor,
+            constant_value,
+            deprecated: false,
+            attributes: Vec::new(),
+        };
+        while let Some(statement) = self.block_statement("field")? {
+            let directive = &statement.tokens[0];
+            let mut operands = Operands::new(statement);
+            match directive.text.as_str() {
+                ".deprecated" if !directive.quoted => field.deprecated = true,
+                ".attribute" if !directive.quoted => {
+                    field.attributes.push(attribute(&mut operands)?)
+                }
+                _ => {
+                    return Err(
+                        directive.error(format!("unexpected `{}` in a field", directive.text))
+                    )
+                }
+            }
+            operands.finish()?;
+        }
+        Ok(field)
+    }
+
+    fn read_constant_value(operands: &mut Operands) -> Result<FieldConstantValue> {
+        let kind_token = operands.next("the kind of the constant value")?;
+        let constant = match ConstantKind::of(kind_token) {
+            Some(kind) => SymbolicConstant::read(kind, operands)?,
+            None => SymbolicConstant::Utf8(String::new()),
+        };
+        match constant {
+            SymbolicConstant::Integer(value) => Ok(FieldConstantValue::Int(value)),
+            SymbolicConstant::Float(value) => Ok(FieldConstantValue::Float(value)),
+            SymbolicConstant::Long(value) => Ok(FieldConstantValue::Long(value)),
+            SymbolicConstant::Double(value) => Ok(FieldConstantValue::Double(value)),
+            SymbolicConstant::String(value) => Ok(FieldConstantValue::String(value)),
+            _ => Err(kind_token.error(
+                "expected `Integer`, `Float`, `Long`, `Double` or `String` for the constant value",
+            )),
+        }
+    }
+
+    fn read_method(&mut self, operands: &mut Operands) -> Result<ClassFileMethod> {
+        let flags = flags::<MethodFlags>(operands);
+        let name = operands.word("the name of the method")?;
+        let descriptor = operands.next("the descriptor of the method")?;
+        let parsed_type_descriptor = MethodDescriptor::parse(&descriptor.text).map_err(|_| {
+            descriptor.error(format!("invalid method descriptor `{}`", descriptor.text))
+        })?;
+        operands.finish()?;
+
+        let mut method = ClassFileMethod {
+            flags,
+            name,
+            type_descriptor: descriptor.text.clone(),
+            parsed_type_descriptor,
+            attributes: Vec::new(),
+            code: None,
+            deprecated: false,
+            thrown_exceptions: Vec::new(),
+        };
+        while let Some(statement) = self.block_statement("method")? {
+            let directive = &statement.tokens[0];
+            let mut operands = Operands::new(statement);
+            match directive.text.as_str() {
+                _ if directive.quoted => {
+                    return Err(
+                        directive.error(format!("unexpected `{}` in a method", directive.text))
+                    )
+                }
+                ".throws" => {
+                    let exception = operands.word("the name of the exception")?;
+                    method.thrown_exceptions.push(exception)
+                }
+                ".deprecated" => method.deprecated = true,
+                ".attribute" => method.attributes.push(attribute(&mut operands)?),
+                ".code" => {
+                    if method.code.is_some() {
+                        return Err(directive.error("duplicate `.code` block"));
+                    }
+                    method.code = Some(self.read_code(&method, directive, &mut operands)?);
+                }
+                _ => {
+                    return Err(
+                        directive.error(format!("unexpected `{}` in a method", directive.text))
+                    )
+                }
+            }
+            operands.finish()?;
+        }
+        Ok(method)
+    }
+
+    fn read_code(
+        &mut self,
+        method: &ClassFileMethod,
+        directive: &Token,
+        operands: &mut Operands,
+    ) -> Result<ClassFileMethodCode> {
+        let mut max_stack = None;
+        let mut max_locals = None;
+        loop {
+            if operands.next_if("stack") {
+                max_stack = Some(operands.number("the maximum depth of the stack")?);
+            } else if operands.next_if("locals") {
+                max_locals = Some(operands.number("the number of local variables")?);
+            } else {
+                break;
+            }
+        }
+
+        let mut code = CodeAssembler::default();
+        while let Some(statement) = self.block_statement("code")? {
+            code.read_statement(statement, &mut self.class.constants)?;
+        }
+        code.build(
+            &self.class.name,
+            method,
+            directive,
+            max_stack,
+            max_locals,
+            &mut self.class.constants,
+        )
+    }
+}
+
+/// A type in a stack map frame
+#[derive(Debug, Clone)]
+enum FrameType {
+    Type(VerificationType),
+    Uninitialized(Position),
+}
+
+#[derive(Debug)]
+enum CodeItem {
+    Label(Token),
+    Instruction {
+        instruction: Instruction,
+        /// The jump targets, in the order of [Instruction::jump_targets]
+        targets: Vec<Position>,
+        mnemonic: Token,
+    },
+    Bytes(Vec<u8>),
+    Line(u16, Option<Position>),
+}
+
+#[derive(Debug)]
+struct CatchItem {
+    start: Position,
+    end: Position,
+    handler: Position,
+    catch_class: Option<String>,
+}
+
+#[derive(Debug)]
+struct FrameItem {
+    position: Position,
+    locals: Vec<FrameType>,
+    stack: Vec<FrameType>,
+}
+
+/// Collects the content of a `.code` block, which is laid out once all labels are known
+#[derive(Debug, Default)]
+struct CodeAssembler {
+    items: Vec<CodeItem>,
+    catches: Vec<CatchItem>,
+    frames: Vec<FrameItem>,
+    attributes: Vec<Attribute>,
+    /// Where the `StackMapTable` attribute goes among the other ones, if there are frames
+    stack_map_table_index: Option<usize>,
+}
+
+impl CodeAssembler {
+    fn read_statement(
+        &mut self,
+        statement: &Statement,
+        constants: &mut ConstantPool,
+    ) -> Result<()> {
+        let mut operands = Operands {
+            statement,
+            position: 0,
+        };
+        let mut first = operands.next("an instruction")?;
+        if !first.quoted && first.text.len() > 1 && first.text.ends_with(':') {
+            if first.text.starts_with(|c: char| c.is_ascii_digit()) {
+                return Err(first.error("labels cannot start with a digit"));
+            }
+            let mut label = first.clone();
+            label.text.pop();
+            self.items.push(CodeItem::Label(label));
+            match operands.peek() {
+                Some(_) => first = operands.next("an instruction")?,
+                None => return Ok(()),
+            }
+        }
+
+        match first.text.as_str() {
+            _ if first.quoted => {
+                return Err(first.error(format!("expected an instruction, found `{}`", first.text)))
+            }
+            ".line" => {
+                let line = operands.number("a line number")?;
+                let position = match operands.peek() {
+                    Some(_) => Some(operands.position()?),
+                    None => None,
+                };
+                self.items.push(CodeItem::Line(line, position));
+            }
+            ".catch" => {
+                let class = operands.next("a class name or `any`")?;
+                let catch_class = (!class.is("any")).then(|| class.text.clone());
+                operands.keyword("from")?;
+                let start = operands.position()?;
+                operands.keyword("to")?;
+                let end = operands.position()?;
+                operands.keyword("using")?;
+                let handler = operands.position()?;
+                self.catches.push(CatchItem {
+                    start,
+                    end,
+                    handler,
+                    catch_class,
+                });
+            }
+            ".frame" => {
+                let position = operands.position()?;
+                let mut locals = Vec::new();
+                let mut stack = Vec::new();
+                if operands.next_if("locals") {
+                    while operands.peek().is_some_and(|token| !token.is("stack")) {
+                        locals.push(Self::frame_type(&mut operands)?);
+                    }
+                }
+                if operands.next_if("stack") {
+                    while operands.peek().is_some() {
+                        stack.push(Self::frame_type(&mut operands)?);
+                    }
+                }
+                self.stack_map_table_index
+                    .get_or_insert(self.attributes.len());
+                self.frames.push(FrameItem {
+                    position,
+                    locals,
+                    stack,
+                });
+            }
+            ".bytes" => self.items.push(CodeItem::Bytes(operands.hex()?)),
+            ".attribute" => self.attributes.push(attribute(&mut operands)?),
+            text if text.starts_with('.') => {
+                return Err(first.error(format!("unexpected `{text}` in the code")))
+            }
+            _ => self.read_instruction(first, &mut operands, constants)?,
+        }
+        operands.finish()
+    }
+
+    fn frame_type(operands: &mut Operands) -> Result<FrameType> {
+        let token = operands.next("a type")?;
+        let verification_type = match token.text.as_str() {
+            _ if token.quoted => None,
+            "top" => Some(VerificationType::Top),
+            "int" => Some(VerificationType::Integer),
+            "float" => Some(VerificationType::Float),
+            "long" => Some(VerificationType::Long),
+            "double" => Some(VerificationType::Double),
+            "null" => Some(VerificationType::Null),
+            "uninitialized_this" => Some(VerificationType::UninitializedThis),
+            "class" => Some(VerificationType::Object(operands.word("a class name")?)),
+            "uninitialized" => return Ok(FrameType::Uninitialized(operands.position()?)),
+            _ => None,
+        };
+        verification_type
+            .map(FrameType::Type)
+            .ok_or_else(|| token.error(format!("unknown type `{}`", token.text)))
+    }
+
+    fn read_instruction(
+        &mut self,
+        mnemonic: &Token,
+        operands: &mut Operands,
+        constants: &mut ConstantPool,
+    ) -> Result<()> {
+        let mut instruction = templates()
+            .get(mnemonic.text.as_str())
+            .cloned()
+            .ok_or_else(|| mnemonic.error(format!("unknown instruction `{}`", mnemonic.text)))?;
+        let mut targets = Vec::new();
+        match &mut instruction {
+            Instruction::Aload { index }
+            | Instruction::Astore { index }
+            | Instruction::Dload { index }
+            | Instruction::Dstore { index }
+            | Instruction::Fload { index }
+            | Instruction::Fstore { index }
+            | Instruction::Iload { index }
+            | Instruction::Istore { index }
+            | Instruction::Lload { index }
+            | Instruction::Lstore { index }
+            | Instruction::Ret { index } => *index = operands.number("a local variable index")?,
+            Instruction::Iinc { index, constant } => {
+                *index = operands.number("a local variable index")?;
+                *constant = operands.number("a byte")?;
+            }
+            Instruction::Bipush { byte } => *byte = operands.number::<i8>("a byte")? as u8,
+            Instruction::Sipush { short } => *short = operands.number("a short")?,
+            Instruction::Ldc { index } => {
+                let constant = self::constant(operands, constants, Some(ConstantKind::String))?;
+                *index = u8::try_from(constant).map_err(|_| {
+                    mnemonic.error(format!(
+                        "constant #{constant} does not fit in `ldc`, use `ldc_w`"
+                    ))
+                })?;
+            }
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                *index = self::constant(operands, constants, Some(ConstantKind::String))?
+            }
+            Instruction::Anewarray { class }
+            | Instruction::Checkcast { class }
+            | Instruction::Instanceof { class }
+            | Instruction::New { class } => {
+                *class = self::constant(operands, constants, Some(ConstantKind::Class))?
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                *class = self::constant(operands, constants, Some(ConstantKind::Class))?;
+                *dimensions = operands.number("the number of dimensions")?;
+            }
+            Instruction::Getfield { field }
+            | Instruction::Getstatic { field }
+            | Instruction::Putfield { field }
+            | Instruction::Putstatic { field } => {
+                *field = self::constant(operands, constants, Some(ConstantKind::Fieldref))?
+            }
+            Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokevirtual { method } => {
+                *method = self::constant(operands, constants, Some(ConstantKind::Methodref))?
+            }
+            Instruction::Invokeinterface { method, count } => {
+                *method =
+                    self::constant(operands, constants, Some(ConstantKind::InterfaceMethodref))?;
+                *count = match operands.peek() {
+                    Some(_) => operands.number("the count of the arguments")?,
+                    None => Self::invokeinterface_count(constants, *method).ok_or_else(|| {
+                        mnemonic
+                            .error("cannot compute the count of the arguments, give it explicitly")
+                    })?,
+                };
+            }
+            Instruction::Invokedynamic { call_site } => {
+                *call_site = self::constant(operands, constants, None)?
+            }
+            Instruction::Newarray { array_type } => {
+                let token = operands.next("an element type")?;
+                *array_type = NEW_ARRAY_TYPES
+                    .into_iter()
+                    .find(|array_type| token.is(array_type.element_type_name()))
+                    .ok_or_else(|| token.error(format!("unknown element type `{}`", token.text)))?;
+            }
+            Instruction::Tableswitch {
+                low,
+                high,
+                jump_addresses,
+                ..
+            } => {
+                *low = operands.number("the lowest key")?;
+                while !operands.next_if("default") {
+                    targets.push(operands.position()?);
+                }
+                targets.insert(0, operands.position()?);
+                let cases = targets.len() - 1;
+                *high = i32::try_from(cases)
+                    .ok()
+                    .and_then(|cases| low.checked_add(cases - 1))
+                    .ok_or_else(|| mnemonic.error("too many cases"))?;
+                *jump_addresses = vec![0; cases];
+            }
+            Instruction::Lookupswitch { match_pairs, .. } => {
+                while !operands.next_if("default") {
+                    let key = operands.number("a key or `default`")?;
+                    targets.push(operands.position()?);
+                    match_pairs.push((key, 0));
+                }
+                targets.insert(0, operands.position()?);
+            }
+            Instruction::Wide { instruction } => match instruction {
+                WideInstruction::Iinc { index, constant } => {
+                    *index = operands.number("a local variable index")?;
+                    *constant = operands.number("a short")?;
+                }
+                WideInstruction::Iload { index }
+                | WideInstruction::Fload { index }
+                | WideInstruction::Aload { index }
+                | WideInstruction::Lload { index }
+                | WideInstruction::Dload { index }
+                | WideInstruction::Istore { index }
+                | WideInstruction::Fstore { index }
+                | WideInstruction::Astore { index }
+                | WideInstruction::Lstore { index }
+                | WideInstruction::Dstore { index }
+                | WideInstruction::Ret { index } => {
+                    *index = operands.number("a local variable index")?
+                }
+            },
+            // The remaining instructions either have no operands, or are jumps
+            _ => {
+                for _ in instruction.jump_targets() {
+                    targets.push(operands.position()?);
+                }
+            }
+        }
+        self.items.push(CodeItem::Instruction {
+            instruction,
+            targets,
+            mnemonic: mnemonic.clone(),
+        });
+        Ok(())
+    }
+
+    /// The count of `invokeinterface` is the number of slots taken by the arguments,
+    /// including the receiver
+    fn invokeinterface_count(constants: &ConstantPool, method: u16) -> Option<u8> {
+        let reference = constants.member_reference(method).ok()?;
+        let descriptor = MethodDescriptor::parse(&reference.type_descriptor).ok()?;
+        u8::try_from(descriptor.arguments_slots() + 1).ok()
+    }
+
+    /// Lays out the code, resolving the labels, and computes the limits that were not given
+    fn build(
+        self,
+        class_name: &str,
+        method: &ClassFileMethod,
+        directive: &Token,
+        max_stack: Option<u16>,
+        max_locals: Option<u16>,
+        constants: &mut ConstantPool,
+    ) -> Result<ClassFileMethodCode> {
+        let mut labels: HashMap<&str, u16> = HashMap::new();
+        let mut addresses = Vec::with_capacity(self.items.len());
+        let mut address = 0;
+        for item in self.items.iter() {
+            let too_large = || directive.error("the code is larger than 65535 bytes");
+            addresses.push(u16::try_from(address).map_err(|_| too_large())?);
+            match item {
+                CodeItem::Label(label) => {
+                    if labels.insert(&label.text, address as u16).is_some() {
+                        return Err(label.error(format!("duplicate label `{}`", label.text)));
+                    }
+                }
+                CodeItem::Instruction { instruction, .. } => {
+                    address += instruction.encoded_len(address)
+                }
+                CodeItem::Bytes(bytes) => address += bytes.len(),
+                CodeItem::Line(..) => {}
+            }
+        }
+        if address > u16::MAX as usize {
+            return Err(directive.error("the code is larger than 65535 bytes"));
+        }
+
+        let resolve = |position: &Position| match position {
+            Position::Address(address) => Ok(*address),
+            Position::Label(label) => labels
+                .get(label.text.as_str())
+                .copied()
+                .ok_or_else(|| label.error(format!("undefined label `{}`", label.text))),
+        };
+
+        let mut code = Vec::with_capacity(address);
+        let mut line_numbers = Vec::new();
+        for (item, address) in self.items.iter().zip(addresses) {
+            match item {
+                CodeItem::Instruction {
+                    instruction,
+                    targets,
+                    mnemonic,
+                } => {
+                    let mut instruction = instruction.clone();
+                    let targets = targets.iter().map(resolve).collect::<Result<Vec<u16>>>()?;
+                    set_jump_targets(&mut instruction, &targets);
+                    instruction
+                        .encode(&mut code)
+                        .map_err(|error| mnemonic.error(error.to_string()))?;
+                }
+                CodeItem::Bytes(bytes) => code.extend_from_slice(bytes),
+                CodeItem::Line(line, position) => {
+                    let program_counter = match position {
+                        Some(position) => resolve(position)?,
+                        None => address,
+                    };
+                    line_numbers.push(LineNumberTableEntry::new(
+                        ProgramCounter(program_counter),
+                        LineNumber(*line),
+                    ));
+                }
+                CodeItem::Label(_) => {}
+            }
+        }
+
+        let exception_table = self
+            .catches
+            .iter()
+            .map(|catch| {
+                Ok(ExceptionTableEntry {
+                    range: ProgramCounter(resolve(&catch.start)?)
+                        ..ProgramCounter(resolve(&catch.end)?),
+                    handler_pc: ProgramCounter(resolve(&catch.handler)?),
+                    catch_class: catch.catch_class.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut attributes = self.attributes;
+        if let Some(index) = self.stack_map_table_index {
+            let types = |types: &[FrameType]| {
+                types
+                    .iter()
+                    .map(|frame_type| match frame_type {
+                        FrameType::Type(verification_type) => Ok(verification_type.clone()),
+                        FrameType::Uninitialized(position) => Ok(VerificationType::Uninitialized(
+                            ProgramCounter(resolve(position)?),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()
+            };
+            let frames = self
+                .frames
+                .iter()
+                .map(|frame| {
+                    Ok(StackMapFrame {
+                        program_counter: ProgramCounter(resolve(&frame.position)?),
+                        locals: types(&frame.locals)?,
+                        stack: types(&frame.stack)?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let bytes = StackMapTable::new(frames)
+                .encode(&initial_locals(class_name, method), constants)
+                .map_err(|error| directive.error(error.to_string()))?;
+            attributes.insert(
+                index,
+                Attribute {
+                    name: "StackMapTable".to_string(),
+                    bytes,
+                },
+            );
+        }
+
+        let instructions = || {
+            Instruction::parse_instructions(&code).map_err(|error| {
+                directive.error(format!(
+                    "cannot compute the limits of invalid code ({error}), give them with `stack` and `locals`"
+                ))
+            })
+        };
+        let max_stack = match max_stack {
+            Some(max_stack) => max_stack,
+            None => compute_max_stack(&instructions()?, &exception_table, constants).map_err(
+                |error| {
+                    directive.error(format!(
+                    "cannot compute the maximum depth of the stack ({error}), give it with `stack`"
+                ))
+                },
+            )?,
+        };
+        let max_locals = match max_locals {
+            Some(max_locals) => max_locals,
+            None => instructions()?
+                .iter()
+                .filter_map(|(_, instruction)| instruction.local_variable_slots())
+                .map(|slots| slots.end)
+                .fold(
+                    method.parsed_type_descriptor.arguments_slots()
+                        + u16::from(!method.is_static()),
+                    u16::max,
+                ),
+        };
+
+        Ok(ClassFileMethodCode {
+            max_stack,
+            max_locals,
+            code,
+            exception_table: ExceptionTable::new(exception_table),
+            line_number_table: if line_numbers.is_empty() {
+                None
+            } else {
+                Some(LineNumberTable::new(line_numbers))
+            },
+            attributes,
+        })
+    }
+}
+
+const NEW_ARRAY_TYPES: [NewArrayType; 8] = [
+    NewArrayType::Boolean,
+    NewArrayType::Char,
+    NewArrayType::Float,
+    NewArrayType::Double,
+    NewArrayType::Byte,
+    NewArrayType::Short,
+    NewArrayType::Int,
+    NewArrayType::Long,
+];
+
+/// Every instruction by mnemonic, with its operands set to zero. Built by decoding all the
+/// opcodes, so that it is always consistent with the decoder.
+fn templates() -> &'static HashMap<&'static str, Instruction> {
+    static TEMPLATES: OnceLock<HashMap<&'static str, Instruction>> = OnceLock::new();
+    TEMPLATES.get_or_init(|| {
+        let newarray_opcode = Instruction::Newarray {
+            array_type: NewArrayType::Int,
+        }
+        .opcode();
+        let wide_opcode = Instruction::Wide {
+            instruction: WideInstruction::Iinc {
+                index: 0,
+                constant: 0,
+            },
+        }
+        .opcode();
+
+        let mut templates = HashMap::new();
+        for opcode in 0..=u8::MAX {
+            // Long enough for a tableswitch with a single case
+            let mut code = [0u8; 24];
+            code[0] = opcode;
+            if opcode == newarray_opcode {
+                code[1] = NewArrayType::Int.type_code();
+            }
+            let mut wide_code = [0u8; 6];
+            wide_code[0] = wide_opcode;
+            wide_code[1] = opcode;
+            for code in [&code[..], &wide_code[..]] {
+                if let Ok((instruction, _)) = Instruction::parse(code, 0) {
+                    templates.insert(instruction.mnemonic(), instruction);
+                }
+            }
+        }
+        templates
+    })
+}
+
+/// Replaces the jump targets of an instruction, given in the order of
+/// [Instruction::jump_targets]
+fn set_jump_targets(instruction: &mut Instruction, targets: &[u16]) {
+    match instruction {
+        Instruction::Goto { jump_address }
+        | Instruction::Goto_w { jump_address }
+        | Instruction::If_acmpeq { jump_address }
+        | Instruction::If_acmpne { jump_address }
+        | Instruction::If_icmpeq { jump_address }
+        | Instruction::If_icmpne { jump_address }
+        | Instruction::If_icmplt { jump_address }
+        | Instruction::If_icmpge { jump_address }
+        | Instruction::If_icmpgt { jump_address }
+        | Instruction::If_icmple { jump_address }
+        | Instruction::Ifeq { jump_address }
+        | Instruction::Ifne { jump_address }
+        | Instruction::Iflt { jump_address }
+        | Instruction::Ifge { jump_address }
+        | Instruction::Ifgt { jump_address }
+        | Instruction::Ifle { jump_address }
+        | Instruction::Ifnonnull { jump_address }
+        | Instruction::Ifnull { jump_address }
+        | Instruction::Jsr { jump_address }
+        | Instruction::Jsr_w { jump_address } => *jump_address = targets[0],
+        Instruction::Tableswitch {
+            default,
+            jump_addresses,
+            ..
+        } => {
+            *default = targets[0];
+            jump_addresses.copy_from_slice(&targets[1..]);
+        }
+        Instruction::Lookupswitch {
+            default,
+            match_pairs,
+        } => {
+            *default = targets[0];
+            for ((_, jump_address), target) in match_pairs.iter_mut().zip(&targets[1..]) {
+                *jump_address = *target;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assembler::{assemble, word},
+        assembler_error::AssemblerError,
+        class_access_flags::ClassAccessFlags,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+    };
+
+    #[test]
+    fn assembles_a_minimal_class() {
+        let class = assemble(
+            r#"
+            .class public super Hello ; a comment
+            .super java/lang/Object
+            .method public static main ([Ljava/lang/String;)V
+              .code
+                    getstatic java/lang/System out Ljava/io/PrintStream;
+                    ldc "Hello, world!"
+                    invokevirtual java/io/PrintStream println (Ljava/lang/String;)V
+                    return
+              .end code
+            .end method
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!("Hello", class.name);
+        assert_eq!(
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            class.flags
+        );
+        let method = &class.methods[0];
+        assert_eq!(MethodFlags::PUBLIC | MethodFlags::STATIC, method.flags);
+        let code = method.code.as_ref().unwrap();
+        assert_eq!(2, code.max_stack);
+        assert_eq!(1, code.max_locals);
+        let instructions = Instruction::parse_instructions(&code.code).unwrap();
+        assert_eq!(4, instructions.len());
+        assert_eq!(
+            "java/io/PrintStream.println: (Ljava/lang/String;)V",
+            class.constants.text_of(14).unwrap()
+        );
+    }
+
+    #[test]
+    fn resolves_labels_forward_and_backward() {
+        let class = assemble(
+            r#"
+            .class Loop
+            .method static loop (I)V
+              .code
+              start: iload_0
+                    ifeq end
+                    iinc 0 -1
+                    goto start
+              end:  return
+              .end code
+            .end method
+            "#,
+        )
+        .unwrap();
+
+        let code = class.methods[0].code.as_ref().unwrap();
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload_0),
+                (1, Instruction::Ifeq { jump_address: 10 }),
+                (
+                    4,
+                    Instruction::Iinc {
+                        index: 0,
+                        constant: -1
+                    }
+                ),
+                (7, Instruction::Goto { jump_address: 0 }),
+                (10, Instruction::Return),
+            ],
+            Instruction::parse_instructions(&code.code).unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_explicit_constants_at_their_index() {
+        let class = assemble(
+            r#"
+            .class Odd
+            .const #1 = Utf8 "same"
+            .const #2 = Utf8 "same"
+            .const #3 = Long 7
+            .const #5 = String #2
+            .method static value ()Ljava/lang/String;
+              .code stack 9 locals 0
+                    ldc #5
+                    areturn
+              .end code
+            .end method
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(5, class.constants.len());
+        let code = class.methods[0].code.as_ref().unwrap();
+        assert_eq!(9, code.max_stack);
+        assert_eq!(vec![0x12, 5, 0xb0], code.code);
+    }
+
+    #[test]
+    fn reports_line_and_column_of_errors() {
+        assert_eq!(
+            Err(AssemblerError::new(
+                4,
+                11,
+                "unknown instruction `frobnicate`"
+            )),
+            assemble(
+                ".class A\n.method m ()V\n  .code\n          frobnicate\n  .end code\n.end method"
+            )
+            .map(|_| ())
+        );
+        assert_eq!(
+            Err(AssemblerError::new(4, 6, "undefined label `nowhere`")),
+            assemble(".class A\n.method m ()V\n.code\ngoto nowhere\n.end code\n.end method")
+                .map(|_| ())
+        );
+        assert_eq!(
+            Err(AssemblerError::new(
+                2,
+                8,
+                "expected constant #1, since constants must be declared in order"
+            )),
+            assemble(".class A\n.const #2 = Integer 1").map(|_| ())
+        );
+        assert_eq!(
+            Err(AssemblerError::new(1, 8, "unterminated string")),
+            assemble(".class \"A").map(|_| ())
+        );
+    }
+
+    #[test]
+    fn quotes_words_only_when_needed() {
+        assert_eq!("java/lang/Object", word("java/lang/Object"));
+        assert_eq!("<init>", word("<init>"));
+        assert_eq!("\"\"", word(""));
+        assert_eq!("\"public\"", word("public"));
+        assert_eq!("\"a \\\"b\\\"\\n\"", word("a \"b\"\n"));
+    }
+}
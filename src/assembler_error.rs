@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+/// Error returned when assembling a class from its textual form, with the position in the
+/// source of the token that caused it. Lines and columns start from 1.
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("line {line}, column {column}: {message}")]
+pub struct AssemblerError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl AssemblerError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AssemblerError>;
@@ -1,7 +1,8 @@
 use std::{fmt, fmt::Formatter};
 
 /// An attribute in the class file, which can belong to a class, field, method, or code block.
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute {
     pub name: String,
     pub bytes: Vec<u8>,
@@ -0,0 +1,217 @@
+use std::{fmt, fmt::Formatter};
+
+use crate::{
+    analyzer::{Interpreter, Value},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    exception_table::ExceptionTableEntry,
+    field_type::{BaseType, FieldType},
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+};
+
+/// The values of [BasicInterpreter], which only distinguishes the kinds of values that the
+/// JVM stores in locals and on the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BasicValue {
+    /// A local not assigned yet, the second slot of a long or double, or a local that holds
+    /// different kinds of values on different execution paths
+    Uninitialized,
+    /// Also used for booleans, bytes, chars and shorts
+    Int,
+    Float,
+    Long,
+    Double,
+    Reference,
+}
+
+impl BasicValue {
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(BaseType::Long) => BasicValue::Long,
+            FieldType::Base(BaseType::Float) => BasicValue::Float,
+            FieldType::Base(BaseType::Double) => BasicValue::Double,
+            FieldType::Base(_) => BasicValue::Int,
+            FieldType::Object(_) | FieldType::Array(_) => BasicValue::Reference,
+        }
+    }
+}
+
+impl Value for BasicValue {
+    fn slots(&self) -> u16 {
+        match self {
+            BasicValue::Long | BasicValue::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for BasicValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BasicValue::Uninitialized => "uninitialized",
+            BasicValue::Int => "int",
+            BasicValue::Float => "float",
+            BasicValue::Long => "long",
+            BasicValue::Double => "double",
+            BasicValue::Reference => "reference",
+        })
+    }
+}
+
+/// An [Interpreter] that computes the kind of every value, like the `BasicInterpreter` of ASM.
+/// It does not check that the instructions are applied to values of the right kind; use the
+/// [verifier](crate::verifier) for that.
+pub struct BasicInterpreter<'a> {
+    constants: &'a ConstantPool,
+}
+
+impl<'a> BasicInterpreter<'a> {
+    /// Creates an interpreter for methods of the class owning the given constant pool, which
+    /// is needed to find the types of constants, fields and methods
+    pub fn new(constants: &'a ConstantPool) -> Self {
+        Self { constants }
+    }
+
+    fn field_type(&self, index: u16) -> Result<BasicValue, String> {
+        let reference = self
+            .constants
+            .member_reference(index)
+            .map_err(|err| err.to_string())?;
+        let field_type =
+            FieldType::parse(&reference.type_descriptor).map_err(|err| err.to_string())?;
+        Ok(BasicValue::from_field_type(&field_type))
+    }
+
+    fn constant_type(&self, index: u16) -> Result<BasicValue, String> {
+        match self.constants.get(index).map_err(|err| err.to_string())? {
+            ConstantPoolEntry::Integer(_) => Ok(BasicValue::Int),
+            ConstantPoolEntry::Float(_) => Ok(BasicValue::Float),
+            ConstantPoolEntry::Long(_) => Ok(BasicValue::Long),
+            ConstantPoolEntry::Double(_) => Ok(BasicValue::Double),
+            ConstantPoolEntry::StringReference(_) | ConstantPoolEntry::ClassReference(_) => {
+                Ok(BasicValue::Reference)
+            }
+            _ => Err(format!("constant #{index} cannot be loaded")),
+        }
+    }
+}
+
+impl<'a> Interpreter<BasicValue> for BasicInterpreter<'a> {
+    fn new_empty_value(&mut self) -> BasicValue {
+        BasicValue::Uninitialized
+    }
+
+    fn new_parameter_value(&mut self, _local: u16, field_type: &FieldType) -> BasicValue {
+        BasicValue::from_field_type(field_type)
+    }
+
+    fn new_exception_value(&mut self, _handler: &ExceptionTableEntry) -> BasicValue {
+        BasicValue::Reference
+    }
+
+    fn new_operation(
+        &mut self,
+        _pc: ProgramCounter,
+        instruction: &Instruction,
+    ) -> Result<BasicValue, String> {
+        Ok(match instruction {
+            Instruction::Lconst_0 | Instruction::Lconst_1 => BasicValue::Long,
+            Instruction::Fconst_0 | Instruction::Fconst_1 | Instruction::Fconst_2 => {
+                BasicValue::Float
+            }
+            Instruction::Dconst_0 | Instruction::Dconst_1 => BasicValue::Double,
+            Instruction::Aconst_null | Instruction::New { .. } => BasicValue::Reference,
+            Instruction::Ldc { index } => self.constant_type(*index as u16)?,
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                self.constant_type(*index)?
+            }
+            Instruction::Getstatic { field } => self.field_type(*field)?,
+            _ => BasicValue::Int,
+        })
+    }
+
+    fn unary_operation(
+        &mut self,
+        _pc: ProgramCounter,
+        instruction: &Instruction,
+        _value: &BasicValue,
+    ) -> Result<BasicValue, String> {
+        Ok(match instruction {
+            Instruction::Lneg | Instruction::I2l | Instruction::F2l | Instruction::D2l => {
+                BasicValue::Long
+            }
+            Instruction::Fneg | Instruction::I2f | Instruction::L2f | Instruction::D2f => {
+                BasicValue::Float
+            }
+            Instruction::Dneg | Instruction::I2d | Instruction::L2d | Instruction::F2d => {
+                BasicValue::Double
+            }
+            Instruction::Getfield { field } => self.field_type(*field)?,
+            Instruction::Newarray { .. }
+            | Instruction::Anewarray { .. }
+            | Instruction::Checkcast { .. } => BasicValue::Reference,
+            _ => BasicValue::Int,
+        })
+    }
+
+    fn binary_operation(
+        &mut self,
+        _pc: ProgramCounter,
+        instruction: &Instruction,
+        first: &BasicValue,
+        _second: &BasicValue,
+    ) -> Result<BasicValue, String> {
+        Ok(match instruction {
+            Instruction::Laload => BasicValue::Long,
+            Instruction::Faload => BasicValue::Float,
+            Instruction::Daload => BasicValue::Double,
+            Instruction::Aaload => BasicValue::Reference,
+            Instruction::Lcmp
+            | Instruction::Fcmpl
+            | Instruction::Fcmpg
+            | Instruction::Dcmpl
+            | Instruction::Dcmpg
+            | Instruction::Iaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload => BasicValue::Int,
+            // Arithmetic, where shifts keep the type of the shifted value
+            _ => *first,
+        })
+    }
+
+    fn nary_operation(
+        &mut self,
+        _pc: ProgramCounter,
+        instruction: &Instruction,
+        _values: &[BasicValue],
+    ) -> Result<BasicValue, String> {
+        match instruction {
+            Instruction::Invokevirtual { method }
+            | Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                let reference = self
+                    .constants
+                    .member_reference(*method)
+                    .map_err(|err| err.to_string())?;
+                let descriptor = MethodDescriptor::parse(&reference.type_descriptor)
+                    .map_err(|err| err.to_string())?;
+                Ok(descriptor
+                    .return_type
+                    .as_ref()
+                    .map_or(BasicValue::Uninitialized, BasicValue::from_field_type))
+            }
+            _ => Ok(BasicValue::Reference),
+        }
+    }
+
+    fn merge(&mut self, first: &BasicValue, second: &BasicValue) -> BasicValue {
+        if first == second {
+            *first
+        } else {
+            BasicValue::Uninitialized
+        }
+    }
+}
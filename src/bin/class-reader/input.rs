@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use class_reader::{class_file::ClassFile, class_reader_error::ClassReaderError, read_buffer};
+
+/// A class read from one of the inputs
+pub struct LoadedClass {
+    /// Where the class was read from, i.e. `app.jar!/com/example/Foo.class`
+    pub origin: String,
+    pub class: ClassFile,
+    /// The content of the class file, shown by the explorer
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+    pub bytes: Vec<u8>,
+}
+
+/// Error for an input that could not be read. Reading goes on with the other inputs, so a
+/// single broken class in a jar does not prevent inspecting the rest of it.
+#[derive(Error, Debug)]
+pub enum InputError {
+    #[error("{origin}: {source}")]
+    Io { origin: String, source: io::Error },
+    #[error("{origin}: {source}")]
+    Zip {
+        origin: String,
+        source: zip::result::ZipError,
+    },
+    #[error("{origin}: {source}")]
+    Class {
+        origin: String,
+        source: ClassReaderError,
+    },
+}
+
+/// All the classes read from the inputs given on the command line
+#[derive(Default)]
+pub struct ClassPath {
+    pub classes: Vec<LoadedClass>,
+    pub errors: Vec<InputError>,
+}
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+impl ClassPath {
+    /// Reads every path, which can be a class file, a jar or a directory that is searched
+    /// recursively for class files. An empty list, or the path `-`, reads a single class from
+    /// the standard input.
+    pub fn load(paths: &[PathBuf]) -> ClassPath {
+        let mut class_path = ClassPath::default();
+        if paths.is_empty() {
+            class_path.load_stdin();
+        }
+        for path in paths {
+            if path.as_os_str() == "-" {
+                class_path.load_stdin();
+            } else if path.is_dir() {
+                class_path.load_directory(path);
+            } else {
+                class_path.load_file(path);
+            }
+        }
+        class_path
+    }
+
+    /// Finds a class given its internal or binary name, i.e. `java/lang/String` or
+    /// `java.lang.String`
+    pub fn find(&self, name: &str) -> Option<&LoadedClass> {
+        let name = internal_name(name);
+        self.classes.iter().find(|loaded| loaded.class.name == name)
+    }
+
+    fn load_stdin(&mut self) {
+        let mut bytes = Vec::new();
+        match io::stdin().read_to_end(&mut bytes) {
+            Ok(_) => self.add("<stdin>".to_string(), &bytes),
+            Err(source) => self.errors.push(InputError::Io {
+                origin: "<stdin>".to_string(),
+                source,
+            }),
+        }
+    }
+
+    fn load_directory(&mut self, directory: &Path) {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(source) => {
+                self.errors.push(InputError::Io {
+                    origin: directory.display().to_string(),
+                    source,
+                });
+                return;
+            }
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                self.load_directory(&path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == "class")
+            {
+                self.load_file(&path);
+            }
+        }
+    }
+
+    fn load_file(&mut self, path: &Path) {
+        let origin = path.display().to_string();
+        match fs::read(path) {
+            Ok(bytes) if bytes.starts_with(ZIP_MAGIC) => self.load_jar(origin, bytes),
+            Ok(bytes) => self.add(origin, &bytes),
+            Err(source) => self.errors.push(InputError::Io { origin, source }),
+        }
+    }
+
+    fn load_jar(&mut self, origin: String, bytes: Vec<u8>) {
+        let mut archive = match zip::ZipArchive::new(io::Cursor::new(bytes)) {
+            Ok(archive) => archive,
+            Err(source) => {
+                self.errors.push(InputError::Zip { origin, source });
+                return;
+            }
+        };
+        for index in 0..archive.len() {
+            let mut entry = match archive.by_index(index) {
+                Ok(entry) => entry,
+                Err(source) => {
+                    self.errors.push(InputError::Zip {
+                        origin: origin.clone(),
+                        source,
+                    });
+                    continue;
+                }
+            };
+            if !entry.is_file() || !entry.name().ends_with(".class") {
+                continue;
+            }
+            let entry_origin = format!("{origin}!/{}", entry.name());
+            let mut bytes = Vec::new();
+            match entry.read_to_end(&mut bytes) {
+                Ok(_) => self.add(entry_origin, &bytes),
+                Err(source) => self.errors.push(InputError::Io {
+                    origin: entry_origin,
+                    source,
+                }),
+            }
+        }
+    }
+
+    fn add(&mut self, origin: String, bytes: &[u8]) {
+        match read_buffer(bytes) {
+            Ok(class) => self.classes.push(LoadedClass {
+                origin,
+                class,
+                bytes: bytes.to_vec(),
+            }),
+            Err(source) => self.errors.push(InputError::Class { origin, source }),
+        }
+    }
+}
+
+pub fn internal_name(name: &str) -> String {
+    name.replace('.', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::input::ClassPath;
+
+    fn resource(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/resources")
+            .join(name)
+    }
+
+    #[test]
+    fn reads_the_classes_of_a_directory() {
+        let class_path = ClassPath::load(&[resource("rjvm")]);
+
+        assert!(class_path.errors.is_empty());
+        let names: Vec<&str> = class_path
+            .classes
+            .iter()
+            .map(|loaded| loaded.class.name.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "rjvm/Complex",
+                "rjvm/Constants",
+                "rjvm/DeprecatedClass",
+                "rjvm/ExceptionsHandlers",
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn reports_the_inputs_that_cannot_be_read() {
+        let class_path = ClassPath::load(&[resource("rjvm/Complex.java"), resource("missing")]);
+
+        assert!(class_path.classes.is_empty());
+        assert_eq!(2, class_path.errors.len());
+        assert!(class_path.errors[0].to_string().contains("Complex.java"));
+    }
+}
@@ -0,0 +1,236 @@
+//! `class-reader`: inspects class files, jars and directories of classes without needing a
+//! JDK, printing them either in the format of `javap` or as JSON.
+
+mod input;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use class_reader::{
+    class_file::ClassFile,
+    disassembler::{disassemble, disassemble_constant_pool, DisasmOptions},
+};
+
+use crate::input::{internal_name, ClassPath, LoadedClass};
+
+#[derive(Parser)]
+#[command(
+    name = "class-reader",
+    version,
+    about = "Inspects class files, jars and directories of classes"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Disassembles the classes like `javap`
+    Dump {
+        #[command(flatten)]
+        inputs: Inputs,
+        #[command(flatten)]
+        members: MemberFilter,
+        /// Print the instructions of the methods
+        #[arg(short = 'c', long)]
+        code: bool,
+        /// Print the line number tables
+        #[arg(short = 'l', long)]
+        line_numbers: bool,
+        /// Print the constant pool, the flags and all the attributes
+        #[arg(short = 'v', long)]
+        verbose: bool,
+    },
+    /// Prints the whole model of the classes as JSON, one document per class
+    Json {
+        #[command(flatten)]
+        inputs: Inputs,
+        /// Only include the methods with this name
+        #[arg(long)]
+        method: Option<String>,
+        /// Indent the output
+        #[arg(long)]
+        pretty: bool,
+    },
+    /// Prints the constant pool of the classes
+    Constants {
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+    /// Lists the methods of the classes
+    Methods {
+        #[command(flatten)]
+        inputs: Inputs,
+        #[command(flatten)]
+        members: MemberFilter,
+        /// Print the instructions of the methods
+        #[arg(short = 'c', long)]
+        code: bool,
+    },
+    /// Prints the constant pool entries containing the given text, prefixed by their class
+    GrepConstants {
+        /// Text to search, matched against the entries as printed by `constants`
+        pattern: String,
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+    /// Explores the classes interactively, starting from the one given with `--class`
+    #[cfg(feature = "tui")]
+    Tui {
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+}
+
+#[derive(Args)]
+struct Inputs {
+    /// Class files, jars or directories containing classes. With no path, or with `-`, a
+    /// class is read from the standard input
+    paths: Vec<PathBuf>,
+    /// Only consider the class with this name, i.e. `java/lang/String` or `java.lang.String`
+    #[arg(long = "class", value_name = "NAME")]
+    class_name: Option<String>,
+}
+
+#[derive(Args)]
+struct MemberFilter {
+    /// Include the private members
+    #[arg(short = 'p', long)]
+    private: bool,
+    /// Only include the methods with this name
+    #[arg(long)]
+    method: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let inputs = match &cli.command {
+        Command::Dump { inputs, .. }
+        | Command::Json { inputs, .. }
+        | Command::Constants { inputs }
+        | Command::Methods { inputs, .. }
+        | Command::GrepConstants { inputs, .. } => inputs,
+        #[cfg(feature = "tui")]
+        Command::Tui { inputs } => inputs,
+    };
+
+    let class_path = ClassPath::load(&inputs.paths);
+    #[cfg(feature = "tui")]
+    if let Command::Tui { .. } = cli.command {
+        return match tui::explore(&class_path, inputs.class_name.as_deref()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    for error in &class_path.errors {
+        eprintln!("error: {error}");
+    }
+    let classes: Vec<&LoadedClass> = match &inputs.class_name {
+        Some(name) => class_path.find(name).into_iter().collect(),
+        None => class_path.classes.iter().collect(),
+    };
+    if let Some(name) = &inputs.class_name {
+        if classes.is_empty() {
+            eprintln!("error: class {} not found", internal_name(name));
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let result = run(&cli.command, &classes);
+    match result {
+        Ok(()) if class_path.errors.is_empty() => ExitCode::SUCCESS,
+        // Writing to a closed pipe, i.e. `class-reader dump foo.jar | head`, is not an error
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+        Ok(()) => ExitCode::FAILURE,
+    }
+}
+
+fn run(command: &Command, classes: &[&LoadedClass]) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for loaded in classes {
+        let class = &loaded.class;
+        match command {
+            Command::Dump {
+                members,
+                code,
+                line_numbers,
+                verbose,
+                ..
+            } => {
+                let options = DisasmOptions {
+                    code: *code,
+                    line_numbers: *line_numbers,
+                    verbose: *verbose,
+                    private: members.private,
+                };
+                let class = members.apply(class, false);
+                write!(out, "{}", disassemble(&class, options))?;
+            }
+            Command::Json { method, pretty, .. } => {
+                let class = with_methods_named(class.clone(), method.as_deref());
+                if *pretty {
+                    serde_json::to_writer_pretty(&mut out, &class)?;
+                } else {
+                    serde_json::to_writer(&mut out, &class)?;
+                }
+                writeln!(out)?;
+            }
+            Command::Constants { .. } => {
+                writeln!(out, "{}:", loaded.origin)?;
+                write!(out, "{}", disassemble_constant_pool(class))?;
+            }
+            Command::Methods { members, code, .. } => {
+                let options = DisasmOptions {
+                    code: *code,
+                    private: members.private,
+                    ..DisasmOptions::default()
+                };
+                write!(out, "{}", disassemble(&members.apply(class, true), options))?;
+            }
+            Command::GrepConstants { pattern, .. } => {
+                for line in disassemble_constant_pool(class)
+                    .lines()
+                    .skip(1)
+                    .filter(|line| line.contains(pattern.as_str()))
+                {
+                    writeln!(out, "{}: {}", class.name, line.trim_start())?;
+                }
+            }
+            #[cfg(feature = "tui")]
+            Command::Tui { .. } => unreachable!("the explorer is started by main"),
+        }
+    }
+    Ok(())
+}
+
+impl MemberFilter {
+    fn apply(&self, class: &ClassFile, methods_only: bool) -> ClassFile {
+        let mut class = with_methods_named(class.clone(), self.method.as_deref());
+        if methods_only || self.method.is_some() {
+            class.fields.clear();
+        }
+        class
+    }
+}
+
+fn with_methods_named(mut class: ClassFile, name: Option<&str>) -> ClassFile {
+    if let Some(name) = name {
+        class.methods.retain(|method| method.name == name);
+    }
+    class
+}
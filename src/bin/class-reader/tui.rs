@@ -0,0 +1,867 @@
+//! Interactive explorer of the classes, started by `class-reader tui`. It shows a tree of the
+//! classes with their members and attributes; selecting an element shows its disassembly, the
+//! constant pool entries it refers to and its bytes in the class file.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    io,
+};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use class_reader::{
+    attribute::Attribute,
+    class_file::ClassFile,
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    disassembler::{disassemble, disassemble_constant_pool, DisasmOptions},
+    instruction::Instruction,
+    span::{class_spans, format_hex_dump},
+};
+
+use crate::input::{internal_name, ClassPath};
+
+/// Runs the explorer until the user quits, optionally starting from the given class
+pub fn explore(class_path: &ClassPath, class_name: Option<&str>) -> io::Result<()> {
+    let mut app = App::new(class_path);
+    if !class_path.errors.is_empty() {
+        app.status = format!(
+            "{} inputs could not be read, run another command to see why",
+            class_path.errors.len()
+        );
+    }
+    if let Some(name) = class_name {
+        let name = internal_name(name);
+        if let Some(index) = class_path.classes.iter().position(|l| l.class.name == name) {
+            app.expanded.insert(Node::Class(index));
+            app.select_node(Node::Class(index));
+        }
+    }
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// An element of a class shown in the tree. Classes, fields and methods are identified by
+/// their index in the class path and in their class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    Class(usize),
+    Field(usize, usize),
+    Method(usize, usize),
+    Attribute(usize, Owner, usize),
+}
+
+/// The element an attribute belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Owner {
+    Class,
+    Field(usize),
+    Method(usize),
+}
+
+impl Node {
+    fn class_index(&self) -> usize {
+        match self {
+            Node::Class(class)
+            | Node::Field(class, _)
+            | Node::Method(class, _)
+            | Node::Attribute(class, _, _) => *class,
+        }
+    }
+
+    fn parent(&self) -> Option<Node> {
+        match *self {
+            Node::Class(_) => None,
+            Node::Field(class, _) | Node::Method(class, _) => Some(Node::Class(class)),
+            Node::Attribute(class, Owner::Class, _) => Some(Node::Class(class)),
+            Node::Attribute(class, Owner::Field(field), _) => Some(Node::Field(class, field)),
+            Node::Attribute(class, Owner::Method(method), _) => Some(Node::Method(class, method)),
+        }
+    }
+}
+
+struct Row {
+    node: Node,
+    depth: usize,
+    label: String,
+    expandable: bool,
+}
+
+/// A line of the disassembly, with the constant pool entry its instruction refers to
+struct DisassemblyLine {
+    text: String,
+    constant: Option<u16>,
+}
+
+/// What is shown for the selected element
+#[derive(Default)]
+struct Detail {
+    disassembly: Vec<DisassemblyLine>,
+    /// Indexes of the constant pool entries referred to, and their description
+    references: Vec<(u16, String)>,
+    raw_bytes: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Tree,
+    Disassembly,
+    References,
+    RawBytes,
+}
+
+impl Pane {
+    fn next(self) -> Pane {
+        match self {
+            Pane::Tree => Pane::Disassembly,
+            Pane::Disassembly => Pane::References,
+            Pane::References => Pane::RawBytes,
+            Pane::RawBytes => Pane::Tree,
+        }
+    }
+
+    fn previous(self) -> Pane {
+        match self {
+            Pane::Tree => Pane::RawBytes,
+            Pane::Disassembly => Pane::Tree,
+            Pane::References => Pane::Disassembly,
+            Pane::RawBytes => Pane::References,
+        }
+    }
+}
+
+const HELP: &str =
+    "tab: switch pane | enter: expand, go to constant | g: go to class | b: back | q: quit";
+
+struct App<'a> {
+    class_path: &'a ClassPath,
+    expanded: HashSet<Node>,
+    rows: Vec<Row>,
+    tree: ListState,
+    focus: Pane,
+    detail: Detail,
+    disassembly: ListState,
+    references: ListState,
+    raw_bytes_scroll: u16,
+    /// The elements selected before each jump, to go back to them
+    history: Vec<Node>,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(class_path: &'a ClassPath) -> App<'a> {
+        let mut app = App {
+            class_path,
+            expanded: HashSet::new(),
+            rows: Vec::new(),
+            tree: ListState::default(),
+            focus: Pane::Tree,
+            detail: Detail::default(),
+            disassembly: ListState::default(),
+            references: ListState::default(),
+            raw_bytes_scroll: 0,
+            history: Vec::new(),
+            status: HELP.to_string(),
+        };
+        app.refresh_rows();
+        if !app.rows.is_empty() {
+            app.tree.select(Some(0));
+            app.update_detail();
+        }
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn refresh_rows(&mut self) {
+        let mut rows = Vec::new();
+        for (c, loaded) in self.class_path.classes.iter().enumerate() {
+            let class = &loaded.class;
+            let node = Node::Class(c);
+            rows.push(Row {
+                node,
+                depth: 0,
+                label: class.name.replace('/', "."),
+                expandable: true,
+            });
+            if !self.expanded.contains(&node) {
+                continue;
+            }
+            for (f, field) in class.fields.iter().enumerate() {
+                let node = Node::Field(c, f);
+                rows.push(Row {
+                    node,
+                    depth: 1,
+                    label: format!("{}: {}", field.name, field.type_descriptor.descriptor()),
+                    expandable: !field.attributes.is_empty(),
+                });
+                if self.expanded.contains(&node) {
+                    Self::push_attributes(&mut rows, &field.attributes, c, Owner::Field(f));
+                }
+            }
+            for (m, method) in class.methods.iter().enumerate() {
+                let node = Node::Method(c, m);
+                rows.push(Row {
+                    node,
+                    depth: 1,
+                    label: format!("{}{}", method.name, method.type_descriptor),
+                    expandable: !method.attributes.is_empty(),
+                });
+                if self.expanded.contains(&node) {
+                    Self::push_attributes(&mut rows, &method.attributes, c, Owner::Method(m));
+                }
+            }
+            Self::push_attributes(&mut rows, &class.attributes, c, Owner::Class);
+        }
+        self.rows = rows;
+    }
+
+    fn push_attributes(rows: &mut Vec<Row>, attributes: &[Attribute], class: usize, owner: Owner) {
+        let depth = if owner == Owner::Class { 1 } else { 2 };
+        for (a, attribute) in attributes.iter().enumerate() {
+            rows.push(Row {
+                node: Node::Attribute(class, owner, a),
+                depth,
+                label: format!("@{}", attribute.name),
+                expandable: false,
+            });
+        }
+    }
+
+    fn selected_node(&self) -> Option<Node> {
+        self.tree
+            .selected()
+            .and_then(|row| self.rows.get(row))
+            .map(|row| row.node)
+    }
+
+    /// Selects the given element in the tree, expanding the elements containing it
+    fn select_node(&mut self, node: Node) {
+        let mut parent = node.parent();
+        while let Some(ancestor) = parent {
+            self.expanded.insert(ancestor);
+            parent = ancestor.parent();
+        }
+        self.refresh_rows();
+        let row = self.rows.iter().position(|row| row.node == node);
+        self.tree.select(row);
+        self.update_detail();
+    }
+
+    fn update_detail(&mut self) {
+        self.detail = self
+            .selected_node()
+            .map(|node| self.detail_of(node))
+            .unwrap_or_default();
+        self.disassembly.select(None);
+        *self.disassembly.offset_mut() = 0;
+        self.references.select(None);
+        *self.references.offset_mut() = 0;
+        self.raw_bytes_scroll = 0;
+    }
+
+    fn detail_of(&self, node: Node) -> Detail {
+        let loaded = &self.class_path.classes[node.class_index()];
+        let class = &loaded.class;
+        let constants = &class.constants;
+        let options = DisasmOptions::default().code().line_numbers().private();
+        let plain_lines = |text: String| -> Vec<DisassemblyLine> {
+            text.lines()
+                .map(|line| DisassemblyLine {
+                    text: line.to_string(),
+                    constant: None,
+                })
+                .collect()
+        };
+        let utf8 = |text: &str| constants.find(&ConstantPoolEntry::Utf8(text.to_string()));
+
+        let (disassembly, references, span_path): (_, Vec<u16>, _) = match node {
+            Node::Class(_) => (
+                plain_lines(disassemble(class, options)),
+                constants.iter().map(|(index, _)| index as u16).collect(),
+                None,
+            ),
+            Node::Field(_, f) => {
+                let field = &class.fields[f];
+                let mut member = class.clone();
+                member.fields = vec![field.clone()];
+                member.methods.clear();
+                let references = [utf8(&field.name), utf8(&field.type_descriptor.descriptor())]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (
+                    plain_lines(disassemble(&member, options)),
+                    references,
+                    Some(format!("field[{f}]")),
+                )
+            }
+            Node::Method(_, m) => {
+                let method = &class.methods[m];
+                let mut member = class.clone();
+                member.fields.clear();
+                member.methods = vec![method.clone()];
+                let instructions: HashMap<usize, Instruction> = method
+                    .code
+                    .as_ref()
+                    .and_then(|code| Instruction::parse_instructions(&code.code).ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+                let disassembly: Vec<DisassemblyLine> = disassemble(&member, options)
+                    .lines()
+                    .map(|line| DisassemblyLine {
+                        text: line.to_string(),
+                        constant: instruction_address(line)
+                            .and_then(|address| instructions.get(&address))
+                            .and_then(Instruction::constant_pool_index),
+                    })
+                    .collect();
+                let mut references: BTreeSet<u16> =
+                    [utf8(&method.name), utf8(&method.type_descriptor)]
+                        .into_iter()
+                        .flatten()
+                        .collect();
+                references.extend(disassembly.iter().filter_map(|line| line.constant));
+                (
+                    disassembly,
+                    references.into_iter().collect(),
+                    Some(format!("method[{m}]")),
+                )
+            }
+            Node::Attribute(_, owner, a) => {
+                let (attribute, prefix) = match owner {
+                    Owner::Class => (&class.attributes[a], String::new()),
+                    Owner::Field(f) => (&class.fields[f].attributes[a], format!("field[{f}].")),
+                    Owner::Method(m) => (&class.methods[m].attributes[a], format!("method[{m}].")),
+                };
+                (
+                    plain_lines(attribute.to_string()),
+                    utf8(&attribute.name).into_iter().collect(),
+                    Some(format!("{prefix}{}", attribute.name)),
+                )
+            }
+        };
+
+        Detail {
+            disassembly,
+            references: describe_constants(constants, &references),
+            raw_bytes: raw_bytes(&loaded.bytes, span_path.as_deref()),
+        }
+    }
+
+    /// Handles a key press, returning false if the explorer should quit
+    fn handle_key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Tab => self.focus = self.focus.next(),
+            KeyCode::BackTab => self.focus = self.focus.previous(),
+            KeyCode::Char('b') | KeyCode::Backspace => self.back(),
+            KeyCode::Char('g') => self.go_to_class(),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-20),
+            KeyCode::PageDown => self.move_selection(20),
+            KeyCode::Enter => match self.focus {
+                Pane::Tree => self.toggle_expanded(),
+                Pane::Disassembly => self.go_to_constant(),
+                _ => {}
+            },
+            KeyCode::Right | KeyCode::Char('l') if self.focus == Pane::Tree => {
+                self.set_expanded(true)
+            }
+            KeyCode::Left | KeyCode::Char('h') if self.focus == Pane::Tree => {
+                self.set_expanded(false)
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.focus {
+            Pane::Tree => (&mut self.tree, self.rows.len()),
+            Pane::Disassembly => (&mut self.disassembly, self.detail.disassembly.len()),
+            Pane::References => (&mut self.references, self.detail.references.len()),
+            Pane::RawBytes => {
+                let scroll = self.raw_bytes_scroll as isize + delta;
+                let max = self.detail.raw_bytes.lines().count().saturating_sub(1);
+                self.raw_bytes_scroll = scroll.clamp(0, max as isize) as u16;
+                return;
+            }
+        };
+        if len == 0 {
+            return;
+        }
+        let selected = match state.selected() {
+            Some(selected) => (selected as isize + delta).clamp(0, len as isize - 1) as usize,
+            None => 0,
+        };
+        state.select(Some(selected));
+        if self.focus == Pane::Tree {
+            self.update_detail();
+        }
+    }
+
+    fn toggle_expanded(&mut self) {
+        if let Some(node) = self.selected_node() {
+            let expanded = self.expanded.contains(&node);
+            self.set_expanded(!expanded);
+        }
+    }
+
+    /// Expands or collapses the selected element. Collapsing an element that is not expanded
+    /// selects its parent instead.
+    fn set_expanded(&mut self, expanded: bool) {
+        let Some(node) = self.selected_node() else {
+            return;
+        };
+        if expanded {
+            self.expanded.insert(node);
+        } else if !self.expanded.remove(&node) {
+            if let Some(parent) = node.parent() {
+                self.select_node(parent);
+            }
+            return;
+        }
+        self.select_node(node);
+    }
+
+    fn selected_constant(&self) -> Option<u16> {
+        match self.focus {
+            Pane::Disassembly => self
+                .disassembly
+                .selected()
+                .and_then(|line| self.detail.disassembly.get(line))
+                .and_then(|line| line.constant),
+            Pane::References => self
+                .references
+                .selected()
+                .and_then(|row| self.detail.references.get(row))
+                .map(|(index, _)| *index),
+            _ => None,
+        }
+    }
+
+    /// Selects the constant pool entry referred to by the selected instruction
+    fn go_to_constant(&mut self) {
+        let Some(constant) = self.selected_constant() else {
+            self.status = "The selected line does not refer to a constant".to_string();
+            return;
+        };
+        let row = self
+            .detail
+            .references
+            .iter()
+            .position(|(index, _)| *index == constant);
+        self.references.select(row);
+        self.focus = Pane::References;
+        self.status = format!("Constant #{constant}");
+    }
+
+    /// Selects the class, field or method referred to by the selected constant, if its class
+    /// is loaded
+    fn go_to_class(&mut self) {
+        let (Some(node), Some(constant)) = (self.selected_node(), self.selected_constant()) else {
+            self.status = "Select an instruction or a constant referring to a class".to_string();
+            return;
+        };
+        let constants = &self.class_path.classes[node.class_index()].class.constants;
+        let (class_name, member) = match constants.get(constant) {
+            Ok(ConstantPoolEntry::ClassReference(_)) => match constants.text_of(constant) {
+                Ok(class_name) => (class_name, None),
+                Err(_) => return,
+            },
+            Ok(
+                ConstantPoolEntry::FieldReference(..)
+                | ConstantPoolEntry::MethodReference(..)
+                | ConstantPoolEntry::InterfaceMethodReference(..),
+            ) => match constants.member_reference(constant) {
+                Ok(member) => (
+                    member.class_name,
+                    Some((member.name, member.type_descriptor)),
+                ),
+                Err(_) => return,
+            },
+            _ => {
+                self.status = format!("Constant #{constant} does not refer to a class");
+                return;
+            }
+        };
+
+        let classes = &self.class_path.classes;
+        let Some(target) = classes.iter().position(|l| l.class.name == class_name) else {
+            self.status = format!("Class {class_name} is not loaded");
+            return;
+        };
+        let class = &classes[target].class;
+        let target_node = member
+            .and_then(|(name, descriptor)| {
+                let method = class
+                    .methods
+                    .iter()
+                    .position(|m| m.name == name && m.type_descriptor == descriptor)
+                    .map(|m| Node::Method(target, m));
+                method.or_else(|| {
+                    class
+                        .fields
+                        .iter()
+                        .position(|f| {
+                            f.name == name && f.type_descriptor.descriptor() == descriptor
+                        })
+                        .map(|f| Node::Field(target, f))
+                })
+            })
+            .unwrap_or(Node::Class(target));
+
+        self.history.push(node);
+        self.select_node(target_node);
+        self.focus = Pane::Tree;
+        self.status = format!("Went to {}", self.node_label(target_node));
+    }
+
+    fn back(&mut self) {
+        if let Some(node) = self.history.pop() {
+            self.select_node(node);
+            self.focus = Pane::Tree;
+        }
+    }
+
+    fn node_label(&self, node: Node) -> String {
+        let class = &self.class_path.classes[node.class_index()].class;
+        match node {
+            Node::Class(_) => class.name.clone(),
+            _ => self
+                .rows
+                .iter()
+                .find(|row| row.node == node)
+                .map_or_else(String::new, |row| format!("{}.{}", class.name, row.label)),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree, disassembly, side] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+            Constraint::Percentage(35),
+        ])
+        .areas(main);
+        let [references, raw_bytes] =
+            Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(side);
+
+        let tree_items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let marker = match (row.expandable, self.expanded.contains(&row.node)) {
+                    (false, _) => "  ",
+                    (true, false) => "▸ ",
+                    (true, true) => "▾ ",
+                };
+                ListItem::new(format!("{}{marker}{}", "  ".repeat(row.depth), row.label))
+            })
+            .collect();
+        self.draw_list(frame, tree, "Classes", Pane::Tree, tree_items);
+
+        let disassembly_items: Vec<ListItem> = self
+            .detail
+            .disassembly
+            .iter()
+            .map(|line| ListItem::new(line.text.clone()))
+            .collect();
+        self.draw_list(
+            frame,
+            disassembly,
+            "Disassembly",
+            Pane::Disassembly,
+            disassembly_items,
+        );
+
+        let reference_items: Vec<ListItem> = self
+            .detail
+            .references
+            .iter()
+            .map(|(_, text)| ListItem::new(text.clone()))
+            .collect();
+        self.draw_list(
+            frame,
+            references,
+            "Constant pool",
+            Pane::References,
+            reference_items,
+        );
+
+        let raw = Paragraph::new(self.detail.raw_bytes.as_str())
+            .block(self.block("Raw bytes", Pane::RawBytes))
+            .scroll((self.raw_bytes_scroll, 0));
+        frame.render_widget(raw, raw_bytes);
+
+        frame.render_widget(Line::from(self.status.as_str()), status);
+    }
+
+    fn draw_list(
+        &mut self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        pane: Pane,
+        items: Vec<ListItem>,
+    ) {
+        let list = List::new(items)
+            .block(self.block(title, pane))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let state = match pane {
+            Pane::Tree => &mut self.tree,
+            Pane::Disassembly => &mut self.disassembly,
+            Pane::References => &mut self.references,
+            Pane::RawBytes => unreachable!("the raw bytes are not a list"),
+        };
+        frame.render_stateful_widget(list, area, state);
+    }
+
+    fn block(&self, title: &str, pane: Pane) -> Block<'static> {
+        let style = if self.focus == pane {
+            Style::new().fg(Color::Yellow)
+        } else {
+            Style::new()
+        };
+        Block::bordered()
+            .title(title.to_string())
+            .border_style(style)
+    }
+}
+
+/// Returns the address of the instruction printed on a line of the disassembly, if it is one
+fn instruction_address(line: &str) -> Option<usize> {
+    let (address, _) = line.trim_start().split_once(": ")?;
+    address.parse().ok()
+}
+
+/// Describes the given constants as in the constant pool listing of `javap -v`
+fn describe_constants(constants: &ConstantPool, indexes: &[u16]) -> Vec<(u16, String)> {
+    let listing = disassemble_constant_pool(&class_with_constants(constants));
+    let lines: HashMap<u16, &str> = listing
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let index = line.strip_prefix('#')?.split_once(' ')?.0.parse().ok()?;
+            Some((index, line))
+        })
+        .collect();
+    indexes
+        .iter()
+        .filter_map(|index| lines.get(index).map(|line| (*index, line.to_string())))
+        .collect()
+}
+
+fn class_with_constants(constants: &ConstantPool) -> ClassFile {
+    ClassFile {
+        constants: constants.clone(),
+        ..Default::default()
+    }
+}
+
+/// Renders the hex dump of the element whose spans start with the given path, or of the
+/// whole class if there is none
+fn raw_bytes(bytes: &[u8], path: Option<&str>) -> String {
+    let Ok(spans) = class_spans(bytes) else {
+        return "The spans of the class could not be computed".to_string();
+    };
+    let spans: Vec<_> = match path {
+        None => spans,
+        Some(path) => spans
+            .into_iter()
+            .filter(|labeled| {
+                labeled.path == path
+                    || labeled
+                        .path
+                        .strip_prefix(path)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .collect(),
+    };
+    format_hex_dump(bytes, &spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal};
+
+    use crate::{
+        input::ClassPath,
+        tui::{App, Node, Pane},
+    };
+
+    fn class_path() -> ClassPath {
+        ClassPath::load(&[PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/resources/rjvm")])
+    }
+
+    fn class_index(app: &App, name: &str) -> usize {
+        app.class_path
+            .classes
+            .iter()
+            .position(|loaded| loaded.class.name == name)
+            .unwrap()
+    }
+
+    fn method_index(app: &App, class: usize, name: &str) -> usize {
+        app.class_path.classes[class]
+            .class
+            .methods
+            .iter()
+            .position(|method| method.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn expands_and_collapses_classes() {
+        let class_path = class_path();
+        let mut app = App::new(&class_path);
+        assert_eq!(4, app.rows.len());
+        assert_eq!(Some(Node::Class(0)), app.selected_node());
+
+        app.handle_key(KeyCode::Enter);
+        let complex = &class_path.classes[0].class;
+        assert!(app.rows.len() > 4 + complex.methods.len());
+        app.handle_key(KeyCode::Down);
+        assert_eq!(Some(Node::Field(0, 0)), app.selected_node());
+
+        app.handle_key(KeyCode::Left);
+        assert_eq!(Some(Node::Class(0)), app.selected_node());
+        app.handle_key(KeyCode::Left);
+        assert_eq!(4, app.rows.len());
+    }
+
+    #[test]
+    fn shows_the_details_of_a_method() {
+        let class_path = class_path();
+        let mut app = App::new(&class_path);
+        let class = class_index(&app, "rjvm/Complex");
+        let method = method_index(&app, class, "abs");
+        app.select_node(Node::Method(class, method));
+
+        assert!(app
+            .detail
+            .disassembly
+            .iter()
+            .any(|line| line.text.contains("invokestatic  #4")));
+        let references: Vec<u16> = app.detail.references.iter().map(|(i, _)| *i).collect();
+        assert!(references.contains(&4));
+        assert!(app
+            .detail
+            .references
+            .iter()
+            .any(|(_, text)| text.ends_with("= Utf8               abs")));
+        assert!(app
+            .detail
+            .raw_bytes
+            .lines()
+            .all(|line| line.contains(&format!("method[{method}]")) || !line.contains("method[")));
+    }
+
+    #[test]
+    fn jumps_from_an_invocation_to_the_constant_and_to_the_target_method() {
+        let class_path = class_path();
+        let mut app = App::new(&class_path);
+        let class = class_index(&app, "rjvm/ExceptionsHandlers");
+        let test = method_index(&app, class, "test");
+        app.select_node(Node::Method(class, test));
+
+        app.focus = Pane::Disassembly;
+        let line = app
+            .detail
+            .disassembly
+            .iter()
+            .position(|line| line.text.contains("invokevirtual"))
+            .unwrap();
+        app.disassembly.select(Some(line));
+        let constant = app.detail.disassembly[line].constant.unwrap();
+
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(Pane::References, app.focus);
+        let selected = app.references.selected().unwrap();
+        assert_eq!(constant, app.detail.references[selected].0);
+
+        app.handle_key(KeyCode::Char('g'));
+        let target = app.selected_node().unwrap();
+        let Node::Method(target_class, target_method) = target else {
+            panic!("expected a method, got {target:?}");
+        };
+        assert_eq!(class, target_class);
+        assert_ne!(test, target_method);
+        assert!(app.status.starts_with("Went to rjvm/ExceptionsHandlers."));
+
+        app.handle_key(KeyCode::Char('b'));
+        assert_eq!(Some(Node::Method(class, test)), app.selected_node());
+    }
+
+    #[test]
+    fn reports_classes_that_are_not_loaded() {
+        let class_path = class_path();
+        let mut app = App::new(&class_path);
+        let class = class_index(&app, "rjvm/Complex");
+        let method = method_index(&app, class, "abs");
+        app.select_node(Node::Method(class, method));
+        app.focus = Pane::Disassembly;
+        let line = app
+            .detail
+            .disassembly
+            .iter()
+            .position(|line| line.text.contains("invokestatic"))
+            .unwrap();
+        app.disassembly.select(Some(line));
+
+        app.handle_key(KeyCode::Char('g'));
+        assert_eq!("Class java/lang/Math is not loaded", app.status);
+        assert_eq!(Some(Node::Method(class, method)), app.selected_node());
+    }
+
+    #[test]
+    fn draws_all_the_panes() {
+        let class_path = class_path();
+        let mut app = App::new(&class_path);
+        let mut terminal = Terminal::new(TestBackend::new(160, 40)).unwrap();
+
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for text in [
+            "Classes",
+            "Disassembly",
+            "Constant pool",
+            "Raw bytes",
+            "rjvm.ExceptionsHandlers",
+        ] {
+            assert!(screen.contains(text), "missing {text}");
+        }
+    }
+}
@@ -8,7 +8,8 @@ pub struct Buffer<'a> {
 }
 
 /// Errors related to reading from a [Buffer]
-#[derive(Error, Debug, PartialEq)]
+#[derive(Error, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BufferError {
     #[error("unexpected end of data")]
     UnexpectedEndOfData,
@@ -27,16 +28,26 @@ impl<'a> Buffer<'a> {
         }
     }
 
-    fn advance(&mut self, size: usize) -> Result<&'a [u8]> {
-        if self.position + size > self.buffer.len() {
-            Err(BufferError::UnexpectedEndOfData)
-        } else {
-            let slice = &self.buffer[self.position..self.position + size];
-            self.position += size;
-            Ok(slice)
+    /// Creates a buffer that starts reading at the given position of the data
+    pub fn starting_at(data: &'a [u8], position: usize) -> Self {
+        Buffer {
+            buffer: data,
+            position,
         }
     }
 
+    fn advance(&mut self, size: usize) -> Result<&'a [u8]> {
+        // The sum can overflow on 32-bit targets, given a hostile attribute length
+        let end = self
+            .position
+            .checked_add(size)
+            .filter(|end| *end <= self.buffer.len())
+            .ok_or(BufferError::UnexpectedEndOfData)?;
+        let slice = &self.buffer[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
     pub fn read_u8(&mut self) -> Result<u8> {
         self.advance(std::mem::size_of::<u8>())
             .map(|bytes| u8::from_be_bytes(bytes.try_into().unwrap()))
@@ -82,6 +93,11 @@ impl<'a> Buffer<'a> {
         self.advance(len)
     }
 
+    /// The offset of the next byte to be read
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
     #[allow(dead_code)]
     pub fn has_more_data(&self) -> bool {
         self.position < self.buffer.len()
@@ -90,7 +106,7 @@ impl<'a> Buffer<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::buffer::Buffer;
+    use crate::buffer::{Buffer, BufferError};
 
     #[test]
     fn buffer_works() {
@@ -103,4 +119,17 @@ mod tests {
 
         assert!(buffer.read_u32().is_err());
     }
+
+    #[test]
+    fn reading_past_the_largest_position_is_an_error() {
+        let data = vec![0x00, 0x00, 0x00, 0x42];
+        let mut buffer = Buffer::starting_at(&data, 2);
+
+        assert_eq!(
+            Err(BufferError::UnexpectedEndOfData),
+            buffer.read_bytes(usize::MAX)
+        );
+        assert_eq!(2, buffer.position());
+        assert_eq!(0x0042u16, buffer.read_u16().unwrap());
+    }
 }
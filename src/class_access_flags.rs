@@ -1,7 +1,6 @@
 use bitflags::bitflags;
 
-#[derive(Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Class flags
 pub struct ClassAccessFlags(u16);
 
@@ -23,3 +22,17 @@ impl Default for ClassAccessFlags {
         ClassAccessFlags::empty()
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ClassAccessFlags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::flags_serde::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ClassAccessFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::flags_serde::deserialize(deserializer)
+    }
+}
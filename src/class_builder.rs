@@ -0,0 +1,771 @@
+use std::collections::HashSet;
+
+use crate::{
+    annotation::Annotation,
+    attribute::Attribute,
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    class_file_version::ClassFileVersion,
+    class_hierarchy::{ClassHierarchy, ObjectClassHierarchy},
+    class_writer_error::{ClassWriterError, Result},
+    code_builder::CodeBuilder,
+    constant_pool::ConstantPool,
+    field_flags::FieldFlags,
+    field_type::FieldType,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    stack_map_table::update_stack_map_table,
+};
+
+/// The elements that make up a class, other than its version, flags, name and superclass
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassElement {
+    Interface(String),
+    Field(ClassFileField),
+    Method(ClassFileMethod),
+    SourceFile(String),
+    Deprecated,
+    /// An attribute not mapped to any other element
+    Attribute(Attribute),
+}
+
+impl ClassElement {
+    /// Splits a class into its header, i.e. a class with no elements but owning the constant
+    /// pool, and its elements
+    pub fn split(mut class: ClassFile) -> (ClassFile, Vec<ClassElement>) {
+        // Spans refer to the original file, and are not valid after transformations
+        class.spans.clear();
+        let mut elements = Vec::new();
+        elements.extend(
+            std::mem::take(&mut class.interfaces)
+                .into_iter()
+                .map(ClassElement::Interface),
+        );
+        elements.extend(
+            std::mem::take(&mut class.fields)
+                .into_iter()
+                .map(ClassElement::Field),
+        );
+        elements.extend(
+            std::mem::take(&mut class.methods)
+                .into_iter()
+                .map(ClassElement::Method),
+        );
+        elements.extend(class.source_file.take().map(ClassElement::SourceFile));
+        if std::mem::take(&mut class.deprecated) {
+            elements.push(ClassElement::Deprecated);
+        }
+        elements.extend(
+            std::mem::take(&mut class.attributes)
+                .into_iter()
+                .map(ClassElement::Attribute),
+        );
+        (class, elements)
+    }
+}
+
+/// The elements that make up a method, other than its flags, name and descriptor
+#[derive(Debug, Clone, PartialEq)]
+pub enum MethodElement {
+    Code(ClassFileMethodCode),
+    ThrownException(String),
+    Deprecated,
+    /// An attribute not mapped to any other element
+    Attribute(Attribute),
+}
+
+impl MethodElement {
+    /// Splits a method into its elements. The raw `Code`, `Exceptions` and `Deprecated`
+    /// attributes are dropped, since they are already modelled by the other elements.
+    pub fn split(method: ClassFileMethod) -> Vec<MethodElement> {
+        let mut elements = Vec::new();
+        elements.extend(method.code.map(MethodElement::Code));
+        elements.extend(
+            method
+                .thrown_exceptions
+                .into_iter()
+                .map(MethodElement::ThrownException),
+        );
+        if method.deprecated {
+            elements.push(MethodElement::Deprecated);
+        }
+        elements.extend(
+            method
+                .attributes
+                .into_iter()
+                .filter(|attribute| {
+                    !matches!(
+                        attribute.name.as_str(),
+                        "Code" | "Exceptions" | "Deprecated"
+                    )
+                })
+                .map(MethodElement::Attribute),
+        );
+        elements
+    }
+}
+
+/// A builder that generates a [ClassFile] from scratch, managing the constant pool. The
+/// resulting class is consistent and can be serialized with [crate::write_class].
+///
+/// Errors found while generating the code of the methods are reported by [ClassBuilder::build].
+pub struct ClassBuilder {
+    class: ClassFile,
+    annotations: Annotations,
+    error: Option<ClassWriterError>,
+    /// Whether the builder produces a transformed class, whose code without stack map frames
+    /// is kept as it was read unless it is rebuilt
+    transforming: bool,
+    /// Name and descriptor of the methods whose code was generated by a [CodeBuilder]
+    rebuilt_methods: HashSet<(String, String)>,
+}
+
+impl ClassBuilder {
+    /// Creates a builder for a public class with the given name, in the internal form
+    /// (i.e. `java/lang/String`), that extends `java/lang/Object`
+    pub fn new(name: &str) -> Self {
+        Self {
+            class: ClassFile {
+                flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+                name: name.to_string(),
+                superclass: Some("java/lang/Object".to_string()),
+                ..Default::default()
+            },
+            annotations: Default::default(),
+            error: None,
+            transforming: false,
+            rebuilt_methods: HashSet::new(),
+        }
+    }
+
+    /// Creates a builder that starts from the given class, which must have no elements
+    pub(crate) fn from_header(class: ClassFile) -> Self {
+        Self {
+            class,
+            annotations: Default::default(),
+            error: None,
+            transforming: false,
+            rebuilt_methods: HashSet::new(),
+        }
+    }
+
+    /// Creates a builder that starts from the header of a class being transformed, and
+    /// computes the stack map frames only for the code rebuilt by the transform
+    pub(crate) fn for_transform(header: ClassFile) -> Self {
+        // Classes older than Java 6 have no frames, so all of their code needs them if the
+        // transform moves the class to a newer version
+        let transforming = header.version.major() >= ClassFileVersion::Jdk6.major();
+        Self {
+            transforming,
+            ..Self::from_header(header)
+        }
+    }
+
+    /// Creates an empty builder with the same header, that temporarily takes over the
+    /// constant pool. Must be given back to [ClassBuilder::join].
+    pub(crate) fn fork(&mut self) -> ClassBuilder {
+        ClassBuilder {
+            transforming: self.transforming,
+            ..ClassBuilder::from_header(ClassFile {
+                version: self.class.version,
+                constants: std::mem::take(&mut self.class.constants),
+                flags: self.class.flags,
+                name: self.class.name.clone(),
+                superclass: self.class.superclass.clone(),
+                ..Default::default()
+            })
+        }
+    }
+
+    /// Takes back the constant pool and the header from a builder created by
+    /// [ClassBuilder::fork], and returns the elements added to it. Methods rebuilt by the fork
+    /// still get their frames computed when added back as is.
+    pub(crate) fn join(&mut self, fork: ClassBuilder) -> Vec<ClassElement> {
+        let ClassBuilder {
+            class,
+            annotations,
+            error,
+            rebuilt_methods,
+            ..
+        } = fork;
+        self.rebuilt_methods.extend(rebuilt_methods);
+        let (header, elements) = ClassElement::split(class);
+        self.class.version = header.version;
+        self.class.constants = header.constants;
+        self.class.flags = header.flags;
+        self.class.superclass = header.superclass;
+        self.annotations.visible.extend(annotations.visible);
+        self.annotations.invisible.extend(annotations.invisible);
+        if self.error.is_none() {
+            self.error = error;
+        }
+        elements
+    }
+
+    pub fn name(&self) -> &str {
+        &self.class.name
+    }
+
+    pub fn class_version(&self) -> ClassFileVersion {
+        self.class.version
+    }
+
+    /// Adds an element to the class as is
+    pub fn with(&mut self, element: ClassElement) -> &mut Self {
+        match element {
+            ClassElement::Interface(interface) => self.class.interfaces.push(interface),
+            ClassElement::Field(field) => self.class.fields.push(field),
+            ClassElement::Method(method) => self.class.methods.push(method),
+            ClassElement::SourceFile(source_file) => self.class.source_file = Some(source_file),
+            ClassElement::Deprecated => self.class.deprecated = true,
+            ClassElement::Attribute(attribute) => self.class.attributes.push(attribute),
+        }
+        self
+    }
+
+    pub fn version(&mut self, version: ClassFileVersion) -> &mut Self {
+        self.class.version = version;
+        self
+    }
+
+    pub fn flags(&mut self, flags: ClassAccessFlags) -> &mut Self {
+        self.class.flags = flags;
+        self
+    }
+
+    pub fn superclass(&mut self, superclass: &str) -> &mut Self {
+        self.class.superclass = Some(superclass.to_string());
+        self
+    }
+
+    pub fn interface(&mut self, interface: &str) -> &mut Self {
+        self.class.interfaces.push(interface.to_string());
+        self
+    }
+
+    pub fn source_file(&mut self, source_file: &str) -> &mut Self {
+        self.class.source_file = Some(source_file.to_string());
+        self
+    }
+
+    pub fn deprecated(&mut self) -> &mut Self {
+        self.class.deprecated = true;
+        self
+    }
+
+    /// Adds a raw attribute to the class. Any constant referred to by the attribute's content
+    /// must be added to the pool returned by [ClassBuilder::constants].
+    pub fn attribute(&mut self, attribute: Attribute) -> &mut Self {
+        self.class.attributes.push(attribute);
+        self
+    }
+
+    /// Adds an annotation visible at runtime via reflection
+    pub fn annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.visible.push(annotation);
+        self
+    }
+
+    /// Adds an annotation that is stored in the class file, but not visible at runtime
+    pub fn invisible_annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.invisible.push(annotation);
+        self
+    }
+
+    pub fn constants(&mut self) -> &mut ConstantPool {
+        &mut self.class.constants
+    }
+
+    pub fn field(&mut self, flags: FieldFlags, name: &str, field_type: FieldType) -> &mut Self {
+        self.field_with(flags, name, field_type, |_| {})
+    }
+
+    /// Adds a field, which can be further customized by the given function
+    pub fn field_with(
+        &mut self,
+        flags: FieldFlags,
+        name: &str,
+        field_type: FieldType,
+        build: impl FnOnce(&mut FieldBuilder),
+    ) -> &mut Self {
+        let mut builder = FieldBuilder {
+            constants: &mut self.class.constants,
+            field: ClassFileField {
+                flags,
+                name: name.to_string(),
+                type_descriptor: field_type,
+                constant_value: None,
+                deprecated: false,
+                attributes: Vec::new(),
+            },
+            annotations: Default::default(),
+        };
+        build(&mut builder);
+        let (field, error) = builder.finish();
+        self.class.fields.push(field);
+        if self.error.is_none() {
+            self.error = error;
+        }
+        self
+    }
+
+    /// Adds a method, whose code is generated by the given function
+    pub fn method(
+        &mut self,
+        flags: MethodFlags,
+        name: &str,
+        descriptor: MethodDescriptor,
+        build_code: impl FnOnce(&mut CodeBuilder),
+    ) -> &mut Self {
+        self.method_with(flags, name, descriptor, |method| {
+            method.code(build_code);
+        })
+    }
+
+    /// Adds a method, which can be further customized by the given function. Abstract and
+    /// native methods should not call [MethodBuilder::code].
+    pub fn method_with(
+        &mut self,
+        flags: MethodFlags,
+        name: &str,
+        descriptor: MethodDescriptor,
+        build: impl FnOnce(&mut MethodBuilder),
+    ) -> &mut Self {
+        let mut builder = MethodBuilder {
+            constants: &mut self.class.constants,
+            method: ClassFileMethod {
+                flags,
+                name: name.to_string(),
+                type_descriptor: descriptor.descriptor(),
+                parsed_type_descriptor: descriptor,
+                attributes: Vec::new(),
+                code: None,
+                deprecated: false,
+                thrown_exceptions: Vec::new(),
+            },
+            annotations: Default::default(),
+            error: None,
+            rebuilt_code: false,
+        };
+        build(&mut builder);
+        let (method, rebuilt_code, error) = builder.finish();
+        if rebuilt_code {
+            self.rebuilt_methods
+                .insert((method.name.clone(), method.type_descriptor.clone()));
+        }
+        self.class.methods.push(method);
+        if self.error.is_none() {
+            self.error = error;
+        }
+        self
+    }
+
+    /// Returns the class. If the class version requires it, the stack map frames are computed
+    /// for the methods that do not have them yet, assuming that the common superclass of two
+    /// different classes is `java/lang/Object`.
+    pub fn build(self) -> Result<ClassFile> {
+        self.build_with_hierarchy(&ObjectClassHierarchy)
+    }
+
+    /// Returns the class, using the given hierarchy to compute the stack map frames
+    pub fn build_with_hierarchy(mut self, hierarchy: &dyn ClassHierarchy) -> Result<ClassFile> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let annotations = self
+            .annotations
+            .into_attributes(&mut self.class.constants)?;
+        self.class.attributes.extend(annotations);
+        if self.class.version.major() >= ClassFileVersion::Jdk6.major() {
+            let ClassFile {
+                name,
+                methods,
+                constants,
+                ..
+            } = &mut self.class;
+            for method in methods.iter_mut() {
+                let needs_frames = method.code.as_ref().is_some_and(|code| {
+                    !has_stack_map_table(code)
+                        && (!self.transforming
+                            || self
+                                .rebuilt_methods
+                                .contains(&(method.name.clone(), method.type_descriptor.clone())))
+                });
+                if needs_frames {
+                    update_stack_map_table(name, method, constants, hierarchy)?;
+                }
+            }
+        }
+        Ok(self.class)
+    }
+}
+
+fn has_stack_map_table(code: &ClassFileMethodCode) -> bool {
+    code.attributes
+        .iter()
+        .any(|attribute| attribute.name == "StackMapTable")
+}
+
+/// Customizes a field added by [ClassBuilder::field_with]
+pub struct FieldBuilder<'a> {
+    constants: &'a mut ConstantPool,
+    field: ClassFileField,
+    annotations: Annotations,
+}
+
+impl<'a> FieldBuilder<'a> {
+    pub fn constant_value(&mut self, value: FieldConstantValue) -> &mut Self {
+        self.field.constant_value = Some(value);
+        self
+    }
+
+    pub fn deprecated(&mut self) -> &mut Self {
+        self.field.deprecated = true;
+        self
+    }
+
+    pub fn attribute(&mut self, attribute: Attribute) -> &mut Self {
+        self.field.attributes.push(attribute);
+        self
+    }
+
+    pub fn annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.visible.push(annotation);
+        self
+    }
+
+    pub fn invisible_annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.invisible.push(annotation);
+        self
+    }
+
+    pub fn constants(&mut self) -> &mut ConstantPool {
+        self.constants
+    }
+
+    fn finish(mut self) -> (ClassFileField, Option<ClassWriterError>) {
+        match self.annotations.into_attributes(self.constants) {
+            Ok(annotations) => {
+                self.field.attributes.extend(annotations);
+                (self.field, None)
+            }
+            Err(error) => (self.field, Some(error)),
+        }
+    }
+}
+
+/// Customizes a method added by [ClassBuilder::method_with]
+pub struct MethodBuilder<'a> {
+    constants: &'a mut ConstantPool,
+    method: ClassFileMethod,
+    annotations: Annotations,
+    error: Option<ClassWriterError>,
+    /// Whether the code was generated by a [CodeBuilder], rather than added as is
+    rebuilt_code: bool,
+}
+
+impl<'a> MethodBuilder<'a> {
+    /// Creates an empty builder for the same method, that shares the constant pool.
+    /// Its elements must be retrieved with [MethodBuilder::join].
+    pub(crate) fn fork(&mut self) -> MethodBuilder<'_> {
+        MethodBuilder {
+            constants: self.constants,
+            method: ClassFileMethod {
+                flags: self.method.flags,
+                name: self.method.name.clone(),
+                type_descriptor: self.method.type_descriptor.clone(),
+                parsed_type_descriptor: self.method.parsed_type_descriptor.clone(),
+                attributes: Vec::new(),
+                code: None,
+                deprecated: false,
+                thrown_exceptions: Vec::new(),
+            },
+            annotations: Default::default(),
+            error: None,
+            rebuilt_code: false,
+        }
+    }
+
+    /// Returns the elements added to a builder created by [MethodBuilder::fork], and takes
+    /// back any change to the flags
+    pub(crate) fn join(&mut self, fork: MethodParts) -> Vec<MethodElement> {
+        let (method, annotations, error, rebuilt_code) = fork;
+        self.method.flags = method.flags;
+        self.rebuilt_code |= rebuilt_code;
+        self.annotations.visible.extend(annotations.visible);
+        self.annotations.invisible.extend(annotations.invisible);
+        if self.error.is_none() {
+            self.error = error;
+        }
+        MethodElement::split(method)
+    }
+
+    pub(crate) fn into_parts(self) -> MethodParts {
+        (self.method, self.annotations, self.error, self.rebuilt_code)
+    }
+
+    /// Creates a builder for the code of this method
+    pub(crate) fn code_builder(&mut self) -> CodeBuilder<'_> {
+        CodeBuilder::new(
+            self.constants,
+            self.method.is_static(),
+            &self.method.parsed_type_descriptor,
+        )
+    }
+
+    /// Sets code generated by a [CodeBuilder], whose stack map frames are computed when the
+    /// class is built
+    pub(crate) fn rebuilt_code(&mut self, code: ClassFileMethodCode) -> &mut Self {
+        self.method.code = Some(code);
+        self.rebuilt_code = true;
+        self
+    }
+
+    pub(crate) fn fail(&mut self, error: ClassWriterError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.method.name
+    }
+
+    pub fn descriptor(&self) -> &MethodDescriptor {
+        &self.method.parsed_type_descriptor
+    }
+
+    pub fn flags(&mut self, flags: MethodFlags) -> &mut Self {
+        self.method.flags = flags;
+        self
+    }
+
+    /// Adds an element to the method as is
+    pub fn with(&mut self, element: MethodElement) -> &mut Self {
+        match element {
+            MethodElement::Code(code) => self.method.code = Some(code),
+            MethodElement::ThrownException(exception) => {
+                self.method.thrown_exceptions.push(exception)
+            }
+            MethodElement::Deprecated => self.method.deprecated = true,
+            MethodElement::Attribute(attribute) => self.method.attributes.push(attribute),
+        }
+        self
+    }
+
+    /// Generates the code of the method via the given function
+    pub fn code(&mut self, build_code: impl FnOnce(&mut CodeBuilder)) -> &mut Self {
+        let mut code_builder = CodeBuilder::new(
+            self.constants,
+            self.method.is_static(),
+            &self.method.parsed_type_descriptor,
+        );
+        build_code(&mut code_builder);
+        match code_builder.build() {
+            Ok(code) => self.rebuilt_code(code),
+            Err(error) => {
+                self.fail(error);
+                self
+            }
+        }
+    }
+
+    /// Adds an exception to the `throws` clause of the method
+    pub fn throws(&mut self, exception: &str) -> &mut Self {
+        self.method.thrown_exceptions.push(exception.to_string());
+        self
+    }
+
+    pub fn deprecated(&mut self) -> &mut Self {
+        self.method.deprecated = true;
+        self
+    }
+
+    pub fn attribute(&mut self, attribute: Attribute) -> &mut Self {
+        self.method.attributes.push(attribute);
+        self
+    }
+
+    pub fn annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.visible.push(annotation);
+        self
+    }
+
+    pub fn invisible_annotation(&mut self, annotation: Annotation) -> &mut Self {
+        self.annotations.invisible.push(annotation);
+        self
+    }
+
+    pub fn constants(&mut self) -> &mut ConstantPool {
+        self.constants
+    }
+
+    fn finish(mut self) -> (ClassFileMethod, bool, Option<ClassWriterError>) {
+        let annotations = std::mem::take(&mut self.annotations);
+        match annotations.into_attributes(self.constants) {
+            Ok(annotations) => self.method.attributes.extend(annotations),
+            Err(error) => self.fail(error),
+        }
+        (self.method, self.rebuilt_code, self.error)
+    }
+}
+
+/// The content of a builder created by [MethodBuilder::fork]: the method, its annotations,
+/// the first error and whether its code was rebuilt
+pub(crate) type MethodParts = (ClassFileMethod, Annotations, Option<ClassWriterError>, bool);
+
+/// Annotations collected by a builder, stored as attributes once the element is complete
+#[derive(Debug, Default)]
+pub(crate) struct Annotations {
+    visible: Vec<Annotation>,
+    invisible: Vec<Annotation>,
+}
+
+impl Annotations {
+    fn into_attributes(self, constants: &mut ConstantPool) -> Result<Vec<Attribute>> {
+        let mut attributes = Vec::new();
+        if !self.visible.is_empty() {
+            attributes.push(Attribute {
+                name: "RuntimeVisibleAnnotations".to_string(),
+                bytes: Annotation::encode_attribute(&self.visible, constants)?,
+            });
+        }
+        if !self.invisible.is_empty() {
+            attributes.push(Attribute {
+                name: "RuntimeInvisibleAnnotations".to_string(),
+                bytes: Annotation::encode_attribute(&self.invisible, constants)?,
+            });
+        }
+        Ok(attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        annotation::{Annotation, ElementValue},
+        class_builder::ClassBuilder,
+        class_file_field::FieldConstantValue,
+        class_writer_error::ClassWriterError,
+        code_builder::{JumpKind, LocalKind},
+        field_flags::FieldFlags,
+        field_type::{BaseType, FieldType},
+        instruction::Instruction,
+        method_descriptor::MethodDescriptor,
+        method_flags::MethodFlags,
+    };
+
+    #[test]
+    fn can_build_class_with_fields_methods_and_annotations() {
+        let mut builder = ClassBuilder::new("a/Counter");
+        builder
+            .interface("java/lang/Runnable")
+            .annotation(Annotation::new("La/Marker;").element("value", ElementValue::Int(1)))
+            .field(FieldFlags::PRIVATE, "count", FieldType::Base(BaseType::Int))
+            .field_with(
+                FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
+                "NAME",
+                FieldType::Object("java/lang/String".to_string()),
+                |field| {
+                    field.constant_value(FieldConstantValue::String("counter".to_string()));
+                },
+            )
+            .method(
+                MethodFlags::PUBLIC,
+                "run",
+                MethodDescriptor::parse("()V").unwrap(),
+                |code| {
+                    code.load(LocalKind::Reference, 0)
+                        .instruction(Instruction::Dup)
+                        .getfield("a/Counter", "count", "I")
+                        .iconst(1)
+                        .instruction(Instruction::Iadd)
+                        .putfield("a/Counter", "count", "I")
+                        .instruction(Instruction::Return);
+                },
+            )
+            .method_with(
+                MethodFlags::PUBLIC | MethodFlags::ABSTRACT,
+                "reset",
+                MethodDescriptor::parse("()V").unwrap(),
+                |method| {
+                    method.throws("java/lang/IllegalStateException");
+                },
+            );
+        let class = builder.build().unwrap();
+
+        assert_eq!("a/Counter", class.name);
+        assert_eq!(vec!["java/lang/Runnable".to_string()], class.interfaces);
+        assert_eq!(2, class.fields.len());
+        assert_eq!(
+            Some(FieldConstantValue::String("counter".to_string())),
+            class.fields[1].constant_value
+        );
+        assert_eq!(2, class.methods.len());
+        assert_eq!("()V", class.methods[0].type_descriptor);
+        assert_eq!(3, class.methods[0].code.as_ref().unwrap().max_stack);
+        assert_eq!(None, class.methods[1].code);
+        assert_eq!(
+            vec!["java/lang/IllegalStateException".to_string()],
+            class.methods[1].thrown_exceptions
+        );
+
+        let annotations = &class.attributes[0];
+        assert_eq!("RuntimeVisibleAnnotations", annotations.name);
+        assert_eq!(
+            vec![Annotation::new("La/Marker;").element("value", ElementValue::Int(1))],
+            Annotation::decode_attribute(&annotations.bytes, &class.constants).unwrap()
+        );
+    }
+
+    #[test]
+    fn computes_stack_map_frames() {
+        let mut builder = ClassBuilder::new("a/Test");
+        builder.method(
+            MethodFlags::PUBLIC | MethodFlags::STATIC,
+            "abs",
+            MethodDescriptor::parse("(I)I").unwrap(),
+            |code| {
+                let positive = code.new_label();
+                code.load(LocalKind::Int, 0)
+                    .jump(JumpKind::Ifge, positive)
+                    .load(LocalKind::Int, 0)
+                    .instruction(Instruction::Ineg)
+                    .instruction(Instruction::Ireturn)
+                    .place_label(positive)
+                    .load(LocalKind::Int, 0)
+                    .instruction(Instruction::Ireturn);
+            },
+        );
+        let class = builder.build().unwrap();
+
+        let code = class.methods[0].code.as_ref().unwrap();
+        assert_eq!(
+            vec!["StackMapTable"],
+            code.attributes
+                .iter()
+                .map(|attribute| attribute.name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reports_errors_in_generated_code() {
+        let mut builder = ClassBuilder::new("a/Test");
+        builder.method(
+            MethodFlags::STATIC,
+            "broken",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                code.instruction(Instruction::Pop)
+                    .instruction(Instruction::Return);
+            },
+        );
+        assert_eq!(
+            Err(ClassWriterError::StackUnderflow(0)),
+            builder.build().map(|_| ())
+        );
+    }
+}
@@ -1,17 +1,21 @@
 use std::fmt;
 
 use crate::{
-    class_access_flags::ClassAccessFlags, class_file_field::ClassFileField,
-    class_file_method::ClassFileMethod, class_file_version::ClassFileVersion,
+    attribute::Attribute,
+    class_access_flags::ClassAccessFlags,
+    class_file_field::ClassFileField,
+    class_file_method::ClassFileMethod,
+    class_file_version::ClassFileVersion,
     constant_pool::ConstantPool,
+    format_check::{FormatChecker, FormatViolation},
+    span::LabeledSpan,
 };
 
 /// Represents the content of a .class file.
-#[derive(Debug, Default)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFile {
     pub version: ClassFileVersion,
-    #[cfg_attr(feature = "wasm", serde(skip_serializing))]
     pub constants: ConstantPool,
     pub flags: ClassAccessFlags,
     pub name: String,
@@ -21,6 +25,21 @@ pub struct ClassFile {
     pub methods: Vec<ClassFileMethod>,
     pub deprecated: bool,
     pub source_file: Option<String>,
+    /// Attributes of the class that are not mapped to any other field
+    pub attributes: Vec<Attribute>,
+    /// Where each element of the class is stored in the file. Recorded only when requested
+    /// via [crate::read_options::ReadOptions::record_spans].
+    pub spans: Vec<LabeledSpan>,
+}
+
+impl ClassFile {
+    /// Checks the constraints of the class file format that the reader does not enforce, like
+    /// references between constants of the wrong kinds, invalid names, illegal combinations of
+    /// flags or exception handlers outside the code. Returns all the violations found, that
+    /// would make the JVM reject the class.
+    pub fn check_format(&self) -> Vec<FormatViolation> {
+        FormatChecker::new(self).check()
+    }
 }
 
 impl fmt::Display for ClassFile {
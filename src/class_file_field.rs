@@ -1,18 +1,19 @@
 use std::{fmt, fmt::Formatter};
 
-use crate::{field_flags::FieldFlags, field_type::FieldType};
+use crate::{attribute::Attribute, field_flags::FieldFlags, field_type::FieldType};
 
 /// Models a field in a class
-#[derive(Debug, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFileField {
     pub flags: FieldFlags,
     pub name: String,
     pub type_descriptor: FieldType,
     /// Fields which model a constant (final) will have an attribute specifying the value
-    #[cfg_attr(feature = "wasm", serde(skip_serializing))]
     pub constant_value: Option<FieldConstantValue>,
     pub deprecated: bool,
+    /// Attributes of the field that are not mapped to any other field
+    pub attributes: Vec<Attribute>,
 }
 
 impl fmt::Display for ClassFileField {
@@ -30,8 +31,9 @@ impl fmt::Display for ClassFileField {
 }
 
 /// Possible constant values of a field
-#[derive(Debug, PartialEq, strum_macros::Display)]
-#[cfg_attr(feature = "wasm", derive(tsify::Tsify, serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, strum_macros::Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub enum FieldConstantValue {
     Int(i32),
     Float(f32),
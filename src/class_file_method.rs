@@ -2,17 +2,21 @@ use std::{fmt, fmt::Formatter};
 
 use crate::{
     attribute::Attribute,
+    class_reader_error::ClassReaderError,
+    control_flow_graph::ControlFlowGraph,
     exception_table::ExceptionTable,
     field_type::{BaseType, FieldType},
     instruction::Instruction,
     line_number_table::LineNumberTable,
+    liveness::Liveness,
     method_descriptor::MethodDescriptor,
     method_flags::MethodFlags,
+    reaching_definitions::ReachingDefinitions,
 };
 
 /// Models a method in a class
-#[derive(Debug, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFileMethod {
     pub flags: MethodFlags,
     pub name: String,
@@ -22,7 +26,6 @@ pub struct ClassFileMethod {
     pub parsed_type_descriptor: MethodDescriptor,
     /// Generic attributes of the method
     // TODO: replace with some proper struct
-    #[cfg_attr(feature = "wasm", serde(skip_serializing))]
     pub attributes: Vec<Attribute>,
     pub code: Option<ClassFileMethodCode>,
     pub deprecated: bool,
@@ -76,8 +79,8 @@ impl ClassFileMethod {
 }
 
 /// Code of a given method
-#[derive(Debug, Default, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClassFileMethodCode {
     /// Maximum depth of the stack at any time
     pub max_stack: u16,
@@ -90,10 +93,26 @@ pub struct ClassFileMethodCode {
 
     /// Generic unmapped attributes of the code
     // TODO: replace with some proper struct
-    #[cfg_attr(feature = "wasm", serde(skip_serializing))]
     pub attributes: Vec<Attribute>,
 }
 
+impl ClassFileMethodCode {
+    /// Splits the code in basic blocks, linked by the possible control flow between them
+    pub fn control_flow_graph(&self) -> Result<ControlFlowGraph, ClassReaderError> {
+        ControlFlowGraph::new(self)
+    }
+
+    /// Computes which local variable slots are live before and after each instruction
+    pub fn liveness(&self) -> Result<Liveness, ClassReaderError> {
+        Liveness::new(self)
+    }
+
+    /// Computes which definitions of the local variables reach each instruction
+    pub fn reaching_definitions(&self) -> Result<ReachingDefinitions, ClassReaderError> {
+        ReachingDefinitions::new(self)
+    }
+}
+
 impl fmt::Display for ClassFileMethodCode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(
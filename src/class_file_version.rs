@@ -4,10 +4,10 @@ use wasm_bindgen::prelude::*;
 use crate::class_reader_error::{ClassReaderError, Result};
 
 /// Versions of the JVM class file format.
-#[derive(Debug, PartialEq, Default, strum_macros::Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, strum_macros::Display)]
 #[allow(dead_code)]
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ClassFileVersion {
     Jdk1_1,
     Jdk1_2,
@@ -63,6 +63,34 @@ impl ClassFileVersion {
             _ => Err(ClassReaderError::UnsupportedVersion(major, minor)),
         }
     }
+
+    /// Returns the major version used in the class file for this version
+    pub fn major(&self) -> u16 {
+        match self {
+            ClassFileVersion::Jdk1_1 => 45,
+            ClassFileVersion::Jdk1_2 => 46,
+            ClassFileVersion::Jdk1_3 => 47,
+            ClassFileVersion::Jdk1_4 => 48,
+            ClassFileVersion::Jdk1_5 => 49,
+            ClassFileVersion::Jdk6 => 50,
+            ClassFileVersion::Jdk7 => 51,
+            ClassFileVersion::Jdk8 => 52,
+            ClassFileVersion::Jdk9 => 53,
+            ClassFileVersion::Jdk10 => 54,
+            ClassFileVersion::Jdk11 => 55,
+            ClassFileVersion::Jdk12 => 56,
+            ClassFileVersion::Jdk13 => 57,
+            ClassFileVersion::Jdk14 => 58,
+            ClassFileVersion::Jdk15 => 59,
+            ClassFileVersion::Jdk16 => 60,
+            ClassFileVersion::Jdk17 => 61,
+            ClassFileVersion::Jdk18 => 62,
+            ClassFileVersion::Jdk19 => 63,
+            ClassFileVersion::Jdk20 => 64,
+            ClassFileVersion::Jdk21 => 65,
+            ClassFileVersion::Jdk22 => 66,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +105,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_get_major_version() {
+        assert_eq!(50, ClassFileVersion::Jdk6.major());
+        assert_eq!(66, ClassFileVersion::Jdk22.major());
+    }
+
     #[test]
     fn can_parse_future_versions() {
         assert_eq!(
@@ -0,0 +1,372 @@
+use std::borrow::Cow;
+
+use cesu8::from_java_cesu8;
+
+use crate::{
+    buffer::{Buffer, BufferError},
+    class_access_flags::ClassAccessFlags,
+    class_file_version::ClassFileVersion,
+    class_reader_error::{ClassReaderError, Result},
+    constant_pool::{
+        decode_constant, scan_constants, ConstantPool, ConstantPoolEntry,
+        InvalidConstantPoolIndexError,
+    },
+    field_flags::FieldFlags,
+    field_type::FieldType,
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// A read-only view of a class that borrows from the bytes it was read from, for example
+/// an mmapped file. Names are borrowed unless they need to be decoded from the modified
+/// UTF-8 used by the JVM, and code and attributes are slices of the input.
+/// Unlike [crate::class_file::ClassFile], attributes are not interpreted, except for `Code`.
+#[derive(Debug)]
+pub struct ClassFileView<'a> {
+    pub version: ClassFileVersion,
+    pub constants: ConstantPoolView<'a>,
+    pub flags: ClassAccessFlags,
+    pub name: Cow<'a, str>,
+    pub superclass: Option<Cow<'a, str>>,
+    pub interfaces: Vec<Cow<'a, str>>,
+    pub fields: Vec<FieldView<'a>>,
+    pub methods: Vec<MethodView<'a>>,
+    pub attributes: Vec<AttributeView<'a>>,
+}
+
+#[derive(Debug)]
+pub struct FieldView<'a> {
+    pub flags: FieldFlags,
+    pub name: Cow<'a, str>,
+    pub type_descriptor: Cow<'a, str>,
+    pub attributes: Vec<AttributeView<'a>>,
+}
+
+#[derive(Debug)]
+pub struct MethodView<'a> {
+    pub flags: MethodFlags,
+    pub name: Cow<'a, str>,
+    pub type_descriptor: Cow<'a, str>,
+    pub code: Option<CodeView<'a>>,
+    /// All the attributes of the method, including `Code`
+    pub attributes: Vec<AttributeView<'a>>,
+}
+
+#[derive(Debug)]
+pub struct CodeView<'a> {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: &'a [u8],
+    pub exception_table: Vec<ExceptionHandlerView<'a>>,
+    pub attributes: Vec<AttributeView<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExceptionHandlerView<'a> {
+    pub start_pc: ProgramCounter,
+    pub end_pc: ProgramCounter,
+    pub handler_pc: ProgramCounter,
+    /// None for handlers that catch everything, i.e. `finally` blocks
+    pub catch_class: Option<Cow<'a, str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeView<'a> {
+    pub name: Cow<'a, str>,
+    pub bytes: &'a [u8],
+}
+
+/// A constant pool whose entries are decoded only when accessed.
+/// Note that constants are 1-based in java.
+#[derive(Debug, Clone)]
+pub struct ConstantPoolView<'a> {
+    bytes: &'a [u8],
+    /// Offset in `bytes` of the tag of each entry, or `None` for the unused slot that
+    /// follows long and double constants
+    offsets: Vec<Option<usize>>,
+}
+
+impl<'a> ConstantPoolView<'a> {
+    /// Scans the constant pool starting at the current position of the buffer, recording
+    /// where each entry starts
+    fn scan(buffer: &mut Buffer<'a>, bytes: &'a [u8]) -> Result<Self> {
+        let offsets = scan_constants(buffer)?;
+        Ok(Self::new(bytes, offsets))
+    }
+
+    /// Creates a view given the offsets in `bytes` returned by [scan_constants]
+    pub(crate) fn new(bytes: &'a [u8], offsets: Vec<Option<usize>>) -> Self {
+        Self { bytes, offsets }
+    }
+
+    /// The number of slots in the pool, including the unused ones after long and double
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    fn offset(&self, index: u16) -> Result<usize> {
+        let offset = index
+            .checked_sub(1)
+            .and_then(|i| self.offsets.get(i.into_usize_safe()))
+            .copied()
+            .flatten()
+            .ok_or(InvalidConstantPoolIndexError { index })?;
+        Ok(offset)
+    }
+
+    fn entry_bytes(&self, index: u16) -> Result<(u8, Buffer<'a>)> {
+        let mut buffer = Buffer::new(&self.bytes[self.offset(index)?..]);
+        let tag = buffer.read_u8()?;
+        Ok((tag, buffer))
+    }
+
+    /// Decodes an entry given its index. Note that it must be 1-based!
+    pub fn get(&self, index: u16) -> Result<ConstantPoolEntry> {
+        decode_constant(&self.bytes[self.offset(index)?..])
+    }
+
+    /// Returns the content of an `Utf8` entry, borrowing it when it is also valid UTF-8
+    pub fn utf8(&self, index: u16) -> Result<Cow<'a, str>> {
+        let (tag, mut buffer) = self.entry_bytes(index)?;
+        if tag != 1 {
+            return Err(ClassReaderError::invalid_class_data(format!(
+                "constant at index {index} is not a string"
+            )));
+        }
+        let len = buffer.read_u16()?.into_usize_safe();
+        from_java_cesu8(buffer.read_bytes(len)?).map_err(|_| BufferError::InvalidCesu8String.into())
+    }
+
+    /// Returns the text of an `Utf8`, `ClassReference` or `StringReference` entry
+    pub fn text_of(&self, index: u16) -> Result<Cow<'a, str>> {
+        let (tag, mut buffer) = self.entry_bytes(index)?;
+        match tag {
+            7 | 8 => self.utf8(buffer.read_u16()?),
+            _ => self.utf8(index),
+        }
+    }
+
+    /// Decodes all the entries into an owned [ConstantPool], which is needed to interpret
+    /// attributes, i.e. via [crate::annotation::Annotation::decode_attribute]
+    pub fn to_constant_pool(&self) -> Result<ConstantPool> {
+        let mut constants = ConstantPool::new();
+        for (i, offset) in self.offsets.iter().enumerate() {
+            if offset.is_some() {
+                constants
+                    .add(self.get(i as u16 + 1)?)
+                    .map_err(|error| ClassReaderError::invalid_class_data(error.to_string()))?;
+            }
+        }
+        Ok(constants)
+    }
+}
+
+impl<'a> FieldView<'a> {
+    pub fn field_type(&self) -> Result<FieldType> {
+        FieldType::parse(&self.type_descriptor)
+    }
+}
+
+impl<'a> MethodView<'a> {
+    pub fn method_descriptor(&self) -> Result<MethodDescriptor> {
+        MethodDescriptor::parse(&self.type_descriptor)
+    }
+}
+
+impl<'a> CodeView<'a> {
+    /// Parses the instructions, along with their address
+    pub fn instructions(&self) -> Result<Vec<(usize, Instruction)>> {
+        Instruction::parse_instructions(self.code)
+    }
+}
+
+/// A reader that builds a [ClassFileView], without copying the data
+struct ClassFileViewReader<'a> {
+    data: &'a [u8],
+    buffer: Buffer<'a>,
+}
+
+impl<'a> ClassFileViewReader<'a> {
+    fn read(mut self) -> Result<ClassFileView<'a>> {
+        if self.buffer.read_u32()? != 0xCAFEBABE {
+            return Err(ClassReaderError::invalid_class_data(
+                "invalid magic number".to_owned(),
+            ));
+        }
+        let minor_version = self.buffer.read_u16()?;
+        let major_version = self.buffer.read_u16()?;
+        let version = ClassFileVersion::from(major_version, minor_version)?;
+        let constants = ConstantPoolView::scan(&mut self.buffer, self.data)?;
+
+        let flags_bits = self.buffer.read_u16()?;
+        let flags = ClassAccessFlags::from_bits(flags_bits).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!("invalid class flags: {flags_bits}"))
+        })?;
+        let name = constants.text_of(self.buffer.read_u16()?)?;
+        let superclass = match self.buffer.read_u16()? {
+            0 => None,
+            index => Some(constants.text_of(index)?),
+        };
+        let interfaces_count = self.buffer.read_u16()?;
+        let interfaces = (0..interfaces_count)
+            .map(|_| constants.text_of(self.buffer.read_u16()?))
+            .collect::<Result<Vec<_>>>()?;
+
+        let fields_count = self.buffer.read_u16()?;
+        let fields = (0..fields_count)
+            .map(|_| self.read_field(&constants))
+            .collect::<Result<Vec<_>>>()?;
+        let methods_count = self.buffer.read_u16()?;
+        let methods = (0..methods_count)
+            .map(|_| self.read_method(&constants))
+            .collect::<Result<Vec<_>>>()?;
+        let attributes = Self::read_attributes(&mut self.buffer, &constants)?;
+
+        Ok(ClassFileView {
+            version,
+            constants,
+            flags,
+            name,
+            superclass,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+        })
+    }
+
+    fn read_field(&mut self, constants: &ConstantPoolView<'a>) -> Result<FieldView<'a>> {
+        let flags_bits = self.buffer.read_u16()?;
+        let flags = FieldFlags::from_bits(flags_bits).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!("invalid field flags: {flags_bits:#0x}"))
+        })?;
+        Ok(FieldView {
+            flags,
+            name: constants.utf8(self.buffer.read_u16()?)?,
+            type_descriptor: constants.utf8(self.buffer.read_u16()?)?,
+            attributes: Self::read_attributes(&mut self.buffer, constants)?,
+        })
+    }
+
+    fn read_method(&mut self, constants: &ConstantPoolView<'a>) -> Result<MethodView<'a>> {
+        let flags_bits = self.buffer.read_u16()?;
+        let flags = MethodFlags::from_bits(flags_bits).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!("invalid method flags: {flags_bits:#0x}"))
+        })?;
+        let name = constants.utf8(self.buffer.read_u16()?)?;
+        let type_descriptor = constants.utf8(self.buffer.read_u16()?)?;
+        let attributes = Self::read_attributes(&mut self.buffer, constants)?;
+        let code = attributes
+            .iter()
+            .find(|attribute| attribute.name == "Code")
+            .map(|attribute| Self::read_code(attribute.bytes, constants))
+            .transpose()?;
+        Ok(MethodView {
+            flags,
+            name,
+            type_descriptor,
+            code,
+            attributes,
+        })
+    }
+
+    fn read_code(bytes: &'a [u8], constants: &ConstantPoolView<'a>) -> Result<CodeView<'a>> {
+        let mut buffer = Buffer::new(bytes);
+        let max_stack = buffer.read_u16()?;
+        let max_locals = buffer.read_u16()?;
+        let code_length = buffer.read_u32()?.into_usize_safe();
+        let code = buffer.read_bytes(code_length)?;
+        let exception_table_length = buffer.read_u16()?;
+        let exception_table = (0..exception_table_length)
+            .map(|_| {
+                let start_pc = ProgramCounter(buffer.read_u16()?);
+                let end_pc = ProgramCounter(buffer.read_u16()?);
+                let handler_pc = ProgramCounter(buffer.read_u16()?);
+                let catch_class = match buffer.read_u16()? {
+                    0 => None,
+                    index => Some(constants.text_of(index)?),
+                };
+                Ok(ExceptionHandlerView {
+                    start_pc,
+                    end_pc,
+                    handler_pc,
+                    catch_class,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let attributes = Self::read_attributes(&mut buffer, constants)?;
+        Ok(CodeView {
+            max_stack,
+            max_locals,
+            code,
+            exception_table,
+            attributes,
+        })
+    }
+
+    fn read_attributes(
+        buffer: &mut Buffer<'a>,
+        constants: &ConstantPoolView<'a>,
+    ) -> Result<Vec<AttributeView<'a>>> {
+        let attributes_count = buffer.read_u16()?;
+        (0..attributes_count)
+            .map(|_| {
+                let name = constants.utf8(buffer.read_u16()?)?;
+                let len = buffer.read_u32()?.into_usize_safe();
+                let bytes = buffer.read_bytes(len)?;
+                Ok(AttributeView { name, bytes })
+            })
+            .collect()
+    }
+}
+
+/// Reads a view of a class from a byte slice, borrowing names, code and attributes from it
+pub fn view_buffer(buf: &[u8]) -> Result<ClassFileView<'_>> {
+    ClassFileViewReader {
+        data: buf,
+        buffer: Buffer::new(buf),
+    }
+    .read()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{
+        class_builder::ClassBuilder,
+        class_file_view::view_buffer,
+        class_reader::read_buffer,
+        class_writer::write_class,
+        field_flags::FieldFlags,
+        field_type::{BaseType, FieldType},
+    };
+
+    #[test]
+    fn names_are_borrowed_unless_they_need_decoding() {
+        let mut builder = ClassBuilder::new("a/Test");
+        builder
+            .field(FieldFlags::PUBLIC, "plain", FieldType::Base(BaseType::Int))
+            .field(FieldFlags::PUBLIC, "nul\0", FieldType::Base(BaseType::Int));
+        let bytes = write_class(&builder.build().unwrap()).unwrap();
+
+        let view = view_buffer(&bytes).unwrap();
+        assert_eq!("a/Test", view.name);
+        assert!(matches!(view.fields[0].name, Cow::Borrowed("plain")));
+        assert!(matches!(&view.fields[1].name, Cow::Owned(name) if name == "nul\0"));
+
+        let constants = view.constants.to_constant_pool().unwrap();
+        let original = read_buffer(&bytes).unwrap().constants;
+        assert_eq!(
+            original.iter().collect::<Vec<_>>(),
+            constants.iter().collect::<Vec<_>>()
+        );
+    }
+}
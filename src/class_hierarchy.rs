@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{class_access_flags::ClassAccessFlags, class_file::ClassFile};
+
+/// Provides information about the hierarchy of classes, which is needed to merge the types
+/// of values coming from different execution paths when computing stack map frames, and to
+/// check assignability when verifying methods.
+/// Class names are in the internal form, i.e. `java/lang/Object`.
+pub trait ClassHierarchy {
+    /// Returns the most specific common superclass of the two given classes
+    fn common_superclass(&self, first: &str, second: &str) -> String;
+
+    /// Whether the given class is an interface
+    fn is_interface(&self, _class: &str) -> bool {
+        false
+    }
+
+    /// Whether a value of class `from` can be assigned to a variable of class `to`.
+    /// As in the JVM verifier, every class is assignable to an interface.
+    fn is_assignable(&self, from: &str, to: &str) -> bool {
+        from == to
+            || to == "java/lang/Object"
+            || self.is_interface(to)
+            || self.common_superclass(from, to) == to
+    }
+}
+
+/// A [ClassHierarchy] that does not know anything about the classes, and assumes that the
+/// common superclass of two different classes is always `java/lang/Object`
+#[derive(Debug, Default)]
+pub struct ObjectClassHierarchy;
+
+impl ClassHierarchy for ObjectClassHierarchy {
+    fn common_superclass(&self, first: &str, second: &str) -> String {
+        if first == second {
+            first.to_string()
+        } else {
+            "java/lang/Object".to_string()
+        }
+    }
+
+    /// Since nothing is known about the classes, every class is considered assignable
+    fn is_assignable(&self, _from: &str, _to: &str) -> bool {
+        true
+    }
+}
+
+/// A [ClassHierarchy] backed by a map from each class to its superclass. Classes not in the
+/// map are assumed to extend `java/lang/Object`.
+#[derive(Debug, Default)]
+pub struct MapClassHierarchy {
+    superclasses: HashMap<String, String>,
+    interfaces: HashSet<String>,
+}
+
+impl MapClassHierarchy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Builds the hierarchy from the given classes
+    pub fn from_classes<'a>(classes: impl IntoIterator<Item = &'a ClassFile>) -> Self {
+        let mut hierarchy = Self::new();
+        for class in classes {
+            if class.flags.contains(ClassAccessFlags::INTERFACE) {
+                hierarchy.add_interface(&class.name);
+            }
+            if let Some(superclass) = &class.superclass {
+                hierarchy.add(&class.name, superclass);
+            }
+        }
+        hierarchy
+    }
+
+    pub fn add(&mut self, class: &str, superclass: &str) {
+        self.superclasses
+            .insert(class.to_string(), superclass.to_string());
+    }
+
+    pub fn add_interface(&mut self, interface: &str) {
+        self.interfaces.insert(interface.to_string());
+    }
+
+    /// Returns the given class followed by all its superclasses
+    fn superclass_chain<'a>(&'a self, class: &'a str) -> Vec<&'a str> {
+        let mut chain = vec![class];
+        let mut current = class;
+        while current != "java/lang/Object" {
+            current = self
+                .superclasses
+                .get(current)
+                .map_or("java/lang/Object", String::as_str);
+            if chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+        }
+        chain
+    }
+}
+
+impl ClassHierarchy for MapClassHierarchy {
+    fn common_superclass(&self, first: &str, second: &str) -> String {
+        let second_chain = self.superclass_chain(second);
+        self.superclass_chain(first)
+            .into_iter()
+            .find(|class| second_chain.contains(class))
+            .unwrap_or("java/lang/Object")
+            .to_string()
+    }
+
+    fn is_interface(&self, class: &str) -> bool {
+        self.interfaces.contains(class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::class_hierarchy::{ClassHierarchy, MapClassHierarchy, ObjectClassHierarchy};
+
+    #[test]
+    fn object_hierarchy_merges_everything_into_object() {
+        let hierarchy = ObjectClassHierarchy;
+        assert_eq!("a/A", hierarchy.common_superclass("a/A", "a/A"));
+        assert_eq!(
+            "java/lang/Object",
+            hierarchy.common_superclass("a/A", "a/B")
+        );
+    }
+
+    #[test]
+    fn map_hierarchy_finds_common_superclass() {
+        let mut hierarchy = MapClassHierarchy::new();
+        hierarchy.add("a/Base", "java/lang/Object");
+        hierarchy.add("a/Left", "a/Base");
+        hierarchy.add("a/Right", "a/Base");
+        hierarchy.add("a/LeftChild", "a/Left");
+
+        assert_eq!(
+            "a/Base",
+            hierarchy.common_superclass("a/LeftChild", "a/Right")
+        );
+        assert_eq!(
+            "a/Left",
+            hierarchy.common_superclass("a/Left", "a/LeftChild")
+        );
+        assert_eq!(
+            "java/lang/Object",
+            hierarchy.common_superclass("a/Left", "b/Unknown")
+        );
+    }
+
+    #[test]
+    fn map_hierarchy_checks_assignability() {
+        let mut hierarchy = MapClassHierarchy::new();
+        hierarchy.add("a/Base", "java/lang/Object");
+        hierarchy.add("a/Child", "a/Base");
+        hierarchy.add_interface("a/Interface");
+
+        assert!(hierarchy.is_assignable("a/Child", "a/Base"));
+        assert!(hierarchy.is_assignable("a/Child", "java/lang/Object"));
+        assert!(hierarchy.is_assignable("a/Child", "a/Interface"));
+        assert!(!hierarchy.is_assignable("a/Base", "a/Child"));
+        assert!(!hierarchy.is_assignable("a/Base", "b/Unknown"));
+    }
+}
@@ -10,48 +10,312 @@ use crate::{
     class_file_method::{ClassFileMethod, ClassFileMethodCode},
     class_file_version::ClassFileVersion,
     class_reader_error::{ClassReaderError, Result},
-    constant_pool::{ConstantPool, ConstantPoolEntry},
+    class_visitor::{AttributeTarget, ClassHeader, ClassVisitor, Visit},
+    constant_pool::{scan_constants, ConstantPool, ConstantPoolEntry},
+    diagnostic::{Diagnostic, Severity},
     exception_table::{ExceptionTable, ExceptionTableEntry},
     field_flags::FieldFlags,
     field_type::FieldType,
+    instruction::Instruction,
     line_number::LineNumber,
     line_number_table::{LineNumberTable, LineNumberTableEntry},
     method_descriptor::MethodDescriptor,
     method_flags::MethodFlags,
     program_counter::ProgramCounter,
+    read_limits::ReadLimit,
+    read_options::ReadOptions,
+    span::class_spans,
     type_conversion::ToUsizeSafe,
 };
 
 /// A reader of a byte array representing a class. Supports only a subset of Java 7 class format,
 /// in particular it does not support generics.
 struct ClassFileReader<'a> {
+    data: &'a [u8],
     buffer: Buffer<'a>,
+    options: ReadOptions,
+    /// Attributes that are skipped without being copied, depending on the options
+    skipped_attributes: Vec<&'static str>,
+    /// The path of the element being read, added to the errors
+    path: Vec<String>,
+    /// Whether errors in elements that can be skipped should be recorded as diagnostics
+    /// instead of failing the read
+    lenient: bool,
+    diagnostics: Vec<Diagnostic>,
+    /// Number of bytes allocated so far, checked against the limits
+    allocated: usize,
     /// The class being read, created empty and updated in place
     class_file: ClassFile,
 }
 
+/// An attribute whose content has not been copied out of the class bytes yet
+struct RawAttribute<'a> {
+    name: String,
+    /// Offset of the content in the class file
+    offset: usize,
+    bytes: &'a [u8],
+}
+
+impl RawAttribute<'_> {
+    fn into_attribute(self) -> Attribute {
+        Attribute {
+            name: self.name,
+            bytes: Vec::from(self.bytes),
+        }
+    }
+}
+
 /// Reference: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html
 impl<'a> ClassFileReader<'a> {
-    fn new(data: &[u8]) -> ClassFileReader {
+    fn new(data: &[u8], options: ReadOptions) -> ClassFileReader<'_> {
         ClassFileReader {
+            data,
             buffer: Buffer::new(data),
+            options,
+            skipped_attributes: options.skipped_attributes(),
+            path: Vec::new(),
+            lenient: false,
+            diagnostics: Vec::new(),
+            allocated: 0,
             class_file: Default::default(),
         }
     }
 
     fn read(mut self) -> Result<ClassFile> {
-        self.check_magic_number()?;
-        self.read_version()?;
-        self.read_constants()?;
-        self.read_access_flags()?;
-        self.class_file.name = self.read_class_reference()?;
-        self.class_file.superclass = self.read_class_reference_optional()?;
-        self.read_interfaces()?;
+        self.read_class().map_err(|err| self.locate(err))?;
+        if self.options.record_spans {
+            self.class_file.spans = class_spans(self.data).map_err(|err| self.locate(err))?;
+        }
+        Ok(self.class_file)
+    }
+
+    /// Reads as much as possible of the class, collecting diagnostics for the broken parts.
+    /// Fails only if the header of the class cannot be read.
+    fn read_lenient(mut self) -> Result<(ClassFile, Vec<Diagnostic>)> {
+        self.lenient = true;
+        self.read_header().map_err(|err| self.locate(err))?;
+        if !self.options.header_only {
+            let body = self.read_body();
+            // The rest of the class cannot be found after a truncated or malformed element
+            self.recover(Severity::Error, body, || ())?;
+        }
+        if self.options.record_spans {
+            let spans = class_spans(self.data);
+            self.class_file.spans = self.recover(Severity::Warning, spans, Vec::new)?;
+        }
+        Ok((self.class_file, self.diagnostics))
+    }
+
+    fn read_class(&mut self) -> Result<()> {
+        self.read_header()?;
+        if self.options.header_only {
+            return Ok(());
+        }
+        self.read_body()
+    }
+
+    fn read_body(&mut self) -> Result<()> {
         self.read_fields()?;
         self.read_methods()?;
-        self.read_class_attributes()?;
+        self.read_class_attributes()
+    }
 
-        Ok(self.class_file)
+    fn read_header(&mut self) -> Result<()> {
+        self.within("magic", Self::check_magic_number)?;
+        self.within("version", Self::read_version)?;
+        self.read_constants()?;
+        self.within("access_flags", Self::read_access_flags)?;
+        self.class_file.name = self.within("this_class", Self::read_class_reference)?;
+        self.class_file.superclass =
+            self.within("super_class", Self::read_class_reference_optional)?;
+        self.read_interfaces()
+    }
+
+    /// Adds the current location to the error
+    fn locate(&self, err: ClassReaderError) -> ClassReaderError {
+        err.located(self.buffer.position(), self.path.join(" > "))
+    }
+
+    /// Reads an element of the class, adding its location to the errors
+    fn within<T>(
+        &mut self,
+        segment: impl Into<String>,
+        read: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.path.push(segment.into());
+        let result = read(self).map_err(|err| self.locate(err));
+        self.path.pop();
+        result
+    }
+
+    /// In lenient mode, records a diagnostic for the error and replaces the broken element
+    /// with the placeholder. Otherwise, returns the error.
+    fn recover<T>(
+        &mut self,
+        severity: Severity,
+        result: Result<T>,
+        placeholder: impl FnOnce() -> T,
+    ) -> Result<T> {
+        match result {
+            Err(err) if self.lenient => {
+                let err = self.locate(err);
+                self.diagnostics
+                    .push(Diagnostic::from_error(severity, &err));
+                Ok(placeholder())
+            }
+            result => result,
+        }
+    }
+
+    /// Adds the name of the element being read to its path, i.e. `methods[3] "foo"`
+    fn name_element(&mut self, name: &str) {
+        if let Some(segment) = self.path.last_mut() {
+            segment.push_str(&format!(" {name:?}"));
+        }
+    }
+
+    /// Reads the content of an attribute, adding its location to the errors
+    fn read_attribute<T>(
+        &mut self,
+        attribute: &RawAttribute<'a>,
+        read: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let data = self.data;
+        let end = attribute.offset + attribute.bytes.len();
+        let buffer = Buffer::starting_at(&data[..end], attribute.offset);
+        let outer_buffer = std::mem::replace(&mut self.buffer, buffer);
+        let result = self.within(attribute.name.as_str(), read);
+        self.buffer = outer_buffer;
+        result
+    }
+
+    /// Reads the class, passing its content to the visitor instead of storing it
+    fn accept(mut self, visitor: &mut impl ClassVisitor) -> Result<()> {
+        self.accept_class(visitor).map_err(|err| self.locate(err))
+    }
+
+    fn accept_class(&mut self, visitor: &mut impl ClassVisitor) -> Result<()> {
+        self.read_header()?;
+        let header = ClassHeader {
+            version: self.class_file.version,
+            constants: &self.class_file.constants,
+            flags: self.class_file.flags,
+            name: &self.class_file.name,
+            superclass: self.class_file.superclass.as_deref(),
+            interfaces: &self.class_file.interfaces,
+        };
+        if visitor.visit_header(&header) == Visit::Skip {
+            return Ok(());
+        }
+
+        let fields_count = self.buffer.read_u16()?;
+        for i in 0..fields_count {
+            self.within(format!("fields[{i}]"), |reader| {
+                let flags = reader.read_field_flags()?;
+                let name = reader.read_utf8_reference()?;
+                reader.name_element(&name);
+                let type_descriptor = FieldType::parse_with_limits(
+                    &reader.read_utf8_reference()?,
+                    &reader.options.limits,
+                )?;
+                let visit = visitor.visit_field(flags, &name, &type_descriptor);
+                reader.visit_attributes(visitor, AttributeTarget::Field, visit)
+            })?;
+        }
+
+        let methods_count = self.buffer.read_u16()?;
+        for i in 0..methods_count {
+            self.within(format!("methods[{i}]"), |reader| {
+                let flags = reader.read_method_flags()?;
+                let name = reader.read_utf8_reference()?;
+                reader.name_element(&name);
+                let type_descriptor = MethodDescriptor::parse_with_limits(
+                    &reader.read_utf8_reference()?,
+                    &reader.options.limits,
+                )?;
+                let visit = visitor.visit_method(flags, &name, &type_descriptor);
+                reader.visit_attributes(visitor, AttributeTarget::Method, visit)
+            })?;
+        }
+
+        self.visit_attributes(visitor, AttributeTarget::Class, Visit::Continue)?;
+        visitor.visit_end();
+        Ok(())
+    }
+
+    /// Reads a list of attributes, passing them to the visitor unless they should be skipped
+    fn visit_attributes(
+        &mut self,
+        visitor: &mut impl ClassVisitor,
+        target: AttributeTarget,
+        visit: Visit,
+    ) -> Result<()> {
+        let attributes_count = self.buffer.read_u16()?;
+        for _ in 0..attributes_count {
+            let name_constant_index = self.buffer.read_u16()?;
+            let len = self.read_attribute_length()?;
+            let offset = self.buffer.position();
+            let bytes = self.buffer.read_bytes(len)?;
+            if visit == Visit::Skip {
+                continue;
+            }
+            let name = self.read_string_reference(name_constant_index)?;
+            if target == AttributeTarget::Method && name == "Code" {
+                let attribute = RawAttribute {
+                    name,
+                    offset,
+                    bytes,
+                };
+                self.read_attribute(&attribute, |reader| reader.visit_code(visitor))?;
+            } else {
+                visitor.visit_attribute(target, &name, bytes, &self.class_file.constants);
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_code(&mut self, visitor: &mut impl ClassVisitor) -> Result<()> {
+        let max_stack = self.buffer.read_u16()?;
+        let max_locals = self.buffer.read_u16()?;
+        if visitor.visit_code(max_stack, max_locals) == Visit::Skip {
+            return Ok(());
+        }
+
+        let code_length = self.read_code_length()?;
+        let code_offset = self.buffer.position();
+        let code = self.buffer.read_bytes(code_length)?;
+        let mut address = 0;
+        while address < code.len() {
+            let (instruction, next_address) = Instruction::parse(code, address).map_err(|err| {
+                err.located(
+                    code_offset + address,
+                    format!("{} > code[{address}]", self.path.join(" > ")),
+                )
+            })?;
+            let program_counter = u16::try_from(address).map_err(|_| {
+                ClassReaderError::invalid_class_data(format!("code too large: {code_length}"))
+            })?;
+            visitor.visit_code_instruction(ProgramCounter(program_counter), &instruction);
+            address = next_address;
+        }
+
+        for entry in self.read_exception_table()?.entries() {
+            visitor.visit_exception_handler(entry);
+        }
+
+        let attributes_count = self.buffer.read_u16()?;
+        for _ in 0..attributes_count {
+            let name = self.read_utf8_reference()?;
+            let len = self.read_attribute_length()?;
+            let bytes = self.buffer.read_bytes(len)?;
+            visitor.visit_attribute(
+                AttributeTarget::Code,
+                &name,
+                bytes,
+                &self.class_file.constants,
+            );
+        }
+        Ok(())
     }
 
     fn check_magic_number(&mut self) -> Result<()> {
@@ -68,42 +332,72 @@ impl<'a> ClassFileReader<'a> {
         let minor_version = self.buffer.read_u16()?;
         let major_version = self.buffer.read_u16()?;
 
-        self.class_file.version = ClassFileVersion::from(major_version, minor_version)?;
+        let version = ClassFileVersion::from(major_version, minor_version);
+        // Newer versions are usually backward compatible enough to be read anyway, while
+        // unknown older ones are not class files we can make sense of
+        self.class_file.version = if major_version > ClassFileVersion::Jdk22.major() {
+            self.recover(Severity::Warning, version, || ClassFileVersion::Jdk22)?
+        } else {
+            version?
+        };
         Ok(())
     }
 
     fn read_constants(&mut self) -> Result<()> {
-        let constants_count = self.buffer.read_u16()? - 1;
+        if self.options.lazy_constants {
+            let start = self.buffer.position();
+            let offsets =
+                self.within("constant_pool", |reader| scan_constants(&mut reader.buffer))?;
+            let raw = &self.data[start..self.buffer.position()];
+            self.allocate(raw.len())?;
+            let offsets = offsets
+                .into_iter()
+                .map(|offset| offset.map(|offset| offset - start))
+                .collect();
+            self.class_file.constants = ConstantPool::lazy(raw, offsets);
+            return Ok(());
+        }
+
+        // A count of 0 is malformed, but like a count of 1 it means that the pool is empty
+        let constants_count = self
+            .within("constant_pool", |reader| Ok(reader.buffer.read_u16()?))?
+            .saturating_sub(1);
         let mut i = 0;
         while i < constants_count {
-            let tag = self.buffer.read_u8()?;
-            let constant = match tag {
-                1 => self.read_utf8_constant()?,
-                3 => self.read_int_constant()?,
-                4 => self.read_float_constant()?,
-                5 => {
-                    i += 1; // long constants takes up two slots in the pool
-                    self.read_long_constant()?
-                }
-                6 => {
-                    i += 1; // double constants takes up two slots in the pool
-                    self.read_double_constant()?
-                }
-                7 => self.read_class_reference_constant()?,
-                8 => self.read_string_reference_constant()?,
-                9 => self.read_field_reference_constant()?,
-                10 => self.read_method_reference_constant()?,
-                11 => self.read_interface_method_reference_constant()?,
-                12 => self.read_name_and_type_constant()?,
-                // For newer versions of java, there are more constant types
-                _ => {
-                    warn!("invalid entry in constant pool at index {} tag {}", i, tag);
-                    return Err(ClassReaderError::invalid_class_data(format!(
-                        "Unknown constant type: 0x{tag:X}"
-                    )));
-                }
-            };
-            self.class_file.constants.add(constant);
+            let index = i + 1;
+            let constant = self.within(format!("constant_pool[{index}]"), |reader| {
+                let tag = reader.buffer.read_u8()?;
+                Ok(match tag {
+                    1 => reader.read_utf8_constant()?,
+                    3 => reader.read_int_constant()?,
+                    4 => reader.read_float_constant()?,
+                    5 => {
+                        i += 1; // long constants takes up two slots in the pool
+                        reader.read_long_constant()?
+                    }
+                    6 => {
+                        i += 1; // double constants takes up two slots in the pool
+                        reader.read_double_constant()?
+                    }
+                    7 => reader.read_class_reference_constant()?,
+                    8 => reader.read_string_reference_constant()?,
+                    9 => reader.read_field_reference_constant()?,
+                    10 => reader.read_method_reference_constant()?,
+                    11 => reader.read_interface_method_reference_constant()?,
+                    12 => reader.read_name_and_type_constant()?,
+                    // For newer versions of java, there are more constant types
+                    _ => {
+                        warn!("invalid entry in constant pool at index {} tag {}", i, tag);
+                        return Err(ClassReaderError::invalid_class_data(format!(
+                            "Unknown constant type: 0x{tag:X}"
+                        )));
+                    }
+                })
+            })?;
+            self.class_file
+                .constants
+                .add(constant)
+                .map_err(|error| ClassReaderError::invalid_class_data(error.to_string()))?;
 
             i += 1;
         }
@@ -113,6 +407,7 @@ impl<'a> ClassFileReader<'a> {
 
     fn read_utf8_constant(&mut self) -> Result<ConstantPoolEntry> {
         let len = self.buffer.read_u16()?;
+        self.allocate(len as usize)?;
         self.buffer
             .read_utf8(len as usize)
             .map(ConstantPoolEntry::Utf8)
@@ -195,20 +490,18 @@ impl<'a> ClassFileReader<'a> {
 
     fn read_access_flags(&mut self) -> Result<()> {
         let num = self.buffer.read_u16()?;
-        match ClassAccessFlags::from_bits(num) {
-            Some(flags) => {
-                self.class_file.flags = flags;
-                Ok(())
-            }
-            None => Err(ClassReaderError::invalid_class_data(format!(
-                "invalid class flags: {num}"
-            ))),
-        }
+        let flags = ClassAccessFlags::from_bits(num).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!("invalid class flags: {num}"))
+        });
+        self.class_file.flags = self.recover(Severity::Warning, flags, || {
+            ClassAccessFlags::from_bits_truncate(num)
+        })?;
+        Ok(())
     }
 
     fn read_class_reference(&mut self) -> Result<String> {
         let class_constant_idx = self.buffer.read_u16()?;
-        self.read_string_reference(class_constant_idx)
+        self.resolve_string_reference(class_constant_idx)
     }
 
     fn read_class_reference_optional(&mut self) -> Result<Option<String>> {
@@ -216,45 +509,73 @@ impl<'a> ClassFileReader<'a> {
         if super_constant_idx == 0 {
             Ok(None)
         } else {
-            Ok(Some(self.read_string_reference(super_constant_idx)?))
+            Ok(Some(self.resolve_string_reference(super_constant_idx)?))
         }
     }
 
-    fn read_string_reference(&self, index: u16) -> Result<String> {
-        Self::read_string_reference_from(&self.class_file.constants, index)
+    fn read_utf8_reference(&mut self) -> Result<String> {
+        let index = self.buffer.read_u16()?;
+        self.resolve_string_reference(index)
     }
 
-    fn read_string_reference_from(constants_pool: &ConstantPool, index: u16) -> Result<String> {
-        constants_pool.text_of(index).map_err(|err| err.into())
+    /// Like [Self::read_string_reference], but in lenient mode invalid references are
+    /// replaced by a placeholder
+    fn resolve_string_reference(&mut self, index: u16) -> Result<String> {
+        let text = self.read_string_reference(index);
+        self.recover(Severity::Error, text, || {
+            format!("<invalid constant #{index}>")
+        })
+    }
+
+    fn read_string_reference(&self, index: u16) -> Result<String> {
+        self.class_file
+            .constants
+            .text_of(index)
+            .map_err(|err| err.into())
     }
 
     fn read_interfaces(&mut self) -> Result<()> {
         let interfaces_count = self.buffer.read_u16()?;
         self.class_file.interfaces = (0..interfaces_count)
-            .map(|_| self.read_class_reference())
+            .map(|i| self.within(format!("interfaces[{i}]"), Self::read_class_reference))
             .collect::<Result<Vec<String>>>()?;
         Ok(())
     }
 
+    /// Reads the fields, adding them to the class one at a time so that a lenient read
+    /// keeps the ones before a broken field
     fn read_fields(&mut self) -> Result<()> {
         let fields_count = self.buffer.read_u16()?;
-        self.class_file.fields = (0..fields_count)
-            .map(|_| self.read_field())
-            .collect::<Result<Vec<ClassFileField>>>()?;
+        for i in 0..fields_count {
+            let field = self.within(format!("fields[{i}]"), Self::read_field)?;
+            self.class_file.fields.push(field);
+        }
         Ok(())
     }
 
     fn read_field(&mut self) -> Result<ClassFileField> {
         let flags = self.read_field_flags()?;
         let name_constant_index = self.buffer.read_u16()?;
-        let name = self.read_string_reference(name_constant_index)?;
+        let name = self.resolve_string_reference(name_constant_index)?;
+        self.name_element(&name);
         let type_constant_index = self.buffer.read_u16()?;
-        let type_descriptor_raw = self.read_string_reference(type_constant_index)?;
-        let type_descriptor = FieldType::parse(&type_descriptor_raw)?;
+        let type_descriptor_raw = self.resolve_string_reference(type_constant_index)?;
+        let type_descriptor =
+            FieldType::parse_with_limits(&type_descriptor_raw, &self.options.limits);
+        let type_descriptor = self.recover(Severity::Error, type_descriptor, || {
+            FieldType::Object("java/lang/Object".to_string())
+        })?;
 
         let raw_attributes = self.read_raw_attributes()?;
-        let constant_value = self.extract_constant_value(&raw_attributes)?;
+        let constant_value = self.extract_constant_value(&raw_attributes);
+        // A broken attribute is kept as it is, rather than being mapped
+        let mut mapped_names = vec!["Deprecated"];
+        if constant_value.is_ok() {
+            mapped_names.push("ConstantValue");
+        }
+        let constant_value = self.recover(Severity::Error, constant_value, || None)?;
         let deprecated = self.search_deprecated_attribute(&raw_attributes);
+        let attributes = Self::unmapped_attributes(raw_attributes, &mapped_names);
 
         Ok(ClassFileField {
             flags,
@@ -262,91 +583,100 @@ impl<'a> ClassFileReader<'a> {
             type_descriptor,
             constant_value,
             deprecated,
+            attributes,
         })
     }
 
     fn read_field_flags(&mut self) -> Result<FieldFlags> {
         let field_flags_bits = self.buffer.read_u16()?;
-        match FieldFlags::from_bits(field_flags_bits) {
-            Some(flags) => Ok(flags),
-            None => Err(ClassReaderError::invalid_class_data(format!(
+        let flags = FieldFlags::from_bits(field_flags_bits).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!(
                 "invalid field flags: {field_flags_bits:#0x}"
-            ))),
-        }
+            ))
+        });
+        self.recover(Severity::Warning, flags, || {
+            FieldFlags::from_bits_truncate(field_flags_bits)
+        })
     }
 
     fn extract_constant_value(
-        &self,
-        raw_attributes: &[Attribute],
+        &mut self,
+        raw_attributes: &[RawAttribute<'a>],
     ) -> Result<Option<FieldConstantValue>> {
         raw_attributes
             .iter()
-            .filter(|attr| attr.name == "ConstantValue")
+            .find(|attr| attr.name == "ConstantValue")
             .map(|attr| {
-                if attr.bytes.len() != std::mem::size_of::<u16>() {
-                    Err(ClassReaderError::invalid_class_data(
-                        "invalid attribute of type ConstantValue".to_string(),
-                    ))
-                } else {
-                    let attribute_bytes: &[u8] = &attr.bytes;
-                    let constant_index = u16::from_be_bytes(attribute_bytes.try_into().unwrap());
-                    self.class_file
-                        .constants
-                        .get(constant_index)
-                        .map_err(|err| err.into())
-                        .and_then(|entry| match entry {
-                            ConstantPoolEntry::StringReference(v) => {
-                                let referred_string = self.read_string_reference(*v)?;
-                                Ok(FieldConstantValue::String(referred_string))
-                            }
-                            ConstantPoolEntry::Integer(v) => Ok(FieldConstantValue::Int(*v)),
-                            ConstantPoolEntry::Float(v) => Ok(FieldConstantValue::Float(*v)),
-                            ConstantPoolEntry::Long(v) => Ok(FieldConstantValue::Long(*v)),
-                            ConstantPoolEntry::Double(v) => Ok(FieldConstantValue::Double(*v)),
-                            v => Err(ClassReaderError::invalid_class_data(format!(
-                                "invalid type for ConstantValue: {v:?}"
-                            ))),
-                        })
-                }
+                self.read_attribute(attr, |reader| {
+                    if attr.bytes.len() != std::mem::size_of::<u16>() {
+                        return Err(ClassReaderError::invalid_class_data(
+                            "invalid attribute of type ConstantValue".to_string(),
+                        ));
+                    }
+                    let constant_index = reader.buffer.read_u16()?;
+                    match reader.class_file.constants.get(constant_index)? {
+                        ConstantPoolEntry::StringReference(v) => {
+                            let referred_string = reader.read_string_reference(*v)?;
+                            Ok(FieldConstantValue::String(referred_string))
+                        }
+                        ConstantPoolEntry::Integer(v) => Ok(FieldConstantValue::Int(*v)),
+                        ConstantPoolEntry::Float(v) => Ok(FieldConstantValue::Float(*v)),
+                        ConstantPoolEntry::Long(v) => Ok(FieldConstantValue::Long(*v)),
+                        ConstantPoolEntry::Double(v) => Ok(FieldConstantValue::Double(*v)),
+                        v => Err(ClassReaderError::invalid_class_data(format!(
+                            "invalid type for ConstantValue: {v:?}"
+                        ))),
+                    }
+                })
             })
-            .next()
             .invert()
     }
 
-    fn search_deprecated_attribute(&self, raw_attributes: &[Attribute]) -> bool {
+    fn search_deprecated_attribute(&self, raw_attributes: &[RawAttribute]) -> bool {
         raw_attributes.iter().any(|attr| attr.name == "Deprecated")
     }
 
     fn read_methods(&mut self) -> Result<()> {
         let methods_count = self.buffer.read_u16()?;
-        self.class_file.methods = (0..methods_count)
-            .map(|_| self.read_method())
-            .collect::<Result<Vec<ClassFileMethod>>>()?;
+        for i in 0..methods_count {
+            let method = self.within(format!("methods[{i}]"), Self::read_method)?;
+            self.class_file.methods.push(method);
+        }
         Ok(())
     }
 
     fn read_method(&mut self) -> Result<ClassFileMethod> {
         let flags = self.read_method_flags()?;
         let name_constant_index = self.buffer.read_u16()?;
-        let name = self.read_string_reference(name_constant_index)?;
+        let name = self.resolve_string_reference(name_constant_index)?;
+        self.name_element(&name);
         let type_constant_index = self.buffer.read_u16()?;
-        let type_descriptor = self.read_string_reference(type_constant_index)?;
-        let parsed_type_descriptor = MethodDescriptor::parse(&type_descriptor)?;
+        let type_descriptor = self.resolve_string_reference(type_constant_index)?;
+        let parsed_type_descriptor =
+            MethodDescriptor::parse_with_limits(&type_descriptor, &self.options.limits);
+        let parsed_type_descriptor =
+            self.recover(Severity::Error, parsed_type_descriptor, Default::default)?;
         let raw_attributes = self.read_raw_attributes()?;
-        let code = if flags.contains(MethodFlags::NATIVE) || flags.contains(MethodFlags::ABSTRACT) {
+        let code = if flags.contains(MethodFlags::NATIVE)
+            || flags.contains(MethodFlags::ABSTRACT)
+            || self.options.skip_code
+        {
             None
         } else {
-            Some(self.extract_code(&raw_attributes, &name)?)
+            // The raw Code attribute is kept in the attributes of the method anyway
+            let code = self.extract_code(&raw_attributes, &name).map(Some);
+            self.recover(Severity::Error, code, || None)?
         };
         let deprecated = self.search_deprecated_attribute(&raw_attributes);
-        let thrown_exceptions = self.extract_thrown_exceptions(&raw_attributes)?;
+        let thrown_exceptions = self.extract_thrown_exceptions(&raw_attributes);
+        let thrown_exceptions = self.recover(Severity::Error, thrown_exceptions, Vec::new)?;
 
         Ok(ClassFileMethod {
             flags,
             name,
             type_descriptor,
             parsed_type_descriptor,
-            attributes: raw_attributes,
+            attributes: Self::unmapped_attributes(raw_attributes, &[]),
             code,
             deprecated,
             thrown_exceptions,
@@ -355,43 +685,47 @@ impl<'a> ClassFileReader<'a> {
 
     fn read_method_flags(&mut self) -> Result<MethodFlags> {
         let method_flags_bits = self.buffer.read_u16()?;
-        match MethodFlags::from_bits(method_flags_bits) {
-            Some(flags) => Ok(flags),
-            None => Err(ClassReaderError::invalid_class_data(format!(
+        let flags = MethodFlags::from_bits(method_flags_bits).ok_or_else(|| {
+            ClassReaderError::invalid_class_data(format!(
                 "invalid method flags: {method_flags_bits:#0x}"
-            ))),
-        }
+            ))
+        });
+        self.recover(Severity::Warning, flags, || {
+            MethodFlags::from_bits_truncate(method_flags_bits)
+        })
     }
 
     fn extract_code(
-        &self,
-        raw_attributes: &[Attribute],
+        &mut self,
+        raw_attributes: &[RawAttribute<'a>],
         name: &str,
     ) -> Result<ClassFileMethodCode> {
         raw_attributes
             .iter()
-            .filter(|attr| attr.name == "Code")
+            .find(|attr| attr.name == "Code")
             .map(|attr| {
-                let mut buf = Buffer::new(&attr.bytes);
-                let max_stack = buf.read_u16()?;
-                let max_locals = buf.read_u16()?;
-                let code_length = buf.read_u32()?.into_usize_safe();
-                let code = Vec::from(buf.read_bytes(code_length)?);
-                let exception_table = self.read_exception_table(&mut buf)?;
-                let attributes =
-                    Self::read_raw_attributes_from(&self.class_file.constants, &mut buf)?;
-                let line_number_table = self.extract_line_number_table(&attributes)?;
-
-                Result::<ClassFileMethodCode>::Ok(ClassFileMethodCode {
-                    max_stack,
-                    max_locals,
-                    code,
-                    exception_table,
-                    line_number_table,
-                    attributes,
+                self.read_attribute(attr, |reader| {
+                    let max_stack = reader.buffer.read_u16()?;
+                    let max_locals = reader.buffer.read_u16()?;
+                    let code_length = reader.read_code_length()?;
+                    reader.allocate(code_length)?;
+                    let code = Vec::from(reader.buffer.read_bytes(code_length)?);
+                    let exception_table = reader.read_exception_table()?;
+                    let raw_attributes = reader.read_raw_attributes()?;
+                    let line_number_table = reader.extract_line_number_table(&raw_attributes);
+                    let line_number_table =
+                        reader.recover(Severity::Error, line_number_table, || None)?;
+
+                    Ok(ClassFileMethodCode {
+                        max_stack,
+                        max_locals,
+                        code,
+                        exception_table,
+                        line_number_table,
+                        attributes: Self::unmapped_attributes(raw_attributes, &[]),
+                    })
                 })
             })
-            .next()
             .invert()?
             .ok_or_else(|| {
                 ClassReaderError::invalid_class_data(format!(
@@ -400,66 +734,72 @@ impl<'a> ClassFileReader<'a> {
             })
     }
 
-    fn read_exception_table(&self, buf: &mut Buffer) -> Result<ExceptionTable> {
-        let exception_table_length = buf.read_u16()?.into_usize_safe();
+    fn read_exception_table(&mut self) -> Result<ExceptionTable> {
+        let exception_table_length = self.buffer.read_u16()?.into_usize_safe();
         let mut entries: Vec<ExceptionTableEntry> = Vec::with_capacity(exception_table_length / 8);
-        for _ in 0..exception_table_length {
-            let start_pc = buf.read_u16()?;
-            let end_pc = buf.read_u16()?;
-            let handler_pc = buf.read_u16()?;
-            let catch_class_constant = buf.read_u16()?;
-            let catch_class = if catch_class_constant == 0 {
-                None
-            } else {
-                Some(self.read_string_reference(catch_class_constant)?)
-            };
-            entries.push(ExceptionTableEntry {
-                range: ProgramCounter(start_pc)..ProgramCounter(end_pc),
-                handler_pc: ProgramCounter(handler_pc),
-                catch_class,
-            })
+        for i in 0..exception_table_length {
+            let entry = self.within(format!("exception_table[{i}]"), |reader| {
+                let start_pc = reader.buffer.read_u16()?;
+                let end_pc = reader.buffer.read_u16()?;
+                let handler_pc = reader.buffer.read_u16()?;
+                let catch_class_constant = reader.buffer.read_u16()?;
+                let catch_class = if catch_class_constant == 0 {
+                    None
+                } else {
+                    Some(reader.resolve_string_reference(catch_class_constant)?)
+                };
+                Ok(ExceptionTableEntry {
+                    range: ProgramCounter(start_pc)..ProgramCounter(end_pc),
+                    handler_pc: ProgramCounter(handler_pc),
+                    catch_class,
+                })
+            })?;
+            entries.push(entry)
         }
         Ok(ExceptionTable::new(entries))
     }
 
     fn extract_line_number_table(
-        &self,
-        raw_attributes: &[Attribute],
+        &mut self,
+        raw_attributes: &[RawAttribute<'a>],
     ) -> Result<Option<LineNumberTable>> {
         raw_attributes
             .iter()
             .find(|attr| attr.name == "LineNumberTable")
             .map(|attr| {
-                let mut buf = Buffer::new(&attr.bytes);
-                let num_entries = buf.read_u16()?.into_usize_safe();
-                let mut entries = Vec::with_capacity(num_entries);
-                for _ in 0..num_entries {
-                    let program_counter = buf.read_u16()?;
-                    let line_number = buf.read_u16()?;
-                    entries.push(LineNumberTableEntry::new(
-                        ProgramCounter(program_counter),
-                        LineNumber(line_number),
-                    ));
-                }
-                Ok(LineNumberTable::new(entries))
+                self.read_attribute(attr, |reader| {
+                    let num_entries = reader.buffer.read_u16()?.into_usize_safe();
+                    let mut entries = Vec::with_capacity(num_entries);
+                    for _ in 0..num_entries {
+                        let program_counter = reader.buffer.read_u16()?;
+                        let line_number = reader.buffer.read_u16()?;
+                        entries.push(LineNumberTableEntry::new(
+                            ProgramCounter(program_counter),
+                            LineNumber(line_number),
+                        ));
+                    }
+                    Ok(LineNumberTable::new(entries))
+                })
             })
             .invert()
     }
 
-    fn extract_thrown_exceptions(&self, raw_attributes: &[Attribute]) -> Result<Vec<String>> {
+    fn extract_thrown_exceptions(
+        &mut self,
+        raw_attributes: &[RawAttribute<'a>],
+    ) -> Result<Vec<String>> {
         raw_attributes
             .iter()
             .find(|attr| attr.name == "Exceptions")
             .map(|attr| {
-                let mut buf = Buffer::new(&attr.bytes);
-                let num_entries = buf.read_u16()?.into_usize_safe();
-                let mut exceptions = Vec::with_capacity(num_entries);
-                for _ in 0..num_entries {
-                    let class_name = buf.read_u16()?;
-                    let exception_class_name = self.read_string_reference(class_name)?;
-                    exceptions.push(exception_class_name);
-                }
-                Ok(exceptions)
+                self.read_attribute(attr, |reader| {
+                    let num_entries = reader.buffer.read_u16()?.into_usize_safe();
+                    let mut exceptions = Vec::with_capacity(num_entries);
+                    for _ in 0..num_entries {
+                        exceptions.push(reader.read_class_reference()?);
+                    }
+                    Ok(exceptions)
+                })
             })
             .unwrap_or(Ok(Vec::new()))
     }
@@ -467,72 +807,156 @@ impl<'a> ClassFileReader<'a> {
     fn read_class_attributes(&mut self) -> Result<()> {
         let raw_attributes = self.read_raw_attributes()?;
         self.class_file.deprecated = self.search_deprecated_attribute(&raw_attributes);
-        self.class_file.source_file = self.search_source_file_attribute(&raw_attributes)?;
+        let source_file = self.search_source_file_attribute(&raw_attributes);
+        let mut mapped_names = vec!["Deprecated"];
+        if source_file.is_ok() {
+            mapped_names.push("SourceFile");
+        }
+        self.class_file.source_file = self.recover(Severity::Error, source_file, || None)?;
+        self.class_file.attributes = Self::unmapped_attributes(raw_attributes, &mapped_names);
         Ok(())
     }
 
-    fn search_source_file_attribute(&self, raw_attributes: &[Attribute]) -> Result<Option<String>> {
+    /// Returns the attributes that are not already modelled by some other field
+    fn unmapped_attributes(
+        raw_attributes: Vec<RawAttribute>,
+        mapped_names: &[&str],
+    ) -> Vec<Attribute> {
+        raw_attributes
+            .into_iter()
+            .filter(|attr| !mapped_names.contains(&attr.name.as_str()))
+            .map(RawAttribute::into_attribute)
+            .collect()
+    }
+
+    fn search_source_file_attribute(
+        &mut self,
+        raw_attributes: &[RawAttribute<'a>],
+    ) -> Result<Option<String>> {
         raw_attributes
             .iter()
             .find(|attr| attr.name == "SourceFile")
             .map(|attr| {
-                let attribute_bytes: &[u8] = &attr.bytes;
-                let constant_index = u16::from_be_bytes(attribute_bytes.try_into().unwrap());
-                self.class_file
-                    .constants
-                    .get(constant_index)
-                    .map_err(|err| err.into())
-                    .and_then(|entry| match entry {
+                self.read_attribute(attr, |reader| {
+                    let constant_index = reader.buffer.read_u16()?;
+                    match reader.class_file.constants.get(constant_index)? {
                         ConstantPoolEntry::Utf8(file_name) => Ok(file_name.clone()),
                         _ => Err(ClassReaderError::invalid_class_data(
                             "invalid SourceFile attribute".to_string(),
                         )),
-                    })
+                    }
+                })
             })
             .invert()
     }
 
-    fn read_raw_attributes(&mut self) -> Result<Vec<Attribute>> {
-        Self::read_raw_attributes_from(&self.class_file.constants, &mut self.buffer)
+    /// Reads a list of attributes, leaving out the skipped ones without copying them
+    fn read_raw_attributes(&mut self) -> Result<Vec<RawAttribute<'a>>> {
+        let attributes_count = self.buffer.read_u16()?;
+        let mut attributes = Vec::with_capacity(attributes_count.into_usize_safe());
+        for _ in 0..attributes_count {
+            let name = self.read_utf8_reference()?;
+            let len = self.read_attribute_length()?;
+            let offset = self.buffer.position();
+            let bytes = self.buffer.read_bytes(len)?;
+            if !self.skipped_attributes.contains(&name.as_str()) {
+                self.allocate(len)?;
+                attributes.push(RawAttribute {
+                    name,
+                    offset,
+                    bytes,
+                });
+            }
+        }
+        Ok(attributes)
     }
 
-    fn read_raw_attributes_from(
-        constants_pool: &ConstantPool,
-        buffer: &mut Buffer,
-    ) -> Result<Vec<Attribute>> {
-        let attributes_count = buffer.read_u16()?;
-        (0..attributes_count)
-            .map(|_| Self::read_raw_attribute(constants_pool, buffer))
-            .collect::<Result<Vec<Attribute>>>()
+    fn read_attribute_length(&mut self) -> Result<usize> {
+        let len = self.buffer.read_u32()?.into_usize_safe();
+        self.options.limits.check(ReadLimit::AttributeLength, len)?;
+        Ok(len)
     }
 
-    fn read_raw_attribute(constants_pool: &ConstantPool, buffer: &mut Buffer) -> Result<Attribute> {
-        let name_constant_index = buffer.read_u16()?;
-        let name = Self::read_string_reference_from(constants_pool, name_constant_index)?;
-        let len = buffer.read_u32()?;
-        let bytes = buffer.read_bytes(len.into_usize_safe())?;
-        Ok(Attribute {
-            name,
-            bytes: Vec::from(bytes),
-        })
+    fn read_code_length(&mut self) -> Result<usize> {
+        let code_length = self.buffer.read_u32()?.into_usize_safe();
+        self.options
+            .limits
+            .check(ReadLimit::CodeLength, code_length)?;
+        Ok(code_length)
+    }
+
+    /// Counts bytes copied out of the class against the allocation limit
+    fn allocate(&mut self, len: usize) -> Result<()> {
+        self.allocated = self.allocated.saturating_add(len);
+        self.options
+            .limits
+            .check(ReadLimit::Allocation, self.allocated)
     }
 }
 
 /// Reads a class from a byte slice.
 pub fn read_buffer(buf: &[u8]) -> Result<ClassFile> {
-    ClassFileReader::new(buf).read()
+    ClassFileReader::new(buf, ReadOptions::default()).read()
+}
+
+/// Reads a class from a byte slice, recovering from the errors in the elements that can be
+/// skipped, like a method whose code is invalid. Returns the class with the broken elements
+/// replaced by placeholders, and the problems that were found. Fails only if the header of
+/// the class is unreadable.
+pub fn read_buffer_lenient(buf: &[u8]) -> Result<(ClassFile, Vec<Diagnostic>)> {
+    ClassFileReader::new(buf, ReadOptions::default()).read_lenient()
+}
+
+/// Reads a class from a byte slice, parsing only the parts selected by the options
+pub fn read_buffer_with_options(buf: &[u8], options: ReadOptions) -> Result<ClassFile> {
+    ClassFileReader::new(buf, options).read()
+}
+
+/// Reads a class from a byte slice in a single pass, passing its content to the visitor
+/// without building a [ClassFile].
+pub fn visit_buffer(buf: &[u8], visitor: &mut impl ClassVisitor) -> Result<()> {
+    ClassFileReader::new(buf, ReadOptions::default()).accept(visitor)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{class_reader::read_buffer, class_reader_error::ClassReaderError};
+    use std::error::Error;
+
+    use crate::{
+        class_reader::read_buffer,
+        class_reader_error::{BufferError, ClassReaderError},
+    };
 
     #[test]
     fn magic_number_is_required() {
         let data = vec![0x00, 0x01, 0x02, 0x03];
+        let err = read_buffer(&data).unwrap_err();
         assert!(matches!(
-            read_buffer(&data),
-            Err(ClassReaderError::InvalidClassData(s, None)) if s == "invalid magic number"
+            err.kind(),
+            ClassReaderError::InvalidClassData(s, None) if s == "invalid magic number"
         ));
+        assert_eq!(Some(4), err.offset());
+        assert_eq!(Some("magic"), err.path());
+    }
+
+    #[test]
+    fn errors_are_located() {
+        let data = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32, 0x00, 0x03, 0x01, 0x00, 0x01, 0x41,
+            0x07,
+        ];
+        let err = read_buffer(&data).unwrap_err();
+        assert_eq!(
+            &ClassReaderError::MalformedData(BufferError::UnexpectedEndOfData),
+            err.kind()
+        );
+        assert_eq!("unexpected-end-of-data", err.code());
+        assert_eq!(Some(15), err.offset());
+        assert_eq!(Some("constant_pool[2]"), err.path());
+        assert_eq!(
+            "invalid class file: unexpected end of data at offset 0xf (constant_pool[2])",
+            err.to_string()
+        );
+        assert!(err.source().is_some());
     }
 }
@@ -3,23 +3,104 @@ use std::{
     fmt::{Debug, Display, Formatter},
 };
 
-use crate::{buffer::BufferError, constant_pool::InvalidConstantPoolIndexError};
+pub use crate::buffer::BufferError;
+use crate::{constant_pool::InvalidConstantPoolIndexError, read_limits::ReadLimit};
 
 /// Models the possible errors returned when reading a .class file
 #[derive(Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ClassReaderError {
     /// Generic error meaning that the class file is invalid
     InvalidClassData(String, Option<InvalidConstantPoolIndexError>),
     UnsupportedVersion(u16, u16),
     /// Error while parsing a given type descriptor in the file
     InvalidTypeDescriptor(String),
+    /// The class file is truncated, or contains a malformed string
+    MalformedData(BufferError),
+    /// The class exceeds one of the [crate::read_limits::ReadLimits], whose value is given
+    LimitExceeded(ReadLimit, usize),
+    /// An error, along with the location in the class file where it happened
+    Located {
+        error: Box<ClassReaderError>,
+        /// Offset of the byte being read when the error happened
+        offset: usize,
+        /// Path of the element being read, i.e. `methods[3] "foo" > Code > exception_table[1]`
+        path: String,
+    },
 }
 
 impl ClassReaderError {
     pub fn invalid_class_data(message: String) -> Self {
         ClassReaderError::InvalidClassData(message, None)
     }
+
+    /// Adds the location to the error, unless it already has one
+    pub(crate) fn located(self, offset: usize, path: String) -> Self {
+        match self {
+            ClassReaderError::Located { .. } => self,
+            error => ClassReaderError::Located {
+                error: Box::new(error),
+                offset,
+                path,
+            },
+        }
+    }
+
+    /// The error, without its location
+    pub fn kind(&self) -> &ClassReaderError {
+        match self {
+            ClassReaderError::Located { error, .. } => error.kind(),
+            _ => self,
+        }
+    }
+
+    /// The offset in the class file of the byte being read when the error happened
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ClassReaderError::Located { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// The path of the element being read when the error happened
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            ClassReaderError::Located { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// A stable identifier of the kind of error, that will not change across versions
+    pub fn code(&self) -> &'static str {
+        match self {
+            ClassReaderError::InvalidClassData(_, None) => "invalid-class-data",
+            ClassReaderError::InvalidClassData(_, Some(_)) => "invalid-constant-pool-index",
+            ClassReaderError::UnsupportedVersion(_, _) => "unsupported-version",
+            ClassReaderError::InvalidTypeDescriptor(_) => "invalid-type-descriptor",
+            ClassReaderError::MalformedData(BufferError::UnexpectedEndOfData) => {
+                "unexpected-end-of-data"
+            }
+            ClassReaderError::MalformedData(BufferError::InvalidCesu8String) => {
+                "invalid-cesu8-string"
+            }
+            ClassReaderError::LimitExceeded(ReadLimit::Allocation, _) => {
+                "allocation-limit-exceeded"
+            }
+            ClassReaderError::LimitExceeded(ReadLimit::AttributeLength, _) => {
+                "attribute-length-limit-exceeded"
+            }
+            ClassReaderError::LimitExceeded(ReadLimit::CodeLength, _) => {
+                "code-length-limit-exceeded"
+            }
+            ClassReaderError::LimitExceeded(ReadLimit::DescriptorDepth, _) => {
+                "descriptor-depth-limit-exceeded"
+            }
+            ClassReaderError::LimitExceeded(ReadLimit::AnnotationDepth, _) => {
+                "annotation-depth-limit-exceeded"
+            }
+            ClassReaderError::Located { error, .. } => error.code(),
+        }
+    }
 }
 
 impl Display for ClassReaderError {
@@ -34,6 +115,23 @@ impl Display for ClassReaderError {
             ClassReaderError::InvalidTypeDescriptor(descriptor) => {
                 write!(f, "invalid type descriptor: {descriptor}")
             }
+            ClassReaderError::MalformedData(err) => {
+                write!(f, "invalid class file: {err}")
+            }
+            ClassReaderError::LimitExceeded(limit, max) => {
+                write!(f, "{limit} exceeds the limit of {max}")
+            }
+            ClassReaderError::Located {
+                error,
+                offset,
+                path,
+            } => {
+                write!(f, "{error} at offset {offset:#x}")?;
+                if !path.is_empty() {
+                    write!(f, " ({path})")?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -42,6 +140,8 @@ impl Error for ClassReaderError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             ClassReaderError::InvalidClassData(_, Some(source)) => Some(source),
+            ClassReaderError::MalformedData(source) => Some(source),
+            ClassReaderError::Located { error, .. } => error.source(),
             _ => None,
         }
     }
@@ -57,13 +157,6 @@ impl From<InvalidConstantPoolIndexError> for ClassReaderError {
 
 impl From<BufferError> for ClassReaderError {
     fn from(err: BufferError) -> Self {
-        match err {
-            BufferError::UnexpectedEndOfData => {
-                Self::invalid_class_data("unexpected end of class file".to_string())
-            }
-            BufferError::InvalidCesu8String => {
-                Self::invalid_class_data("invalid cesu8 string".to_string())
-            }
-        }
+        Self::MalformedData(err)
     }
 }
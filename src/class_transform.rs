@@ -0,0 +1,551 @@
+use crate::{
+    class_builder::{ClassBuilder, ClassElement, MethodBuilder, MethodElement},
+    class_file::ClassFile,
+    class_file_method::ClassFileMethod,
+    class_hierarchy::{ClassHierarchy, ObjectClassHierarchy},
+    class_writer_error::Result,
+    code_builder::{CodeBuilder, CodeElement},
+};
+
+/// A transformation of a class. Each element of the class is passed to [ClassTransform::accept],
+/// which decides what to emit in the builder of the new class: the element itself (keep),
+/// nothing (drop), something else (replace) or additional elements (inject).
+pub trait ClassTransform {
+    /// Handles an element of the class. By default, the element is kept as is.
+    fn accept(&mut self, builder: &mut ClassBuilder, element: ClassElement) {
+        builder.with(element);
+    }
+
+    /// Invoked after all the elements have been passed, to add new elements at the end
+    fn at_end(&mut self, _builder: &mut ClassBuilder) {}
+
+    /// Returns a transform that feeds the output of this transform to the given one
+    fn and_then<T: ClassTransform>(self, next: T) -> Chained<Self, T>
+    where
+        Self: Sized,
+    {
+        Chained { first: self, next }
+    }
+}
+
+/// A transformation of a method, analogous to [ClassTransform]
+pub trait MethodTransform {
+    /// Handles an element of the method. By default, the element is kept as is.
+    fn accept(&mut self, builder: &mut MethodBuilder, element: MethodElement) {
+        builder.with(element);
+    }
+
+    /// Invoked after all the elements of a method have been passed
+    fn at_end(&mut self, _builder: &mut MethodBuilder) {}
+
+    /// Returns a transform that feeds the output of this transform to the given one
+    fn and_then<T: MethodTransform>(self, next: T) -> Chained<Self, T>
+    where
+        Self: Sized,
+    {
+        Chained { first: self, next }
+    }
+}
+
+/// A transformation of the code of a method, analogous to [ClassTransform]. Jumps, switches
+/// and try/catch blocks refer to [crate::code_builder::Label], so instructions can be freely
+/// added or removed without fixing the addresses.
+pub trait CodeTransform {
+    /// Handles an element of the code. By default, the element is kept as is.
+    fn accept(&mut self, builder: &mut CodeBuilder, element: CodeElement) {
+        builder.with(element);
+    }
+
+    /// Invoked after all the elements of the code have been passed
+    fn at_end(&mut self, _builder: &mut CodeBuilder) {}
+
+    /// Returns a transform that feeds the output of this transform to the given one
+    fn and_then<T: CodeTransform>(self, next: T) -> Chained<Self, T>
+    where
+        Self: Sized,
+    {
+        Chained { first: self, next }
+    }
+}
+
+impl<F: FnMut(&mut ClassBuilder, ClassElement)> ClassTransform for F {
+    fn accept(&mut self, builder: &mut ClassBuilder, element: ClassElement) {
+        self(builder, element)
+    }
+}
+
+impl<F: FnMut(&mut MethodBuilder, MethodElement)> MethodTransform for F {
+    fn accept(&mut self, builder: &mut MethodBuilder, element: MethodElement) {
+        self(builder, element)
+    }
+}
+
+impl<F: FnMut(&mut CodeBuilder, CodeElement)> CodeTransform for F {
+    fn accept(&mut self, builder: &mut CodeBuilder, element: CodeElement) {
+        self(builder, element)
+    }
+}
+
+/// Two transforms applied one after the other, created via `and_then`
+#[derive(Debug)]
+pub struct Chained<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A: ClassTransform, B: ClassTransform> ClassTransform for Chained<A, B> {
+    fn accept(&mut self, builder: &mut ClassBuilder, element: ClassElement) {
+        let mut fork = builder.fork();
+        self.first.accept(&mut fork, element);
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+    }
+
+    fn at_end(&mut self, builder: &mut ClassBuilder) {
+        let mut fork = builder.fork();
+        self.first.at_end(&mut fork);
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+        self.next.at_end(builder);
+    }
+}
+
+impl<A: MethodTransform, B: MethodTransform> MethodTransform for Chained<A, B> {
+    fn accept(&mut self, builder: &mut MethodBuilder, element: MethodElement) {
+        let mut fork = builder.fork();
+        self.first.accept(&mut fork, element);
+        let fork = fork.into_parts();
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+    }
+
+    fn at_end(&mut self, builder: &mut MethodBuilder) {
+        let mut fork = builder.fork();
+        self.first.at_end(&mut fork);
+        let fork = fork.into_parts();
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+        self.next.at_end(builder);
+    }
+}
+
+impl<A: CodeTransform, B: CodeTransform> CodeTransform for Chained<A, B> {
+    fn accept(&mut self, builder: &mut CodeBuilder, element: CodeElement) {
+        let mut fork = builder.fork();
+        self.first.accept(&mut fork, element);
+        let fork = fork.into_parts();
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+    }
+
+    fn at_end(&mut self, builder: &mut CodeBuilder) {
+        let mut fork = builder.fork();
+        self.first.at_end(&mut fork);
+        let fork = fork.into_parts();
+        for element in builder.join(fork) {
+            self.next.accept(builder, element);
+        }
+        self.next.at_end(builder);
+    }
+}
+
+/// A [ClassTransform] that applies a [MethodTransform] to the methods of the class.
+/// Methods excluded by the filter are moved to the new class without being inspected.
+pub struct TransformingMethods<T> {
+    transform: T,
+    filter: Box<dyn Fn(&ClassFileMethod) -> bool>,
+}
+
+impl<T: MethodTransform> TransformingMethods<T> {
+    /// Applies the transform to all the methods
+    pub fn new(transform: T) -> Self {
+        Self {
+            transform,
+            filter: Box::new(|_| true),
+        }
+    }
+
+    /// Restricts the transform to the methods matching the given filter
+    pub fn only(mut self, filter: impl Fn(&ClassFileMethod) -> bool + 'static) -> Self {
+        self.filter = Box::new(filter);
+        self
+    }
+}
+
+impl<T: MethodTransform> ClassTransform for TransformingMethods<T> {
+    fn accept(&mut self, builder: &mut ClassBuilder, element: ClassElement) {
+        match element {
+            ClassElement::Method(method) if (self.filter)(&method) => {
+                let flags = method.flags;
+                let name = method.name.clone();
+                let descriptor = method.parsed_type_descriptor.clone();
+                builder.method_with(flags, &name, descriptor, |builder| {
+                    for element in MethodElement::split(method) {
+                        self.transform.accept(builder, element);
+                    }
+                    self.transform.at_end(builder);
+                });
+            }
+            element => {
+                builder.with(element);
+            }
+        }
+    }
+}
+
+/// A [MethodTransform] that applies a [CodeTransform] to the code of the method.
+/// If the transform emits exactly the elements it received, the original code is kept,
+/// including its stack map frames and its other attributes. Otherwise, the code is rebuilt:
+/// attributes such as `LocalVariableTable` are dropped, and the stack map frames are
+/// recomputed when the class is built.
+pub struct TransformingCode<T> {
+    transform: T,
+}
+
+impl<T: CodeTransform> TransformingCode<T> {
+    pub fn new(transform: T) -> Self {
+        Self { transform }
+    }
+}
+
+impl<T: CodeTransform> MethodTransform for TransformingCode<T> {
+    fn accept(&mut self, builder: &mut MethodBuilder, element: MethodElement) {
+        let MethodElement::Code(code) = element else {
+            builder.with(element);
+            return;
+        };
+
+        let mut code_builder = builder.code_builder();
+        let elements = match code_builder.read_elements(&code) {
+            Ok(elements) => elements,
+            Err(error) => {
+                builder.fail(error);
+                return;
+            }
+        };
+        // The code is unchanged as long as the transform emits each element it receives, and
+        // nothing else
+        let mut unchanged = true;
+        for element in elements {
+            if unchanged {
+                let emitted = code_builder.elements().len();
+                self.transform.accept(&mut code_builder, element.clone());
+                unchanged = code_builder.elements()[emitted..] == [element];
+            } else {
+                self.transform.accept(&mut code_builder, element);
+            }
+        }
+        let emitted = code_builder.elements().len();
+        self.transform.at_end(&mut code_builder);
+        unchanged &= code_builder.elements().len() == emitted;
+
+        if unchanged {
+            builder.with(MethodElement::Code(code));
+        } else {
+            match code_builder.build() {
+                Ok(code) => builder.rebuilt_code(code),
+                Err(error) => {
+                    builder.fail(error);
+                    builder
+                }
+            };
+        }
+    }
+}
+
+/// Transforms a class, computing the stack map frames of the modified methods assuming that
+/// the common superclass of two different classes is `java/lang/Object`.
+/// The constant pool of the class is shared by all the transforms, so the existing constants
+/// keep their index.
+pub fn transform_class(class: ClassFile, transform: &mut impl ClassTransform) -> Result<ClassFile> {
+    transform_class_with_hierarchy(class, transform, &ObjectClassHierarchy)
+}
+
+/// Transforms a class, using the given hierarchy to compute the stack map frames
+pub fn transform_class_with_hierarchy(
+    class: ClassFile,
+    transform: &mut impl ClassTransform,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<ClassFile> {
+    let (header, elements) = ClassElement::split(class);
+    let mut builder = ClassBuilder::for_transform(header);
+    for element in elements {
+        transform.accept(&mut builder, element);
+    }
+    transform.at_end(&mut builder);
+    builder.build_with_hierarchy(hierarchy)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_builder::{ClassBuilder, ClassElement, MethodBuilder, MethodElement},
+        class_file::ClassFile,
+        class_transform::{
+            transform_class, ClassTransform, CodeTransform, MethodTransform, TransformingCode,
+            TransformingMethods,
+        },
+        class_writer::write_class,
+        code_builder::{CodeBuilder, CodeElement, JumpKind, LocalKind},
+        field_flags::FieldFlags,
+        field_type::{BaseType, FieldType},
+        instruction::Instruction,
+        method_descriptor::MethodDescriptor,
+        method_flags::MethodFlags,
+    };
+
+    fn sample_class() -> ClassFile {
+        let mut builder = ClassBuilder::new("a/Sample");
+        builder
+            .method(
+                MethodFlags::PUBLIC | MethodFlags::STATIC,
+                "abs",
+                MethodDescriptor::parse("(I)I").unwrap(),
+                |code| {
+                    let positive = code.new_label();
+                    code.load(LocalKind::Int, 0)
+                        .jump(JumpKind::Ifge, positive)
+                        .load(LocalKind::Int, 0)
+                        .instruction(Instruction::Ineg)
+                        .instruction(Instruction::Ireturn)
+                        .place_label(positive)
+                        .load(LocalKind::Int, 0)
+                        .instruction(Instruction::Ireturn);
+                },
+            )
+            .method(
+                MethodFlags::PUBLIC | MethodFlags::STATIC,
+                "one",
+                MethodDescriptor::parse("()I").unwrap(),
+                |code| {
+                    code.iconst(1).instruction(Instruction::Ireturn);
+                },
+            );
+        builder.build().unwrap()
+    }
+
+    fn method_names(class: &ClassFile) -> Vec<&str> {
+        class
+            .methods
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn identity_transform_keeps_class_unchanged() {
+        let class = sample_class();
+        let transformed = transform_class(
+            class.clone(),
+            &mut TransformingMethods::new(TransformingCode::new(
+                |code: &mut CodeBuilder, element: CodeElement| {
+                    code.with(element);
+                },
+            )),
+        )
+        .unwrap();
+        assert_eq!(
+            write_class(&class).unwrap(),
+            write_class(&transformed).unwrap()
+        );
+    }
+
+    #[test]
+    fn can_drop_methods_and_inject_fields() {
+        struct DropOneAddField;
+        impl ClassTransform for DropOneAddField {
+            fn accept(&mut self, builder: &mut ClassBuilder, element: ClassElement) {
+                if !matches!(&element, ClassElement::Method(method) if method.name == "one") {
+                    builder.with(element);
+                }
+            }
+
+            fn at_end(&mut self, builder: &mut ClassBuilder) {
+                builder.field(FieldFlags::PRIVATE, "added", FieldType::Base(BaseType::Int));
+            }
+        }
+
+        let transformed = transform_class(sample_class(), &mut DropOneAddField).unwrap();
+        assert_eq!(vec!["abs"], method_names(&transformed));
+        assert_eq!(1, transformed.fields.len());
+        assert_eq!("added", transformed.fields[0].name);
+    }
+
+    #[test]
+    fn can_replace_instructions_and_recompute_frames() {
+        let original = sample_class();
+        let mut transform = TransformingMethods::new(TransformingCode::new(
+            |code: &mut CodeBuilder, element: CodeElement| match element {
+                CodeElement::Instruction(Instruction::Ineg) => {
+                    code.iconst(0)
+                        .instruction(Instruction::Swap)
+                        .instruction(Instruction::Isub);
+                }
+                element => {
+                    code.with(element);
+                }
+            },
+        ))
+        .only(|method| method.name == "abs");
+        let transformed = transform_class(original.clone(), &mut transform).unwrap();
+
+        assert_eq!(original.methods[1], transformed.methods[1]);
+        let code = transformed.methods[0].code.as_ref().unwrap();
+        assert_eq!(
+            vec![
+                Instruction::Iload_0,
+                Instruction::Ifge { jump_address: 9 },
+                Instruction::Iload_0,
+                Instruction::Iconst_0,
+                Instruction::Swap,
+                Instruction::Isub,
+                Instruction::Ireturn,
+                Instruction::Iload_0,
+                Instruction::Ireturn,
+            ],
+            Instruction::parse_instructions(&code.code)
+                .unwrap()
+                .into_iter()
+                .map(|(_, instruction)| instruction)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, code.max_stack);
+        assert!(code
+            .attributes
+            .iter()
+            .any(|attribute| attribute.name == "StackMapTable"));
+    }
+
+    #[test]
+    fn keeps_unchanged_code_without_frames_as_is() {
+        let mut original = sample_class();
+        for method in original.methods.iter_mut() {
+            let code = method.code.as_mut().unwrap();
+            code.attributes
+                .retain(|attribute| attribute.name != "StackMapTable");
+        }
+        let mut transform = TransformingMethods::new(TransformingCode::new(
+            |code: &mut CodeBuilder, element: CodeElement| match element {
+                CodeElement::Instruction(Instruction::Iconst_1) => {
+                    code.iconst(2);
+                }
+                element => {
+                    code.with(element);
+                }
+            },
+        ));
+        let transformed = transform_class(original.clone(), &mut transform).unwrap();
+
+        assert_eq!(original.methods[0], transformed.methods[0]);
+        assert_ne!(original.methods[1], transformed.methods[1]);
+    }
+
+    #[test]
+    fn computes_frames_of_rebuilt_code_only() {
+        let mut original = sample_class();
+        original.methods[0]
+            .code
+            .as_mut()
+            .unwrap()
+            .attributes
+            .retain(|attribute| attribute.name != "StackMapTable");
+        let mut transform = TransformingMethods::new(TransformingCode::new(
+            |code: &mut CodeBuilder, element: CodeElement| match element {
+                CodeElement::Instruction(Instruction::Ineg) => {
+                    code.iconst(-1).instruction(Instruction::Imul);
+                }
+                element => {
+                    code.with(element);
+                }
+            },
+        ));
+        let transformed = transform_class(original, &mut transform).unwrap();
+
+        let code = transformed.methods[0].code.as_ref().unwrap();
+        assert!(code
+            .attributes
+            .iter()
+            .any(|attribute| attribute.name == "StackMapTable"));
+    }
+
+    #[test]
+    fn computes_frames_of_code_rebuilt_by_a_previous_transform() {
+        let mut original = sample_class();
+        original.methods[0]
+            .code
+            .as_mut()
+            .unwrap()
+            .attributes
+            .retain(|attribute| attribute.name != "StackMapTable");
+        let mut transform = TransformingMethods::new(TransformingCode::new(
+            |code: &mut CodeBuilder, element: CodeElement| match element {
+                CodeElement::Instruction(Instruction::Ineg) => {
+                    code.iconst(-1).instruction(Instruction::Imul);
+                }
+                element => {
+                    code.with(element);
+                }
+            },
+        ))
+        .and_then(|builder: &mut ClassBuilder, element: ClassElement| {
+            builder.with(element);
+        });
+        let transformed = transform_class(original, &mut transform).unwrap();
+
+        let code = transformed.methods[0].code.as_ref().unwrap();
+        assert!(code
+            .attributes
+            .iter()
+            .any(|attribute| attribute.name == "StackMapTable"));
+    }
+
+    #[test]
+    fn chained_transforms_see_the_output_of_the_previous_one() {
+        let replace_one = |code: &mut CodeBuilder, element: CodeElement| match element {
+            CodeElement::Instruction(Instruction::Iconst_1) => {
+                code.iconst(2);
+            }
+            element => {
+                code.with(element);
+            }
+        };
+        let double_two = |code: &mut CodeBuilder, element: CodeElement| match element {
+            CodeElement::Instruction(Instruction::Iconst_2) => {
+                code.iconst(2).iconst(2).instruction(Instruction::Imul);
+            }
+            element => {
+                code.with(element);
+            }
+        };
+        let deprecate = |method: &mut MethodBuilder, element: MethodElement| {
+            method.with(element).deprecated();
+        };
+        let mut transform = TransformingMethods::new(
+            TransformingCode::new(replace_one.and_then(double_two)).and_then(deprecate),
+        )
+        .only(|method| method.name == "one");
+        let transformed = transform_class(sample_class(), &mut transform).unwrap();
+
+        let method = &transformed.methods[1];
+        assert!(method.deprecated);
+        let code = method.code.as_ref().unwrap();
+        assert_eq!(
+            vec![
+                Instruction::Iconst_2,
+                Instruction::Iconst_2,
+                Instruction::Imul,
+                Instruction::Ireturn,
+            ],
+            Instruction::parse_instructions(&code.code)
+                .unwrap()
+                .into_iter()
+                .map(|(_, instruction)| instruction)
+                .collect::<Vec<_>>()
+        );
+    }
+}
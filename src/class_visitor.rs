@@ -0,0 +1,95 @@
+use crate::{
+    class_access_flags::ClassAccessFlags, class_file_version::ClassFileVersion,
+    constant_pool::ConstantPool, exception_table::ExceptionTableEntry, field_flags::FieldFlags,
+    field_type::FieldType, instruction::Instruction, method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags, program_counter::ProgramCounter,
+};
+
+/// Returned by the callbacks of a [ClassVisitor] to decide whether the content of the
+/// element just visited should be read or skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    Skip,
+}
+
+/// The part of a class that precedes fields and methods
+#[derive(Debug)]
+pub struct ClassHeader<'a> {
+    pub version: ClassFileVersion,
+    pub constants: &'a ConstantPool,
+    pub flags: ClassAccessFlags,
+    pub name: &'a str,
+    pub superclass: Option<&'a str>,
+    pub interfaces: &'a [String],
+}
+
+/// The element an attribute is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeTarget {
+    Class,
+    Field,
+    Method,
+    Code,
+}
+
+/// Receives the content of a class while it is being read by [crate::visit_buffer], in the
+/// order in which it appears in the .class file. Nothing is kept in memory after the
+/// callback returns, except for the constant pool, so large amounts of classes can be
+/// scanned cheaply.
+///
+/// All callbacks have an empty default implementation, so that visitors need to implement
+/// only the ones they are interested in.
+pub trait ClassVisitor {
+    /// Invoked after the constant pool and the interfaces have been read.
+    /// Returning [Visit::Skip] stops the reading of the class.
+    fn visit_header(&mut self, _header: &ClassHeader) -> Visit {
+        Visit::Continue
+    }
+
+    /// Returning [Visit::Skip] skips the attributes of the field
+    fn visit_field(
+        &mut self,
+        _flags: FieldFlags,
+        _name: &str,
+        _type_descriptor: &FieldType,
+    ) -> Visit {
+        Visit::Continue
+    }
+
+    /// Returning [Visit::Skip] skips the attributes of the method, including its code
+    fn visit_method(
+        &mut self,
+        _flags: MethodFlags,
+        _name: &str,
+        _type_descriptor: &MethodDescriptor,
+    ) -> Visit {
+        Visit::Continue
+    }
+
+    /// Invoked when the `Code` attribute of the current method is found, in place of
+    /// [ClassVisitor::visit_attribute]. Returning [Visit::Skip] skips the instructions,
+    /// the exception handlers and the attributes of the code.
+    fn visit_code(&mut self, _max_stack: u16, _max_locals: u16) -> Visit {
+        Visit::Continue
+    }
+
+    fn visit_code_instruction(&mut self, _address: ProgramCounter, _instruction: &Instruction) {}
+
+    fn visit_exception_handler(&mut self, _entry: &ExceptionTableEntry) {}
+
+    /// Invoked for every attribute of the class, its fields, methods and code, with the
+    /// exception of `Code`. The content can be decoded using the constant pool, i.e. via
+    /// [crate::annotation::Annotation::decode_attribute].
+    fn visit_attribute(
+        &mut self,
+        _target: AttributeTarget,
+        _name: &str,
+        _bytes: &[u8],
+        _constants: &ConstantPool,
+    ) {
+    }
+
+    /// Invoked after the whole class has been read
+    fn visit_end(&mut self) {}
+}
@@ -0,0 +1,339 @@
+use crate::{
+    attribute::Attribute,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    class_writer_error::{ClassWriterError, Result},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    line_number_table::LineNumberTable,
+};
+
+/// A writer that serializes a [ClassFile] in the .class format. The constants of the class are
+/// kept at their original index, since the bytecode and the raw attributes refer to them; the
+/// constants needed by the attributes generated from the model are appended if missing.
+struct ClassFileWriter<'a> {
+    class_file: &'a ClassFile,
+    constants: ConstantPool,
+    /// Everything that follows the constant pool, which can only be written once all the
+    /// other parts of the class have been serialized
+    body: Vec<u8>,
+}
+
+/// Reference: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html
+impl<'a> ClassFileWriter<'a> {
+    fn new(class_file: &'a ClassFile) -> ClassFileWriter<'a> {
+        ClassFileWriter {
+            class_file,
+            constants: class_file.constants.clone(),
+            body: Vec::new(),
+        }
+    }
+
+    fn write(mut self) -> Result<Vec<u8>> {
+        self.write_u16(self.class_file.flags.bits());
+        let name = self
+            .constants
+            .intern_class_reference(&self.class_file.name)?;
+        self.write_u16(name);
+        let superclass = match &self.class_file.superclass {
+            Some(superclass) => self.constants.intern_class_reference(superclass)?,
+            None => 0,
+        };
+        self.write_u16(superclass);
+        self.write_interfaces()?;
+        self.write_fields()?;
+        self.write_methods()?;
+        self.write_class_attributes()?;
+
+        let mut bytes = Vec::with_capacity(self.body.len() + 1024);
+        bytes.extend_from_slice(&0xCAFEBABEu32.to_be_bytes());
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&self.class_file.version.major().to_be_bytes());
+        self.constants.encode(&mut bytes)?;
+        bytes.extend_from_slice(&self.body);
+        Ok(bytes)
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.body.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_interfaces(&mut self) -> Result<()> {
+        write_length(&mut self.body, self.class_file.interfaces.len())?;
+        for interface in self.class_file.interfaces.iter() {
+            let index = self.constants.intern_class_reference(interface)?;
+            self.write_u16(index);
+        }
+        Ok(())
+    }
+
+    fn write_fields(&mut self) -> Result<()> {
+        write_length(&mut self.body, self.class_file.fields.len())?;
+        for field in self.class_file.fields.iter() {
+            self.write_field(field)?;
+        }
+        Ok(())
+    }
+
+    fn write_field(&mut self, field: &ClassFileField) -> Result<()> {
+        self.write_u16(field.flags.bits());
+        let name = self.constants.intern_utf8(&field.name)?;
+        self.write_u16(name);
+        let type_descriptor = self
+            .constants
+            .intern_utf8(&field.type_descriptor.descriptor())?;
+        self.write_u16(type_descriptor);
+
+        let mut attributes = Vec::new();
+        if let Some(constant_value) = &field.constant_value {
+            let index = match constant_value {
+                FieldConstantValue::Int(value) => {
+                    self.constants.intern(ConstantPoolEntry::Integer(*value))?
+                }
+                FieldConstantValue::Float(value) => {
+                    self.constants.intern(ConstantPoolEntry::Float(*value))?
+                }
+                FieldConstantValue::Long(value) => {
+                    self.constants.intern(ConstantPoolEntry::Long(*value))?
+                }
+                FieldConstantValue::Double(value) => {
+                    self.constants.intern(ConstantPoolEntry::Double(*value))?
+                }
+                FieldConstantValue::String(value) => {
+                    self.constants.intern_string_reference(value)?
+                }
+            };
+            attributes.push(Attribute {
+                name: "ConstantValue".to_string(),
+                bytes: index.to_be_bytes().to_vec(),
+            });
+        }
+        if field.deprecated {
+            attributes.push(deprecated_attribute());
+        }
+        self.write_attributes(
+            &attributes,
+            &field.attributes,
+            &["ConstantValue", "Deprecated"],
+        )
+    }
+
+    fn write_methods(&mut self) -> Result<()> {
+        write_length(&mut self.body, self.class_file.methods.len())?;
+        for method in self.class_file.methods.iter() {
+            self.write_method(method)?;
+        }
+        Ok(())
+    }
+
+    fn write_method(&mut self, method: &ClassFileMethod) -> Result<()> {
+        self.write_u16(method.flags.bits());
+        let name = self.constants.intern_utf8(&method.name)?;
+        self.write_u16(name);
+        let type_descriptor = self.constants.intern_utf8(&method.type_descriptor)?;
+        self.write_u16(type_descriptor);
+
+        let mut attributes = Vec::new();
+        if let Some(code) = &method.code {
+            attributes.push(Attribute {
+                name: "Code".to_string(),
+                bytes: self.encode_code(code)?,
+            });
+        }
+        if !method.thrown_exceptions.is_empty() {
+            let mut bytes = Vec::new();
+            write_length(&mut bytes, method.thrown_exceptions.len())?;
+            for exception in method.thrown_exceptions.iter() {
+                let index = self.constants.intern_class_reference(exception)?;
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            attributes.push(Attribute {
+                name: "Exceptions".to_string(),
+                bytes,
+            });
+        }
+        if method.deprecated {
+            attributes.push(deprecated_attribute());
+        }
+        self.write_attributes(
+            &attributes,
+            &method.attributes,
+            &["Code", "Exceptions", "Deprecated"],
+        )
+    }
+
+    fn encode_code(&mut self, code: &ClassFileMethodCode) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(code.code.len() + 64);
+        bytes.extend_from_slice(&code.max_stack.to_be_bytes());
+        bytes.extend_from_slice(&code.max_locals.to_be_bytes());
+        let code_length = u32::try_from(code.code.len())
+            .map_err(|_| ClassWriterError::CodeTooLarge(code.code.len()))?;
+        bytes.extend_from_slice(&code_length.to_be_bytes());
+        bytes.extend_from_slice(&code.code);
+
+        let entries = code.exception_table.entries();
+        write_length(&mut bytes, entries.len())?;
+        for entry in entries {
+            bytes.extend_from_slice(&entry.range.start.0.to_be_bytes());
+            bytes.extend_from_slice(&entry.range.end.0.to_be_bytes());
+            bytes.extend_from_slice(&entry.handler_pc.0.to_be_bytes());
+            let catch_class = match &entry.catch_class {
+                Some(catch_class) => self.constants.intern_class_reference(catch_class)?,
+                None => 0,
+            };
+            bytes.extend_from_slice(&catch_class.to_be_bytes());
+        }
+
+        let mut attributes = Vec::new();
+        if let Some(line_number_table) = &code.line_number_table {
+            attributes.push(Self::line_number_table_attribute(line_number_table)?);
+        }
+        encode_attributes(
+            &mut bytes,
+            &mut self.constants,
+            &attributes,
+            &code.attributes,
+            &["LineNumberTable"],
+        )?;
+        Ok(bytes)
+    }
+
+    fn line_number_table_attribute(line_number_table: &LineNumberTable) -> Result<Attribute> {
+        let entries = line_number_table.entries();
+        let mut bytes = Vec::with_capacity(2 + entries.len() * 4);
+        write_length(&mut bytes, entries.len())?;
+        for entry in entries {
+            bytes.extend_from_slice(&entry.program_counter.0.to_be_bytes());
+            bytes.extend_from_slice(&entry.line_number.0.to_be_bytes());
+        }
+        Ok(Attribute {
+            name: "LineNumberTable".to_string(),
+            bytes,
+        })
+    }
+
+    fn write_class_attributes(&mut self) -> Result<()> {
+        let mut attributes = Vec::new();
+        if let Some(source_file) = &self.class_file.source_file {
+            let index = self.constants.intern_utf8(source_file)?;
+            attributes.push(Attribute {
+                name: "SourceFile".to_string(),
+                bytes: index.to_be_bytes().to_vec(),
+            });
+        }
+        if self.class_file.deprecated {
+            attributes.push(deprecated_attribute());
+        }
+        self.write_attributes(
+            &attributes,
+            &self.class_file.attributes,
+            &["SourceFile", "Deprecated"],
+        )
+    }
+
+    fn write_attributes(
+        &mut self,
+        mapped_attributes: &[Attribute],
+        raw_attributes: &[Attribute],
+        mapped_names: &[&str],
+    ) -> Result<()> {
+        encode_attributes(
+            &mut self.body,
+            &mut self.constants,
+            mapped_attributes,
+            raw_attributes,
+            mapped_names,
+        )
+    }
+}
+
+fn deprecated_attribute() -> Attribute {
+    Attribute {
+        name: "Deprecated".to_string(),
+        bytes: Vec::new(),
+    }
+}
+
+fn write_length(bytes: &mut Vec<u8>, length: usize) -> Result<()> {
+    let length = u16::try_from(length).map_err(|_| ClassWriterError::TooManyEntries(length))?;
+    bytes.extend_from_slice(&length.to_be_bytes());
+    Ok(())
+}
+
+/// Writes the attributes generated from the model, followed by the raw attributes. Raw
+/// attributes that are also generated from the model are skipped, since the model wins.
+fn encode_attributes(
+    bytes: &mut Vec<u8>,
+    constants: &mut ConstantPool,
+    mapped_attributes: &[Attribute],
+    raw_attributes: &[Attribute],
+    mapped_names: &[&str],
+) -> Result<()> {
+    let attributes: Vec<&Attribute> = mapped_attributes
+        .iter()
+        .chain(
+            raw_attributes
+                .iter()
+                .filter(|attribute| !mapped_names.contains(&attribute.name.as_str())),
+        )
+        .collect();
+    write_length(bytes, attributes.len())?;
+    for attribute in attributes {
+        let name = constants.intern_utf8(&attribute.name)?;
+        bytes.extend_from_slice(&name.to_be_bytes());
+        let length = u32::try_from(attribute.bytes.len())
+            .map_err(|_| ClassWriterError::TooManyEntries(attribute.bytes.len()))?;
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(&attribute.bytes);
+    }
+    Ok(())
+}
+
+/// Serializes a class in the .class file format.
+pub fn write_class(class_file: &ClassFile) -> Result<Vec<u8>> {
+    ClassFileWriter::new(class_file).write()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        attribute::Attribute,
+        class_file::ClassFile,
+        class_file_field::{ClassFileField, FieldConstantValue},
+        class_reader::read_buffer,
+        class_writer::write_class,
+        field_flags::FieldFlags,
+        field_type::{BaseType, FieldType},
+    };
+
+    #[test]
+    fn can_write_and_read_back_class() {
+        let class = ClassFile {
+            name: "a/Test".to_string(),
+            superclass: Some("java/lang/Object".to_string()),
+            interfaces: vec!["java/io/Serializable".to_string()],
+            fields: vec![ClassFileField {
+                flags: FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
+                name: "ANSWER".to_string(),
+                type_descriptor: FieldType::Base(BaseType::Long),
+                constant_value: Some(FieldConstantValue::Long(42)),
+                deprecated: true,
+                attributes: vec![],
+            }],
+            source_file: Some("Test.java".to_string()),
+            attributes: vec![Attribute {
+                name: "Custom".to_string(),
+                bytes: vec![1, 2, 3],
+            }],
+            ..Default::default()
+        };
+
+        let read = read_buffer(&write_class(&class).unwrap()).unwrap();
+        assert_eq!(class.name, read.name);
+        assert_eq!(class.superclass, read.superclass);
+        assert_eq!(class.interfaces, read.interfaces);
+        assert_eq!(class.fields, read.fields);
+        assert_eq!(class.source_file, read.source_file);
+        assert_eq!(class.attributes, read.attributes);
+    }
+}
@@ -0,0 +1,58 @@
+use thiserror::Error;
+
+use crate::class_reader_error::ClassReaderError;
+
+/// Models the possible errors returned when generating bytecode or a .class file
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ClassWriterError {
+    #[error("jump offset out of range for instruction at address {0}")]
+    JumpOffsetOutOfRange(usize),
+
+    #[error("code too large: {0} bytes")]
+    CodeTooLarge(usize),
+
+    #[error("label {0} is used but was never placed")]
+    UnplacedLabel(usize),
+
+    #[error("label {0} was placed more than once")]
+    LabelPlacedTwice(usize),
+
+    #[error("operand stack underflow at address {0}")]
+    StackUnderflow(usize),
+
+    #[error("inconsistent operand stack height at address {0}")]
+    InconsistentStackHeight(usize),
+
+    #[error("jump to address {0}, which is not the start of an instruction")]
+    InvalidJumpTarget(usize),
+
+    #[error("execution falls off the end of the code")]
+    FallsOffEndOfCode,
+
+    #[error("subroutines (jsr/ret) are not supported, found at address {0}")]
+    SubroutinesNotSupported(usize),
+
+    #[error("unreachable code at address {0}")]
+    UnreachableCode(usize),
+
+    #[error("key range of the tableswitch at address {0} does not match its targets")]
+    InvalidSwitchRange(usize),
+
+    #[error("too many argument slots for invokeinterface: {0}")]
+    TooManyArgumentSlots(u16),
+
+    #[error("too many constants in the constant pool: {0}")]
+    TooManyConstants(usize),
+
+    #[error("string constant too long: {0} bytes")]
+    StringTooLong(usize),
+
+    #[error("too many entries in a table of the class file: {0}")]
+    TooManyEntries(usize),
+
+    /// Error while resolving the constants or the types referred to by the code
+    #[error(transparent)]
+    InvalidClassData(#[from] ClassReaderError),
+}
+
+pub type Result<T> = std::result::Result<T, ClassWriterError>;
@@ -0,0 +1,1379 @@
+use std::collections::HashMap;
+
+pub use crate::instruction::LocalKind;
+#[cfg(test)]
+use crate::{class_file_method::ClassFileMethod, method_flags::MethodFlags};
+use crate::{
+    class_file_method::ClassFileMethodCode,
+    class_writer_error::{ClassWriterError, Result},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    exception_table::{ExceptionTable, ExceptionTableEntry},
+    instruction::{Instruction, WideInstruction},
+    line_number::LineNumber,
+    line_number_table::{LineNumberTable, LineNumberTableEntry},
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// A symbolic position in the code being built, used as target of jumps and to delimit
+/// try/catch blocks. Labels are created by [CodeBuilder::new_label] and must be placed
+/// exactly once with [CodeBuilder::place_label].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(usize);
+
+/// Jump instructions that can target a [Label]
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpKind {
+    Goto,
+    Jsr,
+    If_acmpeq,
+    If_acmpne,
+    If_icmpeq,
+    If_icmpne,
+    If_icmplt,
+    If_icmpge,
+    If_icmpgt,
+    If_icmple,
+    Ifeq,
+    Ifne,
+    Iflt,
+    Ifge,
+    Ifgt,
+    Ifle,
+    Ifnonnull,
+    Ifnull,
+}
+
+impl JumpKind {
+    /// Returns the kind and the target address of a jump instruction. The wide forms are
+    /// mapped to the normal ones, since [CodeBuilder] picks the form depending on the offset.
+    pub fn of(instruction: &Instruction) -> Option<(JumpKind, u16)> {
+        let (kind, jump_address) = match instruction {
+            Instruction::Goto { jump_address } | Instruction::Goto_w { jump_address } => {
+                (JumpKind::Goto, jump_address)
+            }
+            Instruction::Jsr { jump_address } | Instruction::Jsr_w { jump_address } => {
+                (JumpKind::Jsr, jump_address)
+            }
+            Instruction::If_acmpeq { jump_address } => (JumpKind::If_acmpeq, jump_address),
+            Instruction::If_acmpne { jump_address } => (JumpKind::If_acmpne, jump_address),
+            Instruction::If_icmpeq { jump_address } => (JumpKind::If_icmpeq, jump_address),
+            Instruction::If_icmpne { jump_address } => (JumpKind::If_icmpne, jump_address),
+            Instruction::If_icmplt { jump_address } => (JumpKind::If_icmplt, jump_address),
+            Instruction::If_icmpge { jump_address } => (JumpKind::If_icmpge, jump_address),
+            Instruction::If_icmpgt { jump_address } => (JumpKind::If_icmpgt, jump_address),
+            Instruction::If_icmple { jump_address } => (JumpKind::If_icmple, jump_address),
+            Instruction::Ifeq { jump_address } => (JumpKind::Ifeq, jump_address),
+            Instruction::Ifne { jump_address } => (JumpKind::Ifne, jump_address),
+            Instruction::Iflt { jump_address } => (JumpKind::Iflt, jump_address),
+            Instruction::Ifge { jump_address } => (JumpKind::Ifge, jump_address),
+            Instruction::Ifgt { jump_address } => (JumpKind::Ifgt, jump_address),
+            Instruction::Ifle { jump_address } => (JumpKind::Ifle, jump_address),
+            Instruction::Ifnonnull { jump_address } => (JumpKind::Ifnonnull, jump_address),
+            Instruction::Ifnull { jump_address } => (JumpKind::Ifnull, jump_address),
+            _ => return None,
+        };
+        Some((kind, *jump_address))
+    }
+
+    fn instruction(self, jump_address: u16) -> Instruction {
+        match self {
+            JumpKind::Goto => Instruction::Goto { jump_address },
+            JumpKind::Jsr => Instruction::Jsr { jump_address },
+            JumpKind::If_acmpeq => Instruction::If_acmpeq { jump_address },
+            JumpKind::If_acmpne => Instruction::If_acmpne { jump_address },
+            JumpKind::If_icmpeq => Instruction::If_icmpeq { jump_address },
+            JumpKind::If_icmpne => Instruction::If_icmpne { jump_address },
+            JumpKind::If_icmplt => Instruction::If_icmplt { jump_address },
+            JumpKind::If_icmpge => Instruction::If_icmpge { jump_address },
+            JumpKind::If_icmpgt => Instruction::If_icmpgt { jump_address },
+            JumpKind::If_icmple => Instruction::If_icmple { jump_address },
+            JumpKind::Ifeq => Instruction::Ifeq { jump_address },
+            JumpKind::Ifne => Instruction::Ifne { jump_address },
+            JumpKind::Iflt => Instruction::Iflt { jump_address },
+            JumpKind::Ifge => Instruction::Ifge { jump_address },
+            JumpKind::Ifgt => Instruction::Ifgt { jump_address },
+            JumpKind::Ifle => Instruction::Ifle { jump_address },
+            JumpKind::Ifnonnull => Instruction::Ifnonnull { jump_address },
+            JumpKind::Ifnull => Instruction::Ifnull { jump_address },
+        }
+    }
+
+    fn wide_instruction(self, jump_address: u16) -> Option<Instruction> {
+        match self {
+            JumpKind::Goto => Some(Instruction::Goto_w { jump_address }),
+            JumpKind::Jsr => Some(Instruction::Jsr_w { jump_address }),
+            _ => None,
+        }
+    }
+
+    /// The conditional jump with the opposite condition, or `None` for unconditional jumps
+    fn negated(self) -> Option<JumpKind> {
+        match self {
+            JumpKind::Goto | JumpKind::Jsr => None,
+            JumpKind::If_acmpeq => Some(JumpKind::If_acmpne),
+            JumpKind::If_acmpne => Some(JumpKind::If_acmpeq),
+            JumpKind::If_icmpeq => Some(JumpKind::If_icmpne),
+            JumpKind::If_icmpne => Some(JumpKind::If_icmpeq),
+            JumpKind::If_icmplt => Some(JumpKind::If_icmpge),
+            JumpKind::If_icmpge => Some(JumpKind::If_icmplt),
+            JumpKind::If_icmpgt => Some(JumpKind::If_icmple),
+            JumpKind::If_icmple => Some(JumpKind::If_icmpgt),
+            JumpKind::Ifeq => Some(JumpKind::Ifne),
+            JumpKind::Ifne => Some(JumpKind::Ifeq),
+            JumpKind::Iflt => Some(JumpKind::Ifge),
+            JumpKind::Ifge => Some(JumpKind::Iflt),
+            JumpKind::Ifgt => Some(JumpKind::Ifle),
+            JumpKind::Ifle => Some(JumpKind::Ifgt),
+            JumpKind::Ifnonnull => Some(JumpKind::Ifnull),
+            JumpKind::Ifnull => Some(JumpKind::Ifnonnull),
+        }
+    }
+
+    /// Size in bytes of the jump. A conditional jump whose offset does not fit in an i16 is
+    /// replaced by the negated condition jumping over a `goto_w`.
+    fn size(self, wide: bool) -> usize {
+        match (wide, self) {
+            (false, _) => 3,
+            (true, JumpKind::Goto | JumpKind::Jsr) => 5,
+            (true, _) => 8,
+        }
+    }
+}
+
+/// The elements that make up the code of a method, where jump targets and the boundaries of
+/// exception handlers are expressed as [Label]s rather than addresses
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodeElement {
+    /// Any instruction other than jumps and switches
+    Instruction(Instruction),
+    Jump(JumpKind, Label),
+    /// A `tableswitch`, where `targets[i]` is the target for the key `low + i`
+    TableSwitch {
+        low: i32,
+        default: Label,
+        targets: Vec<Label>,
+    },
+    /// A `lookupswitch`, with the pairs sorted by key
+    LookupSwitch {
+        default: Label,
+        pairs: Vec<(i32, Label)>,
+    },
+    Label(Label),
+    /// Marks the following instructions as generated from the given line of the source file
+    LineNumber(u16),
+    /// An entry of the exception table
+    TryCatch {
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_class: Option<String>,
+    },
+}
+
+/// Builds the code of a method, taking care of computing jump offsets from [Label]s,
+/// choosing the most compact form of the instructions, and computing `max_stack` and
+/// `max_locals`.
+///
+/// Jumps are encoded with a 16-bit offset when possible; when the offset does not fit
+/// `goto` and `jsr` are replaced by `goto_w` and `jsr_w`, while conditional jumps are
+/// replaced by the negated condition jumping over a `goto_w`.
+pub struct CodeBuilder<'a> {
+    constants: &'a mut ConstantPool,
+    /// Slots taken by the arguments, including `this` for instance methods
+    arguments_slots: u16,
+    elements: Vec<CodeElement>,
+    labels_count: usize,
+    /// The first error found while adding constants, reported by [CodeBuilder::build]
+    error: Option<ClassWriterError>,
+}
+
+impl<'a> CodeBuilder<'a> {
+    /// Creates a builder for the code of a method with the given descriptor. New constants
+    /// needed by the code will be added to the given pool.
+    pub fn new(
+        constants: &'a mut ConstantPool,
+        is_static: bool,
+        descriptor: &MethodDescriptor,
+    ) -> Self {
+        Self {
+            constants,
+            arguments_slots: descriptor.arguments_slots() + if is_static { 0 } else { 1 },
+            elements: Vec::new(),
+            labels_count: 0,
+            error: None,
+        }
+    }
+
+    /// Creates an empty builder that shares the constant pool and the labels of this one.
+    /// Used to collect the elements generated by a transform before passing them to the next.
+    pub(crate) fn fork(&mut self) -> CodeBuilder<'_> {
+        CodeBuilder {
+            constants: self.constants,
+            arguments_slots: self.arguments_slots,
+            elements: Vec::new(),
+            labels_count: self.labels_count,
+            error: None,
+        }
+    }
+
+    /// Returns the elements added to a builder created by [CodeBuilder::fork], making sure that
+    /// the labels it created are not reused by this builder
+    pub(crate) fn join(
+        &mut self,
+        fork: (Vec<CodeElement>, usize, Option<ClassWriterError>),
+    ) -> Vec<CodeElement> {
+        let (elements, labels_count, error) = fork;
+        self.labels_count = self.labels_count.max(labels_count);
+        if self.error.is_none() {
+            self.error = error;
+        }
+        elements
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<CodeElement>, usize, Option<ClassWriterError>) {
+        (self.elements, self.labels_count, self.error)
+    }
+
+    pub(crate) fn elements(&self) -> &[CodeElement] {
+        &self.elements
+    }
+
+    /// The constant pool where constants referred to by the code are added
+    pub fn constants(&mut self) -> &mut ConstantPool {
+        self.constants
+    }
+
+    /// Adds a constant to the pool via the given function. If the pool is full, the error is
+    /// reported by [CodeBuilder::build].
+    fn intern(&mut self, intern: impl FnOnce(&mut ConstantPool) -> Result<u16>) -> u16 {
+        intern(self.constants).unwrap_or_else(|error| {
+            if self.error.is_none() {
+                self.error = Some(error);
+            }
+            0
+        })
+    }
+
+    pub fn new_label(&mut self) -> Label {
+        self.labels_count += 1;
+        Label(self.labels_count - 1)
+    }
+
+    /// Binds the label to the address of the next instruction
+    pub fn place_label(&mut self, label: Label) -> &mut Self {
+        self.elements.push(CodeElement::Label(label));
+        self
+    }
+
+    /// Adds an instruction as is. Jumps and switches should be added via [CodeBuilder::jump],
+    /// [CodeBuilder::tableswitch] and [CodeBuilder::lookupswitch], since their addresses are
+    /// not known until the code is built.
+    pub fn instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.elements.push(CodeElement::Instruction(instruction));
+        self
+    }
+
+    /// Marks the following instructions as generated from the given line of the source file
+    pub fn line_number(&mut self, line: u16) -> &mut Self {
+        self.elements.push(CodeElement::LineNumber(line));
+        self
+    }
+
+    pub fn jump(&mut self, kind: JumpKind, target: Label) -> &mut Self {
+        self.elements.push(CodeElement::Jump(kind, target));
+        self
+    }
+
+    /// Adds a `tableswitch`, where `targets[i]` is the target for the key `low + i`.
+    /// Panics if `targets` is empty, since a `tableswitch` covers at least one key.
+    pub fn tableswitch(&mut self, low: i32, default: Label, targets: Vec<Label>) -> &mut Self {
+        assert!(
+            !targets.is_empty(),
+            "a tableswitch needs at least one target"
+        );
+        self.elements.push(CodeElement::TableSwitch {
+            low,
+            default,
+            targets,
+        });
+        self
+    }
+
+    /// Adds a `lookupswitch`. The pairs do not need to be sorted by key.
+    pub fn lookupswitch(&mut self, default: Label, mut pairs: Vec<(i32, Label)>) -> &mut Self {
+        pairs.sort_by_key(|(key, _)| *key);
+        self.elements
+            .push(CodeElement::LookupSwitch { default, pairs });
+        self
+    }
+
+    /// Registers an exception handler at `handler` for the code between `start` (inclusive)
+    /// and `end` (exclusive). A `catch_class` of `None` catches everything, as in `finally`.
+    pub fn try_catch(
+        &mut self,
+        start: Label,
+        end: Label,
+        handler: Label,
+        catch_class: Option<&str>,
+    ) -> &mut Self {
+        self.elements.push(CodeElement::TryCatch {
+            start,
+            end,
+            handler,
+            catch_class: catch_class.map(str::to_string),
+        });
+        self
+    }
+
+    /// Adds an element as is, except for the pairs of a `lookupswitch` that get sorted
+    pub fn with(&mut self, element: CodeElement) -> &mut Self {
+        match element {
+            CodeElement::LookupSwitch { default, pairs } => self.lookupswitch(default, pairs),
+            element => {
+                self.elements.push(element);
+                self
+            }
+        }
+    }
+
+    /// Converts existing code into elements, using new labels of this builder for all the
+    /// addresses referred to by jumps and by the exception table. The elements can then be
+    /// added back, possibly after transforming them, via [CodeBuilder::with].
+    /// Exception table entries come first, in their original order.
+    pub fn read_elements(&mut self, code: &ClassFileMethodCode) -> Result<Vec<CodeElement>> {
+        let instructions = Instruction::parse_instructions(&code.code)?;
+
+        let mut labels: HashMap<u16, Label> = HashMap::new();
+        let mut label_at = |builder: &mut Self, address: u16| {
+            *labels.entry(address).or_insert_with(|| builder.new_label())
+        };
+
+        let mut elements = Vec::new();
+        for entry in code.exception_table.entries() {
+            elements.push(CodeElement::TryCatch {
+                start: label_at(self, entry.range.start.0),
+                end: label_at(self, entry.range.end.0),
+                handler: label_at(self, entry.handler_pc.0),
+                catch_class: entry.catch_class.clone(),
+            });
+        }
+
+        let mut body = Vec::with_capacity(instructions.len());
+        for (_, instruction) in instructions.iter() {
+            body.push(match instruction {
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    jump_addresses,
+                    ..
+                } => CodeElement::TableSwitch {
+                    low: *low,
+                    default: label_at(self, *default),
+                    targets: jump_addresses
+                        .iter()
+                        .map(|address| label_at(self, *address))
+                        .collect(),
+                },
+                Instruction::Lookupswitch {
+                    default,
+                    match_pairs,
+                } => CodeElement::LookupSwitch {
+                    default: label_at(self, *default),
+                    pairs: match_pairs
+                        .iter()
+                        .map(|(key, address)| (*key, label_at(self, *address)))
+                        .collect(),
+                },
+                instruction => match JumpKind::of(instruction) {
+                    Some((kind, address)) => CodeElement::Jump(kind, label_at(self, address)),
+                    None => CodeElement::Instruction(instruction.clone()),
+                },
+            });
+        }
+
+        let line_numbers = code
+            .line_number_table
+            .as_ref()
+            .map_or(&[][..], |table| table.entries());
+        let mut line_numbers = line_numbers.iter().peekable();
+        let end_address = code.code.len();
+        let addresses = instructions
+            .iter()
+            .map(|(address, _)| *address)
+            .chain(std::iter::once(end_address));
+        let mut body = body.into_iter();
+        for address in addresses {
+            if let Some(label) = u16::try_from(address)
+                .ok()
+                .and_then(|address| labels.get(&address))
+            {
+                elements.push(CodeElement::Label(*label));
+            }
+            while let Some(entry) =
+                line_numbers.next_if(|entry| entry.program_counter.0.into_usize_safe() <= address)
+            {
+                elements.push(CodeElement::LineNumber(entry.line_number.0));
+            }
+            elements.extend(body.next());
+        }
+        Ok(elements)
+    }
+
+    /// Loads a local variable, using the shortest available form
+    pub fn load(&mut self, kind: LocalKind, index: u16) -> &mut Self {
+        let instruction = match (kind, index) {
+            (LocalKind::Int, 0) => Instruction::Iload_0,
+            (LocalKind::Int, 1) => Instruction::Iload_1,
+            (LocalKind::Int, 2) => Instruction::Iload_2,
+            (LocalKind::Int, 3) => Instruction::Iload_3,
+            (LocalKind::Long, 0) => Instruction::Lload_0,
+            (LocalKind::Long, 1) => Instruction::Lload_1,
+            (LocalKind::Long, 2) => Instruction::Lload_2,
+            (LocalKind::Long, 3) => Instruction::Lload_3,
+            (LocalKind::Float, 0) => Instruction::Fload_0,
+            (LocalKind::Float, 1) => Instruction::Fload_1,
+            (LocalKind::Float, 2) => Instruction::Fload_2,
+            (LocalKind::Float, 3) => Instruction::Fload_3,
+            (LocalKind::Double, 0) => Instruction::Dload_0,
+            (LocalKind::Double, 1) => Instruction::Dload_1,
+            (LocalKind::Double, 2) => Instruction::Dload_2,
+            (LocalKind::Double, 3) => Instruction::Dload_3,
+            (LocalKind::Reference, 0) => Instruction::Aload_0,
+            (LocalKind::Reference, 1) => Instruction::Aload_1,
+            (LocalKind::Reference, 2) => Instruction::Aload_2,
+            (LocalKind::Reference, 3) => Instruction::Aload_3,
+            _ => match u8::try_from(index) {
+                Ok(index) => match kind {
+                    LocalKind::Int => Instruction::Iload { index },
+                    LocalKind::Long => Instruction::Lload { index },
+                    LocalKind::Float => Instruction::Fload { index },
+                    LocalKind::Double => Instruction::Dload { index },
+                    LocalKind::Reference => Instruction::Aload { index },
+                },
+                Err(_) => Instruction::Wide {
+                    instruction: match kind {
+                        LocalKind::Int => WideInstruction::Iload { index },
+                        LocalKind::Long => WideInstruction::Lload { index },
+                        LocalKind::Float => WideInstruction::Fload { index },
+                        LocalKind::Double => WideInstruction::Dload { index },
+                        LocalKind::Reference => WideInstruction::Aload { index },
+                    },
+                },
+            },
+        };
+        self.instruction(instruction)
+    }
+
+    /// Stores the value on top of the stack into a local variable, using the shortest
+    /// available form
+    pub fn store(&mut self, kind: LocalKind, index: u16) -> &mut Self {
+        let instruction = match (kind, index) {
+            (LocalKind::Int, 0) => Instruction::Istore_0,
+            (LocalKind::Int, 1) => Instruction::Istore_1,
+            (LocalKind::Int, 2) => Instruction::Istore_2,
+            (LocalKind::Int, 3) => Instruction::Istore_3,
+            (LocalKind::Long, 0) => Instruction::Lstore_0,
+            (LocalKind::Long, 1) => Instruction::Lstore_1,
+            (LocalKind::Long, 2) => Instruction::Lstore_2,
+            (LocalKind::Long, 3) => Instruction::Lstore_3,
+            (LocalKind::Float, 0) => Instruction::Fstore_0,
+            (LocalKind::Float, 1) => Instruction::Fstore_1,
+            (LocalKind::Float, 2) => Instruction::Fstore_2,
+            (LocalKind::Float, 3) => Instruction::Fstore_3,
+            (LocalKind::Double, 0) => Instruction::Dstore_0,
+            (LocalKind::Double, 1) => Instruction::Dstore_1,
+            (LocalKind::Double, 2) => Instruction::Dstore_2,
+            (LocalKind::Double, 3) => Instruction::Dstore_3,
+            (LocalKind::Reference, 0) => Instruction::Astore_0,
+            (LocalKind::Reference, 1) => Instruction::Astore_1,
+            (LocalKind::Reference, 2) => Instruction::Astore_2,
+            (LocalKind::Reference, 3) => Instruction::Astore_3,
+            _ => match u8::try_from(index) {
+                Ok(index) => match kind {
+                    LocalKind::Int => Instruction::Istore { index },
+                    LocalKind::Long => Instruction::Lstore { index },
+                    LocalKind::Float => Instruction::Fstore { index },
+                    LocalKind::Double => Instruction::Dstore { index },
+                    LocalKind::Reference => Instruction::Astore { index },
+                },
+                Err(_) => Instruction::Wide {
+                    instruction: match kind {
+                        LocalKind::Int => WideInstruction::Istore { index },
+                        LocalKind::Long => WideInstruction::Lstore { index },
+                        LocalKind::Float => WideInstruction::Fstore { index },
+                        LocalKind::Double => WideInstruction::Dstore { index },
+                        LocalKind::Reference => WideInstruction::Astore { index },
+                    },
+                },
+            },
+        };
+        self.instruction(instruction)
+    }
+
+    /// Increments an int local variable, using `wide` if needed
+    pub fn iinc(&mut self, index: u16, constant: i16) -> &mut Self {
+        let instruction = match (u8::try_from(index), i8::try_from(constant)) {
+            (Ok(index), Ok(constant)) => Instruction::Iinc { index, constant },
+            _ => Instruction::Wide {
+                instruction: WideInstruction::Iinc { index, constant },
+            },
+        };
+        self.instruction(instruction)
+    }
+
+    /// Pushes an int constant, using `iconst_<n>`, `bipush` or `sipush` when possible
+    pub fn iconst(&mut self, value: i32) -> &mut Self {
+        let instruction = match value {
+            -1 => Instruction::Iconst_m1,
+            0 => Instruction::Iconst_0,
+            1 => Instruction::Iconst_1,
+            2 => Instruction::Iconst_2,
+            3 => Instruction::Iconst_3,
+            4 => Instruction::Iconst_4,
+            5 => Instruction::Iconst_5,
+            _ => {
+                if let Ok(byte) = i8::try_from(value) {
+                    Instruction::Bipush { byte: byte as u8 }
+                } else if let Ok(short) = i16::try_from(value) {
+                    Instruction::Sipush { short }
+                } else {
+                    let index = self
+                        .intern(|constants| constants.intern(ConstantPoolEntry::Integer(value)));
+                    return self.ldc(index);
+                }
+            }
+        };
+        self.instruction(instruction)
+    }
+
+    /// Pushes a long constant, using `lconst_<n>` when possible
+    pub fn lconst(&mut self, value: i64) -> &mut Self {
+        match value {
+            0 => self.instruction(Instruction::Lconst_0),
+            1 => self.instruction(Instruction::Lconst_1),
+            _ => {
+                let index =
+                    self.intern(|constants| constants.intern(ConstantPoolEntry::Long(value)));
+                self.ldc(index)
+            }
+        }
+    }
+
+    /// Pushes a float constant, using `fconst_<n>` when possible
+    pub fn fconst(&mut self, value: f32) -> &mut Self {
+        if value.to_bits() == 0.0f32.to_bits() {
+            self.instruction(Instruction::Fconst_0)
+        } else if value == 1.0 {
+            self.instruction(Instruction::Fconst_1)
+        } else if value == 2.0 {
+            self.instruction(Instruction::Fconst_2)
+        } else {
+            let index = self.intern(|constants| constants.intern(ConstantPoolEntry::Float(value)));
+            self.ldc(index)
+        }
+    }
+
+    /// Pushes a double constant, using `dconst_<n>` when possible
+    pub fn dconst(&mut self, value: f64) -> &mut Self {
+        if value.to_bits() == 0.0f64.to_bits() {
+            self.instruction(Instruction::Dconst_0)
+        } else if value == 1.0 {
+            self.instruction(Instruction::Dconst_1)
+        } else {
+            let index = self.intern(|constants| constants.intern(ConstantPoolEntry::Double(value)));
+            self.ldc(index)
+        }
+    }
+
+    /// Pushes a string constant
+    pub fn sconst(&mut self, value: &str) -> &mut Self {
+        let index = self.intern(|constants| constants.intern_string_reference(value));
+        self.ldc(index)
+    }
+
+    /// Loads a constant from the pool, choosing between `ldc`, `ldc_w` and `ldc2_w`
+    pub fn ldc(&mut self, index: u16) -> &mut Self {
+        let is_wide_constant = matches!(
+            self.constants.get(index),
+            Ok(ConstantPoolEntry::Long(_)) | Ok(ConstantPoolEntry::Double(_))
+        );
+        let instruction = if is_wide_constant {
+            Instruction::Ldc2_w { index }
+        } else {
+            match u8::try_from(index) {
+                Ok(index) => Instruction::Ldc { index },
+                Err(_) => Instruction::Ldc_w { index },
+            }
+        };
+        self.instruction(instruction)
+    }
+
+    pub fn getfield(&mut self, class_name: &str, name: &str, type_descriptor: &str) -> &mut Self {
+        let field = self.intern(|constants| {
+            constants.intern_field_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Getfield { field })
+    }
+
+    pub fn putfield(&mut self, class_name: &str, name: &str, type_descriptor: &str) -> &mut Self {
+        let field = self.intern(|constants| {
+            constants.intern_field_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Putfield { field })
+    }
+
+    pub fn getstatic(&mut self, class_name: &str, name: &str, type_descriptor: &str) -> &mut Self {
+        let field = self.intern(|constants| {
+            constants.intern_field_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Getstatic { field })
+    }
+
+    pub fn putstatic(&mut self, class_name: &str, name: &str, type_descriptor: &str) -> &mut Self {
+        let field = self.intern(|constants| {
+            constants.intern_field_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Putstatic { field })
+    }
+
+    pub fn invokevirtual(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> &mut Self {
+        let method = self.intern(|constants| {
+            constants.intern_method_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Invokevirtual { method })
+    }
+
+    pub fn invokespecial(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> &mut Self {
+        let method = self.intern(|constants| {
+            constants.intern_method_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Invokespecial { method })
+    }
+
+    pub fn invokestatic(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> &mut Self {
+        let method = self.intern(|constants| {
+            constants.intern_method_reference(class_name, name, type_descriptor)
+        });
+        self.instruction(Instruction::Invokestatic { method })
+    }
+
+    /// Adds an `invokeinterface`, computing its `count` operand from the descriptor. The
+    /// arguments, including the receiver, can take at most 255 slots.
+    pub fn invokeinterface(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> Result<&mut Self> {
+        let count = 1 + MethodDescriptor::parse(type_descriptor)?.arguments_slots();
+        let count =
+            u8::try_from(count).map_err(|_| ClassWriterError::TooManyArgumentSlots(count))?;
+        let method =
+            self.constants
+                .intern_interface_method_reference(class_name, name, type_descriptor)?;
+        Ok(self.instruction(Instruction::Invokeinterface { method, count }))
+    }
+
+    /// Adds a `new` instruction for the given class
+    pub fn new_object(&mut self, class_name: &str) -> &mut Self {
+        let class = self.intern(|constants| constants.intern_class_reference(class_name));
+        self.instruction(Instruction::New { class })
+    }
+
+    pub fn checkcast(&mut self, class_name: &str) -> &mut Self {
+        let class = self.intern(|constants| constants.intern_class_reference(class_name));
+        self.instruction(Instruction::Checkcast { class })
+    }
+
+    pub fn instanceof(&mut self, class_name: &str) -> &mut Self {
+        let class = self.intern(|constants| constants.intern_class_reference(class_name));
+        self.instruction(Instruction::Instanceof { class })
+    }
+
+    pub fn anewarray(&mut self, class_name: &str) -> &mut Self {
+        let class = self.intern(|constants| constants.intern_class_reference(class_name));
+        self.instruction(Instruction::Anewarray { class })
+    }
+
+    /// Lays out the code, resolving all labels, and computes `max_stack` and `max_locals`
+    pub fn build(mut self) -> Result<ClassFileMethodCode> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        let (addresses, label_addresses) = self.layout()?;
+
+        let mut instructions: Vec<(usize, Instruction)> = Vec::new();
+        let mut line_numbers: Vec<LineNumberTableEntry> = Vec::new();
+        for (element, (address, wide)) in self.elements.iter().zip(addresses) {
+            match element {
+                CodeElement::Instruction(instruction) => {
+                    instructions.push((address, instruction.clone()))
+                }
+                CodeElement::Jump(kind, label) => {
+                    let target = label_addresses[label];
+                    if !wide {
+                        instructions.push((address, kind.instruction(target)));
+                    } else if let Some(instruction) = kind.wide_instruction(target) {
+                        instructions.push((address, instruction));
+                    } else {
+                        let negated = kind.negated().expect("only conditional jumps are negated");
+                        let after_goto_w = (address + 8) as u16;
+                        instructions.push((address, negated.instruction(after_goto_w)));
+                        instructions.push((
+                            address + 3,
+                            Instruction::Goto_w {
+                                jump_address: target,
+                            },
+                        ));
+                    }
+                }
+                CodeElement::TableSwitch {
+                    low,
+                    default,
+                    targets,
+                } => {
+                    // The last key is `low + targets.len() - 1`, which must fit in an i32
+                    let high = targets
+                        .len()
+                        .checked_sub(1)
+                        .and_then(|offset| i32::try_from(offset).ok())
+                        .and_then(|offset| low.checked_add(offset))
+                        .ok_or(ClassWriterError::InvalidSwitchRange(address))?;
+                    instructions.push((
+                        address,
+                        Instruction::Tableswitch {
+                            default: label_addresses[default],
+                            low: *low,
+                            high,
+                            jump_addresses: targets.iter().map(|t| label_addresses[t]).collect(),
+                        },
+                    ))
+                }
+                CodeElement::LookupSwitch { default, pairs } => instructions.push((
+                    address,
+                    Instruction::Lookupswitch {
+                        default: label_addresses[default],
+                        match_pairs: pairs
+                            .iter()
+                            .map(|(key, target)| (*key, label_addresses[target]))
+                            .collect(),
+                    },
+                )),
+                CodeElement::Label(_) | CodeElement::TryCatch { .. } => {}
+                CodeElement::LineNumber(line) => {
+                    let entry = LineNumberTableEntry::new(
+                        ProgramCounter(address as u16),
+                        LineNumber(*line),
+                    );
+                    match line_numbers.last_mut() {
+                        Some(last) if last.program_counter == entry.program_counter => {
+                            *last = entry
+                        }
+                        _ => line_numbers.push(entry),
+                    }
+                }
+            }
+        }
+
+        let mut code = Vec::new();
+        for (_, instruction) in instructions.iter() {
+            instruction.encode(&mut code)?;
+        }
+
+        let exception_table_entries: Vec<ExceptionTableEntry> = self
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                CodeElement::TryCatch {
+                    start,
+                    end,
+                    handler,
+                    catch_class,
+                } => Some(ExceptionTableEntry {
+                    range: ProgramCounter(label_addresses[start])
+                        ..ProgramCounter(label_addresses[end]),
+                    handler_pc: ProgramCounter(label_addresses[handler]),
+                    catch_class: catch_class.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let max_stack = compute_max_stack(&instructions, &exception_table_entries, self.constants)?;
+        let max_locals = instructions
+            .iter()
+            .filter_map(|(_, instruction)| instruction.local_variable_slots())
+            .map(|slots| slots.end)
+            .fold(self.arguments_slots, u16::max);
+
+        Ok(ClassFileMethodCode {
+            max_stack,
+            max_locals,
+            code,
+            exception_table: ExceptionTable::new(exception_table_entries),
+            line_number_table: if line_numbers.is_empty() {
+                None
+            } else {
+                Some(LineNumberTable::new(line_numbers))
+            },
+            attributes: Vec::new(),
+        })
+    }
+
+    /// Computes the address of every element and whether jumps need the wide form. Starts by
+    /// assuming all jumps are short, and widens the ones that do not fit until nothing changes.
+    /// Since jumps only ever grow, this terminates.
+    #[allow(clippy::type_complexity)]
+    fn layout(&self) -> Result<(Vec<(usize, bool)>, HashMap<Label, u16>)> {
+        let mut wide = vec![false; self.elements.len()];
+        loop {
+            let (addresses, label_addresses) = self.compute_addresses(&wide)?;
+
+            let mut changed = false;
+            for (i, element) in self.elements.iter().enumerate() {
+                if let CodeElement::Jump(_, label) = element {
+                    let offset = label_addresses[label] as i64 - addresses[i] as i64;
+                    if !wide[i] && i16::try_from(offset).is_err() {
+                        wide[i] = true;
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                return Ok((addresses.into_iter().zip(wide).collect(), label_addresses));
+            }
+        }
+    }
+
+    fn compute_addresses(&self, wide: &[bool]) -> Result<(Vec<usize>, HashMap<Label, u16>)> {
+        let mut addresses = Vec::with_capacity(self.elements.len());
+        let mut label_addresses: HashMap<Label, usize> = HashMap::new();
+        let mut address = 0;
+        for (element, wide) in self.elements.iter().zip(wide) {
+            addresses.push(address);
+            address += match element {
+                CodeElement::Instruction(instruction) => instruction.encoded_len(address),
+                CodeElement::Jump(kind, _) => kind.size(*wide),
+                CodeElement::TableSwitch { low, targets, .. } => Instruction::Tableswitch {
+                    default: 0,
+                    low: *low,
+                    high: *low,
+                    jump_addresses: vec![0; targets.len()],
+                }
+                .encoded_len(address),
+                CodeElement::LookupSwitch { pairs, .. } => Instruction::Lookupswitch {
+                    default: 0,
+                    match_pairs: vec![(0, 0); pairs.len()],
+                }
+                .encoded_len(address),
+                CodeElement::Label(label) => {
+                    if label_addresses.insert(*label, address).is_some() {
+                        return Err(ClassWriterError::LabelPlacedTwice(label.0));
+                    }
+                    0
+                }
+                CodeElement::LineNumber(_) | CodeElement::TryCatch { .. } => 0,
+            }
+        }
+        if address > u16::MAX.into_usize_safe() {
+            return Err(ClassWriterError::CodeTooLarge(address));
+        }
+
+        for label in self.used_labels() {
+            if !label_addresses.contains_key(&label) {
+                return Err(ClassWriterError::UnplacedLabel(label.0));
+            }
+        }
+        let label_addresses = label_addresses
+            .into_iter()
+            .map(|(label, address)| (label, address as u16))
+            .collect();
+        Ok((addresses, label_addresses))
+    }
+
+    fn used_labels(&self) -> Vec<Label> {
+        let mut labels = Vec::new();
+        for element in self.elements.iter() {
+            match element {
+                CodeElement::Jump(_, label) => labels.push(*label),
+                CodeElement::TableSwitch {
+                    default, targets, ..
+                } => {
+                    labels.push(*default);
+                    labels.extend(targets);
+                }
+                CodeElement::LookupSwitch { default, pairs } => {
+                    labels.push(*default);
+                    labels.extend(pairs.iter().map(|(_, label)| label));
+                }
+                CodeElement::TryCatch {
+                    start,
+                    end,
+                    handler,
+                    ..
+                } => labels.extend([*start, *end, *handler]),
+                _ => {}
+            }
+        }
+        labels
+    }
+}
+
+/// Computes the maximum depth of the operand stack, by following all the possible execution
+/// paths of the code. Exception handlers start with just the exception on the stack.
+pub(crate) fn compute_max_stack(
+    instructions: &[(usize, Instruction)],
+    exception_table: &[ExceptionTableEntry],
+    constants: &ConstantPool,
+) -> Result<u16> {
+    let index_of_address: HashMap<usize, usize> = instructions
+        .iter()
+        .enumerate()
+        .map(|(index, (address, _))| (*address, index))
+        .collect();
+    let index_of = |address: usize| {
+        index_of_address
+            .get(&address)
+            .copied()
+            .ok_or(ClassWriterError::InvalidJumpTarget(address))
+    };
+
+    let mut heights: Vec<Option<u16>> = vec![None; instructions.len()];
+    let mut max_stack = 0;
+    let mut worklist: Vec<(usize, u16)> = Vec::new();
+    if !instructions.is_empty() {
+        worklist.push((0, 0));
+    }
+
+    while let Some((index, height)) = worklist.pop() {
+        let (address, instruction) = &instructions[index];
+        match heights[index] {
+            Some(existing) if existing == height => continue,
+            Some(_) => return Err(ClassWriterError::InconsistentStackHeight(*address)),
+            None => heights[index] = Some(height),
+        }
+
+        let effect = instruction.stack_effect(constants)?;
+        let height_after = height
+            .checked_sub(effect.popped)
+            .ok_or(ClassWriterError::StackUnderflow(*address))?
+            + effect.pushed;
+        max_stack = max_stack.max(height).max(height_after);
+
+        for target in instruction.jump_targets() {
+            worklist.push((index_of(target.into_usize_safe())?, height_after));
+        }
+        if instruction.can_fall_through() {
+            if index + 1 >= instructions.len() {
+                return Err(ClassWriterError::FallsOffEndOfCode);
+            }
+            let is_subroutine_call = matches!(
+                instruction,
+                Instruction::Jsr { .. } | Instruction::Jsr_w { .. }
+            );
+            let next_height = if is_subroutine_call {
+                height
+            } else {
+                height_after
+            };
+            worklist.push((index + 1, next_height));
+        }
+        for entry in exception_table
+            .iter()
+            .filter(|entry| entry.range.contains(&ProgramCounter(*address as u16)))
+        {
+            worklist.push((index_of(entry.handler_pc.0.into_usize_safe())?, 1));
+        }
+    }
+
+    Ok(max_stack)
+}
+
+/// Builds a method whose code is generated by the given function, for the tests of the
+/// modules that analyze code
+#[cfg(test)]
+pub(crate) fn test_method(
+    constants: &mut ConstantPool,
+    flags: MethodFlags,
+    name: &str,
+    descriptor: &str,
+    build: impl FnOnce(&mut CodeBuilder),
+) -> ClassFileMethod {
+    let parsed_type_descriptor = MethodDescriptor::parse(descriptor).unwrap();
+    let mut builder = CodeBuilder::new(
+        constants,
+        flags.contains(MethodFlags::STATIC),
+        &parsed_type_descriptor,
+    );
+    build(&mut builder);
+    ClassFileMethod {
+        flags,
+        name: name.to_string(),
+        type_descriptor: descriptor.to_string(),
+        parsed_type_descriptor,
+        attributes: vec![],
+        code: Some(builder.build().unwrap()),
+        deprecated: false,
+        thrown_exceptions: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_writer_error::ClassWriterError,
+        code_builder::{CodeBuilder, JumpKind, LocalKind},
+        constant_pool::{ConstantPool, ConstantPoolEntry},
+        exception_table::{ExceptionTable, ExceptionTableEntry},
+        instruction::{Instruction, WideInstruction},
+        line_number::LineNumber,
+        line_number_table::{LineNumberTable, LineNumberTableEntry},
+        method_descriptor::MethodDescriptor,
+        program_counter::ProgramCounter,
+    };
+
+    fn instructions_of(code: &[u8]) -> Vec<(usize, Instruction)> {
+        Instruction::parse_instructions(code).unwrap()
+    }
+
+    #[test]
+    fn picks_compact_forms_and_computes_limits() {
+        let mut constants = ConstantPool::new();
+        let descriptor = MethodDescriptor::parse("(JI)J").unwrap();
+        let mut builder = CodeBuilder::new(&mut constants, true, &descriptor);
+        builder
+            .load(LocalKind::Long, 0)
+            .load(LocalKind::Int, 2)
+            .instruction(Instruction::I2l)
+            .instruction(Instruction::Ladd)
+            .lconst(1)
+            .instruction(Instruction::Ladd)
+            .store(LocalKind::Long, 300)
+            .load(LocalKind::Long, 300)
+            .instruction(Instruction::Lreturn);
+        let code = builder.build().unwrap();
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Lload_0),
+                (1, Instruction::Iload_2),
+                (2, Instruction::I2l),
+                (3, Instruction::Ladd),
+                (4, Instruction::Lconst_1),
+                (5, Instruction::Ladd),
+                (
+                    6,
+                    Instruction::Wide {
+                        instruction: WideInstruction::Lstore { index: 300 }
+                    }
+                ),
+                (
+                    10,
+                    Instruction::Wide {
+                        instruction: WideInstruction::Lload { index: 300 }
+                    }
+                ),
+                (14, Instruction::Lreturn),
+            ],
+            instructions_of(&code.code)
+        );
+        assert_eq!(4, code.max_stack);
+        assert_eq!(302, code.max_locals);
+    }
+
+    #[test]
+    fn picks_constant_loading_instructions() {
+        let mut constants = ConstantPool::new();
+        for i in 0..300 {
+            constants.add(ConstantPoolEntry::Integer(i)).unwrap();
+        }
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        builder
+            .iconst(5)
+            .iconst(-100)
+            .iconst(1000)
+            .iconst(12)
+            .iconst(299)
+            .iconst(100_000)
+            .dconst(2.5)
+            .instruction(Instruction::Return);
+        let code = builder.build().unwrap();
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iconst_5),
+                (1, Instruction::Bipush { byte: 156 }),
+                (3, Instruction::Sipush { short: 1000 }),
+                (6, Instruction::Bipush { byte: 12 }),
+                (8, Instruction::Sipush { short: 299 }),
+                (11, Instruction::Ldc_w { index: 301 }),
+                (14, Instruction::Ldc2_w { index: 302 }),
+                (17, Instruction::Return),
+            ],
+            instructions_of(&code.code)
+        );
+        assert_eq!(8, code.max_stack);
+        assert_eq!(0, code.max_locals);
+    }
+
+    #[test]
+    fn resolves_labels() {
+        let mut constants = ConstantPool::new();
+        let descriptor = MethodDescriptor::parse("(I)I").unwrap();
+        let mut builder = CodeBuilder::new(&mut constants, false, &descriptor);
+        let else_label = builder.new_label();
+        let end_label = builder.new_label();
+        builder
+            .load(LocalKind::Int, 1)
+            .jump(JumpKind::Ifle, else_label)
+            .iconst(1)
+            .jump(JumpKind::Goto, end_label)
+            .place_label(else_label)
+            .iconst(-1)
+            .place_label(end_label)
+            .instruction(Instruction::Ireturn);
+        let code = builder.build().unwrap();
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload_1),
+                (1, Instruction::Ifle { jump_address: 8 }),
+                (4, Instruction::Iconst_1),
+                (5, Instruction::Goto { jump_address: 9 }),
+                (8, Instruction::Iconst_m1),
+                (9, Instruction::Ireturn),
+            ],
+            instructions_of(&code.code)
+        );
+        assert_eq!(1, code.max_stack);
+        assert_eq!(2, code.max_locals);
+    }
+
+    #[test]
+    fn widens_jumps_that_do_not_fit_in_16_bits() {
+        let mut constants = ConstantPool::new();
+        let descriptor = MethodDescriptor::parse("(I)V").unwrap();
+        let mut builder = CodeBuilder::new(&mut constants, true, &descriptor);
+        let far_label = builder.new_label();
+        builder
+            .load(LocalKind::Int, 0)
+            .jump(JumpKind::Ifeq, far_label)
+            .jump(JumpKind::Goto, far_label);
+        for _ in 0..40000 {
+            builder.instruction(Instruction::Nop);
+        }
+        builder
+            .place_label(far_label)
+            .instruction(Instruction::Return);
+        let code = builder.build().unwrap();
+
+        let instructions = instructions_of(&code.code);
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload_0),
+                (1, Instruction::Ifne { jump_address: 9 }),
+                (
+                    4,
+                    Instruction::Goto_w {
+                        jump_address: 40014
+                    }
+                ),
+                (
+                    9,
+                    Instruction::Goto_w {
+                        jump_address: 40014
+                    }
+                ),
+            ],
+            instructions[0..4]
+        );
+        assert_eq!(Some(&(40014, Instruction::Return)), instructions.last());
+    }
+
+    #[test]
+    fn encodes_switches() {
+        let mut constants = ConstantPool::new();
+        let descriptor = MethodDescriptor::parse("(I)I").unwrap();
+        let mut builder = CodeBuilder::new(&mut constants, true, &descriptor);
+        let default_label = builder.new_label();
+        let one_label = builder.new_label();
+        let two_label = builder.new_label();
+        builder
+            .load(LocalKind::Int, 0)
+            .tableswitch(1, default_label, vec![one_label, two_label])
+            .place_label(one_label)
+            .load(LocalKind::Int, 0)
+            .lookupswitch(default_label, vec![(100, two_label), (-5, one_label)])
+            .place_label(two_label)
+            .iconst(2)
+            .instruction(Instruction::Ireturn)
+            .place_label(default_label)
+            .iconst(0)
+            .instruction(Instruction::Ireturn);
+        let code = builder.build().unwrap();
+
+        assert_eq!(
+            vec![
+                (0, Instruction::Iload_0),
+                (
+                    1,
+                    Instruction::Tableswitch {
+                        default: 54,
+                        low: 1,
+                        high: 2,
+                        jump_addresses: vec![24, 52],
+                    }
+                ),
+                (24, Instruction::Iload_0),
+                (
+                    25,
+                    Instruction::Lookupswitch {
+                        default: 54,
+                        match_pairs: vec![(-5, 24), (100, 52)],
+                    }
+                ),
+                (52, Instruction::Iconst_2),
+                (53, Instruction::Ireturn),
+                (54, Instruction::Iconst_0),
+                (55, Instruction::Ireturn),
+            ],
+            instructions_of(&code.code)
+        );
+    }
+
+    #[test]
+    fn generates_exception_table_and_line_numbers() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, false, &MethodDescriptor::default());
+        let start = builder.new_label();
+        let end = builder.new_label();
+        let handler = builder.new_label();
+        let exit = builder.new_label();
+        builder
+            .line_number(10)
+            .place_label(start)
+            .load(LocalKind::Reference, 0)
+            .invokevirtual("java/lang/Object", "hashCode", "()I")
+            .instruction(Instruction::Pop)
+            .place_label(end)
+            .jump(JumpKind::Goto, exit)
+            .place_label(handler)
+            .line_number(11)
+            .store(LocalKind::Reference, 1)
+            .place_label(exit)
+            .line_number(12)
+            .instruction(Instruction::Return)
+            .try_catch(start, end, handler, Some("java/lang/RuntimeException"));
+        let code = builder.build().unwrap();
+
+        assert_eq!(
+            ExceptionTable::new(vec![ExceptionTableEntry {
+                range: ProgramCounter(0)..ProgramCounter(5),
+                handler_pc: ProgramCounter(8),
+                catch_class: Some("java/lang/RuntimeException".to_string()),
+            }]),
+            code.exception_table
+        );
+        assert_eq!(
+            Some(LineNumberTable::new(vec![
+                LineNumberTableEntry::new(ProgramCounter(0), LineNumber(10)),
+                LineNumberTableEntry::new(ProgramCounter(8), LineNumber(11)),
+                LineNumberTableEntry::new(ProgramCounter(9), LineNumber(12)),
+            ])),
+            code.line_number_table
+        );
+        assert_eq!(1, code.max_stack);
+        assert_eq!(2, code.max_locals);
+    }
+
+    #[test]
+    fn rejects_unplaced_labels() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        let label = builder.new_label();
+        builder.jump(JumpKind::Goto, label);
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn rejects_tableswitch_keys_overflowing() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        let target = builder.new_label();
+        builder
+            .iconst(0)
+            .tableswitch(i32::MAX, target, vec![target, target])
+            .place_label(target)
+            .instruction(Instruction::Return);
+        assert_eq!(
+            Err(ClassWriterError::InvalidSwitchRange(1)),
+            builder.build().map(|_| ())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a tableswitch needs at least one target")]
+    fn rejects_tableswitch_without_targets() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        let target = builder.new_label();
+        builder.tableswitch(0, target, vec![]);
+    }
+
+    #[test]
+    fn reports_a_full_constant_pool() {
+        let mut constants = ConstantPool::new();
+        for i in 0..65534 {
+            constants.add(ConstantPoolEntry::Integer(i)).unwrap();
+        }
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        builder
+            .sconst("does not fit")
+            .instruction(Instruction::Pop)
+            .instruction(Instruction::Return);
+        assert_eq!(
+            Err(ClassWriterError::TooManyConstants(65536)),
+            builder.build().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn rejects_invokeinterface_with_too_many_arguments() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        let descriptor = format!("({})V", "J".repeat(128));
+        assert_eq!(
+            Some(ClassWriterError::TooManyArgumentSlots(257)),
+            builder
+                .invokeinterface("a/Interface", "call", &descriptor)
+                .err()
+        );
+    }
+
+    #[test]
+    fn rejects_code_falling_off_the_end() {
+        let mut constants = ConstantPool::new();
+        let mut builder = CodeBuilder::new(&mut constants, true, &MethodDescriptor::default());
+        builder.iconst(1);
+        assert!(builder.build().is_err());
+    }
+}
@@ -1,10 +1,26 @@
-use std::{fmt, vec::Vec};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock},
+    vec::Vec,
+};
+
+use cesu8::{from_java_cesu8, to_java_cesu8};
 use thiserror::Error;
 
+use crate::{
+    buffer::{Buffer, BufferError},
+    class_reader_error::{ClassReaderError, Result as ClassReaderResult},
+    class_writer_error::ClassWriterError,
+    type_conversion::ToUsizeSafe,
+};
+
 /// Types of a constant in the constant pool of a class, following the JVM spec:
 /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.4
-#[derive(Debug, PartialEq, Clone)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub enum ConstantPoolEntry {
     Utf8(String),
     Integer(i32),
@@ -19,25 +35,84 @@ pub enum ConstantPoolEntry {
     NameAndTypeDescriptor(u16, u16),
 }
 
+/// Float and double constants are compared by their bits, like the JVM does when resolving
+/// them, so that `0.0` and `-0.0` are different constants and a `NaN` is equal to itself.
+impl PartialEq for ConstantPoolEntry {
+    fn eq(&self, other: &Self) -> bool {
+        use ConstantPoolEntry::*;
+        match (self, other) {
+            (Utf8(a), Utf8(b)) => a == b,
+            (Integer(a), Integer(b)) => a == b,
+            (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+            (Long(a), Long(b)) => a == b,
+            (Double(a), Double(b)) => a.to_bits() == b.to_bits(),
+            (ClassReference(a), ClassReference(b)) => a == b,
+            (StringReference(a), StringReference(b)) => a == b,
+            (FieldReference(a1, a2), FieldReference(b1, b2))
+            | (MethodReference(a1, a2), MethodReference(b1, b2))
+            | (InterfaceMethodReference(a1, a2), InterfaceMethodReference(b1, b2))
+            | (NameAndTypeDescriptor(a1, a2), NameAndTypeDescriptor(b1, b2)) => {
+                a1 == b1 && a2 == b2
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ConstantPoolEntry {}
+
+impl Hash for ConstantPoolEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use ConstantPoolEntry::*;
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Utf8(text) => text.hash(state),
+            Integer(value) => value.hash(state),
+            Float(value) => value.to_bits().hash(state),
+            Long(value) => value.hash(state),
+            Double(value) => value.to_bits().hash(state),
+            ClassReference(index) | StringReference(index) => index.hash(state),
+            FieldReference(first, second)
+            | MethodReference(first, second)
+            | InterfaceMethodReference(first, second)
+            | NameAndTypeDescriptor(first, second) => (first, second).hash(state),
+        }
+    }
+}
+
 /// Constants in the pool generally take one slot, but long and double take two. We do not use
 /// the second one, so we have a tombstone to ensure the indexes match.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum ConstantPoolPhysicalEntry {
     Entry(ConstantPoolEntry),
     MultiByteEntryTombstone(),
+    /// An entry that is decoded on first access, from the given offset of the raw pool
+    Lazy(usize, OnceLock<ConstantPoolEntry>),
 }
 
+/// Valid references between constants are at most a few levels deep, i.e. a method reference
+/// pointing to a class pointing to an utf8 string. Deeper ones can only come from cyclic
+/// references in a malformed class, and are rejected rather than followed forever.
+const MAX_REFERENCE_DEPTH: usize = 4;
+
 /// Implementation of the constant pool of a java class.
 /// Note that constants are 1-based in java.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct ConstantPool {
     entries: Vec<ConstantPoolPhysicalEntry>,
+    /// The bytes of the pool as read from the class file, used by the lazy entries
+    raw: Arc<[u8]>,
+    /// Index of the first occurrence of each entry, used to intern entries without scanning
+    /// the whole pool. It covers the first `indexed_len` slots: the lazy entries of a pool read
+    /// from a class file are only indexed when something is interned in it.
+    index: HashMap<ConstantPoolEntry, u16>,
+    indexed_len: usize,
 }
 
 /// Error used to signal that an attempt was made to access a non existing constant pool entry.
 #[derive(Error, Debug, PartialEq, Eq)]
 #[error("invalid constant pool index: {index}")]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InvalidConstantPoolIndexError {
     pub index: u16,
 }
@@ -53,18 +128,145 @@ impl ConstantPool {
         Default::default()
     }
 
-    /// Adds a new entry.
-    pub fn add(&mut self, entry: ConstantPoolEntry) {
+    /// Creates a pool whose entries are decoded only when accessed. `raw` contains the
+    /// entries in the class file format, and `offsets` the position of each of them, as
+    /// returned by [scan_constants].
+    pub(crate) fn lazy(raw: &[u8], offsets: Vec<Option<usize>>) -> ConstantPool {
+        ConstantPool {
+            entries: offsets
+                .into_iter()
+                .map(|offset| match offset {
+                    Some(offset) => ConstantPoolPhysicalEntry::Lazy(offset, OnceLock::new()),
+                    None => ConstantPoolPhysicalEntry::MultiByteEntryTombstone(),
+                })
+                .collect(),
+            raw: raw.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a new entry, and returns its (1-based) index. A pool holds at most 65534 slots,
+    /// so adding an entry that does not fit is an error.
+    pub fn add(&mut self, entry: ConstantPoolEntry) -> Result<u16, ClassWriterError> {
         let add_tombstone = matches!(
             &entry,
             ConstantPoolEntry::Long(_) | ConstantPoolEntry::Double(_)
         );
+        let count = self.entries.len() + if add_tombstone { 3 } else { 2 };
+        if count > u16::MAX.into_usize_safe() {
+            return Err(ClassWriterError::TooManyConstants(count));
+        }
+        let index = (self.entries.len() + 1) as u16;
+        if self.indexed_len == self.entries.len() {
+            self.index.entry(entry.clone()).or_insert(index);
+            self.indexed_len += if add_tombstone { 2 } else { 1 };
+        }
         self.entries.push(ConstantPoolPhysicalEntry::Entry(entry));
 
         if add_tombstone {
             self.entries
                 .push(ConstantPoolPhysicalEntry::MultiByteEntryTombstone())
         }
+        Ok(index)
+    }
+
+    /// Returns the index of an entry equal to the given one, if any.
+    pub fn find(&self, entry: &ConstantPoolEntry) -> Option<u16> {
+        self.index.get(entry).copied().or_else(|| {
+            self.iter_from(self.indexed_len)
+                .find(|(_, existing)| *existing == entry)
+                .map(|(index, _)| index as u16)
+        })
+    }
+
+    /// Returns the index of an entry equal to the given one, adding it if it is not present.
+    pub fn intern(&mut self, entry: ConstantPoolEntry) -> Result<u16, ClassWriterError> {
+        self.index_all();
+        match self.index.get(&entry) {
+            Some(index) => Ok(*index),
+            None => self.add(entry),
+        }
+    }
+
+    /// Adds the entries not yet indexed to the index, decoding the lazy ones
+    fn index_all(&mut self) {
+        if self.indexed_len == self.entries.len() {
+            return;
+        }
+        let mut index = std::mem::take(&mut self.index);
+        for (slot, entry) in self.iter_from(self.indexed_len) {
+            index.entry(entry.clone()).or_insert(slot as u16);
+        }
+        self.index = index;
+        self.indexed_len = self.entries.len();
+    }
+
+    pub fn intern_utf8(&mut self, text: &str) -> Result<u16, ClassWriterError> {
+        self.intern(ConstantPoolEntry::Utf8(text.to_string()))
+    }
+
+    pub fn intern_class_reference(&mut self, class_name: &str) -> Result<u16, ClassWriterError> {
+        let name_index = self.intern_utf8(class_name)?;
+        self.intern(ConstantPoolEntry::ClassReference(name_index))
+    }
+
+    pub fn intern_string_reference(&mut self, text: &str) -> Result<u16, ClassWriterError> {
+        let text_index = self.intern_utf8(text)?;
+        self.intern(ConstantPoolEntry::StringReference(text_index))
+    }
+
+    pub fn intern_name_and_type(
+        &mut self,
+        name: &str,
+        type_descriptor: &str,
+    ) -> Result<u16, ClassWriterError> {
+        let name_index = self.intern_utf8(name)?;
+        let type_index = self.intern_utf8(type_descriptor)?;
+        self.intern(ConstantPoolEntry::NameAndTypeDescriptor(
+            name_index, type_index,
+        ))
+    }
+
+    pub fn intern_field_reference(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> Result<u16, ClassWriterError> {
+        let class_index = self.intern_class_reference(class_name)?;
+        let name_and_type_index = self.intern_name_and_type(name, type_descriptor)?;
+        self.intern(ConstantPoolEntry::FieldReference(
+            class_index,
+            name_and_type_index,
+        ))
+    }
+
+    pub fn intern_method_reference(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> Result<u16, ClassWriterError> {
+        let class_index = self.intern_class_reference(class_name)?;
+        let name_and_type_index = self.intern_name_and_type(name, type_descriptor)?;
+        self.intern(ConstantPoolEntry::MethodReference(
+            class_index,
+            name_and_type_index,
+        ))
+    }
+
+    pub fn intern_interface_method_reference(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        type_descriptor: &str,
+    ) -> Result<u16, ClassWriterError> {
+        let class_index = self.intern_class_reference(class_name)?;
+        let name_and_type_index = self.intern_name_and_type(name, type_descriptor)?;
+        self.intern(ConstantPoolEntry::InterfaceMethodReference(
+            class_index,
+            name_and_type_index,
+        ))
     }
 
     /// Accesses an entry given its index. Note that it must be 1-based!
@@ -82,11 +284,23 @@ impl ConstantPool {
                 ConstantPoolPhysicalEntry::MultiByteEntryTombstone() => {
                     Err(InvalidConstantPoolIndexError::new(input_index))
                 }
+                ConstantPoolPhysicalEntry::Lazy(offset, entry) => {
+                    Ok(entry.get_or_init(|| self.decode_lazy(*offset)))
+                }
             }
         }
     }
 
-    fn fmt_entry(&self, idx: u16) -> Result<String, InvalidConstantPoolIndexError> {
+    fn decode_lazy(&self, offset: usize) -> ConstantPoolEntry {
+        // Cannot fail, since the entries are validated by scan_constants
+        decode_constant(&self.raw[offset..]).unwrap_or(ConstantPoolEntry::Utf8(String::new()))
+    }
+
+    fn fmt_entry(&self, idx: u16, depth: usize) -> Result<String, InvalidConstantPoolIndexError> {
+        if depth > MAX_REFERENCE_DEPTH {
+            return Err(InvalidConstantPoolIndexError::new(idx));
+        }
+        let fmt_entry = |idx: u16| self.fmt_entry(idx, depth + 1);
         let entry = self.get(idx)?;
         let text = match entry {
             ConstantPoolEntry::Utf8(ref s) => format!("String: \"{s}\""),
@@ -95,18 +309,18 @@ impl ConstantPool {
             ConstantPoolEntry::Long(n) => format!("Long: {n}"),
             ConstantPoolEntry::Double(n) => format!("Double: {n}"),
             ConstantPoolEntry::ClassReference(n) => {
-                format!("ClassReference: {} => ({})", n, self.fmt_entry(*n)?)
+                format!("ClassReference: {} => ({})", n, fmt_entry(*n)?)
             }
             ConstantPoolEntry::StringReference(n) => {
-                format!("StringReference: {} => ({})", n, self.fmt_entry(*n)?)
+                format!("StringReference: {} => ({})", n, fmt_entry(*n)?)
             }
             ConstantPoolEntry::FieldReference(i, j) => {
                 format!(
                     "FieldReference: {}, {} => ({}), ({})",
                     i,
                     j,
-                    self.fmt_entry(*i)?,
-                    self.fmt_entry(*j)?
+                    fmt_entry(*i)?,
+                    fmt_entry(*j)?
                 )
             }
             ConstantPoolEntry::MethodReference(i, j) => {
@@ -114,8 +328,8 @@ impl ConstantPool {
                     "MethodReference: {}, {} => ({}), ({})",
                     i,
                     j,
-                    self.fmt_entry(*i)?,
-                    self.fmt_entry(*j)?
+                    fmt_entry(*i)?,
+                    fmt_entry(*j)?
                 )
             }
             ConstantPoolEntry::InterfaceMethodReference(i, j) => {
@@ -123,8 +337,8 @@ impl ConstantPool {
                     "InterfaceMethodReference: {}, {} => ({}), ({})",
                     i,
                     j,
-                    self.fmt_entry(*i)?,
-                    self.fmt_entry(*j)?
+                    fmt_entry(*i)?,
+                    fmt_entry(*j)?
                 )
             }
             &ConstantPoolEntry::NameAndTypeDescriptor(i, j) => {
@@ -132,8 +346,8 @@ impl ConstantPool {
                     "NameAndTypeDescriptor: {}, {} => ({}), ({})",
                     i,
                     j,
-                    self.fmt_entry(i)?,
-                    self.fmt_entry(j)?
+                    fmt_entry(i)?,
+                    fmt_entry(j)?
                 )
             }
         };
@@ -141,6 +355,18 @@ impl ConstantPool {
     }
 
     pub fn text_of(&self, idx: u16) -> Result<String, InvalidConstantPoolIndexError> {
+        self.text_at_depth(idx, 0)
+    }
+
+    fn text_at_depth(
+        &self,
+        idx: u16,
+        depth: usize,
+    ) -> Result<String, InvalidConstantPoolIndexError> {
+        if depth > MAX_REFERENCE_DEPTH {
+            return Err(InvalidConstantPoolIndexError::new(idx));
+        }
+        let text_of = |idx: u16| self.text_at_depth(idx, depth + 1);
         let entry = self.get(idx)?;
         let text = match entry {
             ConstantPoolEntry::Utf8(ref s) => s.clone(),
@@ -148,32 +374,198 @@ impl ConstantPool {
             ConstantPoolEntry::Float(n) => n.to_string(),
             ConstantPoolEntry::Long(n) => n.to_string(),
             ConstantPoolEntry::Double(n) => n.to_string(),
-            ConstantPoolEntry::ClassReference(n) => self.text_of(*n)?,
-            ConstantPoolEntry::StringReference(n) => self.text_of(*n)?,
+            ConstantPoolEntry::ClassReference(n) => text_of(*n)?,
+            ConstantPoolEntry::StringReference(n) => text_of(*n)?,
             ConstantPoolEntry::FieldReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
+                format!("{}.{}", text_of(*i)?, text_of(*j)?)
             }
             ConstantPoolEntry::MethodReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
+                format!("{}.{}", text_of(*i)?, text_of(*j)?)
             }
             ConstantPoolEntry::InterfaceMethodReference(i, j) => {
-                format!("{}.{}", self.text_of(*i)?, self.text_of(*j)?)
+                format!("{}.{}", text_of(*i)?, text_of(*j)?)
             }
             ConstantPoolEntry::NameAndTypeDescriptor(i, j) => {
-                format!("{}: {}", self.text_of(*i)?, self.text_of(*j)?)
+                format!("{}: {}", text_of(*i)?, text_of(*j)?)
             }
         };
         Ok(text)
     }
 
-    pub fn iter(&self) -> ConstantPoolIterator {
+    /// Resolves a field, method or interface method reference
+    pub fn member_reference(
+        &self,
+        idx: u16,
+    ) -> Result<MemberReference, InvalidConstantPoolIndexError> {
+        match self.get(idx)? {
+            ConstantPoolEntry::FieldReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::MethodReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::InterfaceMethodReference(class_index, name_and_type_index) => {
+                match self.get(*name_and_type_index)? {
+                    ConstantPoolEntry::NameAndTypeDescriptor(name_index, type_index) => {
+                        Ok(MemberReference {
+                            class_name: self.text_of(*class_index)?,
+                            name: self.text_of(*name_index)?,
+                            type_descriptor: self.text_of(*type_index)?,
+                        })
+                    }
+                    _ => Err(InvalidConstantPoolIndexError::new(*name_and_type_index)),
+                }
+            }
+            _ => Err(InvalidConstantPoolIndexError::new(idx)),
+        }
+    }
+
+    /// Serializes the constant pool, including the count of entries, in the class file format
+    pub fn encode(&self, bytes: &mut Vec<u8>) -> Result<(), ClassWriterError> {
+        let count = self.entries.len() + 1;
+        let count = u16::try_from(count).map_err(|_| ClassWriterError::TooManyConstants(count))?;
+        bytes.extend_from_slice(&count.to_be_bytes());
+        for (_, entry) in self.iter() {
+            match entry {
+                ConstantPoolEntry::Utf8(text) => {
+                    let encoded = to_java_cesu8(text);
+                    let len = u16::try_from(encoded.len())
+                        .map_err(|_| ClassWriterError::StringTooLong(encoded.len()))?;
+                    bytes.push(1);
+                    bytes.extend_from_slice(&len.to_be_bytes());
+                    bytes.extend_from_slice(&encoded);
+                }
+                ConstantPoolEntry::Integer(value) => {
+                    bytes.push(3);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolEntry::Float(value) => {
+                    bytes.push(4);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolEntry::Long(value) => {
+                    bytes.push(5);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolEntry::Double(value) => {
+                    bytes.push(6);
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+                ConstantPoolEntry::ClassReference(index) => {
+                    bytes.push(7);
+                    bytes.extend_from_slice(&index.to_be_bytes());
+                }
+                ConstantPoolEntry::StringReference(index) => {
+                    bytes.push(8);
+                    bytes.extend_from_slice(&index.to_be_bytes());
+                }
+                ConstantPoolEntry::FieldReference(first, second)
+                | ConstantPoolEntry::MethodReference(first, second)
+                | ConstantPoolEntry::InterfaceMethodReference(first, second)
+                | ConstantPoolEntry::NameAndTypeDescriptor(first, second) => {
+                    bytes.push(match entry {
+                        ConstantPoolEntry::FieldReference(..) => 9,
+                        ConstantPoolEntry::MethodReference(..) => 10,
+                        ConstantPoolEntry::InterfaceMethodReference(..) => 11,
+                        _ => 12,
+                    });
+                    bytes.extend_from_slice(&first.to_be_bytes());
+                    bytes.extend_from_slice(&second.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of slots taken by the entries, where long and double constants take two.
+    /// The `constant_pool_count` of a class file is one more than this.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> ConstantPoolIterator<'_> {
+        self.iter_from(0)
+    }
+
+    /// Iterates over the entries after the first `skipped` slots
+    fn iter_from(&self, skipped: usize) -> ConstantPoolIterator<'_> {
         ConstantPoolIterator {
             pool: self,
-            index: 0,
+            index: skipped,
         }
     }
 }
 
+/// Reads the count and the entries of a constant pool in the class file format, validating
+/// them without decoding. Returns the offset in the buffer of each entry, or `None` for the
+/// unused slot that follows long and double constants.
+pub(crate) fn scan_constants(buffer: &mut Buffer) -> ClassReaderResult<Vec<Option<usize>>> {
+    let constants_count = buffer.read_u16()?.into_usize_safe();
+    let mut offsets = Vec::with_capacity(constants_count);
+    while offsets.len() + 1 < constants_count {
+        offsets.push(Some(buffer.position()));
+        let tag = buffer.read_u8()?;
+        match tag {
+            1 => {
+                let len = buffer.read_u16()?.into_usize_safe();
+                from_java_cesu8(buffer.read_bytes(len)?)
+                    .map_err(|_| BufferError::InvalidCesu8String)?;
+            }
+            3 | 4 | 9 | 10 | 11 | 12 => {
+                buffer.read_bytes(4)?;
+            }
+            5 | 6 => {
+                offsets.push(None);
+                buffer.read_bytes(8)?;
+            }
+            7 | 8 => {
+                buffer.read_bytes(2)?;
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "Unknown constant type: 0x{tag:X}"
+                )))
+            }
+        }
+    }
+    Ok(offsets)
+}
+
+/// Decodes the entry of the constant pool starting at the first byte of the slice
+pub(crate) fn decode_constant(bytes: &[u8]) -> ClassReaderResult<ConstantPoolEntry> {
+    let mut buffer = Buffer::new(bytes);
+    let tag = buffer.read_u8()?;
+    Ok(match tag {
+        1 => {
+            let len = buffer.read_u16()?.into_usize_safe();
+            ConstantPoolEntry::Utf8(buffer.read_utf8(len)?)
+        }
+        3 => ConstantPoolEntry::Integer(buffer.read_i32()?),
+        4 => ConstantPoolEntry::Float(buffer.read_f32()?),
+        5 => ConstantPoolEntry::Long(buffer.read_i64()?),
+        6 => ConstantPoolEntry::Double(buffer.read_f64()?),
+        7 => ConstantPoolEntry::ClassReference(buffer.read_u16()?),
+        8 => ConstantPoolEntry::StringReference(buffer.read_u16()?),
+        9 => ConstantPoolEntry::FieldReference(buffer.read_u16()?, buffer.read_u16()?),
+        10 => ConstantPoolEntry::MethodReference(buffer.read_u16()?, buffer.read_u16()?),
+        11 => ConstantPoolEntry::InterfaceMethodReference(buffer.read_u16()?, buffer.read_u16()?),
+        12 => ConstantPoolEntry::NameAndTypeDescriptor(buffer.read_u16()?, buffer.read_u16()?),
+        _ => {
+            return Err(ClassReaderError::invalid_class_data(format!(
+                "Unknown constant type: 0x{tag:X}"
+            )))
+        }
+    })
+}
+
+/// A reference to a field or a method of a class, resolved from the constant pool
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MemberReference {
+    pub class_name: String,
+    pub name: String,
+    pub type_descriptor: String,
+}
+
 pub struct ConstantPoolIterator<'a> {
     pool: &'a ConstantPool,
     index: usize,
@@ -190,6 +582,9 @@ impl<'a> Iterator for ConstantPoolIterator<'a> {
             match entry {
                 ConstantPoolPhysicalEntry::Entry(entry) => Some((index, entry)),
                 ConstantPoolPhysicalEntry::MultiByteEntryTombstone() => self.next(),
+                ConstantPoolPhysicalEntry::Lazy(offset, entry) => {
+                    Some((index, entry.get_or_init(|| self.pool.decode_lazy(*offset))))
+                }
             }
         } else {
             None
@@ -197,13 +592,41 @@ impl<'a> Iterator for ConstantPoolIterator<'a> {
     }
 }
 
+/// The pool is serialized as the list of its entries, without the unused slots that follow
+/// long and double constants, so that deserializing it gives back the same indexes
+#[cfg(feature = "serde")]
+impl serde::Serialize for ConstantPool {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entries: Vec<&ConstantPoolEntry> = self.iter().map(|(_, entry)| entry).collect();
+        entries.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConstantPool {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut pool = ConstantPool::new();
+        for entry in Vec::<ConstantPoolEntry>::deserialize(deserializer)? {
+            pool.add(entry).map_err(serde::de::Error::custom)?;
+        }
+        Ok(pool)
+    }
+}
+
 impl fmt::Display for ConstantPool {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Constant pool: (size: {})", self.entries.len())?;
-        for (raw_idx, _) in self.entries.iter().enumerate() {
+        for (raw_idx, entry) in self.entries.iter().enumerate() {
+            if matches!(entry, ConstantPoolPhysicalEntry::MultiByteEntryTombstone()) {
+                continue;
+            }
             let index = (raw_idx + 1) as u16;
-            let entry_text = self.fmt_entry(index).map_err(|_| fmt::Error)?;
-            writeln!(f, "    {}, {}", index, entry_text)?;
+            // Malformed classes can contain invalid references, which are shown rather than
+            // making the whole formatting fail
+            match self.fmt_entry(index, 0) {
+                Ok(entry_text) => writeln!(f, "    {}, {}", index, entry_text)?,
+                Err(err) => writeln!(f, "    {}, <{}>", index, err)?,
+            }
         }
         Ok(())
     }
@@ -211,23 +634,31 @@ impl fmt::Display for ConstantPool {
 
 #[cfg(test)]
 mod tests {
-    use crate::constant_pool::{ConstantPool, ConstantPoolEntry, InvalidConstantPoolIndexError};
+    use crate::{
+        buffer::Buffer,
+        class_writer_error::ClassWriterError,
+        constant_pool::{
+            scan_constants, ConstantPool, ConstantPoolEntry, InvalidConstantPoolIndexError,
+        },
+    };
 
     #[test]
     fn constant_pool_works() {
         let mut cp = ConstantPool::new();
-        cp.add(ConstantPoolEntry::Utf8("hey".to_string()));
-        cp.add(ConstantPoolEntry::Integer(1));
-        cp.add(ConstantPoolEntry::Float(2.1));
-        cp.add(ConstantPoolEntry::Long(123));
-        cp.add(ConstantPoolEntry::Double(3.56));
-        cp.add(ConstantPoolEntry::ClassReference(1));
-        cp.add(ConstantPoolEntry::StringReference(1));
-        cp.add(ConstantPoolEntry::Utf8("joe".to_string()));
-        cp.add(ConstantPoolEntry::FieldReference(1, 10));
-        cp.add(ConstantPoolEntry::MethodReference(1, 10));
-        cp.add(ConstantPoolEntry::InterfaceMethodReference(1, 10));
-        cp.add(ConstantPoolEntry::NameAndTypeDescriptor(1, 10));
+        cp.add(ConstantPoolEntry::Utf8("hey".to_string())).unwrap();
+        cp.add(ConstantPoolEntry::Integer(1)).unwrap();
+        cp.add(ConstantPoolEntry::Float(2.1)).unwrap();
+        cp.add(ConstantPoolEntry::Long(123)).unwrap();
+        cp.add(ConstantPoolEntry::Double(3.56)).unwrap();
+        cp.add(ConstantPoolEntry::ClassReference(1)).unwrap();
+        cp.add(ConstantPoolEntry::StringReference(1)).unwrap();
+        cp.add(ConstantPoolEntry::Utf8("joe".to_string())).unwrap();
+        cp.add(ConstantPoolEntry::FieldReference(1, 10)).unwrap();
+        cp.add(ConstantPoolEntry::MethodReference(1, 10)).unwrap();
+        cp.add(ConstantPoolEntry::InterfaceMethodReference(1, 10))
+            .unwrap();
+        cp.add(ConstantPoolEntry::NameAndTypeDescriptor(1, 10))
+            .unwrap();
 
         assert_eq!(
             ConstantPoolEntry::Utf8("hey".to_string()),
@@ -281,14 +712,99 @@ mod tests {
     #[test]
     fn iterators_work() {
         let mut cp = ConstantPool::new();
-        cp.add(ConstantPoolEntry::Integer(1));
-        cp.add(ConstantPoolEntry::Long(2));
-        cp.add(ConstantPoolEntry::Integer(3));
+        cp.add(ConstantPoolEntry::Integer(1)).unwrap();
+        cp.add(ConstantPoolEntry::Long(2)).unwrap();
+        cp.add(ConstantPoolEntry::Integer(3)).unwrap();
 
+        assert_eq!(4, cp.len());
         let mut iter = cp.iter();
         assert_eq!(iter.next(), Some((1, &ConstantPoolEntry::Integer(1))));
         assert_eq!(iter.next(), Some((2, &ConstantPoolEntry::Long(2))));
         assert_eq!(iter.next(), Some((4, &ConstantPoolEntry::Integer(3))));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn lazy_constant_pool_decodes_entries_on_access() {
+        let mut cp = ConstantPool::new();
+        cp.add(ConstantPoolEntry::Utf8("hey".to_string())).unwrap();
+        cp.add(ConstantPoolEntry::Long(123)).unwrap();
+        cp.add(ConstantPoolEntry::ClassReference(1)).unwrap();
+        let mut bytes = Vec::new();
+        cp.encode(&mut bytes).unwrap();
+
+        let offsets = scan_constants(&mut Buffer::new(&bytes)).unwrap();
+        assert_eq!(vec![Some(2), Some(8), None, Some(17)], offsets);
+        let lazy = ConstantPool::lazy(&bytes, offsets);
+        assert_eq!(ConstantPoolEntry::Long(123), *lazy.get(2).unwrap());
+        assert_eq!(Err(InvalidConstantPoolIndexError::new(3)), lazy.get(3));
+        assert_eq!("hey", lazy.text_of(4).unwrap());
+        assert_eq!(
+            cp.iter().collect::<Vec<_>>(),
+            lazy.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn intern_reuses_existing_entries() {
+        let mut cp = ConstantPool::new();
+        cp.add(ConstantPoolEntry::Double(0.0)).unwrap();
+        cp.add(ConstantPoolEntry::Utf8("hey".to_string())).unwrap();
+        cp.add(ConstantPoolEntry::Utf8("hey".to_string())).unwrap();
+
+        assert_eq!(Ok(3), cp.intern_utf8("hey"));
+        assert_eq!(Ok(5), cp.intern_class_reference("hey"));
+        assert_eq!(Ok(5), cp.intern_class_reference("hey"));
+        assert_eq!(Ok(1), cp.intern(ConstantPoolEntry::Double(0.0)));
+        assert_eq!(Ok(6), cp.intern(ConstantPoolEntry::Double(-0.0)));
+        assert_eq!(Some(6), cp.find(&ConstantPoolEntry::Double(-0.0)));
+        assert_eq!(Ok(8), cp.intern(ConstantPoolEntry::Float(f32::NAN)));
+        assert_eq!(Ok(8), cp.intern(ConstantPoolEntry::Float(f32::NAN)));
+    }
+
+    #[test]
+    fn intern_finds_the_entries_of_a_lazy_constant_pool() {
+        let mut cp = ConstantPool::new();
+        cp.add(ConstantPoolEntry::Utf8("hey".to_string())).unwrap();
+        cp.add(ConstantPoolEntry::Long(123)).unwrap();
+        cp.add(ConstantPoolEntry::ClassReference(1)).unwrap();
+        let mut bytes = Vec::new();
+        cp.encode(&mut bytes).unwrap();
+
+        let offsets = scan_constants(&mut Buffer::new(&bytes)).unwrap();
+        let mut lazy = ConstantPool::lazy(&bytes, offsets);
+        assert_eq!(Some(2), lazy.find(&ConstantPoolEntry::Long(123)));
+        assert_eq!(Ok(4), lazy.intern_class_reference("hey"));
+        assert_eq!(Ok(5), lazy.intern_utf8("joe"));
+        assert_eq!(
+            Some(5),
+            lazy.find(&ConstantPoolEntry::Utf8("joe".to_string()))
+        );
+    }
+
+    #[test]
+    fn adding_past_the_last_slot_is_an_error() {
+        let mut cp = ConstantPool::new();
+        for value in 0..65533 {
+            cp.add(ConstantPoolEntry::Integer(value)).unwrap();
+        }
+
+        assert_eq!(
+            Err(ClassWriterError::TooManyConstants(65536)),
+            cp.add(ConstantPoolEntry::Long(0))
+        );
+        assert_eq!(Ok(65534), cp.intern_utf8("last"));
+        assert_eq!(
+            Err(ClassWriterError::TooManyConstants(65536)),
+            cp.intern_utf8("one too many")
+        );
+        let mut bytes = Vec::new();
+        assert!(cp.encode(&mut bytes).is_ok());
+    }
+
+    #[test]
+    fn scanning_rejects_unknown_constants() {
+        let bytes = [0x00, 0x02, 0x42];
+        assert!(scan_constants(&mut Buffer::new(&bytes)).is_err());
+    }
 }
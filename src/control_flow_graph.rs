@@ -0,0 +1,473 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    fmt::Formatter,
+    ops::Range,
+};
+
+use crate::{
+    class_file_method::ClassFileMethodCode, class_reader_error::ClassReaderError,
+    dominator_tree::DominatorTree, instruction::Instruction, loops::Loops,
+    program_counter::ProgramCounter, type_conversion::ToUsizeSafe,
+};
+
+/// Index of a block in [ControlFlowGraph::blocks]
+pub type BlockId = usize;
+
+/// A sequence of instructions that is always executed from the first to the last one.
+/// Only the first instruction can be the target of a jump, and only the last one can jump.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    /// The addresses of the instructions of the block
+    pub range: Range<ProgramCounter>,
+    pub instructions: Vec<(ProgramCounter, Instruction)>,
+}
+
+impl BasicBlock {
+    /// Returns the last instruction of the block, which decides its successors
+    pub fn last_instruction(&self) -> &Instruction {
+        &self
+            .instructions
+            .last()
+            .expect("basic blocks are never empty")
+            .1
+    }
+}
+
+/// How the control can move from a block to another one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution continues with the next instruction
+    Normal,
+    /// A conditional or unconditional jump, or a jump to a subroutine
+    Branch,
+    /// A case of a `tableswitch` or `lookupswitch`, with its key, or `None` for the default
+    SwitchCase(Option<i32>),
+    /// An exception thrown in the block and caught by the handler, with the class it catches,
+    /// or `None` for handlers that catch everything, i.e. `finally` blocks
+    Exceptional(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: BlockId,
+    pub to: BlockId,
+    pub kind: EdgeKind,
+}
+
+/// The control flow graph of a method's code. The first block is the entry point.
+///
+/// Blocks are split at jump targets, switch cases, exception handlers and at the boundaries
+/// of the ranges covered by the exception table, so that every block is either entirely
+/// covered by an exception handler or not at all. The exceptional edges are listed in the
+/// order of the exception table, which is the order in which the JVM looks up the handler.
+///
+/// Subroutines are approximated: `jsr` has a branch edge to the subroutine and a normal edge
+/// to the following instruction, while `ret` has no successors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+    edges: Vec<Edge>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl ControlFlowGraph {
+    /// Builds the graph of the given code. Fails if the code cannot be parsed, or if it jumps
+    /// to an address that is not an instruction.
+    pub fn new(code: &ClassFileMethodCode) -> Result<Self, ClassReaderError> {
+        let instructions = Instruction::parse_instructions(&code.code)?;
+        let code_length = code.code.len();
+        let is_instruction_or_end = |address: usize| {
+            address == code_length
+                || instructions
+                    .binary_search_by_key(&address, |(address, _)| *address)
+                    .is_ok()
+        };
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        for (i, (address, instruction)) in instructions.iter().enumerate() {
+            for target in instruction.jump_targets() {
+                let target = target.into_usize_safe();
+                if target == code_length || !is_instruction_or_end(target) {
+                    return Err(invalid_address(*address, "jumps to", target));
+                }
+                leaders.insert(target);
+            }
+            let ends_block =
+                !instruction.jump_targets().is_empty() || !instruction.can_fall_through();
+            if ends_block {
+                if let Some((next_address, _)) = instructions.get(i + 1) {
+                    leaders.insert(*next_address);
+                }
+            }
+        }
+        for entry in code.exception_table.entries() {
+            let start = entry.range.start.0.into_usize_safe();
+            let end = entry.range.end.0.into_usize_safe();
+            let handler = entry.handler_pc.0.into_usize_safe();
+            for address in [start, end, handler] {
+                if !is_instruction_or_end(address) || (address == code_length && address != end) {
+                    return Err(invalid_address(
+                        handler,
+                        "exception handler refers to",
+                        address,
+                    ));
+                }
+                leaders.insert(address);
+            }
+        }
+        leaders.remove(&code_length);
+
+        let mut blocks: Vec<BasicBlock> = Vec::new();
+        for (address, instruction) in instructions {
+            if leaders.contains(&address) || blocks.is_empty() {
+                blocks.push(BasicBlock {
+                    range: ProgramCounter(address as u16)..ProgramCounter(address as u16),
+                    instructions: Vec::new(),
+                });
+            }
+            let block = blocks.last_mut().expect("a block was just pushed");
+            block
+                .instructions
+                .push((ProgramCounter(address as u16), instruction));
+        }
+        let block_starts: HashMap<usize, BlockId> = blocks
+            .iter()
+            .enumerate()
+            .map(|(id, block)| (block.range.start.0.into_usize_safe(), id))
+            .collect();
+        for id in 0..blocks.len() {
+            let end = blocks
+                .get(id + 1)
+                .map_or(code_length, |next| next.range.start.0.into_usize_safe());
+            blocks[id].range.end = ProgramCounter(end as u16);
+        }
+
+        let mut edges = Vec::new();
+        for (id, block) in blocks.iter().enumerate() {
+            let mut add_edge = |to: u16, kind| {
+                edges.push(Edge {
+                    from: id,
+                    to: block_starts[&to.into_usize_safe()],
+                    kind,
+                })
+            };
+            let last_instruction = block.last_instruction();
+            match last_instruction {
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    jump_addresses,
+                    ..
+                } => {
+                    for (key, target) in (*low..).zip(jump_addresses.iter()) {
+                        add_edge(*target, EdgeKind::SwitchCase(Some(key)));
+                    }
+                    add_edge(*default, EdgeKind::SwitchCase(None));
+                }
+                Instruction::Lookupswitch {
+                    default,
+                    match_pairs,
+                } => {
+                    for (key, target) in match_pairs {
+                        add_edge(*target, EdgeKind::SwitchCase(Some(*key)));
+                    }
+                    add_edge(*default, EdgeKind::SwitchCase(None));
+                }
+                instruction => {
+                    for target in instruction.jump_targets() {
+                        add_edge(target, EdgeKind::Branch);
+                    }
+                }
+            }
+            if last_instruction.can_fall_through()
+                && block.range.end.0.into_usize_safe() < code_length
+            {
+                add_edge(block.range.end.0, EdgeKind::Normal);
+            }
+            for entry in code.exception_table.entries() {
+                if entry.range.contains(&block.range.start) {
+                    add_edge(
+                        entry.handler_pc.0,
+                        EdgeKind::Exceptional(entry.catch_class.clone()),
+                    );
+                }
+            }
+        }
+
+        let mut successors = vec![Vec::new(); blocks.len()];
+        let mut predecessors = vec![Vec::new(); blocks.len()];
+        for (index, edge) in edges.iter().enumerate() {
+            successors[edge.from].push(index);
+            predecessors[edge.to].push(index);
+        }
+        Ok(Self {
+            blocks,
+            edges,
+            successors,
+            predecessors,
+        })
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    pub fn block(&self, id: BlockId) -> &BasicBlock {
+        &self.blocks[id]
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Returns the block that contains the instruction at the given address
+    pub fn block_at(&self, pc: ProgramCounter) -> Option<BlockId> {
+        let id = self
+            .blocks
+            .partition_point(|block| block.range.start <= pc)
+            .checked_sub(1)?;
+        self.blocks[id].range.contains(&pc).then_some(id)
+    }
+
+    /// Returns the edges leaving the given block
+    pub fn successors(&self, id: BlockId) -> impl Iterator<Item = &Edge> {
+        self.successors[id].iter().map(|index| &self.edges[*index])
+    }
+
+    /// Returns the edges entering the given block
+    pub fn predecessors(&self, id: BlockId) -> impl Iterator<Item = &Edge> {
+        self.predecessors[id]
+            .iter()
+            .map(|index| &self.edges[*index])
+    }
+
+    /// Returns, for each block, the blocks of its successors and of its predecessors
+    pub(crate) fn adjacency(&self) -> (Vec<Vec<BlockId>>, Vec<Vec<BlockId>>) {
+        let mut successors = vec![Vec::new(); self.blocks.len()];
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for edge in self.edges.iter() {
+            successors[edge.from].push(edge.to);
+            predecessors[edge.to].push(edge.from);
+        }
+        (successors, predecessors)
+    }
+
+    pub fn dominator_tree(&self) -> DominatorTree {
+        DominatorTree::dominators(self)
+    }
+
+    pub fn post_dominator_tree(&self) -> DominatorTree {
+        DominatorTree::post_dominators(self)
+    }
+
+    /// Finds the natural loops of the graph
+    pub fn loops(&self) -> Loops {
+        Loops::new(self, &self.dominator_tree())
+    }
+
+    /// Returns the blocks that can be reached from the entry point, ordered by address
+    pub fn reachable_blocks(&self) -> Vec<BlockId> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut pending: Vec<BlockId> =
+            (!self.blocks.is_empty()).then_some(0).into_iter().collect();
+        while let Some(id) = pending.pop() {
+            if !std::mem::replace(&mut reachable[id], true) {
+                pending.extend(self.successors(id).map(|edge| edge.to));
+            }
+        }
+        (0..self.blocks.len()).filter(|id| reachable[*id]).collect()
+    }
+
+    /// Returns the blocks that can never be executed, i.e. dead code
+    pub fn unreachable_blocks(&self) -> Vec<BlockId> {
+        let reachable = self.reachable_blocks();
+        (0..self.blocks.len())
+            .filter(|id| !reachable.contains(id))
+            .collect()
+    }
+}
+
+/// Returns, for each of the given instructions of the code, the indexes of the instructions
+/// that can be executed after it. Every instruction covered by an exception handler has the
+/// handler as a successor, since it can be reached with the state after the instruction.
+/// Like in [ControlFlowGraph], `ret` has no successors.
+pub(crate) fn instruction_successors(
+    code: &ClassFileMethodCode,
+    instructions: &[(usize, Instruction)],
+) -> Result<Vec<Vec<usize>>, ClassReaderError> {
+    let index_of = |source: usize, action: &str, address: usize| {
+        instructions
+            .binary_search_by_key(&address, |(address, _)| *address)
+            .map_err(|_| invalid_address(source, action, address))
+    };
+    let mut successors = Vec::with_capacity(instructions.len());
+    for (index, (address, instruction)) in instructions.iter().enumerate() {
+        let mut targets = Vec::new();
+        for target in instruction.jump_targets() {
+            targets.push(index_of(*address, "jumps to", target.into_usize_safe())?);
+        }
+        if instruction.can_fall_through() && index + 1 < instructions.len() {
+            targets.push(index + 1);
+        }
+        for entry in code.exception_table.lookup(ProgramCounter(*address as u16)) {
+            let handler = entry.handler_pc.0.into_usize_safe();
+            targets.push(index_of(*address, "is covered by a handler at", handler)?);
+        }
+        targets.sort();
+        targets.dedup();
+        successors.push(targets);
+    }
+    Ok(successors)
+}
+
+fn invalid_address(address: usize, action: &str, target: usize) -> ClassReaderError {
+    ClassReaderError::InvalidClassData(
+        format!("instruction at address {address} {action} address {target}, which is not an instruction"),
+        None,
+    )
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeKind::Normal => f.write_str("normal"),
+            EdgeKind::Branch => f.write_str("branch"),
+            EdgeKind::SwitchCase(Some(key)) => write!(f, "case {key}"),
+            EdgeKind::SwitchCase(None) => f.write_str("default"),
+            EdgeKind::Exceptional(Some(class)) => write!(f, "catch {class}"),
+            EdgeKind::Exceptional(None) => f.write_str("catch any"),
+        }
+    }
+}
+
+impl fmt::Display for ControlFlowGraph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (id, block) in self.blocks.iter().enumerate() {
+            writeln!(
+                f,
+                "block {id} [{}..{}):",
+                block.range.start, block.range.end
+            )?;
+            for (address, instruction) in block.instructions.iter() {
+                writeln!(f, "    {address:3} {instruction:?}")?;
+            }
+            for edge in self.successors(id) {
+                writeln!(f, "    -> block {} ({})", edge.to, edge.kind)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_file_method::ClassFileMethodCode,
+        code_builder::{test_method, CodeBuilder, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        control_flow_graph::{Edge, EdgeKind},
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+    };
+
+    fn code(descriptor: &str, build: impl FnOnce(&mut CodeBuilder)) -> ClassFileMethodCode {
+        let mut constants = ConstantPool::new();
+        test_method(
+            &mut constants,
+            MethodFlags::STATIC,
+            "test",
+            descriptor,
+            build,
+        )
+        .code
+        .unwrap()
+    }
+
+    fn edge(from: usize, to: usize, kind: EdgeKind) -> Edge {
+        Edge { from, to, kind }
+    }
+
+    #[test]
+    fn splits_blocks_at_branches() {
+        let code = code("(I)I", |code| {
+            let else_label = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, else_label)
+                .iconst(1)
+                .instruction(Instruction::Ireturn)
+                .place_label(else_label)
+                .iconst(0)
+                .instruction(Instruction::Ireturn);
+        });
+        let graph = code.control_flow_graph().unwrap();
+
+        assert_eq!(3, graph.blocks().len());
+        assert_eq!(ProgramCounter(0)..ProgramCounter(4), graph.block(0).range);
+        assert_eq!(
+            vec![edge(0, 2, EdgeKind::Branch), edge(0, 1, EdgeKind::Normal),],
+            graph.edges()
+        );
+        assert_eq!(
+            vec![&edge(0, 2, EdgeKind::Branch)],
+            graph.predecessors(2).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(1), graph.block_at(ProgramCounter(5)));
+        assert_eq!(None, graph.block_at(ProgramCounter(8)));
+        assert!(graph.unreachable_blocks().is_empty());
+    }
+
+    #[test]
+    fn has_an_edge_for_each_switch_case() {
+        let code = code("(I)V", |code| {
+            let first = code.new_label();
+            let default = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .lookupswitch(default, vec![(10, first), (20, default)])
+                .place_label(first)
+                .instruction(Instruction::Return)
+                .place_label(default)
+                .instruction(Instruction::Return);
+        });
+        let graph = code.control_flow_graph().unwrap();
+
+        assert_eq!(
+            vec![
+                edge(0, 1, EdgeKind::SwitchCase(Some(10))),
+                edge(0, 2, EdgeKind::SwitchCase(Some(20))),
+                edge(0, 2, EdgeKind::SwitchCase(None)),
+            ],
+            graph.edges()
+        );
+    }
+
+    #[test]
+    fn finds_unreachable_blocks() {
+        let code = code("()V", |code| {
+            let end = code.new_label();
+            code.jump(JumpKind::Goto, end)
+                .instruction(Instruction::Nop)
+                .place_label(end)
+                .instruction(Instruction::Return);
+        });
+        let graph = code.control_flow_graph().unwrap();
+
+        assert_eq!(vec![0, 2], graph.reachable_blocks());
+        assert_eq!(vec![1], graph.unreachable_blocks());
+    }
+
+    #[test]
+    fn rejects_jumps_outside_of_the_code() {
+        let code = ClassFileMethodCode {
+            code: vec![0xa7, 0x00, 0x10], // goto 16
+            ..Default::default()
+        };
+        assert_eq!(
+            "invalid class file: instruction at address 0 jumps to address 16, which is not an instruction",
+            code.control_flow_graph().unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,3798 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ops::Range,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    analyzer::{Analyzer, Frame, Value},
+    basic_interpreter::{BasicInterpreter, BasicValue},
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    constant_pool::{ConstantPool, ConstantPoolEntry, MemberReference},
+    control_flow_graph::{BlockId, ControlFlowGraph, EdgeKind},
+    dominator_tree::DominatorTree,
+    exception_table::ExceptionTableEntry,
+    field_flags::FieldFlags,
+    field_type::{BaseType, FieldType},
+    instruction::{Instruction, LocalKind, LocalVariableAccess, NewArrayType, WideInstruction},
+    loops::Loop,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+};
+
+const INT: FieldType = FieldType::Base(BaseType::Int);
+const LONG: FieldType = FieldType::Base(BaseType::Long);
+const FLOAT: FieldType = FieldType::Base(BaseType::Float);
+const DOUBLE: FieldType = FieldType::Base(BaseType::Double);
+const BOOLEAN: FieldType = FieldType::Base(BaseType::Boolean);
+
+/// Decompiles a class into pseudo-Java source code.
+///
+/// The declarations are rendered from the flags and the types of the class, its fields and
+/// its methods. The bodies of the methods are recovered from the bytecode: expressions are
+/// rebuilt from the operand stack, and the control flow graph is structured into `if`/`else`,
+/// loops, `switch` and `try`/`catch`/`finally`, falling back to labels and `goto` for the
+/// jumps that do not fit. Local variables are named from the `LocalVariableTable` when the
+/// class was compiled with debug information, and `argN` and `varN` otherwise.
+///
+/// The output is meant to be read, not compiled: it does not declare imports, generics are
+/// erased, and the code of methods that cannot be decompiled is listed as comments.
+pub fn decompile(class: &ClassFile) -> String {
+    let package = package_of(&class.name);
+    let mut printer = Printer::new(package);
+    if let Some(source_file) = &class.source_file {
+        printer.line(&format!("// Compiled from \"{source_file}\""));
+    }
+    if !package.is_empty() {
+        printer.line(&format!("package {};", package.replace('/', ".")));
+        printer.line("");
+    }
+    if class.deprecated {
+        printer.line("@Deprecated");
+    }
+    printer.line(&format!("{} {{", class_declaration(class, package)));
+    printer.indent += 1;
+    for field in class.fields.iter() {
+        printer.line(&field_declaration(field, package));
+    }
+    for (index, method) in class.methods.iter().enumerate() {
+        if index > 0 || !class.fields.is_empty() {
+            printer.line("");
+        }
+        write_method(&mut printer, class, method);
+    }
+    printer.indent -= 1;
+    printer.line("}");
+    printer.out
+}
+
+/// Decompiles a single method of a class, like [decompile] does for all of them
+pub fn decompile_method(class: &ClassFile, method: &ClassFileMethod) -> String {
+    let mut printer = Printer::new(package_of(&class.name));
+    write_method(&mut printer, class, method);
+    printer.out
+}
+
+fn package_of(class_name: &str) -> &str {
+    class_name
+        .rfind('/')
+        .map_or("", |index| &class_name[..index])
+}
+
+/// Returns the name of a class as written in Java source. Classes of `java.lang` and of the
+/// given package are not qualified.
+fn class_name(internal_name: &str, package: &str) -> String {
+    let (class_package, simple_name) = match internal_name.rfind('/') {
+        Some(index) => (&internal_name[..index], &internal_name[index + 1..]),
+        None => ("", internal_name),
+    };
+    let simple_name = simple_name.replace('$', ".");
+    if class_package == package || class_package == "java/lang" {
+        simple_name
+    } else {
+        format!("{}.{simple_name}", class_package.replace('/', "."))
+    }
+}
+
+fn type_name(field_type: &FieldType, package: &str) -> String {
+    match field_type {
+        FieldType::Base(base_type) => base_type_name(base_type).to_string(),
+        FieldType::Object(class) => class_name(class, package),
+        FieldType::Array(component) => format!("{}[]", type_name(component, package)),
+    }
+}
+
+fn base_type_name(base_type: &BaseType) -> &'static str {
+    match base_type {
+        BaseType::Byte => "byte",
+        BaseType::Char => "char",
+        BaseType::Double => "double",
+        BaseType::Float => "float",
+        BaseType::Int => "int",
+        BaseType::Long => "long",
+        BaseType::Short => "short",
+        BaseType::Boolean => "boolean",
+    }
+}
+
+/// Parses the name of a class constant, which is a type descriptor for array classes
+fn class_constant_type(name: String) -> FieldType {
+    if name.starts_with('[') {
+        FieldType::parse(&name).unwrap_or(FieldType::Object(name))
+    } else {
+        FieldType::Object(name)
+    }
+}
+
+fn escape_char(c: char, quote: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        '\\' => "\\\\".to_string(),
+        c if c == quote => format!("\\{c}"),
+        c if c.is_control() => format!("\\u{:04x}", c as u32),
+        c => c.to_string(),
+    }
+}
+
+fn string_literal(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.chars()
+            .map(|c| escape_char(c, '"'))
+            .collect::<String>()
+    )
+}
+
+fn char_literal(value: i32) -> Option<String> {
+    let c = char::from_u32(u32::try_from(value).ok()?)?;
+    Some(format!("'{}'", escape_char(c, '\'')))
+}
+
+fn float_literal(value: f32) -> String {
+    if value.is_nan() {
+        "Float.NaN".to_string()
+    } else if value.is_infinite() {
+        let sign = if value > 0.0 { "POSITIVE" } else { "NEGATIVE" };
+        format!("Float.{sign}_INFINITY")
+    } else {
+        format!("{value:?}F")
+    }
+}
+
+fn double_literal(value: f64) -> String {
+    if value.is_nan() {
+        "Double.NaN".to_string()
+    } else if value.is_infinite() {
+        let sign = if value > 0.0 { "POSITIVE" } else { "NEGATIVE" };
+        format!("Double.{sign}_INFINITY")
+    } else {
+        format!("{value:?}")
+    }
+}
+
+fn class_declaration(class: &ClassFile, package: &str) -> String {
+    let flags = class.flags;
+    let mut words = Vec::new();
+    if flags.contains(ClassAccessFlags::PUBLIC) {
+        words.push("public");
+    }
+    let kind = if flags.contains(ClassAccessFlags::ANNOTATION) {
+        "@interface"
+    } else if flags.contains(ClassAccessFlags::INTERFACE) {
+        "interface"
+    } else if flags.contains(ClassAccessFlags::ENUM) {
+        "enum"
+    } else {
+        if flags.contains(ClassAccessFlags::ABSTRACT) {
+            words.push("abstract");
+        }
+        if flags.contains(ClassAccessFlags::FINAL) {
+            words.push("final");
+        }
+        "class"
+    };
+    words.push(kind);
+    let simple_name = class.name.rsplit(['/', '$']).next().unwrap_or(&class.name);
+    let mut declaration = format!("{} {simple_name}", words.join(" "));
+
+    let interfaces = class
+        .interfaces
+        .iter()
+        .map(|interface| class_name(interface, package))
+        .join(", ");
+    if flags.contains(ClassAccessFlags::INTERFACE) {
+        if !interfaces.is_empty() {
+            declaration.push_str(&format!(" extends {interfaces}"));
+        }
+        return declaration;
+    }
+    if let Some(superclass) = class.superclass.as_deref() {
+        if superclass != "java/lang/Object" && superclass != "java/lang/Enum" {
+            declaration.push_str(&format!(" extends {}", class_name(superclass, package)));
+        }
+    }
+    if !interfaces.is_empty() {
+        declaration.push_str(&format!(" implements {interfaces}"));
+    }
+    declaration
+}
+
+fn field_declaration(field: &ClassFileField, package: &str) -> String {
+    let modifiers = [
+        (FieldFlags::PUBLIC, "public"),
+        (FieldFlags::PROTECTED, "protected"),
+        (FieldFlags::PRIVATE, "private"),
+        (FieldFlags::STATIC, "static"),
+        (FieldFlags::FINAL, "final"),
+        (FieldFlags::TRANSIENT, "transient"),
+        (FieldFlags::VOLATILE, "volatile"),
+    ];
+    let mut declaration = String::new();
+    if field.deprecated {
+        declaration.push_str("@Deprecated ");
+    }
+    for (flag, modifier) in modifiers {
+        if field.flags.contains(flag) {
+            declaration.push_str(modifier);
+            declaration.push(' ');
+        }
+    }
+    declaration.push_str(&format!(
+        "{} {}",
+        type_name(&field.type_descriptor, package),
+        field.name
+    ));
+    if let Some(value) = &field.constant_value {
+        let value = match value {
+            FieldConstantValue::Int(value) => coerce(Expr::int(*value), &field.type_descriptor),
+            FieldConstantValue::Float(value) => Expr::literal(float_literal(*value), FLOAT),
+            FieldConstantValue::Long(value) => Expr::literal(format!("{value}L"), LONG),
+            FieldConstantValue::Double(value) => Expr::literal(double_literal(*value), DOUBLE),
+            FieldConstantValue::String(value) => Expr::literal(
+                string_literal(value),
+                FieldType::Object("java/lang/String".to_string()),
+            ),
+        };
+        declaration.push_str(&format!(" = {}", value.render(package)));
+    }
+    declaration.push(';');
+    declaration
+}
+
+fn write_method(printer: &mut Printer, class: &ClassFile, method: &ClassFileMethod) {
+    let package = printer.package;
+    let names = LocalNames::new(class, method);
+    let is_interface = class.flags.contains(ClassAccessFlags::INTERFACE);
+    let flags = method.flags;
+
+    let mut words: Vec<String> = Vec::new();
+    let modifiers = [
+        (MethodFlags::PUBLIC, "public"),
+        (MethodFlags::PROTECTED, "protected"),
+        (MethodFlags::PRIVATE, "private"),
+        (MethodFlags::ABSTRACT, "abstract"),
+        (MethodFlags::STATIC, "static"),
+        (MethodFlags::FINAL, "final"),
+        (MethodFlags::SYNCHRONIZED, "synchronized"),
+        (MethodFlags::NATIVE, "native"),
+        (MethodFlags::STRICT, "strictfp"),
+    ];
+    for (flag, modifier) in modifiers {
+        if flags.contains(flag) && !(is_interface && flag == MethodFlags::ABSTRACT) {
+            words.push(modifier.to_string());
+        }
+    }
+    let has_body =
+        method.code.is_some() && !flags.intersects(MethodFlags::ABSTRACT | MethodFlags::NATIVE);
+    if is_interface && has_body && !flags.intersects(MethodFlags::STATIC | MethodFlags::PRIVATE) {
+        words.push("default".to_string());
+    }
+
+    let header = if method.name == "<clinit>" {
+        "static".to_string()
+    } else {
+        let last_parameter = names.parameters.len().checked_sub(1);
+        let parameters = names
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, (slot, parameter_type))| {
+                let parameter_type = match parameter_type {
+                    FieldType::Array(component)
+                        if flags.contains(MethodFlags::VARARGS)
+                            && Some(index) == last_parameter =>
+                    {
+                        format!("{}...", type_name(component, package))
+                    }
+                    _ => type_name(parameter_type, package),
+                };
+                format!(
+                    "{parameter_type} {}",
+                    names.local(*slot, &[0]).render(package)
+                )
+            })
+            .join(", ");
+        if method.name == "<init>" {
+            let simple_name = class.name.rsplit(['/', '$']).next().unwrap_or(&class.name);
+            words.push(format!("{simple_name}({parameters})"));
+        } else {
+            let return_type = method
+                .parsed_type_descriptor
+                .return_type
+                .as_ref()
+                .map_or_else(|| "void".to_string(), |t| type_name(t, package));
+            words.push(format!("{return_type} {}({parameters})", method.name));
+        }
+        if !method.thrown_exceptions.is_empty() {
+            words.push(format!(
+                "throws {}",
+                method
+                    .thrown_exceptions
+                    .iter()
+                    .map(|exception| class_name(exception, package))
+                    .join(", ")
+            ));
+        }
+        words.join(" ")
+    };
+
+    if method.deprecated {
+        printer.line("@Deprecated");
+    }
+    let Some(code) = method.code.as_ref().filter(|_| has_body) else {
+        printer.line(&format!("{header};"));
+        return;
+    };
+    printer.line(&format!("{header} {{"));
+    printer.indent += 1;
+    printer.used_labels.clear();
+    printer.declared = names
+        .parameters
+        .iter()
+        .map(|(slot, _)| names.local(*slot, &[0]).render(package))
+        .chain(["this".to_string()])
+        .collect();
+    match MethodDecompiler::new(class, method, code, names).and_then(|mut d| d.decompile()) {
+        Ok(body) => {
+            collect_labels(&body, &mut printer.used_labels);
+            printer.statements(&body);
+        }
+        Err(message) => {
+            printer.line(&format!("// Could not decompile: {message}"));
+            if let Ok(instructions) = Instruction::parse_instructions(&code.code) {
+                for (address, instruction) in instructions {
+                    printer.line(&format!("// {address:5}: {instruction:?}"));
+                }
+            }
+        }
+    }
+    printer.indent -= 1;
+    printer.line("}");
+}
+
+/// An entry of the `LocalVariableTable` attribute of the code of a method
+#[derive(Debug, Clone)]
+struct LocalVariable {
+    start: u16,
+    length: u16,
+    slot: u16,
+    name: String,
+    descriptor: FieldType,
+}
+
+impl LocalVariable {
+    fn covers(&self, pc: u16) -> bool {
+        let start = u32::from(self.start);
+        (start..start + u32::from(self.length)).contains(&u32::from(pc))
+    }
+}
+
+/// Parses the `LocalVariableTable` attributes of the code, skipping malformed entries
+fn local_variable_table(
+    code: &ClassFileMethodCode,
+    constants: &ConstantPool,
+) -> Vec<LocalVariable> {
+    let mut variables = Vec::new();
+    for attribute in code.attributes.iter() {
+        if attribute.name != "LocalVariableTable" || attribute.bytes.len() < 2 {
+            continue;
+        }
+        let count = u16::from_be_bytes([attribute.bytes[0], attribute.bytes[1]]);
+        for entry in attribute.bytes[2..].chunks_exact(10).take(count.into()) {
+            let u16_at = |index: usize| u16::from_be_bytes([entry[index], entry[index + 1]]);
+            let (Ok(name), Ok(descriptor)) =
+                (constants.text_of(u16_at(4)), constants.text_of(u16_at(6)))
+            else {
+                continue;
+            };
+            let Ok(descriptor) = FieldType::parse(&descriptor) else {
+                continue;
+            };
+            variables.push(LocalVariable {
+                start: u16_at(0),
+                length: u16_at(2),
+                slot: u16_at(8),
+                name,
+                descriptor,
+            });
+        }
+    }
+    variables
+}
+
+/// Names the local variables of a method, from the debug information if present
+struct LocalNames {
+    variables: Vec<LocalVariable>,
+    this_class: Option<String>,
+    /// The slot and the type of each parameter
+    parameters: Vec<(u16, FieldType)>,
+}
+
+impl LocalNames {
+    fn new(class: &ClassFile, method: &ClassFileMethod) -> Self {
+        let variables = method
+            .code
+            .as_ref()
+            .map(|code| local_variable_table(code, &class.constants))
+            .unwrap_or_default();
+        let this_class = (!method.is_static()).then(|| class.name.clone());
+        let mut slot = u16::from(this_class.is_some());
+        let parameters = method
+            .parsed_type_descriptor
+            .parameters
+            .iter()
+            .map(|parameter| {
+                let parameter_slot = slot;
+                slot = slot.saturating_add(parameter.slots());
+                (parameter_slot, parameter.clone())
+            })
+            .collect();
+        Self {
+            variables,
+            this_class,
+            parameters,
+        }
+    }
+
+    /// Returns the variable in the given slot, named after the first of the given addresses
+    /// that the debug information covers
+    fn local(&self, slot: u16, addresses: &[u16]) -> Expr {
+        let variable = addresses.iter().find_map(|pc| {
+            self.variables
+                .iter()
+                .find(|variable| variable.slot == slot && variable.covers(*pc))
+        });
+        if let Some(variable) = variable {
+            return Expr::Local {
+                slot,
+                name: variable.name.clone(),
+                value_type: Some(variable.descriptor.clone()),
+            };
+        }
+        if let (0, Some(class)) = (slot, &self.this_class) {
+            return Expr::Local {
+                slot,
+                name: "this".to_string(),
+                value_type: Some(FieldType::Object(class.clone())),
+            };
+        }
+        match self.parameters.iter().position(|(s, _)| *s == slot) {
+            Some(index) => Expr::Local {
+                slot,
+                name: format!("arg{index}"),
+                value_type: Some(self.parameters[index].1.clone()),
+            },
+            None => Expr::Local {
+                slot,
+                name: format!("var{slot}"),
+                value_type: None,
+            },
+        }
+    }
+}
+
+/// An expression, rebuilt from the instructions that push values on the operand stack
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal {
+        text: String,
+        value_type: Option<FieldType>,
+    },
+    ClassLiteral(FieldType),
+    Local {
+        slot: u16,
+        name: String,
+        value_type: Option<FieldType>,
+    },
+    /// A value that is not held by a local variable, i.e. a caught exception, or a value
+    /// moved out of the operand stack to preserve the order of evaluation
+    Temporary {
+        name: String,
+        value_type: Option<FieldType>,
+    },
+    /// An object created by `new` whose constructor has not been called yet
+    Uninitialized {
+        id: usize,
+        class: String,
+    },
+    /// The receiver of static members
+    ClassName(String),
+    New {
+        class: String,
+        arguments: Vec<Expr>,
+    },
+    NewArray {
+        array_type: FieldType,
+        lengths: Vec<Expr>,
+    },
+    /// An array initializer, whose elements past the given ones have the default value
+    ArrayInit {
+        array_type: FieldType,
+        length: usize,
+        elements: Vec<Expr>,
+    },
+    Field {
+        object: Box<Expr>,
+        name: String,
+        field_type: FieldType,
+    },
+    ArrayElement {
+        array: Box<Expr>,
+        index: Box<Expr>,
+        element_type: Option<FieldType>,
+    },
+    ArrayLength(Box<Expr>),
+    Call {
+        receiver: Option<Box<Expr>>,
+        name: String,
+        arguments: Vec<Expr>,
+        return_type: Option<FieldType>,
+    },
+    Binary {
+        operator: &'static str,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// The result of `lcmp`, `fcmpl` and friends, unless it is only compared with zero
+    Compare {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Unary {
+        operator: &'static str,
+        operand: Box<Expr>,
+    },
+    Cast {
+        target: FieldType,
+        operand: Box<Expr>,
+    },
+    InstanceOf {
+        operand: Box<Expr>,
+        target: FieldType,
+    },
+    /// A postfix `++` or `--`
+    Increment {
+        target: Box<Expr>,
+        operator: &'static str,
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+}
+
+impl Expr {
+    fn literal(text: String, value_type: FieldType) -> Self {
+        Expr::Literal {
+            text,
+            value_type: Some(value_type),
+        }
+    }
+
+    fn int(value: i32) -> Self {
+        Expr::literal(value.to_string(), INT)
+    }
+
+    fn null() -> Self {
+        Expr::Literal {
+            text: "null".to_string(),
+            value_type: None,
+        }
+    }
+
+    fn binary(operator: &'static str, left: Expr, right: Expr) -> Self {
+        Expr::Binary {
+            operator,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn int_value(&self) -> Option<i32> {
+        match self {
+            Expr::Literal {
+                text,
+                value_type: Some(INT),
+            } => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn value_type(&self) -> Option<FieldType> {
+        match self {
+            Expr::Literal { value_type, .. }
+            | Expr::Local { value_type, .. }
+            | Expr::Temporary { value_type, .. } => value_type.clone(),
+            Expr::ClassLiteral(_) => Some(FieldType::Object("java/lang/Class".to_string())),
+            Expr::Uninitialized { class, .. } | Expr::New { class, .. } => {
+                Some(FieldType::Object(class.clone()))
+            }
+            Expr::ClassName(_) => None,
+            Expr::NewArray { array_type, .. } | Expr::ArrayInit { array_type, .. } => {
+                Some(array_type.clone())
+            }
+            Expr::Field { field_type, .. } => Some(field_type.clone()),
+            Expr::ArrayElement { element_type, .. } => element_type.clone(),
+            Expr::ArrayLength(_) | Expr::Compare { .. } => Some(INT),
+            Expr::Call { return_type, .. } => return_type.clone(),
+            Expr::Binary { operator, left, .. } => match binary_precedence(operator) {
+                4..=5 | 8..=10 => Some(BOOLEAN),
+                _ => match left.value_type() {
+                    Some(FieldType::Base(
+                        BaseType::Byte | BaseType::Short | BaseType::Char | BaseType::Int,
+                    )) => Some(INT),
+                    // `&`, `|` and `^` of booleans
+                    other => other,
+                },
+            },
+            Expr::Unary { operator, operand } => match *operator {
+                "!" => Some(BOOLEAN),
+                _ => operand.value_type(),
+            },
+            Expr::Cast { target, .. } => Some(target.clone()),
+            Expr::InstanceOf { .. } => Some(BOOLEAN),
+            Expr::Increment { target, .. } => target.value_type(),
+            Expr::Conditional {
+                then, otherwise, ..
+            } => then.value_type().or_else(|| otherwise.value_type()),
+        }
+    }
+
+    fn is_boolean(&self) -> bool {
+        self.value_type() == Some(BOOLEAN)
+    }
+
+    fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Literal { .. }
+            | Expr::ClassLiteral(_)
+            | Expr::Local { .. }
+            | Expr::Temporary { .. }
+            | Expr::Uninitialized { .. }
+            | Expr::ClassName(_) => Vec::new(),
+            Expr::New { arguments, .. } => arguments.iter().collect(),
+            Expr::NewArray { lengths, .. } => lengths.iter().collect(),
+            Expr::ArrayInit { elements, .. } => elements.iter().collect(),
+            Expr::Field { object, .. } => vec![object],
+            Expr::ArrayElement { array, index, .. } => vec![array, index],
+            Expr::ArrayLength(array) => vec![array],
+            Expr::Call {
+                receiver,
+                arguments,
+                ..
+            } => receiver
+                .iter()
+                .map(|r| &**r)
+                .chain(arguments.iter())
+                .collect(),
+            Expr::Binary { left, right, .. } | Expr::Compare { left, right } => {
+                vec![left, right]
+            }
+            Expr::Unary { operand, .. }
+            | Expr::Cast { operand, .. }
+            | Expr::InstanceOf { operand, .. } => vec![operand],
+            Expr::Increment { target, .. } => vec![target],
+            Expr::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => vec![condition, then, otherwise],
+        }
+    }
+
+    fn any(&self, predicate: &dyn Fn(&Expr) -> bool) -> bool {
+        predicate(self)
+            || self
+                .children()
+                .into_iter()
+                .any(|child| child.any(predicate))
+    }
+
+    fn reads_local(&self, slot: u16) -> bool {
+        self.any(&|e| matches!(e, Expr::Local { slot: s, .. } if *s == slot))
+    }
+
+    /// Whether evaluating the expression later could give a different result, because it
+    /// reads the heap or changes something
+    fn touches_memory(&self) -> bool {
+        self.any(&|e| {
+            matches!(
+                e,
+                Expr::Field { .. }
+                    | Expr::ArrayElement { .. }
+                    | Expr::ArrayLength(_)
+                    | Expr::Call { .. }
+                    | Expr::New { .. }
+                    | Expr::Increment { .. }
+            )
+        })
+    }
+
+    fn has_side_effects(&self) -> bool {
+        self.any(&|e| {
+            matches!(
+                e,
+                Expr::Call { .. } | Expr::New { .. } | Expr::Increment { .. }
+            )
+        })
+    }
+
+    /// Whether the expression can be evaluated twice without changing its meaning
+    fn is_simple(&self) -> bool {
+        matches!(
+            self,
+            Expr::Literal { .. }
+                | Expr::ClassLiteral(_)
+                | Expr::Local { .. }
+                | Expr::Temporary { .. }
+                | Expr::Uninitialized { .. }
+                | Expr::ClassName(_)
+        )
+    }
+
+    /// The precedence of the outermost operator, from 16 for primary expressions down to 3
+    /// for the conditional operator
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { operator, .. } => binary_precedence(operator),
+            Expr::InstanceOf { .. } => 10,
+            Expr::Unary { .. } | Expr::Cast { .. } => 14,
+            Expr::Increment { .. } => 15,
+            Expr::Conditional { .. } => 3,
+            Expr::Literal { text, .. } if text.starts_with('-') => 14,
+            _ => 16,
+        }
+    }
+
+    fn render(&self, package: &str) -> String {
+        let mut out = String::new();
+        self.write(&mut out, package);
+        out
+    }
+
+    fn write_operand(&self, out: &mut String, package: &str, precedence: u8) {
+        if self.precedence() < precedence {
+            out.push('(');
+            self.write(out, package);
+            out.push(')');
+        } else {
+            self.write(out, package);
+        }
+    }
+
+    fn write_arguments(arguments: &[Expr], out: &mut String, package: &str) {
+        out.push('(');
+        for (index, argument) in arguments.iter().enumerate() {
+            if index > 0 {
+                out.push_str(", ");
+            }
+            argument.write(out, package);
+        }
+        out.push(')');
+    }
+
+    fn write(&self, out: &mut String, package: &str) {
+        match self {
+            Expr::Literal { text, .. } => out.push_str(text),
+            Expr::ClassLiteral(class) => {
+                out.push_str(&type_name(class, package));
+                out.push_str(".class");
+            }
+            Expr::Local { name, .. } | Expr::Temporary { name, .. } => out.push_str(name),
+            Expr::Uninitialized { class, .. } => {
+                out.push_str("new ");
+                out.push_str(&class_name(class, package));
+            }
+            Expr::ClassName(class) => out.push_str(&class_name(class, package)),
+            Expr::New { class, arguments } => {
+                out.push_str("new ");
+                out.push_str(&class_name(class, package));
+                Self::write_arguments(arguments, out, package);
+            }
+            Expr::NewArray {
+                array_type,
+                lengths,
+            } => {
+                let mut element_type = array_type;
+                let mut dimensions = 0;
+                while let FieldType::Array(component) = element_type {
+                    element_type = component;
+                    dimensions += 1;
+                }
+                out.push_str("new ");
+                out.push_str(&type_name(element_type, package));
+                for length in lengths.iter() {
+                    out.push('[');
+                    length.write(out, package);
+                    out.push(']');
+                }
+                for _ in lengths.len()..dimensions {
+                    out.push_str("[]");
+                }
+            }
+            Expr::ArrayInit {
+                array_type,
+                length,
+                elements,
+            } => {
+                out.push_str("new ");
+                out.push_str(&type_name(array_type, package));
+                out.push('{');
+                let component = match array_type {
+                    FieldType::Array(component) => Some(&**component),
+                    _ => None,
+                };
+                let defaults = (elements.len()..*length).map(|_| default_value(component));
+                for (index, element) in elements.iter().cloned().chain(defaults).enumerate() {
+                    if index > 0 {
+                        out.push_str(", ");
+                    }
+                    element.write(out, package);
+                }
+                out.push('}');
+            }
+            Expr::Field { object, name, .. } => {
+                object.write_operand(out, package, 16);
+                out.push('.');
+                out.push_str(name);
+            }
+            Expr::ArrayElement { array, index, .. } => {
+                array.write_operand(out, package, 16);
+                out.push('[');
+                index.write(out, package);
+                out.push(']');
+            }
+            Expr::ArrayLength(array) => {
+                array.write_operand(out, package, 16);
+                out.push_str(".length");
+            }
+            Expr::Call {
+                receiver,
+                name,
+                arguments,
+                ..
+            } => {
+                if let Some(receiver) = receiver {
+                    receiver.write_operand(out, package, 16);
+                    out.push('.');
+                }
+                out.push_str(name);
+                Self::write_arguments(arguments, out, package);
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let precedence = binary_precedence(operator);
+                left.write_operand(out, package, precedence);
+                out.push_str(&format!(" {operator} "));
+                right.write_operand(out, package, precedence + 1);
+            }
+            Expr::Compare { left, right } => {
+                out.push_str("compare");
+                Self::write_arguments(&[(**left).clone(), (**right).clone()], out, package);
+            }
+            Expr::Unary { operator, operand } => {
+                out.push_str(operator);
+                let operand_text = operand.render(package);
+                if operand.precedence() < 14 || operand_text.starts_with(operator) {
+                    out.push_str(&format!("({operand_text})"));
+                } else {
+                    out.push_str(&operand_text);
+                }
+            }
+            Expr::Cast { target, operand } => {
+                out.push_str(&format!("({}) ", type_name(target, package)));
+                operand.write_operand(out, package, 14);
+            }
+            Expr::InstanceOf { operand, target } => {
+                operand.write_operand(out, package, 11);
+                out.push_str(" instanceof ");
+                out.push_str(&type_name(target, package));
+            }
+            Expr::Increment { target, operator } => {
+                target.write_operand(out, package, 16);
+                out.push_str(operator);
+            }
+            Expr::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition.write_operand(out, package, 4);
+                out.push_str(" ? ");
+                then.write_operand(out, package, 3);
+                out.push_str(" : ");
+                otherwise.write_operand(out, package, 3);
+            }
+        }
+    }
+}
+
+fn binary_precedence(operator: &str) -> u8 {
+    match operator {
+        "*" | "/" | "%" => 13,
+        "+" | "-" => 12,
+        "<<" | ">>" | ">>>" => 11,
+        "<" | "<=" | ">" | ">=" => 10,
+        "==" | "!=" => 9,
+        "&" => 8,
+        "^" => 7,
+        "|" => 6,
+        "&&" => 5,
+        "||" => 4,
+        _ => 16,
+    }
+}
+
+/// Returns the opposite of a condition
+fn negate(condition: Expr) -> Expr {
+    match condition {
+        Expr::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let negated = match operator {
+                "==" => "!=",
+                "!=" => "==",
+                "<" => ">=",
+                ">=" => "<",
+                ">" => "<=",
+                "<=" => ">",
+                "&&" => return Expr::binary("||", negate(*left), negate(*right)),
+                "||" => return Expr::binary("&&", negate(*left), negate(*right)),
+                _ => {
+                    return Expr::Unary {
+                        operator: "!",
+                        operand: Box::new(Expr::Binary {
+                            operator,
+                            left,
+                            right,
+                        }),
+                    }
+                }
+            };
+            Expr::Binary {
+                operator: negated,
+                left,
+                right,
+            }
+        }
+        Expr::Unary {
+            operator: "!",
+            operand,
+        } => *operand,
+        Expr::Literal {
+            text,
+            value_type: Some(BOOLEAN),
+        } => Expr::literal((text != "true").to_string(), BOOLEAN),
+        condition => Expr::Unary {
+            operator: "!",
+            operand: Box::new(condition),
+        },
+    }
+}
+
+/// Adds an element to an array initializer, or to a new array becoming one. Elements with the
+/// default value are skipped by `javac`, so they are added back.
+fn array_initializer(array: &Expr, index: &Expr, value: &Expr) -> Option<Expr> {
+    let index = usize::try_from(index.int_value()?).ok()?;
+    let (array_type, length, mut elements) = match array {
+        Expr::NewArray {
+            array_type,
+            lengths,
+        } if lengths.len() == 1 => (
+            array_type.clone(),
+            usize::try_from(lengths[0].int_value()?).ok()?,
+            Vec::new(),
+        ),
+        Expr::ArrayInit {
+            array_type,
+            length,
+            elements,
+        } => (array_type.clone(), *length, elements.clone()),
+        _ => return None,
+    };
+    if index < elements.len() || index >= length {
+        return None;
+    }
+    let component = match &array_type {
+        FieldType::Array(component) => Some(&**component),
+        _ => None,
+    };
+    elements.resize_with(index, || default_value(component));
+    elements.push(value.clone());
+    Some(Expr::ArrayInit {
+        array_type,
+        length,
+        elements,
+    })
+}
+
+/// Returns the default value of the elements of arrays with the given component type
+fn default_value(component: Option<&FieldType>) -> Expr {
+    match component {
+        Some(FieldType::Base(BaseType::Long)) => Expr::literal("0L".to_string(), LONG),
+        Some(FieldType::Base(BaseType::Float)) => Expr::literal(float_literal(0.0), FLOAT),
+        Some(FieldType::Base(BaseType::Double)) => Expr::literal(double_literal(0.0), DOUBLE),
+        Some(base_type @ FieldType::Base(_)) => coerce(Expr::int(0), base_type),
+        _ => Expr::null(),
+    }
+}
+
+/// Builds the condition of `ifeq` and the other jumps comparing a value with zero
+fn compare_with_zero(value: Expr, operator: &'static str) -> Expr {
+    match value {
+        Expr::Compare { left, right } => Expr::Binary {
+            operator,
+            left,
+            right,
+        },
+        value if matches!(operator, "==" | "!=") && !value.is_simple() => {
+            // The result of a condition pushed as an int, e.g. by `a ? b : c` with booleans
+            let coerced = coerce(value.clone(), &BOOLEAN);
+            match (coerced.is_boolean(), operator) {
+                (true, "!=") => coerced,
+                (true, _) => negate(coerced),
+                (false, _) => Expr::binary(operator, value, Expr::int(0)),
+            }
+        }
+        value if value.is_boolean() && operator == "!=" => value,
+        value if value.is_boolean() && operator == "==" => negate(value),
+        value => Expr::binary(operator, value, Expr::int(0)),
+    }
+}
+
+/// Builds a comparison of two values, showing integer constants as characters when they are
+/// compared with a `char`
+fn compare(operator: &'static str, left: Expr, right: Expr) -> Expr {
+    let left_type = left.value_type();
+    let right_type = right.value_type();
+    let left = match right_type {
+        Some(FieldType::Base(BaseType::Char)) => coerce(left, &FieldType::Base(BaseType::Char)),
+        _ => left,
+    };
+    let right = match left_type {
+        Some(FieldType::Base(BaseType::Char)) => coerce(right, &FieldType::Base(BaseType::Char)),
+        _ => right,
+    };
+    Expr::binary(operator, left, right)
+}
+
+/// Adapts an expression to the type it is used as: the JVM represents booleans and chars as
+/// ints, so the constants used for them are shown as `true`, `false` and character literals
+fn coerce(expr: Expr, target: &FieldType) -> Expr {
+    match (target, expr.int_value()) {
+        (FieldType::Base(BaseType::Boolean), Some(value @ 0..=1)) => {
+            return Expr::literal((value == 1).to_string(), BOOLEAN);
+        }
+        (FieldType::Base(BaseType::Char), Some(value)) => {
+            if let Some(text) = char_literal(value) {
+                return Expr::literal(text, FieldType::Base(BaseType::Char));
+            }
+        }
+        _ => {}
+    }
+    match expr {
+        Expr::Conditional {
+            condition,
+            then,
+            otherwise,
+        } if matches!(target, FieldType::Base(BaseType::Boolean | BaseType::Char)) => conditional(
+            *condition,
+            coerce(*then, target),
+            coerce(*otherwise, target),
+        ),
+        expr => expr,
+    }
+}
+
+/// Builds a conditional expression, simplifying the ones that just turn a condition into
+/// a boolean
+fn conditional(condition: Expr, then: Expr, otherwise: Expr) -> Expr {
+    let is = |expr: &Expr, value: &str| match expr {
+        Expr::Literal {
+            text,
+            value_type: Some(BOOLEAN),
+        } => text == value,
+        _ => false,
+    };
+    if is(&then, "true") && is(&otherwise, "false") {
+        condition
+    } else if is(&then, "false") && is(&otherwise, "true") {
+        negate(condition)
+    } else {
+        Expr::Conditional {
+            condition: Box::new(condition),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        }
+    }
+}
+
+/// Turns the `StringBuilder` chains that `javac` uses for string concatenation back into
+/// `+` operations
+fn string_concatenation(receiver: &Expr) -> Option<Expr> {
+    let mut parts = Vec::new();
+    let mut current = receiver;
+    loop {
+        match current {
+            Expr::Call {
+                receiver: Some(receiver),
+                name,
+                arguments,
+                ..
+            } if name == "append" && arguments.len() == 1 => {
+                parts.push(arguments[0].clone());
+                current = receiver;
+            }
+            Expr::New { class, arguments } if class == "java/lang/StringBuilder" => {
+                match arguments.as_slice() {
+                    [] => {}
+                    [first] if first.value_type() != Some(INT) => parts.push(first.clone()),
+                    _ => return None,
+                }
+                break;
+            }
+            _ => return None,
+        }
+    }
+    parts.reverse();
+    let string = FieldType::Object("java/lang/String".to_string());
+    let is_string = |part: Option<&Expr>| part.and_then(|p| p.value_type()) == Some(string.clone());
+    if !is_string(parts.first()) && !is_string(parts.get(1)) {
+        parts.insert(0, Expr::literal("\"\"".to_string(), string.clone()));
+    }
+    parts
+        .into_iter()
+        .reduce(|left, right| Expr::binary("+", left, right))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LoopKind {
+    /// `while (true)`
+    Infinite,
+    While(Expr),
+    DoWhile(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Catch {
+    /// The classes of the exceptions caught, or none to catch everything
+    classes: Vec<String>,
+    variable: String,
+    body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Expression(Expr),
+    /// An assignment, which declares the target if it is a local variable assigned for the
+    /// first time
+    Assign {
+        target: Expr,
+        value: Expr,
+        target_type: Option<FieldType>,
+    },
+    Return(Option<Expr>),
+    Throw(Expr),
+    If {
+        condition: Expr,
+        then: Vec<Stmt>,
+        otherwise: Vec<Stmt>,
+    },
+    Loop {
+        kind: LoopKind,
+        label: String,
+        body: Vec<Stmt>,
+    },
+    /// The cases have their keys, with `None` for the default, and fall through unless they
+    /// end with a jump
+    Switch {
+        value: Expr,
+        label: String,
+        cases: Vec<(Vec<Option<Expr>>, Vec<Stmt>)>,
+    },
+    Try {
+        body: Vec<Stmt>,
+        catches: Vec<Catch>,
+        finally: Option<Vec<Stmt>>,
+    },
+    Synchronized {
+        lock: Expr,
+        body: Vec<Stmt>,
+    },
+    Break(Option<String>),
+    Continue(Option<String>),
+    Goto(String),
+    Label(String),
+}
+
+fn ends_with_jump(statements: &[Stmt]) -> bool {
+    matches!(
+        statements.last(),
+        Some(Stmt::Return(_) | Stmt::Throw(_) | Stmt::Break(_) | Stmt::Continue(_) | Stmt::Goto(_))
+    )
+}
+
+/// Adds an `if` statement, or what is left of it: an `if` whose body always jumps away does
+/// not need an `else`, and empty branches are dropped
+fn push_if(out: &mut Vec<Stmt>, condition: Expr, then: Vec<Stmt>, otherwise: Vec<Stmt>) {
+    if then.is_empty() && otherwise.is_empty() {
+        if condition.has_side_effects() {
+            out.push(Stmt::Expression(condition));
+        }
+    } else if then.is_empty() {
+        push_if(out, negate(condition), otherwise, then);
+    } else if !otherwise.is_empty() && ends_with_jump(&then) {
+        out.push(Stmt::If {
+            condition,
+            then,
+            otherwise: Vec::new(),
+        });
+        out.extend(otherwise);
+    } else {
+        out.push(Stmt::If {
+            condition,
+            then,
+            otherwise,
+        });
+    }
+}
+
+/// Recognizes the `while` and `do`/`while` loops among the `while (true)` ones
+fn shape_loop(mut body: Vec<Stmt>) -> (LoopKind, Vec<Stmt>) {
+    if matches!(body.last(), Some(Stmt::Continue(None))) {
+        body.pop();
+    }
+    if let Some(Stmt::If {
+        then, otherwise, ..
+    }) = body.first()
+    {
+        if then == &[Stmt::Break(None)] && otherwise.is_empty() {
+            let Stmt::If { condition, .. } = body.remove(0) else {
+                unreachable!("the first statement is an if")
+            };
+            return (LoopKind::While(negate(condition)), body);
+        }
+    }
+    if let [.., Stmt::If {
+        then, otherwise, ..
+    }, Stmt::Break(None)] = body.as_slice()
+    {
+        if then == &[Stmt::Continue(None)] && otherwise.is_empty() {
+            body.pop();
+            let Some(Stmt::If { condition, .. }) = body.pop() else {
+                unreachable!("the statement before the break is an if")
+            };
+            return (LoopKind::DoWhile(condition), body);
+        }
+    }
+    (LoopKind::Infinite, body)
+}
+
+/// Calls the given function on the statements, and on the nested blocks of statements
+fn for_each_body(statements: &mut Vec<Stmt>, function: &mut dyn FnMut(&mut Vec<Stmt>)) {
+    for statement in statements.iter_mut() {
+        match statement {
+            Stmt::If {
+                then, otherwise, ..
+            } => {
+                for_each_body(then, function);
+                for_each_body(otherwise, function);
+            }
+            Stmt::Loop { body, .. } | Stmt::Synchronized { body, .. } => {
+                for_each_body(body, function)
+            }
+            Stmt::Switch { cases, .. } => {
+                for (_, body) in cases.iter_mut() {
+                    for_each_body(body, function);
+                }
+            }
+            Stmt::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                for_each_body(body, function);
+                for catch in catches.iter_mut() {
+                    for_each_body(&mut catch.body, function);
+                }
+                if let Some(finally) = finally {
+                    for_each_body(finally, function);
+                }
+            }
+            _ => {}
+        }
+    }
+    function(statements);
+}
+
+/// Replaces the local variables assigned just to be returned, as `javac` does in the `try`
+/// blocks with a `finally`, with the returned value. When the `return` follows a `try` that
+/// assigns the variable last, and whose `catch` blocks all jump away, it is moved back into
+/// the `try`, where the variable is declared.
+fn inline_returned_locals(statements: &mut Vec<Stmt>) {
+    let mut index = 1;
+    while index < statements.len() {
+        match &mut statements[index - 1..=index] {
+            [Stmt::Assign { target, value, .. }, Stmt::Return(Some(returned))]
+                if matches!(target, Expr::Local { .. }) && target == returned =>
+            {
+                let value = value.clone();
+                statements.splice(index - 1..=index, [Stmt::Return(Some(value))]);
+                continue;
+            }
+            [Stmt::Try {
+                body,
+                catches,
+                finally,
+            }, Stmt::Return(Some(returned @ Expr::Local { .. }))]
+                if catches.iter().all(|catch| ends_with_jump(&catch.body))
+                    && !finally
+                        .as_ref()
+                        .is_some_and(|finally| statements_read(finally, returned)) =>
+            {
+                if let Some(Stmt::Assign { target, value, .. }) = body.last() {
+                    if target == returned {
+                        let value = value.clone();
+                        body.pop();
+                        body.push(Stmt::Return(Some(value)));
+                        statements.remove(index);
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+}
+
+/// Whether any of the statements, or of the statements nested in them, uses the expression
+fn statements_read(statements: &[Stmt], expr: &Expr) -> bool {
+    let reads = |candidate: &Expr| candidate.any(&|e| e == expr);
+    statements.iter().any(|statement| match statement {
+        Stmt::Expression(value) | Stmt::Throw(value) | Stmt::Return(Some(value)) => reads(value),
+        Stmt::Assign { target, value, .. } => reads(target) || reads(value),
+        Stmt::If {
+            condition,
+            then,
+            otherwise,
+        } => reads(condition) || statements_read(then, expr) || statements_read(otherwise, expr),
+        Stmt::Loop { kind, body, .. } => {
+            let condition = match kind {
+                LoopKind::While(condition) | LoopKind::DoWhile(condition) => reads(condition),
+                LoopKind::Infinite => false,
+            };
+            condition || statements_read(body, expr)
+        }
+        Stmt::Switch { value, cases, .. } => {
+            reads(value) || cases.iter().any(|(_, body)| statements_read(body, expr))
+        }
+        Stmt::Try {
+            body,
+            catches,
+            finally,
+        } => {
+            statements_read(body, expr)
+                || catches
+                    .iter()
+                    .any(|catch| statements_read(&catch.body, expr))
+                || finally
+                    .as_ref()
+                    .is_some_and(|finally| statements_read(finally, expr))
+        }
+        Stmt::Synchronized { lock, body } => reads(lock) || statements_read(body, expr),
+        Stmt::Return(None)
+        | Stmt::Break(_)
+        | Stmt::Continue(_)
+        | Stmt::Goto(_)
+        | Stmt::Label(_) => false,
+    })
+}
+
+/// Removes the copies of the code of a `finally` block that `javac` puts at the end of the
+/// `try` and `catch` blocks, and before the statements jumping out of them
+fn strip_finally_copies(statements: &mut Vec<Stmt>, copy: &[Stmt]) {
+    let mut index = 0;
+    while index <= statements.len() {
+        let is_exit = match statements.get(index) {
+            None => true,
+            Some(statement) => matches!(
+                statement,
+                Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_) | Stmt::Goto(_)
+            ),
+        };
+        if is_exit && index >= copy.len() && statements[index - copy.len()..index] == *copy {
+            statements.drain(index - copy.len()..index);
+            index -= copy.len();
+        }
+        if let Some(statement) = statements.get_mut(index) {
+            match statement {
+                Stmt::If {
+                    then, otherwise, ..
+                } => {
+                    strip_finally_copies(then, copy);
+                    strip_finally_copies(otherwise, copy);
+                }
+                Stmt::Loop { body, .. } | Stmt::Synchronized { body, .. } => {
+                    strip_finally_copies(body, copy)
+                }
+                Stmt::Switch { cases, .. } => {
+                    for (_, body) in cases.iter_mut() {
+                        strip_finally_copies(body, copy);
+                    }
+                }
+                Stmt::Try { body, catches, .. } => {
+                    strip_finally_copies(body, copy);
+                    for catch in catches.iter_mut() {
+                        strip_finally_copies(&mut catch.body, copy);
+                    }
+                }
+                _ => {}
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Recognizes the code generated for `synchronized` blocks: the lock is stored in a local
+/// variable and entered before a `try` whose `finally` exits it. Removes the statements
+/// entering the lock from the given ones, and returns the block.
+fn synchronized_block(
+    out: &mut Vec<Stmt>,
+    body: &[Stmt],
+    no_catches: bool,
+    finally: Option<&[Stmt]>,
+) -> Option<Stmt> {
+    let Some(
+        [Stmt::Expression(Expr::Call {
+            name, arguments, ..
+        })],
+    ) = finally
+    else {
+        return None;
+    };
+    let [held] = arguments.as_slice() else {
+        return None;
+    };
+    let Some(Stmt::Expression(Expr::Call {
+        receiver: None,
+        name: enter,
+        arguments: entered,
+        ..
+    })) = out.last()
+    else {
+        return None;
+    };
+    if !no_catches || name != "monitorexit" || enter != "monitorenter" || entered.len() != 1 {
+        return None;
+    }
+    let mut lock = entered[0].clone();
+    out.pop();
+    if let Some(Stmt::Assign { target, value, .. }) = out.last() {
+        if target == held && *value == lock {
+            out.pop();
+        }
+    }
+    if let Expr::Temporary { .. } = lock {
+        // The lock was computed before being stored in the variable
+        if let Some(Stmt::Assign { target, value, .. }) = out.last() {
+            if *target == lock {
+                lock = value.clone();
+                out.pop();
+            }
+        }
+    }
+    Some(Stmt::Synchronized {
+        lock,
+        body: body.to_vec(),
+    })
+}
+
+fn collect_labels(statements: &[Stmt], labels: &mut HashSet<String>) {
+    for statement in statements {
+        match statement {
+            Stmt::Break(Some(label)) | Stmt::Continue(Some(label)) => {
+                labels.insert(label.clone());
+            }
+            Stmt::If {
+                then, otherwise, ..
+            } => {
+                collect_labels(then, labels);
+                collect_labels(otherwise, labels);
+            }
+            Stmt::Loop { body, .. } | Stmt::Synchronized { body, .. } => {
+                collect_labels(body, labels)
+            }
+            Stmt::Switch { cases, .. } => {
+                for (_, body) in cases {
+                    collect_labels(body, labels);
+                }
+            }
+            Stmt::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                collect_labels(body, labels);
+                for catch in catches {
+                    collect_labels(&catch.body, labels);
+                }
+                collect_labels(finally.as_deref().unwrap_or_default(), labels);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Writes indented lines of source code
+struct Printer<'a> {
+    package: &'a str,
+    out: String,
+    indent: usize,
+    /// The local variables declared so far in the current method
+    declared: HashSet<String>,
+    /// The labels of the loops and switches that are the target of a `break` or `continue`
+    used_labels: HashSet<String>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(package: &'a str) -> Self {
+        Self {
+            package,
+            out: String::new(),
+            indent: 0,
+            declared: HashSet::new(),
+            used_labels: HashSet::new(),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.out.push_str(&"    ".repeat(self.indent));
+            self.out.push_str(text);
+        }
+        self.out.push('\n');
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        expr.render(self.package)
+    }
+
+    fn nested(&mut self, statements: &[Stmt]) {
+        self.indent += 1;
+        self.statements(statements);
+        self.indent -= 1;
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn label_prefix(&self, label: &str) -> String {
+        if self.used_labels.contains(label) {
+            format!("{label}: ")
+        } else {
+            String::new()
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Expression(expr) => {
+                let text = self.expr(expr);
+                self.line(&format!("{text};"));
+            }
+            Stmt::Assign {
+                target,
+                value,
+                target_type,
+            } => {
+                let line = self.assignment(target, value, target_type.as_ref());
+                self.line(&format!("{line};"));
+            }
+            Stmt::Return(None) => self.line("return;"),
+            Stmt::Return(Some(value)) => {
+                let text = self.expr(value);
+                self.line(&format!("return {text};"));
+            }
+            Stmt::Throw(value) => {
+                let text = self.expr(value);
+                self.line(&format!("throw {text};"));
+            }
+            Stmt::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let text = self.expr(condition);
+                self.line(&format!("if ({text}) {{"));
+                self.nested(then);
+                let mut otherwise = otherwise;
+                loop {
+                    match otherwise.as_slice() {
+                        [] => {
+                            self.line("}");
+                            break;
+                        }
+                        [Stmt::If {
+                            condition,
+                            then,
+                            otherwise: next,
+                        }] => {
+                            let text = self.expr(condition);
+                            self.line(&format!("}} else if ({text}) {{"));
+                            self.nested(then);
+                            otherwise = next;
+                        }
+                        statements => {
+                            self.line("} else {");
+                            self.nested(statements);
+                            self.line("}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Stmt::Loop { kind, label, body } => {
+                let prefix = self.label_prefix(label);
+                match kind {
+                    LoopKind::Infinite => self.line(&format!("{prefix}while (true) {{")),
+                    LoopKind::While(condition) => {
+                        let text = self.expr(condition);
+                        self.line(&format!("{prefix}while ({text}) {{"));
+                    }
+                    LoopKind::DoWhile(_) => self.line(&format!("{prefix}do {{")),
+                }
+                self.nested(body);
+                match kind {
+                    LoopKind::DoWhile(condition) => {
+                        let text = self.expr(condition);
+                        self.line(&format!("}} while ({text});"));
+                    }
+                    _ => self.line("}"),
+                }
+            }
+            Stmt::Switch {
+                value,
+                label,
+                cases,
+            } => {
+                let prefix = self.label_prefix(label);
+                let text = self.expr(value);
+                self.line(&format!("{prefix}switch ({text}) {{"));
+                self.indent += 1;
+                for (keys, body) in cases {
+                    for key in keys {
+                        match key {
+                            Some(key) => {
+                                let text = self.expr(key);
+                                self.line(&format!("case {text}:"));
+                            }
+                            None => self.line("default:"),
+                        }
+                    }
+                    self.nested(body);
+                }
+                self.indent -= 1;
+                self.line("}");
+            }
+            Stmt::Try {
+                body,
+                catches,
+                finally,
+            } => {
+                self.line("try {");
+                self.nested(body);
+                for catch in catches {
+                    let classes = match catch.classes.as_slice() {
+                        [] => class_name("java/lang/Throwable", self.package),
+                        classes => classes
+                            .iter()
+                            .map(|class| class_name(class, self.package))
+                            .join(" | "),
+                    };
+                    self.declared.insert(catch.variable.clone());
+                    self.line(&format!("}} catch ({classes} {}) {{", catch.variable));
+                    self.nested(&catch.body);
+                }
+                if let Some(finally) = finally {
+                    self.line("} finally {");
+                    self.nested(finally);
+                }
+                self.line("}");
+            }
+            Stmt::Synchronized { lock, body } => {
+                let text = self.expr(lock);
+                self.line(&format!("synchronized ({text}) {{"));
+                self.nested(body);
+                self.line("}");
+            }
+            Stmt::Break(None) => self.line("break;"),
+            Stmt::Break(Some(label)) => self.line(&format!("break {label};")),
+            Stmt::Continue(None) => self.line("continue;"),
+            Stmt::Continue(Some(label)) => self.line(&format!("continue {label};")),
+            Stmt::Goto(label) => self.line(&format!("goto {label};")),
+            Stmt::Label(label) => self.line(&format!("{label}:")),
+        }
+    }
+
+    fn assignment(
+        &mut self,
+        target: &Expr,
+        value: &Expr,
+        target_type: Option<&FieldType>,
+    ) -> String {
+        let target_text = self.expr(target);
+        if let Expr::Local { name, .. } | Expr::Temporary { name, .. } = target {
+            if self.declared.insert(name.clone()) {
+                let declared_type =
+                    target_type.map_or_else(|| "var".to_string(), |t| type_name(t, self.package));
+                return format!("{declared_type} {target_text} = {}", self.expr(value));
+            }
+        }
+        if let Expr::Binary {
+            operator,
+            left,
+            right,
+        } = value
+        {
+            if **left == *target && binary_precedence(operator) >= 6 {
+                let step = right.int_value().filter(|_| matches!(*operator, "+" | "-"));
+                return match (step, *operator) {
+                    (Some(1), "+") => format!("{target_text}++"),
+                    (Some(1), "-") => format!("{target_text}--"),
+                    _ if binary_precedence(operator) <= 10 && binary_precedence(operator) > 8 => {
+                        format!("{target_text} = {}", self.expr(value))
+                    }
+                    _ => format!("{target_text} {operator}= {}", self.expr(right)),
+                };
+            }
+        }
+        format!("{target_text} = {}", self.expr(value))
+    }
+}
+
+/// How a block of code ends
+#[derive(Debug)]
+enum Exit {
+    Jump(BlockId),
+    /// Jumps to `target` if the condition holds, and to `fallthrough` otherwise
+    Branch {
+        condition: Expr,
+        target: BlockId,
+        fallthrough: BlockId,
+    },
+    Switch {
+        value: Expr,
+        cases: Vec<(i32, BlockId)>,
+        default: BlockId,
+    },
+    Return(Option<Expr>),
+    Throw(Expr),
+}
+
+/// The statements of a block, with the values left on the stack and how the block ends
+#[derive(Debug)]
+struct Lifted {
+    statements: Vec<Stmt>,
+    stack: Vec<Expr>,
+    exit: Exit,
+}
+
+/// What comes after a structured statement
+enum Flow {
+    /// The code continues with the given block, with the given values on the stack, or the
+    /// ones expected at its start if `None`
+    Continue(BlockId, Option<Vec<Expr>>),
+    /// The code does not continue, having reached one of the stops, or a jump
+    Stopped(Option<BlockId>),
+}
+
+/// A loop or a switch, which `break` can exit
+struct Breakable {
+    label: String,
+    /// The header and blocks of loops, which `continue` can jump to
+    header: Option<BlockId>,
+    blocks: Vec<BlockId>,
+    follow: Option<BlockId>,
+}
+
+/// The operand stack and the statements of a block being lifted
+struct BlockState {
+    stack: Vec<Expr>,
+    statements: Vec<Stmt>,
+    temporaries: usize,
+}
+
+impl BlockState {
+    fn pop(&mut self) -> Result<Expr, String> {
+        self.stack
+            .pop()
+            .ok_or_else(|| "operand stack underflow".to_string())
+    }
+
+    fn pop_many(&mut self, count: usize) -> Result<Vec<Expr>, String> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(count)
+            .ok_or_else(|| "operand stack underflow".to_string())?;
+        Ok(self.stack.split_off(start))
+    }
+
+    /// Moves the value at the given index of the stack to a temporary variable, and the
+    /// values below it too if their evaluation could be affected
+    fn spill_at(&mut self, index: usize) {
+        for current in 0..=index {
+            let value = &self.stack[current];
+            if current == index || (value.touches_memory() && !value.is_simple()) {
+                self.spill_entry(current);
+            }
+        }
+    }
+
+    fn spill_entry(&mut self, index: usize) {
+        let value = &self.stack[index];
+        if value.is_simple() && !matches!(value, Expr::Uninitialized { .. }) {
+            return;
+        }
+        let temporary = Expr::Temporary {
+            name: format!("tmp{}", self.temporaries),
+            value_type: value.value_type(),
+        };
+        self.temporaries += 1;
+        let value = std::mem::replace(&mut self.stack[index], temporary.clone());
+        self.statements.push(Stmt::Assign {
+            target: temporary,
+            target_type: value.value_type(),
+            value,
+        });
+    }
+
+    /// Adds a statement, after moving to temporaries the values on the stack whose evaluation
+    /// it could affect
+    fn effect(&mut self, statement: Stmt, conflicts: impl Fn(&Expr) -> bool) {
+        for index in 0..self.stack.len() {
+            let value = &self.stack[index];
+            if !matches!(value, Expr::Uninitialized { .. }) && conflicts(value) {
+                self.spill_entry(index);
+            }
+        }
+        self.statements.push(statement);
+    }
+
+    fn memory_effect(&mut self, statement: Stmt) {
+        self.effect(statement, Expr::touches_memory);
+    }
+}
+
+/// Decompiles the body of one method
+struct MethodDecompiler<'a> {
+    class: &'a ClassFile,
+    method: &'a ClassFileMethod,
+    code: &'a ClassFileMethodCode,
+    names: LocalNames,
+    graph: ControlFlowGraph,
+    /// The post-dominators without the exceptional edges, which give the merge points of the
+    /// normal control flow
+    post_dominators: DominatorTree,
+    /// The loops formed by normal edges: the handlers covering their own code form loops
+    /// through exceptional edges only, which are not loops of the source code
+    loops: Vec<Loop>,
+    frames: BTreeMap<ProgramCounter, Frame<BasicValue>>,
+
+    emitted: Vec<bool>,
+    labeled: Vec<bool>,
+    /// The blocks whose successor, returning the value they computed, was copied into them
+    inlined_returns: HashSet<BlockId>,
+    consumed_handlers: BTreeSet<ProgramCounter>,
+    breakables: Vec<Breakable>,
+    /// The blocks that need a label, found by the previous pass
+    labels: HashSet<BlockId>,
+    goto_targets: HashSet<BlockId>,
+    temporaries: usize,
+    objects: usize,
+}
+
+impl<'a> MethodDecompiler<'a> {
+    fn new(
+        class: &'a ClassFile,
+        method: &'a ClassFileMethod,
+        code: &'a ClassFileMethodCode,
+        names: LocalNames,
+    ) -> Result<Self, String> {
+        let graph = code.control_flow_graph().map_err(|err| err.to_string())?;
+        let frames = Analyzer::new(&mut BasicInterpreter::new(&class.constants))
+            .analyze(&class.name, method, &class.constants)
+            .map_err(|err| err.to_string())?;
+        let successors = (0..graph.blocks().len())
+            .map(|block| {
+                graph
+                    .successors(block)
+                    .filter(|edge| !matches!(edge.kind, EdgeKind::Exceptional(_)))
+                    .map(|edge| edge.to)
+                    .collect()
+            })
+            .collect();
+        let post_dominators = DominatorTree::post_dominators_of(successors);
+        let loops = graph
+            .loops()
+            .loops()
+            .iter()
+            .filter(|l| {
+                l.latches.iter().any(|latch| {
+                    graph.successors(*latch).any(|edge| {
+                        edge.to == l.header && !matches!(edge.kind, EdgeKind::Exceptional(_))
+                    })
+                })
+            })
+            .cloned()
+            .collect();
+        let blocks = graph.blocks().len();
+        Ok(Self {
+            class,
+            method,
+            code,
+            names,
+            graph,
+            post_dominators,
+            loops,
+            frames,
+            emitted: vec![false; blocks],
+            labeled: vec![false; blocks],
+            inlined_returns: HashSet::new(),
+            consumed_handlers: BTreeSet::new(),
+            breakables: Vec::new(),
+            labels: HashSet::new(),
+            goto_targets: HashSet::new(),
+            temporaries: 0,
+            objects: 0,
+        })
+    }
+
+    /// Decompiles the body. A first pass finds the targets of the `goto` statements, if any,
+    /// and a second one adds the labels they need.
+    fn decompile(&mut self) -> Result<Vec<Stmt>, String> {
+        let mut body = self.pass()?;
+        if !self.goto_targets.is_empty() {
+            self.labels = std::mem::take(&mut self.goto_targets);
+            body = self.pass()?;
+        }
+        for_each_body(&mut body, &mut inline_returned_locals);
+        if self.method.is_void() && body.last() == Some(&Stmt::Return(None)) {
+            body.pop();
+        }
+        if self.method.name == "<init>" {
+            if let Some(Stmt::Expression(Expr::Call {
+                receiver: None,
+                name,
+                arguments,
+                ..
+            })) = body.first()
+            {
+                if name == "super" && arguments.is_empty() {
+                    body.remove(0);
+                }
+            }
+        }
+        Ok(body)
+    }
+
+    fn pass(&mut self) -> Result<Vec<Stmt>, String> {
+        let blocks = self.graph.blocks().len();
+        self.emitted = vec![false; blocks];
+        self.labeled = vec![false; blocks];
+        self.inlined_returns.clear();
+        self.consumed_handlers.clear();
+        self.breakables.clear();
+        self.goto_targets.clear();
+        self.temporaries = 0;
+        self.objects = 0;
+
+        let mut body = Vec::new();
+        if blocks == 0 {
+            return Ok(body);
+        }
+        self.sequence(0, None, &[], &mut body)?;
+        // Code that is only reachable through handlers that could not be structured
+        for block in 0..blocks {
+            if !self.emitted[block]
+                && !self.is_fully_inlined(block)
+                && self.frames.contains_key(&self.start(block))
+            {
+                if !self.labeled[block] {
+                    self.labeled[block] = true;
+                    body.push(Stmt::Label(self.label(block)));
+                }
+                self.sequence_from(block, None, &[], &mut body)?;
+            }
+        }
+        Ok(body)
+    }
+
+    fn start(&self, block: BlockId) -> ProgramCounter {
+        self.graph.block(block).range.start
+    }
+
+    fn label(&self, block: BlockId) -> String {
+        format!("label{}", self.start(block))
+    }
+
+    fn block_at(&self, address: u16) -> Result<BlockId, String> {
+        self.graph
+            .block_at(ProgramCounter(address))
+            .ok_or_else(|| format!("no instruction at address {address}"))
+    }
+
+    fn member(&self, index: u16) -> Result<MemberReference, String> {
+        self.class
+            .constants
+            .member_reference(index)
+            .map_err(|err| err.to_string())
+    }
+
+    fn class_constant(&self, index: u16) -> Result<FieldType, String> {
+        self.class
+            .constants
+            .text_of(index)
+            .map(class_constant_type)
+            .map_err(|err| err.to_string())
+    }
+
+    fn is_handler(&self, block: BlockId) -> bool {
+        let start = self.start(block);
+        self.code
+            .exception_table
+            .entries()
+            .iter()
+            .any(|entry| entry.handler_pc == start)
+    }
+
+    /// The values on the stack at the start of a block reached by a jump: the caught
+    /// exception for handlers, and temporary variables otherwise
+    fn entry_stack(&self, block: BlockId) -> Vec<Expr> {
+        let start = self.start(block);
+        if let Some(entry) = self
+            .code
+            .exception_table
+            .entries()
+            .iter()
+            .find(|entry| entry.handler_pc == start)
+        {
+            let class = entry
+                .catch_class
+                .as_deref()
+                .unwrap_or("java/lang/Throwable");
+            return vec![Expr::Temporary {
+                name: "exception".to_string(),
+                value_type: Some(FieldType::Object(class.to_string())),
+            }];
+        }
+        self.frames
+            .get(&start)
+            .map(|frame| {
+                frame
+                    .stack
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| Expr::Temporary {
+                        name: format!("stack{index}"),
+                        value_type: match value {
+                            BasicValue::Int => Some(INT),
+                            BasicValue::Long => Some(LONG),
+                            BasicValue::Float => Some(FLOAT),
+                            BasicValue::Double => Some(DOUBLE),
+                            BasicValue::Reference | BasicValue::Uninitialized => None,
+                        },
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Moves the values left on the stack at the end of a block to the temporary variables
+    /// that the next blocks expect
+    fn spill_stack(&self, stack: Vec<Expr>, out: &mut Vec<Stmt>) {
+        for (index, value) in stack.into_iter().enumerate() {
+            let name = format!("stack{index}");
+            if matches!(&value, Expr::Temporary { name: n, .. } if *n == name) {
+                continue;
+            }
+            let value_type = value.value_type();
+            out.push(Stmt::Assign {
+                target: Expr::Temporary {
+                    name,
+                    value_type: value_type.clone(),
+                },
+                value,
+                target_type: value_type,
+            });
+        }
+    }
+
+    fn lift(&mut self, block: BlockId, stack: Vec<Expr>) -> Result<Lifted, String> {
+        let basic_block = self.graph.block(block).clone();
+        let mut state = BlockState {
+            stack,
+            statements: Vec::new(),
+            temporaries: self.temporaries,
+        };
+        let mut exit = None;
+        for (index, (pc, instruction)) in basic_block.instructions.iter().enumerate() {
+            let next_pc = basic_block
+                .instructions
+                .get(index + 1)
+                .map_or(basic_block.range.end, |(next, _)| *next);
+            let frame = self
+                .frames
+                .get(pc)
+                .ok_or_else(|| format!("unreachable instruction at address {pc}"))?;
+            if state.stack.len() != frame.stack.len() {
+                return Err(format!("inconsistent operand stack at address {pc}"));
+            }
+            let stack_kinds: Vec<BasicValue> = frame.stack.clone();
+            exit = self.lift_instruction(&mut state, *pc, next_pc, instruction, &stack_kinds)?;
+        }
+        self.temporaries = state.temporaries;
+        let exit = match exit {
+            Some(exit) => exit,
+            None => Exit::Jump(self.block_at(basic_block.range.end.0)?),
+        };
+        Ok(Lifted {
+            statements: state.statements,
+            stack: state.stack,
+            exit,
+        })
+    }
+
+    fn lift_instruction(
+        &mut self,
+        state: &mut BlockState,
+        pc: ProgramCounter,
+        next_pc: ProgramCounter,
+        instruction: &Instruction,
+        stack_kinds: &[BasicValue],
+    ) -> Result<Option<Exit>, String> {
+        if let Some(access) = instruction.local_variable_access() {
+            match access {
+                LocalVariableAccess::Load(_, slot) => {
+                    state.stack.push(self.names.local(slot, &[pc.0]));
+                }
+                LocalVariableAccess::Store(kind, slot) => {
+                    let value = state.pop()?;
+                    let target = self.names.local(slot, &[next_pc.0, pc.0]);
+                    let target_type = target
+                        .value_type()
+                        .or_else(|| value.value_type().filter(|t| LocalKind::from(t) == kind))
+                        .or(match kind {
+                            LocalKind::Int => Some(INT),
+                            LocalKind::Long => Some(LONG),
+                            LocalKind::Float => Some(FLOAT),
+                            LocalKind::Double => Some(DOUBLE),
+                            LocalKind::Reference => None,
+                        });
+                    let value = match &target_type {
+                        Some(t) => coerce(value, t),
+                        None => value,
+                    };
+                    state.effect(
+                        Stmt::Assign {
+                            target,
+                            value,
+                            target_type,
+                        },
+                        |e| e.reads_local(slot),
+                    );
+                }
+                LocalVariableAccess::Increment(slot) => {
+                    let constant = match instruction {
+                        Instruction::Iinc { constant, .. } => i32::from(*constant),
+                        Instruction::Wide {
+                            instruction: WideInstruction::Iinc { constant, .. },
+                        } => i32::from(*constant),
+                        _ => return Err("unexpected increment".to_string()),
+                    };
+                    let target = self.names.local(slot, &[pc.0]);
+                    let operator = match constant {
+                        1 => "++",
+                        -1 => "--",
+                        _ => "",
+                    };
+                    let reads = state.stack.iter().filter(|e| e.reads_local(slot)).count();
+                    let loaded_on_top = matches!(
+                        state.stack.last(),
+                        Some(Expr::Local { slot: s, .. }) if *s == slot
+                    );
+                    if reads == 1 && loaded_on_top && !operator.is_empty() {
+                        let top = state.pop()?;
+                        state.stack.push(Expr::Increment {
+                            target: Box::new(top),
+                            operator,
+                        });
+                    } else {
+                        let (operator, amount) = if constant < 0 {
+                            ("-", -constant)
+                        } else {
+                            ("+", constant)
+                        };
+                        let value = Expr::binary(operator, target.clone(), Expr::int(amount));
+                        state.effect(
+                            Stmt::Assign {
+                                target,
+                                value,
+                                target_type: Some(INT),
+                            },
+                            |e| e.reads_local(slot),
+                        );
+                    }
+                }
+                LocalVariableAccess::Ret(_) => {
+                    return Err("subroutines are not supported".to_string());
+                }
+            }
+            return Ok(None);
+        }
+
+        let string_type = || FieldType::Object("java/lang/String".to_string());
+        match instruction {
+            Instruction::Nop => {}
+            Instruction::Aconst_null => state.stack.push(Expr::null()),
+            Instruction::Iconst_m1 => state.stack.push(Expr::int(-1)),
+            Instruction::Iconst_0 => state.stack.push(Expr::int(0)),
+            Instruction::Iconst_1 => state.stack.push(Expr::int(1)),
+            Instruction::Iconst_2 => state.stack.push(Expr::int(2)),
+            Instruction::Iconst_3 => state.stack.push(Expr::int(3)),
+            Instruction::Iconst_4 => state.stack.push(Expr::int(4)),
+            Instruction::Iconst_5 => state.stack.push(Expr::int(5)),
+            Instruction::Bipush { byte } => state.stack.push(Expr::int(i32::from(*byte as i8))),
+            Instruction::Sipush { short } => state.stack.push(Expr::int(i32::from(*short))),
+            Instruction::Lconst_0 => state.stack.push(Expr::literal("0L".to_string(), LONG)),
+            Instruction::Lconst_1 => state.stack.push(Expr::literal("1L".to_string(), LONG)),
+            Instruction::Fconst_0 => state.stack.push(Expr::literal(float_literal(0.0), FLOAT)),
+            Instruction::Fconst_1 => state.stack.push(Expr::literal(float_literal(1.0), FLOAT)),
+            Instruction::Fconst_2 => state.stack.push(Expr::literal(float_literal(2.0), FLOAT)),
+            Instruction::Dconst_0 => state.stack.push(Expr::literal(double_literal(0.0), DOUBLE)),
+            Instruction::Dconst_1 => state.stack.push(Expr::literal(double_literal(1.0), DOUBLE)),
+            Instruction::Ldc { index: _ }
+            | Instruction::Ldc_w { index: _ }
+            | Instruction::Ldc2_w { index: _ } => {
+                let index = match instruction {
+                    Instruction::Ldc { index } => u16::from(*index),
+                    Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => *index,
+                    _ => unreachable!("the instruction is a ldc"),
+                };
+                let constants = &self.class.constants;
+                let value = match constants.get(index).map_err(|err| err.to_string())? {
+                    ConstantPoolEntry::Integer(value) => Expr::int(*value),
+                    ConstantPoolEntry::Float(value) => Expr::literal(float_literal(*value), FLOAT),
+                    ConstantPoolEntry::Long(value) => Expr::literal(format!("{value}L"), LONG),
+                    ConstantPoolEntry::Double(value) => {
+                        Expr::literal(double_literal(*value), DOUBLE)
+                    }
+                    ConstantPoolEntry::StringReference(_) => Expr::literal(
+                        string_literal(&constants.text_of(index).map_err(|err| err.to_string())?),
+                        string_type(),
+                    ),
+                    ConstantPoolEntry::ClassReference(_) => {
+                        Expr::ClassLiteral(self.class_constant(index)?)
+                    }
+                    entry => return Err(format!("cannot load constant {entry:?}")),
+                };
+                state.stack.push(value);
+            }
+
+            Instruction::Iaload
+            | Instruction::Laload
+            | Instruction::Faload
+            | Instruction::Daload
+            | Instruction::Aaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload => {
+                let index = state.pop()?;
+                let array = state.pop()?;
+                let component = match array.value_type() {
+                    Some(FieldType::Array(component)) => Some(*component),
+                    _ => None,
+                };
+                let element_type = match instruction {
+                    Instruction::Iaload => Some(INT),
+                    Instruction::Laload => Some(LONG),
+                    Instruction::Faload => Some(FLOAT),
+                    Instruction::Daload => Some(DOUBLE),
+                    Instruction::Caload => Some(FieldType::Base(BaseType::Char)),
+                    Instruction::Saload => Some(FieldType::Base(BaseType::Short)),
+                    Instruction::Baload => component.or(Some(FieldType::Base(BaseType::Byte))),
+                    _ => component,
+                };
+                state.stack.push(Expr::ArrayElement {
+                    array: Box::new(array),
+                    index: Box::new(index),
+                    element_type,
+                });
+            }
+            Instruction::Iastore
+            | Instruction::Lastore
+            | Instruction::Fastore
+            | Instruction::Dastore
+            | Instruction::Aastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Sastore => {
+                let value = state.pop()?;
+                let index = state.pop()?;
+                let array = state.pop()?;
+                let element_type = match array.value_type() {
+                    Some(FieldType::Array(component)) => Some(*component),
+                    _ if matches!(instruction, Instruction::Castore) => {
+                        Some(FieldType::Base(BaseType::Char))
+                    }
+                    _ => None,
+                };
+                let value = match &element_type {
+                    Some(t) => coerce(value, t),
+                    None => value,
+                };
+                // `javac` builds array initializers by storing into a copy of the new array
+                if state.stack.last() == Some(&array) {
+                    if let Some(initializer) = array_initializer(&array, &index, &value) {
+                        *state.stack.last_mut().expect("the stack is not empty") = initializer;
+                        return Ok(None);
+                    }
+                }
+                state.memory_effect(Stmt::Assign {
+                    target: Expr::ArrayElement {
+                        array: Box::new(array),
+                        index: Box::new(index),
+                        element_type,
+                    },
+                    value,
+                    target_type: None,
+                });
+            }
+
+            Instruction::Pop | Instruction::Pop2 => {
+                let slots = if matches!(instruction, Instruction::Pop) {
+                    1
+                } else {
+                    2
+                };
+                let count = Self::values_for_slots(stack_kinds, 0, slots)?;
+                for value in state.pop_many(count)? {
+                    if value.has_side_effects() {
+                        state.memory_effect(Stmt::Expression(value));
+                    }
+                }
+            }
+            Instruction::Dup => Self::dup(state, stack_kinds, 1, 0)?,
+            Instruction::Dup_x1 => Self::dup(state, stack_kinds, 1, 1)?,
+            Instruction::Dup_x2 => Self::dup(state, stack_kinds, 1, 2)?,
+            Instruction::Dup2 => Self::dup(state, stack_kinds, 2, 0)?,
+            Instruction::Dup2_x1 => Self::dup(state, stack_kinds, 2, 1)?,
+            Instruction::Dup2_x2 => Self::dup(state, stack_kinds, 2, 2)?,
+            Instruction::Swap => {
+                let top = state.pop()?;
+                let below = state.pop()?;
+                state.stack.push(top);
+                state.stack.push(below);
+            }
+
+            Instruction::Iadd | Instruction::Ladd | Instruction::Fadd | Instruction::Dadd => {
+                Self::binary(state, "+")?
+            }
+            Instruction::Isub | Instruction::Lsub | Instruction::Fsub | Instruction::Dsub => {
+                Self::binary(state, "-")?
+            }
+            Instruction::Imul | Instruction::Lmul | Instruction::Fmul | Instruction::Dmul => {
+                Self::binary(state, "*")?
+            }
+            Instruction::Idiv | Instruction::Ldiv | Instruction::Fdiv | Instruction::Ddiv => {
+                Self::binary(state, "/")?
+            }
+            Instruction::Irem | Instruction::Lrem | Instruction::Frem | Instruction::Drem => {
+                Self::binary(state, "%")?
+            }
+            Instruction::Ishl | Instruction::Lshl => Self::binary(state, "<<")?,
+            Instruction::Ishr | Instruction::Lshr => Self::binary(state, ">>")?,
+            Instruction::Iushr | Instruction::Lushr => Self::binary(state, ">>>")?,
+            Instruction::Iand | Instruction::Land => Self::binary(state, "&")?,
+            Instruction::Ior | Instruction::Lor => Self::binary(state, "|")?,
+            Instruction::Ixor | Instruction::Lxor => Self::binary(state, "^")?,
+            Instruction::Ineg | Instruction::Lneg | Instruction::Fneg | Instruction::Dneg => {
+                let operand = state.pop()?;
+                state.stack.push(Expr::Unary {
+                    operator: "-",
+                    operand: Box::new(operand),
+                });
+            }
+            Instruction::I2l | Instruction::F2l | Instruction::D2l => Self::cast(state, LONG)?,
+            Instruction::I2f | Instruction::L2f | Instruction::D2f => Self::cast(state, FLOAT)?,
+            Instruction::I2d | Instruction::L2d | Instruction::F2d => Self::cast(state, DOUBLE)?,
+            Instruction::L2i | Instruction::F2i | Instruction::D2i => Self::cast(state, INT)?,
+            Instruction::I2b => Self::cast(state, FieldType::Base(BaseType::Byte))?,
+            Instruction::I2c => Self::cast(state, FieldType::Base(BaseType::Char))?,
+            Instruction::I2s => Self::cast(state, FieldType::Base(BaseType::Short))?,
+            Instruction::Lcmp
+            | Instruction::Fcmpl
+            | Instruction::Fcmpg
+            | Instruction::Dcmpl
+            | Instruction::Dcmpg => {
+                let right = state.pop()?;
+                let left = state.pop()?;
+                state.stack.push(Expr::Compare {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                });
+            }
+
+            Instruction::Ifeq { jump_address }
+            | Instruction::Ifne { jump_address }
+            | Instruction::Iflt { jump_address }
+            | Instruction::Ifge { jump_address }
+            | Instruction::Ifgt { jump_address }
+            | Instruction::Ifle { jump_address }
+            | Instruction::Ifnull { jump_address }
+            | Instruction::Ifnonnull { jump_address } => {
+                let value = state.pop()?;
+                let condition = match instruction {
+                    Instruction::Ifeq { .. } => compare_with_zero(value, "=="),
+                    Instruction::Ifne { .. } => compare_with_zero(value, "!="),
+                    Instruction::Iflt { .. } => compare_with_zero(value, "<"),
+                    Instruction::Ifge { .. } => compare_with_zero(value, ">="),
+                    Instruction::Ifgt { .. } => compare_with_zero(value, ">"),
+                    Instruction::Ifle { .. } => compare_with_zero(value, "<="),
+                    Instruction::Ifnull { .. } => Expr::binary("==", value, Expr::null()),
+                    _ => Expr::binary("!=", value, Expr::null()),
+                };
+                return self.branch(condition, *jump_address, next_pc);
+            }
+            Instruction::If_icmpeq { jump_address }
+            | Instruction::If_icmpne { jump_address }
+            | Instruction::If_icmplt { jump_address }
+            | Instruction::If_icmpge { jump_address }
+            | Instruction::If_icmpgt { jump_address }
+            | Instruction::If_icmple { jump_address }
+            | Instruction::If_acmpeq { jump_address }
+            | Instruction::If_acmpne { jump_address } => {
+                let right = state.pop()?;
+                let left = state.pop()?;
+                let operator = match instruction {
+                    Instruction::If_icmpeq { .. } | Instruction::If_acmpeq { .. } => "==",
+                    Instruction::If_icmpne { .. } | Instruction::If_acmpne { .. } => "!=",
+                    Instruction::If_icmplt { .. } => "<",
+                    Instruction::If_icmpge { .. } => ">=",
+                    Instruction::If_icmpgt { .. } => ">",
+                    _ => "<=",
+                };
+                return self.branch(compare(operator, left, right), *jump_address, next_pc);
+            }
+            Instruction::Goto { jump_address } | Instruction::Goto_w { jump_address } => {
+                return Ok(Some(Exit::Jump(self.block_at(*jump_address)?)));
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                jump_addresses,
+                ..
+            } => {
+                let value = state.pop()?;
+                let cases = jump_addresses
+                    .iter()
+                    .zip(*low..)
+                    .map(|(address, key)| Ok((key, self.block_at(*address)?)))
+                    .collect::<Result<_, String>>()?;
+                return Ok(Some(Exit::Switch {
+                    value,
+                    cases,
+                    default: self.block_at(*default)?,
+                }));
+            }
+            Instruction::Lookupswitch {
+                default,
+                match_pairs,
+            } => {
+                let value = state.pop()?;
+                let cases = match_pairs
+                    .iter()
+                    .map(|(key, address)| Ok((*key, self.block_at(*address)?)))
+                    .collect::<Result<_, String>>()?;
+                return Ok(Some(Exit::Switch {
+                    value,
+                    cases,
+                    default: self.block_at(*default)?,
+                }));
+            }
+            Instruction::Ireturn
+            | Instruction::Lreturn
+            | Instruction::Freturn
+            | Instruction::Dreturn
+            | Instruction::Areturn => {
+                let value = state.pop()?;
+                let value = match &self.method.parsed_type_descriptor.return_type {
+                    Some(return_type) => coerce(value, return_type),
+                    None => value,
+                };
+                return Ok(Some(Exit::Return(Some(value))));
+            }
+            Instruction::Return => return Ok(Some(Exit::Return(None))),
+            Instruction::Athrow => return Ok(Some(Exit::Throw(state.pop()?))),
+
+            Instruction::Getstatic { field } | Instruction::Getfield { field } => {
+                let reference = self.member(*field)?;
+                let field_type =
+                    FieldType::parse(&reference.type_descriptor).map_err(|err| err.to_string())?;
+                let object = match instruction {
+                    Instruction::Getstatic { .. } => Expr::ClassName(reference.class_name),
+                    _ => state.pop()?,
+                };
+                state.stack.push(Expr::Field {
+                    object: Box::new(object),
+                    name: reference.name,
+                    field_type,
+                });
+            }
+            Instruction::Putstatic { field } | Instruction::Putfield { field } => {
+                let reference = self.member(*field)?;
+                let field_type =
+                    FieldType::parse(&reference.type_descriptor).map_err(|err| err.to_string())?;
+                let value = coerce(state.pop()?, &field_type);
+                let object = match instruction {
+                    Instruction::Putstatic { .. } => Expr::ClassName(reference.class_name),
+                    _ => state.pop()?,
+                };
+                state.memory_effect(Stmt::Assign {
+                    target: Expr::Field {
+                        object: Box::new(object),
+                        name: reference.name,
+                        field_type,
+                    },
+                    value,
+                    target_type: None,
+                });
+            }
+            Instruction::Invokevirtual { method }
+            | Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                self.invoke(state, instruction, *method)?;
+            }
+            Instruction::Invokedynamic { .. } => {
+                return Err("invokedynamic is not supported".to_string());
+            }
+
+            Instruction::New { class } => {
+                let FieldType::Object(class) = self.class_constant(*class)? else {
+                    return Err(format!("cannot instantiate an array at address {pc}"));
+                };
+                state.stack.push(Expr::Uninitialized {
+                    id: self.objects,
+                    class,
+                });
+                self.objects += 1;
+            }
+            Instruction::Newarray { array_type } => {
+                let element_type = match array_type {
+                    NewArrayType::Boolean => BaseType::Boolean,
+                    NewArrayType::Char => BaseType::Char,
+                    NewArrayType::Float => BaseType::Float,
+                    NewArrayType::Double => BaseType::Double,
+                    NewArrayType::Byte => BaseType::Byte,
+                    NewArrayType::Short => BaseType::Short,
+                    NewArrayType::Int => BaseType::Int,
+                    NewArrayType::Long => BaseType::Long,
+                };
+                let length = state.pop()?;
+                state.stack.push(Expr::NewArray {
+                    array_type: FieldType::Array(Box::new(FieldType::Base(element_type))),
+                    lengths: vec![length],
+                });
+            }
+            Instruction::Anewarray { class } => {
+                let component = self.class_constant(*class)?;
+                let length = state.pop()?;
+                state.stack.push(Expr::NewArray {
+                    array_type: FieldType::Array(Box::new(component)),
+                    lengths: vec![length],
+                });
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                let array_type = self.class_constant(*class)?;
+                let lengths = state.pop_many(usize::from(*dimensions))?;
+                state.stack.push(Expr::NewArray {
+                    array_type,
+                    lengths,
+                });
+            }
+            Instruction::Arraylength => {
+                let array = state.pop()?;
+                state.stack.push(Expr::ArrayLength(Box::new(array)));
+            }
+            Instruction::Checkcast { class } => {
+                let target = self.class_constant(*class)?;
+                Self::cast(state, target)?;
+            }
+            Instruction::Instanceof { class } => {
+                let target = self.class_constant(*class)?;
+                let operand = state.pop()?;
+                state.stack.push(Expr::InstanceOf {
+                    operand: Box::new(operand),
+                    target,
+                });
+            }
+            Instruction::Monitorenter | Instruction::Monitorexit => {
+                let name = match instruction {
+                    Instruction::Monitorenter => "monitorenter",
+                    _ => "monitorexit",
+                };
+                let object = state.pop()?;
+                // The values computed inside a `synchronized` block, and returned after
+                // exiting the lock, are shown as if they were computed after it
+                state.effect(
+                    Stmt::Expression(Expr::Call {
+                        receiver: None,
+                        name: name.to_string(),
+                        arguments: vec![object],
+                        return_type: None,
+                    }),
+                    |_| false,
+                );
+            }
+            Instruction::Jsr { .. } | Instruction::Jsr_w { .. } => {
+                return Err("subroutines are not supported".to_string());
+            }
+            _ => {
+                return Err(format!(
+                    "unexpected instruction {instruction:?} at address {pc}"
+                ))
+            }
+        }
+        Ok(None)
+    }
+
+    fn branch(
+        &self,
+        condition: Expr,
+        jump_address: u16,
+        next_pc: ProgramCounter,
+    ) -> Result<Option<Exit>, String> {
+        Ok(Some(Exit::Branch {
+            condition,
+            target: self.block_at(jump_address)?,
+            fallthrough: self.block_at(next_pc.0)?,
+        }))
+    }
+
+    fn binary(state: &mut BlockState, operator: &'static str) -> Result<(), String> {
+        let right = state.pop()?;
+        let left = state.pop()?;
+        state.stack.push(Expr::binary(operator, left, right));
+        Ok(())
+    }
+
+    fn cast(state: &mut BlockState, target: FieldType) -> Result<(), String> {
+        let operand = state.pop()?;
+        state.stack.push(Expr::Cast {
+            target,
+            operand: Box::new(operand),
+        });
+        Ok(())
+    }
+
+    /// Returns how many values, from the top of the stack after skipping the given number of
+    /// values, take the given number of slots
+    fn values_for_slots(kinds: &[BasicValue], skip: usize, slots: u16) -> Result<usize, String> {
+        let mut taken = 0;
+        let mut count = 0;
+        for kind in kinds.iter().rev().skip(skip) {
+            if taken >= slots {
+                break;
+            }
+            taken += kind.slots();
+            count += 1;
+        }
+        if taken == slots {
+            Ok(count)
+        } else {
+            Err("the operand stack does not match the instruction".to_string())
+        }
+    }
+
+    /// Duplicates the values taking `top_slots` at the top of the stack, inserting the copies
+    /// below the values taking the next `below_slots`
+    fn dup(
+        state: &mut BlockState,
+        kinds: &[BasicValue],
+        top_slots: u16,
+        below_slots: u16,
+    ) -> Result<(), String> {
+        let top = Self::values_for_slots(kinds, 0, top_slots)?;
+        let below = Self::values_for_slots(kinds, top, below_slots)?;
+        let length = state.stack.len();
+        for index in length - top..length {
+            let value = &state.stack[index];
+            let is_array_initializer = matches!(value, Expr::ArrayInit { .. })
+                || matches!(value, Expr::NewArray { lengths, .. }
+                    if lengths.len() == 1 && lengths[0].int_value().is_some());
+            if !value.is_simple() && !is_array_initializer {
+                state.spill_at(index);
+            }
+        }
+        let copies = state.stack[length - top..].to_vec();
+        let position = length - top - below;
+        state.stack.splice(position..position, copies);
+        Ok(())
+    }
+
+    fn invoke(
+        &mut self,
+        state: &mut BlockState,
+        instruction: &Instruction,
+        index: u16,
+    ) -> Result<(), String> {
+        let reference = self.member(index)?;
+        let descriptor =
+            MethodDescriptor::parse(&reference.type_descriptor).map_err(|err| err.to_string())?;
+        let arguments = state
+            .pop_many(descriptor.parameters.len())?
+            .into_iter()
+            .zip(descriptor.parameters.iter())
+            .map(|(argument, parameter)| coerce(argument, parameter))
+            .collect();
+        let receiver = match instruction {
+            Instruction::Invokestatic { .. } => Expr::ClassName(reference.class_name.clone()),
+            _ => state.pop()?,
+        };
+
+        if matches!(instruction, Instruction::Invokespecial { .. }) && reference.name == "<init>" {
+            if let Expr::Uninitialized { id, class } = &receiver {
+                let created = Expr::New {
+                    class: class.clone(),
+                    arguments,
+                };
+                let mut replaced = false;
+                for value in state.stack.iter_mut() {
+                    if matches!(value, Expr::Uninitialized { id: other, .. } if other == id) {
+                        *value = created.clone();
+                        replaced = true;
+                    }
+                }
+                if !replaced {
+                    state.memory_effect(Stmt::Expression(created));
+                }
+            } else {
+                let name = if reference.class_name == self.class.name {
+                    "this"
+                } else {
+                    "super"
+                };
+                state.memory_effect(Stmt::Expression(Expr::Call {
+                    receiver: None,
+                    name: name.to_string(),
+                    arguments,
+                    return_type: None,
+                }));
+            }
+            return Ok(());
+        }
+
+        let receiver = match receiver {
+            Expr::Local { ref name, .. }
+                if name == "this"
+                    && matches!(instruction, Instruction::Invokespecial { .. })
+                    && reference.class_name != self.class.name =>
+            {
+                Expr::Temporary {
+                    name: "super".to_string(),
+                    value_type: None,
+                }
+            }
+            receiver => receiver,
+        };
+        let call = Expr::Call {
+            receiver: Some(Box::new(receiver)),
+            name: reference.name,
+            arguments,
+            return_type: descriptor.return_type,
+        };
+        let call = match &call {
+            Expr::Call {
+                receiver: Some(receiver),
+                name,
+                arguments,
+                ..
+            } if name == "toString" && arguments.is_empty() => {
+                string_concatenation(receiver).unwrap_or(call)
+            }
+            _ => call,
+        };
+        if call.value_type().is_some() {
+            state.stack.push(call);
+        } else {
+            state.memory_effect(Stmt::Expression(call));
+        }
+        Ok(())
+    }
+
+    /// Returns the statement that jumps to the given block, if the block was already emitted
+    /// or is where an enclosing loop or switch continues
+    fn jump_statement(&mut self, block: BlockId) -> Option<Stmt> {
+        let innermost = self.breakables.len().checked_sub(1);
+        let innermost_loop = self.breakables.iter().rposition(|b| b.header.is_some());
+        for (index, breakable) in self.breakables.iter().enumerate().rev() {
+            if breakable.header == Some(block) {
+                let label = (Some(index) != innermost_loop).then(|| breakable.label.clone());
+                return Some(Stmt::Continue(label));
+            }
+            if breakable.follow == Some(block) {
+                let label = (Some(index) != innermost).then(|| breakable.label.clone());
+                return Some(Stmt::Break(label));
+            }
+        }
+        if self.emitted[block] {
+            self.goto_targets.insert(block);
+            return Some(Stmt::Goto(self.label(block)));
+        }
+        None
+    }
+
+    /// Emits the code starting at the given block until reaching one of the stops, which is
+    /// returned, or a statement that does not continue
+    fn sequence(
+        &mut self,
+        start: BlockId,
+        stack: Option<Vec<Expr>>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Option<BlockId>, String> {
+        let (mut block, mut stack) = (start, stack);
+        loop {
+            if stops.contains(&block) {
+                self.spill_stack(stack.unwrap_or_default(), out);
+                return Ok(Some(block));
+            }
+            if stack.is_none() && !self.emitted[block] && self.is_fully_inlined(block) {
+                return Ok(None);
+            }
+            if let Some(jump) = self.jump_statement(block) {
+                self.spill_stack(stack.unwrap_or_default(), out);
+                out.push(jump);
+                return Ok(None);
+            }
+            match self.emit_block(block, stack, stops, out)? {
+                Flow::Continue(next, next_stack) => (block, stack) = (next, next_stack),
+                Flow::Stopped(reached) => return Ok(reached),
+            }
+        }
+    }
+
+    /// Like [Self::sequence], but always emits the first block
+    fn sequence_from(
+        &mut self,
+        start: BlockId,
+        stack: Option<Vec<Expr>>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Option<BlockId>, String> {
+        match self.emit_block(start, stack, stops, out)? {
+            Flow::Continue(next, stack) => self.sequence(next, stack, stops, out),
+            Flow::Stopped(reached) => Ok(reached),
+        }
+    }
+
+    fn is_inactive_loop_header(&self, block: BlockId) -> bool {
+        self.loops.iter().any(|l| l.header == block)
+            && !self.breakables.iter().any(|b| b.header == Some(block))
+    }
+
+    fn emit_block(
+        &mut self,
+        block: BlockId,
+        stack: Option<Vec<Expr>>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Flow, String> {
+        if self.labels.contains(&block) && !self.labeled[block] {
+            self.labeled[block] = true;
+            out.push(Stmt::Label(self.label(block)));
+        }
+        let inactive_header = self.is_inactive_loop_header(block);
+        if let Some(entries) = self.try_region(block) {
+            let covers_loop = !inactive_header
+                || self.loops.iter().any(|l| {
+                    l.header == block
+                        && l.blocks.iter().all(|b| {
+                            entries[0].range.contains(&self.start(*b))
+                                || self.only_exits_between(
+                                    self.start(*b),
+                                    self.graph.block(*b).range.end,
+                                )
+                        })
+                });
+            if covers_loop {
+                return self.emit_try(block, entries, stops, out);
+            }
+        }
+        if inactive_header {
+            return self.emit_loop(block, out);
+        }
+
+        self.emitted[block] = true;
+        let stack = stack.unwrap_or_else(|| self.entry_stack(block));
+        let lifted = self.lift(block, stack)?;
+        out.extend(lifted.statements);
+        match lifted.exit {
+            Exit::Return(value) => {
+                out.push(Stmt::Return(value));
+                Ok(Flow::Stopped(None))
+            }
+            Exit::Throw(value) => {
+                out.push(Stmt::Throw(value));
+                Ok(Flow::Stopped(None))
+            }
+            Exit::Jump(target) => {
+                if let Some(value) = self.returned_value(target, &lifted.stack) {
+                    // `javac` shares the instruction returning a value between the branches
+                    // computing it, as in `return a ? b : c;`
+                    self.inlined_returns.insert(block);
+                    out.push(Stmt::Return(Some(value)));
+                    Ok(Flow::Stopped(None))
+                } else if lifted.stack.is_empty() {
+                    Ok(Flow::Continue(target, None))
+                } else if self.graph.predecessors(target).count() == 1 {
+                    Ok(Flow::Continue(target, Some(lifted.stack)))
+                } else {
+                    self.spill_stack(lifted.stack, out);
+                    Ok(Flow::Continue(target, None))
+                }
+            }
+            Exit::Branch {
+                condition,
+                target,
+                fallthrough,
+            } => self.emit_if(
+                block,
+                condition,
+                target,
+                fallthrough,
+                lifted.stack,
+                stops,
+                out,
+            ),
+            Exit::Switch {
+                value,
+                cases,
+                default,
+            } => self.emit_switch(block, value, cases, default, lifted.stack, stops, out),
+        }
+    }
+
+    /// Whether all the blocks jumping to the given one already returned its value
+    fn is_fully_inlined(&self, block: BlockId) -> bool {
+        !self.inlined_returns.is_empty()
+            && self
+                .graph
+                .predecessors(block)
+                .all(|edge| self.inlined_returns.contains(&edge.from))
+            && self.graph.predecessors(block).next().is_some()
+    }
+
+    /// Returns the value returned by the given block, if it only returns the value on the
+    /// top of the given stack
+    fn returned_value(&self, block: BlockId, stack: &[Expr]) -> Option<Expr> {
+        let [value] = stack else {
+            return None;
+        };
+        if !self.is_value_return(block) || self.labels.contains(&block) {
+            return None;
+        }
+        Some(match &self.method.parsed_type_descriptor.return_type {
+            Some(return_type) => coerce(value.clone(), return_type),
+            None => value.clone(),
+        })
+    }
+
+    /// Whether the block only returns the value on the top of the stack
+    fn is_value_return(&self, block: BlockId) -> bool {
+        matches!(
+            self.graph.block(block).instructions.as_slice(),
+            [(
+                _,
+                Instruction::Ireturn
+                    | Instruction::Lreturn
+                    | Instruction::Freturn
+                    | Instruction::Dreturn
+                    | Instruction::Areturn
+            )]
+        )
+    }
+
+    /// Returns where the two branches of the given block meet again, if it is in the current
+    /// loop and not emitted yet
+    fn merge_point(&self, block: BlockId) -> Option<BlockId> {
+        let merge = self.post_dominators.immediate_dominator(block)?;
+        (self.in_current_loop(merge) && !self.emitted[merge]).then_some(merge)
+    }
+
+    /// Returns the first block reached by both the given branches, for the branches that do
+    /// not have a merge point because one of them can return or throw
+    fn joint_block(&self, first: BlockId, second: BlockId) -> Option<BlockId> {
+        let reached_from_first = self.reachable_in_current_loop(first);
+        let reached_from_second = self.reachable_in_current_loop(second);
+        (0..self.graph.blocks().len()).find(|block| {
+            reached_from_first[*block] && reached_from_second[*block] && !self.emitted[*block]
+        })
+    }
+
+    /// Returns the blocks reachable from the given one through normal edges, without leaving
+    /// the innermost loop being emitted nor going back to its header
+    fn reachable_in_current_loop(&self, start: BlockId) -> Vec<bool> {
+        let current_loop = self.breakables.iter().rev().find(|b| b.header.is_some());
+        let mut reached = vec![false; self.graph.blocks().len()];
+        let mut pending = vec![start];
+        while let Some(block) = pending.pop() {
+            let is_outside = current_loop.is_some_and(|current_loop| {
+                current_loop.header == Some(block) || !current_loop.blocks.contains(&block)
+            });
+            if reached[block] || is_outside || self.emitted[block] {
+                continue;
+            }
+            reached[block] = true;
+            pending.extend(
+                self.graph
+                    .successors(block)
+                    .filter(|edge| !matches!(edge.kind, EdgeKind::Exceptional(_)))
+                    .map(|edge| edge.to),
+            );
+        }
+        reached
+    }
+
+    /// Whether the block is part of the innermost loop being emitted, if any
+    fn in_current_loop(&self, block: BlockId) -> bool {
+        self.breakables
+            .iter()
+            .rev()
+            .find(|b| b.header.is_some())
+            .is_none_or(|current_loop| current_loop.blocks.contains(&block))
+    }
+
+    /// Whether a block can be folded into the expression of the blocks jumping to it, which
+    /// must all be among the given ones
+    fn is_foldable(&self, block: BlockId, folded: &[BlockId]) -> bool {
+        !self.emitted[block]
+            && !folded.contains(&block)
+            && !self.labels.contains(&block)
+            && !self.is_handler(block)
+            && !self.loops.iter().any(|l| l.header == block)
+            && self
+                .graph
+                .predecessors(block)
+                .all(|edge| folded.contains(&edge.from))
+            && !self.code.exception_table.entries().iter().any(|entry| {
+                entry.range.start == self.start(block)
+                    && !self.consumed_handlers.contains(&entry.handler_pc)
+            })
+    }
+
+    /// Lifts a block without emitting it, returning its condition and successors if it only
+    /// tests a condition
+    fn condition_block(
+        &mut self,
+        block: BlockId,
+        stack: &[Expr],
+        folded: &[BlockId],
+    ) -> Option<(Expr, BlockId, BlockId)> {
+        if !self.is_foldable(block, folded) {
+            return None;
+        }
+        let (temporaries, objects) = (self.temporaries, self.objects);
+        let lifted = self.lift(block, stack.to_vec()).ok();
+        match lifted {
+            Some(Lifted {
+                statements,
+                stack: remaining,
+                exit:
+                    Exit::Branch {
+                        condition,
+                        target,
+                        fallthrough,
+                    },
+            }) if statements.is_empty() && remaining == stack => {
+                Some((condition, target, fallthrough))
+            }
+            _ => {
+                (self.temporaries, self.objects) = (temporaries, objects);
+                None
+            }
+        }
+    }
+
+    /// Lifts a block without emitting it, returning the value it pushes if it only computes
+    /// one value before jumping to the given merge point. Blocks testing a condition give
+    /// a conditional expression, if both their successors compute a value.
+    fn value_block(
+        &mut self,
+        block: BlockId,
+        stack: &[Expr],
+        merge: BlockId,
+        folded: &mut Vec<BlockId>,
+    ) -> Option<Expr> {
+        if !self.is_foldable(block, folded) {
+            return None;
+        }
+        let lifted = self.lift(block, stack.to_vec()).ok()?;
+        if !lifted.statements.is_empty() {
+            return None;
+        }
+        folded.push(block);
+        match lifted.exit {
+            Exit::Jump(target)
+                if target == merge
+                    && lifted.stack.len() == stack.len() + 1
+                    && lifted.stack[..stack.len()] == *stack =>
+            {
+                lifted.stack.last().cloned()
+            }
+            Exit::Branch {
+                condition,
+                target,
+                fallthrough,
+            } if lifted.stack == stack && target != merge && fallthrough != merge => {
+                let then = self.value_block(fallthrough, stack, merge, folded)?;
+                let otherwise = self.value_block(target, stack, merge, folded)?;
+                Some(conditional(negate(condition), then, otherwise))
+            }
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn emit_if(
+        &mut self,
+        block: BlockId,
+        condition: Expr,
+        target: BlockId,
+        fallthrough: BlockId,
+        mut stack: Vec<Expr>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Flow, String> {
+        let (mut condition, mut target, mut fallthrough) = (condition, target, fallthrough);
+        // Conditions combined with `&&` and `||` are blocks that only test a condition, and
+        // share one of their successors with the previous one
+        let mut folded = vec![block];
+        while let Some((next_condition, next_target, next_fallthrough)) =
+            self.condition_block(fallthrough, &stack, &folded)
+        {
+            let tested = fallthrough;
+            if next_target == target {
+                condition = Expr::binary("||", condition, next_condition);
+                fallthrough = next_fallthrough;
+            } else if next_fallthrough == target {
+                condition = Expr::binary("&&", negate(condition), next_condition);
+                fallthrough = target;
+                target = next_target;
+            } else {
+                break;
+            }
+            self.emitted[tested] = true;
+            folded.push(tested);
+        }
+
+        let merge = self
+            .merge_point(block)
+            .or_else(|| self.joint_block(target, fallthrough));
+        if let Some(merge) = merge.filter(|m| {
+            target != *m && fallthrough != *m && !self.loops.iter().any(|l| l.header == *m)
+        }) {
+            let (temporaries, objects) = (self.temporaries, self.objects);
+            let mut values_folded = folded.clone();
+            let then = self.value_block(fallthrough, &stack, merge, &mut values_folded);
+            let otherwise = match then {
+                Some(_) => self.value_block(target, &stack, merge, &mut values_folded),
+                None => None,
+            };
+            if let (Some(then), Some(otherwise)) = (then, otherwise) {
+                for value_block in values_folded {
+                    self.emitted[value_block] = true;
+                }
+                stack.push(conditional(negate(condition), then, otherwise));
+                return Ok(Flow::Continue(merge, Some(stack)));
+            }
+            (self.temporaries, self.objects) = (temporaries, objects);
+        }
+
+        self.spill_stack(stack, out);
+        if Some(target) != merge && !stops.contains(&target) {
+            if let Some(jump) = self.jump_statement(target) {
+                push_if(out, condition, vec![jump], Vec::new());
+                return Ok(Flow::Continue(fallthrough, None));
+            }
+        }
+        if Some(fallthrough) != merge && !stops.contains(&fallthrough) {
+            if let Some(jump) = self.jump_statement(fallthrough) {
+                push_if(out, negate(condition), vec![jump], Vec::new());
+                return Ok(Flow::Continue(target, None));
+            }
+        }
+
+        let inner_stops: Vec<BlockId> = stops.iter().copied().chain(merge).collect();
+        let (condition, then_block, else_block) = if Some(fallthrough) == merge {
+            (condition, target, None)
+        } else if Some(target) == merge {
+            (negate(condition), fallthrough, None)
+        } else {
+            (negate(condition), fallthrough, Some(target))
+        };
+        let mut then = Vec::new();
+        let then_reached = self.sequence(then_block, None, &inner_stops, &mut then)?;
+        let mut otherwise = Vec::new();
+        let else_reached = match else_block {
+            Some(else_block) => self.sequence(else_block, None, &inner_stops, &mut otherwise)?,
+            None => None,
+        };
+        push_if(out, condition, then, otherwise);
+        Ok(match merge {
+            Some(merge) => Flow::Continue(merge, None),
+            None => Flow::Stopped(then_reached.or(else_reached)),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn emit_switch(
+        &mut self,
+        block: BlockId,
+        value: Expr,
+        cases: Vec<(i32, BlockId)>,
+        default: BlockId,
+        stack: Vec<Expr>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Flow, String> {
+        self.spill_stack(stack, out);
+        // When some cases return, the others still meet after the switch
+        let merge = self.merge_point(block).or_else(|| {
+            let merges: Vec<BlockId> = cases
+                .iter()
+                .map(|(_, target)| *target)
+                .chain([default])
+                .filter_map(|target| self.post_dominators.immediate_dominator(target))
+                .collect();
+            self.common_post_dominator(&merges)
+                .filter(|merge| self.in_current_loop(*merge) && !self.emitted[*merge])
+        });
+        let key_type = value.value_type();
+        let targets: Vec<BlockId> = cases
+            .iter()
+            .map(|(_, target)| *target)
+            .chain([default])
+            .sorted()
+            .dedup()
+            .collect();
+        let label = format!("switch{}", self.start(block));
+        self.breakables.push(Breakable {
+            label: label.clone(),
+            header: None,
+            blocks: Vec::new(),
+            follow: merge,
+        });
+        let mut switch_cases = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let mut keys: Vec<Option<Expr>> = cases
+                .iter()
+                .filter(|(_, case_target)| case_target == target && *target != default)
+                .map(|(key, _)| {
+                    Some(match &key_type {
+                        Some(key_type) => coerce(Expr::int(*key), key_type),
+                        None => Expr::int(*key),
+                    })
+                })
+                .collect();
+            if *target == default {
+                if Some(default) == merge {
+                    continue;
+                }
+                keys.push(None);
+            }
+            let mut body = Vec::new();
+            if Some(*target) == merge {
+                body.push(Stmt::Break(None));
+            } else {
+                let case_stops: Vec<BlockId> = stops
+                    .iter()
+                    .copied()
+                    .chain(merge)
+                    .chain(targets.get(index + 1).copied())
+                    .collect();
+                let reached = self.sequence(*target, None, &case_stops, &mut body)?;
+                if reached.is_some() && reached == merge {
+                    body.push(Stmt::Break(None));
+                }
+            }
+            switch_cases.push((keys, body));
+        }
+        self.breakables.pop();
+        if let Some((_, body)) = switch_cases.last_mut() {
+            if body.len() > 1 && body.last() == Some(&Stmt::Break(None)) {
+                body.pop();
+            }
+        }
+        out.push(Stmt::Switch {
+            value,
+            label,
+            cases: switch_cases,
+        });
+        Ok(match merge {
+            Some(merge) => Flow::Continue(merge, None),
+            None => Flow::Stopped(None),
+        })
+    }
+
+    fn emit_loop(&mut self, header: BlockId, out: &mut Vec<Stmt>) -> Result<Flow, String> {
+        let natural_loop = self
+            .loops
+            .iter()
+            .find(|l| l.header == header)
+            .cloned()
+            .ok_or_else(|| "not a loop header".to_string())?;
+        let mut exits: Vec<BlockId> = Vec::new();
+        for block in natural_loop.blocks.iter() {
+            for edge in self.graph.successors(*block) {
+                if !matches!(edge.kind, EdgeKind::Exceptional(_))
+                    && !natural_loop.contains(edge.to)
+                    && !exits.contains(&edge.to)
+                {
+                    exits.push(edge.to);
+                }
+            }
+        }
+        // Prefer the exit of the condition of `while` loops, then the first block after the
+        // loop, which is where `javac` puts the code following it
+        let last = natural_loop.blocks.last().copied().unwrap_or(header);
+        let follow = exits
+            .iter()
+            .copied()
+            .find(|exit| self.graph.successors(header).any(|edge| edge.to == *exit))
+            .or_else(|| exits.iter().copied().filter(|exit| *exit > last).min())
+            .or_else(|| exits.iter().copied().min())
+            .filter(|follow| !self.emitted[*follow]);
+
+        let label = format!("loop{}", self.start(header));
+        self.breakables.push(Breakable {
+            label: label.clone(),
+            header: Some(header),
+            blocks: natural_loop.blocks.clone(),
+            follow,
+        });
+        let mut body = Vec::new();
+        let result = self.sequence_from(header, None, &[], &mut body);
+        self.breakables.pop();
+        result?;
+        let (kind, body) = shape_loop(body);
+        out.push(Stmt::Loop { kind, label, body });
+        Ok(match follow {
+            Some(follow) => Flow::Continue(follow, None),
+            None => Flow::Stopped(None),
+        })
+    }
+
+    /// Returns the entries of the exception table whose range starts at the given block,
+    /// and that were not structured yet. Only the widest range is returned, as it encloses
+    /// the others.
+    fn try_region(&self, block: BlockId) -> Option<Vec<ExceptionTableEntry>> {
+        let start = self.start(block);
+        let entries: Vec<ExceptionTableEntry> = self
+            .code
+            .exception_table
+            .entries()
+            .iter()
+            .filter(|entry| {
+                entry.range.start == start
+                    && !entry.range.contains(&entry.handler_pc)
+                    && !self.consumed_handlers.contains(&entry.handler_pc)
+                    && self.frames.contains_key(&entry.handler_pc)
+            })
+            .map(|entry| ExceptionTableEntry {
+                range: entry.range.start..self.extended_end(entry),
+                ..entry.clone()
+            })
+            .collect();
+        let end = entries.iter().map(|entry| entry.range.end).max()?;
+        Some(
+            entries
+                .into_iter()
+                .filter(|entry| entry.range.end == end)
+                .collect(),
+        )
+    }
+
+    /// Returns the end of the range of the `try` block of the given entry. `javac` leaves the
+    /// instructions returning from the block, which cannot throw, out of the ranges, splitting
+    /// them in several entries.
+    fn extended_end(&self, entry: &ExceptionTableEntry) -> ProgramCounter {
+        let mut end = entry.range.end;
+        while let Some(next) = self.code.exception_table.entries().iter().find(|next| {
+            next.handler_pc == entry.handler_pc
+                && next.catch_class == entry.catch_class
+                && next.range.start >= end
+                && !next.range.contains(&next.handler_pc)
+                && self.only_exits_between(end, next.range.start)
+        }) {
+            end = next.range.end;
+        }
+        end
+    }
+
+    /// Whether the instructions in the given range can only return or jump
+    fn only_exits_between(&self, start: ProgramCounter, end: ProgramCounter) -> bool {
+        self.graph
+            .blocks()
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .filter(|(pc, _)| (start..end).contains(pc))
+            .all(|(_, instruction)| {
+                matches!(
+                    instruction,
+                    Instruction::Ireturn
+                        | Instruction::Lreturn
+                        | Instruction::Freturn
+                        | Instruction::Dreturn
+                        | Instruction::Areturn
+                        | Instruction::Return
+                        | Instruction::Goto { .. }
+                        | Instruction::Goto_w { .. }
+                )
+            })
+    }
+
+    /// Follows the blocks made of a single `goto`, like the ones jumping over the catch blocks
+    fn skip_jumps(&self, block: BlockId) -> BlockId {
+        let mut current = block;
+        for _ in 0..self.graph.blocks().len() {
+            let target = match self.graph.block(current).instructions.as_slice() {
+                [(_, Instruction::Goto { jump_address })]
+                | [(_, Instruction::Goto_w { jump_address })] => {
+                    self.graph.block_at(ProgramCounter(*jump_address))
+                }
+                _ => None,
+            };
+            match target {
+                Some(target) => current = target,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Returns the closest block post-dominating all the given ones
+    fn common_post_dominator(&self, blocks: &[BlockId]) -> Option<BlockId> {
+        let (first, rest) = blocks.split_first()?;
+        let mut candidate = Some(*first);
+        while let Some(current) = candidate {
+            if rest
+                .iter()
+                .all(|block| self.post_dominators.dominates(current, *block))
+            {
+                return Some(current);
+            }
+            candidate = self.post_dominators.immediate_dominator(current);
+        }
+        None
+    }
+
+    fn emit_try(
+        &mut self,
+        block: BlockId,
+        entries: Vec<ExceptionTableEntry>,
+        stops: &[BlockId],
+        out: &mut Vec<Stmt>,
+    ) -> Result<Flow, String> {
+        let range: Range<ProgramCounter> = entries[0].range.clone();
+        // The classes caught by each handler, with `None` for the ones catching everything
+        let mut handlers: Vec<(Option<Vec<String>>, BlockId)> = Vec::new();
+        for entry in entries.iter() {
+            self.consumed_handlers.insert(entry.handler_pc);
+            let handler = self.block_at(entry.handler_pc.0)?;
+            let index = match handlers
+                .iter()
+                .position(|(_, existing)| *existing == handler)
+            {
+                Some(index) => index,
+                None => {
+                    handlers.push((Some(Vec::new()), handler));
+                    handlers.len() - 1
+                }
+            };
+            match (&mut handlers[index].0, &entry.catch_class) {
+                (Some(classes), Some(class)) => classes.push(class.clone()),
+                (classes, _) => *classes = None,
+            }
+        }
+
+        // The try and catch blocks meet where the code following the statement starts. The
+        // handlers of `finally` always end by throwing again, so they are left out.
+        let mut candidates: Vec<BlockId> = Vec::new();
+        for inside in (0..self.graph.blocks().len()).filter(|b| range.contains(&self.start(*b))) {
+            for edge in self.graph.successors(inside) {
+                let outside = !range.contains(&self.start(edge.to));
+                let is_handler = handlers.iter().any(|(_, handler)| *handler == edge.to);
+                let target = self.skip_jumps(edge.to);
+                if outside
+                    && !is_handler
+                    && !matches!(edge.kind, EdgeKind::Exceptional(_))
+                    && !candidates.contains(&target)
+                {
+                    candidates.push(target);
+                }
+            }
+        }
+        // When some of the catch blocks return or throw, the try and catch blocks may not meet
+        // at all: the code following the statement is then where the try block continues
+        let successors = candidates.clone();
+        candidates.extend(
+            handlers
+                .iter()
+                .filter(|(classes, _)| classes.is_some())
+                .map(|(_, handler)| *handler),
+        );
+        let is_valid = |merge: &BlockId| self.in_current_loop(*merge) && !self.emitted[*merge];
+        let continuing: Vec<BlockId> = successors
+            .iter()
+            .copied()
+            .filter(|successor| !self.is_value_return(*successor))
+            .collect();
+        let merge = self
+            .common_post_dominator(&candidates)
+            .filter(is_valid)
+            .or_else(|| self.common_post_dominator(&successors).filter(is_valid))
+            .or_else(|| self.common_post_dominator(&continuing).filter(is_valid));
+        let inner_stops: Vec<BlockId> = stops.iter().copied().chain(merge).collect();
+
+        let mut body = Vec::new();
+        self.sequence_from(block, None, &inner_stops, &mut body)?;
+        let mut catches = Vec::new();
+        let mut finally: Option<Vec<Stmt>> = None;
+        for (classes, handler) in handlers {
+            let mut handler_body = Vec::new();
+            self.sequence_from(handler, None, &inner_stops, &mut handler_body)?;
+            let variable = match handler_body.first() {
+                Some(Stmt::Assign {
+                    target: Expr::Local { name, .. },
+                    value:
+                        Expr::Temporary {
+                            name: exception, ..
+                        },
+                    ..
+                }) if exception == "exception" => {
+                    let name = name.clone();
+                    handler_body.remove(0);
+                    name
+                }
+                _ => "exception".to_string(),
+            };
+            let rethrows = matches!(
+                handler_body.last(),
+                Some(Stmt::Throw(Expr::Local { name, .. } | Expr::Temporary { name, .. }))
+                    if *name == variable
+            );
+            if classes.is_none() && finally.is_none() && rethrows {
+                handler_body.pop();
+                finally = Some(handler_body);
+            } else {
+                catches.push(Catch {
+                    classes: classes.unwrap_or_default(),
+                    variable,
+                    body: handler_body,
+                });
+            }
+        }
+
+        // `javac` copies the code of `finally` at the end of the try and catch blocks, and
+        // before the statements leaving them
+        let copied = finally.clone().unwrap_or_default();
+        if !copied.is_empty() {
+            strip_finally_copies(&mut body, &copied);
+            for catch in catches.iter_mut() {
+                strip_finally_copies(&mut catch.body, &copied);
+            }
+        }
+        match synchronized_block(out, &body, catches.is_empty(), finally.as_deref()) {
+            Some(synchronized) => out.push(synchronized),
+            None => out.push(Stmt::Try {
+                body,
+                catches,
+                finally,
+            }),
+        }
+
+        let Some(merge) = merge else {
+            return Ok(Flow::Stopped(None));
+        };
+        let mut rest = Vec::new();
+        let reached = self.sequence(merge, None, stops, &mut rest)?;
+        if !copied.is_empty() && rest.starts_with(&copied) {
+            rest.drain(..copied.len());
+        }
+        out.extend(rest);
+        Ok(Flow::Stopped(reached))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_access_flags::ClassAccessFlags,
+        class_builder::ClassBuilder,
+        class_file_field::FieldConstantValue,
+        class_file_version::ClassFileVersion,
+        code_builder::{JumpKind, LocalKind},
+        decompiler::{decompile, negate, Expr},
+        field_flags::FieldFlags,
+        field_type::{BaseType, FieldType},
+        instruction::Instruction,
+        method_descriptor::MethodDescriptor,
+        method_flags::MethodFlags,
+    };
+
+    fn local(name: &str) -> Expr {
+        Expr::Local {
+            slot: 0,
+            name: name.to_string(),
+            value_type: Some(FieldType::Base(BaseType::Int)),
+        }
+    }
+
+    #[test]
+    fn renders_expressions_with_minimal_parentheses() {
+        let (a, b, c) = (local("a"), local("b"), local("c"));
+        let sum = Expr::binary("+", a.clone(), b.clone());
+        assert_eq!(
+            "(a + b) * c",
+            Expr::binary("*", sum.clone(), c.clone()).render("")
+        );
+        assert_eq!(
+            "c * a + b",
+            Expr::binary("+", Expr::binary("*", c.clone(), a.clone()), b.clone()).render("")
+        );
+        assert_eq!("c - (a + b)", Expr::binary("-", c.clone(), sum).render(""));
+
+        let condition = Expr::binary(
+            "&&",
+            Expr::binary("<", a.clone(), b.clone()),
+            Expr::binary("==", c, Expr::null()),
+        );
+        assert_eq!("a < b && c == null", condition.render(""));
+        assert_eq!("a >= b || c != null", negate(condition).render(""));
+
+        let negated = Expr::Unary {
+            operator: "-",
+            operand: Box::new(Expr::int(-1)),
+        };
+        assert_eq!("-(-1)", negated.render(""));
+    }
+
+    #[test]
+    fn decompiles_declarations_and_control_flow() {
+        let mut builder = ClassBuilder::new("a/b/Sample");
+        builder
+            .flags(ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL | ClassAccessFlags::SUPER)
+            .superclass("a/b/Base")
+            .interface("java/lang/Runnable")
+            .field_with(
+                FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
+                "ENABLED",
+                FieldType::Base(BaseType::Boolean),
+                |field| {
+                    field.constant_value(FieldConstantValue::Int(1));
+                },
+            )
+            .field(
+                FieldFlags::PRIVATE,
+                "names",
+                FieldType::Array(Box::new(FieldType::Object("java/util/List".to_string()))),
+            )
+            .method(
+                MethodFlags::PUBLIC | MethodFlags::STATIC,
+                "abs",
+                MethodDescriptor::parse("(I)I").unwrap(),
+                |code| {
+                    let positive = code.new_label();
+                    code.load(LocalKind::Int, 0)
+                        .jump(JumpKind::Ifge, positive)
+                        .load(LocalKind::Int, 0)
+                        .instruction(Instruction::Ineg)
+                        .instruction(Instruction::Ireturn)
+                        .place_label(positive)
+                        .load(LocalKind::Int, 0)
+                        .instruction(Instruction::Ireturn);
+                },
+            )
+            .method_with(
+                MethodFlags::PUBLIC | MethodFlags::ABSTRACT,
+                "run",
+                MethodDescriptor::parse("()V").unwrap(),
+                |method| {
+                    method.throws("java/io/IOException");
+                },
+            );
+        let class = builder.build().unwrap();
+
+        assert_eq!(
+            "package a.b;
+
+public final class Sample extends Base implements Runnable {
+    public static final boolean ENABLED = true;
+    private java.util.List[] names;
+
+    public static int abs(int arg0) {
+        if (arg0 < 0) {
+            return -arg0;
+        }
+        return arg0;
+    }
+
+    public abstract void run() throws java.io.IOException;
+}
+",
+            decompile(&class)
+        );
+    }
+
+    #[test]
+    fn lists_the_instructions_of_methods_that_cannot_be_decompiled() {
+        let mut builder = ClassBuilder::new("Sample");
+        builder.version(ClassFileVersion::Jdk1_4).method(
+            MethodFlags::STATIC,
+            "subroutine",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                let subroutine = code.new_label();
+                code.jump(JumpKind::Jsr, subroutine)
+                    .instruction(Instruction::Return)
+                    .place_label(subroutine)
+                    .store(LocalKind::Reference, 0)
+                    .instruction(Instruction::Ret { index: 0 });
+            },
+        );
+        let class = builder.build().unwrap();
+
+        let decompiled = decompile(&class);
+        assert!(decompiled.contains("    static void subroutine() {\n        // Could not"));
+        assert!(decompiled.contains("//     3: Return"));
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fmt, fmt::Formatter};
+
+use crate::class_reader_error::ClassReaderError;
+
+/// How serious a problem found while reading a class leniently is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, strum_macros::Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    /// The element was read, but some of its data was dropped, i.e. unknown flag bits
+    Warning,
+    /// The element could not be read and was replaced by a placeholder
+    Error,
+}
+
+/// A problem found by [crate::read_buffer_lenient]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The stable code of the underlying error, see [ClassReaderError::code]
+    pub code: &'static str,
+    pub message: String,
+    /// Offset in the class file where the problem was found
+    pub offset: Option<usize>,
+    /// Path of the element with the problem, i.e. `methods[3] "foo" > Code`
+    pub path: String,
+}
+
+impl Diagnostic {
+    pub fn from_error(severity: Severity, err: &ClassReaderError) -> Self {
+        Diagnostic {
+            severity,
+            code: err.code(),
+            message: err.kind().to_string(),
+            offset: err.offset(),
+            path: err.path().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset:#x}")?;
+        }
+        if !self.path.is_empty() {
+            write!(f, " ({})", self.path)?;
+        }
+        Ok(())
+    }
+}
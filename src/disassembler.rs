@@ -0,0 +1,2307 @@
+use std::collections::BTreeSet;
+
+use bitflags::Flags;
+use itertools::Itertools;
+
+use crate::{
+    assembler::{hex, word, ConstantKind, SymbolicConstant},
+    attribute::Attribute,
+    buffer::Buffer,
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    constant_pool::ConstantPoolEntry,
+    field_flags::FieldFlags,
+    field_type::{BaseType, FieldType},
+    instruction::{Instruction, WideInstruction},
+    line_number_table::LineNumberTable,
+    method_flags::MethodFlags,
+    stack_map_table::{initial_locals, StackMapTable, VerificationType},
+};
+
+/// Controls what [disassemble] prints. The options have the same meaning as the `javap` ones
+/// they are named after; the default ones print only the declarations of the non-private
+/// members, like `javap` without options.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisasmOptions {
+    /// Print the instructions and the exception table of the methods, like `javap -c`
+    pub code: bool,
+    /// Print the line number and local variable tables, like `javap -l`
+    pub line_numbers: bool,
+    /// Print the version and the flags of the class, the constant pool, the descriptors and
+    /// flags of the members and all the attributes, like `javap -v`
+    pub verbose: bool,
+    /// Print the private members too, like `javap -p`
+    pub private: bool,
+}
+
+impl DisasmOptions {
+    pub fn code(mut self) -> Self {
+        self.code = true;
+        self
+    }
+
+    pub fn line_numbers(mut self) -> Self {
+        self.line_numbers = true;
+        self
+    }
+
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    pub fn private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+}
+
+/// Disassembles a class into the textual format of `javap`, so that the two outputs can be
+/// compared. Instructions are listed as `pc: mnemonic operands`, with the constants they
+/// refer to resolved in a comment.
+///
+/// With all the options enabled the output matches the one of `javap -c -v -p`, except for
+/// the lines describing the file rather than the class, like its path and checksum, which
+/// are not printed. Since the model does not keep the minor version, it is always printed
+/// as 0, and the attributes mapped to fields of the model, like `SourceFile`, are printed in
+/// the order `javac` writes them.
+pub fn disassemble(class: &ClassFile, options: DisasmOptions) -> String {
+    let mut disassembler = Disassembler {
+        class,
+        options,
+        this_class: 0,
+        writer: Writer::default(),
+    };
+    disassembler.this_class = disassembler.class_index(&class.name);
+    disassembler.write_class();
+    disassembler.writer.out
+}
+
+/// Prints only the constant pool of a class, in the same format as the `Constant pool:`
+/// section of `javap -v`, one entry per line
+pub fn disassemble_constant_pool(class: &ClassFile) -> String {
+    let mut disassembler = Disassembler {
+        class,
+        options: DisasmOptions::default().verbose(),
+        this_class: 0,
+        writer: Writer::default(),
+    };
+    disassembler.write_constant_pool();
+    disassembler.writer.out
+}
+
+const INDENT_WIDTH: usize = 2;
+const TAB_COLUMN: usize = 40;
+
+/// Accumulates the output the way `javap` does: spaces at the end of a line are dropped, and
+/// [Writer::tab] aligns the comments to a column that depends on the indentation
+#[derive(Default)]
+struct Writer {
+    out: String,
+    line: String,
+    line_width: usize,
+    pending_spaces: usize,
+    indent: usize,
+}
+
+impl Writer {
+    fn print(&mut self, text: &str) {
+        for c in text.chars() {
+            match c {
+                ' ' => self.pending_spaces += 1,
+                '\n' => self.new_line(),
+                c => {
+                    if self.line.is_empty() {
+                        self.pending_spaces += self.indent * INDENT_WIDTH;
+                    }
+                    self.line_width += self.pending_spaces + 1;
+                    self.line.push_str(&" ".repeat(self.pending_spaces));
+                    self.pending_spaces = 0;
+                    self.line.push(c);
+                }
+            }
+        }
+    }
+
+    fn println(&mut self, text: &str) {
+        self.print(text);
+        self.new_line();
+    }
+
+    fn new_line(&mut self) {
+        self.out.push_str(&self.line);
+        self.out.push('\n');
+        self.line.clear();
+        self.line_width = 0;
+        self.pending_spaces = 0;
+    }
+
+    /// Moves to the column of the comments, or by one space if the line is already past it
+    fn tab(&mut self) {
+        let column = self.indent * INDENT_WIDTH + TAB_COLUMN;
+        self.pending_spaces += column.saturating_sub(self.line_width).max(1);
+    }
+}
+
+/// The modifiers and the flags of classes, fields and methods, in the order javap prints them
+const CLASS_MODIFIERS: [(u16, &str); 3] = [
+    (ClassAccessFlags::PUBLIC.bits(), "public"),
+    (ClassAccessFlags::FINAL.bits(), "final"),
+    (ClassAccessFlags::ABSTRACT.bits(), "abstract"),
+];
+
+const CLASS_FLAGS: [(u16, &str); 8] = [
+    (ClassAccessFlags::PUBLIC.bits(), "ACC_PUBLIC"),
+    (ClassAccessFlags::FINAL.bits(), "ACC_FINAL"),
+    (ClassAccessFlags::SUPER.bits(), "ACC_SUPER"),
+    (ClassAccessFlags::INTERFACE.bits(), "ACC_INTERFACE"),
+    (ClassAccessFlags::ABSTRACT.bits(), "ACC_ABSTRACT"),
+    (ClassAccessFlags::SYNTHETIC.bits(), "ACC_SYNTHETIC"),
+    (ClassAccessFlags::ANNOTATION.bits(), "ACC_ANNOTATION"),
+    (ClassAccessFlags::ENUM.bits(), "ACC_ENUM"),
+];
+
+/// Inner classes can also be private, protected and static
+const INNER_CLASS_MODIFIERS: [(u16, &str); 6] = [
+    (FieldFlags::PUBLIC.bits(), "public"),
+    (FieldFlags::PRIVATE.bits(), "private"),
+    (FieldFlags::PROTECTED.bits(), "protected"),
+    (FieldFlags::STATIC.bits(), "static"),
+    (ClassAccessFlags::ABSTRACT.bits(), "abstract"),
+    (FieldFlags::FINAL.bits(), "final"),
+];
+
+const FIELD_MODIFIERS: [(u16, &str); 7] = [
+    (FieldFlags::PUBLIC.bits(), "public"),
+    (FieldFlags::PRIVATE.bits(), "private"),
+    (FieldFlags::PROTECTED.bits(), "protected"),
+    (FieldFlags::STATIC.bits(), "static"),
+    (FieldFlags::FINAL.bits(), "final"),
+    (FieldFlags::VOLATILE.bits(), "volatile"),
+    (FieldFlags::TRANSIENT.bits(), "transient"),
+];
+
+const FIELD_FLAGS: [(u16, &str); 9] = [
+    (FieldFlags::PUBLIC.bits(), "ACC_PUBLIC"),
+    (FieldFlags::PRIVATE.bits(), "ACC_PRIVATE"),
+    (FieldFlags::PROTECTED.bits(), "ACC_PROTECTED"),
+    (FieldFlags::STATIC.bits(), "ACC_STATIC"),
+    (FieldFlags::FINAL.bits(), "ACC_FINAL"),
+    (FieldFlags::VOLATILE.bits(), "ACC_VOLATILE"),
+    (FieldFlags::TRANSIENT.bits(), "ACC_TRANSIENT"),
+    (FieldFlags::SYNTHETIC.bits(), "ACC_SYNTHETIC"),
+    (FieldFlags::ENUM.bits(), "ACC_ENUM"),
+];
+
+const METHOD_MODIFIERS: [(u16, &str); 9] = [
+    (MethodFlags::PUBLIC.bits(), "public"),
+    (MethodFlags::PRIVATE.bits(), "private"),
+    (MethodFlags::PROTECTED.bits(), "protected"),
+    (MethodFlags::STATIC.bits(), "static"),
+    (MethodFlags::FINAL.bits(), "final"),
+    (MethodFlags::SYNCHRONIZED.bits(), "synchronized"),
+    (MethodFlags::NATIVE.bits(), "native"),
+    (MethodFlags::ABSTRACT.bits(), "abstract"),
+    (MethodFlags::STRICT.bits(), "strictfp"),
+];
+
+const METHOD_FLAGS: [(u16, &str); 12] = [
+    (MethodFlags::PUBLIC.bits(), "ACC_PUBLIC"),
+    (MethodFlags::PRIVATE.bits(), "ACC_PRIVATE"),
+    (MethodFlags::PROTECTED.bits(), "ACC_PROTECTED"),
+    (MethodFlags::STATIC.bits(), "ACC_STATIC"),
+    (MethodFlags::FINAL.bits(), "ACC_FINAL"),
+    (MethodFlags::SYNCHRONIZED.bits(), "ACC_SYNCHRONIZED"),
+    (MethodFlags::BRIDGE.bits(), "ACC_BRIDGE"),
+    (MethodFlags::VARARGS.bits(), "ACC_VARARGS"),
+    (MethodFlags::NATIVE.bits(), "ACC_NATIVE"),
+    (MethodFlags::ABSTRACT.bits(), "ACC_ABSTRACT"),
+    (MethodFlags::STRICT.bits(), "ACC_STRICT"),
+    (MethodFlags::SYNTHETIC.bits(), "ACC_SYNTHETIC"),
+];
+
+/// The flags of the parameters in the `MethodParameters` attribute
+const PARAMETER_FLAGS: [(u16, &str); 3] = [
+    (0x0010, "final"),
+    (0x8000, "mandated"),
+    (0x1000, "synthetic"),
+];
+
+fn modifiers(bits: u16, names: &[(u16, &str)]) -> String {
+    names
+        .iter()
+        .filter(|(flag, _)| bits & flag != 0)
+        .map(|(_, name)| format!("{name} "))
+        .collect()
+}
+
+fn flags_line(bits: u16, names: &[(u16, &str)]) -> String {
+    let names = names
+        .iter()
+        .filter(|(flag, _)| bits & flag != 0)
+        .map(|(_, name)| name)
+        .join(", ");
+    format!("flags: (0x{bits:04x}) {names}")
+}
+
+/// The name of a class in Java source, i.e. `java.lang.String`
+fn java_name(internal_name: &str) -> String {
+    internal_name.replace('/', ".")
+}
+
+fn java_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Base(base_type) => base_type_name(base_type).to_string(),
+        FieldType::Object(class) => java_name(class),
+        FieldType::Array(component) => format!("{}[]", java_type(component)),
+    }
+}
+
+fn base_type_name(base_type: &BaseType) -> &'static str {
+    match base_type {
+        BaseType::Byte => "byte",
+        BaseType::Char => "char",
+        BaseType::Double => "double",
+        BaseType::Float => "float",
+        BaseType::Int => "int",
+        BaseType::Long => "long",
+        BaseType::Short => "short",
+        BaseType::Boolean => "boolean",
+    }
+}
+
+/// Escapes a string constant like javap, which uses the Java escape sequences
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '"' => escaped.push_str("\\\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns a name unchanged if it is made of Java identifiers separated by slashes, like the
+/// names of classes and members, and quoted otherwise, like `"<init>"` or `"[I"`
+fn check_name(name: &str) -> String {
+    let is_start = |c: char| c.is_alphabetic() || c == '$' || c == '_';
+    let mut previous = '/';
+    for c in name.chars() {
+        if (previous == '/' && !is_start(c)) || (c != '/' && !is_start(c) && !c.is_numeric()) {
+            let escaped: String = name
+                .chars()
+                .map(|c| match c {
+                    '\\' => "\\\\".to_string(),
+                    '"' => "\\\"".to_string(),
+                    '\n' => "\\n".to_string(),
+                    '\t' => "\\t".to_string(),
+                    c => c.to_string(),
+                })
+                .collect();
+            return format!("\"{escaped}\"");
+        }
+        previous = c;
+    }
+    if name.is_empty() {
+        "\"\"".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Formats a float like Java's `Float.toString`
+fn java_float(value: f32) -> String {
+    if value.is_nan() || value.is_infinite() {
+        java_special_floating(value.is_nan(), value > 0.0)
+    } else {
+        java_floating(
+            format!("{value:?}"),
+            format!("{value:e}"),
+            f64::from(value.abs()),
+        )
+    }
+}
+
+/// Formats a double like Java's `Double.toString`
+fn java_double(value: f64) -> String {
+    if value.is_nan() || value.is_infinite() {
+        java_special_floating(value.is_nan(), value > 0.0)
+    } else {
+        java_floating(format!("{value:?}"), format!("{value:e}"), value.abs())
+    }
+}
+
+fn java_special_floating(is_nan: bool, is_positive: bool) -> String {
+    match (is_nan, is_positive) {
+        (true, _) => "NaN",
+        (false, true) => "Infinity",
+        (false, false) => "-Infinity",
+    }
+    .to_string()
+}
+
+/// Java uses the decimal notation for magnitudes between 10^-3 and 10^7, and the scientific
+/// one, with at least one fractional digit, for the others
+fn java_floating(decimal: String, scientific: String, magnitude: f64) -> String {
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        return decimal;
+    }
+    match scientific.split_once('e') {
+        Some((mantissa, exponent)) if mantissa.contains('.') => format!("{mantissa}E{exponent}"),
+        Some((mantissa, exponent)) => format!("{mantissa}.0E{exponent}"),
+        None => decimal,
+    }
+}
+
+/// An attribute to print, either one kept as it was read or one rebuilt from the model
+enum AttributeItem<'a> {
+    Raw(&'a Attribute),
+    SourceFile(&'a str),
+    Deprecated,
+    ConstantValue(&'a FieldConstantValue),
+    Code(&'a ClassFileMethod, &'a ClassFileMethodCode),
+    Exceptions(&'a [String]),
+    LineNumberTable(&'a LineNumberTable),
+}
+
+impl AttributeItem<'_> {
+    fn name(&self) -> &str {
+        match self {
+            AttributeItem::Raw(attribute) => &attribute.name,
+            AttributeItem::SourceFile(_) => "SourceFile",
+            AttributeItem::Deprecated => "Deprecated",
+            AttributeItem::ConstantValue(_) => "ConstantValue",
+            AttributeItem::Code(..) => "Code",
+            AttributeItem::Exceptions(_) => "Exceptions",
+            AttributeItem::LineNumberTable(_) => "LineNumberTable",
+        }
+    }
+}
+
+/// Appends the raw attributes to the mapped ones, like the class writer does. Raw attributes
+/// with the same name as a mapped one are left out, since they hold the same data.
+fn with_raw_attributes<'a>(
+    mut mapped: Vec<AttributeItem<'a>>,
+    raw: &'a [Attribute],
+) -> Vec<AttributeItem<'a>> {
+    let mapped_count = mapped.len();
+    for attribute in raw {
+        if !mapped[..mapped_count]
+            .iter()
+            .any(|item| item.name() == attribute.name)
+        {
+            mapped.push(AttributeItem::Raw(attribute));
+        }
+    }
+    mapped
+}
+
+/// Reads the content of an attribute. Every method fails on truncated data.
+struct AttributeReader<'b> {
+    buffer: Buffer<'b>,
+}
+
+impl<'b> AttributeReader<'b> {
+    fn new(bytes: &'b [u8]) -> Self {
+        Self {
+            buffer: Buffer::new(bytes),
+        }
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.buffer.read_u8().ok()
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.buffer.read_u16().ok()
+    }
+
+    /// Reads a count followed by the given number of elements
+    fn list<T>(&mut self, mut element: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let count = self.u16()?;
+        (0..count).map(|_| element(self)).collect()
+    }
+}
+
+/// Annotations are nested at most this deep. Deeper ones come from malformed classes, and
+/// are printed as unknown attributes.
+const MAX_ANNOTATION_DEPTH: usize = 32;
+
+/// An annotation, as stored in the class file
+struct RawAnnotation {
+    type_index: u16,
+    elements: Vec<(u16, RawElementValue)>,
+}
+
+enum RawElementValue {
+    /// A primitive or string constant, with the tag that tells its type
+    Constant(u8, u16),
+    Enum(u16, u16),
+    Class(u16),
+    Annotation(RawAnnotation),
+    Array(Vec<RawElementValue>),
+}
+
+impl RawAnnotation {
+    fn read(reader: &mut AttributeReader, depth: usize) -> Option<Self> {
+        if depth > MAX_ANNOTATION_DEPTH {
+            return None;
+        }
+        let type_index = reader.u16()?;
+        let elements = reader
+            .list(|reader| Some((reader.u16()?, RawElementValue::read(reader, depth + 1)?)))?;
+        Some(Self {
+            type_index,
+            elements,
+        })
+    }
+}
+
+impl RawElementValue {
+    fn read(reader: &mut AttributeReader, depth: usize) -> Option<Self> {
+        if depth > MAX_ANNOTATION_DEPTH {
+            return None;
+        }
+        let tag = reader.u8()?;
+        Some(match tag {
+            b'e' => RawElementValue::Enum(reader.u16()?, reader.u16()?),
+            b'c' => RawElementValue::Class(reader.u16()?),
+            b'@' => RawElementValue::Annotation(RawAnnotation::read(reader, depth + 1)?),
+            b'[' => RawElementValue::Array(
+                reader.list(|reader| RawElementValue::read(reader, depth + 1))?,
+            ),
+            _ => RawElementValue::Constant(tag, reader.u16()?),
+        })
+    }
+}
+
+/// A frame of the `StackMapTable` attribute, as stored in the class file
+struct RawFrame {
+    frame_type: u8,
+    offset_delta: Option<u16>,
+    locals: Option<Vec<RawVerificationType>>,
+    stack: Option<Vec<RawVerificationType>>,
+}
+
+enum RawVerificationType {
+    Simple(&'static str),
+    Object(u16),
+    Uninitialized(u16),
+}
+
+impl RawFrame {
+    fn read(reader: &mut AttributeReader) -> Option<Self> {
+        let frame_type = reader.u8()?;
+        let types = |reader: &mut AttributeReader, count: u16| {
+            (0..count)
+                .map(|_| RawVerificationType::read(reader))
+                .collect::<Option<Vec<_>>>()
+        };
+        let (offset_delta, locals, stack) = match frame_type {
+            0..=63 => (None, None, None),
+            64..=127 => (None, None, Some(types(reader, 1)?)),
+            247 => (Some(reader.u16()?), None, Some(types(reader, 1)?)),
+            248..=251 => (Some(reader.u16()?), None, None),
+            252..=254 => {
+                let offset_delta = reader.u16()?;
+                let locals = types(reader, u16::from(frame_type - 251))?;
+                (Some(offset_delta), Some(locals), None)
+            }
+            255 => {
+                let offset_delta = reader.u16()?;
+                let count = reader.u16()?;
+                let locals = types(reader, count)?;
+                let count = reader.u16()?;
+                (
+                    Some(offset_delta),
+                    Some(locals),
+                    Some(types(reader, count)?),
+                )
+            }
+            _ => return None,
+        };
+        Some(Self {
+            frame_type,
+            offset_delta,
+            locals,
+            stack,
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.frame_type {
+            0..=63 => "same",
+            64..=127 => "same_locals_1_stack_item",
+            247 => "same_locals_1_stack_item_frame_extended",
+            248..=250 => "chop",
+            251 => "same_frame_extended",
+            252..=254 => "append",
+            _ => "full_frame",
+        }
+    }
+}
+
+impl RawVerificationType {
+    fn read(reader: &mut AttributeReader) -> Option<Self> {
+        Some(match reader.u8()? {
+            0 => RawVerificationType::Simple("top"),
+            1 => RawVerificationType::Simple("int"),
+            2 => RawVerificationType::Simple("float"),
+            3 => RawVerificationType::Simple("double"),
+            4 => RawVerificationType::Simple("long"),
+            5 => RawVerificationType::Simple("null"),
+            6 => RawVerificationType::Simple("this"),
+            7 => RawVerificationType::Object(reader.u16()?),
+            8 => RawVerificationType::Uninitialized(reader.u16()?),
+            _ => return None,
+        })
+    }
+}
+
+/// A type in a generic signature
+#[derive(Debug, Clone, PartialEq)]
+enum SignatureType {
+    /// A primitive type, or void
+    Base(&'static str),
+    Class {
+        outer: Option<Box<SignatureType>>,
+        name: String,
+        arguments: Vec<SignatureType>,
+    },
+    Variable(String),
+    Array(Box<SignatureType>),
+    /// `?`, or `? extends T` and `? super T` with the keyword
+    Wildcard(Option<(&'static str, Box<SignatureType>)>),
+}
+
+impl SignatureType {
+    fn is_object(&self) -> bool {
+        matches!(self, SignatureType::Class { outer: None, name, arguments }
+            if name == "java/lang/Object" && arguments.is_empty())
+    }
+}
+
+impl std::fmt::Display for SignatureType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureType::Base(name) => f.write_str(name),
+            SignatureType::Class {
+                outer,
+                name,
+                arguments,
+            } => {
+                if let Some(outer) = outer {
+                    write!(f, "{outer}.")?;
+                }
+                f.write_str(&java_name(name))?;
+                if !arguments.is_empty() {
+                    write!(f, "<{}>", arguments.iter().join(", "))?;
+                }
+                Ok(())
+            }
+            SignatureType::Variable(name) => f.write_str(name),
+            SignatureType::Array(component) => write!(f, "{component}[]"),
+            SignatureType::Wildcard(None) => f.write_str("?"),
+            SignatureType::Wildcard(Some((keyword, bound))) => write!(f, "? {keyword} {bound}"),
+        }
+    }
+}
+
+/// A type parameter of a generic class or method, like `T extends Comparable<T>`
+#[derive(Debug, Clone, PartialEq)]
+struct TypeParameter {
+    name: String,
+    class_bound: Option<SignatureType>,
+    interface_bounds: Vec<SignatureType>,
+}
+
+/// Renders type parameters like javap, which omits the `Object` bounds unless verbose
+fn type_parameters_text(parameters: &[TypeParameter], verbose: bool) -> String {
+    if parameters.is_empty() {
+        return String::new();
+    }
+    let parameters = parameters.iter().map(|parameter| {
+        let mut text = parameter.name.clone();
+        let mut separator = " extends ";
+        let class_bound = parameter
+            .class_bound
+            .iter()
+            .filter(|bound| verbose || !bound.is_object());
+        for bound in class_bound.chain(parameter.interface_bounds.iter()) {
+            text.push_str(separator);
+            text.push_str(&bound.to_string());
+            separator = " & ";
+        }
+        text
+    });
+    format!("<{}>", parameters.joined(", "))
+}
+
+trait JoinedExt {
+    fn joined(self, separator: &str) -> String;
+}
+
+impl<I: Iterator<Item = String>> JoinedExt for I {
+    fn joined(mut self, separator: &str) -> String {
+        self.join(separator)
+    }
+}
+
+/// The signature of a generic class
+struct ClassSignature {
+    type_parameters: Vec<TypeParameter>,
+    superclass: SignatureType,
+    interfaces: Vec<SignatureType>,
+}
+
+/// The signature of a generic method
+struct MethodSignature {
+    type_parameters: Vec<TypeParameter>,
+    parameters: Vec<SignatureType>,
+    return_type: SignatureType,
+    exceptions: Vec<SignatureType>,
+}
+
+/// Parses the generic signatures stored in the `Signature` attributes:
+/// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.9.1
+struct SignatureParser<'s> {
+    text: &'s str,
+    position: usize,
+}
+
+impl<'s> SignatureParser<'s> {
+    fn new(text: &'s str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    fn class_signature(mut self) -> Option<ClassSignature> {
+        let type_parameters = self.type_parameters()?;
+        let superclass = self.java_type()?;
+        let mut interfaces = Vec::new();
+        while !self.at_end() {
+            interfaces.push(self.java_type()?);
+        }
+        Some(ClassSignature {
+            type_parameters,
+            superclass,
+            interfaces,
+        })
+    }
+
+    fn method_signature(mut self) -> Option<MethodSignature> {
+        let type_parameters = self.type_parameters()?;
+        self.expect('(')?;
+        let mut parameters = Vec::new();
+        while self.peek()? != ')' {
+            parameters.push(self.java_type()?);
+        }
+        self.expect(')')?;
+        let return_type = self.java_type()?;
+        let mut exceptions = Vec::new();
+        while !self.at_end() {
+            self.expect('^')?;
+            exceptions.push(self.java_type()?);
+        }
+        Some(MethodSignature {
+            type_parameters,
+            parameters,
+            return_type,
+            exceptions,
+        })
+    }
+
+    fn field_signature(mut self) -> Option<SignatureType> {
+        let field_type = self.java_type()?;
+        self.at_end().then_some(field_type)
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.text.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        (self.next()? == expected).then_some(())
+    }
+
+    /// Reads a name, up to the first of the given characters
+    fn identifier(&mut self, terminators: &[char]) -> Option<String> {
+        let rest = &self.text[self.position..];
+        let length = rest.find(terminators)?;
+        self.position += length;
+        (length > 0).then(|| rest[..length].to_string())
+    }
+
+    fn type_parameters(&mut self) -> Option<Vec<TypeParameter>> {
+        let mut parameters = Vec::new();
+        if self.peek() != Some('<') {
+            return Some(parameters);
+        }
+        self.next();
+        while self.peek()? != '>' {
+            let name = self.identifier(&[':'])?;
+            self.expect(':')?;
+            let class_bound = match self.peek()? {
+                ':' => None,
+                _ => Some(self.java_type()?),
+            };
+            let mut interface_bounds = Vec::new();
+            while self.peek()? == ':' {
+                self.next();
+                interface_bounds.push(self.java_type()?);
+            }
+            parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+        }
+        self.next();
+        Some(parameters)
+    }
+
+    fn java_type(&mut self) -> Option<SignatureType> {
+        Some(match self.next()? {
+            'B' => SignatureType::Base("byte"),
+            'C' => SignatureType::Base("char"),
+            'D' => SignatureType::Base("double"),
+            'F' => SignatureType::Base("float"),
+            'I' => SignatureType::Base("int"),
+            'J' => SignatureType::Base("long"),
+            'S' => SignatureType::Base("short"),
+            'Z' => SignatureType::Base("boolean"),
+            'V' => SignatureType::Base("void"),
+            'T' => {
+                let name = self.identifier(&[';'])?;
+                self.expect(';')?;
+                SignatureType::Variable(name)
+            }
+            '[' => SignatureType::Array(Box::new(self.java_type()?)),
+            'L' => self.class_type()?,
+            _ => return None,
+        })
+    }
+
+    fn class_type(&mut self) -> Option<SignatureType> {
+        let mut outer = None;
+        loop {
+            let name = self.identifier(&['<', '.', ';'])?;
+            let mut arguments = Vec::new();
+            if self.peek()? == '<' {
+                self.next();
+                while self.peek()? != '>' {
+                    arguments.push(self.type_argument()?);
+                }
+                self.next();
+            }
+            let class_type = SignatureType::Class {
+                outer: outer.map(Box::new),
+                name,
+                arguments,
+            };
+            match self.next()? {
+                '.' => outer = Some(class_type),
+                ';' => return Some(class_type),
+                _ => return None,
+            }
+        }
+    }
+
+    fn type_argument(&mut self) -> Option<SignatureType> {
+        let keyword = match self.peek()? {
+            '*' => {
+                self.next();
+                return Some(SignatureType::Wildcard(None));
+            }
+            '+' => "extends",
+            '-' => "super",
+            _ => return self.java_type(),
+        };
+        self.next();
+        let bound = self.java_type()?;
+        Some(SignatureType::Wildcard(Some((keyword, Box::new(bound)))))
+    }
+}
+
+struct Disassembler<'a> {
+    class: &'a ClassFile,
+    options: DisasmOptions,
+    /// The index of the constant of the class itself. References to its members are printed
+    /// without the class name, like javap does.
+    this_class: u16,
+    writer: Writer,
+}
+
+impl<'a> Disassembler<'a> {
+    fn print(&mut self, text: &str) {
+        self.writer.print(text);
+    }
+
+    fn println(&mut self, text: &str) {
+        self.writer.println(text);
+    }
+
+    fn is_interface(&self) -> bool {
+        self.class.flags.contains(ClassAccessFlags::INTERFACE)
+    }
+
+    fn is_shown(&self, flags: u16) -> bool {
+        self.options.private || flags & FieldFlags::PRIVATE.bits() == 0
+    }
+
+    fn utf8(&self, index: u16) -> Option<&'a str> {
+        match self.class.constants.get(index) {
+            Ok(ConstantPoolEntry::Utf8(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn class_name(&self, index: u16) -> Option<&'a str> {
+        match self.class.constants.get(index) {
+            Ok(ConstantPoolEntry::ClassReference(name_index)) => self.utf8(*name_index),
+            _ => None,
+        }
+    }
+
+    /// The index of the constant of the given class, or 0 if there is none
+    fn class_index(&self, name: &str) -> u16 {
+        self.class
+            .constants
+            .iter()
+            .find(|(_, entry)| match entry {
+                ConstantPoolEntry::ClassReference(name_index) => {
+                    self.utf8(*name_index) == Some(name)
+                }
+                _ => false,
+            })
+            .map_or(0, |(index, _)| index as u16)
+    }
+
+    /// The value of a constant, as shown in the comments
+    fn string_value(&self, index: u16) -> String {
+        match self.class.constants.get(index) {
+            Ok(entry) => self.entry_value(entry),
+            Err(_) => format!("#{index}"),
+        }
+    }
+
+    fn entry_value(&self, entry: &ConstantPoolEntry) -> String {
+        let name = |index: u16| self.utf8(index).map_or(format!("#{index}"), check_name);
+        match entry {
+            ConstantPoolEntry::Utf8(text) => escape(text),
+            ConstantPoolEntry::Integer(value) => value.to_string(),
+            ConstantPoolEntry::Float(value) => format!("{}f", java_float(*value)),
+            ConstantPoolEntry::Long(value) => format!("{value}l"),
+            ConstantPoolEntry::Double(value) => format!("{}d", java_double(*value)),
+            ConstantPoolEntry::ClassReference(name_index) => name(*name_index),
+            ConstantPoolEntry::StringReference(text_index) => self
+                .utf8(*text_index)
+                .map_or(format!("#{text_index}"), escape),
+            ConstantPoolEntry::FieldReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::MethodReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::InterfaceMethodReference(class_index, name_and_type_index) => {
+                let class_name = self
+                    .class_name(*class_index)
+                    .map_or(format!("#{class_index}"), check_name);
+                let name_and_type = match self.class.constants.get(*name_and_type_index) {
+                    Ok(entry @ ConstantPoolEntry::NameAndTypeDescriptor(..)) => {
+                        self.entry_value(entry)
+                    }
+                    _ => format!("#{name_and_type_index}"),
+                };
+                format!("{class_name}.{name_and_type}")
+            }
+            ConstantPoolEntry::NameAndTypeDescriptor(name_index, type_index) => {
+                let type_descriptor = self
+                    .utf8(*type_index)
+                    .map_or(format!("#{type_index}"), str::to_string);
+                format!("{}:{type_descriptor}", name(*name_index))
+            }
+        }
+    }
+
+    /// A constant with its kind, like `Method java/lang/Object."<init>":()V`. The members of
+    /// the class itself are shown without the class name.
+    fn constant(&self, index: u16) -> String {
+        let Ok(entry) = self.class.constants.get(index) else {
+            return format!("#{index}");
+        };
+        let kind = match entry {
+            ConstantPoolEntry::Utf8(_) => "Utf8",
+            ConstantPoolEntry::Integer(_) => "int",
+            ConstantPoolEntry::Float(_) => "float",
+            ConstantPoolEntry::Long(_) => "long",
+            ConstantPoolEntry::Double(_) => "double",
+            ConstantPoolEntry::ClassReference(_) => "class",
+            ConstantPoolEntry::StringReference(_) => "String",
+            ConstantPoolEntry::FieldReference(..) => "Field",
+            ConstantPoolEntry::MethodReference(..) => "Method",
+            ConstantPoolEntry::InterfaceMethodReference(..) => "InterfaceMethod",
+            ConstantPoolEntry::NameAndTypeDescriptor(..) => "NameAndType",
+        };
+        let value = match entry {
+            ConstantPoolEntry::FieldReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::MethodReference(class_index, name_and_type_index)
+            | ConstantPoolEntry::InterfaceMethodReference(class_index, name_and_type_index)
+                if *class_index == self.this_class =>
+            {
+                self.string_value(*name_and_type_index)
+            }
+            _ => self.entry_value(entry),
+        };
+        format!("{kind} {value}")
+    }
+
+    /// The `Signature` attribute among the given ones, if any
+    fn signature(&self, attributes: &[Attribute]) -> Option<&'a str> {
+        let attribute = attributes.iter().find(|a| a.name == "Signature")?;
+        let index = AttributeReader::new(&attribute.bytes).u16()?;
+        self.utf8(index)
+    }
+
+    fn write_class(&mut self) {
+        if let Some(source_file) = &self.class.source_file {
+            // In verbose mode, javap prints it under the path of the class file
+            let indent = if self.options.verbose { "  " } else { "" };
+            self.println(&format!("{indent}Compiled from \"{source_file}\""));
+        }
+        let declaration = self.class_declaration();
+        self.print(&declaration);
+        if self.options.verbose {
+            self.println("");
+            self.writer.indent += 1;
+            self.write_version_and_flags();
+            self.writer.indent -= 1;
+            self.write_constant_pool();
+        } else {
+            self.print(" ");
+        }
+        self.println("{");
+
+        self.writer.indent += 1;
+        let class = self.class;
+        for field in class.fields.iter() {
+            if self.is_shown(field.flags.bits()) {
+                self.write_field(field);
+            }
+        }
+        let mut first = true;
+        for method in class.methods.iter() {
+            if self.is_shown(method.flags.bits()) {
+                if !first
+                    && (self.options.code || self.options.line_numbers || self.options.verbose)
+                {
+                    self.println("");
+                }
+                self.write_method(method);
+                first = false;
+            }
+        }
+        self.writer.indent -= 1;
+        self.println("}");
+
+        if self.options.verbose {
+            for attribute in self.class_attributes() {
+                self.write_attribute(attribute);
+            }
+        }
+    }
+
+    fn class_attributes(&self) -> Vec<AttributeItem<'a>> {
+        // javac writes the signature before the attributes that are mapped to the model
+        let (signatures, others): (Vec<_>, Vec<_>) = self
+            .class
+            .attributes
+            .iter()
+            .partition(|attribute| attribute.name == "Signature");
+        let mut attributes: Vec<_> = signatures.into_iter().map(AttributeItem::Raw).collect();
+        if let Some(source_file) = &self.class.source_file {
+            attributes.push(AttributeItem::SourceFile(source_file));
+        }
+        if self.class.deprecated {
+            attributes.push(AttributeItem::Deprecated);
+        }
+        attributes.extend(others.into_iter().map(AttributeItem::Raw));
+        attributes
+    }
+
+    fn class_declaration(&self) -> String {
+        let class = self.class;
+        let is_interface = self.is_interface();
+        let mut flags = class.flags.bits();
+        if is_interface {
+            flags &= !ClassAccessFlags::ABSTRACT.bits();
+        }
+        let mut declaration = modifiers(flags, &CLASS_MODIFIERS);
+        declaration.push_str(if is_interface { "interface " } else { "class " });
+        declaration.push_str(&java_name(&class.name));
+
+        let signature = self
+            .signature(&class.attributes)
+            .and_then(|signature| SignatureParser::new(signature).class_signature());
+        let verbose = self.options.verbose;
+        match signature {
+            Some(signature) => {
+                declaration.push_str(&type_parameters_text(&signature.type_parameters, verbose));
+                let interfaces = signature.interfaces.iter().map(ToString::to_string);
+                let shows_superclass = verbose || !signature.superclass.is_object();
+                if signature.type_parameters.is_empty() && signature.interfaces.is_empty() {
+                    // Like javap, which cannot tell such a signature from a field type
+                    if shows_superclass {
+                        declaration.push_str(&format!(" extends {}", signature.superclass));
+                    }
+                } else if is_interface {
+                    if !signature.interfaces.is_empty() {
+                        declaration.push_str(&format!(" extends {}", interfaces.joined(", ")));
+                    }
+                } else {
+                    if shows_superclass {
+                        declaration.push_str(&format!(" extends {}", signature.superclass));
+                    }
+                    if !signature.interfaces.is_empty() {
+                        declaration.push_str(&format!(" implements {}", interfaces.joined(", ")));
+                    }
+                }
+            }
+            None => {
+                if let Some(superclass) = class.superclass.as_ref().filter(|_| !is_interface) {
+                    if superclass != "java/lang/Object" {
+                        declaration.push_str(&format!(" extends {}", java_name(superclass)));
+                    }
+                }
+                for (index, interface) in class.interfaces.iter().enumerate() {
+                    declaration.push_str(match (index, is_interface) {
+                        (0, true) => " extends ",
+                        (0, false) => " implements ",
+                        _ => ",",
+                    });
+                    declaration.push_str(&java_name(interface));
+                }
+            }
+        }
+        declaration
+    }
+
+    fn write_version_and_flags(&mut self) {
+        let class = self.class;
+        self.println("minor version: 0");
+        self.println(&format!("major version: {}", class.version.major()));
+        self.println(&flags_line(class.flags.bits(), &CLASS_FLAGS));
+        self.write_class_index("this_class", self.this_class);
+        let super_class = class
+            .superclass
+            .as_ref()
+            .map_or(0, |superclass| self.class_index(superclass));
+        self.write_class_index("super_class", super_class);
+        self.println(&format!(
+            "interfaces: {}, fields: {}, methods: {}, attributes: {}",
+            class.interfaces.len(),
+            class.fields.len(),
+            class.methods.len(),
+            self.class_attributes().len()
+        ));
+    }
+
+    fn write_class_index(&mut self, label: &str, index: u16) {
+        self.print(&format!("{label}: #{index}"));
+        if index != 0 {
+            self.writer.tab();
+            self.print(&format!("// {}", self.string_value(index)));
+        }
+        self.println("");
+    }
+
+    fn write_constant_pool(&mut self) {
+        self.println("Constant pool:");
+        self.writer.indent += 1;
+        let width = (self.class.constants.len() + 1).to_string().len() + 1;
+        let class = self.class;
+        for (index, entry) in class.constants.iter() {
+            let (kind, references) = match entry {
+                ConstantPoolEntry::Utf8(_) => ("Utf8", None),
+                ConstantPoolEntry::Integer(_) => ("Integer", None),
+                ConstantPoolEntry::Float(_) => ("Float", None),
+                ConstantPoolEntry::Long(_) => ("Long", None),
+                ConstantPoolEntry::Double(_) => ("Double", None),
+                ConstantPoolEntry::ClassReference(name) => ("Class", Some(format!("#{name}"))),
+                ConstantPoolEntry::StringReference(text) => ("String", Some(format!("#{text}"))),
+                ConstantPoolEntry::FieldReference(class, name_and_type) => {
+                    ("Fieldref", Some(format!("#{class}.#{name_and_type}")))
+                }
+                ConstantPoolEntry::MethodReference(class, name_and_type) => {
+                    ("Methodref", Some(format!("#{class}.#{name_and_type}")))
+                }
+                ConstantPoolEntry::InterfaceMethodReference(class, name_and_type) => (
+                    "InterfaceMethodref",
+                    Some(format!("#{class}.#{name_and_type}")),
+                ),
+                ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor) => {
+                    ("NameAndType", Some(format!("#{name}:#{descriptor}")))
+                }
+            };
+            self.print(&format!("{:>width$} = {kind:<18} ", format!("#{index}")));
+            match references {
+                Some(references) => {
+                    self.print(&references);
+                    self.writer.tab();
+                    self.println(&format!("// {}", self.entry_value(entry)));
+                }
+                None => self.println(&self.entry_value(entry)),
+            }
+        }
+        self.writer.indent -= 1;
+    }
+
+    fn write_field(&mut self, field: &'a ClassFileField) {
+        let field_type = self
+            .signature(&field.attributes)
+            .and_then(|signature| SignatureParser::new(signature).field_signature())
+            .map_or_else(|| java_type(&field.type_descriptor), |t| t.to_string());
+        self.println(&format!(
+            "{}{field_type} {};",
+            modifiers(field.flags.bits(), &FIELD_MODIFIERS),
+            field.name
+        ));
+        self.writer.indent += 1;
+        if self.options.verbose {
+            self.println(&format!(
+                "descriptor: {}",
+                field.type_descriptor.descriptor()
+            ));
+            self.println(&flags_line(field.flags.bits(), &FIELD_FLAGS));
+            let mut mapped = Vec::new();
+            if let Some(constant_value) = &field.constant_value {
+                mapped.push(AttributeItem::ConstantValue(constant_value));
+            }
+            if field.deprecated {
+                mapped.push(AttributeItem::Deprecated);
+            }
+            for attribute in with_raw_attributes(mapped, &field.attributes) {
+                self.write_attribute(attribute);
+            }
+        }
+        self.writer.indent -= 1;
+        if self.options.verbose || self.options.code || self.options.line_numbers {
+            self.println("");
+        }
+    }
+
+    fn write_method(&mut self, method: &'a ClassFileMethod) {
+        self.println(&format!("{};", self.method_declaration(method)));
+        self.writer.indent += 1;
+        if self.options.verbose {
+            self.println(&format!("descriptor: {}", method.type_descriptor));
+            self.println(&flags_line(method.flags.bits(), &METHOD_FLAGS));
+            for attribute in Self::method_attributes(method) {
+                self.write_attribute(attribute);
+            }
+        } else if let Some(code) = &method.code {
+            if self.options.code {
+                self.println("Code:");
+                self.write_instructions(code);
+                self.write_exception_table(code);
+            }
+            if self.options.line_numbers {
+                let attributes = Self::code_attributes(code);
+                for name in ["LineNumberTable", "LocalVariableTable"] {
+                    if let Some(index) = attributes.iter().position(|a| a.name() == name) {
+                        let attribute = Self::code_attributes(code).swap_remove(index);
+                        self.write_attribute(attribute);
+                    }
+                }
+            }
+        }
+        self.writer.indent -= 1;
+    }
+
+    fn method_declaration(&self, method: &ClassFileMethod) -> String {
+        let flags = method.flags.bits();
+        let mut declaration = modifiers(flags, &METHOD_MODIFIERS);
+        let is_default = self.is_interface()
+            && self.class.version.major() >= 52
+            && method.name != "<clinit>"
+            && !method
+                .flags
+                .intersects(MethodFlags::ABSTRACT | MethodFlags::STATIC | MethodFlags::PRIVATE);
+        if is_default {
+            declaration.push_str("default ");
+        }
+
+        let signature = self
+            .signature(&method.attributes)
+            .and_then(|signature| SignatureParser::new(signature).method_signature());
+        let (parameters, return_type, exceptions) = match &signature {
+            Some(signature) => {
+                if !signature.type_parameters.is_empty() {
+                    declaration.push_str(&type_parameters_text(
+                        &signature.type_parameters,
+                        self.options.verbose,
+                    ));
+                    declaration.push(' ');
+                }
+                (
+                    signature
+                        .parameters
+                        .iter()
+                        .map(ToString::to_string)
+                        .joined(", "),
+                    signature.return_type.to_string(),
+                    signature
+                        .exceptions
+                        .iter()
+                        .map(ToString::to_string)
+                        .joined(", "),
+                )
+            }
+            None => {
+                let descriptor = &method.parsed_type_descriptor;
+                (
+                    descriptor.parameters.iter().map(java_type).joined(", "),
+                    descriptor
+                        .return_type
+                        .as_ref()
+                        .map_or("void".to_string(), java_type),
+                    String::new(),
+                )
+            }
+        };
+        let mut parameters = format!("({parameters})");
+        if method.flags.contains(MethodFlags::VARARGS) {
+            if let Some(index) = parameters.rfind("[]").filter(|index| *index > 0) {
+                parameters.replace_range(index..index + 2, "...");
+            }
+        }
+        match method.name.as_str() {
+            "<init>" => {
+                declaration.push_str(&java_name(&self.class.name));
+                declaration.push_str(&parameters);
+            }
+            "<clinit>" => declaration.push_str("{}"),
+            name => declaration.push_str(&format!("{return_type} {name}{parameters}")),
+        }
+
+        let has_exceptions = !method.thrown_exceptions.is_empty()
+            || method.attributes.iter().any(|a| a.name == "Exceptions");
+        if has_exceptions {
+            let exceptions = if exceptions.is_empty() {
+                method
+                    .thrown_exceptions
+                    .iter()
+                    .map(|e| java_name(e))
+                    .joined(", ")
+            } else {
+                exceptions
+            };
+            declaration.push_str(&format!(" throws {exceptions}"));
+        }
+        declaration
+    }
+
+    fn method_attributes(method: &'a ClassFileMethod) -> Vec<AttributeItem<'a>> {
+        let mut mapped = Vec::new();
+        if let Some(code) = &method.code {
+            mapped.push(AttributeItem::Code(method, code));
+        }
+        if !method.thrown_exceptions.is_empty() {
+            mapped.push(AttributeItem::Exceptions(&method.thrown_exceptions));
+        }
+        if method.deprecated {
+            mapped.push(AttributeItem::Deprecated);
+        }
+        with_raw_attributes(mapped, &method.attributes)
+    }
+
+    fn code_attributes(code: &'a ClassFileMethodCode) -> Vec<AttributeItem<'a>> {
+        let mapped = code
+            .line_number_table
+            .iter()
+            .map(AttributeItem::LineNumberTable)
+            .collect();
+        with_raw_attributes(mapped, &code.attributes)
+    }
+
+    fn write_code(&mut self, method: &'a ClassFileMethod, code: &'a ClassFileMethodCode) {
+        self.println("Code:");
+        self.writer.indent += 1;
+        let arguments =
+            method.parsed_type_descriptor.parameters.len() + usize::from(!method.is_static());
+        self.println(&format!(
+            "stack={}, locals={}, args_size={arguments}",
+            code.max_stack, code.max_locals
+        ));
+        self.write_instructions(code);
+        self.write_exception_table(code);
+        for attribute in Self::code_attributes(code) {
+            self.write_attribute(attribute);
+        }
+        self.writer.indent -= 1;
+    }
+
+    fn write_instructions(&mut self, code: &ClassFileMethodCode) {
+        let mut address = 0;
+        while address < code.code.len() {
+            match Instruction::parse(&code.code, address) {
+                Ok((instruction, next_address)) => {
+                    self.write_instruction(address, &instruction);
+                    address = next_address;
+                }
+                Err(_) => {
+                    self.println(&format!("Error: error at or after byte {address}"));
+                    break;
+                }
+            }
+        }
+    }
+
+    fn write_instruction(&mut self, address: usize, instruction: &Instruction) {
+        self.print(&format!("{address:4}: {:<13} ", instruction.mnemonic()));
+        match instruction {
+            Instruction::Anewarray { class: index }
+            | Instruction::Checkcast { class: index }
+            | Instruction::Instanceof { class: index }
+            | Instruction::New { class: index }
+            | Instruction::Getfield { field: index }
+            | Instruction::Getstatic { field: index }
+            | Instruction::Putfield { field: index }
+            | Instruction::Putstatic { field: index }
+            | Instruction::Invokespecial { method: index }
+            | Instruction::Invokestatic { method: index }
+            | Instruction::Invokevirtual { method: index }
+            | Instruction::Ldc_w { index }
+            | Instruction::Ldc2_w { index } => {
+                self.write_constant_operand(&format!("#{index}"), *index)
+            }
+            Instruction::Ldc { index } => {
+                self.write_constant_operand(&format!("#{index}"), u16::from(*index))
+            }
+            Instruction::Invokeinterface { method, count } => {
+                self.write_constant_operand(&format!("#{method},  {count}"), *method)
+            }
+            Instruction::Invokedynamic { call_site } => {
+                self.write_constant_operand(&format!("#{call_site},  0"), *call_site)
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                self.write_constant_operand(&format!("#{class},  {dimensions}"), *class)
+            }
+            Instruction::Aload { index }
+            | Instruction::Astore { index }
+            | Instruction::Dload { index }
+            | Instruction::Dstore { index }
+            | Instruction::Fload { index }
+            | Instruction::Fstore { index }
+            | Instruction::Iload { index }
+            | Instruction::Istore { index }
+            | Instruction::Lload { index }
+            | Instruction::Lstore { index }
+            | Instruction::Ret { index } => self.print(&index.to_string()),
+            Instruction::Iinc { index, constant } => self.print(&format!("{index}, {constant}")),
+            Instruction::Wide { instruction } => match instruction {
+                WideInstruction::Iinc { index, constant } => {
+                    self.print(&format!("{index}, {constant}"))
+                }
+                _ => self.print(&instruction.local_variable_index().to_string()),
+            },
+            Instruction::Bipush { byte } => self.print(&(*byte as i8).to_string()),
+            Instruction::Sipush { short } => self.print(&short.to_string()),
+            Instruction::Newarray { array_type } => {
+                self.print(&format!(" {}", array_type.element_type_name()))
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                high,
+                jump_addresses,
+            } => {
+                let cases = (*low..=*high).zip(jump_addresses.iter().copied());
+                self.write_switch(&format!("{low} to {high}"), cases, *default);
+            }
+            Instruction::Lookupswitch {
+                default,
+                match_pairs,
+            } => {
+                let cases = match_pairs.iter().copied();
+                self.write_switch(&match_pairs.len().to_string(), cases, *default);
+            }
+            _ => {
+                if let [target] = instruction.jump_targets()[..] {
+                    self.print(&target.to_string());
+                }
+            }
+        }
+        self.println("");
+    }
+
+    fn write_constant_operand(&mut self, operand: &str, index: u16) {
+        self.print(operand);
+        self.writer.tab();
+        self.print(&format!("// {}", self.constant(index)));
+    }
+
+    /// Prints the cases of a switch on separate lines, indented past the address
+    fn write_switch(
+        &mut self,
+        description: &str,
+        cases: impl Iterator<Item = (i32, u16)>,
+        default: u16,
+    ) {
+        const CASES_INDENT: usize = 3;
+        self.print(&format!("{{ // {description}"));
+        self.writer.indent += CASES_INDENT;
+        for (key, target) in cases {
+            self.print(&format!("\n{key:12}: {target}"));
+        }
+        self.print(&format!("\n     default: {default}\n}}"));
+        self.writer.indent -= CASES_INDENT;
+    }
+
+    fn write_exception_table(&mut self, code: &ClassFileMethodCode) {
+        let entries = code.exception_table.entries();
+        if entries.is_empty() {
+            return;
+        }
+        self.println("Exception table:");
+        self.writer.indent += 1;
+        self.println(" from    to  target type");
+        for entry in entries {
+            self.print(&format!(
+                " {:5} {:5} {:5}   ",
+                entry.range.start.0, entry.range.end.0, entry.handler_pc.0
+            ));
+            match &entry.catch_class {
+                Some(catch_class) => self.println(&format!("Class {}", check_name(catch_class))),
+                None => self.println("any"),
+            }
+        }
+        self.writer.indent -= 1;
+    }
+
+    fn write_attribute(&mut self, attribute: AttributeItem<'a>) {
+        match attribute {
+            AttributeItem::Raw(attribute) => {
+                if self.write_raw_attribute(attribute).is_none() {
+                    self.write_unknown_attribute(attribute);
+                }
+            }
+            AttributeItem::SourceFile(source_file) => {
+                self.println(&format!("SourceFile: \"{source_file}\""))
+            }
+            AttributeItem::Deprecated => self.println("Deprecated: true"),
+            AttributeItem::ConstantValue(value) => {
+                let value = match value {
+                    FieldConstantValue::Int(value) => format!("int {value}"),
+                    FieldConstantValue::Float(value) => format!("float {}f", java_float(*value)),
+                    FieldConstantValue::Long(value) => format!("long {value}l"),
+                    FieldConstantValue::Double(value) => {
+                        format!("double {}d", java_double(*value))
+                    }
+                    FieldConstantValue::String(value) => format!("String {}", escape(value)),
+                };
+                self.println(&format!("ConstantValue: {value}"));
+            }
+            AttributeItem::Code(method, code) => self.write_code(method, code),
+            AttributeItem::Exceptions(exceptions) => {
+                self.write_exceptions(exceptions.iter().map(|e| java_name(e)))
+            }
+            AttributeItem::LineNumberTable(table) => self.write_line_numbers(
+                table
+                    .entries()
+                    .iter()
+                    .map(|entry| (entry.program_counter.0, entry.line_number.0)),
+            ),
+        }
+    }
+
+    fn write_exceptions(&mut self, exceptions: impl Iterator<Item = String>) {
+        self.println("Exceptions:");
+        self.writer.indent += 1;
+        self.println(&format!("throws {}", exceptions.joined(", ")));
+        self.writer.indent -= 1;
+    }
+
+    fn write_line_numbers(&mut self, entries: impl Iterator<Item = (u16, u16)>) {
+        self.println("LineNumberTable:");
+        self.writer.indent += 1;
+        for (program_counter, line_number) in entries {
+            self.println(&format!("line {line_number}: {program_counter}"));
+        }
+        self.writer.indent -= 1;
+    }
+
+    /// Prints an attribute from its content. Returns `None`, without printing anything, if the
+    /// attribute is not known or its content is malformed.
+    fn write_raw_attribute(&mut self, attribute: &Attribute) -> Option<()> {
+        let mut reader = AttributeReader::new(&attribute.bytes);
+        match attribute.name.as_str() {
+            "ConstantValue" => {
+                let index = reader.u16()?;
+                self.println(&format!("ConstantValue: {}", self.constant(index)));
+            }
+            "Deprecated" | "Synthetic" => self.println(&format!("{}: true", attribute.name)),
+            "SourceFile" => {
+                let source_file = self.utf8(reader.u16()?)?;
+                self.println(&format!("SourceFile: \"{source_file}\""));
+            }
+            "Signature" => {
+                let index = reader.u16()?;
+                let signature = self.utf8(index)?;
+                self.print(&format!("Signature: #{index}"));
+                self.writer.tab();
+                self.println(&format!("// {signature}"));
+            }
+            "Exceptions" => {
+                let exceptions = reader.list(|reader| self.class_name(reader.u16()?))?;
+                self.write_exceptions(exceptions.into_iter().map(java_name));
+            }
+            "LineNumberTable" => {
+                let entries = reader.list(|reader| Some((reader.u16()?, reader.u16()?)))?;
+                self.write_line_numbers(entries.into_iter());
+            }
+            "LocalVariableTable" | "LocalVariableTypeTable" => {
+                let entries = reader.list(|reader| {
+                    Some([
+                        reader.u16()?,
+                        reader.u16()?,
+                        reader.u16()?,
+                        reader.u16()?,
+                        reader.u16()?,
+                    ])
+                })?;
+                self.println(&format!("{}:", attribute.name));
+                self.writer.indent += 1;
+                self.println("Start  Length  Slot  Name   Signature");
+                for [start, length, name, descriptor, slot] in entries {
+                    self.println(&format!(
+                        "{start:5} {length:7} {slot:5} {:>5}   {}",
+                        self.string_value(name),
+                        self.string_value(descriptor)
+                    ));
+                }
+                self.writer.indent -= 1;
+            }
+            "StackMapTable" => {
+                let frames = reader.list(RawFrame::read)?;
+                self.write_stack_map_table(&frames);
+            }
+            "InnerClasses" => {
+                let classes = reader.list(|reader| {
+                    Some([reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?])
+                })?;
+                self.write_inner_classes(&classes);
+            }
+            "EnclosingMethod" => {
+                let class_index = reader.u16()?;
+                let method_index = reader.u16()?;
+                self.print(&format!("EnclosingMethod: #{class_index}.#{method_index}"));
+                self.writer.tab();
+                let class_name = self.class_name(class_index).map_or("", |n| n);
+                self.print(&format!("// {}", java_name(class_name)));
+                if let Ok(ConstantPoolEntry::NameAndTypeDescriptor(name, _)) =
+                    self.class.constants.get(method_index)
+                {
+                    self.print(&format!(".{}", self.utf8(*name).unwrap_or_default()));
+                }
+                self.println("");
+            }
+            "NestHost" => {
+                let index = reader.u16()?;
+                self.println(&format!("NestHost: {}", self.constant(index)));
+            }
+            "NestMembers" => {
+                let members = reader.list(AttributeReader::u16)?;
+                self.println("NestMembers:");
+                self.writer.indent += 1;
+                for member in members {
+                    self.println(&self.string_value(member));
+                }
+                self.writer.indent -= 1;
+            }
+            "MethodParameters" => {
+                let count = reader.u8()?;
+                let parameters = (0..count)
+                    .map(|_| Some((reader.u16()?, reader.u16()?)))
+                    .collect::<Option<Vec<_>>>()?;
+                self.println("MethodParameters:");
+                self.writer.indent += 1;
+                self.println(&format!("{:<30} {}", "Name", "Flags"));
+                for (name, flags) in parameters {
+                    let name = match name {
+                        0 => "<no name>".to_string(),
+                        name => self.string_value(name),
+                    };
+                    self.println(&format!(
+                        "{name:<30} {}",
+                        modifiers(flags, &PARAMETER_FLAGS)
+                    ));
+                }
+                self.writer.indent -= 1;
+            }
+            "RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations" => {
+                let annotations = reader.list(|reader| RawAnnotation::read(reader, 0))?;
+                self.println(&format!("{}:", attribute.name));
+                self.writer.indent += 1;
+                self.write_annotations(&annotations);
+                self.writer.indent -= 1;
+            }
+            "RuntimeVisibleParameterAnnotations" | "RuntimeInvisibleParameterAnnotations" => {
+                let count = reader.u8()?;
+                let parameters = (0..count)
+                    .map(|_| reader.list(|reader| RawAnnotation::read(reader, 0)))
+                    .collect::<Option<Vec<_>>>()?;
+                self.println(&format!("{}:", attribute.name));
+                self.writer.indent += 1;
+                for (index, annotations) in parameters.iter().enumerate() {
+                    self.println(&format!("parameter {index}: "));
+                    self.writer.indent += 1;
+                    self.write_annotations(annotations);
+                    self.writer.indent -= 1;
+                }
+                self.writer.indent -= 1;
+            }
+            "AnnotationDefault" => {
+                let value = RawElementValue::read(&mut reader, 0)?;
+                self.println("AnnotationDefault:");
+                self.writer.indent += 1;
+                self.print("default_value: ");
+                self.print(&self.element_value(&value));
+                self.println("");
+                self.writer.indent += 1;
+                self.write_resolved_element_value(&value);
+                self.writer.indent -= 1;
+                self.println("");
+                self.writer.indent -= 1;
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Prints an attribute that is not known as a hex dump of its content
+    fn write_unknown_attribute(&mut self, attribute: &Attribute) {
+        self.println(&format!(
+            "  {}: length = 0x{:X} (unknown attribute)",
+            attribute.name,
+            attribute.bytes.len()
+        ));
+        self.print("   ");
+        for (index, byte) in attribute.bytes.iter().enumerate() {
+            self.print(&format!("{byte:02X}"));
+            if index % 16 == 15 {
+                self.println("");
+                self.print("   ");
+            } else {
+                self.print(" ");
+            }
+        }
+        self.println("");
+    }
+
+    fn write_stack_map_table(&mut self, frames: &[RawFrame]) {
+        self.println(&format!(
+            "StackMapTable: number_of_entries = {}",
+            frames.len()
+        ));
+        self.writer.indent += 1;
+        for frame in frames {
+            self.println(&format!(
+                "frame_type = {} /* {} */",
+                frame.frame_type,
+                frame.kind()
+            ));
+            self.writer.indent += 1;
+            if let Some(offset_delta) = frame.offset_delta {
+                self.println(&format!("offset_delta = {offset_delta}"));
+            }
+            for (name, types) in [("locals", &frame.locals), ("stack", &frame.stack)] {
+                if let Some(types) = types {
+                    let types = types.iter().map(|t| match t {
+                        RawVerificationType::Simple(name) => format!(" {name}"),
+                        RawVerificationType::Object(index) => format!(" {}", self.constant(*index)),
+                        RawVerificationType::Uninitialized(offset) => {
+                            format!(" uninitialized {offset}")
+                        }
+                    });
+                    let types = types.joined(",");
+                    let padding = if types.is_empty() { "" } else { " " };
+                    self.println(&format!("{name} = [{types}{padding}]"));
+                }
+            }
+            self.writer.indent -= 1;
+        }
+        self.writer.indent -= 1;
+    }
+
+    fn write_inner_classes(&mut self, classes: &[[u16; 4]]) {
+        let mut first = true;
+        for &[inner_class, outer_class, inner_name, flags] in classes {
+            if !self.is_shown(flags) {
+                continue;
+            }
+            if first {
+                self.println("InnerClasses:");
+                self.writer.indent += 1;
+                first = false;
+            }
+            let mut flags = flags;
+            if flags & ClassAccessFlags::INTERFACE.bits() != 0 {
+                flags &= !ClassAccessFlags::ABSTRACT.bits();
+            }
+            self.print(&modifiers(flags, &INNER_CLASS_MODIFIERS));
+            if inner_name != 0 {
+                self.print(&format!("#{inner_name}= "));
+            }
+            self.print(&format!("#{inner_class}"));
+            if outer_class != 0 {
+                self.print(&format!(" of #{outer_class}"));
+            }
+            self.print(";");
+            self.writer.tab();
+            self.print("// ");
+            if inner_name != 0 {
+                self.print(&format!("{}=", self.utf8(inner_name).unwrap_or_default()));
+            }
+            self.print(&self.constant(inner_class));
+            if outer_class != 0 {
+                self.print(&format!(" of {}", self.constant(outer_class)));
+            }
+            self.println("");
+        }
+        if !first {
+            self.writer.indent -= 1;
+        }
+    }
+
+    /// Prints each annotation with the indexes of the constants, and then resolved
+    fn write_annotations(&mut self, annotations: &[RawAnnotation]) {
+        for (index, annotation) in annotations.iter().enumerate() {
+            self.print(&format!("{index}: {}", self.annotation(annotation)));
+            self.println("");
+            self.writer.indent += 1;
+            self.write_resolved_annotation(annotation);
+            self.writer.indent -= 1;
+            self.println("");
+        }
+    }
+
+    /// An annotation with the indexes of the constants, like `#21(#49=s#50)`
+    fn annotation(&self, annotation: &RawAnnotation) -> String {
+        let elements = annotation
+            .elements
+            .iter()
+            .map(|(name, value)| format!("#{name}={}", self.element_value(value)))
+            .joined(",");
+        format!("#{}({elements})", annotation.type_index)
+    }
+
+    fn element_value(&self, value: &RawElementValue) -> String {
+        match value {
+            RawElementValue::Constant(tag, index) => format!("{}#{index}", *tag as char),
+            RawElementValue::Enum(type_name, constant_name) => {
+                format!("e#{type_name}.#{constant_name}")
+            }
+            RawElementValue::Class(index) => format!("c#{index}"),
+            RawElementValue::Annotation(annotation) => format!("@{}", self.annotation(annotation)),
+            RawElementValue::Array(values) => format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|value| self.element_value(value))
+                    .joined(",")
+            ),
+        }
+    }
+
+    /// Prints an annotation with the constants resolved, with one element per line
+    fn write_resolved_annotation(&mut self, annotation: &RawAnnotation) {
+        let type_name = self
+            .utf8(annotation.type_index)
+            .and_then(|descriptor| FieldType::parse(descriptor).ok())
+            .map_or(format!("#{}", annotation.type_index), |t| java_type(&t));
+        self.print(&type_name);
+        if annotation.elements.is_empty() {
+            return;
+        }
+        self.println("(");
+        self.writer.indent += 1;
+        for (name, value) in annotation.elements.iter() {
+            self.print(&format!("{}=", self.string_value(*name)));
+            self.write_resolved_element_value(value);
+            self.println("");
+        }
+        self.writer.indent -= 1;
+        self.print(")");
+    }
+
+    fn write_resolved_element_value(&mut self, value: &RawElementValue) {
+        match value {
+            RawElementValue::Constant(tag, index) => {
+                let constant = self.class.constants.get(*index);
+                let text = match (tag, constant) {
+                    (b'B', _) => format!("(byte) {}", self.string_value(*index)),
+                    (b'S', _) => format!("(short) {}", self.string_value(*index)),
+                    (b'C', Ok(ConstantPoolEntry::Integer(value))) => {
+                        let value = char::from_u32(*value as u32).unwrap_or_default();
+                        format!("'{}'", escape(&value.to_string()))
+                    }
+                    (b'Z', Ok(ConstantPoolEntry::Integer(value))) => (*value != 0).to_string(),
+                    (b'D' | b'F' | b'I' | b'J', _) => self.string_value(*index),
+                    (b's', _) => format!("\"{}\"", self.string_value(*index)),
+                    _ => format!("{}#{index}", *tag as char),
+                };
+                self.print(&text);
+            }
+            RawElementValue::Enum(type_name, constant_name) => self.print(&format!(
+                "{}.{}",
+                self.string_value(*type_name),
+                self.string_value(*constant_name)
+            )),
+            RawElementValue::Class(index) => {
+                self.print(&format!("class {}", self.string_value(*index)))
+            }
+            RawElementValue::Annotation(annotation) => {
+                self.print("@");
+                self.write_resolved_annotation(annotation);
+            }
+            RawElementValue::Array(values) => {
+                self.print("[");
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        self.print(",");
+                    }
+                    self.write_resolved_element_value(value);
+                }
+                self.print("]");
+            }
+        }
+    }
+}
+
+/// Disassembles a class into the syntax of [crate::assembler::assemble], which gives back
+/// the same class. All the constants are listed with `.const`, and the operands of the
+/// instructions are written in the resolved form whenever it maps back to the same constant.
+/// Stack map frames are written with `.frame`, unless the encoding of the attribute would
+/// change; the other attributes that are not part of the model are written as raw bytes.
+pub fn to_assembly(class: &ClassFile) -> String {
+    let mut printer = AssemblyPrinter {
+        class,
+        out: String::new(),
+    };
+    printer.write_class();
+    printer.out
+}
+
+struct AssemblyPrinter<'a> {
+    class: &'a ClassFile,
+    out: String,
+}
+
+/// Flags as the lowercase keywords read by the assembler, each followed by a space
+fn flag_keywords<F: Flags>(flags: &F) -> String {
+    flags
+        .iter_names()
+        .map(|(name, _)| format!("{} ", name.to_ascii_lowercase()))
+        .collect()
+}
+
+impl AssemblyPrinter<'_> {
+    fn line(&mut self, indent: usize, text: &str) {
+        self.out.push_str(&"  ".repeat(indent));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Writes the raw attributes, except the ones generated from the model when writing the
+    /// class, which the reader may keep
+    fn write_attributes(&mut self, indent: usize, attributes: &[Attribute], mapped_names: &[&str]) {
+        for attribute in attributes
+            .iter()
+            .filter(|attribute| !mapped_names.contains(&attribute.name.as_str()))
+        {
+            self.line(
+                indent,
+                &format!(
+                    ".attribute {} {}",
+                    word(&attribute.name),
+                    hex(&attribute.bytes)
+                ),
+            );
+        }
+    }
+
+    fn write_class(&mut self) {
+        let class = self.class;
+        self.line(0, &format!(".version {}", class.version.major()));
+        self.line(
+            0,
+            &format!(
+                ".class {}{}",
+                flag_keywords(&class.flags),
+                word(&class.name)
+            ),
+        );
+        if let Some(superclass) = &class.superclass {
+            self.line(0, &format!(".super {}", word(superclass)));
+        }
+        for interface in class.interfaces.iter() {
+            self.line(0, &format!(".implements {}", word(interface)));
+        }
+        if let Some(source_file) = &class.source_file {
+            self.line(0, &format!(".source {}", word(source_file)));
+        }
+        if class.deprecated {
+            self.line(0, ".deprecated");
+        }
+        self.write_attributes(0, &class.attributes, &["SourceFile", "Deprecated"]);
+
+        if !class.constants.is_empty() {
+            self.line(0, "");
+        }
+        for (index, entry) in class.constants.iter() {
+            let entry = match entry {
+                ConstantPoolEntry::Utf8(text) => format!("Utf8 {}", word(text)),
+                ConstantPoolEntry::Integer(value) => format!("Integer {value}"),
+                ConstantPoolEntry::Float(value) => format!("Float {value:?}"),
+                ConstantPoolEntry::Long(value) => format!("Long {value}"),
+                ConstantPoolEntry::Double(value) => format!("Double {value:?}"),
+                ConstantPoolEntry::ClassReference(name) => format!("Class #{name}"),
+                ConstantPoolEntry::StringReference(text) => format!("String #{text}"),
+                ConstantPoolEntry::FieldReference(class, name_and_type) => {
+                    format!("Fieldref #{class}.#{name_and_type}")
+                }
+                ConstantPoolEntry::MethodReference(class, name_and_type) => {
+                    format!("Methodref #{class}.#{name_and_type}")
+                }
+                ConstantPoolEntry::InterfaceMethodReference(class, name_and_type) => {
+                    format!("InterfaceMethodref #{class}.#{name_and_type}")
+                }
+                ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor) => {
+                    format!("NameAndType #{name}:#{descriptor}")
+                }
+            };
+            self.line(0, &format!(".const #{index} = {entry}"));
+        }
+
+        for field in class.fields.iter() {
+            self.line(0, "");
+            self.write_field(field);
+        }
+        for method in class.methods.iter() {
+            self.line(0, "");
+            self.write_method(method);
+        }
+    }
+
+    fn write_field(&mut self, field: &ClassFileField) {
+        let constant_value = match &field.constant_value {
+            None => String::new(),
+            Some(FieldConstantValue::Int(value)) => format!(" = Integer {value}"),
+            Some(FieldConstantValue::Float(value)) => format!(" = Float {value:?}"),
+            Some(FieldConstantValue::Long(value)) => format!(" = Long {value}"),
+            Some(FieldConstantValue::Double(value)) => format!(" = Double {value:?}"),
+            Some(FieldConstantValue::String(value)) => format!(" = String {}", word(value)),
+        };
+        self.line(
+            0,
+            &format!(
+                ".field {}{} {}{constant_value}",
+                flag_keywords(&field.flags),
+                word(&field.name),
+                word(&field.type_descriptor.descriptor()),
+            ),
+        );
+        if field.deprecated {
+            self.line(1, ".deprecated");
+        }
+        self.write_attributes(1, &field.attributes, &["ConstantValue", "Deprecated"]);
+        self.line(0, ".end field");
+    }
+
+    fn write_method(&mut self, method: &ClassFileMethod) {
+        self.line(
+            0,
+            &format!(
+                ".method {}{} {}",
+                flag_keywords(&method.flags),
+                word(&method.name),
+                word(&method.type_descriptor),
+            ),
+        );
+        for exception in method.thrown_exceptions.iter() {
+            self.line(1, &format!(".throws {}", word(exception)));
+        }
+        if method.deprecated {
+            self.line(1, ".deprecated");
+        }
+        self.write_attributes(1, &method.attributes, &["Code", "Exceptions", "Deprecated"]);
+        if let Some(code) = &method.code {
+            self.write_code(method, code);
+        }
+        self.line(0, ".end method");
+    }
+
+    fn write_code(&mut self, method: &ClassFileMethod, code: &ClassFileMethodCode) {
+        self.line(
+            1,
+            &format!(".code stack {} locals {}", code.max_stack, code.max_locals),
+        );
+        let instructions = Instruction::parse_instructions(&code.code).ok();
+        let boundaries: BTreeSet<u16> = instructions
+            .iter()
+            .flatten()
+            .map(|(address, _)| *address)
+            .chain(std::iter::once(code.code.len()))
+            .filter_map(|address| u16::try_from(address).ok())
+            .collect();
+        let frames = code
+            .attributes
+            .iter()
+            .position(|attribute| attribute.name == "StackMapTable")
+            .and_then(|index| {
+                let frames = self.stack_map_frames(method, &code.attributes[index])?;
+                Some((index, frames))
+            });
+
+        // The positions referred to by jumps, exception handlers and frames get a label
+        let mut targets: BTreeSet<u16> = instructions
+            .iter()
+            .flatten()
+            .flat_map(|(_, instruction)| instruction.jump_targets())
+            .collect();
+        for entry in code.exception_table.entries() {
+            targets.extend([entry.range.start.0, entry.range.end.0, entry.handler_pc.0]);
+        }
+        for frame in frames.iter().flat_map(|(_, table)| table.frames()) {
+            targets.insert(frame.program_counter.0);
+            for verification_type in frame.locals.iter().chain(frame.stack.iter()) {
+                if let VerificationType::Uninitialized(pc) = verification_type {
+                    targets.insert(pc.0);
+                }
+            }
+        }
+        let labels: BTreeSet<u16> = targets.intersection(&boundaries).copied().collect();
+        let position = |pc: u16| match labels.contains(&pc) {
+            true => format!("L{pc}"),
+            false => pc.to_string(),
+        };
+
+        let line_numbers = code
+            .line_number_table
+            .as_ref()
+            .map_or(&[][..], |table| table.entries());
+        match &instructions {
+            Some(instructions) => {
+                for (address, instruction) in instructions {
+                    let address = *address as u16;
+                    if labels.contains(&address) {
+                        self.line(1, &format!("L{address}:"));
+                    }
+                    for entry in line_numbers
+                        .iter()
+                        .filter(|entry| entry.program_counter.0 == address)
+                    {
+                        self.line(2, &format!(".line {}", entry.line_number));
+                    }
+                    let operands = self.operands(instruction, &position);
+                    let text = format!("{} {operands}", instruction.mnemonic());
+                    self.line(2, text.trim_end());
+                }
+                if labels.contains(&(code.code.len() as u16)) {
+                    self.line(1, &format!("L{}:", code.code.len()));
+                }
+            }
+            None => self.line(2, &format!(".bytes {}", hex(&code.code))),
+        }
+        for entry in line_numbers {
+            let is_instruction = instructions.is_some()
+                && entry.program_counter.0 as usize != code.code.len()
+                && boundaries.contains(&entry.program_counter.0);
+            if !is_instruction {
+                self.line(
+                    2,
+                    &format!(
+                        ".line {} {}",
+                        entry.line_number,
+                        position(entry.program_counter.0)
+                    ),
+                );
+            }
+        }
+
+        for entry in code.exception_table.entries() {
+            let catch_class = entry.catch_class.as_deref().map_or("any".to_string(), word);
+            self.line(
+                2,
+                &format!(
+                    ".catch {catch_class} from {} to {} using {}",
+                    position(entry.range.start.0),
+                    position(entry.range.end.0),
+                    position(entry.handler_pc.0)
+                ),
+            );
+        }
+        for (index, attribute) in code.attributes.iter().enumerate() {
+            match &frames {
+                Some((frames_index, table)) if *frames_index == index => {
+                    for frame in table.frames() {
+                        let types = |types: &[VerificationType]| {
+                            types
+                                .iter()
+                                .map(|verification_type| match verification_type {
+                                    VerificationType::Object(name) => {
+                                        format!(" class {}", word(name))
+                                    }
+                                    VerificationType::Uninitialized(pc) => {
+                                        format!(" uninitialized {}", position(pc.0))
+                                    }
+                                    other => format!(" {other}"),
+                                })
+                                .collect::<String>()
+                        };
+                        let mut text = format!(".frame {}", position(frame.program_counter.0));
+                        if !frame.locals.is_empty() {
+                            text.push_str(&format!(" locals{}", types(&frame.locals)));
+                        }
+                        if !frame.stack.is_empty() {
+                            text.push_str(&format!(" stack{}", types(&frame.stack)));
+                        }
+                        self.line(2, &text);
+                    }
+                }
+                _ => {
+                    self.write_attributes(2, std::slice::from_ref(attribute), &["LineNumberTable"])
+                }
+            }
+        }
+        self.line(1, ".end code");
+    }
+
+    /// Decodes a `StackMapTable` attribute, if encoding the frames gives back the same bytes
+    fn stack_map_frames(
+        &self,
+        method: &ClassFileMethod,
+        attribute: &Attribute,
+    ) -> Option<StackMapTable> {
+        let initial_locals = initial_locals(&self.class.name, method);
+        let table =
+            StackMapTable::decode(&attribute.bytes, &initial_locals, &self.class.constants).ok()?;
+        let mut constants = self.class.constants.clone();
+        let encoded = table.encode(&initial_locals, &mut constants).ok()?;
+        (encoded == attribute.bytes && constants.len() == self.class.constants.len())
+            .then_some(table)
+    }
+
+    /// A constant in the resolved form, omitting the kind if it is the default one, or by
+    /// index if the resolved form would not give back the same constant
+    fn constant(&self, index: u16, default_kind: ConstantKind) -> String {
+        let constants = &self.class.constants;
+        let resolved = SymbolicConstant::resolve(constants, index).filter(|constant| {
+            let mut copy = constants.clone();
+            constant.intern(&mut copy) == Ok(index) && copy.len() == constants.len()
+        });
+        match resolved {
+            Some(constant) if constant.kind() == default_kind => constant.operands(),
+            Some(constant) => format!("{} {}", constant.kind().name(), constant.operands()),
+            None => format!("#{index}"),
+        }
+    }
+
+    fn operands(&self, instruction: &Instruction, position: &dyn Fn(u16) -> String) -> String {
+        match instruction {
+            Instruction::Aload { index }
+            | Instruction::Astore { index }
+            | Instruction::Dload { index }
+            | Instruction::Dstore { index }
+            | Instruction::Fload { index }
+            | Instruction::Fstore { index }
+            | Instruction::Iload { index }
+            | Instruction::Istore { index }
+            | Instruction::Lload { index }
+            | Instruction::Lstore { index }
+            | Instruction::Ret { index } => index.to_string(),
+            Instruction::Iinc { index, constant } => format!("{index} {constant}"),
+            Instruction::Bipush { byte } => (*byte as i8).to_string(),
+            Instruction::Sipush { short } => short.to_string(),
+            Instruction::Ldc { index } => self.constant(*index as u16, ConstantKind::String),
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                self.constant(*index, ConstantKind::String)
+            }
+            Instruction::Anewarray { class }
+            | Instruction::Checkcast { class }
+            | Instruction::Instanceof { class }
+            | Instruction::New { class } => self.constant(*class, ConstantKind::Class),
+            Instruction::Multianewarray { class, dimensions } => {
+                format!(
+                    "{} {dimensions}",
+                    self.constant(*class, ConstantKind::Class)
+                )
+            }
+            Instruction::Getfield { field }
+            | Instruction::Getstatic { field }
+            | Instruction::Putfield { field }
+            | Instruction::Putstatic { field } => self.constant(*field, ConstantKind::Fieldref),
+            Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokevirtual { method } => {
+                self.constant(*method, ConstantKind::Methodref)
+            }
+            Instruction::Invokeinterface { method, count } => format!(
+                "{} {count}",
+                self.constant(*method, ConstantKind::InterfaceMethodref)
+            ),
+            Instruction::Invokedynamic { call_site } => format!("#{call_site}"),
+            Instruction::Newarray { array_type } => array_type.element_type_name().to_string(),
+            Instruction::Tableswitch {
+                default,
+                low,
+                jump_addresses,
+                ..
+            } => format!(
+                "{low} {} default {}",
+                jump_addresses
+                    .iter()
+                    .map(|target| position(*target))
+                    .join(" "),
+                position(*default)
+            ),
+            Instruction::Lookupswitch {
+                default,
+                match_pairs,
+            } => format!(
+                "{} default {}",
+                match_pairs
+                    .iter()
+                    .map(|(key, target)| format!("{key} {}", position(*target)))
+                    .join(" "),
+                position(*default)
+            ),
+            Instruction::Wide { instruction } => match instruction {
+                WideInstruction::Iinc { index, constant } => format!("{index} {constant}"),
+                _ => instruction.local_variable_index().to_string(),
+            },
+            _ => instruction
+                .jump_targets()
+                .into_iter()
+                .map(position)
+                .join(" "),
+        }
+    }
+}
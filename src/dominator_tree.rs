@@ -0,0 +1,245 @@
+use crate::control_flow_graph::{BlockId, ControlFlowGraph};
+
+/// The dominator tree, or post-dominator tree, of the blocks of a [ControlFlowGraph].
+///
+/// A block `a` dominates a block `b` if every path from the entry to `b` goes through `a`;
+/// it post-dominates `b` if every path from `b` to the exit of the method goes through `a`.
+/// Exceptional edges are considered like every other edge. Blocks that cannot be reached from
+/// the entry (or that cannot reach an exit, for post-dominators) are not part of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DominatorTree {
+    immediate_dominators: Vec<Option<BlockId>>,
+    in_tree: Vec<bool>,
+}
+
+impl DominatorTree {
+    /// Computes the dominators of the blocks of the graph
+    pub fn dominators(graph: &ControlFlowGraph) -> Self {
+        let (successors, predecessors) = graph.adjacency();
+        if successors.is_empty() {
+            return Self {
+                immediate_dominators: Vec::new(),
+                in_tree: Vec::new(),
+            };
+        }
+        let idoms = immediate_dominators(&successors, &predecessors, 0);
+        Self {
+            in_tree: idoms.iter().map(Option::is_some).collect(),
+            immediate_dominators: idoms
+                .into_iter()
+                .enumerate()
+                .map(|(block, idom)| idom.filter(|idom| *idom != block))
+                .collect(),
+        }
+    }
+
+    /// Computes the post-dominators of the blocks of the graph. The exits are the blocks
+    /// without successors, i.e. the ones that return or throw an uncaught exception; they are
+    /// linked to a virtual exit node, which is the root of the tree. Thus, the blocks directly
+    /// post-dominated only by the exit have no immediate post-dominator.
+    pub fn post_dominators(graph: &ControlFlowGraph) -> Self {
+        Self::post_dominators_of(graph.adjacency().0)
+    }
+
+    /// Computes the post-dominators of a graph given by the successors of each block, so that
+    /// some of the edges of a [ControlFlowGraph] can be left out
+    pub(crate) fn post_dominators_of(mut successors: Vec<Vec<BlockId>>) -> Self {
+        let mut predecessors = vec![Vec::new(); successors.len()];
+        for (block, targets) in successors.iter().enumerate() {
+            for target in targets {
+                predecessors[*target].push(block);
+            }
+        }
+        let exit = successors.len();
+        let exits: Vec<BlockId> = (0..exit)
+            .filter(|block| successors[*block].is_empty())
+            .collect();
+        for block in exits.iter() {
+            successors[*block].push(exit);
+        }
+        successors.push(Vec::new());
+        predecessors.push(exits);
+
+        // Post-dominators are the dominators of the reversed graph
+        let idoms = immediate_dominators(&predecessors, &successors, exit);
+        Self {
+            in_tree: idoms[..exit].iter().map(Option::is_some).collect(),
+            immediate_dominators: idoms[..exit]
+                .iter()
+                .map(|idom| idom.filter(|idom| *idom != exit))
+                .collect(),
+        }
+    }
+
+    /// Returns the closest strict dominator of the given block, or `None` for the root of the
+    /// tree and for blocks not in the tree
+    pub fn immediate_dominator(&self, block: BlockId) -> Option<BlockId> {
+        self.immediate_dominators[block]
+    }
+
+    /// Whether the given block is part of the tree
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.in_tree[block]
+    }
+
+    /// Whether `dominator` dominates `block`. Every block in the tree dominates itself.
+    pub fn dominates(&self, dominator: BlockId, block: BlockId) -> bool {
+        if !self.in_tree[dominator] || !self.in_tree[block] {
+            return false;
+        }
+        let mut current = Some(block);
+        while let Some(candidate) = current {
+            if candidate == dominator {
+                return true;
+            }
+            current = self.immediate_dominators[candidate];
+        }
+        false
+    }
+
+    /// Returns the blocks immediately dominated by the given one
+    pub fn children(&self, block: BlockId) -> Vec<BlockId> {
+        (0..self.immediate_dominators.len())
+            .filter(|child| self.immediate_dominators[*child] == Some(block))
+            .collect()
+    }
+}
+
+/// Returns the nodes in reverse postorder of a depth-first visit from the root. Nodes that
+/// cannot be reached are omitted.
+pub(crate) fn reverse_postorder(successors: &[Vec<usize>], root: usize) -> Vec<usize> {
+    let mut visited = vec![false; successors.len()];
+    let mut postorder = Vec::new();
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+    while let Some((node, next_successor)) = stack.last().copied() {
+        match successors[node].get(next_successor) {
+            Some(successor) => {
+                stack.last_mut().expect("the stack is not empty").1 += 1;
+                if !visited[*successor] {
+                    visited[*successor] = true;
+                    stack.push((*successor, 0));
+                }
+            }
+            None => {
+                postorder.push(node);
+                stack.pop();
+            }
+        }
+    }
+    postorder.reverse();
+    postorder
+}
+
+/// Computes the immediate dominators with the algorithm by Cooper, Harvey and Kennedy,
+/// "A Simple, Fast Dominance Algorithm". The root is its own immediate dominator, while the
+/// nodes that cannot be reached have none.
+fn immediate_dominators(
+    successors: &[Vec<usize>],
+    predecessors: &[Vec<usize>],
+    root: usize,
+) -> Vec<Option<usize>> {
+    let order = reverse_postorder(successors, root);
+    let mut order_index = vec![usize::MAX; successors.len()];
+    for (index, node) in order.iter().enumerate() {
+        order_index[*node] = index;
+    }
+
+    let mut idoms = vec![None; successors.len()];
+    idoms[root] = Some(root);
+    let intersect = |idoms: &[Option<usize>], mut first: usize, mut second: usize| {
+        while first != second {
+            while order_index[first] > order_index[second] {
+                first = idoms[first].expect("processed nodes have a dominator");
+            }
+            while order_index[second] > order_index[first] {
+                second = idoms[second].expect("processed nodes have a dominator");
+            }
+        }
+        first
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in order.iter().skip(1) {
+            let mut new_idom = None;
+            for predecessor in predecessors[*node].iter() {
+                if idoms[*predecessor].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => *predecessor,
+                    Some(idom) => intersect(&idoms, *predecessor, idom),
+                });
+            }
+            if new_idom != idoms[*node] {
+                idoms[*node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idoms
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        code_builder::{test_method, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        control_flow_graph::ControlFlowGraph,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+    };
+
+    /// Builds `if (x) { a } else { b }; return`, whose blocks are: the condition, the else
+    /// branch, the then branch, and the return
+    fn diamond() -> ControlFlowGraph {
+        let mut constants = ConstantPool::new();
+        let method = test_method(
+            &mut constants,
+            MethodFlags::STATIC,
+            "test",
+            "(I)V",
+            |code| {
+                let then_label = code.new_label();
+                let end_label = code.new_label();
+                code.load(LocalKind::Int, 0)
+                    .jump(JumpKind::Ifne, then_label)
+                    .instruction(Instruction::Nop)
+                    .jump(JumpKind::Goto, end_label)
+                    .place_label(then_label)
+                    .instruction(Instruction::Nop)
+                    .place_label(end_label)
+                    .instruction(Instruction::Return);
+            },
+        );
+        method.code.unwrap().control_flow_graph().unwrap()
+    }
+
+    #[test]
+    fn computes_dominators() {
+        let dominators = diamond().dominator_tree();
+
+        assert_eq!(None, dominators.immediate_dominator(0));
+        for block in 1..=3 {
+            assert_eq!(Some(0), dominators.immediate_dominator(block));
+            assert!(dominators.dominates(0, block));
+        }
+        assert!(!dominators.dominates(1, 3));
+        assert!(dominators.dominates(3, 3));
+        assert_eq!(vec![1, 2, 3], dominators.children(0));
+    }
+
+    #[test]
+    fn computes_post_dominators() {
+        let post_dominators = diamond().post_dominator_tree();
+
+        assert_eq!(None, post_dominators.immediate_dominator(3));
+        for block in 0..=2 {
+            assert_eq!(Some(3), post_dominators.immediate_dominator(block));
+            assert!(post_dominators.dominates(3, block));
+        }
+        assert!(!post_dominators.dominates(1, 0));
+    }
+}
@@ -3,10 +3,11 @@ use std::ops::Range;
 use crate::program_counter::ProgramCounter;
 
 /// Exception table of a method's code
-#[derive(Debug, Default, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct ExceptionTable {
-    #[cfg_attr(feature = "wasm", serde(rename = "exception_table"))]
+    #[cfg_attr(feature = "serde", serde(rename = "exception_table"))]
     entries: Vec<ExceptionTableEntry>,
 }
 
@@ -15,6 +16,10 @@ impl ExceptionTable {
         Self { entries }
     }
 
+    pub fn entries(&self) -> &[ExceptionTableEntry] {
+        &self.entries
+    }
+
     pub fn lookup(&self, pc: ProgramCounter) -> Vec<&ExceptionTableEntry> {
         self.entries
             .iter()
@@ -25,10 +30,15 @@ impl ExceptionTable {
 
 /// Entries of the exception table
 #[derive(Debug, PartialEq, Eq, Clone)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "FlatExceptionTableEntry", into = "FlatExceptionTableEntry")
+)]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct ExceptionTableEntry {
     /// The range of program counters that this entry covers
-    #[cfg_attr(feature = "wasm", serde(flatten))]
+    #[cfg_attr(feature = "serde", serde(flatten))]
     pub range: Range<ProgramCounter>,
     /// The address of the handler of this entry
     pub handler_pc: ProgramCounter,
@@ -36,6 +46,40 @@ pub struct ExceptionTableEntry {
     pub catch_class: Option<String>,
 }
 
+/// Serialized form of [ExceptionTableEntry], with the bounds of the range inlined like
+/// `#[serde(flatten)]` would do, but also supported by formats that are not self-describing
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FlatExceptionTableEntry {
+    start: ProgramCounter,
+    end: ProgramCounter,
+    handler_pc: ProgramCounter,
+    catch_class: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<ExceptionTableEntry> for FlatExceptionTableEntry {
+    fn from(entry: ExceptionTableEntry) -> Self {
+        Self {
+            start: entry.range.start,
+            end: entry.range.end,
+            handler_pc: entry.handler_pc,
+            catch_class: entry.catch_class,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<FlatExceptionTableEntry> for ExceptionTableEntry {
+    fn from(entry: FlatExceptionTableEntry) -> Self {
+        Self {
+            range: entry.start..entry.end,
+            handler_pc: entry.handler_pc,
+            catch_class: entry.catch_class,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
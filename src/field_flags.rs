@@ -1,7 +1,6 @@
 use bitflags::bitflags;
 
-#[derive(Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Possible flags of a class field
 pub struct FieldFlags(u16);
 
@@ -24,3 +23,17 @@ impl Default for FieldFlags {
         FieldFlags::empty()
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FieldFlags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::flags_serde::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FieldFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::flags_serde::deserialize(deserializer)
+    }
+}
@@ -5,12 +5,16 @@ use itertools::Itertools;
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use crate::class_reader_error::ClassReaderError;
+use crate::{
+    class_reader_error::ClassReaderError,
+    read_limits::{ReadLimit, ReadLimits},
+};
 use ClassReaderError::InvalidTypeDescriptor;
 
 /// Models the type of one field, or one parameter of a method
 #[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(tsify::Tsify, serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub enum FieldType {
     /// Primitive types
     Base(BaseType),
@@ -35,7 +39,8 @@ impl fmt::Display for FieldType {
 /// Possible primitive types
 #[derive(Debug, Clone, PartialEq, strum_macros::Display)]
 #[repr(u8)]
-#[cfg_attr(feature = "wasm", derive(tsify::Tsify, serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub enum BaseType {
     Byte,
     Char,
@@ -47,12 +52,54 @@ pub enum BaseType {
     Boolean,
 }
 
+impl BaseType {
+    /// Returns the character used for this type in type descriptors
+    pub fn descriptor(&self) -> char {
+        match self {
+            BaseType::Byte => 'B',
+            BaseType::Char => 'C',
+            BaseType::Double => 'D',
+            BaseType::Float => 'F',
+            BaseType::Int => 'I',
+            BaseType::Long => 'J',
+            BaseType::Short => 'S',
+            BaseType::Boolean => 'Z',
+        }
+    }
+}
+
 impl FieldType {
+    /// Returns the type descriptor in the internal JVM form, i.e. `I` or `[Ljava/lang/String;`
+    pub fn descriptor(&self) -> String {
+        match self {
+            FieldType::Base(base) => base.descriptor().to_string(),
+            FieldType::Object(class) => format!("L{class};"),
+            FieldType::Array(component_type) => format!("[{}", component_type.descriptor()),
+        }
+    }
+
+    /// Number of slots taken by a value of this type in the local variables or in the operand
+    /// stack. Long and double take two slots, everything else one.
+    pub fn slots(&self) -> u16 {
+        match self {
+            FieldType::Base(BaseType::Long) | FieldType::Base(BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+
     /// Parses a type descriptor as specified in the JVM specs:
     /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.2
     pub fn parse(type_descriptor: &str) -> Result<FieldType, ClassReaderError> {
+        Self::parse_with_limits(type_descriptor, &ReadLimits::default())
+    }
+
+    /// Parses a type descriptor, failing if it has more array dimensions than the limits allow
+    pub fn parse_with_limits(
+        type_descriptor: &str,
+        limits: &ReadLimits,
+    ) -> Result<FieldType, ClassReaderError> {
         let mut chars = type_descriptor.chars();
-        let descriptor = Self::parse_from(type_descriptor, &mut chars)?;
+        let descriptor = Self::parse_from(type_descriptor, &mut chars, limits)?;
         match chars.next() {
             None => Ok(descriptor),
             Some(_) => Err(InvalidTypeDescriptor(type_descriptor.to_string())),
@@ -62,12 +109,18 @@ impl FieldType {
     pub(crate) fn parse_from(
         type_descriptor: &str,
         chars: &mut Chars,
+        limits: &ReadLimits,
     ) -> Result<FieldType, ClassReaderError> {
+        // Arrays are counted rather than parsed recursively, so that a long run of `[` cannot
+        // overflow the stack
+        let dimensions = chars.take_while_ref(|c| *c == '[').count();
+        limits.check(ReadLimit::DescriptorDepth, dimensions)?;
+
         let first_char = chars
             .next()
             .ok_or(InvalidTypeDescriptor(type_descriptor.to_string()))?;
 
-        Ok(match first_char {
+        let element_type = match first_char {
             'B' => FieldType::Base(BaseType::Byte),
             'C' => FieldType::Base(BaseType::Char),
             'D' => FieldType::Base(BaseType::Double),
@@ -83,12 +136,11 @@ impl FieldType {
                     _ => return Err(InvalidTypeDescriptor(type_descriptor.to_string())),
                 }
             }
-            '[' => {
-                let component_type = Self::parse_from(type_descriptor, chars)?;
-                FieldType::Array(Box::new(component_type))
-            }
             _ => return Err(InvalidTypeDescriptor(type_descriptor.to_string())),
-        })
+        };
+        Ok((0..dimensions).fold(element_type, |component_type, _| {
+            FieldType::Array(Box::new(component_type))
+        }))
     }
 }
 
@@ -97,6 +149,7 @@ mod tests {
     use crate::{
         class_reader_error::ClassReaderError,
         field_type::{BaseType, FieldType},
+        read_limits::{ReadLimit, ReadLimits},
     };
 
     #[test]
@@ -175,6 +228,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_parse_up_to_max_array_dimensions() {
+        let descriptor = format!("{}I", "[".repeat(255));
+        assert_eq!(
+            descriptor,
+            FieldType::parse(&descriptor).unwrap().descriptor()
+        );
+    }
+
+    #[test]
+    fn cannot_parse_too_many_array_dimensions() {
+        assert_eq!(
+            Err(ClassReaderError::LimitExceeded(
+                ReadLimit::DescriptorDepth,
+                255
+            )),
+            FieldType::parse(&format!("{}I", "[".repeat(256)))
+        );
+        assert_eq!(
+            Err(ClassReaderError::LimitExceeded(
+                ReadLimit::DescriptorDepth,
+                1
+            )),
+            FieldType::parse_with_limits("[[I", &ReadLimits::default().max_descriptor_depth(1))
+        );
+    }
+
+    #[test]
+    fn can_convert_back_to_descriptor() {
+        for descriptor in ["B", "C", "D", "F", "I", "J", "S", "Z", "Lrjvm/Test;", "[[I"] {
+            assert_eq!(
+                descriptor,
+                FieldType::parse(descriptor).unwrap().descriptor()
+            );
+        }
+    }
+
     #[test]
     fn can_format_base_type() {
         assert_eq!("Long", format!("{}", FieldType::parse("J").unwrap()));
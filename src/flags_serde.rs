@@ -0,0 +1,65 @@
+use bitflags::Flags;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes flags as the list of their names in lowercase, i.e. `["public", "static"]`.
+/// Bits that do not correspond to a known flag are kept as a single hexadecimal entry,
+/// i.e. `"0x8000"`, so that they survive a round trip.
+pub(crate) fn serialize<F, S>(flags: &F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    F: Flags<Bits = u16>,
+    S: Serializer,
+{
+    let mut iter = flags.iter_names();
+    let mut names: Vec<String> = iter.by_ref().map(|(name, _)| name.to_lowercase()).collect();
+    let unknown = iter.remaining().bits();
+    if unknown != 0 {
+        names.push(format!("{unknown:#06x}"));
+    }
+    names.serialize(serializer)
+}
+
+/// Deserializes flags from the format written by [serialize]
+pub(crate) fn deserialize<'de, F, D>(deserializer: D) -> Result<F, D::Error>
+where
+    F: Flags<Bits = u16>,
+    D: Deserializer<'de>,
+{
+    let mut flags = F::empty();
+    for name in Vec::<String>::deserialize(deserializer)? {
+        let flag = match name.strip_prefix("0x") {
+            Some(hex) => u16::from_str_radix(hex, 16).ok().map(F::from_bits_retain),
+            None => F::from_name(&name.to_uppercase()),
+        };
+        flags.insert(flag.ok_or_else(|| D::Error::custom(format!("unknown flag `{name}`")))?);
+    }
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{class_access_flags::ClassAccessFlags, method_flags::MethodFlags};
+
+    #[test]
+    fn serializes_flags_as_names() {
+        let flags = ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER;
+
+        let json = serde_json::to_string(&flags).unwrap();
+        assert_eq!(r#"["public","super"]"#, json);
+        assert_eq!(flags, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn keeps_unknown_bits() {
+        let flags = MethodFlags::STATIC | MethodFlags::from_bits_retain(0x8000);
+
+        let json = serde_json::to_string(&flags).unwrap();
+        assert_eq!(r#"["static","0x8000"]"#, json);
+        assert_eq!(flags, serde_json::from_str::<MethodFlags>(&json).unwrap());
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        let error = serde_json::from_str::<MethodFlags>(r#"["public","sealed"]"#).unwrap_err();
+        assert!(error.to_string().starts_with("unknown flag `sealed`"));
+    }
+}
@@ -0,0 +1,539 @@
+use std::{collections::HashSet, fmt, fmt::Formatter};
+
+use bitflags::Flags;
+use itertools::Itertools;
+
+use crate::{
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    class_file_field::{ClassFileField, FieldConstantValue},
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    field_flags::FieldFlags,
+    field_type::{BaseType, FieldType},
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+};
+
+/// A constraint of the class file format that a class does not respect, as found by
+/// [ClassFile::check_format]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatViolation {
+    /// Path of the element with the problem, i.e. `methods[3] "foo" > Code`, using the same
+    /// format as the errors of the reader
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for FormatViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Major version from which interfaces can have non abstract methods
+const DEFAULT_METHODS_MAJOR_VERSION: u16 = 52;
+/// Major version from which `<clinit>` must be static
+const STATIC_CLINIT_MAJOR_VERSION: u16 = 51;
+
+/// Checks the static constraints of JVMS §4.8 that do not need to look at the bytecode:
+/// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.8
+pub(crate) struct FormatChecker<'a> {
+    class: &'a ClassFile,
+    violations: Vec<FormatViolation>,
+}
+
+impl<'a> FormatChecker<'a> {
+    pub(crate) fn new(class: &'a ClassFile) -> Self {
+        Self {
+            class,
+            violations: Vec::new(),
+        }
+    }
+
+    pub(crate) fn check(mut self) -> Vec<FormatViolation> {
+        self.check_constants();
+        self.check_class();
+        let mut members = HashSet::new();
+        for (i, field) in self.class.fields.iter().enumerate() {
+            let path = format!("fields[{i}] \"{}\"", field.name);
+            if !members.insert(("field", &field.name, field.type_descriptor.descriptor())) {
+                self.violation(&path, "duplicate field".to_string());
+            }
+            self.check_field(&path, field);
+        }
+        for (i, method) in self.class.methods.iter().enumerate() {
+            let path = format!("methods[{i}] \"{}\"", method.name);
+            if !members.insert(("method", &method.name, method.type_descriptor.clone())) {
+                self.violation(&path, "duplicate method".to_string());
+            }
+            self.check_method(&path, method);
+        }
+        self.violations
+    }
+
+    fn violation(&mut self, path: &str, message: String) {
+        self.violations.push(FormatViolation {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    fn is_interface(&self) -> bool {
+        self.class.flags.contains(ClassAccessFlags::INTERFACE)
+    }
+
+    fn major_version(&self) -> u16 {
+        self.class.version.major()
+    }
+
+    /// Checks that the references between constants point to entries of the right kinds
+    fn check_constants(&mut self) {
+        let constants = &self.class.constants;
+        let mut problems = Vec::new();
+        for (index, entry) in constants.iter() {
+            let utf8 = |index: u16| match constants.get(index) {
+                Ok(ConstantPoolEntry::Utf8(text)) => Some(text.as_str()),
+                _ => None,
+            };
+            let problem = match entry {
+                ConstantPoolEntry::ClassReference(name) => match utf8(*name) {
+                    Some(name) if is_class_name(name) => None,
+                    Some(name) => Some(format!("invalid class name: {name}")),
+                    None => Some(format!("class name #{name} is not an utf8 constant")),
+                },
+                ConstantPoolEntry::StringReference(text) => utf8(*text)
+                    .is_none()
+                    .then(|| format!("string #{text} is not an utf8 constant")),
+                ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor) => {
+                    if utf8(*name).is_none() {
+                        Some(format!("name #{name} is not an utf8 constant"))
+                    } else if utf8(*descriptor).is_none() {
+                        Some(format!("descriptor #{descriptor} is not an utf8 constant"))
+                    } else {
+                        None
+                    }
+                }
+                ConstantPoolEntry::FieldReference(class, name_and_type) => {
+                    check_member_reference(constants, *class, *name_and_type, |name, descriptor| {
+                        if !is_unqualified_name(name) {
+                            Some(format!("invalid field name: {name}"))
+                        } else if FieldType::parse(descriptor).is_err() {
+                            Some(format!("invalid field descriptor: {descriptor}"))
+                        } else {
+                            None
+                        }
+                    })
+                }
+                ConstantPoolEntry::MethodReference(class, name_and_type)
+                | ConstantPoolEntry::InterfaceMethodReference(class, name_and_type) => {
+                    check_member_reference(constants, *class, *name_and_type, |name, descriptor| {
+                        match MethodDescriptor::parse(descriptor) {
+                            Err(_) => Some(format!("invalid method descriptor: {descriptor}")),
+                            Ok(parsed) if name == "<init>" && parsed.return_type.is_some() => {
+                                Some(format!("<init> must return void: {descriptor}"))
+                            }
+                            Ok(_) if name == "<init>" => None,
+                            Ok(_) if !is_method_name(name) => {
+                                Some(format!("invalid method name: {name}"))
+                            }
+                            Ok(_) => None,
+                        }
+                    })
+                }
+                ConstantPoolEntry::Utf8(_)
+                | ConstantPoolEntry::Integer(_)
+                | ConstantPoolEntry::Float(_)
+                | ConstantPoolEntry::Long(_)
+                | ConstantPoolEntry::Double(_) => None,
+            };
+            if let Some(message) = problem {
+                problems.push((format!("constant_pool[{index}]"), message));
+            }
+        }
+        for (path, message) in problems {
+            self.violation(&path, message);
+        }
+    }
+
+    fn check_class(&mut self) {
+        let class = self.class;
+        if !is_binary_name(&class.name) {
+            self.violation("this_class", format!("invalid class name: {}", class.name));
+        }
+        match &class.superclass {
+            Some(superclass) if !is_binary_name(superclass) => {
+                self.violation("super_class", format!("invalid class name: {superclass}"))
+            }
+            Some(superclass) if self.is_interface() && superclass != "java/lang/Object" => self
+                .violation(
+                    "super_class",
+                    format!(
+                        "the superclass of an interface must be java/lang/Object, not {superclass}"
+                    ),
+                ),
+            None if class.name != "java/lang/Object" => self.violation(
+                "super_class",
+                "only java/lang/Object can have no superclass".to_string(),
+            ),
+            _ => {}
+        }
+        let mut interfaces = HashSet::new();
+        for (i, interface) in class.interfaces.iter().enumerate() {
+            let path = format!("interfaces[{i}]");
+            if !is_binary_name(interface) {
+                self.violation(&path, format!("invalid class name: {interface}"));
+            }
+            if !interfaces.insert(interface) {
+                self.violation(&path, format!("duplicate interface: {interface}"));
+            }
+        }
+
+        let flags = class.flags;
+        if flags.contains(ClassAccessFlags::INTERFACE) {
+            if !flags.contains(ClassAccessFlags::ABSTRACT) {
+                self.violation("access_flags", "interface must be abstract".to_string());
+            }
+            let forbidden =
+                ClassAccessFlags::FINAL | ClassAccessFlags::SUPER | ClassAccessFlags::ENUM;
+            if flags.intersects(forbidden) {
+                self.violation(
+                    "access_flags",
+                    format!(
+                        "interface cannot have flags {}",
+                        flag_names(flags & forbidden)
+                    ),
+                );
+            }
+        } else if flags.contains(ClassAccessFlags::ANNOTATION) {
+            self.violation(
+                "access_flags",
+                "annotation must be an interface".to_string(),
+            );
+        }
+        if flags.contains(ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT) {
+            self.violation(
+                "access_flags",
+                "class cannot be both final and abstract".to_string(),
+            );
+        }
+    }
+
+    fn check_field(&mut self, path: &str, field: &ClassFileField) {
+        if !is_unqualified_name(&field.name) {
+            self.violation(path, format!("invalid field name: {}", field.name));
+        }
+
+        let flags = field.flags;
+        let access = flags & (FieldFlags::PUBLIC | FieldFlags::PRIVATE | FieldFlags::PROTECTED);
+        if access.bits().count_ones() > 1 {
+            self.violation(
+                path,
+                format!("conflicting access flags {}", flag_names(access)),
+            );
+        }
+        if flags.contains(FieldFlags::FINAL | FieldFlags::VOLATILE) {
+            self.violation(path, "field cannot be both final and volatile".to_string());
+        }
+        if self.is_interface() {
+            let required = FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL;
+            if !flags.contains(required) || !(required | FieldFlags::SYNTHETIC).contains(flags) {
+                self.violation(
+                    path,
+                    format!(
+                        "interface field must be public static final, not {}",
+                        flag_names(flags)
+                    ),
+                );
+            }
+        }
+
+        if let Some(value) = &field.constant_value {
+            let matches = match (&field.type_descriptor, value) {
+                (FieldType::Base(BaseType::Long), FieldConstantValue::Long(_))
+                | (FieldType::Base(BaseType::Float), FieldConstantValue::Float(_))
+                | (FieldType::Base(BaseType::Double), FieldConstantValue::Double(_))
+                | (
+                    FieldType::Base(
+                        BaseType::Int
+                        | BaseType::Short
+                        | BaseType::Char
+                        | BaseType::Byte
+                        | BaseType::Boolean,
+                    ),
+                    FieldConstantValue::Int(_),
+                ) => true,
+                (FieldType::Object(class), FieldConstantValue::String(_)) => {
+                    class == "java/lang/String"
+                }
+                _ => false,
+            };
+            if !matches {
+                self.violation(
+                    &format!("{path} > ConstantValue"),
+                    format!(
+                        "constant value {value} does not match the field type {}",
+                        field.type_descriptor
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_method(&mut self, path: &str, method: &ClassFileMethod) {
+        let flags = method.flags;
+        let is_init = method.name == "<init>";
+        let is_clinit = method.name == "<clinit>";
+        if !is_init && !is_clinit && !is_method_name(&method.name) {
+            self.violation(path, format!("invalid method name: {}", method.name));
+        }
+        match MethodDescriptor::parse(&method.type_descriptor) {
+            Err(_) => self.violation(
+                path,
+                format!("invalid method descriptor: {}", method.type_descriptor),
+            ),
+            Ok(descriptor) => {
+                if is_init && descriptor.return_type.is_some() {
+                    self.violation(path, "<init> must return void".to_string());
+                }
+                if is_clinit && descriptor != MethodDescriptor::default() {
+                    self.violation(path, "<clinit> must have descriptor ()V".to_string());
+                }
+            }
+        }
+
+        let access = flags & (MethodFlags::PUBLIC | MethodFlags::PRIVATE | MethodFlags::PROTECTED);
+        if access.bits().count_ones() > 1 {
+            self.violation(
+                path,
+                format!("conflicting access flags {}", flag_names(access)),
+            );
+        }
+        if is_clinit {
+            if self.major_version() >= STATIC_CLINIT_MAJOR_VERSION
+                && !flags.contains(MethodFlags::STATIC)
+            {
+                self.violation(path, "<clinit> must be static".to_string());
+            }
+        } else if is_init {
+            if self.is_interface() {
+                self.violation(path, "interfaces cannot have constructors".to_string());
+            }
+            let allowed = MethodFlags::PUBLIC
+                | MethodFlags::PRIVATE
+                | MethodFlags::PROTECTED
+                | MethodFlags::VARARGS
+                | MethodFlags::STRICT
+                | MethodFlags::SYNTHETIC;
+            if !allowed.contains(flags) {
+                self.violation(
+                    path,
+                    format!("<init> cannot have flags {}", flag_names(flags - allowed)),
+                );
+            }
+        } else if self.is_interface() {
+            self.check_interface_method_flags(path, flags);
+        }
+        if flags.contains(MethodFlags::ABSTRACT) {
+            let forbidden = MethodFlags::PRIVATE
+                | MethodFlags::STATIC
+                | MethodFlags::FINAL
+                | MethodFlags::SYNCHRONIZED
+                | MethodFlags::NATIVE
+                | MethodFlags::STRICT;
+            if flags.intersects(forbidden) {
+                self.violation(
+                    path,
+                    format!(
+                        "abstract method cannot have flags {}",
+                        flag_names(flags & forbidden)
+                    ),
+                );
+            }
+        }
+
+        let needs_code = !flags.intersects(MethodFlags::ABSTRACT | MethodFlags::NATIVE);
+        match &method.code {
+            Some(code) if needs_code => self.check_code(&format!("{path} > Code"), code),
+            Some(_) => self.violation(
+                path,
+                "abstract and native methods cannot have code".to_string(),
+            ),
+            None if needs_code => self.violation(path, "missing Code attribute".to_string()),
+            None => {}
+        }
+    }
+
+    fn check_interface_method_flags(&mut self, path: &str, flags: MethodFlags) {
+        if self.major_version() < DEFAULT_METHODS_MAJOR_VERSION {
+            if !flags.contains(MethodFlags::PUBLIC | MethodFlags::ABSTRACT) {
+                self.violation(
+                    path,
+                    format!(
+                        "interface method must be public abstract, not {}",
+                        flag_names(flags)
+                    ),
+                );
+            }
+            return;
+        }
+        let forbidden = MethodFlags::PROTECTED
+            | MethodFlags::FINAL
+            | MethodFlags::SYNCHRONIZED
+            | MethodFlags::NATIVE;
+        if flags.intersects(forbidden) {
+            self.violation(
+                path,
+                format!(
+                    "interface method cannot have flags {}",
+                    flag_names(flags & forbidden)
+                ),
+            );
+        }
+        if !flags.intersects(MethodFlags::PUBLIC | MethodFlags::PRIVATE) {
+            self.violation(
+                path,
+                "interface method must be either public or private".to_string(),
+            );
+        }
+    }
+
+    fn check_code(&mut self, path: &str, code: &ClassFileMethodCode) {
+        let code_length = code.code.len();
+        if code_length == 0 || code_length > u16::MAX as usize {
+            self.violation(
+                path,
+                format!("code length must be between 1 and 65535, not {code_length}"),
+            );
+            return;
+        }
+
+        let boundaries: HashSet<usize> = match Instruction::parse_instructions(&code.code) {
+            Ok(instructions) => instructions.iter().map(|(address, _)| *address).collect(),
+            Err(err) => {
+                self.violation(path, format!("invalid bytecode: {err}"));
+                return;
+            }
+        };
+        for (i, entry) in code.exception_table.entries().iter().enumerate() {
+            let path = format!("{path} > exception_table[{i}]");
+            let start = entry.range.start.0 as usize;
+            let end = entry.range.end.0 as usize;
+            let handler = entry.handler_pc.0 as usize;
+            if start >= end {
+                self.violation(&path, format!("empty range {start}..{end}"));
+            }
+            if !boundaries.contains(&start) {
+                self.violation(&path, format!("start_pc {start} is not an instruction"));
+            }
+            if end != code_length && !boundaries.contains(&end) {
+                self.violation(
+                    &path,
+                    format!("end_pc {end} is neither an instruction nor the end of the code"),
+                );
+            }
+            if !boundaries.contains(&handler) {
+                self.violation(&path, format!("handler_pc {handler} is not an instruction"));
+            }
+            if let Some(catch_class) = &entry.catch_class {
+                if !is_binary_name(catch_class) {
+                    self.violation(&path, format!("invalid class name: {catch_class}"));
+                }
+            }
+        }
+    }
+}
+
+/// Checks that a field or method reference points to a class and to a name and type, whose
+/// name and descriptor are then validated by the given function
+fn check_member_reference(
+    constants: &ConstantPool,
+    class: u16,
+    name_and_type: u16,
+    check: impl FnOnce(&str, &str) -> Option<String>,
+) -> Option<String> {
+    if !matches!(
+        constants.get(class),
+        Ok(ConstantPoolEntry::ClassReference(_))
+    ) {
+        return Some(format!("class #{class} is not a class constant"));
+    }
+    match constants.get(name_and_type) {
+        Ok(ConstantPoolEntry::NameAndTypeDescriptor(name, descriptor)) => {
+            match (constants.get(*name), constants.get(*descriptor)) {
+                (Ok(ConstantPoolEntry::Utf8(name)), Ok(ConstantPoolEntry::Utf8(descriptor))) => {
+                    check(name, descriptor)
+                }
+                // Reported on the name and type constant itself
+                _ => None,
+            }
+        }
+        _ => Some(format!(
+            "name and type #{name_and_type} is not a name and type constant"
+        )),
+    }
+}
+
+/// Unqualified names cannot contain any of `. ; [ /`:
+/// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.2.2
+fn is_unqualified_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['.', ';', '[', '/'])
+}
+
+/// Method names additionally cannot contain `<` or `>`, except for `<init>` and `<clinit>`
+fn is_method_name(name: &str) -> bool {
+    is_unqualified_name(name) && !name.contains(['<', '>'])
+}
+
+/// Binary names in their internal form, i.e. `java/lang/Object`, are unqualified names
+/// separated by `/`: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.2.1
+fn is_binary_name(name: &str) -> bool {
+    name.split('/').all(is_unqualified_name)
+}
+
+/// Class constants can also refer to array types, using their descriptor
+fn is_class_name(name: &str) -> bool {
+    if name.starts_with('[') {
+        FieldType::parse(name).is_ok()
+    } else {
+        is_binary_name(name)
+    }
+}
+
+/// Names of the flags that are set, i.e. `PUBLIC | STATIC`
+fn flag_names<F: Flags>(flags: F) -> String {
+    flags.iter_names().map(|(name, _)| name).join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format_check::{is_binary_name, is_class_name, is_method_name};
+
+    #[test]
+    fn binary_names() {
+        assert!(is_binary_name("java/lang/Object"));
+        assert!(is_binary_name("Foo$Bar"));
+        assert!(!is_binary_name("java.lang.Object"));
+        assert!(!is_binary_name("java//Object"));
+        assert!(!is_binary_name("/Object"));
+        assert!(!is_binary_name(""));
+    }
+
+    #[test]
+    fn class_names_can_be_arrays() {
+        assert!(is_class_name("[Ljava/lang/String;"));
+        assert!(!is_class_name("[Ljava/lang/String"));
+    }
+
+    #[test]
+    fn method_names() {
+        assert!(is_method_name("lambda$main$0"));
+        assert!(!is_method_name("<init>"));
+        assert!(!is_method_name("a;b"));
+    }
+}
@@ -1,4 +1,12 @@
-use crate::class_reader_error::ClassReaderError;
+use std::ops::Range;
+
+use crate::{
+    class_reader_error::ClassReaderError,
+    class_writer_error::ClassWriterError,
+    constant_pool::ConstantPool,
+    field_type::{BaseType, FieldType},
+    method_descriptor::MethodDescriptor,
+};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
@@ -6,22 +14,29 @@ use wasm_bindgen::prelude::*;
 /// Represents a Java bytecode instruction.
 //noinspection SpellCheckingInspection
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
-#[cfg_attr(feature = "wasm", serde(tag = "opcode"))]
+#[derive(Clone, Debug, Eq, PartialEq, strum_macros::IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub enum Instruction {
     Aaload,
     Aastore,
     Aconst_null,
-    Aload { index: u8 },
+    Aload {
+        index: u8,
+    },
     Aload_0,
     Aload_1,
     Aload_2,
     Aload_3,
-    Anewarray { class: u16 },
+    Anewarray {
+        class: u16,
+    },
     Areturn,
     Arraylength,
-    Astore { index: u8 },
+    Astore {
+        index: u8,
+    },
     Astore_0,
     Astore_1,
     Astore_2,
@@ -29,10 +44,14 @@ pub enum Instruction {
     Athrow,
     Baload,
     Bastore,
-    Bipush { byte: u8 },
+    Bipush {
+        byte: u8,
+    },
     Caload,
     Castore,
-    Checkcast { class: u16 },
+    Checkcast {
+        class: u16,
+    },
     D2f,
     D2i,
     D2l,
@@ -44,7 +63,9 @@ pub enum Instruction {
     Dconst_0,
     Dconst_1,
     Ddiv,
-    Dload { index: u8 },
+    Dload {
+        index: u8,
+    },
     Dload_0,
     Dload_1,
     Dload_2,
@@ -53,7 +74,9 @@ pub enum Instruction {
     Dneg,
     Drem,
     Dreturn,
-    Dstore { index: u8 },
+    Dstore {
+        index: u8,
+    },
     Dstore_0,
     Dstore_1,
     Dstore_2,
@@ -77,7 +100,9 @@ pub enum Instruction {
     Fconst_1,
     Fconst_2,
     Fdiv,
-    Fload { index: u8 },
+    Fload {
+        index: u8,
+    },
     Fload_0,
     Fload_1,
     Fload_2,
@@ -86,16 +111,26 @@ pub enum Instruction {
     Fneg,
     Frem,
     Freturn,
-    Fstore { index: u8 },
+    Fstore {
+        index: u8,
+    },
     Fstore_0,
     Fstore_1,
     Fstore_2,
     Fstore_3,
     Fsub,
-    Getfield { field: u16 },
-    Getstatic { field: u16 },
-    Goto { jump_address: u16 },
-    Goto_w,
+    Getfield {
+        field: u16,
+    },
+    Getstatic {
+        field: u16,
+    },
+    Goto {
+        jump_address: u16,
+    },
+    Goto_w {
+        jump_address: u16,
+    },
     I2b,
     I2c,
     I2d,
@@ -114,42 +149,94 @@ pub enum Instruction {
     Iconst_4,
     Iconst_5,
     Idiv,
-    If_acmpeq { jump_address: u16 },
-    If_acmpne { jump_address: u16 },
-    If_icmpeq { jump_address: u16 },
-    If_icmpne { jump_address: u16 },
-    If_icmplt { jump_address: u16 },
-    If_icmpge { jump_address: u16 },
-    If_icmpgt { jump_address: u16 },
-    If_icmple { jump_address: u16 },
-    Ifeq { jump_address: u16 },
-    Ifne { jump_address: u16 },
-    Iflt { jump_address: u16 },
-    Ifge { jump_address: u16 },
-    Ifgt { jump_address: u16 },
-    Ifle { jump_address: u16 },
-    Ifnonnull { jump_address: u16 },
-    Ifnull { jump_address: u16 },
-    Iinc { index: u8, constant: i8 },
-    Iload { index: u8 },
+    If_acmpeq {
+        jump_address: u16,
+    },
+    If_acmpne {
+        jump_address: u16,
+    },
+    If_icmpeq {
+        jump_address: u16,
+    },
+    If_icmpne {
+        jump_address: u16,
+    },
+    If_icmplt {
+        jump_address: u16,
+    },
+    If_icmpge {
+        jump_address: u16,
+    },
+    If_icmpgt {
+        jump_address: u16,
+    },
+    If_icmple {
+        jump_address: u16,
+    },
+    Ifeq {
+        jump_address: u16,
+    },
+    Ifne {
+        jump_address: u16,
+    },
+    Iflt {
+        jump_address: u16,
+    },
+    Ifge {
+        jump_address: u16,
+    },
+    Ifgt {
+        jump_address: u16,
+    },
+    Ifle {
+        jump_address: u16,
+    },
+    Ifnonnull {
+        jump_address: u16,
+    },
+    Ifnull {
+        jump_address: u16,
+    },
+    Iinc {
+        index: u8,
+        constant: i8,
+    },
+    Iload {
+        index: u8,
+    },
     Iload_0,
     Iload_1,
     Iload_2,
     Iload_3,
     Imul,
     Ineg,
-    Instanceof { class: u16 },
-    Invokedynamic { call_site: u16 },
-    Invokeinterface { method: u16, count: u8 },
-    Invokespecial { method: u16 },
-    Invokestatic { method: u16 },
-    Invokevirtual { method: u16 },
+    Instanceof {
+        class: u16,
+    },
+    Invokedynamic {
+        call_site: u16,
+    },
+    Invokeinterface {
+        method: u16,
+        count: u8,
+    },
+    Invokespecial {
+        method: u16,
+    },
+    Invokestatic {
+        method: u16,
+    },
+    Invokevirtual {
+        method: u16,
+    },
     Ior,
     Irem,
     Ireturn,
     Ishl,
     Ishr,
-    Istore { index: u8 },
+    Istore {
+        index: u8,
+    },
     Istore_0,
     Istore_1,
     Istore_2,
@@ -157,8 +244,12 @@ pub enum Instruction {
     Isub,
     Iushr,
     Ixor,
-    Jsr { jump_address: u16 },
-    Jsr_w,
+    Jsr {
+        jump_address: u16,
+    },
+    Jsr_w {
+        jump_address: u16,
+    },
     L2d,
     L2f,
     L2i,
@@ -169,24 +260,37 @@ pub enum Instruction {
     Lcmp,
     Lconst_0,
     Lconst_1,
-    Ldc { index: u8 },
-    Ldc_w { index: u16 },
-    Ldc2_w { index: u16 },
+    Ldc {
+        index: u8,
+    },
+    Ldc_w {
+        index: u16,
+    },
+    Ldc2_w {
+        index: u16,
+    },
     Ldiv,
-    Lload { index: u8 },
+    Lload {
+        index: u8,
+    },
     Lload_0,
     Lload_1,
     Lload_2,
     Lload_3,
     Lmul,
     Lneg,
-    Lookupswitch,
+    Lookupswitch {
+        default: u16,
+        match_pairs: Vec<(i32, u16)>,
+    },
     Lor,
     Lrem,
     Lreturn,
     Lshl,
     Lshr,
-    Lstore { index: u8 },
+    Lstore {
+        index: u8,
+    },
     Lstore_0,
     Lstore_1,
     Lstore_2,
@@ -196,28 +300,150 @@ pub enum Instruction {
     Lxor,
     Monitorenter,
     Monitorexit,
-    Multianewarray { class: u16, dimensions: u8 },
-    New { class: u16 },
-    Newarray { array_type: NewArrayType },
+    Multianewarray {
+        class: u16,
+        dimensions: u8,
+    },
+    New {
+        class: u16,
+    },
+    Newarray {
+        array_type: NewArrayType,
+    },
     Nop,
     Pop,
     Pop2,
-    Putfield { field: u16 },
-    Putstatic { field: u16 },
-    Ret { index: u8 },
+    Putfield {
+        field: u16,
+    },
+    Putstatic {
+        field: u16,
+    },
+    Ret {
+        index: u8,
+    },
     Return,
     Saload,
     Sastore,
-    Sipush { short: i16 },
+    Sipush {
+        short: i16,
+    },
     Swap,
-    Tableswitch,
-    Wide,
+    Tableswitch {
+        default: u16,
+        low: i32,
+        high: i32,
+        jump_addresses: Vec<u16>,
+    },
+    Wide {
+        instruction: WideInstruction,
+    },
+}
+
+/// Instructions that can be modified by the `wide` prefix, which use a two bytes local
+/// variable index (and, for `iinc`, a two bytes constant)
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub enum WideInstruction {
+    Iload { index: u16 },
+    Fload { index: u16 },
+    Aload { index: u16 },
+    Lload { index: u16 },
+    Dload { index: u16 },
+    Istore { index: u16 },
+    Fstore { index: u16 },
+    Astore { index: u16 },
+    Lstore { index: u16 },
+    Dstore { index: u16 },
+    Ret { index: u16 },
+    Iinc { index: u16, constant: i16 },
+}
+
+/// Kinds of values that can be loaded from and stored into local variables
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalKind {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl LocalKind {
+    /// Number of local variable slots taken by a value of this kind
+    pub fn slots(&self) -> u16 {
+        match self {
+            LocalKind::Long | LocalKind::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl From<&FieldType> for LocalKind {
+    fn from(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(BaseType::Long) => LocalKind::Long,
+            FieldType::Base(BaseType::Float) => LocalKind::Float,
+            FieldType::Base(BaseType::Double) => LocalKind::Double,
+            FieldType::Base(_) => LocalKind::Int,
+            FieldType::Object(_) | FieldType::Array(_) => LocalKind::Reference,
+        }
+    }
+}
+
+/// How an instruction accesses a local variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalVariableAccess {
+    Load(LocalKind, u16),
+    Store(LocalKind, u16),
+    /// `iinc`, which both reads and writes an int variable
+    Increment(u16),
+    /// `ret`, which reads the return address stored by `jsr`
+    Ret(u16),
+}
+
+impl LocalVariableAccess {
+    pub fn index(&self) -> u16 {
+        match self {
+            LocalVariableAccess::Load(_, index)
+            | LocalVariableAccess::Store(_, index)
+            | LocalVariableAccess::Increment(index)
+            | LocalVariableAccess::Ret(index) => *index,
+        }
+    }
+
+    /// The range of slots accessed. Long and double variables take up two slots.
+    pub fn slots(&self) -> Range<u16> {
+        let size = match self {
+            LocalVariableAccess::Load(kind, _) | LocalVariableAccess::Store(kind, _) => {
+                kind.slots()
+            }
+            _ => 1,
+        };
+        self.index()..self.index().saturating_add(size)
+    }
+}
+
+/// Number of stack slots consumed and produced by an instruction. Long and double values
+/// take up two slots.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StackEffect {
+    pub popped: u16,
+    pub pushed: u16,
+}
+
+impl StackEffect {
+    fn new(popped: u16, pushed: u16) -> Self {
+        Self { popped, pushed }
+    }
 }
 
 /// Possible arguments of instruction `newarray`
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NewArrayType {
     Boolean,
     Char,
@@ -345,7 +571,9 @@ impl Instruction {
             0xa7 => Instruction::Goto {
                 jump_address: Self::read_offset(raw_code, &mut address)?,
             },
-            0xc8 => todo!("OpCode::Goto_w"),
+            0xc8 => Instruction::Goto_w {
+                jump_address: Self::read_wide_offset(raw_code, &mut address)?,
+            },
             0x91 => Instruction::I2b,
             0x92 => Instruction::I2c,
             0x87 => Instruction::I2d,
@@ -479,7 +707,9 @@ impl Instruction {
             0xa8 => Instruction::Jsr {
                 jump_address: Self::read_offset(raw_code, &mut address)?,
             },
-            0xc9 => todo!("OpCode::Jsr_w"),
+            0xc9 => Instruction::Jsr_w {
+                jump_address: Self::read_wide_offset(raw_code, &mut address)?,
+            },
             0x8a => Instruction::L2d,
             0x89 => Instruction::L2f,
             0x88 => Instruction::L2i,
@@ -509,7 +739,29 @@ impl Instruction {
             0x21 => Instruction::Lload_3,
             0x69 => Instruction::Lmul,
             0x75 => Instruction::Lneg,
-            0xab => todo!("OpCode::Lookupswitch"),
+            0xab => {
+                let instruction_address = address - 1;
+                address = Self::skip_switch_padding(address);
+                let default =
+                    Self::read_switch_target(raw_code, &mut address, instruction_address)?;
+                let pairs_count = Self::read_i32(raw_code, &mut address)?;
+                if pairs_count < 0 {
+                    return Err(ClassReaderError::invalid_class_data(format!(
+                        "invalid number of pairs for lookupswitch at address {instruction_address}"
+                    )));
+                }
+                let mut match_pairs = Vec::new();
+                for _ in 0..pairs_count {
+                    let key = Self::read_i32(raw_code, &mut address)?;
+                    let target =
+                        Self::read_switch_target(raw_code, &mut address, instruction_address)?;
+                    match_pairs.push((key, target));
+                }
+                Instruction::Lookupswitch {
+                    default,
+                    match_pairs,
+                }
+            }
             0x81 => Instruction::Lor,
             0x71 => Instruction::Lrem,
             0xad => Instruction::Lreturn,
@@ -572,8 +824,81 @@ impl Instruction {
                 short: Self::read_i16(raw_code, &mut address)?,
             },
             0x5f => Instruction::Swap,
-            0xaa => todo!("OpCode::Tableswitch"),
-            0xc4 => todo!("OpCode::Wide"),
+            0xaa => {
+                let instruction_address = address - 1;
+                address = Self::skip_switch_padding(address);
+                let default =
+                    Self::read_switch_target(raw_code, &mut address, instruction_address)?;
+                let low = Self::read_i32(raw_code, &mut address)?;
+                let high = Self::read_i32(raw_code, &mut address)?;
+                if high < low {
+                    return Err(ClassReaderError::invalid_class_data(format!(
+                        "invalid bounds for tableswitch at address {instruction_address}"
+                    )));
+                }
+                let mut jump_addresses = Vec::new();
+                for _ in low..=high {
+                    jump_addresses.push(Self::read_switch_target(
+                        raw_code,
+                        &mut address,
+                        instruction_address,
+                    )?);
+                }
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    high,
+                    jump_addresses,
+                }
+            }
+            0xc4 => {
+                let modified_op_byte = Self::read_u8(raw_code, &mut address)?;
+                let instruction = match modified_op_byte {
+                    0x15 => WideInstruction::Iload {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x17 => WideInstruction::Fload {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x19 => WideInstruction::Aload {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x16 => WideInstruction::Lload {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x18 => WideInstruction::Dload {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x36 => WideInstruction::Istore {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x38 => WideInstruction::Fstore {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x3a => WideInstruction::Astore {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x37 => WideInstruction::Lstore {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x39 => WideInstruction::Dstore {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0xa9 => WideInstruction::Ret {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                    },
+                    0x84 => WideInstruction::Iinc {
+                        index: Self::read_u16(raw_code, &mut address)?,
+                        constant: Self::read_i16(raw_code, &mut address)?,
+                    },
+                    _ => {
+                        return Err(ClassReaderError::invalid_class_data(format!(
+                        "invalid op code after wide: {modified_op_byte:#04x} at address {address}"
+                    )))
+                    }
+                };
+                Instruction::Wide { instruction }
+            }
             _ => {
                 return Err(ClassReaderError::invalid_class_data(format!(
                     "invalid op code: {op_byte:#04x} at address {address}"
@@ -585,7 +910,7 @@ impl Instruction {
     }
 
     /// Parses all instructions in the given raw code.
-    pub(crate) fn parse_instructions(
+    pub fn parse_instructions(
         raw_code: &[u8],
     ) -> Result<Vec<(usize, Instruction)>, ClassReaderError> {
         let mut instructions: Vec<(usize, Self)> = Vec::new();
@@ -621,7 +946,7 @@ impl Instruction {
 
     fn read_i8(raw_code: &[u8], address: &mut usize) -> Result<i8, ClassReaderError> {
         let value = Self::read_u8(raw_code, address)?;
-        Ok(unsafe { std::mem::transmute(value) })
+        Ok(value as i8)
     }
 
     fn read_u16(raw_code: &[u8], address: &mut usize) -> Result<u16, ClassReaderError> {
@@ -632,17 +957,1103 @@ impl Instruction {
 
     fn read_i16(raw_code: &[u8], address: &mut usize) -> Result<i16, ClassReaderError> {
         let value = Self::read_u16(raw_code, address)?;
-        Ok(unsafe { std::mem::transmute(value) })
+        Ok(value as i16)
+    }
+
+    fn read_i32(raw_code: &[u8], address: &mut usize) -> Result<i32, ClassReaderError> {
+        let high = Self::read_u16(raw_code, address)? as u32;
+        let low = Self::read_u16(raw_code, address)? as u32;
+        Ok(((high << 16) | low) as i32)
     }
 
     fn read_offset(raw_code: &[u8], address: &mut usize) -> Result<u16, ClassReaderError> {
         let instruction_address = *address - 1;
         let offset = Self::read_i16(raw_code, address)?;
-        let jump_address = (instruction_address as i32) + (offset as i32);
+        Self::jump_address(instruction_address, offset as i32, *address)
+    }
+
+    fn read_wide_offset(raw_code: &[u8], address: &mut usize) -> Result<u16, ClassReaderError> {
+        let instruction_address = *address - 1;
+        let offset = Self::read_i32(raw_code, address)?;
+        Self::jump_address(instruction_address, offset, *address)
+    }
+
+    fn read_switch_target(
+        raw_code: &[u8],
+        address: &mut usize,
+        instruction_address: usize,
+    ) -> Result<u16, ClassReaderError> {
+        let offset = Self::read_i32(raw_code, address)?;
+        Self::jump_address(instruction_address, offset, *address)
+    }
+
+    fn jump_address(
+        instruction_address: usize,
+        offset: i32,
+        address: usize,
+    ) -> Result<u16, ClassReaderError> {
+        let jump_address = (instruction_address as i64) + (offset as i64);
         u16::try_from(jump_address).map_err(|_| {
             ClassReaderError::invalid_class_data(format!(
                 "invalid jump offset at address {address}"
             ))
         })
     }
+
+    /// The operands of `tableswitch` and `lookupswitch` are aligned to a multiple of four bytes
+    /// from the start of the code
+    fn skip_switch_padding(address: usize) -> usize {
+        address.next_multiple_of(4)
+    }
+}
+
+impl Instruction {
+    /// The name of the instruction, as printed by `javap`. Instructions modified by the `wide`
+    /// prefix are named after the one they modify, with a `_w` suffix, i.e. `iinc_w`.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Wide { instruction } => instruction.mnemonic(),
+            _ => self.into(),
+        }
+    }
+
+    /// The byte that identifies this instruction in the bytecode
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Instruction::Aaload => 0x32,
+            Instruction::Aastore => 0x53,
+            Instruction::Aconst_null => 0x01,
+            Instruction::Aload { .. } => 0x19,
+            Instruction::Aload_0 => 0x2a,
+            Instruction::Aload_1 => 0x2b,
+            Instruction::Aload_2 => 0x2c,
+            Instruction::Aload_3 => 0x2d,
+            Instruction::Anewarray { .. } => 0xbd,
+            Instruction::Areturn => 0xb0,
+            Instruction::Arraylength => 0xbe,
+            Instruction::Astore { .. } => 0x3a,
+            Instruction::Astore_0 => 0x4b,
+            Instruction::Astore_1 => 0x4c,
+            Instruction::Astore_2 => 0x4d,
+            Instruction::Astore_3 => 0x4e,
+            Instruction::Athrow => 0xbf,
+            Instruction::Baload => 0x33,
+            Instruction::Bastore => 0x54,
+            Instruction::Bipush { .. } => 0x10,
+            Instruction::Caload => 0x34,
+            Instruction::Castore => 0x55,
+            Instruction::Checkcast { .. } => 0xc0,
+            Instruction::D2f => 0x90,
+            Instruction::D2i => 0x8e,
+            Instruction::D2l => 0x8f,
+            Instruction::Dadd => 0x63,
+            Instruction::Daload => 0x31,
+            Instruction::Dastore => 0x52,
+            Instruction::Dcmpg => 0x98,
+            Instruction::Dcmpl => 0x97,
+            Instruction::Dconst_0 => 0x0e,
+            Instruction::Dconst_1 => 0x0f,
+            Instruction::Ddiv => 0x6f,
+            Instruction::Dload { .. } => 0x18,
+            Instruction::Dload_0 => 0x26,
+            Instruction::Dload_1 => 0x27,
+            Instruction::Dload_2 => 0x28,
+            Instruction::Dload_3 => 0x29,
+            Instruction::Dmul => 0x6b,
+            Instruction::Dneg => 0x77,
+            Instruction::Drem => 0x73,
+            Instruction::Dreturn => 0xaf,
+            Instruction::Dstore { .. } => 0x39,
+            Instruction::Dstore_0 => 0x47,
+            Instruction::Dstore_1 => 0x48,
+            Instruction::Dstore_2 => 0x49,
+            Instruction::Dstore_3 => 0x4a,
+            Instruction::Dsub => 0x67,
+            Instruction::Dup => 0x59,
+            Instruction::Dup_x1 => 0x5a,
+            Instruction::Dup_x2 => 0x5b,
+            Instruction::Dup2 => 0x5c,
+            Instruction::Dup2_x1 => 0x5d,
+            Instruction::Dup2_x2 => 0x5e,
+            Instruction::F2d => 0x8d,
+            Instruction::F2i => 0x8b,
+            Instruction::F2l => 0x8c,
+            Instruction::Fadd => 0x62,
+            Instruction::Faload => 0x30,
+            Instruction::Fastore => 0x51,
+            Instruction::Fcmpg => 0x96,
+            Instruction::Fcmpl => 0x95,
+            Instruction::Fconst_0 => 0x0b,
+            Instruction::Fconst_1 => 0x0c,
+            Instruction::Fconst_2 => 0x0d,
+            Instruction::Fdiv => 0x6e,
+            Instruction::Fload { .. } => 0x17,
+            Instruction::Fload_0 => 0x22,
+            Instruction::Fload_1 => 0x23,
+            Instruction::Fload_2 => 0x24,
+            Instruction::Fload_3 => 0x25,
+            Instruction::Fmul => 0x6a,
+            Instruction::Fneg => 0x76,
+            Instruction::Frem => 0x72,
+            Instruction::Freturn => 0xae,
+            Instruction::Fstore { .. } => 0x38,
+            Instruction::Fstore_0 => 0x43,
+            Instruction::Fstore_1 => 0x44,
+            Instruction::Fstore_2 => 0x45,
+            Instruction::Fstore_3 => 0x46,
+            Instruction::Fsub => 0x66,
+            Instruction::Getfield { .. } => 0xb4,
+            Instruction::Getstatic { .. } => 0xb2,
+            Instruction::Goto { .. } => 0xa7,
+            Instruction::Goto_w { .. } => 0xc8,
+            Instruction::I2b => 0x91,
+            Instruction::I2c => 0x92,
+            Instruction::I2d => 0x87,
+            Instruction::I2f => 0x86,
+            Instruction::I2l => 0x85,
+            Instruction::I2s => 0x93,
+            Instruction::Iadd => 0x60,
+            Instruction::Iaload => 0x2e,
+            Instruction::Iand => 0x7e,
+            Instruction::Iastore => 0x4f,
+            Instruction::Iconst_m1 => 0x02,
+            Instruction::Iconst_0 => 0x03,
+            Instruction::Iconst_1 => 0x04,
+            Instruction::Iconst_2 => 0x05,
+            Instruction::Iconst_3 => 0x06,
+            Instruction::Iconst_4 => 0x07,
+            Instruction::Iconst_5 => 0x08,
+            Instruction::Idiv => 0x6c,
+            Instruction::If_acmpeq { .. } => 0xa5,
+            Instruction::If_acmpne { .. } => 0xa6,
+            Instruction::If_icmpeq { .. } => 0x9f,
+            Instruction::If_icmpne { .. } => 0xa0,
+            Instruction::If_icmplt { .. } => 0xa1,
+            Instruction::If_icmpge { .. } => 0xa2,
+            Instruction::If_icmpgt { .. } => 0xa3,
+            Instruction::If_icmple { .. } => 0xa4,
+            Instruction::Ifeq { .. } => 0x99,
+            Instruction::Ifne { .. } => 0x9a,
+            Instruction::Iflt { .. } => 0x9b,
+            Instruction::Ifge { .. } => 0x9c,
+            Instruction::Ifgt { .. } => 0x9d,
+            Instruction::Ifle { .. } => 0x9e,
+            Instruction::Ifnonnull { .. } => 0xc7,
+            Instruction::Ifnull { .. } => 0xc6,
+            Instruction::Iinc { .. } => 0x84,
+            Instruction::Iload { .. } => 0x15,
+            Instruction::Iload_0 => 0x1a,
+            Instruction::Iload_1 => 0x1b,
+            Instruction::Iload_2 => 0x1c,
+            Instruction::Iload_3 => 0x1d,
+            Instruction::Imul => 0x68,
+            Instruction::Ineg => 0x74,
+            Instruction::Instanceof { .. } => 0xc1,
+            Instruction::Invokedynamic { .. } => 0xba,
+            Instruction::Invokeinterface { .. } => 0xb9,
+            Instruction::Invokespecial { .. } => 0xb7,
+            Instruction::Invokestatic { .. } => 0xb8,
+            Instruction::Invokevirtual { .. } => 0xb6,
+            Instruction::Ior => 0x80,
+            Instruction::Irem => 0x70,
+            Instruction::Ireturn => 0xac,
+            Instruction::Ishl => 0x78,
+            Instruction::Ishr => 0x7a,
+            Instruction::Istore { .. } => 0x36,
+            Instruction::Istore_0 => 0x3b,
+            Instruction::Istore_1 => 0x3c,
+            Instruction::Istore_2 => 0x3d,
+            Instruction::Istore_3 => 0x3e,
+            Instruction::Isub => 0x64,
+            Instruction::Iushr => 0x7c,
+            Instruction::Ixor => 0x82,
+            Instruction::Jsr { .. } => 0xa8,
+            Instruction::Jsr_w { .. } => 0xc9,
+            Instruction::L2d => 0x8a,
+            Instruction::L2f => 0x89,
+            Instruction::L2i => 0x88,
+            Instruction::Ladd => 0x61,
+            Instruction::Laload => 0x2f,
+            Instruction::Land => 0x7f,
+            Instruction::Lastore => 0x50,
+            Instruction::Lcmp => 0x94,
+            Instruction::Lconst_0 => 0x09,
+            Instruction::Lconst_1 => 0x0a,
+            Instruction::Ldc { .. } => 0x12,
+            Instruction::Ldc_w { .. } => 0x13,
+            Instruction::Ldc2_w { .. } => 0x14,
+            Instruction::Ldiv => 0x6d,
+            Instruction::Lload { .. } => 0x16,
+            Instruction::Lload_0 => 0x1e,
+            Instruction::Lload_1 => 0x1f,
+            Instruction::Lload_2 => 0x20,
+            Instruction::Lload_3 => 0x21,
+            Instruction::Lmul => 0x69,
+            Instruction::Lneg => 0x75,
+            Instruction::Lookupswitch { .. } => 0xab,
+            Instruction::Lor => 0x81,
+            Instruction::Lrem => 0x71,
+            Instruction::Lreturn => 0xad,
+            Instruction::Lshl => 0x79,
+            Instruction::Lshr => 0x7b,
+            Instruction::Lstore { .. } => 0x37,
+            Instruction::Lstore_0 => 0x3f,
+            Instruction::Lstore_1 => 0x40,
+            Instruction::Lstore_2 => 0x41,
+            Instruction::Lstore_3 => 0x42,
+            Instruction::Lsub => 0x65,
+            Instruction::Lushr => 0x7d,
+            Instruction::Lxor => 0x83,
+            Instruction::Monitorenter => 0xc2,
+            Instruction::Monitorexit => 0xc3,
+            Instruction::Multianewarray { .. } => 0xc5,
+            Instruction::New { .. } => 0xbb,
+            Instruction::Newarray { .. } => 0xbc,
+            Instruction::Nop => 0x00,
+            Instruction::Pop => 0x57,
+            Instruction::Pop2 => 0x58,
+            Instruction::Putfield { .. } => 0xb5,
+            Instruction::Putstatic { .. } => 0xb3,
+            Instruction::Ret { .. } => 0xa9,
+            Instruction::Return => 0xb1,
+            Instruction::Saload => 0x35,
+            Instruction::Sastore => 0x56,
+            Instruction::Sipush { .. } => 0x11,
+            Instruction::Swap => 0x5f,
+            Instruction::Tableswitch { .. } => 0xaa,
+            Instruction::Wide { .. } => 0xc4,
+        }
+    }
+
+    /// Appends the encoding of this instruction to the given bytecode. This is the inverse of
+    /// [Instruction::parse]. The instruction is assumed to start at `code.len()`, which matters
+    /// for jumps, whose operands are offsets relative to the instruction, and for the padding
+    /// of `tableswitch` and `lookupswitch`.
+    pub fn encode(&self, code: &mut Vec<u8>) -> Result<(), ClassWriterError> {
+        let address = code.len();
+        code.push(self.opcode());
+        match self {
+            Instruction::Aload { index }
+            | Instruction::Astore { index }
+            | Instruction::Dload { index }
+            | Instruction::Dstore { index }
+            | Instruction::Fload { index }
+            | Instruction::Fstore { index }
+            | Instruction::Iload { index }
+            | Instruction::Istore { index }
+            | Instruction::Lload { index }
+            | Instruction::Lstore { index }
+            | Instruction::Ret { index }
+            | Instruction::Ldc { index } => code.push(*index),
+            Instruction::Bipush { byte } => code.push(*byte),
+            Instruction::Anewarray { class }
+            | Instruction::Checkcast { class }
+            | Instruction::Instanceof { class }
+            | Instruction::New { class } => Self::write_u16(code, *class),
+            Instruction::Getfield { field }
+            | Instruction::Getstatic { field }
+            | Instruction::Putfield { field }
+            | Instruction::Putstatic { field } => Self::write_u16(code, *field),
+            Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokevirtual { method } => Self::write_u16(code, *method),
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                Self::write_u16(code, *index)
+            }
+            Instruction::Sipush { short } => code.extend_from_slice(&short.to_be_bytes()),
+            Instruction::Invokedynamic { call_site } => {
+                Self::write_u16(code, *call_site);
+                Self::write_u16(code, 0);
+            }
+            Instruction::Invokeinterface { method, count } => {
+                Self::write_u16(code, *method);
+                code.push(*count);
+                code.push(0);
+            }
+            Instruction::Iinc { index, constant } => {
+                code.push(*index);
+                code.extend_from_slice(&constant.to_be_bytes());
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                Self::write_u16(code, *class);
+                code.push(*dimensions);
+            }
+            Instruction::Newarray { array_type } => code.push(array_type.type_code()),
+            Instruction::Goto { jump_address }
+            | Instruction::If_acmpeq { jump_address }
+            | Instruction::If_acmpne { jump_address }
+            | Instruction::If_icmpeq { jump_address }
+            | Instruction::If_icmpne { jump_address }
+            | Instruction::If_icmplt { jump_address }
+            | Instruction::If_icmpge { jump_address }
+            | Instruction::If_icmpgt { jump_address }
+            | Instruction::If_icmple { jump_address }
+            | Instruction::Ifeq { jump_address }
+            | Instruction::Ifne { jump_address }
+            | Instruction::Iflt { jump_address }
+            | Instruction::Ifge { jump_address }
+            | Instruction::Ifgt { jump_address }
+            | Instruction::Ifle { jump_address }
+            | Instruction::Ifnonnull { jump_address }
+            | Instruction::Ifnull { jump_address }
+            | Instruction::Jsr { jump_address } => {
+                let offset = i16::try_from(*jump_address as i64 - address as i64)
+                    .map_err(|_| ClassWriterError::JumpOffsetOutOfRange(address))?;
+                code.extend_from_slice(&offset.to_be_bytes());
+            }
+            Instruction::Goto_w { jump_address } | Instruction::Jsr_w { jump_address } => {
+                Self::write_wide_offset(code, address, *jump_address)
+            }
+            Instruction::Tableswitch {
+                default,
+                low,
+                high,
+                jump_addresses,
+            } => {
+                let keys_count = i64::from(*high) - i64::from(*low) + 1;
+                if keys_count != jump_addresses.len() as i64 {
+                    return Err(ClassWriterError::InvalidSwitchRange(address));
+                }
+                code.resize(Self::skip_switch_padding(code.len()), 0);
+                Self::write_wide_offset(code, address, *default);
+                code.extend_from_slice(&low.to_be_bytes());
+                code.extend_from_slice(&high.to_be_bytes());
+                for jump_address in jump_addresses {
+                    Self::write_wide_offset(code, address, *jump_address);
+                }
+            }
+            Instruction::Lookupswitch {
+                default,
+                match_pairs,
+            } => {
+                code.resize(Self::skip_switch_padding(code.len()), 0);
+                Self::write_wide_offset(code, address, *default);
+                code.extend_from_slice(&(match_pairs.len() as i32).to_be_bytes());
+                for (key, jump_address) in match_pairs {
+                    code.extend_from_slice(&key.to_be_bytes());
+                    Self::write_wide_offset(code, address, *jump_address);
+                }
+            }
+            Instruction::Wide { instruction } => {
+                code.push(instruction.opcode());
+                match instruction {
+                    WideInstruction::Iinc { index, constant } => {
+                        Self::write_u16(code, *index);
+                        code.extend_from_slice(&constant.to_be_bytes());
+                    }
+                    _ => Self::write_u16(code, instruction.local_variable_index()),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the number of bytes taken by this instruction when encoded at the given address
+    pub fn encoded_len(&self, address: usize) -> usize {
+        let switch_padding = Self::skip_switch_padding(address + 1) - (address + 1);
+        match self {
+            Instruction::Tableswitch { jump_addresses, .. } => {
+                1 + switch_padding + 12 + 4 * jump_addresses.len()
+            }
+            Instruction::Lookupswitch { match_pairs, .. } => {
+                1 + switch_padding + 8 + 8 * match_pairs.len()
+            }
+            Instruction::Wide {
+                instruction: WideInstruction::Iinc { .. },
+            } => 6,
+            Instruction::Wide { .. } => 4,
+            Instruction::Goto_w { .. }
+            | Instruction::Jsr_w { .. }
+            | Instruction::Invokedynamic { .. }
+            | Instruction::Invokeinterface { .. } => 5,
+            Instruction::Multianewarray { .. } => 4,
+            Instruction::Aload { .. }
+            | Instruction::Astore { .. }
+            | Instruction::Dload { .. }
+            | Instruction::Dstore { .. }
+            | Instruction::Fload { .. }
+            | Instruction::Fstore { .. }
+            | Instruction::Iload { .. }
+            | Instruction::Istore { .. }
+            | Instruction::Lload { .. }
+            | Instruction::Lstore { .. }
+            | Instruction::Ret { .. }
+            | Instruction::Ldc { .. }
+            | Instruction::Bipush { .. }
+            | Instruction::Newarray { .. } => 2,
+            Instruction::Anewarray { .. }
+            | Instruction::Checkcast { .. }
+            | Instruction::Instanceof { .. }
+            | Instruction::New { .. }
+            | Instruction::Getfield { .. }
+            | Instruction::Getstatic { .. }
+            | Instruction::Putfield { .. }
+            | Instruction::Putstatic { .. }
+            | Instruction::Invokespecial { .. }
+            | Instruction::Invokestatic { .. }
+            | Instruction::Invokevirtual { .. }
+            | Instruction::Ldc_w { .. }
+            | Instruction::Ldc2_w { .. }
+            | Instruction::Sipush { .. }
+            | Instruction::Iinc { .. }
+            | Instruction::Goto { .. }
+            | Instruction::If_acmpeq { .. }
+            | Instruction::If_acmpne { .. }
+            | Instruction::If_icmpeq { .. }
+            | Instruction::If_icmpne { .. }
+            | Instruction::If_icmplt { .. }
+            | Instruction::If_icmpge { .. }
+            | Instruction::If_icmpgt { .. }
+            | Instruction::If_icmple { .. }
+            | Instruction::Ifeq { .. }
+            | Instruction::Ifne { .. }
+            | Instruction::Iflt { .. }
+            | Instruction::Ifge { .. }
+            | Instruction::Ifgt { .. }
+            | Instruction::Ifle { .. }
+            | Instruction::Ifnonnull { .. }
+            | Instruction::Ifnull { .. }
+            | Instruction::Jsr { .. } => 3,
+            _ => 1,
+        }
+    }
+
+    fn write_u16(code: &mut Vec<u8>, value: u16) {
+        code.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_wide_offset(code: &mut Vec<u8>, instruction_address: usize, jump_address: u16) {
+        let offset = jump_address as i32 - instruction_address as i32;
+        code.extend_from_slice(&offset.to_be_bytes());
+    }
+
+    /// Returns the addresses this instruction can jump to, not including the next instruction
+    /// reached by simply falling through
+    pub fn jump_targets(&self) -> Vec<u16> {
+        match self {
+            Instruction::Goto { jump_address }
+            | Instruction::Goto_w { jump_address }
+            | Instruction::If_acmpeq { jump_address }
+            | Instruction::If_acmpne { jump_address }
+            | Instruction::If_icmpeq { jump_address }
+            | Instruction::If_icmpne { jump_address }
+            | Instruction::If_icmplt { jump_address }
+            | Instruction::If_icmpge { jump_address }
+            | Instruction::If_icmpgt { jump_address }
+            | Instruction::If_icmple { jump_address }
+            | Instruction::Ifeq { jump_address }
+            | Instruction::Ifne { jump_address }
+            | Instruction::Iflt { jump_address }
+            | Instruction::Ifge { jump_address }
+            | Instruction::Ifgt { jump_address }
+            | Instruction::Ifle { jump_address }
+            | Instruction::Ifnonnull { jump_address }
+            | Instruction::Ifnull { jump_address }
+            | Instruction::Jsr { jump_address }
+            | Instruction::Jsr_w { jump_address } => vec![*jump_address],
+            Instruction::Tableswitch {
+                default,
+                jump_addresses,
+                ..
+            } => std::iter::once(*default)
+                .chain(jump_addresses.iter().copied())
+                .collect(),
+            Instruction::Lookupswitch {
+                default,
+                match_pairs,
+            } => std::iter::once(*default)
+                .chain(match_pairs.iter().map(|(_, jump_address)| *jump_address))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns false for instructions after which the execution never continues with the
+    /// following instruction, i.e. unconditional jumps, switches, returns and `athrow`.
+    /// Note that `jsr` is considered to fall through, since the subroutine will return to
+    /// the following instruction.
+    pub fn can_fall_through(&self) -> bool {
+        !matches!(
+            self,
+            Instruction::Goto { .. }
+                | Instruction::Goto_w { .. }
+                | Instruction::Tableswitch { .. }
+                | Instruction::Lookupswitch { .. }
+                | Instruction::Ret { .. }
+                | Instruction::Wide {
+                    instruction: WideInstruction::Ret { .. }
+                }
+                | Instruction::Athrow
+                | Instruction::Areturn
+                | Instruction::Dreturn
+                | Instruction::Freturn
+                | Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Return
+        )
+    }
+
+    /// Returns the index of the constant pool entry this instruction refers to, if it has one
+    pub fn constant_pool_index(&self) -> Option<u16> {
+        match self {
+            Instruction::Anewarray { class: index }
+            | Instruction::Checkcast { class: index }
+            | Instruction::Instanceof { class: index }
+            | Instruction::New { class: index }
+            | Instruction::Multianewarray { class: index, .. }
+            | Instruction::Getfield { field: index }
+            | Instruction::Getstatic { field: index }
+            | Instruction::Putfield { field: index }
+            | Instruction::Putstatic { field: index }
+            | Instruction::Invokespecial { method: index }
+            | Instruction::Invokestatic { method: index }
+            | Instruction::Invokevirtual { method: index }
+            | Instruction::Invokeinterface { method: index, .. }
+            | Instruction::Invokedynamic { call_site: index }
+            | Instruction::Ldc_w { index }
+            | Instruction::Ldc2_w { index } => Some(*index),
+            Instruction::Ldc { index } => Some(u16::from(*index)),
+            _ => None,
+        }
+    }
+
+    /// Returns how this instruction accesses a local variable, if it does
+    pub fn local_variable_access(&self) -> Option<LocalVariableAccess> {
+        use LocalKind::*;
+        use LocalVariableAccess::{Increment, Load, Ret, Store};
+        let access = match self {
+            Instruction::Iload { index } => Load(Int, *index as u16),
+            Instruction::Lload { index } => Load(Long, *index as u16),
+            Instruction::Fload { index } => Load(Float, *index as u16),
+            Instruction::Dload { index } => Load(Double, *index as u16),
+            Instruction::Aload { index } => Load(Reference, *index as u16),
+            Instruction::Iload_0 => Load(Int, 0),
+            Instruction::Iload_1 => Load(Int, 1),
+            Instruction::Iload_2 => Load(Int, 2),
+            Instruction::Iload_3 => Load(Int, 3),
+            Instruction::Lload_0 => Load(Long, 0),
+            Instruction::Lload_1 => Load(Long, 1),
+            Instruction::Lload_2 => Load(Long, 2),
+            Instruction::Lload_3 => Load(Long, 3),
+            Instruction::Fload_0 => Load(Float, 0),
+            Instruction::Fload_1 => Load(Float, 1),
+            Instruction::Fload_2 => Load(Float, 2),
+            Instruction::Fload_3 => Load(Float, 3),
+            Instruction::Dload_0 => Load(Double, 0),
+            Instruction::Dload_1 => Load(Double, 1),
+            Instruction::Dload_2 => Load(Double, 2),
+            Instruction::Dload_3 => Load(Double, 3),
+            Instruction::Aload_0 => Load(Reference, 0),
+            Instruction::Aload_1 => Load(Reference, 1),
+            Instruction::Aload_2 => Load(Reference, 2),
+            Instruction::Aload_3 => Load(Reference, 3),
+            Instruction::Istore { index } => Store(Int, *index as u16),
+            Instruction::Lstore { index } => Store(Long, *index as u16),
+            Instruction::Fstore { index } => Store(Float, *index as u16),
+            Instruction::Dstore { index } => Store(Double, *index as u16),
+            Instruction::Astore { index } => Store(Reference, *index as u16),
+            Instruction::Istore_0 => Store(Int, 0),
+            Instruction::Istore_1 => Store(Int, 1),
+            Instruction::Istore_2 => Store(Int, 2),
+            Instruction::Istore_3 => Store(Int, 3),
+            Instruction::Lstore_0 => Store(Long, 0),
+            Instruction::Lstore_1 => Store(Long, 1),
+            Instruction::Lstore_2 => Store(Long, 2),
+            Instruction::Lstore_3 => Store(Long, 3),
+            Instruction::Fstore_0 => Store(Float, 0),
+            Instruction::Fstore_1 => Store(Float, 1),
+            Instruction::Fstore_2 => Store(Float, 2),
+            Instruction::Fstore_3 => Store(Float, 3),
+            Instruction::Dstore_0 => Store(Double, 0),
+            Instruction::Dstore_1 => Store(Double, 1),
+            Instruction::Dstore_2 => Store(Double, 2),
+            Instruction::Dstore_3 => Store(Double, 3),
+            Instruction::Astore_0 => Store(Reference, 0),
+            Instruction::Astore_1 => Store(Reference, 1),
+            Instruction::Astore_2 => Store(Reference, 2),
+            Instruction::Astore_3 => Store(Reference, 3),
+            Instruction::Iinc { index, .. } => Increment(*index as u16),
+            Instruction::Ret { index } => Ret(*index as u16),
+            Instruction::Wide { instruction } => match *instruction {
+                WideInstruction::Iload { index } => Load(Int, index),
+                WideInstruction::Lload { index } => Load(Long, index),
+                WideInstruction::Fload { index } => Load(Float, index),
+                WideInstruction::Dload { index } => Load(Double, index),
+                WideInstruction::Aload { index } => Load(Reference, index),
+                WideInstruction::Istore { index } => Store(Int, index),
+                WideInstruction::Lstore { index } => Store(Long, index),
+                WideInstruction::Fstore { index } => Store(Float, index),
+                WideInstruction::Dstore { index } => Store(Double, index),
+                WideInstruction::Astore { index } => Store(Reference, index),
+                WideInstruction::Iinc { index, .. } => Increment(index),
+                WideInstruction::Ret { index } => Ret(index),
+            },
+            _ => return None,
+        };
+        Some(access)
+    }
+
+    /// Returns the local variable slots read or written by this instruction, if any.
+    /// Long and double variables take up two slots.
+    pub fn local_variable_slots(&self) -> Option<Range<u16>> {
+        self.local_variable_access().map(|access| access.slots())
+    }
+
+    /// Computes how many stack slots this instruction pops and pushes. The constant pool is
+    /// needed to resolve the types of fields and methods referred to by the instruction.
+    pub fn stack_effect(&self, constants: &ConstantPool) -> Result<StackEffect, ClassReaderError> {
+        let (popped, pushed) = match self {
+            Instruction::Nop
+            | Instruction::Goto { .. }
+            | Instruction::Goto_w { .. }
+            | Instruction::Iinc { .. }
+            | Instruction::Ret { .. }
+            | Instruction::Return => (0, 0),
+            Instruction::Aconst_null
+            | Instruction::Aload { .. }
+            | Instruction::Aload_0
+            | Instruction::Aload_1
+            | Instruction::Aload_2
+            | Instruction::Aload_3
+            | Instruction::Bipush { .. }
+            | Instruction::Fconst_0
+            | Instruction::Fconst_1
+            | Instruction::Fconst_2
+            | Instruction::Fload { .. }
+            | Instruction::Fload_0
+            | Instruction::Fload_1
+            | Instruction::Fload_2
+            | Instruction::Fload_3
+            | Instruction::Iconst_m1
+            | Instruction::Iconst_0
+            | Instruction::Iconst_1
+            | Instruction::Iconst_2
+            | Instruction::Iconst_3
+            | Instruction::Iconst_4
+            | Instruction::Iconst_5
+            | Instruction::Iload { .. }
+            | Instruction::Iload_0
+            | Instruction::Iload_1
+            | Instruction::Iload_2
+            | Instruction::Iload_3
+            | Instruction::Jsr { .. }
+            | Instruction::Jsr_w { .. }
+            | Instruction::Ldc { .. }
+            | Instruction::Ldc_w { .. }
+            | Instruction::New { .. }
+            | Instruction::Sipush { .. } => (0, 1),
+            Instruction::Dconst_0
+            | Instruction::Dconst_1
+            | Instruction::Dload { .. }
+            | Instruction::Dload_0
+            | Instruction::Dload_1
+            | Instruction::Dload_2
+            | Instruction::Dload_3
+            | Instruction::Lconst_0
+            | Instruction::Lconst_1
+            | Instruction::Ldc2_w { .. }
+            | Instruction::Lload { .. }
+            | Instruction::Lload_0
+            | Instruction::Lload_1
+            | Instruction::Lload_2
+            | Instruction::Lload_3 => (0, 2),
+            Instruction::Anewarray { .. }
+            | Instruction::Arraylength
+            | Instruction::Checkcast { .. }
+            | Instruction::F2i
+            | Instruction::Fneg
+            | Instruction::I2b
+            | Instruction::I2c
+            | Instruction::I2f
+            | Instruction::I2s
+            | Instruction::Ineg
+            | Instruction::Instanceof { .. }
+            | Instruction::Newarray { .. } => (1, 1),
+            Instruction::F2d | Instruction::F2l | Instruction::I2d | Instruction::I2l => (1, 2),
+            Instruction::Areturn
+            | Instruction::Astore { .. }
+            | Instruction::Astore_0
+            | Instruction::Astore_1
+            | Instruction::Astore_2
+            | Instruction::Astore_3
+            | Instruction::Athrow
+            | Instruction::Freturn
+            | Instruction::Fstore { .. }
+            | Instruction::Fstore_0
+            | Instruction::Fstore_1
+            | Instruction::Fstore_2
+            | Instruction::Fstore_3
+            | Instruction::Ifeq { .. }
+            | Instruction::Ifne { .. }
+            | Instruction::Iflt { .. }
+            | Instruction::Ifge { .. }
+            | Instruction::Ifgt { .. }
+            | Instruction::Ifle { .. }
+            | Instruction::Ifnonnull { .. }
+            | Instruction::Ifnull { .. }
+            | Instruction::Ireturn
+            | Instruction::Istore { .. }
+            | Instruction::Istore_0
+            | Instruction::Istore_1
+            | Instruction::Istore_2
+            | Instruction::Istore_3
+            | Instruction::Lookupswitch { .. }
+            | Instruction::Monitorenter
+            | Instruction::Monitorexit
+            | Instruction::Pop
+            | Instruction::Tableswitch { .. } => (1, 0),
+            Instruction::Aaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::D2f
+            | Instruction::D2i
+            | Instruction::Faload
+            | Instruction::Fadd
+            | Instruction::Fcmpg
+            | Instruction::Fcmpl
+            | Instruction::Fdiv
+            | Instruction::Fmul
+            | Instruction::Frem
+            | Instruction::Fsub
+            | Instruction::Iadd
+            | Instruction::Iaload
+            | Instruction::Iand
+            | Instruction::Idiv
+            | Instruction::Imul
+            | Instruction::Ior
+            | Instruction::Irem
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Isub
+            | Instruction::Iushr
+            | Instruction::Ixor
+            | Instruction::L2f
+            | Instruction::L2i
+            | Instruction::Saload => (2, 1),
+            Instruction::D2l
+            | Instruction::Daload
+            | Instruction::Dneg
+            | Instruction::L2d
+            | Instruction::Laload
+            | Instruction::Lneg
+            | Instruction::Swap => (2, 2),
+            Instruction::Dreturn
+            | Instruction::Dstore { .. }
+            | Instruction::Dstore_0
+            | Instruction::Dstore_1
+            | Instruction::Dstore_2
+            | Instruction::Dstore_3
+            | Instruction::If_acmpeq { .. }
+            | Instruction::If_acmpne { .. }
+            | Instruction::If_icmpeq { .. }
+            | Instruction::If_icmpne { .. }
+            | Instruction::If_icmplt { .. }
+            | Instruction::If_icmpge { .. }
+            | Instruction::If_icmpgt { .. }
+            | Instruction::If_icmple { .. }
+            | Instruction::Lreturn
+            | Instruction::Lstore { .. }
+            | Instruction::Lstore_0
+            | Instruction::Lstore_1
+            | Instruction::Lstore_2
+            | Instruction::Lstore_3
+            | Instruction::Pop2 => (2, 0),
+            Instruction::Aastore
+            | Instruction::Bastore
+            | Instruction::Castore
+            | Instruction::Fastore
+            | Instruction::Iastore
+            | Instruction::Sastore => (3, 0),
+            Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => (3, 2),
+            Instruction::Dcmpg | Instruction::Dcmpl | Instruction::Lcmp => (4, 1),
+            Instruction::Dadd
+            | Instruction::Ddiv
+            | Instruction::Dmul
+            | Instruction::Drem
+            | Instruction::Dsub
+            | Instruction::Ladd
+            | Instruction::Land
+            | Instruction::Ldiv
+            | Instruction::Lmul
+            | Instruction::Lor
+            | Instruction::Lrem
+            | Instruction::Lsub
+            | Instruction::Lxor => (4, 2),
+            Instruction::Dastore | Instruction::Lastore => (4, 0),
+            Instruction::Dup => (1, 2),
+            Instruction::Dup_x1 => (2, 3),
+            Instruction::Dup_x2 => (3, 4),
+            Instruction::Dup2 => (2, 4),
+            Instruction::Dup2_x1 => (3, 5),
+            Instruction::Dup2_x2 => (4, 6),
+            Instruction::Multianewarray { dimensions, .. } => (*dimensions as u16, 1),
+            Instruction::Getstatic { field } => (0, Self::field_slots(constants, *field)?),
+            Instruction::Getfield { field } => (1, Self::field_slots(constants, *field)?),
+            Instruction::Putstatic { field } => (Self::field_slots(constants, *field)?, 0),
+            Instruction::Putfield { field } => (1 + Self::field_slots(constants, *field)?, 0),
+            Instruction::Invokestatic { method } => {
+                let descriptor = Self::method_descriptor(constants, *method)?;
+                (descriptor.arguments_slots(), descriptor.return_slots())
+            }
+            Instruction::Invokespecial { method }
+            | Instruction::Invokevirtual { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                let descriptor = Self::method_descriptor(constants, *method)?;
+                (1 + descriptor.arguments_slots(), descriptor.return_slots())
+            }
+            Instruction::Invokedynamic { call_site } => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "unsupported invokedynamic call site {call_site}"
+                )))
+            }
+            Instruction::Wide { instruction } => match instruction {
+                WideInstruction::Iload { .. }
+                | WideInstruction::Fload { .. }
+                | WideInstruction::Aload { .. } => (0, 1),
+                WideInstruction::Lload { .. } | WideInstruction::Dload { .. } => (0, 2),
+                WideInstruction::Istore { .. }
+                | WideInstruction::Fstore { .. }
+                | WideInstruction::Astore { .. } => (1, 0),
+                WideInstruction::Lstore { .. } | WideInstruction::Dstore { .. } => (2, 0),
+                WideInstruction::Ret { .. } | WideInstruction::Iinc { .. } => (0, 0),
+            },
+        };
+        Ok(StackEffect::new(popped, pushed))
+    }
+
+    fn field_slots(constants: &ConstantPool, index: u16) -> Result<u16, ClassReaderError> {
+        let reference = constants.member_reference(index)?;
+        Ok(FieldType::parse(&reference.type_descriptor)?.slots())
+    }
+
+    fn method_descriptor(
+        constants: &ConstantPool,
+        index: u16,
+    ) -> Result<MethodDescriptor, ClassReaderError> {
+        let reference = constants.member_reference(index)?;
+        MethodDescriptor::parse(&reference.type_descriptor)
+    }
+}
+
+impl WideInstruction {
+    /// The name of the instruction, as printed by `javap`
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            WideInstruction::Iload { .. } => "iload_w",
+            WideInstruction::Fload { .. } => "fload_w",
+            WideInstruction::Aload { .. } => "aload_w",
+            WideInstruction::Lload { .. } => "lload_w",
+            WideInstruction::Dload { .. } => "dload_w",
+            WideInstruction::Istore { .. } => "istore_w",
+            WideInstruction::Fstore { .. } => "fstore_w",
+            WideInstruction::Astore { .. } => "astore_w",
+            WideInstruction::Lstore { .. } => "lstore_w",
+            WideInstruction::Dstore { .. } => "dstore_w",
+            WideInstruction::Ret { .. } => "ret_w",
+            WideInstruction::Iinc { .. } => "iinc_w",
+        }
+    }
+
+    /// The byte that identifies the instruction modified by the `wide` prefix
+    pub fn opcode(&self) -> u8 {
+        match self {
+            WideInstruction::Iload { .. } => 0x15,
+            WideInstruction::Fload { .. } => 0x17,
+            WideInstruction::Aload { .. } => 0x19,
+            WideInstruction::Lload { .. } => 0x16,
+            WideInstruction::Dload { .. } => 0x18,
+            WideInstruction::Istore { .. } => 0x36,
+            WideInstruction::Fstore { .. } => 0x38,
+            WideInstruction::Astore { .. } => 0x3a,
+            WideInstruction::Lstore { .. } => 0x37,
+            WideInstruction::Dstore { .. } => 0x39,
+            WideInstruction::Ret { .. } => 0xa9,
+            WideInstruction::Iinc { .. } => 0x84,
+        }
+    }
+
+    /// The index of the local variable accessed by this instruction
+    pub fn local_variable_index(&self) -> u16 {
+        match self {
+            WideInstruction::Iload { index }
+            | WideInstruction::Fload { index }
+            | WideInstruction::Aload { index }
+            | WideInstruction::Lload { index }
+            | WideInstruction::Dload { index }
+            | WideInstruction::Istore { index }
+            | WideInstruction::Fstore { index }
+            | WideInstruction::Astore { index }
+            | WideInstruction::Lstore { index }
+            | WideInstruction::Dstore { index }
+            | WideInstruction::Ret { index }
+            | WideInstruction::Iinc { index, .. } => *index,
+        }
+    }
+}
+
+impl NewArrayType {
+    /// The name of the type of the elements, i.e. `int`
+    pub fn element_type_name(&self) -> &'static str {
+        match self {
+            NewArrayType::Boolean => "boolean",
+            NewArrayType::Char => "char",
+            NewArrayType::Float => "float",
+            NewArrayType::Double => "double",
+            NewArrayType::Byte => "byte",
+            NewArrayType::Short => "short",
+            NewArrayType::Int => "int",
+            NewArrayType::Long => "long",
+        }
+    }
+
+    /// The code used in the bytecode to identify the type
+    pub fn type_code(&self) -> u8 {
+        match self {
+            NewArrayType::Boolean => 4,
+            NewArrayType::Char => 5,
+            NewArrayType::Float => 6,
+            NewArrayType::Double => 7,
+            NewArrayType::Byte => 8,
+            NewArrayType::Short => 9,
+            NewArrayType::Int => 10,
+            NewArrayType::Long => 11,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_writer_error::ClassWriterError,
+        instruction::{Instruction, NewArrayType, WideInstruction},
+    };
+
+    #[test]
+    fn encoding_is_the_inverse_of_parsing() {
+        let instructions = vec![
+            Instruction::Nop,
+            Instruction::Aload { index: 7 },
+            Instruction::Bipush { byte: 0xfe },
+            Instruction::Sipush { short: -300 },
+            Instruction::Iinc {
+                index: 3,
+                constant: -2,
+            },
+            Instruction::Ldc { index: 4 },
+            Instruction::Ldc2_w { index: 0x1234 },
+            Instruction::Getfield { field: 12 },
+            Instruction::Invokeinterface {
+                method: 9,
+                count: 2,
+            },
+            Instruction::Multianewarray {
+                class: 3,
+                dimensions: 2,
+            },
+            Instruction::Newarray {
+                array_type: NewArrayType::Long,
+            },
+            Instruction::Wide {
+                instruction: WideInstruction::Dstore { index: 1000 },
+            },
+            Instruction::Wide {
+                instruction: WideInstruction::Iinc {
+                    index: 1000,
+                    constant: -1000,
+                },
+            },
+            Instruction::Ifeq { jump_address: 0 },
+            Instruction::Goto_w { jump_address: 1 },
+            Instruction::Tableswitch {
+                default: 0,
+                low: -1,
+                high: 1,
+                jump_addresses: vec![1, 3, 5],
+            },
+            Instruction::Lookupswitch {
+                default: 5,
+                match_pairs: vec![(-10, 0), (1_000_000, 1)],
+            },
+            Instruction::Return,
+        ];
+
+        let mut code = Vec::new();
+        let mut expected = Vec::new();
+        for instruction in instructions {
+            let address = code.len();
+            instruction.encode(&mut code).unwrap();
+            assert_eq!(code.len() - address, instruction.encoded_len(address));
+            expected.push((address, instruction));
+        }
+
+        assert_eq!(expected, Instruction::parse_instructions(&code).unwrap());
+    }
+
+    #[test]
+    fn cannot_encode_jump_too_far_away() {
+        let mut code = Vec::new();
+        assert_eq!(
+            Err(ClassWriterError::JumpOffsetOutOfRange(0)),
+            Instruction::Goto {
+                jump_address: 40000
+            }
+            .encode(&mut code)
+        );
+    }
+
+    #[test]
+    fn cannot_encode_tableswitch_with_keys_not_matching_the_targets() {
+        for (low, high) in [(0, 2), (1, 0), (i32::MAX, i32::MIN)] {
+            let mut code = vec![0];
+            assert_eq!(
+                Err(ClassWriterError::InvalidSwitchRange(1)),
+                Instruction::Tableswitch {
+                    default: 0,
+                    low,
+                    high,
+                    jump_addresses: vec![0, 0],
+                }
+                .encode(&mut code)
+            );
+        }
+    }
+
+    #[test]
+    fn mnemonics_match_javap() {
+        assert_eq!("aconst_null", Instruction::Aconst_null.mnemonic());
+        assert_eq!("goto_w", Instruction::Goto_w { jump_address: 0 }.mnemonic());
+        assert_eq!("dup2_x1", Instruction::Dup2_x1.mnemonic());
+        assert_eq!(
+            "iinc_w",
+            Instruction::Wide {
+                instruction: WideInstruction::Iinc {
+                    index: 300,
+                    constant: 1
+                }
+            }
+            .mnemonic()
+        );
+    }
+
+    #[test]
+    fn finds_the_constant_pool_index_of_the_operand() {
+        assert_eq!(
+            Some(7),
+            Instruction::Invokevirtual { method: 7 }.constant_pool_index()
+        );
+        assert_eq!(
+            Some(200),
+            Instruction::Ldc { index: 200 }.constant_pool_index()
+        );
+        assert_eq!(
+            Some(3),
+            Instruction::Multianewarray {
+                class: 3,
+                dimensions: 2
+            }
+            .constant_pool_index()
+        );
+        assert_eq!(None, Instruction::Aload { index: 3 }.constant_pool_index());
+    }
 }
@@ -1,25 +1,59 @@
-mod attribute;
+pub mod analyzer;
+pub mod annotation;
+pub mod assembler;
+pub mod assembler_error;
+pub mod attribute;
+pub mod basic_interpreter;
+mod bit_set;
 mod buffer;
 pub mod class_access_flags;
+pub mod class_builder;
 pub mod class_file;
 pub mod class_file_field;
 pub mod class_file_method;
 pub mod class_file_version;
+pub mod class_file_view;
+pub mod class_hierarchy;
 mod class_reader;
 pub mod class_reader_error;
+pub mod class_transform;
+pub mod class_visitor;
+mod class_writer;
+pub mod class_writer_error;
+pub mod code_builder;
 pub mod constant_pool;
+pub mod control_flow_graph;
+pub mod decompiler;
+pub mod diagnostic;
+pub mod disassembler;
+pub mod dominator_tree;
 pub mod exception_table;
 pub mod field_flags;
 pub mod field_type;
+#[cfg(feature = "serde")]
+mod flags_serde;
+pub mod format_check;
 pub mod instruction;
 pub mod line_number;
 pub mod line_number_table;
+pub mod liveness;
+pub mod loops;
 pub mod method_descriptor;
 pub mod method_flags;
 pub mod program_counter;
+pub mod reaching_definitions;
+pub mod read_limits;
+pub mod read_options;
+mod slot_frame;
+pub mod span;
+pub mod ssa;
+pub mod stack_map_table;
 pub mod type_conversion;
+pub mod verifier;
 
 #[cfg(feature = "wasm")]
 pub mod wasm_wrappers;
 
-pub use class_reader::read_buffer;
+pub use class_file_view::view_buffer;
+pub use class_reader::{read_buffer, read_buffer_lenient, read_buffer_with_options, visit_buffer};
+pub use class_writer::write_class;
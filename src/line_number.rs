@@ -5,7 +5,8 @@ use std::{
 
 /// Line number in the source code
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct LineNumber(pub u16);
 
 impl Display for LineNumber {
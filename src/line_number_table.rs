@@ -8,10 +8,11 @@ use crate::{line_number::LineNumber, program_counter::ProgramCounter};
 /// Entries are sorted by program counter. A table with two entries, the first starting at 0 and
 /// the second at 3, means that the first three instructions in the bytecode correspond to line 1
 /// and the rest to line 2.
-#[derive(Debug, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct LineNumberTable {
-    #[cfg_attr(feature = "wasm", serde(rename = "line_number_table"))]
+    #[cfg_attr(feature = "serde", serde(rename = "line_number_table"))]
     entries: Vec<LineNumberTableEntry>,
 }
 
@@ -22,21 +23,28 @@ impl LineNumberTable {
         }
     }
 
-    pub fn lookup_pc(&self, pc: ProgramCounter) -> LineNumber {
+    pub fn entries(&self) -> &[LineNumberTableEntry] {
+        &self.entries
+    }
+
+    /// Returns the line of the given program counter, or None if it comes before the
+    /// first entry of the table
+    pub fn lookup_pc(&self, pc: ProgramCounter) -> Option<LineNumber> {
         let best_matching_entry_index = match self
             .entries
             .binary_search_by(|e| e.program_counter.cmp(&pc))
         {
             Ok(index) => index,
-            Err(index) => index - 1,
+            Err(index) => index.checked_sub(1)?,
         };
-        self.entries[best_matching_entry_index].line_number
+        Some(self.entries[best_matching_entry_index].line_number)
     }
 }
 
 /// Entries of a [LineNumberTable]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct LineNumberTableEntry {
     pub program_counter: ProgramCounter,
     pub line_number: LineNumber,
@@ -44,7 +52,7 @@ pub struct LineNumberTableEntry {
 
 impl PartialOrd for LineNumberTableEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.program_counter.partial_cmp(&other.program_counter)
+        Some(self.cmp(other))
     }
 }
 
@@ -79,10 +87,24 @@ mod tests {
             LineNumberTableEntry::new(ProgramCounter(20), LineNumber(6)),
         ]);
 
-        assert_eq!(LineNumber(4), table.lookup_pc(ProgramCounter(0)));
-        assert_eq!(LineNumber(4), table.lookup_pc(ProgramCounter(11)));
-        assert_eq!(LineNumber(5), table.lookup_pc(ProgramCounter(12)));
-        assert_eq!(LineNumber(6), table.lookup_pc(ProgramCounter(20)));
-        assert_eq!(LineNumber(6), table.lookup_pc(ProgramCounter(21)));
+        assert_eq!(Some(LineNumber(4)), table.lookup_pc(ProgramCounter(0)));
+        assert_eq!(Some(LineNumber(4)), table.lookup_pc(ProgramCounter(11)));
+        assert_eq!(Some(LineNumber(5)), table.lookup_pc(ProgramCounter(12)));
+        assert_eq!(Some(LineNumber(6)), table.lookup_pc(ProgramCounter(20)));
+        assert_eq!(Some(LineNumber(6)), table.lookup_pc(ProgramCounter(21)));
+    }
+
+    #[test]
+    fn lookup_before_the_first_entry_has_no_line_number() {
+        let table = LineNumberTable::new(vec![LineNumberTableEntry::new(
+            ProgramCounter(3),
+            LineNumber(4),
+        )]);
+
+        assert_eq!(None, table.lookup_pc(ProgramCounter(0)));
+        assert_eq!(
+            None,
+            LineNumberTable::new(vec![]).lookup_pc(ProgramCounter(0))
+        );
     }
 }
@@ -0,0 +1,239 @@
+use std::ops::Range;
+
+use crate::{
+    bit_set::BitSet,
+    class_file_method::ClassFileMethodCode,
+    class_reader_error::ClassReaderError,
+    control_flow_graph::instruction_successors,
+    instruction::{Instruction, LocalVariableAccess},
+    program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// Returns the local variable slots read and written by an instruction. Long and double values
+/// take two slots, which are both read or written.
+pub(crate) fn slots_used_and_defined(instruction: &Instruction) -> (Range<u16>, Range<u16>) {
+    let empty = 0..0;
+    match instruction.local_variable_access() {
+        Some(LocalVariableAccess::Load(kind, index)) => {
+            (index..index.saturating_add(kind.slots()), empty)
+        }
+        Some(LocalVariableAccess::Store(kind, index)) => {
+            (empty, index..index.saturating_add(kind.slots()))
+        }
+        Some(LocalVariableAccess::Increment(index)) => (
+            index..index.saturating_add(1),
+            index..index.saturating_add(1),
+        ),
+        Some(LocalVariableAccess::Ret(index)) => (index..index.saturating_add(1), empty),
+        None => (empty.clone(), empty),
+    }
+}
+
+/// The local variable slots that are live before and after each instruction of a method, i.e.
+/// whose current value may be read later. Computed only from the bytecode, so it does not
+/// need a `LocalVariableTable`.
+///
+/// Exception handlers are considered reachable from every instruction they cover. Subroutines
+/// are not supported: the locals read after a `ret` are not considered live.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+    instructions: Vec<(usize, Instruction)>,
+    max_locals: u16,
+    live_in: Vec<BitSet>,
+    live_out: Vec<BitSet>,
+}
+
+impl Liveness {
+    pub fn new(code: &ClassFileMethodCode) -> Result<Self, ClassReaderError> {
+        let instructions = Instruction::parse_instructions(&code.code)?;
+        let successors = instruction_successors(code, &instructions)?;
+        let accesses: Vec<(Range<u16>, Range<u16>)> = instructions
+            .iter()
+            .map(|(_, instruction)| slots_used_and_defined(instruction))
+            .collect();
+        let slots = accesses
+            .iter()
+            .map(|(used, defined)| used.end.max(defined.end))
+            .fold(code.max_locals, u16::max)
+            .into_usize_safe();
+
+        let mut predecessors = vec![Vec::new(); instructions.len()];
+        for (index, targets) in successors.iter().enumerate() {
+            for target in targets {
+                predecessors[*target].push(index);
+            }
+        }
+
+        let mut live_in = vec![BitSet::new(slots); instructions.len()];
+        let mut live_out = vec![BitSet::new(slots); instructions.len()];
+        let mut pending = vec![true; instructions.len()];
+        let mut worklist: Vec<usize> = (0..instructions.len()).collect();
+        while let Some(index) = worklist.pop() {
+            pending[index] = false;
+            for successor in successors[index].iter() {
+                let successor_in = live_in[*successor].clone();
+                live_out[index].union_with(&successor_in);
+            }
+            let (used, defined) = &accesses[index];
+            let mut new_in = live_out[index].clone();
+            for slot in defined.clone() {
+                new_in.remove(slot.into_usize_safe());
+            }
+            for slot in used.clone() {
+                new_in.insert(slot.into_usize_safe());
+            }
+            if live_in[index].union_with(&new_in) {
+                for predecessor in predecessors[index].iter() {
+                    if !pending[*predecessor] {
+                        pending[*predecessor] = true;
+                        worklist.push(*predecessor);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            instructions,
+            max_locals: code.max_locals,
+            live_in,
+            live_out,
+        })
+    }
+
+    fn index_of(&self, pc: ProgramCounter) -> Option<usize> {
+        self.instructions
+            .binary_search_by_key(&pc.0.into_usize_safe(), |(address, _)| *address)
+            .ok()
+    }
+
+    fn slots(set: &BitSet) -> Vec<u16> {
+        set.iter().map(|slot| slot as u16).collect()
+    }
+
+    /// Returns the slots that are live before the instruction at the given address
+    pub fn live_in(&self, pc: ProgramCounter) -> Vec<u16> {
+        self.index_of(pc)
+            .map_or_else(Vec::new, |index| Self::slots(&self.live_in[index]))
+    }
+
+    /// Returns the slots that are live after the instruction at the given address
+    pub fn live_out(&self, pc: ProgramCounter) -> Vec<u16> {
+        self.index_of(pc)
+            .map_or_else(Vec::new, |index| Self::slots(&self.live_out[index]))
+    }
+
+    pub fn is_live_in(&self, pc: ProgramCounter, slot: u16) -> bool {
+        self.index_of(pc)
+            .is_some_and(|index| self.live_in[index].contains(slot.into_usize_safe()))
+    }
+
+    pub fn is_live_out(&self, pc: ProgramCounter, slot: u16) -> bool {
+        self.index_of(pc)
+            .is_some_and(|index| self.live_out[index].contains(slot.into_usize_safe()))
+    }
+
+    /// Returns the addresses of the stores and `iinc` instructions whose value is never read
+    pub fn dead_stores(&self) -> Vec<ProgramCounter> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .filter(|(index, (_, instruction))| {
+                let (_, defined) = slots_used_and_defined(instruction);
+                !defined.is_empty()
+                    && !self.live_out[*index].contains(defined.start.into_usize_safe())
+            })
+            .map(|(_, (address, _))| ProgramCounter(*address as u16))
+            .collect()
+    }
+
+    /// Returns the slots, below `max_locals`, that are neither live nor written by any
+    /// instruction in the given range of addresses. They can be borrowed to hold a temporary
+    /// value in that range, without affecting the original code.
+    pub fn free_slots(&self, range: Range<ProgramCounter>) -> Vec<u16> {
+        let mut used = BitSet::new(self.max_locals.into_usize_safe());
+        for (index, (address, instruction)) in self.instructions.iter().enumerate() {
+            if !range.contains(&ProgramCounter(*address as u16)) {
+                continue;
+            }
+            used.union_with(&self.live_in[index]);
+            used.union_with(&self.live_out[index]);
+            let (_, defined) = slots_used_and_defined(instruction);
+            for slot in defined.filter(|slot| *slot < self.max_locals) {
+                used.insert(slot.into_usize_safe());
+            }
+        }
+        (0..self.max_locals)
+            .filter(|slot| !used.contains(slot.into_usize_safe()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_file_method::ClassFileMethodCode,
+        code_builder::{test_method, CodeBuilder, LocalKind},
+        constant_pool::ConstantPool,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+    };
+
+    fn code(build: impl FnOnce(&mut CodeBuilder)) -> ClassFileMethodCode {
+        let mut constants = ConstantPool::new();
+        test_method(&mut constants, MethodFlags::STATIC, "test", "(I)I", build)
+            .code
+            .unwrap()
+    }
+
+    #[test]
+    fn computes_live_slots_and_dead_stores() {
+        let code = code(|code| {
+            code.iconst(1)
+                .store(LocalKind::Int, 1)
+                .lconst(0)
+                .store(LocalKind::Long, 2)
+                .iconst(2)
+                .store(LocalKind::Int, 1)
+                .load(LocalKind::Int, 0)
+                .load(LocalKind::Int, 1)
+                .instruction(Instruction::Iadd)
+                .instruction(Instruction::Ireturn);
+        });
+        let liveness = code.liveness().unwrap();
+
+        assert_eq!(vec![0], liveness.live_in(ProgramCounter(0)));
+        assert_eq!(vec![0], liveness.live_out(ProgramCounter(1)));
+        assert_eq!(vec![0, 1], liveness.live_out(ProgramCounter(5)));
+        assert!(liveness.is_live_in(ProgramCounter(7), 1));
+        assert!(!liveness.is_live_out(ProgramCounter(7), 1));
+        assert_eq!(
+            vec![ProgramCounter(1), ProgramCounter(3)],
+            liveness.dead_stores()
+        );
+    }
+
+    #[test]
+    fn finds_free_slots() {
+        let code = code(|code| {
+            code.lconst(0)
+                .store(LocalKind::Long, 2)
+                .load(LocalKind::Int, 0)
+                .store(LocalKind::Int, 1)
+                .load(LocalKind::Int, 1)
+                .instruction(Instruction::Ireturn);
+        });
+        let liveness = code.liveness().unwrap();
+
+        assert_eq!(4, code.max_locals);
+        assert_eq!(
+            vec![1],
+            liveness.free_slots(ProgramCounter(0)..ProgramCounter(2))
+        );
+        assert_eq!(
+            vec![2, 3],
+            liveness.free_slots(ProgramCounter(2)..ProgramCounter(7))
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    control_flow_graph::{BlockId, ControlFlowGraph, Edge},
+    dominator_tree::DominatorTree,
+    program_counter::ProgramCounter,
+};
+
+/// A natural loop: the header dominates all the blocks of the loop, and there is at least
+/// one back edge from a block of the loop to the header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: BlockId,
+    /// The blocks that jump back to the header, i.e. the sources of the back edges
+    pub latches: Vec<BlockId>,
+    /// All the blocks of the loop, including the header, ordered by address
+    pub blocks: Vec<BlockId>,
+    /// Index of the innermost loop containing this one, in [Loops::loops]
+    pub parent: Option<usize>,
+    /// Nesting depth of the loop, which is 1 for outermost loops
+    pub depth: usize,
+}
+
+impl Loop {
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+}
+
+/// The natural loops of a [ControlFlowGraph], found from its back edges. Loops sharing the
+/// same header are merged into a single one.
+///
+/// Control flow that is not reducible, i.e. cycles that can be entered in more than one block,
+/// does not form a natural loop. It is never produced by `javac`, but it is typical of
+/// obfuscated code; the edges causing it are reported by [Loops::irreducible_edges].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loops {
+    loops: Vec<Loop>,
+    /// Index of the innermost loop containing each block
+    innermost: Vec<Option<usize>>,
+    block_starts: Vec<ProgramCounter>,
+    irreducible_edges: Vec<Edge>,
+}
+
+impl Loops {
+    pub fn new(graph: &ControlFlowGraph, dominators: &DominatorTree) -> Self {
+        let mut loops: Vec<Loop> = Vec::new();
+        for edge in graph.edges() {
+            if !dominators.dominates(edge.to, edge.from) {
+                continue;
+            }
+            match loops.iter_mut().find(|l| l.header == edge.to) {
+                Some(existing) if existing.latches.contains(&edge.from) => {}
+                Some(existing) => existing.latches.push(edge.from),
+                None => loops.push(Loop {
+                    header: edge.to,
+                    latches: vec![edge.from],
+                    blocks: Vec::new(),
+                    parent: None,
+                    depth: 0,
+                }),
+            }
+        }
+        for l in loops.iter_mut() {
+            l.latches.sort();
+            l.blocks = Self::natural_loop_blocks(graph, dominators, l.header, &l.latches);
+        }
+        loops.sort_by_key(|l| l.header);
+
+        // Natural loops with different headers are either disjoint or nested
+        for index in 0..loops.len() {
+            loops[index].parent = (0..loops.len())
+                .filter(|other| {
+                    *other != index
+                        && loops[*other].contains(loops[index].header)
+                        && loops[*other].blocks.len() > loops[index].blocks.len()
+                })
+                .min_by_key(|other| loops[*other].blocks.len());
+        }
+        for index in 0..loops.len() {
+            let mut depth = 1;
+            let mut parent = loops[index].parent;
+            while let Some(outer) = parent {
+                depth += 1;
+                parent = loops[outer].parent;
+            }
+            loops[index].depth = depth;
+        }
+
+        let innermost = (0..graph.blocks().len())
+            .map(|block| {
+                (0..loops.len())
+                    .filter(|index| loops[*index].contains(block))
+                    .max_by_key(|index| loops[*index].depth)
+            })
+            .collect();
+
+        Self {
+            loops,
+            innermost,
+            block_starts: graph
+                .blocks()
+                .iter()
+                .map(|block| block.range.start)
+                .collect(),
+            irreducible_edges: Self::find_irreducible_edges(graph, dominators),
+        }
+    }
+
+    /// Returns the blocks that can reach one of the latches without going through the header
+    fn natural_loop_blocks(
+        graph: &ControlFlowGraph,
+        dominators: &DominatorTree,
+        header: BlockId,
+        latches: &[BlockId],
+    ) -> Vec<BlockId> {
+        let mut blocks = BTreeSet::from([header]);
+        let mut pending: Vec<BlockId> = latches.to_vec();
+        while let Some(block) = pending.pop() {
+            if blocks.insert(block) {
+                pending.extend(
+                    graph
+                        .predecessors(block)
+                        .map(|edge| edge.from)
+                        .filter(|predecessor| dominators.contains(*predecessor)),
+                );
+            }
+        }
+        blocks.into_iter().collect()
+    }
+
+    /// Visits the graph depth first, and returns the edges that go back to a block being
+    /// visited, whose target does not dominate their source
+    fn find_irreducible_edges(graph: &ControlFlowGraph, dominators: &DominatorTree) -> Vec<Edge> {
+        let mut irreducible_edges = Vec::new();
+        if graph.blocks().is_empty() {
+            return irreducible_edges;
+        }
+        let mut visited = vec![false; graph.blocks().len()];
+        let mut on_stack = vec![false; graph.blocks().len()];
+        let mut stack = vec![(0, graph.successors(0).collect::<Vec<_>>(), 0)];
+        visited[0] = true;
+        on_stack[0] = true;
+        while let Some((block, successors, next_successor)) = stack.last_mut() {
+            let Some(edge) = successors.get(*next_successor).copied() else {
+                on_stack[*block] = false;
+                stack.pop();
+                continue;
+            };
+            *next_successor += 1;
+            if on_stack[edge.to] {
+                if !dominators.dominates(edge.to, edge.from) {
+                    irreducible_edges.push(edge.clone());
+                }
+            } else if !visited[edge.to] {
+                visited[edge.to] = true;
+                on_stack[edge.to] = true;
+                stack.push((edge.to, graph.successors(edge.to).collect(), 0));
+            }
+        }
+        irreducible_edges
+    }
+
+    /// Returns the loops, ordered by the address of their header
+    pub fn loops(&self) -> &[Loop] {
+        &self.loops
+    }
+
+    /// Returns the innermost loop containing the given block
+    pub fn innermost_loop(&self, block: BlockId) -> Option<&Loop> {
+        self.innermost[block].map(|index| &self.loops[index])
+    }
+
+    /// Returns the number of loops containing the given block
+    pub fn depth(&self, block: BlockId) -> usize {
+        self.innermost_loop(block).map_or(0, |l| l.depth)
+    }
+
+    /// Returns the number of loops containing the instruction at the given address
+    pub fn depth_at(&self, pc: ProgramCounter) -> usize {
+        self.block_starts
+            .partition_point(|start| *start <= pc)
+            .checked_sub(1)
+            .map_or(0, |block| self.depth(block))
+    }
+
+    /// Returns the edges that close a cycle without going back to a block dominating their
+    /// source, which make the control flow irreducible
+    pub fn irreducible_edges(&self) -> &[Edge] {
+        &self.irreducible_edges
+    }
+
+    /// Whether all the cycles of the graph are natural loops
+    pub fn is_reducible(&self) -> bool {
+        self.irreducible_edges.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        code_builder::{test_method, CodeBuilder, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        control_flow_graph::{ControlFlowGraph, Edge, EdgeKind},
+        instruction::Instruction,
+        method_flags::MethodFlags,
+    };
+
+    fn graph(build: impl FnOnce(&mut CodeBuilder)) -> ControlFlowGraph {
+        let mut constants = ConstantPool::new();
+        let method = test_method(&mut constants, MethodFlags::STATIC, "test", "(III)V", build);
+        method.code.unwrap().control_flow_graph().unwrap()
+    }
+
+    #[test]
+    fn finds_nested_loops() {
+        let graph = graph(|code| {
+            let outer = code.new_label();
+            let inner = code.new_label();
+            let outer_latch = code.new_label();
+            let end = code.new_label();
+            code.place_label(outer)
+                .load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, end)
+                .place_label(inner)
+                .load(LocalKind::Int, 1)
+                .jump(JumpKind::Ifeq, outer_latch)
+                .iinc(1, -1)
+                .jump(JumpKind::Goto, inner)
+                .place_label(outer_latch)
+                .iinc(0, -1)
+                .jump(JumpKind::Goto, outer)
+                .place_label(end)
+                .instruction(Instruction::Return);
+        });
+        let loops = graph.loops();
+
+        assert_eq!(2, loops.loops().len());
+        let outer = &loops.loops()[0];
+        assert_eq!(
+            (0, vec![3], vec![0, 1, 2, 3]),
+            (outer.header, outer.latches.clone(), outer.blocks.clone())
+        );
+        assert_eq!((None, 1), (outer.parent, outer.depth));
+        let inner = &loops.loops()[1];
+        assert_eq!(
+            (1, vec![2], vec![1, 2]),
+            (inner.header, inner.latches.clone(), inner.blocks.clone())
+        );
+        assert_eq!((Some(0), 2), (inner.parent, inner.depth));
+
+        assert_eq!(2, loops.depth(2));
+        assert_eq!(1, loops.depth(3));
+        assert_eq!(0, loops.depth(4));
+        assert_eq!(2, loops.depth_at(graph.block(2).range.start));
+        assert!(loops.is_reducible());
+    }
+
+    #[test]
+    fn detects_irreducible_flow() {
+        // Both blocks of the cycle between `first` and `second` can be entered from the entry
+        let graph = graph(|code| {
+            let second = code.new_label();
+            let first = code.new_label();
+            let end = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, second)
+                .place_label(first)
+                .load(LocalKind::Int, 1)
+                .jump(JumpKind::Ifeq, end)
+                .place_label(second)
+                .load(LocalKind::Int, 2)
+                .jump(JumpKind::Ifne, first)
+                .place_label(end)
+                .instruction(Instruction::Return);
+        });
+        let loops = graph.loops();
+
+        assert!(loops.loops().is_empty());
+        assert!(!loops.is_reducible());
+        assert_eq!(
+            &[Edge {
+                from: 1,
+                to: 2,
+                kind: EdgeKind::Normal
+            }],
+            loops.irreducible_edges()
+        );
+    }
+}
@@ -5,12 +5,14 @@ use itertools::Itertools;
 use crate::{
     class_reader_error::{ClassReaderError, ClassReaderError::InvalidTypeDescriptor},
     field_type::FieldType,
+    read_limits::ReadLimits,
 };
 
 /// Models the signature of a method, i.e. the type of the parameters it takes and the type
 /// of the return value
 #[derive(Debug, Default, Clone, PartialEq)]
-#[cfg_attr(feature = "wasm", derive(tsify::Tsify, serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct MethodDescriptor {
     pub parameters: Vec<FieldType>,
     pub return_type: Option<FieldType>,
@@ -31,12 +33,21 @@ impl MethodDescriptor {
     /// Parses a method descriptor as specified in the JVM specs:
     /// https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.3.3
     pub fn parse(descriptor: &str) -> Result<MethodDescriptor, ClassReaderError> {
+        Self::parse_with_limits(descriptor, &ReadLimits::default())
+    }
+
+    /// Parses a method descriptor, failing if a type has more array dimensions than the
+    /// limits allow
+    pub fn parse_with_limits(
+        descriptor: &str,
+        limits: &ReadLimits,
+    ) -> Result<MethodDescriptor, ClassReaderError> {
         let mut chars = descriptor.chars();
         match chars.next() {
             Some('(') => {
-                let parameters = Self::parse_parameters(descriptor, &mut chars)?;
+                let parameters = Self::parse_parameters(descriptor, &mut chars, limits)?;
                 if Some(')') == chars.next() {
-                    let return_type = Self::parse_return_type(descriptor, &mut chars)?;
+                    let return_type = Self::parse_return_type(descriptor, &mut chars, limits)?;
                     Ok(MethodDescriptor {
                         parameters,
                         return_type,
@@ -52,13 +63,14 @@ impl MethodDescriptor {
     fn parse_parameters(
         descriptor: &str,
         chars: &mut Chars,
+        limits: &ReadLimits,
     ) -> Result<Vec<FieldType>, ClassReaderError> {
         let mut parameters = Vec::new();
         loop {
             match chars.clone().next() {
                 Some(')') => return Ok(parameters),
                 Some(_) => {
-                    let param = FieldType::parse_from(descriptor, chars)?;
+                    let param = FieldType::parse_from(descriptor, chars, limits)?;
                     parameters.push(param);
                 }
                 None => return Err(InvalidTypeDescriptor(descriptor.to_string())),
@@ -69,11 +81,12 @@ impl MethodDescriptor {
     fn parse_return_type(
         descriptor: &str,
         chars: &mut Chars,
+        limits: &ReadLimits,
     ) -> Result<Option<FieldType>, ClassReaderError> {
         match chars.clone().next() {
             Some('V') => Ok(None),
             Some(_) => {
-                let return_type = Some(FieldType::parse_from(descriptor, chars)?);
+                let return_type = Some(FieldType::parse_from(descriptor, chars, limits)?);
                 if chars.next().is_none() {
                     Ok(return_type)
                 } else {
@@ -84,9 +97,34 @@ impl MethodDescriptor {
         }
     }
 
+    /// Returns the descriptor in the internal JVM form, i.e. `(ILjava/lang/String;)V`
+    pub fn descriptor(&self) -> String {
+        let mut descriptor = String::from("(");
+        for parameter in self.parameters.iter() {
+            descriptor.push_str(&parameter.descriptor());
+        }
+        descriptor.push(')');
+        match &self.return_type {
+            Some(return_type) => descriptor.push_str(&return_type.descriptor()),
+            None => descriptor.push('V'),
+        }
+        descriptor
+    }
+
     pub fn num_arguments(&self) -> usize {
         self.parameters.len()
     }
+
+    /// Number of slots taken by the arguments, either on the operand stack or in the local
+    /// variables of the invoked method. Does not include `this` for instance methods.
+    pub fn arguments_slots(&self) -> u16 {
+        self.parameters.iter().map(FieldType::slots).sum()
+    }
+
+    /// Number of slots taken by the returned value on the operand stack
+    pub fn return_slots(&self) -> u16 {
+        self.return_type.as_ref().map_or(0, FieldType::slots)
+    }
 }
 
 #[cfg(test)]
@@ -95,6 +133,7 @@ mod tests {
         class_reader_error::ClassReaderError,
         field_type::{BaseType, FieldType},
         method_descriptor::MethodDescriptor,
+        read_limits::{ReadLimit, ReadLimits},
     };
 
     #[test]
@@ -163,6 +202,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cannot_parse_too_many_array_dimensions() {
+        assert_eq!(
+            Err(ClassReaderError::LimitExceeded(
+                ReadLimit::DescriptorDepth,
+                2
+            )),
+            MethodDescriptor::parse_with_limits(
+                "(I)[[[J",
+                &ReadLimits::default().max_descriptor_depth(2)
+            ),
+        );
+    }
+
     #[test]
     fn can_format_void_to_void() {
         assert_eq!(
@@ -191,4 +244,19 @@ mod tests {
                 .num_arguments(),
         );
     }
+
+    #[test]
+    fn can_convert_back_to_descriptor() {
+        for descriptor in [
+            "()V",
+            "(JI)D",
+            "(Ljava/lang/String;I)[J",
+            "([[Ljava/lang/Object;)Z",
+        ] {
+            assert_eq!(
+                descriptor,
+                MethodDescriptor::parse(descriptor).unwrap().descriptor()
+            );
+        }
+    }
 }
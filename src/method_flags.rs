@@ -1,7 +1,6 @@
 use bitflags::bitflags;
 
-#[derive(Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Flags of a class method
 pub struct MethodFlags(u16);
 
@@ -27,3 +26,17 @@ impl Default for MethodFlags {
         MethodFlags::empty()
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for MethodFlags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        crate::flags_serde::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MethodFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        crate::flags_serde::deserialize(deserializer)
+    }
+}
@@ -8,7 +8,8 @@ use wasm_bindgen::prelude::*;
 
 /// Models the program counter, i.e. the address of an instruction in the bytecode of a method
 #[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
-#[cfg_attr(feature = "wasm", derive(serde::Serialize, tsify::Tsify))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
 pub struct ProgramCounter(pub u16);
 
 impl Display for ProgramCounter {
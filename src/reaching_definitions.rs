@@ -0,0 +1,237 @@
+use std::ops::Range;
+
+use crate::{
+    bit_set::BitSet, class_file_method::ClassFileMethodCode, class_reader_error::ClassReaderError,
+    control_flow_graph::instruction_successors, instruction::Instruction,
+    liveness::slots_used_and_defined, program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// Where the value of a local variable was assigned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DefinitionSite {
+    /// The value at the start of the method, i.e. a parameter or an unassigned local
+    Entry,
+    /// A store or `iinc` instruction
+    Instruction(ProgramCounter),
+}
+
+/// An assignment of a local variable. Long and double values define two slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub site: DefinitionSite,
+    pub slots: Range<u16>,
+}
+
+impl Definition {
+    fn overlaps(&self, slots: &Range<u16>) -> bool {
+        self.slots.start < slots.end && slots.start < self.slots.end
+    }
+}
+
+/// Computes which definitions of the local variables can reach each instruction of a method,
+/// and from them the def-use chains, i.e. which instructions can read the value assigned by a
+/// definition. Computed only from the bytecode, so it does not need a `LocalVariableTable`.
+///
+/// Like [Liveness](crate::liveness::Liveness), exception handlers are considered reachable
+/// from every instruction they cover, and subroutines are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachingDefinitions {
+    instructions: Vec<(usize, Instruction)>,
+    definitions: Vec<Definition>,
+    /// The definitions reaching each instruction, as indexes in `definitions`
+    reaching: Vec<BitSet>,
+}
+
+impl ReachingDefinitions {
+    pub fn new(code: &ClassFileMethodCode) -> Result<Self, ClassReaderError> {
+        let instructions = Instruction::parse_instructions(&code.code)?;
+        let successors = instruction_successors(code, &instructions)?;
+        let defined: Vec<Range<u16>> = instructions
+            .iter()
+            .map(|(_, instruction)| slots_used_and_defined(instruction).1)
+            .collect();
+        let slots = defined
+            .iter()
+            .map(|slots| slots.end)
+            .fold(code.max_locals, u16::max);
+
+        let mut definitions: Vec<Definition> = (0..slots)
+            .map(|slot| Definition {
+                site: DefinitionSite::Entry,
+                slots: slot..slot + 1,
+            })
+            .collect();
+        // The index of the definition generated by each instruction
+        let mut generated = vec![None; instructions.len()];
+        for (index, (address, _)) in instructions.iter().enumerate() {
+            if !defined[index].is_empty() {
+                generated[index] = Some(definitions.len());
+                definitions.push(Definition {
+                    site: DefinitionSite::Instruction(ProgramCounter(*address as u16)),
+                    slots: defined[index].clone(),
+                });
+            }
+        }
+
+        let mut reaching = vec![BitSet::new(definitions.len()); instructions.len()];
+        if instructions.is_empty() {
+            return Ok(Self {
+                instructions,
+                definitions,
+                reaching,
+            });
+        }
+        for entry in 0..slots.into_usize_safe() {
+            reaching[0].insert(entry);
+        }
+        let mut pending = vec![false; instructions.len()];
+        let mut worklist = vec![0];
+        pending[0] = true;
+        while let Some(index) = worklist.pop() {
+            pending[index] = false;
+            let mut output = reaching[index].clone();
+            if let Some(generated) = generated[index] {
+                let killed: Vec<usize> = output
+                    .iter()
+                    .filter(|definition| definitions[*definition].overlaps(&defined[index]))
+                    .collect();
+                for definition in killed {
+                    output.remove(definition);
+                }
+                output.insert(generated);
+            }
+            for successor in successors[index].iter() {
+                if reaching[*successor].union_with(&output) && !pending[*successor] {
+                    pending[*successor] = true;
+                    worklist.push(*successor);
+                }
+            }
+        }
+
+        Ok(Self {
+            instructions,
+            definitions,
+            reaching,
+        })
+    }
+
+    /// Returns all the definitions: the ones at the entry, one per slot, followed by the ones
+    /// of the instructions, ordered by address
+    pub fn definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    fn index_of(&self, pc: ProgramCounter) -> Option<usize> {
+        self.instructions
+            .binary_search_by_key(&pc.0.into_usize_safe(), |(address, _)| *address)
+            .ok()
+    }
+
+    /// Returns the definitions that can reach the instruction at the given address
+    pub fn reaching(&self, pc: ProgramCounter) -> Vec<&Definition> {
+        self.index_of(pc).map_or_else(Vec::new, |index| {
+            self.reaching[index]
+                .iter()
+                .map(|definition| &self.definitions[definition])
+                .collect()
+        })
+    }
+
+    /// Returns the definitions of the given slot that can reach the instruction at the given
+    /// address, i.e. the use-def chain of the slot
+    pub fn definitions_of(&self, pc: ProgramCounter, slot: u16) -> Vec<&Definition> {
+        self.reaching(pc)
+            .into_iter()
+            .filter(|definition| definition.slots.contains(&slot))
+            .collect()
+    }
+
+    /// Returns the addresses of the instructions that can read the value assigned by the given
+    /// definition, i.e. its def-use chain
+    pub fn uses_of(&self, definition: &Definition) -> Vec<ProgramCounter> {
+        let Some(index) = self
+            .definitions
+            .iter()
+            .position(|candidate| candidate == definition)
+        else {
+            return Vec::new();
+        };
+        self.instructions
+            .iter()
+            .zip(self.reaching.iter())
+            .filter(|((_, instruction), reaching)| {
+                let (used, _) = slots_used_and_defined(instruction);
+                reaching.contains(index) && definition.overlaps(&used)
+            })
+            .map(|((address, _), _)| ProgramCounter(*address as u16))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        code_builder::{test_method, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+        reaching_definitions::{Definition, DefinitionSite},
+    };
+
+    #[test]
+    fn computes_def_use_chains_in_loops() {
+        let mut constants = ConstantPool::new();
+        let method = test_method(
+            &mut constants,
+            MethodFlags::STATIC,
+            "test",
+            "(I)I",
+            |code| {
+                let condition = code.new_label();
+                let end = code.new_label();
+                code.iconst(0)
+                    .store(LocalKind::Int, 1)
+                    .place_label(condition)
+                    .load(LocalKind::Int, 1)
+                    .load(LocalKind::Int, 0)
+                    .jump(JumpKind::If_icmpge, end)
+                    .iinc(1, 1)
+                    .jump(JumpKind::Goto, condition)
+                    .place_label(end)
+                    .load(LocalKind::Int, 1)
+                    .instruction(Instruction::Ireturn);
+            },
+        );
+        let code = method.code.unwrap();
+        let definitions = code.reaching_definitions().unwrap();
+
+        let store = Definition {
+            site: DefinitionSite::Instruction(ProgramCounter(1)),
+            slots: 1..2,
+        };
+        let increment = Definition {
+            site: DefinitionSite::Instruction(ProgramCounter(7)),
+            slots: 1..2,
+        };
+        assert_eq!(
+            vec![&store, &increment],
+            definitions.definitions_of(ProgramCounter(2), 1)
+        );
+        assert_eq!(
+            vec![ProgramCounter(2), ProgramCounter(7), ProgramCounter(13)],
+            definitions.uses_of(&store)
+        );
+
+        let parameter = Definition {
+            site: DefinitionSite::Entry,
+            slots: 0..1,
+        };
+        assert_eq!(vec![ProgramCounter(3)], definitions.uses_of(&parameter));
+        assert_eq!(
+            vec![&parameter],
+            definitions.definitions_of(ProgramCounter(13), 0)
+        );
+    }
+}
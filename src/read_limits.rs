@@ -0,0 +1,104 @@
+use std::{fmt, fmt::Formatter};
+
+use crate::class_reader_error::{ClassReaderError, Result};
+
+/// Limits on the resources used to read a class, to protect against hostile input that
+/// would otherwise exhaust the memory or the stack. The defaults accept every class that
+/// follows the JVM specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Maximum number of bytes allocated to store the strings, attributes and code of a class
+    pub max_allocation: usize,
+    /// Maximum length of the content of an attribute
+    pub max_attribute_length: usize,
+    /// Maximum length of the bytecode of a method
+    pub max_code_length: usize,
+    /// Maximum number of array dimensions in a type descriptor
+    pub max_descriptor_depth: usize,
+    /// Maximum nesting of annotations and arrays in the value of an annotation element
+    pub max_annotation_depth: usize,
+}
+
+/// The kinds of [ReadLimits]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReadLimit {
+    Allocation,
+    AttributeLength,
+    CodeLength,
+    DescriptorDepth,
+    AnnotationDepth,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_allocation: usize::MAX,
+            max_attribute_length: u32::MAX as usize,
+            // https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.11
+            max_code_length: u16::MAX as usize,
+            max_descriptor_depth: 255,
+            max_annotation_depth: 255,
+        }
+    }
+}
+
+impl ReadLimits {
+    pub fn max_allocation(mut self, max_allocation: usize) -> Self {
+        self.max_allocation = max_allocation;
+        self
+    }
+
+    pub fn max_attribute_length(mut self, max_attribute_length: usize) -> Self {
+        self.max_attribute_length = max_attribute_length;
+        self
+    }
+
+    pub fn max_code_length(mut self, max_code_length: usize) -> Self {
+        self.max_code_length = max_code_length;
+        self
+    }
+
+    pub fn max_descriptor_depth(mut self, max_descriptor_depth: usize) -> Self {
+        self.max_descriptor_depth = max_descriptor_depth;
+        self
+    }
+
+    pub fn max_annotation_depth(mut self, max_annotation_depth: usize) -> Self {
+        self.max_annotation_depth = max_annotation_depth;
+        self
+    }
+
+    /// Returns the maximum value allowed for the given limit
+    pub fn get(&self, limit: ReadLimit) -> usize {
+        match limit {
+            ReadLimit::Allocation => self.max_allocation,
+            ReadLimit::AttributeLength => self.max_attribute_length,
+            ReadLimit::CodeLength => self.max_code_length,
+            ReadLimit::DescriptorDepth => self.max_descriptor_depth,
+            ReadLimit::AnnotationDepth => self.max_annotation_depth,
+        }
+    }
+
+    /// Fails if the value is above the given limit
+    pub(crate) fn check(&self, limit: ReadLimit, value: usize) -> Result<()> {
+        let max = self.get(limit);
+        if value > max {
+            Err(ClassReaderError::LimitExceeded(limit, max))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for ReadLimit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReadLimit::Allocation => "allocation",
+            ReadLimit::AttributeLength => "attribute length",
+            ReadLimit::CodeLength => "code length",
+            ReadLimit::DescriptorDepth => "descriptor nesting depth",
+            ReadLimit::AnnotationDepth => "annotation nesting depth",
+        })
+    }
+}
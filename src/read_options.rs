@@ -0,0 +1,72 @@
+use crate::read_limits::ReadLimits;
+
+/// Controls which parts of a class are parsed by [crate::read_buffer_with_options].
+/// The default options parse everything, like [crate::read_buffer].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// Stop after the name, the superclass and the interfaces. Fields, methods and
+    /// attributes of the class are left empty.
+    pub header_only: bool,
+    /// Parse fields and methods, but not the code of the methods
+    pub skip_code: bool,
+    /// Drop the `LineNumberTable`, `LocalVariableTable`, `LocalVariableTypeTable`,
+    /// `SourceFile` and `SourceDebugExtension` attributes
+    pub skip_debug: bool,
+    /// Decode the entries of the constant pool only when they are accessed, rather than
+    /// while reading the class
+    pub lazy_constants: bool,
+    /// Record in [crate::class_file::ClassFile::spans] where each element is stored
+    pub record_spans: bool,
+    /// Limits on the resources used to read the class
+    pub limits: ReadLimits,
+}
+
+impl ReadOptions {
+    pub fn header_only(mut self) -> Self {
+        self.header_only = true;
+        self
+    }
+
+    pub fn skip_code(mut self) -> Self {
+        self.skip_code = true;
+        self
+    }
+
+    pub fn skip_debug(mut self) -> Self {
+        self.skip_debug = true;
+        self
+    }
+
+    pub fn lazy_constants(mut self) -> Self {
+        self.lazy_constants = true;
+        self
+    }
+
+    pub fn record_spans(mut self) -> Self {
+        self.record_spans = true;
+        self
+    }
+
+    pub fn limits(mut self, limits: ReadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The names of the attributes that should not be read at all
+    pub(crate) fn skipped_attributes(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.skip_code {
+            names.push("Code");
+        }
+        if self.skip_debug {
+            names.extend([
+                "LineNumberTable",
+                "LocalVariableTable",
+                "LocalVariableTypeTable",
+                "SourceFile",
+                "SourceDebugExtension",
+            ]);
+        }
+        names
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{instruction::NewArrayType, stack_map_table::VerificationType};
+
+/// Frame used while simulating the execution of the code, where every slot of the local
+/// variables and of the stack has its own entry. The second slot of long and double values
+/// is `Top`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SlotFrame {
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>,
+}
+
+impl SlotFrame {
+    /// Converts values to slots, adding a `Top` after every long and double
+    pub fn slots_of(values: &[VerificationType]) -> Vec<VerificationType> {
+        let mut slots = Vec::new();
+        for value in values {
+            slots.push(value.clone());
+            if value.slots() == 2 {
+                slots.push(VerificationType::Top);
+            }
+        }
+        slots
+    }
+
+    /// Converts slots back to values, the inverse of [SlotFrame::slots_of]
+    pub fn values_of(slots: &[VerificationType]) -> Vec<VerificationType> {
+        let mut values = Vec::new();
+        let mut iter = slots.iter();
+        while let Some(value) = iter.next() {
+            if value.slots() == 2 {
+                iter.next();
+            }
+            values.push(value.clone());
+        }
+        values
+    }
+
+    pub fn push(&mut self, value: VerificationType) {
+        let slots = value.slots();
+        self.stack.push(value);
+        if slots == 2 {
+            self.stack.push(VerificationType::Top);
+        }
+    }
+
+    /// Whether the slot at the given index of the stack is the second half of a long or double
+    pub fn is_second_half(&self, index: usize) -> bool {
+        index > 0
+            && self.stack[index] == VerificationType::Top
+            && self.stack[index - 1].slots() == 2
+    }
+
+    /// Implements the `dup_x` family: copies the top `slots` entries of the stack, and
+    /// inserts them below the following `below` entries. Returns false, leaving the stack
+    /// unchanged, if it does not have enough entries.
+    pub fn duplicate(&mut self, slots: usize, below: usize) -> bool {
+        if self.stack.len() < slots + below {
+            return false;
+        }
+        let values = self.stack[self.stack.len() - slots..].to_vec();
+        let insert_at = self.stack.len() - slots - below;
+        self.stack.splice(insert_at..insert_at, values);
+        true
+    }
+}
+
+/// Returns the class name of a reference type descriptor, or `None` for primitives
+pub(crate) fn class_of_descriptor(descriptor: &str) -> Option<&str> {
+    if descriptor.starts_with('[') {
+        Some(descriptor)
+    } else {
+        descriptor
+            .strip_prefix('L')
+            .and_then(|descriptor| descriptor.strip_suffix(';'))
+    }
+}
+
+/// Returns the descriptor of the arrays created by `newarray`
+pub(crate) fn primitive_array_descriptor(array_type: &NewArrayType) -> &'static str {
+    match array_type {
+        NewArrayType::Boolean => "[Z",
+        NewArrayType::Char => "[C",
+        NewArrayType::Float => "[F",
+        NewArrayType::Double => "[D",
+        NewArrayType::Byte => "[B",
+        NewArrayType::Short => "[S",
+        NewArrayType::Int => "[I",
+        NewArrayType::Long => "[J",
+    }
+}
@@ -0,0 +1,283 @@
+use std::fmt::Write;
+
+use crate::{
+    buffer::Buffer,
+    class_file_view::ConstantPoolView,
+    class_reader_error::{ClassReaderError, Result},
+    constant_pool::scan_constants,
+    instruction::Instruction,
+    type_conversion::ToUsizeSafe,
+};
+
+/// A range of bytes in a .class file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    pub fn contains(&self, other: &Span) -> bool {
+        self.offset <= other.offset && other.end() <= self.end()
+    }
+}
+
+/// The span of an element of the class, labeled with its path, i.e.
+/// `method[2].Code.exception_table[0].handler_pc`. Fields and methods are identified by their
+/// position, constants by their (1-based) index, instructions by their address and
+/// attributes by their name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", derive(tsify::Tsify))]
+pub struct LabeledSpan {
+    pub path: String,
+    pub span: Span,
+}
+
+/// Walks the structure of a class, recording the span of every element. Elements are
+/// recorded before the elements they contain.
+struct SpanRecorder<'a> {
+    data: &'a [u8],
+    buffer: Buffer<'a>,
+    spans: Vec<LabeledSpan>,
+}
+
+impl<'a> SpanRecorder<'a> {
+    /// Records an element of the given size starting at the current position
+    fn leaf(&mut self, path: String, len: usize) -> Result<&'a [u8]> {
+        let offset = self.buffer.position();
+        let bytes = self.buffer.read_bytes(len)?;
+        self.spans.push(LabeledSpan {
+            path,
+            span: Span { offset, len },
+        });
+        Ok(bytes)
+    }
+
+    fn leaf_u16(&mut self, path: String) -> Result<u16> {
+        let bytes = self.leaf(path, 2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn leaf_u32(&mut self, path: String) -> Result<u32> {
+        let bytes = self.leaf(path, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Records an element containing other ones, whose content is read by the given function
+    fn node(
+        &mut self,
+        path: String,
+        read_content: impl FnOnce(&mut Self, &str) -> Result<()>,
+    ) -> Result<()> {
+        let index = self.spans.len();
+        let offset = self.buffer.position();
+        self.spans.push(LabeledSpan {
+            path: path.clone(),
+            span: Span { offset, len: 0 },
+        });
+        read_content(self, &path)?;
+        self.spans[index].span.len = self.buffer.position() - offset;
+        Ok(())
+    }
+
+    fn record_class(&mut self) -> Result<()> {
+        self.leaf("magic".to_string(), 4)?;
+        self.leaf_u16("minor_version".to_string())?;
+        self.leaf_u16("major_version".to_string())?;
+
+        let constants_start = self.buffer.position();
+        let offsets = scan_constants(&mut self.buffer)?;
+        let constants_end = self.buffer.position();
+        self.spans.push(LabeledSpan {
+            path: "constant_pool_count".to_string(),
+            span: Span {
+                offset: constants_start,
+                len: 2,
+            },
+        });
+        let entry_offsets: Vec<(usize, usize)> = offsets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, offset)| offset.map(|offset| (i + 1, offset)))
+            .collect();
+        for (position, (index, offset)) in entry_offsets.iter().enumerate() {
+            let end = entry_offsets
+                .get(position + 1)
+                .map_or(constants_end, |(_, next)| *next);
+            self.spans.push(LabeledSpan {
+                path: format!("constant_pool[{index}]"),
+                span: Span {
+                    offset: *offset,
+                    len: end - offset,
+                },
+            });
+        }
+        let constants = ConstantPoolView::new(self.data, offsets);
+
+        self.leaf_u16("access_flags".to_string())?;
+        self.leaf_u16("this_class".to_string())?;
+        self.leaf_u16("super_class".to_string())?;
+        let interfaces_count = self.leaf_u16("interfaces_count".to_string())?;
+        for i in 0..interfaces_count {
+            self.leaf_u16(format!("interface[{i}]"))?;
+        }
+
+        for (kind, count_label) in [("field", "fields_count"), ("method", "methods_count")] {
+            let count = self.leaf_u16(count_label.to_string())?;
+            for i in 0..count {
+                self.node(format!("{kind}[{i}]"), |recorder, path| {
+                    recorder.leaf_u16(format!("{path}.access_flags"))?;
+                    recorder.leaf_u16(format!("{path}.name_index"))?;
+                    recorder.leaf_u16(format!("{path}.descriptor_index"))?;
+                    recorder.record_attributes(path, &constants)
+                })?;
+            }
+        }
+
+        self.record_attributes("", &constants)
+    }
+
+    fn record_attributes(&mut self, parent: &str, constants: &ConstantPoolView) -> Result<()> {
+        let prefix = if parent.is_empty() {
+            String::new()
+        } else {
+            format!("{parent}.")
+        };
+        let count = self.leaf_u16(format!("{prefix}attributes_count"))?;
+        for _ in 0..count {
+            let name_index = Buffer::new(&self.data[self.buffer.position()..]).read_u16()?;
+            let name = constants.utf8(name_index)?.into_owned();
+            self.node(format!("{prefix}{name}"), |recorder, path| {
+                recorder.leaf_u16(format!("{path}.attribute_name_index"))?;
+                let len = recorder
+                    .leaf_u32(format!("{path}.attribute_length"))?
+                    .into_usize_safe();
+                let start = recorder.buffer.position();
+                if name == "Code" && parent.starts_with("method[") && !parent.contains('.') {
+                    recorder.record_code(path, constants)?;
+                    if recorder.buffer.position() != start + len {
+                        return Err(ClassReaderError::invalid_class_data(format!(
+                            "invalid length for attribute {path}"
+                        )));
+                    }
+                } else {
+                    recorder.leaf(format!("{path}.info"), len)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn record_code(&mut self, path: &str, constants: &ConstantPoolView) -> Result<()> {
+        self.leaf_u16(format!("{path}.max_stack"))?;
+        self.leaf_u16(format!("{path}.max_locals"))?;
+        let code_length = self
+            .leaf_u32(format!("{path}.code_length"))?
+            .into_usize_safe();
+        let code_offset = self.buffer.position();
+        let code = self.leaf(format!("{path}.code"), code_length)?;
+        let mut address = 0;
+        while address < code.len() {
+            let (_, next_address) = Instruction::parse(code, address)?;
+            self.spans.push(LabeledSpan {
+                path: format!("{path}.code[{address}]"),
+                span: Span {
+                    offset: code_offset + address,
+                    len: next_address - address,
+                },
+            });
+            address = next_address;
+        }
+
+        let exception_table_length = self.leaf_u16(format!("{path}.exception_table_length"))?;
+        for i in 0..exception_table_length {
+            self.node(format!("{path}.exception_table[{i}]"), |recorder, path| {
+                for field in ["start_pc", "end_pc", "handler_pc", "catch_type"] {
+                    recorder.leaf_u16(format!("{path}.{field}"))?;
+                }
+                Ok(())
+            })?;
+        }
+        self.record_attributes(path, constants)
+    }
+}
+
+/// Returns the spans of all the elements of a class, in the order in which they appear in
+/// the file. Elements containing other ones come before their content.
+pub fn class_spans(bytes: &[u8]) -> Result<Vec<LabeledSpan>> {
+    let mut recorder = SpanRecorder {
+        data: bytes,
+        buffer: Buffer::new(bytes),
+        spans: Vec::new(),
+    };
+    recorder.record_class()?;
+    Ok(recorder.spans)
+}
+
+/// Renders a hex dump of a class, where every element is labeled with its path. Elements
+/// containing other ones are shown with their size, and their content on the following lines.
+pub fn hex_dump(bytes: &[u8]) -> Result<String> {
+    Ok(format_hex_dump(bytes, &class_spans(bytes)?))
+}
+
+/// Renders a hex dump of the given spans of the bytes
+pub fn format_hex_dump(bytes: &[u8], spans: &[LabeledSpan]) -> String {
+    const BYTES_PER_LINE: usize = 16;
+
+    let mut dump = String::new();
+    for (i, labeled) in spans.iter().enumerate() {
+        let span = labeled.span;
+        let is_container = spans
+            .get(i + 1)
+            .is_some_and(|next| span.len > 0 && next.span.offset == span.offset);
+        if is_container {
+            let _ = writeln!(
+                dump,
+                "{:08x}  {:width$}  {} ({} bytes)",
+                span.offset,
+                "",
+                labeled.path,
+                span.len,
+                width = BYTES_PER_LINE * 3 - 1
+            );
+            continue;
+        }
+
+        let content = bytes.get(span.offset..span.end()).unwrap_or_default();
+        let mut chunks = content.chunks(BYTES_PER_LINE);
+        let first = chunks.next().unwrap_or_default();
+        let _ = writeln!(
+            dump,
+            "{:08x}  {:width$}  {}",
+            span.offset,
+            hex(first),
+            labeled.path,
+            width = BYTES_PER_LINE * 3 - 1
+        );
+        for (line, chunk) in chunks.enumerate() {
+            let _ = writeln!(
+                dump,
+                "{:08x}  {}",
+                span.offset + (line + 1) * BYTES_PER_LINE,
+                hex(chunk)
+            );
+        }
+    }
+    dump
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
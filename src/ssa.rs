@@ -0,0 +1,1898 @@
+use std::{fmt, fmt::Formatter, ops::Range};
+
+use itertools::Itertools;
+
+use crate::{
+    analyzer::{AnalyzerError, Frame, Interpreter, Value},
+    class_file_method::ClassFileMethod,
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    control_flow_graph::{BlockId, ControlFlowGraph, EdgeKind},
+    dominator_tree::reverse_postorder,
+    exception_table::ExceptionTableEntry,
+    field_type::{BaseType, FieldType},
+    instruction::{Instruction, NewArrayType, WideInstruction},
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+    type_conversion::ToUsizeSafe,
+};
+
+/// Identifies a value of an [SsaMethod]. Every value is assigned exactly once, by a parameter,
+/// a phi or a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValueId(pub usize);
+
+/// The type of a value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    /// The `null` constant, which can be used as a reference of any type
+    Null,
+    Typed(FieldType),
+}
+
+impl ValueType {
+    const INT: ValueType = ValueType::Typed(FieldType::Base(BaseType::Int));
+
+    fn object(class_name: &str) -> Self {
+        ValueType::Typed(FieldType::Object(class_name.to_string()))
+    }
+
+    /// Returns the number of slots taken by the value in the local variables or in the
+    /// operand stack
+    pub fn slots(&self) -> u16 {
+        match self {
+            ValueType::Null => 1,
+            ValueType::Typed(field_type) => field_type.slots(),
+        }
+    }
+
+    /// Booleans, bytes, chars and shorts are computed as ints by the JVM
+    fn computational(&self) -> Self {
+        match self {
+            ValueType::Typed(FieldType::Base(
+                BaseType::Boolean | BaseType::Byte | BaseType::Char | BaseType::Short,
+            )) => ValueType::INT,
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns a type that can hold the values of both types. References of different types
+    /// are merged into `java/lang/Object`, since the class hierarchy is not known.
+    fn join(&self, other: &ValueType) -> ValueType {
+        match (self, other) {
+            _ if self == other => self.clone(),
+            (ValueType::Null, other) | (other, ValueType::Null) => other.clone(),
+            (
+                ValueType::Typed(FieldType::Base(first)),
+                ValueType::Typed(FieldType::Base(second)),
+            ) if is_int_like(first) && is_int_like(second) => ValueType::INT,
+            (
+                ValueType::Typed(FieldType::Object(_) | FieldType::Array(_)),
+                ValueType::Typed(FieldType::Object(_) | FieldType::Array(_)),
+            ) => ValueType::object("java/lang/Object"),
+            _ => self.clone(),
+        }
+    }
+}
+
+fn is_int_like(base_type: &BaseType) -> bool {
+    !matches!(
+        base_type,
+        BaseType::Long | BaseType::Float | BaseType::Double
+    )
+}
+
+/// A constant loaded by `aconst_null`, `iconst_<n>`, `bipush`, `ldc`, ... or the increment of
+/// an `iinc`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Null,
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// A class, by its internal name or by its descriptor for arrays
+    Class(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+    Ushr,
+    And,
+    Or,
+    Xor,
+    /// `lcmp`
+    Compare,
+    /// `fcmpl` and `dcmpl`, which return -1 if either value is NaN
+    CompareL,
+    /// `fcmpg` and `dcmpg`, which return 1 if either value is NaN
+    CompareG,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvokeKind {
+    Virtual,
+    Special,
+    Static,
+    Interface,
+}
+
+/// A field, resolved from the constant pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldReference {
+    pub class_name: String,
+    pub name: String,
+    pub field_type: FieldType,
+}
+
+/// A method, resolved from the constant pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodReference {
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: MethodDescriptor,
+}
+
+/// What a [Statement] computes. Loads, stores and the instructions that only move values on
+/// the stack, like `dup` and `swap`, do not have an operation: their values are used directly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Constant(Constant),
+    /// The exception caught by a handler, which is the first statement of its block
+    CaughtException,
+    Negate(ValueId),
+    /// A primitive conversion, like `i2l` or `i2b`
+    Convert(ValueId, BaseType),
+    Binary(BinaryOperator, ValueId, ValueId),
+    GetField {
+        object: ValueId,
+        field: FieldReference,
+    },
+    GetStatic(FieldReference),
+    PutField {
+        object: ValueId,
+        field: FieldReference,
+        value: ValueId,
+    },
+    PutStatic {
+        field: FieldReference,
+        value: ValueId,
+    },
+    /// A method invocation, where the arguments start with the receiver unless the method
+    /// is static
+    Invoke {
+        kind: InvokeKind,
+        method: MethodReference,
+        arguments: Vec<ValueId>,
+    },
+    /// Allocates an object, which is initialized by invoking `<init>` on it
+    New(String),
+    /// `newarray`, `anewarray` and `multianewarray`, with one length per dimension
+    NewArray {
+        array_type: FieldType,
+        lengths: Vec<ValueId>,
+    },
+    ArrayLength(ValueId),
+    ArrayLoad {
+        array: ValueId,
+        index: ValueId,
+    },
+    ArrayStore {
+        array: ValueId,
+        index: ValueId,
+        value: ValueId,
+    },
+    CheckCast {
+        value: ValueId,
+        target: FieldType,
+    },
+    InstanceOf {
+        value: ValueId,
+        target: FieldType,
+    },
+    MonitorEnter(ValueId),
+    MonitorExit(ValueId),
+}
+
+impl Operation {
+    /// Returns the values used by the operation
+    pub fn operands(&self) -> Vec<ValueId> {
+        let mut operation = self.clone();
+        operation.operands_mut().into_iter().map(|id| *id).collect()
+    }
+
+    fn operands_mut(&mut self) -> Vec<&mut ValueId> {
+        match self {
+            Operation::Constant(_)
+            | Operation::CaughtException
+            | Operation::GetStatic(_)
+            | Operation::New(_) => vec![],
+            Operation::Negate(value)
+            | Operation::Convert(value, _)
+            | Operation::GetField { object: value, .. }
+            | Operation::PutStatic { value, .. }
+            | Operation::ArrayLength(value)
+            | Operation::CheckCast { value, .. }
+            | Operation::InstanceOf { value, .. }
+            | Operation::MonitorEnter(value)
+            | Operation::MonitorExit(value) => vec![value],
+            Operation::Binary(_, first, second)
+            | Operation::PutField {
+                object: first,
+                value: second,
+                ..
+            }
+            | Operation::ArrayLoad {
+                array: first,
+                index: second,
+            } => vec![first, second],
+            Operation::ArrayStore {
+                array,
+                index,
+                value,
+            } => vec![array, index, value],
+            Operation::Invoke {
+                arguments: values, ..
+            }
+            | Operation::NewArray {
+                lengths: values, ..
+            } => values.iter_mut().collect(),
+        }
+    }
+}
+
+/// An operation executed by a block, which may define a new value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    /// The address of the instruction that the statement was lifted from
+    pub address: ProgramCounter,
+    /// The value computed by the statement, if any
+    pub result: Option<ValueId>,
+    pub operation: Operation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Ge,
+    Gt,
+    Le,
+}
+
+/// The condition of a conditional jump
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// `if_icmp<cond>` and `if_acmp<cond>`
+    Compare(Comparison, ValueId, ValueId),
+    /// `if<cond>`, which compares an int with zero
+    CompareWithZero(Comparison, ValueId),
+    IsNull(ValueId),
+    IsNotNull(ValueId),
+}
+
+/// How a block ends
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    If {
+        condition: Condition,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Switch {
+        value: ValueId,
+        cases: Vec<(i32, BlockId)>,
+        default: BlockId,
+    },
+    Return(Option<ValueId>),
+    Throw(ValueId),
+    /// Ends the blocks that can never be executed
+    Unreachable,
+}
+
+impl Terminator {
+    /// Returns the values used by the terminator
+    pub fn operands(&self) -> Vec<ValueId> {
+        let mut terminator = self.clone();
+        terminator
+            .operands_mut()
+            .into_iter()
+            .map(|id| *id)
+            .collect()
+    }
+
+    fn operands_mut(&mut self) -> Vec<&mut ValueId> {
+        match self {
+            Terminator::Goto(_) | Terminator::Return(None) | Terminator::Unreachable => vec![],
+            Terminator::If { condition, .. } => match condition {
+                Condition::Compare(_, first, second) => vec![first, second],
+                Condition::CompareWithZero(_, value)
+                | Condition::IsNull(value)
+                | Condition::IsNotNull(value) => vec![value],
+            },
+            Terminator::Switch { value, .. }
+            | Terminator::Return(Some(value))
+            | Terminator::Throw(value) => vec![value],
+        }
+    }
+
+    /// Returns the blocks that can be executed next, without the exception handlers
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Goto(target) => vec![*target],
+            Terminator::If {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Switch { cases, default, .. } => cases
+                .iter()
+                .map(|(_, target)| *target)
+                .chain([*default])
+                .collect(),
+            Terminator::Return(_) | Terminator::Throw(_) | Terminator::Unreachable => vec![],
+        }
+    }
+}
+
+/// One of the values merged by a [Phi]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhiOperand {
+    /// The block that the value comes from, or `None` for the start of the method, when the
+    /// first block is the target of a jump
+    pub predecessor: Option<BlockId>,
+    pub value: ValueId,
+}
+
+/// Selects a value depending on the block that the control comes from. A block entered from an
+/// exceptional edge can have more than one operand for the same predecessor, since the
+/// exception can be thrown by any of its instructions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phi {
+    pub result: ValueId,
+    pub operands: Vec<PhiOperand>,
+}
+
+/// An exception handler covering all the statements of a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handler {
+    /// The class of the exceptions caught, or `None` for handlers that catch everything
+    pub catch_class: Option<String>,
+    pub block: BlockId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsaBlock {
+    /// The addresses of the instructions that the block was lifted from
+    pub range: Range<ProgramCounter>,
+    pub phis: Vec<Phi>,
+    pub statements: Vec<Statement>,
+    pub terminator: Terminator,
+    /// The handlers of the exceptions thrown by the statements, in the order in which the JVM
+    /// looks them up
+    pub handlers: Vec<Handler>,
+}
+
+/// The code of a method in static single assignment form. The operand stack and the local
+/// variables are replaced by values, each defined once and explicitly used by the statements,
+/// with phis merging the values at the join points of the control flow.
+///
+/// The blocks are the ones of the [ControlFlowGraph] of the code, with the same ids, so the
+/// graph and its dominator tree can be used to navigate them. Blocks that can never be
+/// executed are empty and end with [Terminator::Unreachable].
+///
+/// Like the [Analyzer](crate::analyzer::Analyzer), subroutines and `invokedynamic` are not
+/// supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsaMethod {
+    /// The values of `this`, unless the method is static, and of the parameters
+    pub parameters: Vec<ValueId>,
+    /// The type of every value, indexed by id
+    pub values: Vec<ValueType>,
+    pub blocks: Vec<SsaBlock>,
+}
+
+impl SsaMethod {
+    /// Lifts the code of a method of the given class, using the constant pool of the class to
+    /// resolve the constants, fields and methods. Methods without code only have parameters.
+    pub fn new(
+        class_name: &str,
+        method: &ClassFileMethod,
+        constants: &ConstantPool,
+    ) -> std::result::Result<Self, AnalyzerError> {
+        let error = |address, message| AnalyzerError { address, message };
+        let mut parameter_types = Vec::new();
+        if !method.is_static() {
+            parameter_types.push(FieldType::Object(class_name.to_string()));
+        }
+        parameter_types.extend(method.parsed_type_descriptor.parameters.iter().cloned());
+        let values: Vec<ValueType> = parameter_types.into_iter().map(ValueType::Typed).collect();
+        let parameters = (0..values.len()).map(ValueId).collect();
+        let Some(code) = &method.code else {
+            return Ok(Self {
+                parameters,
+                values,
+                blocks: Vec::new(),
+            });
+        };
+
+        let graph = code
+            .control_flow_graph()
+            .map_err(|err| error(None, err.to_string()))?;
+        let liveness = code
+            .liveness()
+            .map_err(|err| error(None, err.to_string()))?;
+        let max_locals = code.max_locals.into_usize_safe();
+        let max_stack = code.max_stack.into_usize_safe();
+        let mut lifter = Lifter {
+            constants,
+            graph: &graph,
+            values,
+            block: 0,
+            statements: Vec::new(),
+            terminator: None,
+        };
+
+        let mut entry_locals = vec![Register::EMPTY; max_locals];
+        let mut slot = 0;
+        for (index, parameter) in parameters.iter().enumerate() {
+            let slots = lifter.values[index].slots();
+            if slot + slots.into_usize_safe() > max_locals {
+                return Err(error(
+                    None,
+                    "the parameters do not fit in max_locals".to_string(),
+                ));
+            }
+            entry_locals[slot] = Register {
+                value: Some(*parameter),
+                slots,
+            };
+            slot += slots.into_usize_safe();
+        }
+
+        let mut blocks: Vec<SsaBlock> = graph
+            .blocks()
+            .iter()
+            .enumerate()
+            .map(|(id, block)| SsaBlock {
+                range: block.range.clone(),
+                phis: Vec::new(),
+                statements: Vec::new(),
+                terminator: Terminator::Unreachable,
+                handlers: graph
+                    .successors(id)
+                    .filter_map(|edge| match &edge.kind {
+                        EdgeKind::Exceptional(catch_class) => Some(Handler {
+                            catch_class: catch_class.clone(),
+                            block: edge.to,
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        // Where the value of each phi comes from, parallel to the phis of the blocks
+        let mut phi_slots: Vec<Vec<PhiSlot>> = vec![Vec::new(); blocks.len()];
+        // The frame at the end of each block, and the locals that can be seen by the handlers
+        let mut exits: Vec<Option<Frame<Register>>> = vec![None; blocks.len()];
+        let mut thrown_locals: Vec<Vec<Vec<Register>>> = vec![Vec::new(); blocks.len()];
+
+        let (successors, _) = graph.adjacency();
+        let order = if blocks.is_empty() {
+            Vec::new()
+        } else {
+            reverse_postorder(&successors, 0)
+        };
+        for id in order {
+            let block = graph.block(id);
+            let start = block.range.start;
+            lifter.block = id;
+            let predecessors: Vec<_> = graph.predecessors(id).collect();
+            let is_handler = predecessors
+                .iter()
+                .any(|edge| matches!(edge.kind, EdgeKind::Exceptional(_)));
+            let single_predecessor = predecessors
+                .iter()
+                .map(|edge| edge.from)
+                .all_equal()
+                .then(|| predecessors.first().map(|edge| edge.from))
+                .flatten()
+                .filter(|_| id != 0 && !is_handler);
+
+            let mut frame = match single_predecessor {
+                // Reverse postorder visits the only predecessor first
+                Some(predecessor) => exits[predecessor]
+                    .clone()
+                    .expect("predecessors are lifted first"),
+                None => {
+                    // A phi is needed for every live local and every stack value. Their types
+                    // are refined once all the predecessors have been lifted.
+                    let mut incoming_locals: Vec<&[Register]> = Vec::new();
+                    let mut incoming_stack: &[Register] = &[];
+                    if id == 0 {
+                        incoming_locals.push(&entry_locals);
+                    }
+                    for edge in predecessors.iter() {
+                        match edge.kind {
+                            EdgeKind::Exceptional(_) => incoming_locals
+                                .extend(thrown_locals[edge.from].iter().map(Vec::as_slice)),
+                            _ => {
+                                if let Some(exit) = &exits[edge.from] {
+                                    incoming_locals.push(&exit.locals);
+                                    incoming_stack = &exit.stack;
+                                }
+                            }
+                        }
+                    }
+
+                    let mut locals = vec![Register::EMPTY; max_locals];
+                    for (slot, local) in locals.iter_mut().enumerate() {
+                        if !liveness.is_live_in(start, slot as u16) {
+                            continue;
+                        }
+                        let Some(incoming) = incoming_locals
+                            .iter()
+                            .map(|locals| &locals[slot])
+                            .find(|register| register.value.is_some())
+                        else {
+                            continue;
+                        };
+                        *local = lifter.new_phi(&mut blocks[id], incoming);
+                        phi_slots[id].push(PhiSlot::Local(slot));
+                    }
+
+                    let stack = if is_handler {
+                        let catch_classes: Vec<&EdgeKind> = predecessors
+                            .iter()
+                            .map(|edge| &edge.kind)
+                            .filter(|kind| matches!(kind, EdgeKind::Exceptional(_)))
+                            .collect();
+                        let exception_type = match catch_classes.first() {
+                            Some(EdgeKind::Exceptional(Some(catch_class)))
+                                if catch_classes.iter().all_equal() =>
+                            {
+                                catch_class.as_str()
+                            }
+                            _ => "java/lang/Throwable",
+                        };
+                        vec![lifter.caught_exception(start, exception_type)]
+                    } else {
+                        let mut stack = Vec::new();
+                        for (index, incoming) in incoming_stack.iter().enumerate() {
+                            if incoming.value.is_none() {
+                                stack.push(Register::EMPTY);
+                                continue;
+                            }
+                            stack.push(lifter.new_phi(&mut blocks[id], incoming));
+                            phi_slots[id].push(PhiSlot::Stack(index));
+                        }
+                        stack
+                    };
+                    Frame { locals, stack }
+                }
+            };
+
+            let handled = !blocks[id].handlers.is_empty();
+            if handled {
+                thrown_locals[id].push(frame.locals.clone());
+            }
+            for (pc, instruction) in block.instructions.iter() {
+                frame
+                    .execute(*pc, instruction, constants, max_stack, &mut lifter)
+                    .map_err(|message| error(Some(*pc), message))?;
+                if handled {
+                    thrown_locals[id].push(frame.locals.clone());
+                }
+            }
+
+            let (last_pc, last_instruction) = block
+                .instructions
+                .last()
+                .expect("basic blocks are never empty");
+            let terminator = match lifter.terminator.take() {
+                Some(terminator) => terminator,
+                None => Terminator::Goto(
+                    match last_instruction {
+                        Instruction::Goto { jump_address }
+                        | Instruction::Goto_w { jump_address } => lifter.target(*jump_address),
+                        _ => lifter.next_block(),
+                    }
+                    .map_err(|message| error(Some(*last_pc), message))?,
+                ),
+            };
+            blocks[id].statements = std::mem::take(&mut lifter.statements);
+            blocks[id].terminator = terminator;
+            exits[id] = Some(frame);
+        }
+
+        for (id, block) in blocks.iter_mut().enumerate() {
+            for (phi, slot) in block.phis.iter_mut().zip(phi_slots[id].iter()) {
+                let mut operands = Vec::new();
+                if let (0, PhiSlot::Local(slot)) = (id, slot) {
+                    if let Some(value) = entry_locals[*slot].value {
+                        operands.push(PhiOperand {
+                            predecessor: None,
+                            value,
+                        });
+                    }
+                }
+                for edge in graph.predecessors(id) {
+                    let predecessor = Some(edge.from);
+                    if let EdgeKind::Exceptional(_) = edge.kind {
+                        let PhiSlot::Local(slot) = slot else {
+                            continue;
+                        };
+                        for locals in thrown_locals[edge.from].iter() {
+                            if let Some(value) = locals[*slot].value {
+                                operands.push(PhiOperand { predecessor, value });
+                            }
+                        }
+                        continue;
+                    }
+                    let Some(exit) = &exits[edge.from] else {
+                        continue;
+                    };
+                    let incoming = match slot {
+                        PhiSlot::Local(slot) => exit.locals.get(*slot),
+                        PhiSlot::Stack(index) => exit.stack.get(*index),
+                    };
+                    match incoming.and_then(|register| register.value) {
+                        Some(value) => operands.push(PhiOperand { predecessor, value }),
+                        None => {
+                            let message = format!("{slot} is not assigned on every path");
+                            return Err(error(Some(block.range.start), message));
+                        }
+                    }
+                }
+                phi.operands = operands.into_iter().unique().collect();
+            }
+        }
+
+        let mut method = Self {
+            parameters,
+            values: lifter.values,
+            blocks,
+        };
+        method.remove_trivial_phis();
+        method.infer_phi_types();
+        method.renumber();
+        Ok(method)
+    }
+
+    /// Returns the type of the given value
+    pub fn value_type(&self, value: ValueId) -> &ValueType {
+        &self.values[value.0]
+    }
+
+    /// Removes the phis whose operands are all the same value, or the phi itself, replacing
+    /// them with that value
+    fn remove_trivial_phis(&mut self) {
+        let mut replacements: Vec<Option<ValueId>> = vec![None; self.values.len()];
+        let resolve = |replacements: &[Option<ValueId>], mut value: ValueId| {
+            while let Some(replacement) = replacements[value.0] {
+                value = replacement;
+            }
+            value
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in self.blocks.iter_mut() {
+                block.phis.retain(|phi| {
+                    let mut unique = None;
+                    for operand in phi.operands.iter() {
+                        let value = resolve(&replacements, operand.value);
+                        if value == phi.result || unique == Some(value) {
+                            continue;
+                        }
+                        if unique.is_some() {
+                            return true;
+                        }
+                        unique = Some(value);
+                    }
+                    match unique {
+                        Some(value) => {
+                            replacements[phi.result.0] = Some(value);
+                            changed = true;
+                            false
+                        }
+                        None => true,
+                    }
+                });
+            }
+        }
+
+        self.map_values(|value| resolve(&replacements, value));
+        for block in self.blocks.iter_mut() {
+            for phi in block.phis.iter_mut() {
+                phi.operands = phi.operands.drain(..).unique().collect();
+            }
+        }
+    }
+
+    /// Phis start with the type of their first operand; this widens them until they can hold
+    /// all their operands
+    fn infer_phi_types(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for phi in self.blocks.iter().flat_map(|block| block.phis.iter()) {
+                let joined = phi
+                    .operands
+                    .iter()
+                    .fold(self.values[phi.result.0].clone(), |joined, operand| {
+                        joined.join(&self.values[operand.value.0])
+                    });
+                if joined != self.values[phi.result.0] {
+                    self.values[phi.result.0] = joined;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Numbers the values in order of definition, dropping the ones of the removed phis
+    fn renumber(&mut self) {
+        let mut numbering: Vec<Option<ValueId>> = vec![None; self.values.len()];
+        let mut values = Vec::new();
+        let defined = self
+            .parameters
+            .iter()
+            .copied()
+            .chain(self.blocks.iter().flat_map(|block| {
+                block.phis.iter().map(|phi| phi.result).chain(
+                    block
+                        .statements
+                        .iter()
+                        .filter_map(|statement| statement.result),
+                )
+            }));
+        for value in defined {
+            numbering[value.0] = Some(ValueId(values.len()));
+            values.push(self.values[value.0].clone());
+        }
+
+        let renumber = |value: ValueId| numbering[value.0].expect("all the values are defined");
+        for parameter in self.parameters.iter_mut() {
+            *parameter = renumber(*parameter);
+        }
+        for block in self.blocks.iter_mut() {
+            for phi in block.phis.iter_mut() {
+                phi.result = renumber(phi.result);
+            }
+            for statement in block.statements.iter_mut() {
+                statement.result = statement.result.map(renumber);
+            }
+        }
+        self.map_values(renumber);
+        self.values = values;
+    }
+
+    /// Replaces all the uses of the values
+    fn map_values(&mut self, map: impl Fn(ValueId) -> ValueId) {
+        for block in self.blocks.iter_mut() {
+            for phi in block.phis.iter_mut() {
+                for operand in phi.operands.iter_mut() {
+                    operand.value = map(operand.value);
+                }
+            }
+            let statement_operands = block
+                .statements
+                .iter_mut()
+                .flat_map(|statement| statement.operation.operands_mut());
+            for value in statement_operands.chain(block.terminator.operands_mut()) {
+                *value = map(*value);
+            }
+        }
+    }
+}
+
+/// The location of the value merged by a phi
+#[derive(Debug, Clone, Copy)]
+enum PhiSlot {
+    Local(usize),
+    Stack(usize),
+}
+
+impl fmt::Display for PhiSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PhiSlot::Local(slot) => write!(f, "local variable {slot}"),
+            PhiSlot::Stack(index) => write!(f, "stack value {index}"),
+        }
+    }
+}
+
+/// The content of a local variable or a stack entry while lifting the code: the value it
+/// holds, if it has been assigned
+#[derive(Debug, Clone, PartialEq)]
+struct Register {
+    value: Option<ValueId>,
+    slots: u16,
+}
+
+impl Register {
+    const EMPTY: Register = Register {
+        value: None,
+        slots: 1,
+    };
+}
+
+impl Value for Register {
+    fn slots(&self) -> u16 {
+        self.slots
+    }
+}
+
+/// Runs the instructions of a block on the [Frame] of the analyzer, recording a statement for
+/// every operation and replacing the stack and the locals with the values they hold
+struct Lifter<'a> {
+    constants: &'a ConstantPool,
+    graph: &'a ControlFlowGraph,
+    values: Vec<ValueType>,
+    block: BlockId,
+    statements: Vec<Statement>,
+    terminator: Option<Terminator>,
+}
+
+type Result<T> = std::result::Result<T, String>;
+
+impl<'a> Lifter<'a> {
+    fn new_value(&mut self, value_type: ValueType) -> Register {
+        let slots = value_type.slots();
+        let value = ValueId(self.values.len());
+        self.values.push(value_type);
+        Register {
+            value: Some(value),
+            slots,
+        }
+    }
+
+    fn new_phi(&mut self, block: &mut SsaBlock, incoming: &Register) -> Register {
+        let value_type = self.type_of(incoming).clone();
+        let register = self.new_value(value_type);
+        block.phis.push(Phi {
+            result: register.value.expect("new values are assigned"),
+            operands: Vec::new(),
+        });
+        register
+    }
+
+    fn define(
+        &mut self,
+        pc: ProgramCounter,
+        value_type: ValueType,
+        operation: Operation,
+    ) -> Register {
+        let register = self.new_value(value_type);
+        self.statements.push(Statement {
+            address: pc,
+            result: register.value,
+            operation,
+        });
+        register
+    }
+
+    fn emit(&mut self, pc: ProgramCounter, operation: Operation) {
+        self.statements.push(Statement {
+            address: pc,
+            result: None,
+            operation,
+        });
+    }
+
+    fn caught_exception(&mut self, pc: ProgramCounter, catch_class: &str) -> Register {
+        self.define(
+            pc,
+            ValueType::object(catch_class),
+            Operation::CaughtException,
+        )
+    }
+
+    fn type_of(&self, register: &Register) -> &ValueType {
+        &self.values[register
+            .value
+            .expect("only assigned registers have a type")
+            .0]
+    }
+
+    fn id(register: &Register) -> Result<ValueId> {
+        register
+            .value
+            .ok_or_else(|| "use of a local variable that has not been assigned".to_string())
+    }
+
+    fn ids(registers: &[Register]) -> Result<Vec<ValueId>> {
+        registers.iter().map(Self::id).collect()
+    }
+
+    fn constant(&self, index: u16) -> Result<(Constant, ValueType)> {
+        let text = |index| self.constants.text_of(index).map_err(|err| err.to_string());
+        Ok(
+            match self.constants.get(index).map_err(|err| err.to_string())? {
+                ConstantPoolEntry::Integer(value) => (Constant::Int(*value), ValueType::INT),
+                ConstantPoolEntry::Float(value) => (
+                    Constant::Float(*value),
+                    ValueType::Typed(FieldType::Base(BaseType::Float)),
+                ),
+                ConstantPoolEntry::Long(value) => (
+                    Constant::Long(*value),
+                    ValueType::Typed(FieldType::Base(BaseType::Long)),
+                ),
+                ConstantPoolEntry::Double(value) => (
+                    Constant::Double(*value),
+                    ValueType::Typed(FieldType::Base(BaseType::Double)),
+                ),
+                ConstantPoolEntry::StringReference(_) => (
+                    Constant::String(text(index)?),
+                    ValueType::object("java/lang/String"),
+                ),
+                ConstantPoolEntry::ClassReference(_) => (
+                    Constant::Class(text(index)?),
+                    ValueType::object("java/lang/Class"),
+                ),
+                _ => return Err(format!("constant #{index} cannot be loaded")),
+            },
+        )
+    }
+
+    fn field(&self, index: u16) -> Result<FieldReference> {
+        let reference = self
+            .constants
+            .member_reference(index)
+            .map_err(|err| err.to_string())?;
+        Ok(FieldReference {
+            field_type: FieldType::parse(&reference.type_descriptor)
+                .map_err(|err| err.to_string())?,
+            class_name: reference.class_name,
+            name: reference.name,
+        })
+    }
+
+    fn method(&self, index: u16) -> Result<MethodReference> {
+        let reference = self
+            .constants
+            .member_reference(index)
+            .map_err(|err| err.to_string())?;
+        Ok(MethodReference {
+            descriptor: MethodDescriptor::parse(&reference.type_descriptor)
+                .map_err(|err| err.to_string())?,
+            class_name: reference.class_name,
+            name: reference.name,
+        })
+    }
+
+    /// Returns the type referenced by `anewarray`, `checkcast`, `instanceof` and
+    /// `multianewarray`, which is a class name or an array descriptor
+    fn class_type(&self, index: u16) -> Result<FieldType> {
+        let name = self
+            .constants
+            .text_of(index)
+            .map_err(|err| err.to_string())?;
+        if name.starts_with('[') {
+            FieldType::parse(&name).map_err(|err| err.to_string())
+        } else {
+            Ok(FieldType::Object(name))
+        }
+    }
+
+    fn target(&self, address: u16) -> Result<BlockId> {
+        self.graph
+            .block_at(ProgramCounter(address))
+            .ok_or_else(|| format!("address {address} is not an instruction"))
+    }
+
+    fn next_block(&self) -> Result<BlockId> {
+        self.graph
+            .block_at(self.graph.block(self.block).range.end)
+            .ok_or_else(|| "execution falls off the end of the code".to_string())
+    }
+
+    fn invoke(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        arguments: &[Register],
+    ) -> Result<Option<Register>> {
+        let (kind, method) = match instruction {
+            Instruction::Invokevirtual { method } => (InvokeKind::Virtual, method),
+            Instruction::Invokespecial { method } => (InvokeKind::Special, method),
+            Instruction::Invokestatic { method } => (InvokeKind::Static, method),
+            Instruction::Invokeinterface { method, .. } => (InvokeKind::Interface, method),
+            _ => return Ok(None),
+        };
+        let method = self.method(*method)?;
+        let operation = Operation::Invoke {
+            kind,
+            arguments: Self::ids(arguments)?,
+            method: method.clone(),
+        };
+        Ok(Some(match method.descriptor.return_type {
+            Some(return_type) => self.define(pc, ValueType::Typed(return_type), operation),
+            None => {
+                self.emit(pc, operation);
+                Register::EMPTY
+            }
+        }))
+    }
+}
+
+impl<'a> Interpreter<Register> for Lifter<'a> {
+    fn new_empty_value(&mut self) -> Register {
+        Register::EMPTY
+    }
+
+    fn new_parameter_value(&mut self, _local: u16, field_type: &FieldType) -> Register {
+        self.new_value(ValueType::Typed(field_type.clone()))
+    }
+
+    fn new_exception_value(&mut self, handler: &ExceptionTableEntry) -> Register {
+        let catch_class = handler
+            .catch_class
+            .as_deref()
+            .unwrap_or("java/lang/Throwable");
+        self.caught_exception(handler.handler_pc, catch_class)
+    }
+
+    fn new_operation(&mut self, pc: ProgramCounter, instruction: &Instruction) -> Result<Register> {
+        let (constant, value_type) = match instruction {
+            Instruction::Aconst_null => (Constant::Null, ValueType::Null),
+            Instruction::Iconst_m1 => (Constant::Int(-1), ValueType::INT),
+            Instruction::Iconst_0 => (Constant::Int(0), ValueType::INT),
+            Instruction::Iconst_1 => (Constant::Int(1), ValueType::INT),
+            Instruction::Iconst_2 => (Constant::Int(2), ValueType::INT),
+            Instruction::Iconst_3 => (Constant::Int(3), ValueType::INT),
+            Instruction::Iconst_4 => (Constant::Int(4), ValueType::INT),
+            Instruction::Iconst_5 => (Constant::Int(5), ValueType::INT),
+            Instruction::Bipush { byte } => (Constant::Int(*byte as i8 as i32), ValueType::INT),
+            Instruction::Sipush { short } => (Constant::Int(*short as i32), ValueType::INT),
+            Instruction::Lconst_0 | Instruction::Lconst_1 => (
+                Constant::Long((*instruction == Instruction::Lconst_1) as i64),
+                ValueType::Typed(FieldType::Base(BaseType::Long)),
+            ),
+            Instruction::Fconst_0 => (
+                Constant::Float(0.0),
+                ValueType::Typed(FieldType::Base(BaseType::Float)),
+            ),
+            Instruction::Fconst_1 => (
+                Constant::Float(1.0),
+                ValueType::Typed(FieldType::Base(BaseType::Float)),
+            ),
+            Instruction::Fconst_2 => (
+                Constant::Float(2.0),
+                ValueType::Typed(FieldType::Base(BaseType::Float)),
+            ),
+            Instruction::Dconst_0 | Instruction::Dconst_1 => (
+                Constant::Double(if *instruction == Instruction::Dconst_1 {
+                    1.0
+                } else {
+                    0.0
+                }),
+                ValueType::Typed(FieldType::Base(BaseType::Double)),
+            ),
+            Instruction::Ldc { index } => self.constant(*index as u16)?,
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                self.constant(*index)?
+            }
+            Instruction::Getstatic { field } => {
+                let field = self.field(*field)?;
+                let value_type = ValueType::Typed(field.field_type.clone());
+                return Ok(self.define(pc, value_type, Operation::GetStatic(field)));
+            }
+            Instruction::New { class } => {
+                let class_name = self
+                    .constants
+                    .text_of(*class)
+                    .map_err(|err| err.to_string())?;
+                let value_type = ValueType::object(&class_name);
+                return Ok(self.define(pc, value_type, Operation::New(class_name)));
+            }
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        };
+        Ok(self.define(pc, value_type, Operation::Constant(constant)))
+    }
+
+    fn unary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        value: &Register,
+    ) -> Result<Register> {
+        let id = Self::id(value)?;
+        let convert = |base_type: BaseType| {
+            (
+                ValueType::Typed(FieldType::Base(base_type.clone())),
+                Operation::Convert(id, base_type),
+            )
+        };
+        let (value_type, operation) = match instruction {
+            Instruction::Iinc { constant, .. } => {
+                let increment = self.define(
+                    pc,
+                    ValueType::INT,
+                    Operation::Constant(Constant::Int(*constant as i32)),
+                );
+                let increment = Self::id(&increment)?;
+                (
+                    ValueType::INT,
+                    Operation::Binary(BinaryOperator::Add, id, increment),
+                )
+            }
+            Instruction::Wide {
+                instruction: WideInstruction::Iinc { constant, .. },
+            } => {
+                let increment = self.define(
+                    pc,
+                    ValueType::INT,
+                    Operation::Constant(Constant::Int(*constant as i32)),
+                );
+                let increment = Self::id(&increment)?;
+                (
+                    ValueType::INT,
+                    Operation::Binary(BinaryOperator::Add, id, increment),
+                )
+            }
+            Instruction::Ineg | Instruction::Lneg | Instruction::Fneg | Instruction::Dneg => {
+                (self.type_of(value).computational(), Operation::Negate(id))
+            }
+            Instruction::L2i | Instruction::F2i | Instruction::D2i => convert(BaseType::Int),
+            Instruction::I2l | Instruction::F2l | Instruction::D2l => convert(BaseType::Long),
+            Instruction::I2f | Instruction::L2f | Instruction::D2f => convert(BaseType::Float),
+            Instruction::I2d | Instruction::L2d | Instruction::F2d => convert(BaseType::Double),
+            Instruction::I2b => convert(BaseType::Byte),
+            Instruction::I2c => convert(BaseType::Char),
+            Instruction::I2s => convert(BaseType::Short),
+            Instruction::Getfield { field } => {
+                let field = self.field(*field)?;
+                (
+                    ValueType::Typed(field.field_type.clone()),
+                    Operation::GetField { object: id, field },
+                )
+            }
+            Instruction::Newarray { array_type } => {
+                let element_type = match array_type {
+                    NewArrayType::Boolean => BaseType::Boolean,
+                    NewArrayType::Char => BaseType::Char,
+                    NewArrayType::Float => BaseType::Float,
+                    NewArrayType::Double => BaseType::Double,
+                    NewArrayType::Byte => BaseType::Byte,
+                    NewArrayType::Short => BaseType::Short,
+                    NewArrayType::Int => BaseType::Int,
+                    NewArrayType::Long => BaseType::Long,
+                };
+                let array_type = FieldType::Array(Box::new(FieldType::Base(element_type)));
+                (
+                    ValueType::Typed(array_type.clone()),
+                    Operation::NewArray {
+                        array_type,
+                        lengths: vec![id],
+                    },
+                )
+            }
+            Instruction::Anewarray { class } => {
+                let array_type = FieldType::Array(Box::new(self.class_type(*class)?));
+                (
+                    ValueType::Typed(array_type.clone()),
+                    Operation::NewArray {
+                        array_type,
+                        lengths: vec![id],
+                    },
+                )
+            }
+            Instruction::Arraylength => (ValueType::INT, Operation::ArrayLength(id)),
+            Instruction::Checkcast { class } => {
+                let target = self.class_type(*class)?;
+                (
+                    ValueType::Typed(target.clone()),
+                    Operation::CheckCast { value: id, target },
+                )
+            }
+            Instruction::Instanceof { class } => (
+                ValueType::Typed(FieldType::Base(BaseType::Boolean)),
+                Operation::InstanceOf {
+                    value: id,
+                    target: self.class_type(*class)?,
+                },
+            ),
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        };
+        Ok(self.define(pc, value_type, operation))
+    }
+
+    fn binary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        first: &Register,
+        second: &Register,
+    ) -> Result<Register> {
+        let (first_id, second_id) = (Self::id(first)?, Self::id(second)?);
+        let element_type = |base_type| match self.type_of(first) {
+            ValueType::Typed(FieldType::Array(element_type)) => {
+                ValueType::Typed(*element_type.clone())
+            }
+            _ => ValueType::Typed(base_type),
+        };
+        let array_load = match instruction {
+            Instruction::Iaload => Some(element_type(FieldType::Base(BaseType::Int))),
+            Instruction::Laload => Some(element_type(FieldType::Base(BaseType::Long))),
+            Instruction::Faload => Some(element_type(FieldType::Base(BaseType::Float))),
+            Instruction::Daload => Some(element_type(FieldType::Base(BaseType::Double))),
+            Instruction::Aaload => Some(element_type(FieldType::Object(
+                "java/lang/Object".to_string(),
+            ))),
+            Instruction::Baload => Some(element_type(FieldType::Base(BaseType::Byte))),
+            Instruction::Caload => Some(element_type(FieldType::Base(BaseType::Char))),
+            Instruction::Saload => Some(element_type(FieldType::Base(BaseType::Short))),
+            _ => None,
+        };
+        if let Some(value_type) = array_load {
+            let operation = Operation::ArrayLoad {
+                array: first_id,
+                index: second_id,
+            };
+            return Ok(self.define(pc, value_type, operation));
+        }
+
+        let operator = match instruction {
+            Instruction::Iadd | Instruction::Ladd | Instruction::Fadd | Instruction::Dadd => {
+                BinaryOperator::Add
+            }
+            Instruction::Isub | Instruction::Lsub | Instruction::Fsub | Instruction::Dsub => {
+                BinaryOperator::Sub
+            }
+            Instruction::Imul | Instruction::Lmul | Instruction::Fmul | Instruction::Dmul => {
+                BinaryOperator::Mul
+            }
+            Instruction::Idiv | Instruction::Ldiv | Instruction::Fdiv | Instruction::Ddiv => {
+                BinaryOperator::Div
+            }
+            Instruction::Irem | Instruction::Lrem | Instruction::Frem | Instruction::Drem => {
+                BinaryOperator::Rem
+            }
+            Instruction::Ishl | Instruction::Lshl => BinaryOperator::Shl,
+            Instruction::Ishr | Instruction::Lshr => BinaryOperator::Shr,
+            Instruction::Iushr | Instruction::Lushr => BinaryOperator::Ushr,
+            Instruction::Iand | Instruction::Land => BinaryOperator::And,
+            Instruction::Ior | Instruction::Lor => BinaryOperator::Or,
+            Instruction::Ixor | Instruction::Lxor => BinaryOperator::Xor,
+            Instruction::Lcmp => BinaryOperator::Compare,
+            Instruction::Fcmpl | Instruction::Dcmpl => BinaryOperator::CompareL,
+            Instruction::Fcmpg | Instruction::Dcmpg => BinaryOperator::CompareG,
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        };
+        let value_type = match operator {
+            BinaryOperator::Compare | BinaryOperator::CompareL | BinaryOperator::CompareG => {
+                ValueType::INT
+            }
+            // Shifts keep the type of the shifted value
+            _ => self.type_of(first).computational(),
+        };
+        let operation = Operation::Binary(operator, first_id, second_id);
+        Ok(self.define(pc, value_type, operation))
+    }
+
+    fn nary_operation(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        values: &[Register],
+    ) -> Result<Register> {
+        if let Instruction::Multianewarray { class, .. } = instruction {
+            let array_type = self.class_type(*class)?;
+            let operation = Operation::NewArray {
+                array_type: array_type.clone(),
+                lengths: Self::ids(values)?,
+            };
+            return Ok(self.define(pc, ValueType::Typed(array_type), operation));
+        }
+        self.invoke(pc, instruction, values)?
+            .ok_or_else(|| format!("unexpected instruction {instruction:?}"))
+    }
+
+    fn consume(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        values: &[Register],
+    ) -> Result<()> {
+        if self.invoke(pc, instruction, values)?.is_some() {
+            return Ok(());
+        }
+        let ids = Self::ids(values)?;
+        let conditional = |comparison, jump_address: &u16| -> Result<(Comparison, BlockId)> {
+            Ok((comparison, self.target(*jump_address)?))
+        };
+        let (condition, then) = match instruction {
+            Instruction::Ifeq { jump_address } => conditional(Comparison::Eq, jump_address)?,
+            Instruction::Ifne { jump_address } => conditional(Comparison::Ne, jump_address)?,
+            Instruction::Iflt { jump_address } => conditional(Comparison::Lt, jump_address)?,
+            Instruction::Ifge { jump_address } => conditional(Comparison::Ge, jump_address)?,
+            Instruction::Ifgt { jump_address } => conditional(Comparison::Gt, jump_address)?,
+            Instruction::Ifle { jump_address } => conditional(Comparison::Le, jump_address)?,
+            Instruction::If_icmpeq { jump_address } | Instruction::If_acmpeq { jump_address } => {
+                conditional(Comparison::Eq, jump_address)?
+            }
+            Instruction::If_icmpne { jump_address } | Instruction::If_acmpne { jump_address } => {
+                conditional(Comparison::Ne, jump_address)?
+            }
+            Instruction::If_icmplt { jump_address } => conditional(Comparison::Lt, jump_address)?,
+            Instruction::If_icmpge { jump_address } => conditional(Comparison::Ge, jump_address)?,
+            Instruction::If_icmpgt { jump_address } => conditional(Comparison::Gt, jump_address)?,
+            Instruction::If_icmple { jump_address } => conditional(Comparison::Le, jump_address)?,
+            Instruction::Ifnull { jump_address } | Instruction::Ifnonnull { jump_address } => {
+                conditional(Comparison::Eq, jump_address)?
+            }
+            _ => {
+                self.consume_values(pc, instruction, &ids)?;
+                return Ok(());
+            }
+        };
+        let condition = match (instruction, ids.as_slice()) {
+            (Instruction::Ifnull { .. }, [value]) => Condition::IsNull(*value),
+            (Instruction::Ifnonnull { .. }, [value]) => Condition::IsNotNull(*value),
+            (_, [value]) => Condition::CompareWithZero(condition, *value),
+            (_, [first, second]) => Condition::Compare(condition, *first, *second),
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        };
+        self.terminator = Some(Terminator::If {
+            condition,
+            then,
+            otherwise: self.next_block()?,
+        });
+        Ok(())
+    }
+
+    /// Phis are created by [SsaMethod::new] rather than by merging frames
+    fn merge(&mut self, first: &Register, _second: &Register) -> Register {
+        first.clone()
+    }
+}
+
+impl<'a> Lifter<'a> {
+    /// Handles the instructions that consume values, other than conditional jumps and
+    /// invocations
+    fn consume_values(
+        &mut self,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+        ids: &[ValueId],
+    ) -> Result<()> {
+        match (instruction, ids) {
+            (
+                Instruction::Tableswitch {
+                    default,
+                    low,
+                    jump_addresses,
+                    ..
+                },
+                [value],
+            ) => {
+                let mut cases = Vec::new();
+                for (key, jump_address) in (*low..).zip(jump_addresses.iter()) {
+                    cases.push((key, self.target(*jump_address)?));
+                }
+                self.terminator = Some(Terminator::Switch {
+                    value: *value,
+                    cases,
+                    default: self.target(*default)?,
+                });
+            }
+            (
+                Instruction::Lookupswitch {
+                    default,
+                    match_pairs,
+                },
+                [value],
+            ) => {
+                let mut cases = Vec::new();
+                for (key, jump_address) in match_pairs.iter() {
+                    cases.push((*key, self.target(*jump_address)?));
+                }
+                self.terminator = Some(Terminator::Switch {
+                    value: *value,
+                    cases,
+                    default: self.target(*default)?,
+                });
+            }
+            (
+                Instruction::Ireturn
+                | Instruction::Lreturn
+                | Instruction::Freturn
+                | Instruction::Dreturn
+                | Instruction::Areturn,
+                [value],
+            ) => self.terminator = Some(Terminator::Return(Some(*value))),
+            (Instruction::Return, []) => self.terminator = Some(Terminator::Return(None)),
+            (Instruction::Athrow, [value]) => self.terminator = Some(Terminator::Throw(*value)),
+            (Instruction::Putstatic { field }, [value]) => {
+                let field = self.field(*field)?;
+                self.emit(
+                    pc,
+                    Operation::PutStatic {
+                        field,
+                        value: *value,
+                    },
+                );
+            }
+            (Instruction::Putfield { field }, [object, value]) => {
+                let field = self.field(*field)?;
+                self.emit(
+                    pc,
+                    Operation::PutField {
+                        object: *object,
+                        field,
+                        value: *value,
+                    },
+                );
+            }
+            (Instruction::Monitorenter, [value]) => {
+                self.emit(pc, Operation::MonitorEnter(*value));
+            }
+            (Instruction::Monitorexit, [value]) => {
+                self.emit(pc, Operation::MonitorExit(*value));
+            }
+            (
+                Instruction::Iastore
+                | Instruction::Lastore
+                | Instruction::Fastore
+                | Instruction::Dastore
+                | Instruction::Aastore
+                | Instruction::Bastore
+                | Instruction::Castore
+                | Instruction::Sastore,
+                [array, index, value],
+            ) => self.emit(
+                pc,
+                Operation::ArrayStore {
+                    array: *array,
+                    index: *index,
+                    value: *value,
+                },
+            ),
+            _ => return Err(format!("unexpected instruction {instruction:?}")),
+        }
+        Ok(())
+    }
+}
+
+/// Formats a type like in Java sources, but with internal class names, i.e. `int[]` or
+/// `java/lang/String`
+fn type_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Base(base_type) => base_type.to_string().to_lowercase(),
+        FieldType::Object(class_name) => class_name.clone(),
+        FieldType::Array(element_type) => format!("{}[]", type_name(element_type)),
+    }
+}
+
+impl fmt::Display for ValueId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::Null => f.write_str("null"),
+            ValueType::Typed(field_type) => f.write_str(&type_name(field_type)),
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Null => f.write_str("null"),
+            Constant::Int(value) => write!(f, "{value}"),
+            Constant::Long(value) => write!(f, "{value}L"),
+            Constant::Float(value) => write!(f, "{value:?}F"),
+            Constant::Double(value) => write!(f, "{value:?}"),
+            Constant::String(value) => write!(f, "{value:?}"),
+            Constant::Class(class_name) => write!(f, "class {class_name}"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BinaryOperator::Add => "add",
+            BinaryOperator::Sub => "sub",
+            BinaryOperator::Mul => "mul",
+            BinaryOperator::Div => "div",
+            BinaryOperator::Rem => "rem",
+            BinaryOperator::Shl => "shl",
+            BinaryOperator::Shr => "shr",
+            BinaryOperator::Ushr => "ushr",
+            BinaryOperator::And => "and",
+            BinaryOperator::Or => "or",
+            BinaryOperator::Xor => "xor",
+            BinaryOperator::Compare => "cmp",
+            BinaryOperator::CompareL => "cmpl",
+            BinaryOperator::CompareG => "cmpg",
+        })
+    }
+}
+
+impl fmt::Display for InvokeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InvokeKind::Virtual => "invokevirtual",
+            InvokeKind::Special => "invokespecial",
+            InvokeKind::Static => "invokestatic",
+            InvokeKind::Interface => "invokeinterface",
+        })
+    }
+}
+
+impl fmt::Display for FieldReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.class_name, self.name)
+    }
+}
+
+impl fmt::Display for MethodReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}({})",
+            self.class_name,
+            self.name,
+            self.descriptor.parameters.iter().map(type_name).join(", ")
+        )
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Constant(constant) => write!(f, "const {constant}"),
+            Operation::CaughtException => f.write_str("caught exception"),
+            Operation::Negate(value) => write!(f, "neg {value}"),
+            Operation::Convert(value, base_type) => {
+                write!(
+                    f,
+                    "convert {value} to {}",
+                    base_type.to_string().to_lowercase()
+                )
+            }
+            Operation::Binary(operator, first, second) => write!(f, "{operator} {first}, {second}"),
+            Operation::GetField { object, field } => write!(f, "getfield {object} {field}"),
+            Operation::GetStatic(field) => write!(f, "getstatic {field}"),
+            Operation::PutField {
+                object,
+                field,
+                value,
+            } => write!(f, "putfield {object} {field} = {value}"),
+            Operation::PutStatic { field, value } => write!(f, "putstatic {field} = {value}"),
+            Operation::Invoke {
+                kind,
+                method,
+                arguments,
+            } => write!(f, "{kind} {method} ({})", arguments.iter().join(", ")),
+            Operation::New(class_name) => write!(f, "new {class_name}"),
+            Operation::NewArray {
+                array_type,
+                lengths,
+            } => write!(
+                f,
+                "newarray {} ({})",
+                type_name(array_type),
+                lengths.iter().join(", ")
+            ),
+            Operation::ArrayLength(array) => write!(f, "arraylength {array}"),
+            Operation::ArrayLoad { array, index } => write!(f, "{array}[{index}]"),
+            Operation::ArrayStore {
+                array,
+                index,
+                value,
+            } => write!(f, "{array}[{index}] = {value}"),
+            Operation::CheckCast { value, target } => {
+                write!(f, "checkcast {value} {}", type_name(target))
+            }
+            Operation::InstanceOf { value, target } => {
+                write!(f, "instanceof {value} {}", type_name(target))
+            }
+            Operation::MonitorEnter(value) => write!(f, "monitorenter {value}"),
+            Operation::MonitorExit(value) => write!(f, "monitorexit {value}"),
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+            Comparison::Le => "<=",
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Compare(comparison, first, second) => {
+                write!(f, "{first} {comparison} {second}")
+            }
+            Condition::CompareWithZero(comparison, value) => write!(f, "{value} {comparison} 0"),
+            Condition::IsNull(value) => write!(f, "{value} == null"),
+            Condition::IsNotNull(value) => write!(f, "{value} != null"),
+        }
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Goto(target) => write!(f, "goto b{target}"),
+            Terminator::If {
+                condition,
+                then,
+                otherwise,
+            } => write!(f, "if {condition} goto b{then} else b{otherwise}"),
+            Terminator::Switch {
+                value,
+                cases,
+                default,
+            } => write!(
+                f,
+                "switch {value} [{}, default: b{default}]",
+                cases
+                    .iter()
+                    .map(|(key, target)| format!("{key}: b{target}"))
+                    .join(", ")
+            ),
+            Terminator::Return(Some(value)) => write!(f, "return {value}"),
+            Terminator::Return(None) => f.write_str("return"),
+            Terminator::Throw(value) => write!(f, "throw {value}"),
+            Terminator::Unreachable => f.write_str("unreachable"),
+        }
+    }
+}
+
+impl fmt::Display for SsaMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let typed = |value: &ValueId| format!("{value}: {}", self.value_type(*value));
+        writeln!(
+            f,
+            "parameters: {}",
+            self.parameters.iter().map(typed).join(", ")
+        )?;
+        for (id, block) in self.blocks.iter().enumerate() {
+            if block.terminator == Terminator::Unreachable {
+                continue;
+            }
+            writeln!(f, "b{id}:")?;
+            for phi in block.phis.iter() {
+                let operands = phi
+                    .operands
+                    .iter()
+                    .map(|operand| match operand.predecessor {
+                        Some(predecessor) => format!("b{predecessor}: {}", operand.value),
+                        None => format!("entry: {}", operand.value),
+                    });
+                writeln!(
+                    f,
+                    "    {} = phi [{}]",
+                    typed(&phi.result),
+                    operands.format(", ")
+                )?;
+            }
+            for statement in block.statements.iter() {
+                match &statement.result {
+                    Some(result) => writeln!(f, "    {} = {}", typed(result), statement.operation)?,
+                    None => writeln!(f, "    {}", statement.operation)?,
+                }
+            }
+            writeln!(f, "    {}", block.terminator)?;
+            for handler in block.handlers.iter() {
+                writeln!(
+                    f,
+                    "    catch {} goto b{}",
+                    handler.catch_class.as_deref().unwrap_or("any"),
+                    handler.block
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analyzer::AnalyzerError,
+        code_builder::{test_method, CodeBuilder, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+        ssa::SsaMethod,
+    };
+
+    fn lift(build: impl FnOnce(&mut CodeBuilder)) -> Result<SsaMethod, AnalyzerError> {
+        let mut constants = ConstantPool::new();
+        let method = test_method(&mut constants, MethodFlags::STATIC, "test", "(I)I", build);
+        SsaMethod::new("Test", &method, &constants)
+    }
+
+    #[test]
+    fn merges_stack_values_with_phis() {
+        let method = lift(|code| {
+            let otherwise = code.new_label();
+            let join = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, otherwise)
+                .iconst(1)
+                .jump(JumpKind::Goto, join)
+                .place_label(otherwise)
+                .iconst(2)
+                .place_label(join)
+                .instruction(Instruction::Ireturn);
+        })
+        .unwrap();
+
+        assert_eq!(
+            "parameters: v0: int
+b0:
+    if v0 == 0 goto b2 else b1
+b1:
+    v1: int = const 1
+    goto b3
+b2:
+    v2: int = const 2
+    goto b3
+b3:
+    v3: int = phi [b1: v1, b2: v2]
+    return v3
+",
+            method.to_string()
+        );
+    }
+
+    #[test]
+    fn removes_the_phis_of_locals_not_modified_in_loops() {
+        let method = lift(|code| {
+            let condition = code.new_label();
+            let end = code.new_label();
+            code.iconst(5)
+                .store(LocalKind::Int, 1)
+                .place_label(condition)
+                .load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifle, end)
+                .iinc(0, -1)
+                .jump(JumpKind::Goto, condition)
+                .place_label(end)
+                .load(LocalKind::Int, 1)
+                .instruction(Instruction::Ireturn);
+        })
+        .unwrap();
+
+        assert_eq!(
+            "parameters: v0: int
+b0:
+    v1: int = const 5
+    goto b1
+b1:
+    v2: int = phi [b0: v0, b2: v4]
+    if v2 <= 0 goto b3 else b2
+b2:
+    v3: int = const -1
+    v4: int = add v2, v3
+    goto b1
+b3:
+    return v1
+",
+            method.to_string()
+        );
+    }
+
+    #[test]
+    fn merges_the_parameters_when_jumping_to_the_start() {
+        let method = lift(|code| {
+            let start = code.new_label();
+            code.place_label(start)
+                .iinc(0, -1)
+                .load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifgt, start)
+                .load(LocalKind::Int, 0)
+                .instruction(Instruction::Ireturn);
+        })
+        .unwrap();
+
+        assert_eq!(
+            "parameters: v0: int
+b0:
+    v1: int = phi [entry: v0, b0: v3]
+    v2: int = const -1
+    v3: int = add v1, v2
+    if v3 > 0 goto b0 else b1
+b1:
+    return v3
+",
+            method.to_string()
+        );
+    }
+
+    #[test]
+    fn handlers_merge_the_locals_of_every_covered_instruction() {
+        let method = lift(|code| {
+            let start = code.new_label();
+            let end = code.new_label();
+            let handler = code.new_label();
+            code.try_catch(start, end, handler, Some("java/lang/Exception"))
+                .place_label(start)
+                .iconst(1)
+                .store(LocalKind::Int, 1)
+                .load(LocalKind::Int, 0)
+                .invokestatic("Test", "check", "(I)V")
+                .iconst(2)
+                .store(LocalKind::Int, 1)
+                .load(LocalKind::Int, 0)
+                .invokestatic("Test", "check", "(I)V")
+                .place_label(end)
+                .load(LocalKind::Int, 1)
+                .instruction(Instruction::Ireturn)
+                .place_label(handler)
+                .store(LocalKind::Reference, 2)
+                .load(LocalKind::Int, 1)
+                .instruction(Instruction::Ireturn);
+        })
+        .unwrap();
+
+        assert_eq!(
+            "parameters: v0: int
+b0:
+    v1: int = const 1
+    invokestatic Test.check(int) (v0)
+    v2: int = const 2
+    invokestatic Test.check(int) (v0)
+    goto b1
+    catch java/lang/Exception goto b2
+b1:
+    return v2
+b2:
+    v3: int = phi [b0: v1, b0: v2]
+    v4: java/lang/Exception = caught exception
+    return v3
+",
+            method.to_string()
+        );
+    }
+
+    #[test]
+    fn subroutines_are_not_supported() {
+        let error = lift(|code| {
+            let subroutine = code.new_label();
+            code.jump(JumpKind::Jsr, subroutine)
+                .load(LocalKind::Int, 0)
+                .instruction(Instruction::Ireturn)
+                .place_label(subroutine)
+                .store(LocalKind::Reference, 1)
+                .instruction(Instruction::Ret { index: 1 });
+        })
+        .unwrap_err();
+
+        assert_eq!(
+            AnalyzerError {
+                address: Some(ProgramCounter(0)),
+                message: "subroutines are not supported".to_string(),
+            },
+            error
+        );
+    }
+}
@@ -0,0 +1,1211 @@
+use std::{collections::HashMap, fmt, fmt::Formatter};
+
+use crate::{
+    attribute::Attribute,
+    buffer::Buffer,
+    class_file::ClassFile,
+    class_file_method::ClassFileMethod,
+    class_file_version::ClassFileVersion,
+    class_hierarchy::ClassHierarchy,
+    class_reader_error::ClassReaderError,
+    class_writer_error::{ClassWriterError, Result},
+    constant_pool::{ConstantPool, ConstantPoolEntry},
+    field_type::FieldType,
+    instruction::{Instruction, LocalKind, LocalVariableAccess},
+    method_descriptor::MethodDescriptor,
+    program_counter::ProgramCounter,
+    slot_frame::{class_of_descriptor, primitive_array_descriptor, SlotFrame},
+    type_conversion::ToUsizeSafe,
+};
+
+/// Types of the values in the local variables and in the operand stack, as tracked by the
+/// verifier: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.7.4
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    /// `this` in a constructor, before the superclass constructor has been invoked
+    UninitializedThis,
+    /// An instance of the given class, or an array if the name starts with `[`
+    Object(String),
+    /// An object created by the `new` instruction at the given address, whose constructor
+    /// has not been invoked yet
+    Uninitialized(ProgramCounter),
+}
+
+impl VerificationType {
+    pub fn from_field_type(field_type: &FieldType) -> Self {
+        match LocalKind::from(field_type) {
+            LocalKind::Int => VerificationType::Integer,
+            LocalKind::Long => VerificationType::Long,
+            LocalKind::Float => VerificationType::Float,
+            LocalKind::Double => VerificationType::Double,
+            LocalKind::Reference => VerificationType::Object(match field_type {
+                FieldType::Object(class) => class.clone(),
+                _ => field_type.descriptor(),
+            }),
+        }
+    }
+
+    /// Long and double values take two slots in the local variables and operand stack
+    pub fn slots(&self) -> u16 {
+        match self {
+            VerificationType::Long | VerificationType::Double => 2,
+            _ => 1,
+        }
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            VerificationType::Top => 0,
+            VerificationType::Integer => 1,
+            VerificationType::Float => 2,
+            VerificationType::Double => 3,
+            VerificationType::Long => 4,
+            VerificationType::Null => 5,
+            VerificationType::UninitializedThis => 6,
+            VerificationType::Object(_) => 7,
+            VerificationType::Uninitialized(_) => 8,
+        }
+    }
+}
+
+impl fmt::Display for VerificationType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationType::Top => f.write_str("top"),
+            VerificationType::Integer => f.write_str("int"),
+            VerificationType::Float => f.write_str("float"),
+            VerificationType::Long => f.write_str("long"),
+            VerificationType::Double => f.write_str("double"),
+            VerificationType::Null => f.write_str("null"),
+            VerificationType::UninitializedThis => f.write_str("uninitialized_this"),
+            VerificationType::Object(class) => write!(f, "class {class}"),
+            VerificationType::Uninitialized(pc) => write!(f, "uninitialized {pc}"),
+        }
+    }
+}
+
+/// The types of the local variables and of the operand stack at a given instruction.
+/// Long and double values are represented by a single entry, even though they take two slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackMapFrame {
+    pub program_counter: ProgramCounter,
+    pub locals: Vec<VerificationType>,
+    pub stack: Vec<VerificationType>,
+}
+
+/// The `StackMapTable` attribute of a method's code, used by the verifier of class files
+/// with version 50 or later. Frames are sorted by program counter.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StackMapTable {
+    frames: Vec<StackMapFrame>,
+}
+
+impl StackMapTable {
+    pub fn new(mut frames: Vec<StackMapFrame>) -> Self {
+        frames.sort_by_key(|frame| frame.program_counter);
+        Self { frames }
+    }
+
+    pub fn frames(&self) -> &[StackMapFrame] {
+        &self.frames
+    }
+
+    /// Returns the frame declared exactly at the given program counter, if any
+    pub fn lookup(&self, pc: ProgramCounter) -> Option<&StackMapFrame> {
+        self.frames
+            .binary_search_by_key(&pc, |frame| frame.program_counter)
+            .ok()
+            .map(|index| &self.frames[index])
+    }
+
+    /// Encodes the frames in the compact form of the attribute. The frames are stored as a
+    /// difference from the previous one, and the first from the implicit frame built from
+    /// the method's descriptor, whose locals are given by `initial_locals`.
+    pub fn encode(
+        &self,
+        initial_locals: &[VerificationType],
+        constants: &mut ConstantPool,
+    ) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.frames.len() as u16).to_be_bytes());
+
+        let mut previous_locals = initial_locals;
+        let mut previous_pc: Option<u16> = None;
+        for frame in self.frames.iter() {
+            let pc = frame.program_counter.0;
+            let offset_delta = match previous_pc {
+                None => pc,
+                Some(previous_pc) => pc - previous_pc - 1,
+            };
+            let same_locals = frame.locals.as_slice() == previous_locals;
+
+            if same_locals && frame.stack.is_empty() {
+                if offset_delta < 64 {
+                    bytes.push(offset_delta as u8);
+                } else {
+                    bytes.push(251);
+                    bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                }
+            } else if same_locals && frame.stack.len() == 1 {
+                if offset_delta < 64 {
+                    bytes.push(64 + offset_delta as u8);
+                } else {
+                    bytes.push(247);
+                    bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                }
+                Self::encode_type(&frame.stack[0], &mut bytes, constants)?;
+            } else if let Some(added) = Self::appended_locals(previous_locals, frame) {
+                bytes.push(251 + added.len() as u8);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                for local in added {
+                    Self::encode_type(local, &mut bytes, constants)?;
+                }
+            } else if let Some(chopped) = Self::chopped_locals(previous_locals, frame) {
+                bytes.push(251 - chopped as u8);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+            } else {
+                bytes.push(255);
+                bytes.extend_from_slice(&offset_delta.to_be_bytes());
+                bytes.extend_from_slice(&(frame.locals.len() as u16).to_be_bytes());
+                for local in frame.locals.iter() {
+                    Self::encode_type(local, &mut bytes, constants)?;
+                }
+                bytes.extend_from_slice(&(frame.stack.len() as u16).to_be_bytes());
+                for value in frame.stack.iter() {
+                    Self::encode_type(value, &mut bytes, constants)?;
+                }
+            }
+
+            previous_locals = &frame.locals;
+            previous_pc = Some(pc);
+        }
+        Ok(bytes)
+    }
+
+    fn appended_locals<'a>(
+        previous_locals: &[VerificationType],
+        frame: &'a StackMapFrame,
+    ) -> Option<&'a [VerificationType]> {
+        let added = frame.locals.len().checked_sub(previous_locals.len())?;
+        if frame.stack.is_empty()
+            && (1..=3).contains(&added)
+            && frame.locals.starts_with(previous_locals)
+        {
+            Some(&frame.locals[previous_locals.len()..])
+        } else {
+            None
+        }
+    }
+
+    fn chopped_locals(
+        previous_locals: &[VerificationType],
+        frame: &StackMapFrame,
+    ) -> Option<usize> {
+        let chopped = previous_locals.len().checked_sub(frame.locals.len())?;
+        if frame.stack.is_empty()
+            && (1..=3).contains(&chopped)
+            && previous_locals.starts_with(&frame.locals)
+        {
+            Some(chopped)
+        } else {
+            None
+        }
+    }
+
+    fn encode_type(
+        verification_type: &VerificationType,
+        bytes: &mut Vec<u8>,
+        constants: &mut ConstantPool,
+    ) -> Result<()> {
+        bytes.push(verification_type.tag());
+        match verification_type {
+            VerificationType::Object(class) => {
+                let index = constants.intern_class_reference(class)?;
+                bytes.extend_from_slice(&index.to_be_bytes());
+            }
+            VerificationType::Uninitialized(pc) => bytes.extend_from_slice(&pc.0.to_be_bytes()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Decodes the content of a `StackMapTable` attribute. The locals of the implicit initial
+    /// frame must be given, since the first frame is stored as a difference from it.
+    pub fn decode(
+        bytes: &[u8],
+        initial_locals: &[VerificationType],
+        constants: &ConstantPool,
+    ) -> std::result::Result<StackMapTable, ClassReaderError> {
+        let mut buffer = Buffer::new(bytes);
+        let frames_count = buffer.read_u16()?;
+        let mut frames: Vec<StackMapFrame> = Vec::new();
+        let mut locals = initial_locals.to_vec();
+        let mut previous_pc: Option<u16> = None;
+
+        for _ in 0..frames_count {
+            let frame_type = buffer.read_u8()?;
+            let (offset_delta, stack) = match frame_type {
+                0..=63 => (frame_type as u16, Vec::new()),
+                64..=127 => (
+                    frame_type as u16 - 64,
+                    vec![Self::decode_type(&mut buffer, constants)?],
+                ),
+                247 => {
+                    let offset_delta = buffer.read_u16()?;
+                    (
+                        offset_delta,
+                        vec![Self::decode_type(&mut buffer, constants)?],
+                    )
+                }
+                248..=250 => {
+                    let offset_delta = buffer.read_u16()?;
+                    let chopped = (251 - frame_type).into_usize_safe();
+                    if chopped > locals.len() {
+                        return Err(ClassReaderError::invalid_class_data(
+                            "invalid chop frame in StackMapTable".to_string(),
+                        ));
+                    }
+                    locals.truncate(locals.len() - chopped);
+                    (offset_delta, Vec::new())
+                }
+                251 => (buffer.read_u16()?, Vec::new()),
+                252..=254 => {
+                    let offset_delta = buffer.read_u16()?;
+                    for _ in 0..(frame_type - 251) {
+                        locals.push(Self::decode_type(&mut buffer, constants)?);
+                    }
+                    (offset_delta, Vec::new())
+                }
+                255 => {
+                    let offset_delta = buffer.read_u16()?;
+                    let locals_count = buffer.read_u16()?;
+                    locals = (0..locals_count)
+                        .map(|_| Self::decode_type(&mut buffer, constants))
+                        .collect::<std::result::Result<_, _>>()?;
+                    let stack_count = buffer.read_u16()?;
+                    let stack = (0..stack_count)
+                        .map(|_| Self::decode_type(&mut buffer, constants))
+                        .collect::<std::result::Result<_, _>>()?;
+                    (offset_delta, stack)
+                }
+                _ => {
+                    return Err(ClassReaderError::invalid_class_data(format!(
+                        "invalid frame type in StackMapTable: {frame_type}"
+                    )))
+                }
+            };
+
+            let pc = match previous_pc {
+                None => Some(offset_delta),
+                Some(previous_pc) => previous_pc
+                    .checked_add(offset_delta)
+                    .and_then(|pc| pc.checked_add(1)),
+            }
+            .ok_or_else(|| {
+                ClassReaderError::invalid_class_data("invalid offset in StackMapTable".to_string())
+            })?;
+            frames.push(StackMapFrame {
+                program_counter: ProgramCounter(pc),
+                locals: locals.clone(),
+                stack,
+            });
+            previous_pc = Some(pc);
+        }
+
+        Ok(StackMapTable { frames })
+    }
+
+    fn decode_type(
+        buffer: &mut Buffer,
+        constants: &ConstantPool,
+    ) -> std::result::Result<VerificationType, ClassReaderError> {
+        let tag = buffer.read_u8()?;
+        Ok(match tag {
+            0 => VerificationType::Top,
+            1 => VerificationType::Integer,
+            2 => VerificationType::Float,
+            3 => VerificationType::Double,
+            4 => VerificationType::Long,
+            5 => VerificationType::Null,
+            6 => VerificationType::UninitializedThis,
+            7 => VerificationType::Object(constants.text_of(buffer.read_u16()?)?),
+            8 => VerificationType::Uninitialized(ProgramCounter(buffer.read_u16()?)),
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid verification type in StackMapTable: {tag}"
+                )))
+            }
+        })
+    }
+}
+
+/// Returns the locals of the implicit first frame of a method, built from its descriptor
+pub fn initial_locals(class_name: &str, method: &ClassFileMethod) -> Vec<VerificationType> {
+    let mut locals = Vec::new();
+    if !method.is_static() {
+        if method.name == "<init>" && class_name != "java/lang/Object" {
+            locals.push(VerificationType::UninitializedThis);
+        } else {
+            locals.push(VerificationType::Object(class_name.to_string()));
+        }
+    }
+    locals.extend(
+        method
+            .parsed_type_descriptor
+            .parameters
+            .iter()
+            .map(VerificationType::from_field_type),
+    );
+    locals
+}
+
+/// Computes the stack map frames of a method, by inferring the type of the local variables
+/// and of the operand stack at every instruction via data flow analysis. Frames are
+/// generated at jump targets, at exception handlers, and after unconditional jumps, i.e.
+/// wherever the verifier requires them.
+///
+/// Subroutines (`jsr` and `ret`) are not supported, since they are not allowed in class files
+/// that require stack map frames. Unreachable code is also rejected.
+pub fn compute_stack_map_table(
+    class_name: &str,
+    method: &ClassFileMethod,
+    constants: &ConstantPool,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<StackMapTable> {
+    let Some(code) = &method.code else {
+        return Ok(StackMapTable::default());
+    };
+    let instructions = Instruction::parse_instructions(&code.code)?;
+    if instructions.is_empty() {
+        return Ok(StackMapTable::default());
+    }
+
+    let mut initial_frame = SlotFrame {
+        locals: Vec::new(),
+        stack: Vec::new(),
+    };
+    for local in initial_locals(class_name, method) {
+        let index = initial_frame.locals.len() as u16;
+        initial_frame.set_local(index, local);
+    }
+    if initial_frame.locals.len() < code.max_locals.into_usize_safe() {
+        initial_frame
+            .locals
+            .resize(code.max_locals.into_usize_safe(), VerificationType::Top);
+    }
+
+    let mut analysis = FrameAnalysis {
+        class_name,
+        constants,
+        hierarchy,
+        instructions: &instructions,
+        index_of_address: instructions
+            .iter()
+            .enumerate()
+            .map(|(index, (address, _))| (*address, index))
+            .collect(),
+        input_frames: vec![None; instructions.len()],
+        worklist: Vec::new(),
+    };
+    analysis.merge_into(0, initial_frame)?;
+
+    while let Some(index) = analysis.worklist.pop() {
+        let (address, instruction) = &instructions[index];
+        let input_frame = analysis.input_frames[index]
+            .clone()
+            .expect("instructions in the worklist have a frame");
+        let mut frame = input_frame.clone();
+        analysis.execute(&mut frame, *address, instruction)?;
+
+        for target in instruction.jump_targets() {
+            let target_index = analysis.index_of(target.into_usize_safe())?;
+            analysis.merge_into(target_index, frame.clone())?;
+        }
+        if instruction.can_fall_through() {
+            if index + 1 >= instructions.len() {
+                return Err(ClassWriterError::FallsOffEndOfCode);
+            }
+            analysis.merge_into(index + 1, frame.clone())?;
+        }
+
+        let pc = ProgramCounter(*address as u16);
+        for entry in code.exception_table.lookup(pc) {
+            let handler_index = analysis.index_of(entry.handler_pc.0.into_usize_safe())?;
+            let exception = VerificationType::Object(
+                entry
+                    .catch_class
+                    .clone()
+                    .unwrap_or_else(|| "java/lang/Throwable".to_string()),
+            );
+            for locals in [&input_frame.locals, &frame.locals] {
+                analysis.merge_into(
+                    handler_index,
+                    SlotFrame {
+                        locals: locals.clone(),
+                        stack: vec![exception.clone()],
+                    },
+                )?;
+            }
+        }
+    }
+
+    let mut frame_indexes: Vec<usize> = Vec::new();
+    for (index, (_, instruction)) in instructions.iter().enumerate() {
+        for target in instruction.jump_targets() {
+            frame_indexes.push(analysis.index_of(target.into_usize_safe())?);
+        }
+        if !instruction.can_fall_through() && index + 1 < instructions.len() {
+            frame_indexes.push(index + 1);
+        }
+    }
+    for entry in code.exception_table.entries() {
+        frame_indexes.push(analysis.index_of(entry.handler_pc.0.into_usize_safe())?);
+    }
+    frame_indexes.sort();
+    frame_indexes.dedup();
+
+    let frames = frame_indexes
+        .into_iter()
+        .map(|index| {
+            let address = instructions[index].0;
+            analysis.input_frames[index]
+                .as_ref()
+                .map(|frame| frame.to_stack_map_frame(ProgramCounter(address as u16)))
+                .ok_or(ClassWriterError::UnreachableCode(address))
+        })
+        .collect::<Result<Vec<StackMapFrame>>>()?;
+    Ok(StackMapTable::new(frames))
+}
+
+/// Computes the `StackMapTable` attribute of all the methods of the class, replacing the
+/// existing ones. The attribute is omitted for methods that do not need any frame.
+pub fn compute_stack_map_tables(
+    class: &mut ClassFile,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<()> {
+    let ClassFile {
+        name,
+        methods,
+        constants,
+        ..
+    } = class;
+    for method in methods.iter_mut() {
+        update_stack_map_table(name, method, constants, hierarchy)?;
+    }
+    Ok(())
+}
+
+/// Computes the `StackMapTable` attribute of a method, replacing the existing one
+pub(crate) fn update_stack_map_table(
+    class_name: &str,
+    method: &mut ClassFileMethod,
+    constants: &mut ConstantPool,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<()> {
+    let stack_map_table = compute_stack_map_table(class_name, method, constants, hierarchy)?;
+    let initial_locals = initial_locals(class_name, method);
+    if let Some(code) = method.code.as_mut() {
+        code.attributes
+            .retain(|attribute| attribute.name != "StackMapTable");
+        if !stack_map_table.frames().is_empty() {
+            code.attributes.push(Attribute {
+                name: "StackMapTable".to_string(),
+                bytes: stack_map_table.encode(&initial_locals, constants)?,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Changes the version of a class. When moving to a version that uses stack map frames
+/// (Java 6 and later), the frames are recomputed for all methods.
+pub fn upgrade_class_version(
+    class: &mut ClassFile,
+    version: ClassFileVersion,
+    hierarchy: &dyn ClassHierarchy,
+) -> Result<()> {
+    if version.major() >= ClassFileVersion::Jdk6.major() {
+        compute_stack_map_tables(class, hierarchy)?;
+    }
+    class.version = version;
+    Ok(())
+}
+
+/// Operations on the frames specific to their computation, where the local variables grow as
+/// needed
+impl SlotFrame {
+    fn pop(&mut self, slots: u16, address: usize) -> Result<()> {
+        let slots = slots.into_usize_safe();
+        if slots > self.stack.len() {
+            return Err(ClassWriterError::StackUnderflow(address));
+        }
+        self.stack.truncate(self.stack.len() - slots);
+        Ok(())
+    }
+
+    fn pop_value(&mut self, address: usize) -> Result<VerificationType> {
+        self.stack
+            .pop()
+            .ok_or(ClassWriterError::StackUnderflow(address))
+    }
+
+    fn set_local(&mut self, index: u16, value: VerificationType) {
+        let index = index.into_usize_safe();
+        let slots = value.slots().into_usize_safe();
+        if self.locals.len() < index + slots {
+            self.locals.resize(index + slots, VerificationType::Top);
+        }
+        if index > 0 && self.locals[index - 1].slots() == 2 {
+            self.locals[index - 1] = VerificationType::Top;
+        }
+        self.locals[index] = value;
+        if slots == 2 {
+            self.locals[index + 1] = VerificationType::Top;
+        }
+    }
+
+    fn local(&self, index: u16) -> VerificationType {
+        self.locals
+            .get(index.into_usize_safe())
+            .cloned()
+            .unwrap_or(VerificationType::Top)
+    }
+
+    /// Converts the slots into a frame with one entry per value, dropping the trailing
+    /// unused local variables
+    fn to_stack_map_frame(&self, program_counter: ProgramCounter) -> StackMapFrame {
+        let mut locals = Self::values_of(&self.locals);
+        while locals.last() == Some(&VerificationType::Top) {
+            locals.pop();
+        }
+        StackMapFrame {
+            program_counter,
+            locals,
+            stack: Self::values_of(&self.stack),
+        }
+    }
+}
+
+struct FrameAnalysis<'a> {
+    class_name: &'a str,
+    constants: &'a ConstantPool,
+    hierarchy: &'a dyn ClassHierarchy,
+    instructions: &'a [(usize, Instruction)],
+    index_of_address: HashMap<usize, usize>,
+    input_frames: Vec<Option<SlotFrame>>,
+    worklist: Vec<usize>,
+}
+
+impl<'a> FrameAnalysis<'a> {
+    fn index_of(&self, address: usize) -> Result<usize> {
+        self.index_of_address
+            .get(&address)
+            .copied()
+            .ok_or(ClassWriterError::InvalidJumpTarget(address))
+    }
+
+    fn merge_into(&mut self, index: usize, frame: SlotFrame) -> Result<()> {
+        let merged = match &self.input_frames[index] {
+            None => frame,
+            Some(existing) => {
+                if existing.stack.len() != frame.stack.len() {
+                    return Err(ClassWriterError::InconsistentStackHeight(
+                        self.instructions[index].0,
+                    ));
+                }
+                let locals_count = existing.locals.len().max(frame.locals.len());
+                let locals = (0..locals_count)
+                    .map(|i| {
+                        self.merge_types(
+                            existing.locals.get(i).unwrap_or(&VerificationType::Top),
+                            frame.locals.get(i).unwrap_or(&VerificationType::Top),
+                        )
+                    })
+                    .collect();
+                let stack = existing
+                    .stack
+                    .iter()
+                    .zip(frame.stack.iter())
+                    .map(|(first, second)| self.merge_types(first, second))
+                    .collect();
+                let merged = SlotFrame { locals, stack };
+                if &merged == existing {
+                    return Ok(());
+                }
+                merged
+            }
+        };
+        self.input_frames[index] = Some(merged);
+        self.worklist.push(index);
+        Ok(())
+    }
+
+    fn merge_types(&self, first: &VerificationType, second: &VerificationType) -> VerificationType {
+        match (first, second) {
+            _ if first == second => first.clone(),
+            (VerificationType::Null, VerificationType::Object(_)) => second.clone(),
+            (VerificationType::Object(_), VerificationType::Null) => first.clone(),
+            (VerificationType::Object(first), VerificationType::Object(second)) => {
+                VerificationType::Object(self.common_superclass(first, second))
+            }
+            _ => VerificationType::Top,
+        }
+    }
+
+    /// Finds the common superclass of two classes or arrays. Arrays of references are
+    /// merged into arrays of the common superclass of their components.
+    fn common_superclass(&self, first: &str, second: &str) -> String {
+        match (first.strip_prefix('['), second.strip_prefix('[')) {
+            (Some(first_component), Some(second_component)) => {
+                match (
+                    class_of_descriptor(first_component),
+                    class_of_descriptor(second_component),
+                ) {
+                    (Some(first_class), Some(second_class)) => {
+                        let common = self.common_superclass(first_class, second_class);
+                        if common.starts_with('[') {
+                            format!("[{common}")
+                        } else {
+                            format!("[L{common};")
+                        }
+                    }
+                    _ => "java/lang/Object".to_string(),
+                }
+            }
+            (None, None) => self.hierarchy.common_superclass(first, second),
+            _ => "java/lang/Object".to_string(),
+        }
+    }
+
+    fn class_name_at(&self, index: u16) -> Result<String> {
+        Ok(self
+            .constants
+            .text_of(index)
+            .map_err(ClassReaderError::from)?)
+    }
+
+    /// Simulates the execution of an instruction, updating the frame
+    fn execute(
+        &self,
+        frame: &mut SlotFrame,
+        address: usize,
+        instruction: &Instruction,
+    ) -> Result<()> {
+        if let Some(access) = instruction.local_variable_access() {
+            match access {
+                LocalVariableAccess::Load(LocalKind::Reference, index) => {
+                    frame.push(frame.local(index));
+                }
+                LocalVariableAccess::Load(kind, _) => {
+                    frame.push(Self::type_of_kind(kind));
+                }
+                LocalVariableAccess::Store(kind, index) => {
+                    let value = if kind == LocalKind::Reference {
+                        frame.pop_value(address)?
+                    } else {
+                        frame.pop(kind.slots(), address)?;
+                        Self::type_of_kind(kind)
+                    };
+                    frame.set_local(index, value);
+                }
+                LocalVariableAccess::Increment(_) => {}
+                LocalVariableAccess::Ret(_) => {
+                    return Err(ClassWriterError::SubroutinesNotSupported(address))
+                }
+            }
+            return Ok(());
+        }
+
+        match instruction {
+            Instruction::Jsr { .. } | Instruction::Jsr_w { .. } => {
+                return Err(ClassWriterError::SubroutinesNotSupported(address))
+            }
+            Instruction::Aconst_null => frame.push(VerificationType::Null),
+            Instruction::Ldc { index } => frame.push(self.constant_type(*index as u16)?),
+            Instruction::Ldc_w { index } | Instruction::Ldc2_w { index } => {
+                frame.push(self.constant_type(*index)?)
+            }
+            Instruction::Aaload => {
+                frame.pop(1, address)?;
+                let array = frame.pop_value(address)?;
+                frame.push(Self::component_type(&array));
+            }
+            Instruction::Dup => Self::duplicate(frame, 1, 0, address)?,
+            Instruction::Dup_x1 => Self::duplicate(frame, 1, 1, address)?,
+            Instruction::Dup_x2 => Self::duplicate(frame, 1, 2, address)?,
+            Instruction::Dup2 => Self::duplicate(frame, 2, 0, address)?,
+            Instruction::Dup2_x1 => Self::duplicate(frame, 2, 1, address)?,
+            Instruction::Dup2_x2 => Self::duplicate(frame, 2, 2, address)?,
+            Instruction::Swap => {
+                let first = frame.pop_value(address)?;
+                let second = frame.pop_value(address)?;
+                frame.stack.push(first);
+                frame.stack.push(second);
+            }
+            Instruction::Getstatic { field } | Instruction::Getfield { field } => {
+                if matches!(instruction, Instruction::Getfield { .. }) {
+                    frame.pop(1, address)?;
+                }
+                let reference = self
+                    .constants
+                    .member_reference(*field)
+                    .map_err(ClassReaderError::from)?;
+                let field_type = FieldType::parse(&reference.type_descriptor)?;
+                frame.push(VerificationType::from_field_type(&field_type));
+            }
+            Instruction::Invokestatic { method }
+            | Instruction::Invokevirtual { method }
+            | Instruction::Invokespecial { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                let reference = self
+                    .constants
+                    .member_reference(*method)
+                    .map_err(ClassReaderError::from)?;
+                let descriptor = MethodDescriptor::parse(&reference.type_descriptor)?;
+                frame.pop(descriptor.arguments_slots(), address)?;
+                if !matches!(instruction, Instruction::Invokestatic { .. }) {
+                    let receiver = frame.pop_value(address)?;
+                    if reference.name == "<init>" {
+                        self.initialize(frame, &receiver)?;
+                    }
+                }
+                if let Some(return_type) = &descriptor.return_type {
+                    frame.push(VerificationType::from_field_type(return_type));
+                }
+            }
+            Instruction::New { .. } => frame.push(VerificationType::Uninitialized(ProgramCounter(
+                address as u16,
+            ))),
+            Instruction::Newarray { array_type } => {
+                frame.pop(1, address)?;
+                frame.push(VerificationType::Object(
+                    primitive_array_descriptor(array_type).to_string(),
+                ));
+            }
+            Instruction::Anewarray { class } => {
+                frame.pop(1, address)?;
+                let class_name = self.class_name_at(*class)?;
+                frame.push(VerificationType::Object(if class_name.starts_with('[') {
+                    format!("[{class_name}")
+                } else {
+                    format!("[L{class_name};")
+                }));
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                frame.pop(*dimensions as u16, address)?;
+                frame.push(VerificationType::Object(self.class_name_at(*class)?));
+            }
+            Instruction::Checkcast { class } => {
+                frame.pop(1, address)?;
+                frame.push(VerificationType::Object(self.class_name_at(*class)?));
+            }
+            _ => {
+                let effect = instruction.stack_effect(self.constants)?;
+                frame.pop(effect.popped, address)?;
+                if let Some(result) = Self::result_type(instruction) {
+                    frame.push(result);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn type_of_kind(kind: LocalKind) -> VerificationType {
+        match kind {
+            LocalKind::Int => VerificationType::Integer,
+            LocalKind::Long => VerificationType::Long,
+            LocalKind::Float => VerificationType::Float,
+            LocalKind::Double => VerificationType::Double,
+            LocalKind::Reference => VerificationType::Top,
+        }
+    }
+
+    /// The type pushed by instructions that do not depend on the current frame or on the
+    /// constant pool
+    fn result_type(instruction: &Instruction) -> Option<VerificationType> {
+        match instruction {
+            Instruction::Iconst_m1
+            | Instruction::Iconst_0
+            | Instruction::Iconst_1
+            | Instruction::Iconst_2
+            | Instruction::Iconst_3
+            | Instruction::Iconst_4
+            | Instruction::Iconst_5
+            | Instruction::Bipush { .. }
+            | Instruction::Sipush { .. }
+            | Instruction::Iaload
+            | Instruction::Baload
+            | Instruction::Caload
+            | Instruction::Saload
+            | Instruction::Iadd
+            | Instruction::Isub
+            | Instruction::Imul
+            | Instruction::Idiv
+            | Instruction::Irem
+            | Instruction::Ineg
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Iushr
+            | Instruction::Iand
+            | Instruction::Ior
+            | Instruction::Ixor
+            | Instruction::L2i
+            | Instruction::F2i
+            | Instruction::D2i
+            | Instruction::I2b
+            | Instruction::I2c
+            | Instruction::I2s
+            | Instruction::Lcmp
+            | Instruction::Fcmpl
+            | Instruction::Fcmpg
+            | Instruction::Dcmpl
+            | Instruction::Dcmpg
+            | Instruction::Arraylength
+            | Instruction::Instanceof { .. } => Some(VerificationType::Integer),
+            Instruction::Lconst_0
+            | Instruction::Lconst_1
+            | Instruction::Laload
+            | Instruction::Ladd
+            | Instruction::Lsub
+            | Instruction::Lmul
+            | Instruction::Ldiv
+            | Instruction::Lrem
+            | Instruction::Lneg
+            | Instruction::Lshl
+            | Instruction::Lshr
+            | Instruction::Lushr
+            | Instruction::Land
+            | Instruction::Lor
+            | Instruction::Lxor
+            | Instruction::I2l
+            | Instruction::F2l
+            | Instruction::D2l => Some(VerificationType::Long),
+            Instruction::Fconst_0
+            | Instruction::Fconst_1
+            | Instruction::Fconst_2
+            | Instruction::Faload
+            | Instruction::Fadd
+            | Instruction::Fsub
+            | Instruction::Fmul
+            | Instruction::Fdiv
+            | Instruction::Frem
+            | Instruction::Fneg
+            | Instruction::I2f
+            | Instruction::L2f
+            | Instruction::D2f => Some(VerificationType::Float),
+            Instruction::Dconst_0
+            | Instruction::Dconst_1
+            | Instruction::Daload
+            | Instruction::Dadd
+            | Instruction::Dsub
+            | Instruction::Dmul
+            | Instruction::Ddiv
+            | Instruction::Drem
+            | Instruction::Dneg
+            | Instruction::I2d
+            | Instruction::L2d
+            | Instruction::F2d => Some(VerificationType::Double),
+            _ => None,
+        }
+    }
+
+    fn constant_type(&self, index: u16) -> Result<VerificationType> {
+        let entry = self.constants.get(index).map_err(ClassReaderError::from)?;
+        Ok(match entry {
+            ConstantPoolEntry::Integer(_) => VerificationType::Integer,
+            ConstantPoolEntry::Float(_) => VerificationType::Float,
+            ConstantPoolEntry::Long(_) => VerificationType::Long,
+            ConstantPoolEntry::Double(_) => VerificationType::Double,
+            ConstantPoolEntry::StringReference(_) => {
+                VerificationType::Object("java/lang/String".to_string())
+            }
+            ConstantPoolEntry::ClassReference(_) => {
+                VerificationType::Object("java/lang/Class".to_string())
+            }
+            _ => {
+                return Err(ClassReaderError::invalid_class_data(format!(
+                    "invalid constant for ldc: {entry:?}"
+                ))
+                .into())
+            }
+        })
+    }
+
+    fn component_type(array: &VerificationType) -> VerificationType {
+        match array {
+            VerificationType::Object(descriptor) => {
+                match descriptor.strip_prefix('[').and_then(class_of_descriptor) {
+                    Some(component) => VerificationType::Object(component.to_string()),
+                    None => VerificationType::Top,
+                }
+            }
+            VerificationType::Null => VerificationType::Null,
+            _ => VerificationType::Top,
+        }
+    }
+
+    /// Implements the `dup` family, see [SlotFrame::duplicate]
+    fn duplicate(frame: &mut SlotFrame, slots: usize, below: usize, address: usize) -> Result<()> {
+        if frame.duplicate(slots, below) {
+            Ok(())
+        } else {
+            Err(ClassWriterError::StackUnderflow(address))
+        }
+    }
+
+    /// After a constructor is invoked, all the copies of the uninitialized object become
+    /// initialized
+    fn initialize(&self, frame: &mut SlotFrame, receiver: &VerificationType) -> Result<()> {
+        let initialized = match receiver {
+            VerificationType::UninitializedThis => {
+                VerificationType::Object(self.class_name.to_string())
+            }
+            VerificationType::Uninitialized(pc) => {
+                let class = self
+                    .index_of(pc.0.into_usize_safe())
+                    .ok()
+                    .and_then(|index| match self.instructions[index].1 {
+                        Instruction::New { class } => Some(class),
+                        _ => None,
+                    })
+                    .ok_or(ClassWriterError::InvalidJumpTarget(pc.0.into_usize_safe()))?;
+                VerificationType::Object(self.class_name_at(class)?)
+            }
+            _ => return Ok(()),
+        };
+        for value in frame.locals.iter_mut().chain(frame.stack.iter_mut()) {
+            if value == receiver {
+                *value = initialized.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_hierarchy::{MapClassHierarchy, ObjectClassHierarchy},
+        code_builder::{test_method, JumpKind, LocalKind},
+        constant_pool::ConstantPool,
+        instruction::Instruction,
+        method_flags::MethodFlags,
+        program_counter::ProgramCounter,
+        stack_map_table::{
+            compute_stack_map_table, initial_locals, StackMapFrame, StackMapTable, VerificationType,
+        },
+    };
+
+    fn object(class: &str) -> VerificationType {
+        VerificationType::Object(class.to_string())
+    }
+
+    #[test]
+    fn computes_frames_for_branches_and_merges_types() {
+        let mut constants = ConstantPool::new();
+        let method = test_method(
+            &mut constants,
+            MethodFlags::STATIC,
+            "choose",
+            "(ZJ)Ljava/lang/Object;",
+            |code| {
+                let else_label = code.new_label();
+                let end_label = code.new_label();
+                code.load(LocalKind::Int, 0)
+                    .jump(JumpKind::Ifeq, else_label)
+                    .new_object("a/Left")
+                    .instruction(Instruction::Dup)
+                    .invokespecial("a/Left", "<init>", "()V")
+                    .jump(JumpKind::Goto, end_label)
+                    .place_label(else_label)
+                    .new_object("a/Right")
+                    .instruction(Instruction::Dup)
+                    .invokespecial("a/Right", "<init>", "()V")
+                    .place_label(end_label)
+                    .store(LocalKind::Reference, 3)
+                    .load(LocalKind::Reference, 3)
+                    .instruction(Instruction::Areturn);
+            },
+        );
+
+        let mut hierarchy = MapClassHierarchy::new();
+        hierarchy.add("a/Left", "a/Base");
+        hierarchy.add("a/Right", "a/Base");
+        let table = compute_stack_map_table("a/Test", &method, &constants, &hierarchy).unwrap();
+
+        assert_eq!(
+            &[
+                StackMapFrame {
+                    program_counter: ProgramCounter(14),
+                    locals: vec![VerificationType::Integer, VerificationType::Long],
+                    stack: vec![],
+                },
+                StackMapFrame {
+                    program_counter: ProgramCounter(21),
+                    locals: vec![VerificationType::Integer, VerificationType::Long],
+                    stack: vec![object("a/Base")],
+                },
+            ],
+            table.frames()
+        );
+    }
+
+    #[test]
+    fn computes_frames_for_loops_and_exception_handlers() {
+        let mut constants = ConstantPool::new();
+        let method = test_method(
+            &mut constants,
+            MethodFlags::empty(),
+            "<init>",
+            "([Ljava/lang/String;)V",
+            |code| {
+                let loop_start = code.new_label();
+                let loop_end = code.new_label();
+                let try_start = code.new_label();
+                let try_end = code.new_label();
+                let handler = code.new_label();
+                code.load(LocalKind::Reference, 0)
+                    .invokespecial("java/lang/Object", "<init>", "()V")
+                    .iconst(0)
+                    .store(LocalKind::Int, 2)
+                    .place_label(loop_start)
+                    .load(LocalKind::Int, 2)
+                    .load(LocalKind::Reference, 1)
+                    .instruction(Instruction::Arraylength)
+                    .jump(JumpKind::If_icmpge, loop_end)
+                    .place_label(try_start)
+                    .load(LocalKind::Reference, 1)
+                    .load(LocalKind::Int, 2)
+                    .instruction(Instruction::Aaload)
+                    .store(LocalKind::Reference, 3)
+                    .place_label(try_end)
+                    .iinc(2, 1)
+                    .jump(JumpKind::Goto, loop_start)
+                    .place_label(handler)
+                    .store(LocalKind::Reference, 4)
+                    .place_label(loop_end)
+                    .instruction(Instruction::Return)
+                    .try_catch(
+                        try_start,
+                        try_end,
+                        handler,
+                        Some("java/lang/RuntimeException"),
+                    );
+            },
+        );
+
+        let table =
+            compute_stack_map_table("a/Test", &method, &constants, &ObjectClassHierarchy).unwrap();
+        let locals = vec![
+            object("a/Test"),
+            object("[Ljava/lang/String;"),
+            VerificationType::Integer,
+        ];
+        assert_eq!(
+            &[
+                StackMapFrame {
+                    program_counter: ProgramCounter(6),
+                    locals: locals.clone(),
+                    stack: vec![],
+                },
+                StackMapFrame {
+                    program_counter: ProgramCounter(22),
+                    locals: locals.clone(),
+                    stack: vec![object("java/lang/RuntimeException")],
+                },
+                StackMapFrame {
+                    program_counter: ProgramCounter(24),
+                    locals,
+                    stack: vec![],
+                },
+            ],
+            table.frames()
+        );
+    }
+
+    #[test]
+    fn can_encode_and_decode_all_frame_types() {
+        let initial = vec![object("a/Test")];
+        let table = StackMapTable::new(vec![
+            StackMapFrame {
+                program_counter: ProgramCounter(3),
+                locals: initial.clone(),
+                stack: vec![],
+            },
+            StackMapFrame {
+                program_counter: ProgramCounter(100),
+                locals: initial.clone(),
+                stack: vec![VerificationType::Integer],
+            },
+            StackMapFrame {
+                program_counter: ProgramCounter(101),
+                locals: vec![
+                    object("a/Test"),
+                    VerificationType::Long,
+                    VerificationType::Uninitialized(ProgramCounter(7)),
+                ],
+                stack: vec![],
+            },
+            StackMapFrame {
+                program_counter: ProgramCounter(110),
+                locals: initial.clone(),
+                stack: vec![],
+            },
+            StackMapFrame {
+                program_counter: ProgramCounter(120),
+                locals: vec![],
+                stack: vec![VerificationType::Null, VerificationType::Double],
+            },
+        ]);
+
+        let mut constants = ConstantPool::new();
+        let bytes = table.encode(&initial, &mut constants).unwrap();
+        assert_eq!(
+            vec![
+                0, 5, // number of frames
+                3, // same
+                247, 0, 96, 1, // same_locals_1_stack_item_extended
+                253, 0, 0, 4, 8, 0, 7, // append
+                249, 0, 8, // chop
+                255, 0, 9, 0, 0, 0, 2, 5, 3 // full
+            ],
+            bytes
+        );
+        assert_eq!(
+            table,
+            StackMapTable::decode(&bytes, &initial, &constants).unwrap()
+        );
+    }
+
+    #[test]
+    fn initial_frame_of_constructor_has_uninitialized_this() {
+        let mut constants = ConstantPool::new();
+        let method = test_method(
+            &mut constants,
+            MethodFlags::empty(),
+            "<init>",
+            "(I)V",
+            |code| {
+                code.load(LocalKind::Reference, 0)
+                    .invokespecial("java/lang/Object", "<init>", "()V")
+                    .instruction(Instruction::Return);
+            },
+        );
+        assert_eq!(
+            vec![
+                VerificationType::UninitializedThis,
+                VerificationType::Integer
+            ],
+            initial_locals("a/Test", &method)
+        );
+    }
+}
@@ -0,0 +1,1364 @@
+use std::{collections::HashMap, fmt, fmt::Formatter};
+
+use thiserror::Error;
+
+use crate::{
+    class_file::ClassFile,
+    class_file_method::{ClassFileMethod, ClassFileMethodCode},
+    class_hierarchy::ClassHierarchy,
+    class_reader_error::ClassReaderError,
+    constant_pool::{ConstantPoolEntry, MemberReference},
+    field_type::FieldType,
+    instruction::{Instruction, LocalKind, LocalVariableAccess},
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+    slot_frame::{class_of_descriptor, primitive_array_descriptor, SlotFrame},
+    stack_map_table::{initial_locals, StackMapFrame, StackMapTable, VerificationType},
+    type_conversion::ToUsizeSafe,
+};
+
+/// An error found by the verifier, that would make the JVM throw a `VerifyError`
+#[derive(Error, Debug, PartialEq, Eq)]
+pub struct VerifyError {
+    /// Name and descriptor of the method, i.e. `foo(I)V`
+    pub method: String,
+    /// Address of the instruction being verified, if the problem is in the code
+    pub address: Option<usize>,
+    pub kind: VerifyErrorKind,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.method, self.kind)?;
+        if let Some(address) = self.address {
+            write!(f, " at address {address}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The problems found by the verifier
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum VerifyErrorKind {
+    #[error("missing Code attribute")]
+    MissingCode,
+    #[error(transparent)]
+    InvalidClassData(#[from] ClassReaderError),
+    #[error("stack map frame at address {0}, which is not an instruction")]
+    FrameNotAtInstruction(usize),
+    #[error("stack map frame at address {0} has more locals than max_locals")]
+    FrameExceedsMaxLocals(ProgramCounter),
+    #[error("stack map frame at address {0} has a larger stack than max_stack")]
+    FrameExceedsMaxStack(ProgramCounter),
+    #[error("the arguments do not fit in max_locals")]
+    ArgumentsExceedMaxLocals,
+    #[error("exception handler {handler} has an invalid range {start}..{end}")]
+    InvalidHandlerRange {
+        handler: usize,
+        start: usize,
+        end: usize,
+    },
+    #[error(
+        "exception handler {handler} starts at address {address}, which is not an instruction"
+    )]
+    HandlerNotAtInstruction { handler: usize, address: usize },
+    #[error("exception handler {handler} catches {class}, which is not a throwable")]
+    HandlerCatchesNonThrowable { handler: usize, class: String },
+    #[error("missing stack map frame after an unconditional jump")]
+    MissingFrameAfterJump,
+    #[error("execution falls off the end of the code")]
+    FallsOffEndOfCode,
+    #[error("jump to address {0}, which is not an instruction")]
+    InvalidJumpTarget(usize),
+    #[error("missing stack map frame at jump target {0}")]
+    MissingFrameAtJumpTarget(usize),
+    #[error("stack height {height} does not match the stack map frame height {expected}")]
+    StackHeightMismatch { height: usize, expected: usize },
+    #[error("local {index} of type {found} does not match the stack map frame type {expected}")]
+    LocalMismatch {
+        index: usize,
+        found: VerificationType,
+        expected: VerificationType,
+    },
+    #[error(
+        "stack slot {index} of type {found} does not match the stack map frame type {expected}"
+    )]
+    StackSlotMismatch {
+        index: usize,
+        found: VerificationType,
+        expected: VerificationType,
+    },
+    #[error("operand stack overflow")]
+    StackOverflow,
+    #[error("operand stack underflow")]
+    StackUnderflow,
+    #[error("instruction splits a long or double value")]
+    SplitsWideValue,
+    #[error("pop requires a single-slot value")]
+    PopOfWideValue,
+    #[error("expected {expected} on the stack, found {found}")]
+    UnexpectedType {
+        expected: VerificationType,
+        found: VerificationType,
+    },
+    #[error("expected a reference on the stack, found {0}")]
+    ExpectedReference(VerificationType),
+    #[error("expected an array on the stack, found {0}")]
+    ExpectedArray(VerificationType),
+    /// An array whose components do not have the expected descriptor, i.e. `I` or `B or Z`
+    #[error("expected an array of {expected}, found an array of {found}")]
+    UnexpectedArrayComponents { expected: String, found: String },
+    #[error("local variable {0} exceeds max_locals")]
+    LocalOutOfRange(u16),
+    #[error("expected {expected} in local {index}, found {found}")]
+    UnexpectedLocalType {
+        index: u16,
+        expected: VerificationType,
+        found: VerificationType,
+    },
+    #[error("expected a reference in local {index}, found {found}")]
+    ExpectedReferenceInLocal { index: u16, found: VerificationType },
+    #[error("subroutines are not supported")]
+    SubroutinesNotSupported,
+    #[error("invokedynamic is not supported")]
+    InvokedynamicNotSupported,
+    /// `ldc` or `ldc_w` referring to a long or double constant
+    #[error("{0} cannot load a long or double constant")]
+    LoadOfWideConstant(&'static str),
+    #[error("ldc2_w can only load a long or double constant")]
+    LoadOfNarrowConstant,
+    #[error("cannot return a value from a void method")]
+    ReturnFromVoidMethod,
+    #[error("{instruction} does not match the return type {expected}")]
+    ReturnTypeMismatch {
+        instruction: &'static str,
+        expected: VerificationType,
+    },
+    #[error("missing return value of type {0}")]
+    MissingReturnValue(VerificationType),
+    #[error("constructor returns before invoking the superclass constructor")]
+    ThisNotInitialized,
+    #[error("new cannot create the array {0}")]
+    NewOfArray(String),
+    #[error("the object created by new is still uninitialized")]
+    NewOfUninitializedObject,
+    #[error("multianewarray with {dimensions} dimensions for type {class}")]
+    InvalidDimensions { dimensions: u8, class: String },
+    #[error("unexpected instruction {0:?}")]
+    UnexpectedInstruction(Instruction),
+    #[error("invalid invocation of {0}")]
+    InvalidInvocation(String),
+    #[error("<init> must return void")]
+    NonVoidConstructor,
+    #[error("the constructor of {0} cannot initialize this")]
+    InvalidConstructorOfThis(String),
+    #[error("no new instruction at address {0}")]
+    MissingNew(ProgramCounter),
+    #[error("the constructor of {constructor} cannot initialize an instance of {class}")]
+    InvalidConstructor { constructor: String, class: String },
+    #[error("expected an uninitialized object on the stack, found {0}")]
+    ExpectedUninitialized(VerificationType),
+    #[error("constant #{0} is not a class")]
+    NotAClass(u16),
+    #[error("constant #{0} cannot be loaded")]
+    NotLoadable(u16),
+}
+
+type Result<T> = std::result::Result<T, VerifyErrorKind>;
+
+/// Verifies the code of a method by type checking, as the JVM does for class files with
+/// version 50 or later: https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-4.html#jvms-4.10.1
+///
+/// Every instruction is checked against the types declared by the frames of the
+/// `StackMapTable` attribute, which must be present at every jump target, exception handler,
+/// and after every unconditional jump. The hierarchy is used to check that a class can be
+/// assigned to another one.
+///
+/// Subroutines (`jsr` and `ret`) and `invokedynamic` are not supported.
+pub fn verify_method(
+    class: &ClassFile,
+    method: &ClassFileMethod,
+    hierarchy: &dyn ClassHierarchy,
+) -> std::result::Result<(), VerifyError> {
+    let error = |address, kind| VerifyError {
+        method: format!("{}{}", method.name, method.type_descriptor),
+        address,
+        kind,
+    };
+    let Some(code) = &method.code else {
+        return if method.is_native() || method.flags.contains(MethodFlags::ABSTRACT) {
+            Ok(())
+        } else {
+            Err(error(None, VerifyErrorKind::MissingCode))
+        };
+    };
+
+    let instructions = Instruction::parse_instructions(&code.code)
+        .map_err(|err| error(None, VerifyErrorKind::from(err)))?;
+    let mut verifier = MethodVerifier::new(class, method, code, &instructions, hierarchy)
+        .map_err(|kind| error(None, kind))?;
+    verifier
+        .check_exception_table()
+        .map_err(|kind| error(None, kind))?;
+    verifier
+        .verify()
+        .map_err(|(address, kind)| error(Some(address), kind))
+}
+
+/// Verifies all the methods of the class, see [verify_method]
+pub fn verify_class(
+    class: &ClassFile,
+    hierarchy: &dyn ClassHierarchy,
+) -> std::result::Result<(), VerifyError> {
+    class
+        .methods
+        .iter()
+        .try_for_each(|method| verify_method(class, method, hierarchy))
+}
+
+struct MethodVerifier<'a> {
+    class: &'a ClassFile,
+    method: &'a ClassFileMethod,
+    code: &'a ClassFileMethodCode,
+    instructions: &'a [(usize, Instruction)],
+    hierarchy: &'a dyn ClassHierarchy,
+    index_of_address: HashMap<usize, usize>,
+    /// The declared frames, by address
+    frames: HashMap<usize, SlotFrame>,
+    max_stack: usize,
+    max_locals: usize,
+}
+
+impl<'a> MethodVerifier<'a> {
+    fn new(
+        class: &'a ClassFile,
+        method: &'a ClassFileMethod,
+        code: &'a ClassFileMethodCode,
+        instructions: &'a [(usize, Instruction)],
+        hierarchy: &'a dyn ClassHierarchy,
+    ) -> Result<Self> {
+        let mut verifier = Self {
+            class,
+            method,
+            code,
+            instructions,
+            hierarchy,
+            index_of_address: instructions
+                .iter()
+                .enumerate()
+                .map(|(index, (address, _))| (*address, index))
+                .collect(),
+            frames: HashMap::new(),
+            max_stack: code.max_stack.into_usize_safe(),
+            max_locals: code.max_locals.into_usize_safe(),
+        };
+
+        let initial_locals = initial_locals(&class.name, method);
+        let stack_map_table = match code
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name == "StackMapTable")
+        {
+            Some(attribute) => {
+                StackMapTable::decode(&attribute.bytes, &initial_locals, &class.constants)?
+            }
+            None => StackMapTable::default(),
+        };
+        for frame in stack_map_table.frames() {
+            let address = frame.program_counter.0.into_usize_safe();
+            if !verifier.index_of_address.contains_key(&address) {
+                return Err(VerifyErrorKind::FrameNotAtInstruction(address));
+            }
+            let frame = verifier.declared_frame(frame)?;
+            verifier.frames.insert(address, frame);
+        }
+        Ok(verifier)
+    }
+
+    /// Converts a frame of the `StackMapTable` to slots, checking it against the maximum
+    /// number of locals and stack size of the code
+    fn declared_frame(&self, frame: &StackMapFrame) -> Result<SlotFrame> {
+        let mut locals = SlotFrame::slots_of(&frame.locals);
+        if locals.len() > self.max_locals {
+            return Err(VerifyErrorKind::FrameExceedsMaxLocals(
+                frame.program_counter,
+            ));
+        }
+        locals.resize(self.max_locals, VerificationType::Top);
+        let stack = SlotFrame::slots_of(&frame.stack);
+        if stack.len() > self.max_stack {
+            return Err(VerifyErrorKind::FrameExceedsMaxStack(frame.program_counter));
+        }
+        Ok(SlotFrame { locals, stack })
+    }
+
+    fn initial_frame(&self) -> Result<SlotFrame> {
+        let mut locals = SlotFrame::slots_of(&initial_locals(&self.class.name, self.method));
+        if locals.len() > self.max_locals {
+            return Err(VerifyErrorKind::ArgumentsExceedMaxLocals);
+        }
+        locals.resize(self.max_locals, VerificationType::Top);
+        Ok(SlotFrame {
+            locals,
+            stack: Vec::new(),
+        })
+    }
+
+    /// Checks that the handlers cover valid ranges of instructions, and catch throwables
+    fn check_exception_table(&self) -> Result<()> {
+        let code_length = self.code.code.len();
+        for (handler_index, entry) in self.code.exception_table.entries().iter().enumerate() {
+            let start = entry.range.start.0.into_usize_safe();
+            let end = entry.range.end.0.into_usize_safe();
+            let handler = entry.handler_pc.0.into_usize_safe();
+            if start >= end
+                || !self.index_of_address.contains_key(&start)
+                || !(end == code_length || self.index_of_address.contains_key(&end))
+            {
+                return Err(VerifyErrorKind::InvalidHandlerRange {
+                    handler: handler_index,
+                    start,
+                    end,
+                });
+            }
+            if !self.index_of_address.contains_key(&handler) {
+                return Err(VerifyErrorKind::HandlerNotAtInstruction {
+                    handler: handler_index,
+                    address: handler,
+                });
+            }
+            if let Some(catch_class) = &entry.catch_class {
+                if !self.is_class_assignable(catch_class, "java/lang/Throwable") {
+                    return Err(VerifyErrorKind::HandlerCatchesNonThrowable {
+                        handler: handler_index,
+                        class: catch_class.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks every instruction in order, starting from the initial frame and switching to the
+    /// declared frame wherever there is one
+    fn verify(&mut self) -> std::result::Result<(), (usize, VerifyErrorKind)> {
+        let mut current = Some(self.initial_frame().map_err(|kind| (0, kind))?);
+        for (address, instruction) in self.instructions {
+            let address = *address;
+            let at = |kind| (address, kind);
+            let mut frame = match (current.take(), self.frames.get(&address)) {
+                (Some(frame), Some(declared)) => {
+                    self.check_assignable_frame(&frame, declared).map_err(at)?;
+                    declared.clone()
+                }
+                (None, Some(declared)) => declared.clone(),
+                (Some(frame), None) => frame,
+                (None, None) => return Err(at(VerifyErrorKind::MissingFrameAfterJump)),
+            };
+
+            let input_locals = frame.locals.clone();
+            self.execute(&mut frame, address, instruction).map_err(at)?;
+
+            let pc = ProgramCounter(address as u16);
+            for entry in self.code.exception_table.lookup(pc) {
+                let exception = VerificationType::Object(
+                    entry
+                        .catch_class
+                        .clone()
+                        .unwrap_or_else(|| "java/lang/Throwable".to_string()),
+                );
+                for locals in [&input_locals, &frame.locals] {
+                    let handler_frame = SlotFrame {
+                        locals: locals.clone(),
+                        stack: vec![exception.clone()],
+                    };
+                    self.check_jump(&handler_frame, entry.handler_pc.0)
+                        .map_err(at)?;
+                }
+            }
+            for target in instruction.jump_targets() {
+                self.check_jump(&frame, target).map_err(at)?;
+            }
+            if instruction.can_fall_through() {
+                current = Some(frame);
+            }
+        }
+        match current {
+            Some(_) => Err((self.code.code.len(), VerifyErrorKind::FallsOffEndOfCode)),
+            None => Ok(()),
+        }
+    }
+
+    fn check_jump(&self, frame: &SlotFrame, target: u16) -> Result<()> {
+        let target = target.into_usize_safe();
+        if !self.index_of_address.contains_key(&target) {
+            return Err(VerifyErrorKind::InvalidJumpTarget(target));
+        }
+        let declared = self
+            .frames
+            .get(&target)
+            .ok_or(VerifyErrorKind::MissingFrameAtJumpTarget(target))?;
+        self.check_assignable_frame(frame, declared)
+    }
+
+    fn check_assignable_frame(&self, from: &SlotFrame, to: &SlotFrame) -> Result<()> {
+        if from.stack.len() != to.stack.len() {
+            return Err(VerifyErrorKind::StackHeightMismatch {
+                height: from.stack.len(),
+                expected: to.stack.len(),
+            });
+        }
+        for (index, (from, to)) in from.locals.iter().zip(to.locals.iter()).enumerate() {
+            if !self.is_assignable(from, to) {
+                return Err(VerifyErrorKind::LocalMismatch {
+                    index,
+                    found: from.clone(),
+                    expected: to.clone(),
+                });
+            }
+        }
+        for (index, (from, to)) in from.stack.iter().zip(to.stack.iter()).enumerate() {
+            if !self.is_assignable(from, to) {
+                return Err(VerifyErrorKind::StackSlotMismatch {
+                    index,
+                    found: from.clone(),
+                    expected: to.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Assignability between verification types, as defined by JVMS §4.10.1.2
+    fn is_assignable(&self, from: &VerificationType, to: &VerificationType) -> bool {
+        match (from, to) {
+            _ if from == to => true,
+            (_, VerificationType::Top) => true,
+            (VerificationType::Null, VerificationType::Object(_)) => true,
+            (VerificationType::Object(from), VerificationType::Object(to)) => {
+                self.is_class_assignable(from, to)
+            }
+            _ => false,
+        }
+    }
+
+    /// Assignability between classes or arrays, given by name or array descriptor
+    fn is_class_assignable(&self, from: &str, to: &str) -> bool {
+        if from == to || to == "java/lang/Object" {
+            return true;
+        }
+        match (from.strip_prefix('['), to.strip_prefix('[')) {
+            (Some(from_component), Some(to_component)) => {
+                match (
+                    class_of_descriptor(from_component),
+                    class_of_descriptor(to_component),
+                ) {
+                    (Some(from_class), Some(to_class)) => {
+                        self.is_class_assignable(from_class, to_class)
+                    }
+                    _ => from_component == to_component,
+                }
+            }
+            (Some(_), None) => to == "java/lang/Cloneable" || to == "java/io/Serializable",
+            (None, Some(_)) => false,
+            (None, None) => self.hierarchy.is_assignable(from, to),
+        }
+    }
+
+    fn is_reference(value: &VerificationType) -> bool {
+        matches!(
+            value,
+            VerificationType::Object(_)
+                | VerificationType::Null
+                | VerificationType::UninitializedThis
+                | VerificationType::Uninitialized(_)
+        )
+    }
+
+    fn push(&self, frame: &mut SlotFrame, value: VerificationType) -> Result<()> {
+        if frame.stack.len() + value.slots().into_usize_safe() > self.max_stack {
+            return Err(VerifyErrorKind::StackOverflow);
+        }
+        frame.push(value);
+        Ok(())
+    }
+
+    /// Pops a value that must be assignable to the expected type
+    fn pop(&self, frame: &mut SlotFrame, expected: &VerificationType) -> Result<VerificationType> {
+        if expected.slots() == 2 {
+            Self::pop_slot(frame)?;
+        }
+        let value = Self::pop_slot(frame)?;
+        if !self.is_assignable(&value, expected) {
+            return Err(VerifyErrorKind::UnexpectedType {
+                expected: expected.clone(),
+                found: value,
+            });
+        }
+        Ok(value)
+    }
+
+    fn pop_slot(frame: &mut SlotFrame) -> Result<VerificationType> {
+        frame.stack.pop().ok_or(VerifyErrorKind::StackUnderflow)
+    }
+
+    fn pop_int(&self, frame: &mut SlotFrame) -> Result<()> {
+        self.pop(frame, &VerificationType::Integer).map(|_| ())
+    }
+
+    fn pop_reference(&self, frame: &mut SlotFrame) -> Result<VerificationType> {
+        let value = Self::pop_slot(frame)?;
+        if !Self::is_reference(&value) {
+            return Err(VerifyErrorKind::ExpectedReference(value));
+        }
+        Ok(value)
+    }
+
+    /// Pops an array reference, or null, and returns the descriptor of its components
+    fn pop_array(&self, frame: &mut SlotFrame) -> Result<Option<String>> {
+        match self.pop_reference(frame)? {
+            VerificationType::Null => Ok(None),
+            VerificationType::Object(descriptor) if descriptor.starts_with('[') => {
+                Ok(Some(descriptor[1..].to_string()))
+            }
+            value => Err(VerifyErrorKind::ExpectedArray(value)),
+        }
+    }
+
+    /// Pops the index and the array of an array load or store, checking that the components
+    /// have one of the given descriptors
+    fn pop_primitive_array(&self, frame: &mut SlotFrame, components: &[&str]) -> Result<()> {
+        self.pop_int(frame)?;
+        match self.pop_array(frame)? {
+            Some(component) if !components.contains(&component.as_str()) => {
+                Err(VerifyErrorKind::UnexpectedArrayComponents {
+                    expected: components.join(" or "),
+                    found: component,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn type_of_kind(kind: LocalKind) -> VerificationType {
+        match kind {
+            LocalKind::Int => VerificationType::Integer,
+            LocalKind::Long => VerificationType::Long,
+            LocalKind::Float => VerificationType::Float,
+            LocalKind::Double => VerificationType::Double,
+            LocalKind::Reference => VerificationType::Top,
+        }
+    }
+
+    fn local(&self, frame: &SlotFrame, index: u16, slots: u16) -> Result<VerificationType> {
+        let slot = index.into_usize_safe();
+        if slot + slots.into_usize_safe() > frame.locals.len() {
+            return Err(VerifyErrorKind::LocalOutOfRange(index));
+        }
+        Ok(frame.locals[slot].clone())
+    }
+
+    fn set_local(&self, frame: &mut SlotFrame, index: u16, value: VerificationType) -> Result<()> {
+        let slot = index.into_usize_safe();
+        let slots = value.slots().into_usize_safe();
+        if slot + slots > frame.locals.len() {
+            return Err(VerifyErrorKind::LocalOutOfRange(index));
+        }
+        if slot > 0 && frame.locals[slot - 1].slots() == 2 {
+            frame.locals[slot - 1] = VerificationType::Top;
+        }
+        frame.locals[slot] = value;
+        if slots == 2 {
+            frame.locals[slot + 1] = VerificationType::Top;
+        }
+        Ok(())
+    }
+
+    fn execute_local_access(
+        &self,
+        frame: &mut SlotFrame,
+        access: LocalVariableAccess,
+    ) -> Result<()> {
+        match access {
+            LocalVariableAccess::Load(LocalKind::Reference, index) => {
+                let value = self.local(frame, index, 1)?;
+                if !Self::is_reference(&value) {
+                    return Err(VerifyErrorKind::ExpectedReferenceInLocal {
+                        index,
+                        found: value,
+                    });
+                }
+                self.push(frame, value)
+            }
+            LocalVariableAccess::Load(kind, index) => {
+                let expected = Self::type_of_kind(kind);
+                let value = self.local(frame, index, kind.slots())?;
+                if value != expected {
+                    return Err(VerifyErrorKind::UnexpectedLocalType {
+                        index,
+                        expected,
+                        found: value,
+                    });
+                }
+                self.push(frame, value)
+            }
+            LocalVariableAccess::Store(LocalKind::Reference, index) => {
+                let value = self.pop_reference(frame)?;
+                self.set_local(frame, index, value)
+            }
+            LocalVariableAccess::Store(kind, index) => {
+                let value = self.pop(frame, &Self::type_of_kind(kind))?;
+                self.set_local(frame, index, value)
+            }
+            LocalVariableAccess::Increment(index) => {
+                let value = self.local(frame, index, 1)?;
+                if value != VerificationType::Integer {
+                    return Err(VerifyErrorKind::UnexpectedLocalType {
+                        index,
+                        expected: VerificationType::Integer,
+                        found: value,
+                    });
+                }
+                Ok(())
+            }
+            LocalVariableAccess::Ret(_) => Err(VerifyErrorKind::SubroutinesNotSupported),
+        }
+    }
+
+    /// Checks the types consumed by an instruction, and updates the frame with the types it
+    /// produces
+    fn execute(
+        &self,
+        frame: &mut SlotFrame,
+        address: usize,
+        instruction: &Instruction,
+    ) -> Result<()> {
+        use VerificationType::{Double, Float, Integer, Long};
+
+        if let Some(access) = instruction.local_variable_access() {
+            return self.execute_local_access(frame, access);
+        }
+
+        match instruction {
+            Instruction::Nop | Instruction::Goto { .. } | Instruction::Goto_w { .. } => {}
+            Instruction::Aconst_null => self.push(frame, VerificationType::Null)?,
+            Instruction::Iconst_m1
+            | Instruction::Iconst_0
+            | Instruction::Iconst_1
+            | Instruction::Iconst_2
+            | Instruction::Iconst_3
+            | Instruction::Iconst_4
+            | Instruction::Iconst_5
+            | Instruction::Bipush { .. }
+            | Instruction::Sipush { .. } => self.push(frame, Integer)?,
+            Instruction::Lconst_0 | Instruction::Lconst_1 => self.push(frame, Long)?,
+            Instruction::Fconst_0 | Instruction::Fconst_1 | Instruction::Fconst_2 => {
+                self.push(frame, Float)?
+            }
+            Instruction::Dconst_0 | Instruction::Dconst_1 => self.push(frame, Double)?,
+            Instruction::Ldc { index } => {
+                let value = self.constant_type(*index as u16)?;
+                if value.slots() == 2 {
+                    return Err(VerifyErrorKind::LoadOfWideConstant("ldc"));
+                }
+                self.push(frame, value)?
+            }
+            Instruction::Ldc_w { index } => {
+                let value = self.constant_type(*index)?;
+                if value.slots() == 2 {
+                    return Err(VerifyErrorKind::LoadOfWideConstant("ldc_w"));
+                }
+                self.push(frame, value)?
+            }
+            Instruction::Ldc2_w { index } => {
+                let value = self.constant_type(*index)?;
+                if value.slots() != 2 {
+                    return Err(VerifyErrorKind::LoadOfNarrowConstant);
+                }
+                self.push(frame, value)?
+            }
+
+            Instruction::Iaload => {
+                self.pop_primitive_array(frame, &["I"])?;
+                self.push(frame, Integer)?
+            }
+            Instruction::Baload => {
+                self.pop_primitive_array(frame, &["B", "Z"])?;
+                self.push(frame, Integer)?
+            }
+            Instruction::Caload => {
+                self.pop_primitive_array(frame, &["C"])?;
+                self.push(frame, Integer)?
+            }
+            Instruction::Saload => {
+                self.pop_primitive_array(frame, &["S"])?;
+                self.push(frame, Integer)?
+            }
+            Instruction::Laload => {
+                self.pop_primitive_array(frame, &["J"])?;
+                self.push(frame, Long)?
+            }
+            Instruction::Faload => {
+                self.pop_primitive_array(frame, &["F"])?;
+                self.push(frame, Float)?
+            }
+            Instruction::Daload => {
+                self.pop_primitive_array(frame, &["D"])?;
+                self.push(frame, Double)?
+            }
+            Instruction::Aaload => {
+                self.pop_int(frame)?;
+                let component = match self.pop_array(frame)? {
+                    None => VerificationType::Null,
+                    Some(component) => match class_of_descriptor(&component) {
+                        Some(class) => VerificationType::Object(class.to_string()),
+                        None => {
+                            return Err(VerifyErrorKind::UnexpectedArrayComponents {
+                                expected: "references".to_string(),
+                                found: component,
+                            })
+                        }
+                    },
+                };
+                self.push(frame, component)?
+            }
+            Instruction::Iastore => {
+                self.pop_int(frame)?;
+                self.pop_primitive_array(frame, &["I"])?
+            }
+            Instruction::Bastore => {
+                self.pop_int(frame)?;
+                self.pop_primitive_array(frame, &["B", "Z"])?
+            }
+            Instruction::Castore => {
+                self.pop_int(frame)?;
+                self.pop_primitive_array(frame, &["C"])?
+            }
+            Instruction::Sastore => {
+                self.pop_int(frame)?;
+                self.pop_primitive_array(frame, &["S"])?
+            }
+            Instruction::Lastore => {
+                self.pop(frame, &Long)?;
+                self.pop_primitive_array(frame, &["J"])?
+            }
+            Instruction::Fastore => {
+                self.pop(frame, &Float)?;
+                self.pop_primitive_array(frame, &["F"])?
+            }
+            Instruction::Dastore => {
+                self.pop(frame, &Double)?;
+                self.pop_primitive_array(frame, &["D"])?
+            }
+            Instruction::Aastore => {
+                // The type of the value is checked at runtime, against the actual array
+                self.pop_reference(frame)?;
+                self.pop_int(frame)?;
+                if let Some(component) = self.pop_array(frame)? {
+                    if class_of_descriptor(&component).is_none() {
+                        return Err(VerifyErrorKind::UnexpectedArrayComponents {
+                            expected: "references".to_string(),
+                            found: component,
+                        });
+                    }
+                }
+            }
+
+            Instruction::Pop => {
+                if frame.stack.is_empty() || frame.is_second_half(frame.stack.len() - 1) {
+                    return Err(VerifyErrorKind::PopOfWideValue);
+                }
+                frame.stack.pop();
+            }
+            Instruction::Pop2 => {
+                self.check_unsplit(frame, 2)?;
+                frame.stack.truncate(frame.stack.len() - 2);
+            }
+            Instruction::Dup => self.duplicate(frame, 1, 0)?,
+            Instruction::Dup_x1 => self.duplicate(frame, 1, 1)?,
+            Instruction::Dup_x2 => self.duplicate(frame, 1, 2)?,
+            Instruction::Dup2 => self.duplicate(frame, 2, 0)?,
+            Instruction::Dup2_x1 => self.duplicate(frame, 2, 1)?,
+            Instruction::Dup2_x2 => self.duplicate(frame, 2, 2)?,
+            Instruction::Swap => {
+                self.check_unsplit(frame, 1)?;
+                self.check_unsplit(frame, 2)?;
+                let first = Self::pop_slot(frame)?;
+                let second = Self::pop_slot(frame)?;
+                frame.stack.push(first);
+                frame.stack.push(second);
+            }
+
+            Instruction::Iadd
+            | Instruction::Isub
+            | Instruction::Imul
+            | Instruction::Idiv
+            | Instruction::Irem
+            | Instruction::Ishl
+            | Instruction::Ishr
+            | Instruction::Iushr
+            | Instruction::Iand
+            | Instruction::Ior
+            | Instruction::Ixor => self.binary(frame, &Integer, &Integer, Integer)?,
+            Instruction::Ladd
+            | Instruction::Lsub
+            | Instruction::Lmul
+            | Instruction::Ldiv
+            | Instruction::Lrem
+            | Instruction::Land
+            | Instruction::Lor
+            | Instruction::Lxor => self.binary(frame, &Long, &Long, Long)?,
+            Instruction::Lshl | Instruction::Lshr | Instruction::Lushr => {
+                self.binary(frame, &Long, &Integer, Long)?
+            }
+            Instruction::Fadd
+            | Instruction::Fsub
+            | Instruction::Fmul
+            | Instruction::Fdiv
+            | Instruction::Frem => self.binary(frame, &Float, &Float, Float)?,
+            Instruction::Dadd
+            | Instruction::Dsub
+            | Instruction::Dmul
+            | Instruction::Ddiv
+            | Instruction::Drem => self.binary(frame, &Double, &Double, Double)?,
+            Instruction::Lcmp => self.binary(frame, &Long, &Long, Integer)?,
+            Instruction::Fcmpl | Instruction::Fcmpg => {
+                self.binary(frame, &Float, &Float, Integer)?
+            }
+            Instruction::Dcmpl | Instruction::Dcmpg => {
+                self.binary(frame, &Double, &Double, Integer)?
+            }
+            Instruction::Ineg | Instruction::I2b | Instruction::I2c | Instruction::I2s => {
+                self.unary(frame, &Integer, Integer)?
+            }
+            Instruction::I2l => self.unary(frame, &Integer, Long)?,
+            Instruction::I2f => self.unary(frame, &Integer, Float)?,
+            Instruction::I2d => self.unary(frame, &Integer, Double)?,
+            Instruction::Lneg => self.unary(frame, &Long, Long)?,
+            Instruction::L2i => self.unary(frame, &Long, Integer)?,
+            Instruction::L2f => self.unary(frame, &Long, Float)?,
+            Instruction::L2d => self.unary(frame, &Long, Double)?,
+            Instruction::Fneg => self.unary(frame, &Float, Float)?,
+            Instruction::F2i => self.unary(frame, &Float, Integer)?,
+            Instruction::F2l => self.unary(frame, &Float, Long)?,
+            Instruction::F2d => self.unary(frame, &Float, Double)?,
+            Instruction::Dneg => self.unary(frame, &Double, Double)?,
+            Instruction::D2i => self.unary(frame, &Double, Integer)?,
+            Instruction::D2l => self.unary(frame, &Double, Long)?,
+            Instruction::D2f => self.unary(frame, &Double, Float)?,
+
+            Instruction::Ifeq { .. }
+            | Instruction::Ifne { .. }
+            | Instruction::Iflt { .. }
+            | Instruction::Ifge { .. }
+            | Instruction::Ifgt { .. }
+            | Instruction::Ifle { .. }
+            | Instruction::Tableswitch { .. }
+            | Instruction::Lookupswitch { .. } => self.pop_int(frame)?,
+            Instruction::If_icmpeq { .. }
+            | Instruction::If_icmpne { .. }
+            | Instruction::If_icmplt { .. }
+            | Instruction::If_icmpge { .. }
+            | Instruction::If_icmpgt { .. }
+            | Instruction::If_icmple { .. } => {
+                self.pop_int(frame)?;
+                self.pop_int(frame)?;
+            }
+            Instruction::If_acmpeq { .. } | Instruction::If_acmpne { .. } => {
+                self.pop_reference(frame)?;
+                self.pop_reference(frame)?;
+            }
+            Instruction::Ifnull { .. }
+            | Instruction::Ifnonnull { .. }
+            | Instruction::Monitorenter
+            | Instruction::Monitorexit => {
+                self.pop_reference(frame)?;
+            }
+
+            Instruction::Ireturn
+            | Instruction::Lreturn
+            | Instruction::Freturn
+            | Instruction::Dreturn
+            | Instruction::Areturn => {
+                let Some(return_type) = &self.method.parsed_type_descriptor.return_type else {
+                    return Err(VerifyErrorKind::ReturnFromVoidMethod);
+                };
+                let expected = VerificationType::from_field_type(return_type);
+                let matches_instruction = match instruction {
+                    Instruction::Ireturn => expected == Integer,
+                    Instruction::Lreturn => expected == Long,
+                    Instruction::Freturn => expected == Float,
+                    Instruction::Dreturn => expected == Double,
+                    _ => matches!(expected, VerificationType::Object(_)),
+                };
+                if !matches_instruction {
+                    return Err(VerifyErrorKind::ReturnTypeMismatch {
+                        instruction: instruction.into(),
+                        expected,
+                    });
+                }
+                self.pop(frame, &expected)?;
+            }
+            Instruction::Return => {
+                if let Some(return_type) = &self.method.parsed_type_descriptor.return_type {
+                    return Err(VerifyErrorKind::MissingReturnValue(
+                        VerificationType::from_field_type(return_type),
+                    ));
+                }
+                if self.method.name == "<init>"
+                    && frame.locals.contains(&VerificationType::UninitializedThis)
+                {
+                    return Err(VerifyErrorKind::ThisNotInitialized);
+                }
+            }
+            Instruction::Athrow => {
+                self.pop(
+                    frame,
+                    &VerificationType::Object("java/lang/Throwable".to_string()),
+                )?;
+            }
+
+            Instruction::Getstatic { field } => {
+                let (_, field_type) = self.field(*field)?;
+                self.push(frame, field_type)?
+            }
+            Instruction::Putstatic { field } => {
+                let (_, field_type) = self.field(*field)?;
+                self.pop(frame, &field_type)?;
+            }
+            Instruction::Getfield { field } => {
+                let (reference, field_type) = self.field(*field)?;
+                self.pop(frame, &VerificationType::Object(reference.class_name))?;
+                self.push(frame, field_type)?
+            }
+            Instruction::Putfield { field } => {
+                let (reference, field_type) = self.field(*field)?;
+                self.pop(frame, &field_type)?;
+                // Fields of the class can be set before invoking the superclass constructor
+                let receiver = Self::pop_slot(frame)?;
+                if !(receiver == VerificationType::UninitializedThis
+                    && reference.class_name == self.class.name)
+                {
+                    let expected = VerificationType::Object(reference.class_name);
+                    if !self.is_assignable(&receiver, &expected) {
+                        return Err(VerifyErrorKind::UnexpectedType {
+                            expected,
+                            found: receiver,
+                        });
+                    }
+                }
+            }
+
+            Instruction::Invokevirtual { method }
+            | Instruction::Invokespecial { method }
+            | Instruction::Invokestatic { method }
+            | Instruction::Invokeinterface { method, .. } => {
+                self.invoke(frame, instruction, *method)?
+            }
+            Instruction::Invokedynamic { .. } => {
+                return Err(VerifyErrorKind::InvokedynamicNotSupported)
+            }
+
+            Instruction::New { class } => {
+                let class_name = self.class_name_at(*class)?;
+                if class_name.starts_with('[') {
+                    return Err(VerifyErrorKind::NewOfArray(class_name));
+                }
+                let value = VerificationType::Uninitialized(ProgramCounter(address as u16));
+                if frame.stack.contains(&value) {
+                    return Err(VerifyErrorKind::NewOfUninitializedObject);
+                }
+                for local in frame.locals.iter_mut() {
+                    if *local == value {
+                        *local = VerificationType::Top;
+                    }
+                }
+                self.push(frame, value)?
+            }
+            Instruction::Newarray { array_type } => {
+                self.pop_int(frame)?;
+                self.push(
+                    frame,
+                    VerificationType::Object(primitive_array_descriptor(array_type).to_string()),
+                )?
+            }
+            Instruction::Anewarray { class } => {
+                self.pop_int(frame)?;
+                let class_name = self.class_name_at(*class)?;
+                self.push(
+                    frame,
+                    VerificationType::Object(if class_name.starts_with('[') {
+                        format!("[{class_name}")
+                    } else {
+                        format!("[L{class_name};")
+                    }),
+                )?
+            }
+            Instruction::Multianewarray { class, dimensions } => {
+                let class_name = self.class_name_at(*class)?;
+                let array_dimensions = class_name.chars().take_while(|c| *c == '[').count();
+                if *dimensions == 0 || *dimensions as usize > array_dimensions {
+                    return Err(VerifyErrorKind::InvalidDimensions {
+                        dimensions: *dimensions,
+                        class: class_name,
+                    });
+                }
+                for _ in 0..*dimensions {
+                    self.pop_int(frame)?;
+                }
+                self.push(frame, VerificationType::Object(class_name))?
+            }
+            Instruction::Arraylength => {
+                self.pop_array(frame)?;
+                self.push(frame, Integer)?
+            }
+            Instruction::Checkcast { class } => {
+                self.pop_reference(frame)?;
+                let class_name = self.class_name_at(*class)?;
+                self.push(frame, VerificationType::Object(class_name))?
+            }
+            Instruction::Instanceof { class } => {
+                self.pop_reference(frame)?;
+                self.class_name_at(*class)?;
+                self.push(frame, Integer)?
+            }
+
+            Instruction::Jsr { .. } | Instruction::Jsr_w { .. } => {
+                return Err(VerifyErrorKind::SubroutinesNotSupported)
+            }
+            _ => return Err(VerifyErrorKind::UnexpectedInstruction(instruction.clone())),
+        }
+        Ok(())
+    }
+
+    fn unary(
+        &self,
+        frame: &mut SlotFrame,
+        operand: &VerificationType,
+        result: VerificationType,
+    ) -> Result<()> {
+        self.pop(frame, operand)?;
+        self.push(frame, result)
+    }
+
+    /// Pops the second operand, then the first one, and pushes the result
+    fn binary(
+        &self,
+        frame: &mut SlotFrame,
+        first: &VerificationType,
+        second: &VerificationType,
+        result: VerificationType,
+    ) -> Result<()> {
+        self.pop(frame, second)?;
+        self.pop(frame, first)?;
+        self.push(frame, result)
+    }
+
+    /// Checks that the top `slots` entries of the stack do not start in the middle of a long
+    /// or double value
+    fn check_unsplit(&self, frame: &SlotFrame, slots: usize) -> Result<()> {
+        if frame.stack.len() < slots {
+            return Err(VerifyErrorKind::StackUnderflow);
+        }
+        if frame.is_second_half(frame.stack.len() - slots) {
+            return Err(VerifyErrorKind::SplitsWideValue);
+        }
+        Ok(())
+    }
+
+    /// Implements the `dup_x` family: copies the top `slots` entries of the stack, and
+    /// inserts them below the following `below` entries
+    fn duplicate(&self, frame: &mut SlotFrame, slots: usize, below: usize) -> Result<()> {
+        self.check_unsplit(frame, slots)?;
+        if slots == 1 && frame.is_second_half(frame.stack.len() - 1) {
+            return Err(VerifyErrorKind::SplitsWideValue);
+        }
+        if below > 0 {
+            self.check_unsplit(frame, slots + below)?;
+        }
+        if frame.stack.len() + slots > self.max_stack {
+            return Err(VerifyErrorKind::StackOverflow);
+        }
+        if !frame.duplicate(slots, below) {
+            return Err(VerifyErrorKind::StackUnderflow);
+        }
+        Ok(())
+    }
+
+    fn invoke(&self, frame: &mut SlotFrame, instruction: &Instruction, index: u16) -> Result<()> {
+        let reference = self.member_reference(index)?;
+        let descriptor = MethodDescriptor::parse(&reference.type_descriptor)?;
+        let is_init = reference.name == "<init>";
+        if reference.name == "<clinit>"
+            || (is_init && !matches!(instruction, Instruction::Invokespecial { .. }))
+        {
+            return Err(VerifyErrorKind::InvalidInvocation(reference.name));
+        }
+        if is_init && descriptor.return_type.is_some() {
+            return Err(VerifyErrorKind::NonVoidConstructor);
+        }
+
+        for parameter in descriptor.parameters.iter().rev() {
+            self.pop(frame, &VerificationType::from_field_type(parameter))?;
+        }
+        match instruction {
+            Instruction::Invokestatic { .. } => {}
+            Instruction::Invokespecial { .. } if is_init => {
+                let receiver = Self::pop_slot(frame)?;
+                let initialized = self.initialized_type(&receiver, &reference.class_name)?;
+                for value in frame.locals.iter_mut().chain(frame.stack.iter_mut()) {
+                    if *value == receiver {
+                        *value = initialized.clone();
+                    }
+                }
+            }
+            Instruction::Invokespecial { .. } => {
+                self.pop(frame, &VerificationType::Object(self.class.name.clone()))?;
+            }
+            // Interfaces are treated like java/lang/Object by the verifier
+            Instruction::Invokeinterface { .. } => {
+                self.pop_reference(frame)?;
+            }
+            _ => {
+                self.pop(frame, &VerificationType::Object(reference.class_name))?;
+            }
+        }
+        if let Some(return_type) = &descriptor.return_type {
+            self.push(frame, VerificationType::from_field_type(return_type))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the type of an uninitialized object once the constructor of the given class has
+    /// been invoked on it
+    fn initialized_type(
+        &self,
+        receiver: &VerificationType,
+        constructor_class: &str,
+    ) -> Result<VerificationType> {
+        match receiver {
+            VerificationType::UninitializedThis => {
+                let superclass = self.class.superclass.as_deref();
+                if constructor_class != self.class.name && Some(constructor_class) != superclass {
+                    return Err(VerifyErrorKind::InvalidConstructorOfThis(
+                        constructor_class.to_string(),
+                    ));
+                }
+                Ok(VerificationType::Object(self.class.name.clone()))
+            }
+            VerificationType::Uninitialized(pc) => {
+                let class = self
+                    .index_of_address
+                    .get(&pc.0.into_usize_safe())
+                    .and_then(|index| match self.instructions[*index].1 {
+                        Instruction::New { class } => Some(class),
+                        _ => None,
+                    })
+                    .ok_or(VerifyErrorKind::MissingNew(*pc))?;
+                let class_name = self.class_name_at(class)?;
+                if class_name != constructor_class {
+                    return Err(VerifyErrorKind::InvalidConstructor {
+                        constructor: constructor_class.to_string(),
+                        class: class_name,
+                    });
+                }
+                Ok(VerificationType::Object(class_name))
+            }
+            value => Err(VerifyErrorKind::ExpectedUninitialized(value.clone())),
+        }
+    }
+
+    fn member_reference(&self, index: u16) -> Result<MemberReference> {
+        self.class
+            .constants
+            .member_reference(index)
+            .map_err(|err| ClassReaderError::from(err).into())
+    }
+
+    fn field(&self, index: u16) -> Result<(MemberReference, VerificationType)> {
+        let reference = self.member_reference(index)?;
+        let field_type = FieldType::parse(&reference.type_descriptor)?;
+        Ok((reference, VerificationType::from_field_type(&field_type)))
+    }
+
+    fn class_name_at(&self, index: u16) -> Result<String> {
+        match self.class.constants.get(index) {
+            Ok(ConstantPoolEntry::ClassReference(name)) => self
+                .class
+                .constants
+                .text_of(*name)
+                .map_err(|err| ClassReaderError::from(err).into()),
+            _ => Err(VerifyErrorKind::NotAClass(index)),
+        }
+    }
+
+    fn constant_type(&self, index: u16) -> Result<VerificationType> {
+        let entry = self
+            .class
+            .constants
+            .get(index)
+            .map_err(ClassReaderError::from)?;
+        Ok(match entry {
+            ConstantPoolEntry::Integer(_) => VerificationType::Integer,
+            ConstantPoolEntry::Float(_) => VerificationType::Float,
+            ConstantPoolEntry::Long(_) => VerificationType::Long,
+            ConstantPoolEntry::Double(_) => VerificationType::Double,
+            ConstantPoolEntry::StringReference(_) => {
+                VerificationType::Object("java/lang/String".to_string())
+            }
+            ConstantPoolEntry::ClassReference(_) => {
+                VerificationType::Object("java/lang/Class".to_string())
+            }
+            _ => return Err(VerifyErrorKind::NotLoadable(index)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        class_builder::ClassBuilder,
+        class_file::ClassFile,
+        class_hierarchy::{MapClassHierarchy, ObjectClassHierarchy},
+        code_builder::{CodeBuilder, JumpKind, LocalKind},
+        instruction::Instruction,
+        method_descriptor::MethodDescriptor,
+        method_flags::MethodFlags,
+        verifier::{verify_class, verify_method, VerifyError},
+    };
+
+    fn class(descriptor: &str, build: impl FnOnce(&mut CodeBuilder)) -> ClassFile {
+        let mut builder = ClassBuilder::new("a/A");
+        builder.method(
+            MethodFlags::PUBLIC | MethodFlags::STATIC,
+            "test",
+            MethodDescriptor::parse(descriptor).unwrap(),
+            build,
+        );
+        builder.build().unwrap()
+    }
+
+    fn verify(class: &ClassFile) -> Result<(), VerifyError> {
+        verify_method(class, &class.methods[0], &ObjectClassHierarchy)
+    }
+
+    fn error_message(class: &ClassFile) -> String {
+        verify(class).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn accepts_branches_and_object_creation() {
+        let class = class("(I)Ljava/lang/Object;", |code| {
+            let else_label = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, else_label)
+                .new_object("java/lang/StringBuilder")
+                .instruction(Instruction::Dup)
+                .invokespecial("java/lang/StringBuilder", "<init>", "()V")
+                .instruction(Instruction::Areturn)
+                .place_label(else_label)
+                .instruction(Instruction::Aconst_null)
+                .instruction(Instruction::Areturn);
+        });
+        assert_eq!(Ok(()), verify(&class));
+    }
+
+    #[test]
+    fn rejects_operands_of_the_wrong_type() {
+        let class = class("()I", |code| {
+            code.iconst(1)
+                .fconst(1.0)
+                .instruction(Instruction::Iadd)
+                .instruction(Instruction::Ireturn);
+        });
+        assert_eq!(
+            "test()I: expected int on the stack, found float at address 2",
+            error_message(&class)
+        );
+    }
+
+    #[test]
+    fn rejects_return_of_the_wrong_type() {
+        let class = class("()I", |code| {
+            code.fconst(0.0).instruction(Instruction::Freturn);
+        });
+        assert_eq!(
+            "test()I: freturn does not match the return type int at address 1",
+            error_message(&class)
+        );
+    }
+
+    #[test]
+    fn rejects_uninitialized_objects() {
+        let class = class("()V", |code| {
+            code.new_object("java/lang/Object")
+                .invokevirtual("java/lang/Object", "hashCode", "()I")
+                .instruction(Instruction::Pop)
+                .instruction(Instruction::Return);
+        });
+        assert_eq!(
+            "test()V: expected class java/lang/Object on the stack, found uninitialized 0 at address 3",
+            error_message(&class)
+        );
+    }
+
+    #[test]
+    fn rejects_constructors_not_invoking_the_superclass_constructor() {
+        let mut builder = ClassBuilder::new("a/A");
+        builder.method(
+            MethodFlags::PUBLIC,
+            "<init>",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                code.instruction(Instruction::Return);
+            },
+        );
+        let class = builder.build().unwrap();
+        assert_eq!(
+            "<init>()V: constructor returns before invoking the superclass constructor at address 0",
+            error_message(&class)
+        );
+    }
+
+    #[test]
+    fn requires_frames_at_jump_targets() {
+        let mut class = class("(I)I", |code| {
+            let else_label = code.new_label();
+            code.load(LocalKind::Int, 0)
+                .jump(JumpKind::Ifeq, else_label)
+                .iconst(1)
+                .instruction(Instruction::Ireturn)
+                .place_label(else_label)
+                .iconst(0)
+                .instruction(Instruction::Ireturn);
+        });
+        assert_eq!(Ok(()), verify(&class));
+
+        class.methods[0]
+            .code
+            .as_mut()
+            .unwrap()
+            .attributes
+            .retain(|attribute| attribute.name != "StackMapTable");
+        assert_eq!(
+            "test(I)I: missing stack map frame at jump target 6 at address 1",
+            error_message(&class)
+        );
+    }
+
+    #[test]
+    fn checks_assignability_with_the_hierarchy() {
+        let class = class("()La/Base;", |code| {
+            code.new_object("a/Child")
+                .instruction(Instruction::Dup)
+                .invokespecial("a/Child", "<init>", "()V")
+                .instruction(Instruction::Areturn);
+        });
+        let mut hierarchy = MapClassHierarchy::new();
+        assert!(verify_class(&class, &hierarchy).is_err());
+
+        hierarchy.add("a/Child", "a/Base");
+        assert_eq!(Ok(()), verify_class(&class, &hierarchy));
+    }
+}
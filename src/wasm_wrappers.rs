@@ -8,6 +8,7 @@ use crate::{
     class_file_field::{ClassFileField, FieldConstantValue},
     class_file_method::{ClassFileMethod, ClassFileMethodCode},
     class_file_version::ClassFileVersion,
+    class_reader_error::ClassReaderError,
     constant_pool::ConstantPoolEntry,
     exception_table::ExceptionTable,
     field_flags::FieldFlags,
@@ -15,7 +16,9 @@ use crate::{
     line_number_table::LineNumberTable,
     method_descriptor::MethodDescriptor,
     method_flags::MethodFlags,
-    read_buffer,
+    read_buffer_with_options,
+    read_options::ReadOptions,
+    span::{hex_dump, LabeledSpan},
 };
 
 #[derive(Debug, Serialize, Tsify)]
@@ -31,6 +34,7 @@ pub struct WasmClass {
     pub fields: Vec<WasmField>,
     pub methods: Vec<WasmMethod>,
     pub constant_pool: Vec<WasmConstantPoolEntry>,
+    pub spans: Vec<LabeledSpan>,
 }
 
 // TODO: not sure if there is some better way to do this with bitflags
@@ -125,18 +129,28 @@ pub struct WasmConstantPoolEntry {
     constant: ConstantPoolEntry,
 }
 
-impl From<ClassFile> for WasmClass {
-    fn from(class: ClassFile) -> Self {
-        Self {
+impl TryFrom<ClassFile> for WasmClass {
+    type Error = ClassReaderError;
+
+    fn try_from(class: ClassFile) -> Result<Self, Self::Error> {
+        Ok(Self {
             version: class.version,
-            flags: class.flags.iter().map(|f| f.into()).collect(),
+            flags: class
+                .flags
+                .iter()
+                .filter_map(|f| f.try_into().ok())
+                .collect(),
             name: class.name,
             superclass: class.superclass,
             interfaces: class.interfaces,
             deprecated: class.deprecated,
             source_file: class.source_file,
             fields: class.fields.into_iter().map(|f| f.into()).collect(),
-            methods: class.methods.into_iter().map(|f| f.into()).collect(),
+            methods: class
+                .methods
+                .into_iter()
+                .map(|f| f.try_into())
+                .collect::<Result<_, _>>()?,
             constant_pool: class
                 .constants
                 .iter()
@@ -145,22 +159,26 @@ impl From<ClassFile> for WasmClass {
                     constant: constant.clone(),
                 })
                 .collect(),
-        }
+            spans: class.spans,
+        })
     }
 }
 
-impl From<ClassAccessFlags> for WasmClassFlag {
-    fn from(flag: ClassAccessFlags) -> Self {
+/// Fails for the bits that do not correspond to a known flag
+impl TryFrom<ClassAccessFlags> for WasmClassFlag {
+    type Error = ClassAccessFlags;
+
+    fn try_from(flag: ClassAccessFlags) -> Result<Self, Self::Error> {
         match flag {
-            ClassAccessFlags::PUBLIC => Self::Public,
-            ClassAccessFlags::FINAL => Self::Final,
-            ClassAccessFlags::SUPER => Self::Super,
-            ClassAccessFlags::INTERFACE => Self::Interface,
-            ClassAccessFlags::ABSTRACT => Self::Abstract,
-            ClassAccessFlags::SYNTHETIC => Self::Synthetic,
-            ClassAccessFlags::ANNOTATION => Self::Annotation,
-            ClassAccessFlags::ENUM => Self::Enum,
-            _ => panic!("Unknown flag: {:?}", flag),
+            ClassAccessFlags::PUBLIC => Ok(Self::Public),
+            ClassAccessFlags::FINAL => Ok(Self::Final),
+            ClassAccessFlags::SUPER => Ok(Self::Super),
+            ClassAccessFlags::INTERFACE => Ok(Self::Interface),
+            ClassAccessFlags::ABSTRACT => Ok(Self::Abstract),
+            ClassAccessFlags::SYNTHETIC => Ok(Self::Synthetic),
+            ClassAccessFlags::ANNOTATION => Ok(Self::Annotation),
+            ClassAccessFlags::ENUM => Ok(Self::Enum),
+            _ => Err(flag),
         }
     }
 }
@@ -168,7 +186,11 @@ impl From<ClassAccessFlags> for WasmClassFlag {
 impl From<ClassFileField> for WasmField {
     fn from(value: ClassFileField) -> Self {
         Self {
-            flags: value.flags.iter().map(|f| f.into()).collect(),
+            flags: value
+                .flags
+                .iter()
+                .filter_map(|f| f.try_into().ok())
+                .collect(),
             name: value.name,
             type_descriptor: value.type_descriptor.to_string(),
             constant_value: value.constant_value,
@@ -177,71 +199,84 @@ impl From<ClassFileField> for WasmField {
     }
 }
 
-impl From<FieldFlags> for WasmFieldFlag {
-    fn from(flag: FieldFlags) -> Self {
+/// Fails for the bits that do not correspond to a known flag
+impl TryFrom<FieldFlags> for WasmFieldFlag {
+    type Error = FieldFlags;
+
+    fn try_from(flag: FieldFlags) -> Result<Self, Self::Error> {
         match flag {
-            FieldFlags::PUBLIC => Self::Public,
-            FieldFlags::PRIVATE => Self::Private,
-            FieldFlags::PROTECTED => Self::Protected,
-            FieldFlags::STATIC => Self::Static,
-            FieldFlags::FINAL => Self::Final,
-            FieldFlags::VOLATILE => Self::Volatile,
-            FieldFlags::TRANSIENT => Self::Transient,
-            FieldFlags::SYNTHETIC => Self::Synthetic,
-            FieldFlags::ENUM => Self::Enum,
-            _ => panic!("Unknown flag: {:?}", flag),
+            FieldFlags::PUBLIC => Ok(Self::Public),
+            FieldFlags::PRIVATE => Ok(Self::Private),
+            FieldFlags::PROTECTED => Ok(Self::Protected),
+            FieldFlags::STATIC => Ok(Self::Static),
+            FieldFlags::FINAL => Ok(Self::Final),
+            FieldFlags::VOLATILE => Ok(Self::Volatile),
+            FieldFlags::TRANSIENT => Ok(Self::Transient),
+            FieldFlags::SYNTHETIC => Ok(Self::Synthetic),
+            FieldFlags::ENUM => Ok(Self::Enum),
+            _ => Err(flag),
         }
     }
 }
 
-impl From<ClassFileMethod> for WasmMethod {
-    fn from(method: ClassFileMethod) -> Self {
-        Self {
-            flags: method.flags.iter().map(|f| f.into()).collect(),
+impl TryFrom<ClassFileMethod> for WasmMethod {
+    type Error = ClassReaderError;
+
+    fn try_from(method: ClassFileMethod) -> Result<Self, Self::Error> {
+        Ok(Self {
+            flags: method
+                .flags
+                .iter()
+                .filter_map(|f| f.try_into().ok())
+                .collect(),
             name: method.name,
             type_descriptor: method.type_descriptor.to_string(),
             parsed_type_descriptor: method.parsed_type_descriptor,
             deprecated: method.deprecated,
             thrown_exceptions: method.thrown_exceptions,
-            code: method.code.map(|c| c.into()),
-        }
+            code: method.code.map(|c| c.try_into()).transpose()?,
+        })
     }
 }
 
-impl From<MethodFlags> for WasmMethodFlag {
-    fn from(flag: MethodFlags) -> Self {
+/// Fails for the bits that do not correspond to a known flag
+impl TryFrom<MethodFlags> for WasmMethodFlag {
+    type Error = MethodFlags;
+
+    fn try_from(flag: MethodFlags) -> Result<Self, Self::Error> {
         match flag {
-            MethodFlags::PUBLIC => Self::Public,
-            MethodFlags::PRIVATE => Self::Private,
-            MethodFlags::PROTECTED => Self::Protected,
-            MethodFlags::STATIC => Self::Static,
-            MethodFlags::FINAL => Self::Final,
-            MethodFlags::SYNCHRONIZED => Self::Synchronized,
-            MethodFlags::BRIDGE => Self::Bridge,
-            MethodFlags::VARARGS => Self::Vargargs,
-            MethodFlags::NATIVE => Self::Native,
-            MethodFlags::ABSTRACT => Self::Abstract,
-            MethodFlags::STRICT => Self::Strict,
-            MethodFlags::SYNTHETIC => Self::Synthetic,
-            _ => panic!("Unknown flag: {:?}", flag),
+            MethodFlags::PUBLIC => Ok(Self::Public),
+            MethodFlags::PRIVATE => Ok(Self::Private),
+            MethodFlags::PROTECTED => Ok(Self::Protected),
+            MethodFlags::STATIC => Ok(Self::Static),
+            MethodFlags::FINAL => Ok(Self::Final),
+            MethodFlags::SYNCHRONIZED => Ok(Self::Synchronized),
+            MethodFlags::BRIDGE => Ok(Self::Bridge),
+            MethodFlags::VARARGS => Ok(Self::Vargargs),
+            MethodFlags::NATIVE => Ok(Self::Native),
+            MethodFlags::ABSTRACT => Ok(Self::Abstract),
+            MethodFlags::STRICT => Ok(Self::Strict),
+            MethodFlags::SYNTHETIC => Ok(Self::Synthetic),
+            _ => Err(flag),
         }
     }
 }
 
-impl From<ClassFileMethodCode> for WasmMethodCode {
-    fn from(value: ClassFileMethodCode) -> Self {
-        Self {
+impl TryFrom<ClassFileMethodCode> for WasmMethodCode {
+    type Error = ClassReaderError;
+
+    fn try_from(value: ClassFileMethodCode) -> Result<Self, Self::Error> {
+        Ok(Self {
             max_stack: value.max_stack,
             max_locals: value.max_locals,
-            instructions: Instruction::parse_instructions(&value.code)
-                .unwrap()
+            instructions: Instruction::parse_instructions(&value.code)?
                 .iter()
                 .map(|i| i.into())
                 .collect(),
             raw_bytecode: value.code,
             exception_table: value.exception_table,
             line_number_table: value.line_number_table,
-        }
+        })
     }
 }
 
@@ -260,10 +295,20 @@ pub fn wasm_read_buffer(buffer: &[u8]) -> Result<WasmClass, JsValue> {
         .serialize_maps_as_objects(true)
         .serialize_missing_as_null(true);
 
-    let class_file = read_buffer(buffer).map(WasmClass::from);
+    let class_file = read_buffer_with_options(buffer, ReadOptions::default().record_spans())
+        .and_then(WasmClass::try_from);
     match class_file {
         // Ok(class_file) => Ok(class_file.serialize(&serializer)?),
         Ok(class_file) => Ok(class_file),
         Err(err) => Err(err.serialize(&serializer)?),
     }
 }
+
+#[wasm_bindgen]
+pub fn wasm_hex_dump(buffer: &[u8]) -> Result<String, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::new()
+        .serialize_maps_as_objects(true)
+        .serialize_missing_as_null(true);
+
+    hex_dump(buffer).map_err(|err| err.serialize(&serializer).unwrap_or(JsValue::NULL))
+}
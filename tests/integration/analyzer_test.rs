@@ -0,0 +1,91 @@
+extern crate class_reader;
+
+use class_reader::{
+    analyzer::Analyzer,
+    basic_interpreter::{BasicInterpreter, BasicValue},
+    class_file::ClassFile,
+    instruction::Instruction,
+    program_counter::ProgramCounter,
+    stack_map_table::{initial_locals, StackMapTable, VerificationType},
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+fn basic_value(verification_type: &VerificationType) -> BasicValue {
+    match verification_type {
+        VerificationType::Top => BasicValue::Uninitialized,
+        VerificationType::Integer => BasicValue::Int,
+        VerificationType::Float => BasicValue::Float,
+        VerificationType::Long => BasicValue::Long,
+        VerificationType::Double => BasicValue::Double,
+        _ => BasicValue::Reference,
+    }
+}
+
+fn check_against_stack_map_tables(class: &ClassFile) {
+    for method in class.methods.iter() {
+        let mut interpreter = BasicInterpreter::new(&class.constants);
+        let frames = Analyzer::new(&mut interpreter)
+            .analyze(&class.name, method, &class.constants)
+            .unwrap();
+        let Some(code) = &method.code else {
+            assert!(frames.is_empty());
+            continue;
+        };
+        assert_eq!(
+            Instruction::parse_instructions(&code.code).unwrap().len(),
+            frames.len()
+        );
+
+        let Some(attribute) = code
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name == "StackMapTable")
+        else {
+            continue;
+        };
+        let stack_map_table = StackMapTable::decode(
+            &attribute.bytes,
+            &initial_locals(&class.name, method),
+            &class.constants,
+        )
+        .unwrap();
+        for declared in stack_map_table.frames() {
+            let stack: Vec<BasicValue> = declared.stack.iter().map(basic_value).collect();
+            assert_eq!(stack, frames[&declared.program_counter].stack);
+        }
+    }
+}
+
+#[test_log::test]
+fn computed_frames_match_the_ones_generated_by_javac() {
+    for bytes in [
+        include_bytes!("../resources/rjvm/Complex.class").as_slice(),
+        include_bytes!("../resources/rjvm/Constants.class").as_slice(),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class").as_slice(),
+    ] {
+        check_against_stack_map_tables(&read_class_from_bytes(bytes));
+    }
+}
+
+#[test_log::test]
+fn tracks_the_kinds_of_values() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/Complex.class"));
+    let method = class.methods.iter().find(|m| m.name == "abs").unwrap();
+    let mut interpreter = BasicInterpreter::new(&class.constants);
+    let frames = Analyzer::new(&mut interpreter)
+        .analyze(&class.name, method, &class.constants)
+        .unwrap();
+
+    // Before the last dadd, the squares of the two fields are on the stack
+    let code = &method.code.as_ref().unwrap().code;
+    let (dadd, _) = Instruction::parse_instructions(code)
+        .unwrap()
+        .into_iter()
+        .find(|(_, instruction)| *instruction == Instruction::Dadd)
+        .unwrap();
+    let frame = &frames[&ProgramCounter(dadd as u16)];
+    assert_eq!(vec![BasicValue::Double, BasicValue::Double], frame.stack);
+    assert_eq!(vec![BasicValue::Reference], frame.locals);
+}
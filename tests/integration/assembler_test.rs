@@ -0,0 +1,116 @@
+extern crate class_reader;
+
+use class_reader::{
+    assembler::assemble,
+    class_hierarchy::ObjectClassHierarchy,
+    disassembler::{disassemble, to_assembly, DisasmOptions},
+    verifier::verify_class,
+    write_class,
+};
+use utils::{decompiled_class, loops_class, read_class_from_bytes};
+
+use crate::utils;
+
+#[test_log::test]
+fn assembling_the_disassembly_of_a_class_gives_back_the_same_bytes() {
+    let resources: [&[u8]; 4] = [
+        include_bytes!("../resources/rjvm/Complex.class"),
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+    ];
+    let classes = resources
+        .into_iter()
+        .map(read_class_from_bytes)
+        .chain([decompiled_class()]);
+    for class in classes {
+        let assembled = assemble(&to_assembly(&class)).unwrap();
+        assert_eq!(
+            write_class(&class).unwrap(),
+            write_class(&assembled).unwrap()
+        );
+    }
+}
+
+#[test_log::test]
+fn disassembling_an_assembled_class_gives_back_the_same_source() {
+    let class = loops_class();
+    let source = to_assembly(&class);
+    assert_eq!(source, to_assembly(&assemble(&source).unwrap()));
+}
+
+#[test_log::test]
+fn assembles_hand_written_classes() {
+    let class = assemble(
+        r#"
+        .version 50
+        .class public super Counter
+        .super java/lang/Object
+
+        .field private count I
+        .end field
+
+        .method public <init> ()V
+          .code
+                aload_0
+                invokespecial java/lang/Object <init> ()V
+                return
+          .end code
+        .end method
+
+        .method public countDown (I)I
+          .code
+          loop: iload_1
+                ifle done
+                iinc 1 -1
+                aload_0
+                dup
+                getfield Counter count I
+                iconst_1
+                iadd
+                putfield Counter count I
+                goto loop
+          done: aload_0
+                getfield Counter count I
+                ireturn
+                .frame loop locals class Counter int
+                .frame done locals class Counter int
+          .end code
+        .end method
+        "#,
+    )
+    .unwrap();
+    let class = read_class_from_bytes(&write_class(&class).unwrap());
+
+    assert_eq!(
+        r#"public class Counter {
+  private int count;
+
+  public Counter();
+    Code:
+       0: aload_0
+       1: invokespecial #6                  // Method java/lang/Object."<init>":()V
+       4: return
+
+  public int countDown(int);
+    Code:
+       0: iload_1
+       1: ifle          20
+       4: iinc          1, -1
+       7: aload_0
+       8: dup
+       9: getfield      #12                 // Field count:I
+      12: iconst_1
+      13: iadd
+      14: putfield      #12                 // Field count:I
+      17: goto          0
+      20: aload_0
+      21: getfield      #12                 // Field count:I
+      24: ireturn
+}
+"#,
+        disassemble(&class, DisasmOptions::default().code().private())
+    );
+    assert_eq!(3, class.methods[1].code.as_ref().unwrap().max_stack);
+    assert_eq!(Ok(()), verify_class(&class, &ObjectClassHierarchy));
+}
@@ -0,0 +1,72 @@
+extern crate class_reader;
+
+use std::borrow::Cow;
+
+use class_reader::view_buffer;
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn view_matches_the_class_read_from_disk() {
+    let resources: [&[u8]; 4] = [
+        include_bytes!("../resources/rjvm/Complex.class"),
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+    ];
+    for bytes in resources {
+        let class = read_class_from_bytes(bytes);
+        let view = view_buffer(bytes).unwrap();
+
+        assert_eq!(class.version, view.version);
+        assert_eq!(class.flags, view.flags);
+        assert_eq!(class.name, view.name);
+        assert!(matches!(view.name, Cow::Borrowed(_)));
+        assert_eq!(class.superclass.as_deref(), view.superclass.as_deref());
+        assert_eq!(class.interfaces, view.interfaces);
+        assert_eq!(
+            class.constants.iter().collect::<Vec<_>>(),
+            view.constants
+                .to_constant_pool()
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(class.fields.len(), view.fields.len());
+        for (field, field_view) in class.fields.iter().zip(view.fields.iter()) {
+            assert_eq!(field.flags, field_view.flags);
+            assert_eq!(field.name, field_view.name);
+            assert_eq!(field.type_descriptor, field_view.field_type().unwrap());
+        }
+
+        assert_eq!(class.methods.len(), view.methods.len());
+        for (method, method_view) in class.methods.iter().zip(view.methods.iter()) {
+            assert_eq!(method.flags, method_view.flags);
+            assert_eq!(method.name, method_view.name);
+            assert_eq!(method.type_descriptor, method_view.type_descriptor);
+            assert_eq!(method.attributes.len(), method_view.attributes.len());
+            for (attribute, attribute_view) in
+                method.attributes.iter().zip(method_view.attributes.iter())
+            {
+                assert_eq!(attribute.name, attribute_view.name);
+                assert_eq!(attribute.bytes, attribute_view.bytes);
+            }
+
+            let code = method.code.as_ref().unwrap();
+            let code_view = method_view.code.as_ref().unwrap();
+            assert_eq!(code.max_stack, code_view.max_stack);
+            assert_eq!(code.max_locals, code_view.max_locals);
+            assert_eq!(code.code, code_view.code);
+            let entries = code.exception_table.entries();
+            assert_eq!(entries.len(), code_view.exception_table.len());
+            for (entry, handler) in entries.iter().zip(code_view.exception_table.iter()) {
+                assert_eq!(entry.range.start, handler.start_pc);
+                assert_eq!(entry.range.end, handler.end_pc);
+                assert_eq!(entry.handler_pc, handler.handler_pc);
+                assert_eq!(entry.catch_class.as_deref(), handler.catch_class.as_deref());
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_reader_error::ClassReaderError,
+    read_buffer,
+    span::{class_spans, LabeledSpan, Span},
+};
+
+const EXCEPTIONS_HANDLERS: &[u8] = include_bytes!("../resources/rjvm/ExceptionsHandlers.class");
+
+fn find(spans: &[LabeledSpan], path: &str) -> Span {
+    spans
+        .iter()
+        .find(|labeled| labeled.path == path)
+        .unwrap_or_else(|| panic!("missing span {path}"))
+        .span
+}
+
+fn read_u16(bytes: &[u8], span: Span) -> u16 {
+    u16::from_be_bytes([bytes[span.offset], bytes[span.offset + 1]])
+}
+
+#[test_log::test]
+fn errors_report_the_offset_and_path_of_the_element_being_read() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let catch_type = spans
+        .iter()
+        .find(|labeled| {
+            labeled.path.ends_with(".catch_type")
+                && read_u16(EXCEPTIONS_HANDLERS, labeled.span) != 0
+        })
+        .expect("a method catching some exception");
+    // The path looks like method[3].Code.exception_table[1].catch_type
+    let indexes: Vec<usize> = catch_type
+        .path
+        .split(['[', ']'])
+        .skip(1)
+        .step_by(2)
+        .map(|index| index.parse().unwrap())
+        .collect();
+    let (method_index, entry_index) = (indexes[0], indexes[1]);
+    let method_name = &read_buffer(EXCEPTIONS_HANDLERS).unwrap().methods[method_index].name;
+    let catch_type = catch_type.span;
+
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    bytes[catch_type.offset..catch_type.end()].copy_from_slice(&[0xFF, 0xFF]);
+    let err = read_buffer(&bytes).unwrap_err();
+
+    assert_eq!("invalid-constant-pool-index", err.code());
+    assert_eq!(Some(catch_type.end()), err.offset());
+    assert_eq!(
+        Some(
+            format!(
+                "methods[{method_index}] \"{method_name}\" > Code > exception_table[{entry_index}]"
+            )
+            .as_str()
+        ),
+        err.path()
+    );
+    assert!(err
+        .to_string()
+        .starts_with("invalid class file: invalid constant pool index: 65535 at offset"));
+}
+
+#[test_log::test]
+fn invalid_type_descriptors_report_the_member_using_them() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let descriptor_index = read_u16(
+        EXCEPTIONS_HANDLERS,
+        find(&spans, "method[0].descriptor_index"),
+    );
+    let descriptor = find(&spans, &format!("constant_pool[{descriptor_index}]"));
+
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    // Skip the tag and the length of the utf8 constant
+    bytes[descriptor.offset + 3] = b'W';
+    let err = read_buffer(&bytes).unwrap_err();
+
+    assert!(matches!(
+        err.kind(),
+        ClassReaderError::InvalidTypeDescriptor(_)
+    ));
+    assert_eq!("invalid-type-descriptor", err.code());
+    assert_eq!(Some("methods[0] \"<init>\""), err.path());
+}
+
+#[test_log::test]
+fn errors_in_truncated_classes_are_located() {
+    for len in [
+        0,
+        9,
+        EXCEPTIONS_HANDLERS.len() / 2,
+        EXCEPTIONS_HANDLERS.len() - 1,
+    ] {
+        let err = read_buffer(&EXCEPTIONS_HANDLERS[..len]).unwrap_err();
+        assert_eq!("unexpected-end-of-data", err.code());
+        assert!(err.offset().is_some_and(|offset| offset <= len));
+    }
+}
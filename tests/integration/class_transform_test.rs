@@ -0,0 +1,68 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_transform::{transform_class, TransformingCode, TransformingMethods},
+    code_builder::{CodeBuilder, CodeElement},
+    instruction::Instruction,
+    write_class,
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn identity_transform_gives_back_the_same_bytes() {
+    let resources: [&[u8]; 4] = [
+        include_bytes!("../resources/rjvm/Complex.class"),
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+    ];
+    for bytes in resources {
+        let class = read_class_from_bytes(bytes);
+        let mut transform = TransformingMethods::new(TransformingCode::new(
+            |code: &mut CodeBuilder, element: CodeElement| {
+                code.with(element);
+            },
+        ));
+        let transformed = transform_class(class, &mut transform).unwrap();
+        assert_eq!(bytes, write_class(&transformed).unwrap());
+    }
+}
+
+#[test_log::test]
+fn can_insert_instructions_in_methods_with_exception_handlers() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let original_methods = class.methods.clone();
+
+    let mut transform = TransformingMethods::new(TransformingCode::new(
+        |code: &mut CodeBuilder, element: CodeElement| {
+            if matches!(element, CodeElement::Instruction(Instruction::Return)) {
+                code.instruction(Instruction::Nop);
+            }
+            code.with(element);
+        },
+    ));
+    let transformed = transform_class(class, &mut transform).unwrap();
+    let read = read_class_from_bytes(&write_class(&transformed).unwrap());
+
+    assert_eq!(original_methods.len(), read.methods.len());
+    for (original, method) in original_methods.iter().zip(read.methods.iter()) {
+        let original_code = original.code.as_ref().unwrap();
+        let code = method.code.as_ref().unwrap();
+        let original_instructions = Instruction::parse_instructions(&original_code.code).unwrap();
+        let instructions = Instruction::parse_instructions(&code.code).unwrap();
+        let returns = original_instructions
+            .iter()
+            .filter(|(_, instruction)| *instruction == Instruction::Return)
+            .count();
+        assert_eq!(original_instructions.len() + returns, instructions.len());
+        assert_eq!(
+            original_code.exception_table.entries().len(),
+            code.exception_table.entries().len()
+        );
+        if returns == 0 {
+            assert_eq!(original_code.code, code.code);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+extern crate class_reader;
+
+use class_reader::{
+    annotation::Annotation,
+    class_visitor::{AttributeTarget, ClassHeader, ClassVisitor, Visit},
+    constant_pool::ConstantPool,
+    field_flags::FieldFlags,
+    field_type::FieldType,
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+    visit_buffer,
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+/// Records the annotations found in a class, along with the element they are attached to
+#[derive(Default)]
+struct AnnotationsCollector {
+    current_member: String,
+    annotations: Vec<(AttributeTarget, String, String)>,
+}
+
+impl ClassVisitor for AnnotationsCollector {
+    fn visit_header(&mut self, header: &ClassHeader) -> Visit {
+        self.current_member = header.name.to_string();
+        Visit::Continue
+    }
+
+    fn visit_field(
+        &mut self,
+        _flags: FieldFlags,
+        name: &str,
+        _type_descriptor: &FieldType,
+    ) -> Visit {
+        self.current_member = name.to_string();
+        Visit::Continue
+    }
+
+    fn visit_method(
+        &mut self,
+        _flags: MethodFlags,
+        name: &str,
+        _type_descriptor: &MethodDescriptor,
+    ) -> Visit {
+        self.current_member = name.to_string();
+        Visit::Continue
+    }
+
+    fn visit_code(&mut self, _max_stack: u16, _max_locals: u16) -> Visit {
+        Visit::Skip
+    }
+
+    fn visit_code_instruction(&mut self, _address: ProgramCounter, _instruction: &Instruction) {
+        panic!("code should have been skipped");
+    }
+
+    fn visit_attribute(
+        &mut self,
+        target: AttributeTarget,
+        name: &str,
+        bytes: &[u8],
+        constants: &ConstantPool,
+    ) {
+        if name == "RuntimeVisibleAnnotations" {
+            for annotation in Annotation::decode_attribute(bytes, constants).unwrap() {
+                let member = match target {
+                    AttributeTarget::Class => "class".to_string(),
+                    _ => self.current_member.clone(),
+                };
+                self.annotations
+                    .push((target, member, annotation.type_descriptor));
+            }
+        }
+    }
+}
+
+#[test_log::test]
+fn can_collect_annotations_without_reading_code() {
+    let mut collector = AnnotationsCollector::default();
+    visit_buffer(
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        &mut collector,
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![
+            (
+                AttributeTarget::Field,
+                "deprecatedField".to_string(),
+                "Ljava/lang/Deprecated;".to_string()
+            ),
+            (
+                AttributeTarget::Method,
+                "deprecatedMethod".to_string(),
+                "Ljava/lang/Deprecated;".to_string()
+            ),
+            (
+                AttributeTarget::Class,
+                "class".to_string(),
+                "Ljava/lang/Deprecated;".to_string()
+            ),
+        ],
+        collector.annotations
+    );
+}
+
+/// Collects the instructions of the method at the given position
+struct InstructionsCollector {
+    method_index: usize,
+    methods_seen: usize,
+    in_method: bool,
+    instructions: Vec<(ProgramCounter, Instruction)>,
+    ended: bool,
+}
+
+impl ClassVisitor for InstructionsCollector {
+    fn visit_method(
+        &mut self,
+        _flags: MethodFlags,
+        _name: &str,
+        _type_descriptor: &MethodDescriptor,
+    ) -> Visit {
+        self.in_method = self.methods_seen == self.method_index;
+        self.methods_seen += 1;
+        if self.in_method {
+            Visit::Continue
+        } else {
+            Visit::Skip
+        }
+    }
+
+    fn visit_code_instruction(&mut self, address: ProgramCounter, instruction: &Instruction) {
+        assert!(self.in_method);
+        self.instructions.push((address, instruction.clone()));
+    }
+
+    fn visit_end(&mut self) {
+        self.ended = true;
+    }
+}
+
+#[test_log::test]
+fn visits_the_same_instructions_as_the_reader() {
+    let bytes = include_bytes!("../resources/rjvm/Complex.class");
+    let class = read_class_from_bytes(bytes);
+
+    for (method_index, method) in class.methods.iter().enumerate() {
+        let mut collector = InstructionsCollector {
+            method_index,
+            methods_seen: 0,
+            in_method: false,
+            instructions: Vec::new(),
+            ended: false,
+        };
+        visit_buffer(bytes, &mut collector).unwrap();
+
+        let expected: Vec<(ProgramCounter, Instruction)> =
+            Instruction::parse_instructions(&method.code.as_ref().unwrap().code)
+                .unwrap()
+                .into_iter()
+                .map(|(address, instruction)| (ProgramCounter(address as u16), instruction))
+                .collect();
+        assert_eq!(expected, collector.instructions);
+        assert!(collector.ended);
+    }
+}
+
+#[test_log::test]
+fn skipping_the_header_stops_the_reading() {
+    struct HeaderOnly(Option<String>);
+    impl ClassVisitor for HeaderOnly {
+        fn visit_header(&mut self, header: &ClassHeader) -> Visit {
+            self.0 = header.superclass.map(str::to_string);
+            Visit::Skip
+        }
+
+        fn visit_method(
+            &mut self,
+            _flags: MethodFlags,
+            _name: &str,
+            _type_descriptor: &MethodDescriptor,
+        ) -> Visit {
+            panic!("methods should have been skipped");
+        }
+    }
+
+    let mut visitor = HeaderOnly(None);
+    visit_buffer(
+        include_bytes!("../resources/rjvm/Complex.class"),
+        &mut visitor,
+    )
+    .unwrap();
+    assert_eq!(Some("java/lang/Object".to_string()), visitor.0);
+}
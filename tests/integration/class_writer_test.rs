@@ -0,0 +1,153 @@
+extern crate class_reader;
+
+use class_reader::{
+    annotation::{Annotation, ElementValue},
+    class_builder::ClassBuilder,
+    class_file::ClassFile,
+    class_file_field::FieldConstantValue,
+    code_builder::{JumpKind, LocalKind},
+    field_flags::FieldFlags,
+    field_type::{BaseType, FieldType},
+    format_check::FormatViolation,
+    instruction::Instruction,
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+    write_class,
+};
+use utils::read_class_from_bytes;
+
+use crate::{assertions::check_method, utils};
+
+#[test_log::test]
+fn writing_a_class_read_from_disk_gives_back_the_same_bytes() {
+    let resources: [&[u8]; 4] = [
+        include_bytes!("../resources/rjvm/Complex.class"),
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+    ];
+    for bytes in resources {
+        let class = read_class_from_bytes(bytes);
+        assert_eq!(bytes, write_class(&class).unwrap());
+    }
+}
+
+#[test_log::test]
+fn can_generate_and_read_back_a_class() {
+    let class = build_fixture_class();
+    assert_eq!(Vec::<FormatViolation>::new(), class.check_format());
+    let read = read_class_from_bytes(&write_class(&class).unwrap());
+
+    assert_eq!(class.version, read.version);
+    assert_eq!(class.flags, read.flags);
+    assert_eq!("rjvm/Generated", read.name);
+    assert_eq!(Some("java/lang/Object".to_string()), read.superclass);
+    assert_eq!(vec!["java/lang/Comparable".to_string()], read.interfaces);
+    assert_eq!(Some("Generated.java".to_string()), read.source_file);
+    assert_eq!(class.fields, read.fields);
+    assert_eq!(class.attributes, read.attributes);
+
+    assert_eq!(3, read.methods.len());
+    check_method(&read.methods[0], MethodFlags::PUBLIC, "<init>", "()V");
+    check_method(
+        &read.methods[1],
+        MethodFlags::PUBLIC | MethodFlags::STATIC,
+        "max",
+        "(II)I",
+    );
+    check_method(
+        &read.methods[2],
+        MethodFlags::PUBLIC,
+        "compareTo",
+        "(Ljava/lang/Object;)I",
+    );
+    for (expected, actual) in class.methods.iter().zip(read.methods.iter()) {
+        let expected_code = expected.code.as_ref().unwrap();
+        let actual_code = actual.code.as_ref().unwrap();
+        assert_eq!(expected_code.max_stack, actual_code.max_stack);
+        assert_eq!(expected_code.max_locals, actual_code.max_locals);
+        assert_eq!(expected_code.code, actual_code.code);
+        assert_eq!(
+            expected_code.line_number_table,
+            actual_code.line_number_table
+        );
+        assert_eq!(expected.thrown_exceptions, actual.thrown_exceptions);
+        assert_eq!(expected.deprecated, actual.deprecated);
+    }
+
+    let annotations = read.methods[2]
+        .attributes
+        .iter()
+        .find(|attribute| attribute.name == "RuntimeVisibleAnnotations")
+        .unwrap();
+    assert_eq!(
+        vec![Annotation::new("Ljava/lang/Deprecated;")],
+        Annotation::decode_attribute(&annotations.bytes, &read.constants).unwrap()
+    );
+}
+
+fn build_fixture_class() -> ClassFile {
+    let mut builder = ClassBuilder::new("rjvm/Generated");
+    builder
+        .interface("java/lang/Comparable")
+        .source_file("Generated.java")
+        .invisible_annotation(
+            Annotation::new("Lrjvm/Fixture;").element("name", ElementValue::String("gen".into())),
+        )
+        .field_with(
+            FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
+            "LIMIT",
+            FieldType::Base(BaseType::Long),
+            |field| {
+                field.constant_value(FieldConstantValue::Long(1 << 40));
+            },
+        )
+        .field(FieldFlags::PRIVATE, "value", FieldType::Base(BaseType::Int))
+        .method(
+            MethodFlags::PUBLIC,
+            "<init>",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                code.line_number(3)
+                    .load(LocalKind::Reference, 0)
+                    .invokespecial("java/lang/Object", "<init>", "()V")
+                    .instruction(Instruction::Return);
+            },
+        )
+        .method(
+            MethodFlags::PUBLIC | MethodFlags::STATIC,
+            "max",
+            MethodDescriptor::parse("(II)I").unwrap(),
+            |code| {
+                let second = code.new_label();
+                code.load(LocalKind::Int, 0)
+                    .load(LocalKind::Int, 1)
+                    .jump(JumpKind::If_icmplt, second)
+                    .load(LocalKind::Int, 0)
+                    .instruction(Instruction::Ireturn)
+                    .place_label(second)
+                    .load(LocalKind::Int, 1)
+                    .instruction(Instruction::Ireturn);
+            },
+        )
+        .method_with(
+            MethodFlags::PUBLIC,
+            "compareTo",
+            MethodDescriptor::parse("(Ljava/lang/Object;)I").unwrap(),
+            |method| {
+                method
+                    .deprecated()
+                    .annotation(Annotation::new("Ljava/lang/Deprecated;"))
+                    .code(|code| {
+                        code.load(LocalKind::Reference, 0)
+                            .getfield("rjvm/Generated", "value", "I")
+                            .load(LocalKind::Reference, 1)
+                            .checkcast("rjvm/Generated")
+                            .getfield("rjvm/Generated", "value", "I")
+                            .instruction(Instruction::Isub)
+                            .instruction(Instruction::Ireturn);
+                    });
+            },
+        );
+    builder.build().unwrap()
+}
@@ -0,0 +1,173 @@
+extern crate class_reader;
+
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+use zip::{write::FileOptions, ZipWriter};
+
+const RESOURCES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/resources");
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_class-reader"))
+        .args(args)
+        .current_dir(RESOURCES)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// A jar in the temporary directory, deleted when dropped
+struct TemporaryJar(PathBuf);
+
+impl TemporaryJar {
+    /// Packs the given classes of the resources
+    fn new(name: &str, classes: &[&str]) -> Self {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        let jar = TemporaryJar(path);
+        let mut writer = ZipWriter::new(fs::File::create(&jar.0).unwrap());
+        for class in classes {
+            let entry = format!("rjvm/{class}.class");
+            writer
+                .start_file(entry.as_str(), FileOptions::default())
+                .unwrap();
+            writer
+                .write_all(&fs::read(format!("{RESOURCES}/{entry}")).unwrap())
+                .unwrap();
+        }
+        writer.finish().unwrap();
+        jar
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TemporaryJar {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test_log::test]
+fn dumps_a_class_of_a_jar() {
+    let jar = TemporaryJar::new("rjvm.jar", &["Complex", "Constants"]);
+    let output = run(&[
+        "dump",
+        "-c",
+        jar.path(),
+        "--class",
+        "rjvm.Complex",
+        "--method",
+        "getReal",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        r#"Compiled from "Complex.java"
+public class rjvm.Complex implements java.lang.Cloneable,java.io.Serializable {
+  public double getReal();
+    Code:
+       0: aload_0
+       1: getfield      #2                  // Field real:D
+       4: dreturn
+}
+"#,
+        stdout(&output)
+    );
+}
+
+#[test_log::test]
+fn lists_the_methods_including_the_private_ones() {
+    let output = run(&["methods", "-p", "rjvm/ExceptionsHandlers.class"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        r#"Compiled from "ExceptionsHandlers.java"
+class rjvm.ExceptionsHandlers {
+  rjvm.ExceptionsHandlers();
+  void foo();
+  void bar() throws java.lang.IllegalArgumentException, java.lang.IllegalStateException;
+  void test() throws java.lang.Exception;
+}
+"#,
+        stdout(&output)
+    );
+}
+
+#[test_log::test]
+fn reads_a_class_from_the_standard_input() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_class-reader"))
+        .arg("constants")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(include_bytes!("../resources/rjvm/DeprecatedClass.class"))
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let text = stdout(&output);
+    assert!(text.starts_with("<stdin>:\nConstant pool:\n   #1 = Methodref          #3.#16"));
+    assert_eq!(20, text.lines().count());
+}
+
+#[test_log::test]
+fn greps_the_constants_of_all_the_classes() {
+    let output = run(&["grep-constants", "java/lang/Math", "rjvm"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        r#"rjvm/Complex: #4 = Methodref          #26.#27        // java/lang/Math.sqrt:(D)D
+rjvm/Complex: #26 = Class              #33            // java/lang/Math
+rjvm/Complex: #33 = Utf8               java/lang/Math
+"#,
+        stdout(&output)
+    );
+}
+
+#[test_log::test]
+fn prints_one_json_document_per_class() {
+    let output = run(&["json", "rjvm/Constants.class", "rjvm/Complex.class"]);
+
+    assert!(output.status.success());
+    let documents: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(2, documents.len());
+    assert_eq!("rjvm/Constants", documents[0]["name"]);
+    assert_eq!(
+        serde_json::json!({"MethodReference": [3, 28]}),
+        documents[0]["constants"][0]
+    );
+    assert_eq!(2023, documents[0]["fields"][0]["constant_value"]["Int"]);
+    assert_eq!(
+        serde_json::json!(["public", "static", "final"]),
+        documents[0]["fields"][0]["flags"]
+    );
+    assert_eq!("rjvm/Complex", documents[1]["name"]);
+}
+
+#[test_log::test]
+fn fails_for_the_inputs_that_cannot_be_read() {
+    let output = run(&["dump", "rjvm/Complex.class", "rjvm/Complex.java"]);
+
+    assert!(!output.status.success());
+    assert!(stdout(&output).contains("class rjvm.Complex"));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("error: rjvm/Complex.java: "));
+}
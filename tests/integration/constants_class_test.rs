@@ -20,6 +20,7 @@ fn can_read_constants() {
                 type_descriptor: FieldType::Base(BaseType::Int),
                 constant_value: Some(FieldConstantValue::Int(2023)),
                 deprecated: false,
+                attributes: vec![],
             },
             ClassFileField {
                 flags: FieldFlags::PROTECTED | FieldFlags::STATIC | FieldFlags::FINAL,
@@ -27,6 +28,7 @@ fn can_read_constants() {
                 type_descriptor: FieldType::Base(BaseType::Float),
                 constant_value: Some(FieldConstantValue::Float(20.23)),
                 deprecated: false,
+                attributes: vec![],
             },
             ClassFileField {
                 flags: FieldFlags::PRIVATE | FieldFlags::STATIC | FieldFlags::FINAL,
@@ -34,6 +36,7 @@ fn can_read_constants() {
                 type_descriptor: FieldType::Base(BaseType::Long),
                 constant_value: Some(FieldConstantValue::Long(2023)),
                 deprecated: false,
+                attributes: vec![],
             },
             ClassFileField {
                 flags: FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
@@ -41,6 +44,7 @@ fn can_read_constants() {
                 type_descriptor: FieldType::Base(BaseType::Double),
                 constant_value: Some(FieldConstantValue::Double(20.23)),
                 deprecated: false,
+                attributes: vec![],
             },
             ClassFileField {
                 flags: FieldFlags::PUBLIC | FieldFlags::STATIC | FieldFlags::FINAL,
@@ -48,6 +52,7 @@ fn can_read_constants() {
                 type_descriptor: FieldType::Object("java/lang/String".to_string()),
                 constant_value: Some(FieldConstantValue::String("2023".to_string())),
                 deprecated: false,
+                attributes: vec![],
             }
         ),
         class.fields
@@ -0,0 +1,60 @@
+extern crate class_reader;
+
+use class_reader::{
+    control_flow_graph::{Edge, EdgeKind},
+    program_counter::ProgramCounter,
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn exception_handlers_have_exceptional_edges() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let method = &class.methods[3];
+    assert_eq!("test", method.name);
+    let code = method.code.as_ref().unwrap();
+    let graph = code.control_flow_graph().unwrap();
+
+    for entry in code.exception_table.entries() {
+        let handler = graph.block_at(entry.handler_pc).unwrap();
+        assert_eq!(entry.handler_pc, graph.block(handler).range.start);
+
+        let covered = graph.block_at(entry.range.start).unwrap();
+        assert!(graph.successors(covered).any(|edge| *edge
+            == Edge {
+                from: covered,
+                to: handler,
+                kind: EdgeKind::Exceptional(entry.catch_class.clone()),
+            }));
+    }
+    assert!(graph.edges().iter().any(|edge| edge.kind
+        == EdgeKind::Exceptional(Some("java/lang/IllegalStateException".to_string()))));
+    assert!(graph
+        .edges()
+        .iter()
+        .any(|edge| edge.kind == EdgeKind::Exceptional(None)));
+    assert!(graph.unreachable_blocks().is_empty());
+
+    let last_block = graph.blocks().last().unwrap();
+    assert_eq!(ProgramCounter(code.code.len() as u16), last_block.range.end);
+}
+
+#[test_log::test]
+fn every_instruction_belongs_to_exactly_one_block() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/Complex.class"));
+    for method in class.methods.iter() {
+        let code = method.code.as_ref().unwrap();
+        let graph = code.control_flow_graph().unwrap();
+        let instructions: Vec<_> = graph
+            .blocks()
+            .iter()
+            .flat_map(|block| block.instructions.iter())
+            .map(|(address, instruction)| (address.0 as usize, instruction.clone()))
+            .collect();
+        assert_eq!(
+            class_reader::instruction::Instruction::parse_instructions(&code.code).unwrap(),
+            instructions
+        );
+    }
+}
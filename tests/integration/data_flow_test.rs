@@ -0,0 +1,65 @@
+extern crate class_reader;
+
+use class_reader::{
+    program_counter::ProgramCounter,
+    reaching_definitions::{Definition, DefinitionSite},
+};
+use utils::loops_class;
+
+use crate::utils;
+
+#[test_log::test]
+fn computes_liveness_without_local_variable_table() {
+    let class = loops_class();
+    let method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "sumUntilNegative")
+        .unwrap();
+    let code = method.code.as_ref().unwrap();
+    let liveness = code.liveness().unwrap();
+
+    // At the loop condition: values, sum and i are live, this is not
+    assert_eq!(vec![1, 2, 3], liveness.live_in(ProgramCounter(4)));
+    // After the loop only sum is live
+    assert_eq!(vec![2], liveness.live_in(ProgramCounter(31)));
+    assert!(liveness.dead_stores().is_empty());
+    assert_eq!(
+        vec![0],
+        liveness.free_slots(ProgramCounter(4)..ProgramCounter(31))
+    );
+}
+
+#[test_log::test]
+fn computes_def_use_chains_of_loop_counters() {
+    let class = loops_class();
+    let method = class.methods.iter().find(|m| m.name == "nested").unwrap();
+    let definitions = method
+        .code
+        .as_ref()
+        .unwrap()
+        .reaching_definitions()
+        .unwrap();
+
+    // The inner counter j is initialized at 18 and incremented at 40
+    let sites: Vec<DefinitionSite> = definitions
+        .definitions_of(ProgramCounter(20), 5)
+        .into_iter()
+        .map(|definition| definition.site)
+        .collect();
+    assert_eq!(
+        vec![
+            DefinitionSite::Instruction(ProgramCounter(18)),
+            DefinitionSite::Instruction(ProgramCounter(40))
+        ],
+        sites
+    );
+    let increment = Definition {
+        site: DefinitionSite::Instruction(ProgramCounter(40)),
+        slots: 5..6,
+    };
+    assert_eq!(
+        vec![ProgramCounter(20), ProgramCounter(40)],
+        definitions.uses_of(&increment)
+    );
+}
@@ -0,0 +1,123 @@
+extern crate class_reader;
+
+use class_reader::decompiler::{decompile, decompile_method};
+use utils::{decompiled_class, loops_class, read_class_from_bytes};
+
+use crate::utils;
+
+#[test_log::test]
+fn decompiles_class_compiled_with_debug_information() {
+    let class = decompiled_class();
+
+    assert_eq!(
+        r#"// Compiled from "Decompiled.java"
+package rjvm;
+
+class Decompiled {
+    private int count;
+
+    Decompiled() {
+    }
+
+    int max(int first, int second) {
+        return first > second ? first : second;
+    }
+
+    boolean isValid(int value, Object context) {
+        return value > 0 && context != null || value == -1;
+    }
+
+    String describe(int kind) {
+        switch (kind) {
+            case 1:
+                return "one";
+            case 2:
+            case 3:
+                this.count++;
+                break;
+            default:
+                this.count--;
+        }
+        return "kind " + kind + ", count " + this.count;
+    }
+
+    int sum(int[] values) {
+        int total = 0;
+        int index = 0;
+        do {
+            total += values[index];
+            index++;
+        } while (index < values.length);
+        return total;
+    }
+
+    synchronized int[] initialize(Object lock) {
+        synchronized (lock) {
+            this.count = 0;
+        }
+        return new int[]{1, 0, 3};
+    }
+
+    int parse(String text) {
+        try {
+            return Integer.parseInt(text);
+        } catch (NumberFormatException e) {
+            return -1;
+        } finally {
+            this.count++;
+        }
+    }
+}
+"#,
+        decompile(&class)
+    );
+}
+
+#[test_log::test]
+fn decompiles_loops_without_debug_information() {
+    let class = loops_class();
+    let method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "sumUntilNegative")
+        .unwrap();
+
+    assert_eq!(
+        "int sumUntilNegative(int[] arg0) {
+    int var2 = 0;
+    int var3 = 0;
+    while (var3 < arg0.length) {
+        if (arg0[var3] < 0) {
+            break;
+        }
+        var2 += arg0[var3++];
+    }
+    return var2;
+}
+",
+        decompile_method(&class, method)
+    );
+}
+
+#[test_log::test]
+fn decompiles_exception_handlers() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let method = class.methods.iter().find(|m| m.name == "test").unwrap();
+
+    assert_eq!(
+        "void test() throws Exception {
+    try {
+        this.bar();
+    } finally {
+        this.foo();
+    }
+    try {
+        this.bar();
+    } catch (IllegalStateException var1) {
+        this.bar();
+    }
+}
+",
+        decompile_method(&class, method)
+    );
+}
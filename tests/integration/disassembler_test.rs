@@ -0,0 +1,161 @@
+extern crate class_reader;
+
+use class_reader::disassembler::{disassemble, disassemble_constant_pool, DisasmOptions};
+use utils::{loops_class, read_class_from_bytes};
+
+use crate::utils;
+
+#[test_log::test]
+fn disassembles_like_javap_verbose() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/DeprecatedClass.class"));
+
+    let options = DisasmOptions::default().code().verbose().private();
+    assert_eq!(
+        r#"  Compiled from "DeprecatedClass.java"
+class rjvm.DeprecatedClass
+  minor version: 0
+  major version: 50
+  flags: (0x0020) ACC_SUPER
+  this_class: #2                          // rjvm/DeprecatedClass
+  super_class: #3                         // java/lang/Object
+  interfaces: 0, fields: 1, methods: 2, attributes: 3
+Constant pool:
+   #1 = Methodref          #3.#16         // java/lang/Object."<init>":()V
+   #2 = Class              #17            // rjvm/DeprecatedClass
+   #3 = Class              #18            // java/lang/Object
+   #4 = Utf8               deprecatedField
+   #5 = Utf8               I
+   #6 = Utf8               Deprecated
+   #7 = Utf8               RuntimeVisibleAnnotations
+   #8 = Utf8               Ljava/lang/Deprecated;
+   #9 = Utf8               <init>
+  #10 = Utf8               ()V
+  #11 = Utf8               Code
+  #12 = Utf8               LineNumberTable
+  #13 = Utf8               deprecatedMethod
+  #14 = Utf8               SourceFile
+  #15 = Utf8               DeprecatedClass.java
+  #16 = NameAndType        #9:#10         // "<init>":()V
+  #17 = Utf8               rjvm/DeprecatedClass
+  #18 = Utf8               java/lang/Object
+{
+  int deprecatedField;
+    descriptor: I
+    flags: (0x0000)
+    Deprecated: true
+    RuntimeVisibleAnnotations:
+      0: #8()
+        java.lang.Deprecated
+
+  rjvm.DeprecatedClass();
+    descriptor: ()V
+    flags: (0x0000)
+    Code:
+      stack=1, locals=1, args_size=1
+         0: aload_0
+         1: invokespecial #1                  // Method java/lang/Object."<init>":()V
+         4: return
+      LineNumberTable:
+        line 4: 0
+
+  void deprecatedMethod();
+    descriptor: ()V
+    flags: (0x0000)
+    Code:
+      stack=0, locals=1, args_size=1
+         0: return
+      LineNumberTable:
+        line 10: 0
+    Deprecated: true
+    RuntimeVisibleAnnotations:
+      0: #8()
+        java.lang.Deprecated
+}
+SourceFile: "DeprecatedClass.java"
+Deprecated: true
+RuntimeVisibleAnnotations:
+  0: #8()
+    java.lang.Deprecated
+"#,
+        disassemble(&class, options)
+    );
+}
+
+#[test_log::test]
+fn disassembles_code_and_exception_tables() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+
+    let options = DisasmOptions::default().code().private();
+    assert_eq!(
+        r#"Compiled from "ExceptionsHandlers.java"
+class rjvm.ExceptionsHandlers {
+  rjvm.ExceptionsHandlers();
+    Code:
+       0: aload_0
+       1: invokespecial #1                  // Method java/lang/Object."<init>":()V
+       4: return
+
+  void foo();
+    Code:
+       0: return
+
+  void bar() throws java.lang.IllegalArgumentException, java.lang.IllegalStateException;
+    Code:
+       0: return
+
+  void test() throws java.lang.Exception;
+    Code:
+       0: aload_0
+       1: invokevirtual #2                  // Method bar:()V
+       4: aload_0
+       5: invokevirtual #3                  // Method foo:()V
+       8: goto          18
+      11: astore_1
+      12: aload_0
+      13: invokevirtual #3                  // Method foo:()V
+      16: aload_1
+      17: athrow
+      18: aload_0
+      19: invokevirtual #2                  // Method bar:()V
+      22: goto          30
+      25: astore_1
+      26: aload_0
+      27: invokevirtual #2                  // Method bar:()V
+      30: return
+    Exception table:
+       from    to  target type
+           0     4    11   any
+          18    22    25   Class java/lang/IllegalStateException
+}
+"#,
+        disassemble(&class, options)
+    );
+}
+
+#[test_log::test]
+fn disassembles_only_the_declarations_by_default() {
+    let class = loops_class();
+
+    assert_eq!(
+        r#"Compiled from "Loops.java"
+class rjvm.Loops {
+  rjvm.Loops();
+  java.util.List<java.lang.Object> nested(int, int);
+  int sumUntilNegative(int[]);
+}
+"#,
+        disassemble(&class, DisasmOptions::default())
+    );
+}
+
+#[test_log::test]
+fn disassembles_only_the_constant_pool() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/DeprecatedClass.class"));
+
+    let constant_pool = disassemble_constant_pool(&class);
+    assert!(constant_pool.starts_with(
+        "Constant pool:\n   #1 = Methodref          #3.#16         // java/lang/Object.\"<init>\":()V\n"
+    ));
+    assert!(constant_pool.ends_with("  #18 = Utf8               java/lang/Object\n"));
+    assert!(disassemble(&class, DisasmOptions::default().verbose()).contains(&constant_pool));
+}
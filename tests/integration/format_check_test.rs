@@ -0,0 +1,129 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_access_flags::ClassAccessFlags,
+    class_file::ClassFile,
+    constant_pool::ConstantPoolEntry,
+    exception_table::{ExceptionTable, ExceptionTableEntry},
+    field_flags::FieldFlags,
+    format_check::FormatViolation,
+    method_flags::MethodFlags,
+    program_counter::ProgramCounter,
+};
+
+use crate::utils::read_class_from_bytes;
+
+const COMPLEX: &[u8] = include_bytes!("../resources/rjvm/Complex.class");
+
+fn violations(class: &ClassFile) -> Vec<String> {
+    class
+        .check_format()
+        .iter()
+        .map(FormatViolation::to_string)
+        .collect()
+}
+
+#[test_log::test]
+fn classes_compiled_by_javac_have_no_violations() {
+    for bytes in [
+        COMPLEX,
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+    ] {
+        let class = read_class_from_bytes(bytes);
+        assert_eq!(Vec::<String>::new(), violations(&class), "{}", class.name);
+    }
+}
+
+#[test_log::test]
+fn illegal_flag_combinations() {
+    let mut class = read_class_from_bytes(COMPLEX);
+    class.flags |= ClassAccessFlags::INTERFACE;
+    class.fields[0].flags |= FieldFlags::FINAL | FieldFlags::VOLATILE;
+
+    let violations = violations(&class);
+    assert!(violations.contains(&"access_flags: interface must be abstract".to_string()));
+    assert!(violations.contains(&format!(
+        "fields[0] \"{}\": field cannot be both final and volatile",
+        class.fields[0].name
+    )));
+    assert!(violations
+        .contains(&"methods[0] \"<init>\": interfaces cannot have constructors".to_string()));
+}
+
+#[test_log::test]
+fn duplicate_members_and_invalid_names() {
+    let mut class = read_class_from_bytes(COMPLEX);
+    class.methods.push(class.methods[0].clone());
+    class.fields[0].name = "a.b".to_string();
+    class.name = "rjvm//Complex".to_string();
+
+    let violations = violations(&class);
+    let last = class.methods.len() - 1;
+    assert!(violations.contains(&format!("methods[{last}] \"<init>\": duplicate method")));
+    assert!(violations.contains(&"fields[0] \"a.b\": invalid field name: a.b".to_string()));
+    assert!(violations.contains(&"this_class: invalid class name: rjvm//Complex".to_string()));
+}
+
+#[test_log::test]
+fn constructor_descriptor_and_flags() {
+    let mut class = read_class_from_bytes(COMPLEX);
+    let init = &mut class.methods[0];
+    assert_eq!("<init>", init.name);
+    init.type_descriptor = "()I".to_string();
+    init.flags |= MethodFlags::STATIC;
+
+    let violations = violations(&class);
+    assert!(violations.contains(&"methods[0] \"<init>\": <init> must return void".to_string()));
+    assert!(
+        violations.contains(&"methods[0] \"<init>\": <init> cannot have flags STATIC".to_string())
+    );
+}
+
+#[test_log::test]
+fn code_bounds() {
+    let mut class = read_class_from_bytes(COMPLEX);
+    let code = class.methods[0].code.as_mut().unwrap();
+    let code_length = code.code.len() as u16;
+    code.exception_table = ExceptionTable::new(vec![ExceptionTableEntry {
+        range: ProgramCounter(0)..ProgramCounter(code_length + 1),
+        handler_pc: ProgramCounter(code_length),
+        catch_class: None,
+    }]);
+    class.methods[1].code.as_mut().unwrap().code = vec![0; 65536];
+
+    let violations = violations(&class);
+    assert_eq!(
+        vec![
+            format!("methods[0] \"<init>\" > Code > exception_table[0]: end_pc {} is neither an instruction nor the end of the code", code_length + 1),
+            format!("methods[0] \"<init>\" > Code > exception_table[0]: handler_pc {code_length} is not an instruction"),
+            format!("methods[1] \"{}\" > Code: code length must be between 1 and 65535, not 65536", class.methods[1].name),
+        ],
+        violations
+    );
+}
+
+#[test_log::test]
+fn constant_cross_references() {
+    let mut class = read_class_from_bytes(COMPLEX);
+    let integer = class.constants.add(ConstantPoolEntry::Integer(42)).unwrap();
+    let class_reference = class
+        .constants
+        .add(ConstantPoolEntry::ClassReference(integer))
+        .unwrap();
+    let method_reference = class
+        .constants
+        .add(ConstantPoolEntry::MethodReference(integer, integer))
+        .unwrap();
+
+    assert_eq!(
+        vec![
+            format!(
+                "constant_pool[{class_reference}]: class name #{integer} is not an utf8 constant"
+            ),
+            format!("constant_pool[{method_reference}]: class #{integer} is not a class constant"),
+        ],
+        violations(&class)
+    );
+}
@@ -0,0 +1,56 @@
+extern crate class_reader;
+
+use class_reader::{instruction::Instruction, program_counter::ProgramCounter};
+use utils::loops_class;
+
+use crate::utils;
+
+#[test_log::test]
+fn finds_allocations_inside_loops() {
+    let class = loops_class();
+    let method = class.methods.iter().find(|m| m.name == "nested").unwrap();
+    let code = method.code.as_ref().unwrap();
+    let graph = code.control_flow_graph().unwrap();
+    let loops = graph.loops();
+
+    assert_eq!(2, loops.loops().len());
+    assert!(loops.is_reducible());
+    let allocations: Vec<(u16, usize)> = Instruction::parse_instructions(&code.code)
+        .unwrap()
+        .into_iter()
+        .filter(|(_, instruction)| matches!(instruction, Instruction::New { .. }))
+        .map(|(address, _)| {
+            let pc = ProgramCounter(address as u16);
+            (pc.0, loops.depth_at(pc))
+        })
+        .collect();
+    // The list is created outside the loops, the objects in the inner one
+    assert_eq!(vec![(0, 0), (27, 2)], allocations);
+}
+
+#[test_log::test]
+fn loop_exits_are_post_dominators_of_the_header() {
+    let class = loops_class();
+    let method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "sumUntilNegative")
+        .unwrap();
+    let graph = method.code.as_ref().unwrap().control_flow_graph().unwrap();
+    let loops = graph.loops();
+
+    assert_eq!(1, loops.loops().len());
+    let header = loops.loops()[0].header;
+    assert_eq!(ProgramCounter(4), graph.block(header).range.start);
+    // The block with the `break` jumps out of the loop
+    assert_eq!(
+        vec![header, header + 1, header + 3],
+        loops.loops()[0].blocks
+    );
+
+    let exit = graph.block_at(ProgramCounter(31)).unwrap();
+    let post_dominators = graph.post_dominator_tree();
+    assert!(post_dominators.dominates(exit, header));
+    assert_eq!(Some(exit), post_dominators.immediate_dominator(header));
+    assert!(graph.dominator_tree().dominates(header, exit));
+}
@@ -1,6 +1,31 @@
+mod analyzer_test;
+mod assembler_test;
 mod assertions;
+mod class_file_view_test;
+mod class_reader_error_test;
+mod class_transform_test;
+mod class_visitor_test;
+mod class_writer_test;
+#[cfg(feature = "cli")]
+mod cli_test;
 mod constants_class_test;
+mod control_flow_graph_test;
+mod data_flow_test;
+mod decompiler_test;
 mod deprecated_class_test;
+mod disassembler_test;
 mod exceptions;
+mod format_check_test;
+mod loops_test;
+mod malformed_class_test;
 mod pojo_class_test;
+mod read_lenient_test;
+mod read_limits_test;
+mod read_options_test;
+#[cfg(feature = "serde")]
+mod serde_test;
+mod span_test;
+mod ssa_test;
+mod stack_map_table_test;
 mod utils;
+mod verifier_test;
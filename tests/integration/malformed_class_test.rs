@@ -0,0 +1,89 @@
+extern crate class_reader;
+
+use class_reader::{read_buffer, read_buffer_lenient, span::class_spans};
+
+// Regressions for inputs that used to panic while being read
+
+const HEADER: [u8; 8] = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x32];
+
+fn class_with_constants(constants_count: u16, constants: &[u8], this_class: u16) -> Vec<u8> {
+    let mut bytes = HEADER.to_vec();
+    bytes.extend_from_slice(&constants_count.to_be_bytes());
+    bytes.extend_from_slice(constants);
+    // access flags, this class, super class, interfaces, fields, methods, attributes
+    bytes.extend_from_slice(&[0x00, 0x21]);
+    bytes.extend_from_slice(&this_class.to_be_bytes());
+    bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    bytes
+}
+
+#[test_log::test]
+fn constant_pool_count_of_zero_means_an_empty_pool() {
+    let bytes = class_with_constants(0, &[], 1);
+    let err = read_buffer(&bytes).unwrap_err();
+    assert_eq!("invalid-constant-pool-index", err.code());
+    assert_eq!(Some("this_class"), err.path());
+}
+
+#[test_log::test]
+fn cyclic_constant_references_are_invalid() {
+    // A class reference pointing to itself
+    let bytes = class_with_constants(2, &[0x07, 0x00, 0x01], 1);
+    let err = read_buffer(&bytes).unwrap_err();
+    assert_eq!("invalid-constant-pool-index", err.code());
+
+    // Two string references pointing to each other
+    let bytes = class_with_constants(3, &[0x08, 0x00, 0x02, 0x08, 0x00, 0x01], 1);
+    assert!(read_buffer(&bytes).is_err());
+    let (class, diagnostics) = read_buffer_lenient(&bytes).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert!(class
+        .constants
+        .to_string()
+        .contains("1, <invalid constant pool index: 2>"));
+}
+
+#[test_log::test]
+fn constant_pools_with_long_values_can_be_formatted() {
+    let class = read_buffer(include_bytes!("../resources/rjvm/Constants.class")).unwrap();
+    assert!(!class.constants.to_string().contains("invalid"));
+}
+
+#[test_log::test]
+fn source_file_attribute_with_wrong_size_is_invalid() {
+    let original = include_bytes!("../resources/rjvm/DeprecatedClass.class");
+    let spans = class_spans(original).unwrap();
+    let source_file_length = spans
+        .iter()
+        .find(|labeled| labeled.path == "SourceFile.attribute_length")
+        .unwrap()
+        .span;
+
+    // Shrink the attribute to a single byte
+    let mut bytes = original[..source_file_length.offset].to_vec();
+    bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+    bytes.push(original[source_file_length.end()]);
+    bytes.extend_from_slice(&original[source_file_length.end() + 2..]);
+
+    let err = read_buffer(&bytes).unwrap_err();
+    assert_eq!("unexpected-end-of-data", err.code());
+    assert_eq!(Some("SourceFile"), err.path());
+}
+
+#[test_log::test]
+fn attribute_with_the_largest_length_is_invalid() {
+    let original = include_bytes!("../resources/rjvm/DeprecatedClass.class");
+    let spans = class_spans(original).unwrap();
+    let source_file_length = spans
+        .iter()
+        .find(|labeled| labeled.path == "SourceFile.attribute_length")
+        .unwrap()
+        .span;
+
+    let mut bytes = original.to_vec();
+    bytes[source_file_length.offset..source_file_length.end()]
+        .copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+
+    let err = read_buffer(&bytes).unwrap_err();
+    assert_eq!("unexpected-end-of-data", err.code());
+}
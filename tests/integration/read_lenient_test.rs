@@ -0,0 +1,134 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_file_version::ClassFileVersion,
+    diagnostic::Severity,
+    method_flags::MethodFlags,
+    read_buffer, read_buffer_lenient,
+    span::{class_spans, LabeledSpan, Span},
+    write_class,
+};
+
+const EXCEPTIONS_HANDLERS: &[u8] = include_bytes!("../resources/rjvm/ExceptionsHandlers.class");
+
+fn find(spans: &[LabeledSpan], path: &str) -> Span {
+    spans
+        .iter()
+        .find(|labeled| labeled.path == path)
+        .unwrap_or_else(|| panic!("missing span {path}"))
+        .span
+}
+
+#[test_log::test]
+fn valid_classes_have_no_diagnostics() {
+    let (class, diagnostics) = read_buffer_lenient(EXCEPTIONS_HANDLERS).unwrap();
+    assert!(diagnostics.is_empty());
+    assert_eq!(
+        write_class(&read_buffer(EXCEPTIONS_HANDLERS).unwrap()).unwrap(),
+        write_class(&class).unwrap()
+    );
+}
+
+#[test_log::test]
+fn unknown_flags_are_dropped_with_a_warning() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let flags = find(&spans, "method[1].access_flags");
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    bytes[flags.offset] |= 0x80;
+    assert!(read_buffer(&bytes).is_err());
+
+    let (class, diagnostics) = read_buffer_lenient(&bytes).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+    assert_eq!("invalid-class-data", diagnostics[0].code);
+    assert_eq!(Some(flags.end()), diagnostics[0].offset);
+    assert_eq!("methods[1]", diagnostics[0].path);
+    let expected_flags = MethodFlags::from_bits_truncate(u16::from_be_bytes([
+        bytes[flags.offset],
+        bytes[flags.offset + 1],
+    ]));
+    assert_eq!(expected_flags, class.methods[1].flags);
+}
+
+#[test_log::test]
+fn methods_with_invalid_code_are_kept_without_code() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let code_length = find(&spans, "method[1].Code.code_length");
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    bytes[code_length.offset..code_length.end()].copy_from_slice(&[0x00, 0x00, 0xFF, 0xFF]);
+
+    let (class, diagnostics) = read_buffer_lenient(&bytes).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Severity::Error, diagnostics[0].severity);
+    assert_eq!("unexpected-end-of-data", diagnostics[0].code);
+    assert!(diagnostics[0].path.ends_with(" > Code"));
+
+    let method = &class.methods[1];
+    assert!(method.code.is_none());
+    assert!(method.attributes.iter().any(|attr| attr.name == "Code"));
+    assert!(class
+        .methods
+        .iter()
+        .enumerate()
+        .all(|(i, method)| i == 1 || method.code.is_some()));
+}
+
+#[test_log::test]
+fn invalid_constant_references_are_replaced_by_placeholders() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let catch_type = spans
+        .iter()
+        .find(|labeled| {
+            labeled.path.ends_with(".catch_type") && labeled.path.starts_with("method[3]")
+        })
+        .unwrap()
+        .span;
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    bytes[catch_type.offset..catch_type.end()].copy_from_slice(&[0xFF, 0xFF]);
+
+    let (class, diagnostics) = read_buffer_lenient(&bytes).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!("invalid-constant-pool-index", diagnostics[0].code);
+    let catch_classes: Vec<Option<&str>> = class.methods[3]
+        .code
+        .as_ref()
+        .unwrap()
+        .exception_table
+        .entries()
+        .iter()
+        .map(|entry| entry.catch_class.as_deref())
+        .collect();
+    assert!(catch_classes.contains(&Some("<invalid constant #65535>")));
+}
+
+#[test_log::test]
+fn truncated_classes_keep_the_elements_read_before_the_end() {
+    let spans = class_spans(EXCEPTIONS_HANDLERS).unwrap();
+    let method = find(&spans, "method[2]");
+    let (class, diagnostics) =
+        read_buffer_lenient(&EXCEPTIONS_HANDLERS[..method.offset + 4]).unwrap();
+
+    assert_eq!(1, diagnostics.len());
+    assert_eq!("unexpected-end-of-data", diagnostics[0].code);
+    assert!(diagnostics[0].path.starts_with("methods[2]"));
+    assert_eq!(2, class.methods.len());
+}
+
+#[test_log::test]
+fn unreadable_headers_are_errors() {
+    assert!(read_buffer_lenient(&EXCEPTIONS_HANDLERS[..20]).is_err());
+}
+
+#[test_log::test]
+fn only_newer_unknown_versions_are_read_as_the_latest_known_one() {
+    let mut bytes = EXCEPTIONS_HANDLERS.to_vec();
+    bytes[6..8].copy_from_slice(&99u16.to_be_bytes());
+    let (class, diagnostics) = read_buffer_lenient(&bytes).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(Severity::Warning, diagnostics[0].severity);
+    assert_eq!("unsupported-version", diagnostics[0].code);
+    assert_eq!(ClassFileVersion::Jdk22, class.version);
+
+    bytes[6..8].copy_from_slice(&44u16.to_be_bytes());
+    assert!(read_buffer_lenient(&bytes).is_err());
+}
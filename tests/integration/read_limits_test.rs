@@ -0,0 +1,75 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_builder::ClassBuilder,
+    class_reader_error::ClassReaderError,
+    field_flags::FieldFlags,
+    field_type::FieldType,
+    read_buffer_with_options,
+    read_limits::{ReadLimit, ReadLimits},
+    read_options::ReadOptions,
+    write_class,
+};
+
+const COMPLEX: &[u8] = include_bytes!("../resources/rjvm/Complex.class");
+
+fn read_with_limits(limits: ReadLimits) -> Result<(), ClassReaderError> {
+    read_buffer_with_options(COMPLEX, ReadOptions::default().limits(limits)).map(|_| ())
+}
+
+fn assert_limit_exceeded(result: Result<(), ClassReaderError>, limit: ReadLimit) {
+    let err = result.expect_err("the limit should be exceeded");
+    assert!(
+        matches!(err.kind(), ClassReaderError::LimitExceeded(l, _) if *l == limit),
+        "unexpected error {err}"
+    );
+    assert!(err.offset().is_some());
+}
+
+#[test_log::test]
+fn default_limits_accept_valid_classes() {
+    assert!(read_with_limits(ReadLimits::default()).is_ok());
+}
+
+#[test_log::test]
+fn allocation_limit() {
+    assert_limit_exceeded(
+        read_with_limits(ReadLimits::default().max_allocation(100)),
+        ReadLimit::Allocation,
+    );
+    assert!(read_with_limits(ReadLimits::default().max_allocation(COMPLEX.len())).is_ok());
+}
+
+#[test_log::test]
+fn attribute_length_limit() {
+    let result = read_with_limits(ReadLimits::default().max_attribute_length(4));
+    assert_limit_exceeded(result, ReadLimit::AttributeLength);
+}
+
+#[test_log::test]
+fn code_length_limit() {
+    let result = read_with_limits(ReadLimits::default().max_code_length(2));
+    let err = result.as_ref().unwrap_err();
+    assert_eq!("code-length-limit-exceeded", err.code());
+    assert!(err.path().unwrap().ends_with("> Code"));
+    assert_limit_exceeded(result, ReadLimit::CodeLength);
+}
+
+#[test_log::test]
+fn descriptor_depth_limit() {
+    let mut builder = ClassBuilder::new("rjvm/Arrays");
+    builder.field(
+        FieldFlags::PUBLIC,
+        "matrix",
+        FieldType::parse("[[I").unwrap(),
+    );
+    let bytes = write_class(&builder.build().unwrap()).unwrap();
+
+    let options = ReadOptions::default().limits(ReadLimits::default().max_descriptor_depth(1));
+    let err = read_buffer_with_options(&bytes, options).unwrap_err();
+    assert_eq!(
+        &ClassReaderError::LimitExceeded(ReadLimit::DescriptorDepth, 1),
+        err.kind()
+    );
+    assert_eq!(Some("fields[0] \"matrix\""), err.path());
+}
@@ -0,0 +1,75 @@
+extern crate class_reader;
+
+use class_reader::{read_buffer_with_options, read_options::ReadOptions, write_class};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+const COMPLEX: &[u8] = include_bytes!("../resources/rjvm/Complex.class");
+
+#[test_log::test]
+fn can_read_only_the_header() {
+    let class = read_buffer_with_options(COMPLEX, ReadOptions::default().header_only()).unwrap();
+    assert_eq!("rjvm/Complex", class.name);
+    assert_eq!(Some("java/lang/Object".to_string()), class.superclass);
+    assert_eq!(
+        vec![
+            "java/lang/Cloneable".to_string(),
+            "java/io/Serializable".to_string()
+        ],
+        class.interfaces
+    );
+    assert!(class.fields.is_empty());
+    assert!(class.methods.is_empty());
+    assert_eq!(None, class.source_file);
+}
+
+#[test_log::test]
+fn can_skip_code() {
+    let class = read_buffer_with_options(COMPLEX, ReadOptions::default().skip_code()).unwrap();
+    assert_eq!(2, class.fields.len());
+    assert_eq!(5, class.methods.len());
+    for method in class.methods.iter() {
+        assert!(method.code.is_none());
+        assert!(method
+            .attributes
+            .iter()
+            .all(|attribute| attribute.name != "Code"));
+    }
+    assert_eq!(Some("Complex.java".to_string()), class.source_file);
+}
+
+#[test_log::test]
+fn can_skip_debug_info() {
+    let class = read_buffer_with_options(COMPLEX, ReadOptions::default().skip_debug()).unwrap();
+    assert_eq!(None, class.source_file);
+    for method in class.methods.iter() {
+        let code = method.code.as_ref().unwrap();
+        assert!(code.line_number_table.is_none());
+        assert!(code
+            .attributes
+            .iter()
+            .all(|attribute| attribute.name != "LineNumberTable"));
+    }
+
+    let full = read_class_from_bytes(COMPLEX);
+    for (method, full_method) in class.methods.iter().zip(full.methods.iter()) {
+        assert_eq!(
+            full_method.code.as_ref().unwrap().code,
+            method.code.as_ref().unwrap().code
+        );
+    }
+}
+
+#[test_log::test]
+fn lazy_constants_give_the_same_class() {
+    let class = read_buffer_with_options(COMPLEX, ReadOptions::default().lazy_constants()).unwrap();
+    let full = read_class_from_bytes(COMPLEX);
+    assert_eq!(full.name, class.name);
+    assert_eq!(full.fields, class.fields);
+    assert_eq!(
+        full.constants.iter().collect::<Vec<_>>(),
+        class.constants.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(COMPLEX, write_class(&class).unwrap());
+}
@@ -0,0 +1,128 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_file::ClassFile, instruction::Instruction, read_buffer_with_options,
+    read_options::ReadOptions, write_class,
+};
+use utils::{decompiled_class, loops_class};
+
+use crate::utils;
+
+const CLASSES: [&[u8]; 4] = [
+    include_bytes!("../resources/rjvm/Complex.class"),
+    include_bytes!("../resources/rjvm/Constants.class"),
+    include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+    include_bytes!("../resources/rjvm/ExceptionsHandlers.class"),
+];
+
+fn read(bytes: &[u8]) -> ClassFile {
+    read_buffer_with_options(bytes, ReadOptions::default().record_spans()).unwrap()
+}
+
+/// The resources, read with their spans, and the class generated in place of javac output
+fn classes() -> Vec<ClassFile> {
+    CLASSES
+        .into_iter()
+        .map(read)
+        .chain([decompiled_class()])
+        .collect()
+}
+
+#[test_log::test]
+fn classes_round_trip_through_json() {
+    for class in classes() {
+        let json = serde_json::to_string(&class).unwrap();
+        let deserialized: ClassFile = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            write_class(&class).unwrap(),
+            write_class(&deserialized).unwrap()
+        );
+        assert_eq!(class.spans, deserialized.spans);
+        assert_eq!(json, serde_json::to_string(&deserialized).unwrap());
+    }
+}
+
+#[test_log::test]
+fn classes_round_trip_through_bincode() {
+    for class in classes() {
+        let encoded = bincode::serialize(&class).unwrap();
+        let deserialized: ClassFile = bincode::deserialize(&encoded).unwrap();
+
+        assert_eq!(
+            write_class(&class).unwrap(),
+            write_class(&deserialized).unwrap()
+        );
+        assert_eq!(class.methods, deserialized.methods);
+        assert_eq!(class.fields, deserialized.fields);
+    }
+}
+
+#[test_log::test]
+fn serializes_the_constant_pool_and_the_flags() {
+    let class = read(include_bytes!("../resources/rjvm/Constants.class"));
+
+    let json = serde_json::to_value(&class).unwrap();
+    assert_eq!(serde_json::json!(["super"]), json["flags"]);
+    assert_eq!(
+        serde_json::json!({"MethodReference": [3, 28]}),
+        json["constants"][0]
+    );
+    assert_eq!(
+        serde_json::json!(["public", "static", "final"]),
+        json["fields"][0]["flags"]
+    );
+    assert_eq!(
+        serde_json::json!({"Int": 2023}),
+        json["fields"][0]["constant_value"]
+    );
+}
+
+#[test_log::test]
+fn instructions_round_trip_through_json() {
+    let class = loops_class();
+    for method in &class.methods {
+        let code = method.code.as_ref().unwrap();
+        let instructions = Instruction::parse_instructions(&code.code).unwrap();
+
+        let json = serde_json::to_string(&instructions).unwrap();
+        let deserialized: Vec<(usize, Instruction)> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(instructions, deserialized);
+    }
+}
+
+#[test_log::test]
+fn instructions_round_trip_through_bincode() {
+    use class_reader::instruction::WideInstruction;
+
+    let instructions = vec![
+        Instruction::Iload_0,
+        Instruction::Iinc {
+            index: 1,
+            constant: -1,
+        },
+        Instruction::Wide {
+            instruction: WideInstruction::Iinc {
+                index: 300,
+                constant: 1000,
+            },
+        },
+        Instruction::Tableswitch {
+            default: 40,
+            low: -1,
+            high: 1,
+            jump_addresses: vec![20, 28, 36],
+        },
+        Instruction::Lookupswitch {
+            default: 40,
+            match_pairs: vec![(-5, 20), (10, 28)],
+        },
+        Instruction::Ireturn,
+    ];
+
+    let encoded = bincode::serialize(&instructions).unwrap();
+    let deserialized: Vec<Instruction> = bincode::deserialize(&encoded).unwrap();
+
+    assert_eq!(instructions, deserialized);
+}
@@ -0,0 +1,103 @@
+extern crate class_reader;
+
+use class_reader::{
+    read_buffer_with_options,
+    read_options::ReadOptions,
+    span::{class_spans, hex_dump, LabeledSpan, Span},
+};
+
+const EXCEPTIONS_HANDLERS: &[u8] = include_bytes!("../resources/rjvm/ExceptionsHandlers.class");
+
+fn find<'a>(spans: &'a [LabeledSpan], path: &str) -> &'a Span {
+    &spans
+        .iter()
+        .find(|labeled| labeled.path == path)
+        .unwrap_or_else(|| panic!("missing span {path}"))
+        .span
+}
+
+#[test_log::test]
+fn leaf_spans_cover_the_whole_file() {
+    let resources: [&[u8]; 4] = [
+        include_bytes!("../resources/rjvm/Complex.class"),
+        include_bytes!("../resources/rjvm/Constants.class"),
+        include_bytes!("../resources/rjvm/DeprecatedClass.class"),
+        EXCEPTIONS_HANDLERS,
+    ];
+    for bytes in resources {
+        let spans = class_spans(bytes).unwrap();
+        let leaves: Vec<&Span> = spans
+            .iter()
+            .enumerate()
+            .filter(|(i, labeled)| {
+                spans.get(i + 1).is_none_or(|next| {
+                    labeled.span.len == 0 || next.span.offset != labeled.span.offset
+                })
+            })
+            .map(|(_, labeled)| &labeled.span)
+            .collect();
+
+        let mut offset = 0;
+        for leaf in leaves {
+            assert_eq!(offset, leaf.offset);
+            offset = leaf.end();
+        }
+        assert_eq!(bytes.len(), offset);
+    }
+}
+
+#[test_log::test]
+fn records_spans_of_nested_elements() {
+    let class =
+        read_buffer_with_options(EXCEPTIONS_HANDLERS, ReadOptions::default().record_spans())
+            .unwrap();
+    let spans = &class.spans;
+
+    assert_eq!(&Span { offset: 0, len: 4 }, find(spans, "magic"));
+
+    let method_index = class
+        .methods
+        .iter()
+        .position(|method| method.name == "test")
+        .unwrap();
+    let code = class.methods[method_index].code.as_ref().unwrap();
+    let entry = &code.exception_table.entries()[0];
+    let handler_pc = find(
+        spans,
+        &format!("method[{method_index}].Code.exception_table[0].handler_pc"),
+    );
+    assert_eq!(
+        entry.handler_pc.0.to_be_bytes(),
+        EXCEPTIONS_HANDLERS[handler_pc.offset..handler_pc.end()]
+    );
+
+    let code_span = find(spans, &format!("method[{method_index}].Code.code"));
+    assert_eq!(
+        code.code,
+        EXCEPTIONS_HANDLERS[code_span.offset..code_span.end()]
+    );
+    let first_instruction = find(spans, &format!("method[{method_index}].Code.code[0]"));
+    assert_eq!(code_span.offset, first_instruction.offset);
+}
+
+#[test_log::test]
+fn spans_are_not_recorded_by_default() {
+    let class = read_buffer_with_options(EXCEPTIONS_HANDLERS, ReadOptions::default()).unwrap();
+    assert!(class.spans.is_empty());
+}
+
+#[test_log::test]
+fn hex_dump_labels_every_element() {
+    let dump = hex_dump(include_bytes!("../resources/rjvm/DeprecatedClass.class")).unwrap();
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(
+        "00000000  ca fe ba be                                      magic",
+        lines[0]
+    );
+    assert!(lines
+        .iter()
+        .any(|line| line.ends_with("field[0].RuntimeVisibleAnnotations (12 bytes)")));
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("method[1].Code.code[0]")));
+}
@@ -0,0 +1,83 @@
+extern crate class_reader;
+
+use std::collections::HashSet;
+
+use class_reader::{
+    field_type::{BaseType, FieldType},
+    program_counter::ProgramCounter,
+    ssa::{Operation, SsaMethod, ValueId, ValueType},
+};
+use utils::{loops_class, read_class_from_bytes};
+
+use crate::utils;
+
+#[test_log::test]
+fn every_value_is_defined_once_before_being_used() {
+    let classes = [
+        read_class_from_bytes(include_bytes!("../resources/rjvm/Complex.class")),
+        read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class")),
+        loops_class(),
+    ];
+    for class in classes.iter() {
+        for method in class.methods.iter() {
+            let ssa = SsaMethod::new(&class.name, method, &class.constants).unwrap();
+            let mut defined: HashSet<ValueId> = ssa.parameters.iter().copied().collect();
+            for block in ssa.blocks.iter() {
+                let results = block
+                    .phis
+                    .iter()
+                    .map(|phi| phi.result)
+                    .chain(block.statements.iter().filter_map(|s| s.result));
+                for result in results {
+                    assert!(defined.insert(result), "{result} defined twice");
+                }
+            }
+            assert_eq!(ssa.values.len(), defined.len());
+
+            for block in ssa.blocks.iter() {
+                let uses = block
+                    .phis
+                    .iter()
+                    .flat_map(|phi| phi.operands.iter().map(|operand| operand.value))
+                    .chain(block.statements.iter().flat_map(|s| s.operation.operands()))
+                    .chain(block.terminator.operands());
+                for value in uses {
+                    assert!(defined.contains(&value), "{value} is not defined");
+                }
+            }
+        }
+    }
+}
+
+#[test_log::test]
+fn loop_variables_become_typed_phis() {
+    let class = loops_class();
+    let method = class
+        .methods
+        .iter()
+        .find(|m| m.name == "sumUntilNegative")
+        .unwrap();
+    let ssa = SsaMethod::new(&class.name, method, &class.constants).unwrap();
+    let int = ValueType::Typed(FieldType::Base(BaseType::Int));
+
+    // sum and i are merged at the loop header, values never changes
+    let header = ssa
+        .blocks
+        .iter()
+        .find(|block| block.range.start == ProgramCounter(4))
+        .unwrap();
+    assert_eq!(2, header.phis.len());
+    for phi in header.phis.iter() {
+        assert_eq!(&int, ssa.value_type(phi.result));
+        assert_eq!(2, phi.operands.len());
+    }
+
+    let array_loads: Vec<&ValueType> = ssa
+        .blocks
+        .iter()
+        .flat_map(|block| block.statements.iter())
+        .filter(|statement| matches!(statement.operation, Operation::ArrayLoad { .. }))
+        .map(|statement| ssa.value_type(statement.result.unwrap()))
+        .collect();
+    assert_eq!(vec![&int, &int], array_loads);
+}
@@ -0,0 +1,70 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_file_version::ClassFileVersion,
+    class_hierarchy::ObjectClassHierarchy,
+    stack_map_table::{
+        compute_stack_map_table, initial_locals, upgrade_class_version, StackMapTable,
+    },
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+#[test_log::test]
+fn computed_frames_match_the_ones_generated_by_javac() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let method = &class.methods[3];
+    let code = method.code.as_ref().unwrap();
+    let attribute = code
+        .attributes
+        .iter()
+        .find(|attribute| attribute.name == "StackMapTable")
+        .unwrap();
+    let expected = StackMapTable::decode(
+        &attribute.bytes,
+        &initial_locals(&class.name, method),
+        &class.constants,
+    )
+    .unwrap();
+    assert_eq!(4, expected.frames().len());
+
+    let computed =
+        compute_stack_map_table(&class.name, method, &class.constants, &ObjectClassHierarchy)
+            .unwrap();
+    assert_eq!(expected, computed);
+}
+
+#[test_log::test]
+fn can_upgrade_class_version_and_recompute_frames() {
+    let mut class =
+        read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let original_bytes = stack_map_table_bytes(&class.methods[3].code.as_ref().unwrap().attributes);
+    for method in class.methods.iter_mut() {
+        if let Some(code) = method.code.as_mut() {
+            code.attributes
+                .retain(|attribute| attribute.name != "StackMapTable");
+        }
+    }
+
+    upgrade_class_version(&mut class, ClassFileVersion::Jdk8, &ObjectClassHierarchy).unwrap();
+
+    assert_eq!(ClassFileVersion::Jdk8, class.version);
+    for method in class.methods[0..3].iter() {
+        assert_eq!(
+            None,
+            stack_map_table_bytes(&method.code.as_ref().unwrap().attributes)
+        );
+    }
+    assert_eq!(
+        original_bytes,
+        stack_map_table_bytes(&class.methods[3].code.as_ref().unwrap().attributes)
+    );
+}
+
+fn stack_map_table_bytes(attributes: &[class_reader::attribute::Attribute]) -> Option<Vec<u8>> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == "StackMapTable")
+        .map(|attribute| attribute.bytes.clone())
+}
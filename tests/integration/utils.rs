@@ -1,9 +1,569 @@
 use log::info;
 
-use class_reader::class_file::ClassFile;
+use class_reader::{
+    attribute::Attribute,
+    class_access_flags::ClassAccessFlags,
+    class_builder::ClassBuilder,
+    class_file::ClassFile,
+    class_file_version::ClassFileVersion,
+    code_builder::{CodeBuilder, JumpKind, LocalKind},
+    field_flags::FieldFlags,
+    field_type::{BaseType, FieldType},
+    instruction::{Instruction, NewArrayType},
+    method_descriptor::MethodDescriptor,
+    method_flags::MethodFlags,
+};
 
 pub fn read_class_from_bytes(bytes: &[u8]) -> ClassFile {
     let class = class_reader::read_buffer(bytes).unwrap();
     info!("read class file: {}", class);
     class
 }
+
+/// Builds the class with the same bytecode that javac generates for:
+///
+/// ```java
+/// package rjvm;
+///
+/// import java.util.ArrayList;
+/// import java.util.List;
+///
+/// class Loops {
+///     List<Object> nested(int rows, int columns) {
+///         List<Object> result = new ArrayList<Object>();
+///         for (int i = 0; i < rows; i++) {
+///             for (int j = 0; j < columns; j++) {
+///                 result.add(new Object());
+///             }
+///         }
+///         return result;
+///     }
+///
+///     int sumUntilNegative(int[] values) {
+///         int sum = 0;
+///         int i = 0;
+///         while (i < values.length) {
+///             if (values[i] < 0) {
+///                 break;
+///             }
+///             sum += values[i++];
+///         }
+///         return sum;
+///     }
+/// }
+/// ```
+pub fn loops_class() -> ClassFile {
+    let mut builder = ClassBuilder::new("rjvm/Loops");
+    builder
+        .version(ClassFileVersion::Jdk7)
+        .flags(ClassAccessFlags::SUPER)
+        .source_file("Loops.java")
+        .method(
+            MethodFlags::empty(),
+            "<init>",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                code.line_number(6)
+                    .load(LocalKind::Reference, 0)
+                    .invokespecial("java/lang/Object", "<init>", "()V")
+                    .instruction(Instruction::Return);
+            },
+        )
+        .method_with(
+            MethodFlags::empty(),
+            "nested",
+            MethodDescriptor::parse("(II)Ljava/util/List;").unwrap(),
+            |method| {
+                let signature = method
+                    .constants()
+                    .intern_utf8("(II)Ljava/util/List<Ljava/lang/Object;>;")
+                    .unwrap();
+                method.attribute(Attribute {
+                    name: "Signature".to_string(),
+                    bytes: signature.to_be_bytes().to_vec(),
+                });
+                method.code(|code| {
+                    let (outer, outer_end) = (code.new_label(), code.new_label());
+                    let (inner, inner_end) = (code.new_label(), code.new_label());
+                    code.line_number(8)
+                        .new_object("java/util/ArrayList")
+                        .instruction(Instruction::Dup)
+                        .invokespecial("java/util/ArrayList", "<init>", "()V")
+                        .store(LocalKind::Reference, 3)
+                        .line_number(9)
+                        .iconst(0)
+                        .store(LocalKind::Int, 4)
+                        .place_label(outer)
+                        .load(LocalKind::Int, 4)
+                        .load(LocalKind::Int, 1)
+                        .jump(JumpKind::If_icmpge, outer_end)
+                        .line_number(10)
+                        .iconst(0)
+                        .store(LocalKind::Int, 5)
+                        .place_label(inner)
+                        .load(LocalKind::Int, 5)
+                        .load(LocalKind::Int, 2)
+                        .jump(JumpKind::If_icmpge, inner_end)
+                        .line_number(11)
+                        .load(LocalKind::Reference, 3)
+                        .new_object("java/lang/Object")
+                        .instruction(Instruction::Dup)
+                        .invokespecial("java/lang/Object", "<init>", "()V")
+                        .invokeinterface("java/util/List", "add", "(Ljava/lang/Object;)Z")
+                        .unwrap()
+                        .instruction(Instruction::Pop)
+                        .line_number(10)
+                        .iinc(5, 1)
+                        .jump(JumpKind::Goto, inner)
+                        .place_label(inner_end)
+                        .line_number(9)
+                        .iinc(4, 1)
+                        .jump(JumpKind::Goto, outer)
+                        .place_label(outer_end)
+                        .line_number(14)
+                        .load(LocalKind::Reference, 3)
+                        .instruction(Instruction::Areturn);
+                });
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "sumUntilNegative",
+            MethodDescriptor::parse("([I)I").unwrap(),
+            |code| {
+                let (condition, add, end) = (code.new_label(), code.new_label(), code.new_label());
+                code.line_number(18)
+                    .iconst(0)
+                    .store(LocalKind::Int, 2)
+                    .line_number(19)
+                    .iconst(0)
+                    .store(LocalKind::Int, 3)
+                    .line_number(20)
+                    .place_label(condition)
+                    .load(LocalKind::Int, 3)
+                    .load(LocalKind::Reference, 1)
+                    .instruction(Instruction::Arraylength)
+                    .jump(JumpKind::If_icmpge, end)
+                    .line_number(21)
+                    .load(LocalKind::Reference, 1)
+                    .load(LocalKind::Int, 3)
+                    .instruction(Instruction::Iaload)
+                    .jump(JumpKind::Ifge, add)
+                    .line_number(22)
+                    .jump(JumpKind::Goto, end)
+                    .line_number(24)
+                    .place_label(add)
+                    .load(LocalKind::Int, 2)
+                    .load(LocalKind::Reference, 1)
+                    .load(LocalKind::Int, 3)
+                    .iinc(3, 1)
+                    .instruction(Instruction::Iaload)
+                    .instruction(Instruction::Iadd)
+                    .store(LocalKind::Int, 2)
+                    .jump(JumpKind::Goto, condition)
+                    .line_number(26)
+                    .place_label(end)
+                    .load(LocalKind::Int, 2)
+                    .instruction(Instruction::Ireturn);
+            },
+        );
+    builder.build().unwrap()
+}
+
+/// Builds the class with the same bytecode and debug information that javac generates for:
+///
+/// ```java
+/// package rjvm;
+///
+/// class Decompiled {
+///     private int count;
+///
+///     int max(int first, int second) {
+///         return first > second ? first : second;
+///     }
+///
+///     boolean isValid(int value, Object context) {
+///         return value > 0 && context != null || value == -1;
+///     }
+///
+///     String describe(int kind) {
+///         switch (kind) {
+///             case 1:
+///                 return "one";
+///             case 2:
+///             case 3:
+///                 count++;
+///                 break;
+///             default:
+///                 count--;
+///         }
+///         return "kind " + kind + ", count " + count;
+///     }
+///
+///     int sum(int[] values) {
+///         int total = 0;
+///         int index = 0;
+///         do {
+///             total += values[index];
+///             index++;
+///         } while (index < values.length);
+///         return total;
+///     }
+///
+///     synchronized int[] initialize(Object lock) {
+///         synchronized (lock) {
+///             count = 0;
+///         }
+///         return new int[] {1, 0, 3};
+///     }
+///
+///     int parse(String text) {
+///         try {
+///             return Integer.parseInt(text);
+///         } catch (NumberFormatException e) {
+///             return -1;
+///         } finally {
+///             count++;
+///         }
+///     }
+/// }
+/// ```
+pub fn decompiled_class() -> ClassFile {
+    const THIS: &str = "rjvm/Decompiled";
+    const BUILDER: &str = "java/lang/StringBuilder";
+    let add_to_count = |code: &mut CodeBuilder, operation: Instruction| {
+        code.load(LocalKind::Reference, 0)
+            .instruction(Instruction::Dup)
+            .getfield(THIS, "count", "I")
+            .iconst(1)
+            .instruction(operation)
+            .putfield(THIS, "count", "I");
+    };
+
+    let mut builder = ClassBuilder::new(THIS);
+    builder
+        .version(ClassFileVersion::Jdk7)
+        .flags(ClassAccessFlags::SUPER)
+        .source_file("Decompiled.java")
+        .field(FieldFlags::PRIVATE, "count", FieldType::Base(BaseType::Int))
+        .method(
+            MethodFlags::empty(),
+            "<init>",
+            MethodDescriptor::parse("()V").unwrap(),
+            |code| {
+                code.line_number(3)
+                    .load(LocalKind::Reference, 0)
+                    .invokespecial("java/lang/Object", "<init>", "()V")
+                    .instruction(Instruction::Return);
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "max",
+            MethodDescriptor::parse("(II)I").unwrap(),
+            |code| {
+                let (second, end) = (code.new_label(), code.new_label());
+                code.line_number(7)
+                    .load(LocalKind::Int, 1)
+                    .load(LocalKind::Int, 2)
+                    .jump(JumpKind::If_icmple, second)
+                    .load(LocalKind::Int, 1)
+                    .jump(JumpKind::Goto, end)
+                    .place_label(second)
+                    .load(LocalKind::Int, 2)
+                    .place_label(end)
+                    .instruction(Instruction::Ireturn);
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "isValid",
+            MethodDescriptor::parse("(ILjava/lang/Object;)Z").unwrap(),
+            |code| {
+                let (minus_one, valid, invalid, end) = (
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                );
+                code.line_number(11)
+                    .load(LocalKind::Int, 1)
+                    .jump(JumpKind::Ifle, minus_one)
+                    .load(LocalKind::Reference, 2)
+                    .jump(JumpKind::Ifnonnull, valid)
+                    .place_label(minus_one)
+                    .load(LocalKind::Int, 1)
+                    .iconst(-1)
+                    .jump(JumpKind::If_icmpne, invalid)
+                    .place_label(valid)
+                    .iconst(1)
+                    .jump(JumpKind::Goto, end)
+                    .place_label(invalid)
+                    .iconst(0)
+                    .place_label(end)
+                    .instruction(Instruction::Ireturn);
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "describe",
+            MethodDescriptor::parse("(I)Ljava/lang/String;").unwrap(),
+            |code| {
+                let (one, increment, decrement, end) = (
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                );
+                code.line_number(15)
+                    .load(LocalKind::Int, 1)
+                    .tableswitch(1, decrement, vec![one, increment, increment])
+                    .line_number(17)
+                    .place_label(one)
+                    .sconst("one")
+                    .instruction(Instruction::Areturn)
+                    .line_number(20)
+                    .place_label(increment);
+                add_to_count(code, Instruction::Iadd);
+                code.line_number(21)
+                    .jump(JumpKind::Goto, end)
+                    .line_number(23)
+                    .place_label(decrement);
+                add_to_count(code, Instruction::Isub);
+                code.line_number(25)
+                    .place_label(end)
+                    .new_object(BUILDER)
+                    .instruction(Instruction::Dup)
+                    .invokespecial(BUILDER, "<init>", "()V")
+                    .sconst("kind ")
+                    .invokevirtual(
+                        BUILDER,
+                        "append",
+                        "(Ljava/lang/String;)Ljava/lang/StringBuilder;",
+                    )
+                    .load(LocalKind::Int, 1)
+                    .invokevirtual(BUILDER, "append", "(I)Ljava/lang/StringBuilder;")
+                    .sconst(", count ")
+                    .invokevirtual(
+                        BUILDER,
+                        "append",
+                        "(Ljava/lang/String;)Ljava/lang/StringBuilder;",
+                    )
+                    .load(LocalKind::Reference, 0)
+                    .getfield(THIS, "count", "I")
+                    .invokevirtual(BUILDER, "append", "(I)Ljava/lang/StringBuilder;")
+                    .invokevirtual(BUILDER, "toString", "()Ljava/lang/String;")
+                    .instruction(Instruction::Areturn);
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "sum",
+            MethodDescriptor::parse("([I)I").unwrap(),
+            |code| {
+                let body = code.new_label();
+                code.line_number(29)
+                    .iconst(0)
+                    .store(LocalKind::Int, 2)
+                    .line_number(30)
+                    .iconst(0)
+                    .store(LocalKind::Int, 3)
+                    .line_number(32)
+                    .place_label(body)
+                    .load(LocalKind::Int, 2)
+                    .load(LocalKind::Reference, 1)
+                    .load(LocalKind::Int, 3)
+                    .instruction(Instruction::Iaload)
+                    .instruction(Instruction::Iadd)
+                    .store(LocalKind::Int, 2)
+                    .line_number(33)
+                    .iinc(3, 1)
+                    .line_number(34)
+                    .load(LocalKind::Int, 3)
+                    .load(LocalKind::Reference, 1)
+                    .instruction(Instruction::Arraylength)
+                    .jump(JumpKind::If_icmplt, body)
+                    .line_number(35)
+                    .load(LocalKind::Int, 2)
+                    .instruction(Instruction::Ireturn);
+            },
+        )
+        .method(
+            MethodFlags::SYNCHRONIZED,
+            "initialize",
+            MethodDescriptor::parse("(Ljava/lang/Object;)[I").unwrap(),
+            |code| {
+                let (start, end, handler, handler_end, after) = (
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                );
+                code.try_catch(start, end, handler, None)
+                    .try_catch(handler, handler_end, handler, None)
+                    .line_number(39)
+                    .load(LocalKind::Reference, 1)
+                    .instruction(Instruction::Dup)
+                    .store(LocalKind::Reference, 2)
+                    .instruction(Instruction::Monitorenter)
+                    .line_number(40)
+                    .place_label(start)
+                    .load(LocalKind::Reference, 0)
+                    .iconst(0)
+                    .putfield(THIS, "count", "I")
+                    .line_number(41)
+                    .load(LocalKind::Reference, 2)
+                    .instruction(Instruction::Monitorexit)
+                    .place_label(end)
+                    .jump(JumpKind::Goto, after)
+                    .place_label(handler)
+                    .store(LocalKind::Reference, 3)
+                    .load(LocalKind::Reference, 2)
+                    .instruction(Instruction::Monitorexit)
+                    .place_label(handler_end)
+                    .load(LocalKind::Reference, 3)
+                    .instruction(Instruction::Athrow)
+                    .line_number(42)
+                    .place_label(after)
+                    .iconst(3)
+                    .instruction(Instruction::Newarray {
+                        array_type: NewArrayType::Int,
+                    });
+                for (index, value) in [1, 0, 3].into_iter().enumerate() {
+                    code.instruction(Instruction::Dup)
+                        .iconst(index as i32)
+                        .iconst(value)
+                        .instruction(Instruction::Iastore);
+                }
+                code.instruction(Instruction::Areturn);
+            },
+        )
+        .method(
+            MethodFlags::empty(),
+            "parse",
+            MethodDescriptor::parse("(Ljava/lang/String;)I").unwrap(),
+            |code| {
+                let (start, end, catch, catch_end, finally, finally_end) = (
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                    code.new_label(),
+                );
+                code.try_catch(start, end, catch, Some("java/lang/NumberFormatException"))
+                    .try_catch(start, end, finally, None)
+                    .try_catch(catch, catch_end, finally, None)
+                    .try_catch(finally, finally_end, finally, None)
+                    .line_number(47)
+                    .place_label(start)
+                    .load(LocalKind::Reference, 1)
+                    .invokestatic("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I")
+                    .store(LocalKind::Int, 2)
+                    .place_label(end)
+                    .line_number(51);
+                add_to_count(code, Instruction::Iadd);
+                code.line_number(47)
+                    .load(LocalKind::Int, 2)
+                    .instruction(Instruction::Ireturn)
+                    .line_number(48)
+                    .place_label(catch)
+                    .store(LocalKind::Reference, 2)
+                    .line_number(49)
+                    .iconst(-1)
+                    .store(LocalKind::Int, 3)
+                    .line_number(51)
+                    .place_label(catch_end);
+                add_to_count(code, Instruction::Iadd);
+                code.line_number(49)
+                    .load(LocalKind::Int, 3)
+                    .instruction(Instruction::Ireturn)
+                    .line_number(51)
+                    .place_label(finally)
+                    .store(LocalKind::Reference, 4)
+                    .place_label(finally_end);
+                add_to_count(code, Instruction::Iadd);
+                code.line_number(52)
+                    .load(LocalKind::Reference, 4)
+                    .instruction(Instruction::Athrow);
+            },
+        );
+    let mut class = builder.build().unwrap();
+
+    let this = (0, 5, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(&mut class, "<init>", &[this]);
+    let this = (0, 11, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(
+        &mut class,
+        "max",
+        &[this, (0, 11, "first", "I", 1), (0, 11, "second", "I", 2)],
+    );
+    let this = (0, 19, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(
+        &mut class,
+        "isValid",
+        &[
+            this,
+            (0, 19, "value", "I", 1),
+            (0, 19, "context", "Ljava/lang/Object;", 2),
+        ],
+    );
+    let this = (0, 86, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(&mut class, "describe", &[this, (0, 86, "kind", "I", 1)]);
+    let this = (0, 21, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(
+        &mut class,
+        "sum",
+        &[
+            this,
+            (0, 21, "values", "[I", 1),
+            (2, 19, "total", "I", 2),
+            (4, 17, "index", "I", 3),
+        ],
+    );
+    let this = (0, 35, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(
+        &mut class,
+        "initialize",
+        &[this, (0, 35, "lock", "Ljava/lang/Object;", 1)],
+    );
+    // javac lists the variables of the catch block before the parameters
+    let this = (0, 47, "this", "Lrjvm/Decompiled;", 0);
+    add_local_variables(
+        &mut class,
+        "parse",
+        &[
+            (18, 14, "e", "Ljava/lang/NumberFormatException;", 2),
+            this,
+            (0, 47, "text", "Ljava/lang/String;", 1),
+        ],
+    );
+    class
+}
+
+/// Adds the `LocalVariableTable` that javac generates with `-g` to the code of a method. Each
+/// variable is given by its start, length, name, descriptor and slot.
+fn add_local_variables(
+    class: &mut ClassFile,
+    method: &str,
+    variables: &[(u16, u16, &str, &str, u16)],
+) {
+    let mut bytes = (variables.len() as u16).to_be_bytes().to_vec();
+    for (start, length, name, descriptor, slot) in variables {
+        let name = class.constants.intern_utf8(name).unwrap();
+        let descriptor = class.constants.intern_utf8(descriptor).unwrap();
+        for value in [*start, *length, name, descriptor, *slot] {
+            bytes.extend(value.to_be_bytes());
+        }
+    }
+    let method = class
+        .methods
+        .iter_mut()
+        .find(|candidate| candidate.name == method)
+        .unwrap();
+    method.code.as_mut().unwrap().attributes.push(Attribute {
+        name: "LocalVariableTable".to_string(),
+        bytes,
+    });
+}
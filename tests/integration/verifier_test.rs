@@ -0,0 +1,99 @@
+extern crate class_reader;
+
+use class_reader::{
+    class_file::ClassFile,
+    class_hierarchy::{MapClassHierarchy, ObjectClassHierarchy},
+    exception_table::ExceptionTable,
+    instruction::Instruction,
+    program_counter::ProgramCounter,
+    stack_map_table::VerificationType,
+    verifier::{verify_class, verify_method, VerifyErrorKind},
+};
+use utils::read_class_from_bytes;
+
+use crate::utils;
+
+fn resource_classes() -> Vec<ClassFile> {
+    vec![
+        read_class_from_bytes(include_bytes!("../resources/rjvm/Complex.class")),
+        read_class_from_bytes(include_bytes!("../resources/rjvm/Constants.class")),
+        read_class_from_bytes(include_bytes!("../resources/rjvm/DeprecatedClass.class")),
+        read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class")),
+    ]
+}
+
+#[test_log::test]
+fn classes_generated_by_javac_are_valid() {
+    let classes = resource_classes();
+    let mut hierarchy = MapClassHierarchy::from_classes(&classes);
+    hierarchy.add("java/lang/Throwable", "java/lang/Object");
+    hierarchy.add("java/lang/Exception", "java/lang/Throwable");
+    hierarchy.add("java/lang/RuntimeException", "java/lang/Exception");
+    hierarchy.add(
+        "java/lang/IllegalStateException",
+        "java/lang/RuntimeException",
+    );
+
+    for class in classes.iter() {
+        assert_eq!(Ok(()), verify_class(class, &ObjectClassHierarchy));
+        assert_eq!(Ok(()), verify_class(class, &hierarchy));
+    }
+}
+
+#[test_log::test]
+fn rejects_catching_classes_that_are_not_throwable() {
+    let class = read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let error = verify_class(&class, &MapClassHierarchy::new()).unwrap_err();
+    assert_eq!(
+        "test()V: exception handler 1 catches java/lang/IllegalStateException, which is not a throwable",
+        error.to_string()
+    );
+}
+
+#[test_log::test]
+fn rejects_exception_handlers_outside_of_the_code() {
+    let mut class =
+        read_class_from_bytes(include_bytes!("../resources/rjvm/ExceptionsHandlers.class"));
+    let code = class.methods[3].code.as_mut().unwrap();
+    let mut entries = code.exception_table.entries().to_vec();
+    entries[0].range.end = ProgramCounter(code.code.len() as u16 + 1);
+    code.exception_table = ExceptionTable::new(entries);
+
+    let error = verify_method(&class, &class.methods[3], &ObjectClassHierarchy).unwrap_err();
+    assert_eq!(None, error.address);
+    assert!(matches!(
+        error.kind,
+        VerifyErrorKind::InvalidHandlerRange { handler: 0, .. }
+    ));
+}
+
+#[test_log::test]
+fn rejects_instructions_with_operands_of_the_wrong_type() {
+    let mut class = read_class_from_bytes(include_bytes!("../resources/rjvm/Complex.class"));
+    let (index, method) = class
+        .methods
+        .iter()
+        .enumerate()
+        .find(|(_, method)| method.name == "abs")
+        .unwrap();
+    assert_eq!(Ok(()), verify_method(&class, method, &ObjectClassHierarchy));
+
+    // Replaces the first dmul with an fmul, that finds two doubles on the stack
+    let code = class.methods[index].code.as_mut().unwrap();
+    let instructions = Instruction::parse_instructions(&code.code).unwrap();
+    let (address, _) = instructions
+        .iter()
+        .find(|(_, instruction)| matches!(instruction, Instruction::Dmul))
+        .unwrap();
+    code.code[*address] = 0x6a; // fmul
+
+    let error = verify_method(&class, &class.methods[index], &ObjectClassHierarchy).unwrap_err();
+    assert_eq!(Some(*address), error.address);
+    assert_eq!(
+        VerifyErrorKind::UnexpectedType {
+            expected: VerificationType::Float,
+            found: VerificationType::Top,
+        },
+        error.kind
+    );
+}